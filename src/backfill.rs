@@ -0,0 +1,465 @@
+use anyhow::{bail, Context};
+use semver::Version;
+use serde::Deserialize;
+use worker::Env;
+
+use crate::{
+    announce_post, commit_and_announce, http, is_same_release,
+    log::{log_info, log_warn},
+    markdown::PostOptions,
+    platform::Platform,
+    prepare_post_from_comparison, release_localization_inputs,
+    state::{PlatformState, StateController},
+    types::github::Tag,
+    utils, PlatformCheckOutcome,
+    PlatformCheckOutcome::*,
+    PreparedPost,
+};
+
+const RETROACTIVE_NOTICE: &str = "This version was posted retroactively.";
+
+/// Body of a request to post a version that was skipped, e.g. because of an outage.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct BackfillRequest {
+    pub platform: String,
+    pub previous_tag: String,
+    pub new_tag: String,
+
+    /// Topic to post to, instead of the one found for the version of `new_tag`.
+    #[serde(default)]
+    pub topic_id: Option<u64>,
+
+    /// Whether to save `new_tag` as the last posted tag after posting, which must be
+    /// newer than the current one.
+    #[serde(default)]
+    pub advance_state: bool,
+}
+
+/// Fails unless `new_version` comes after the last posted tag of `previous`, as saving
+/// it would otherwise move the state back.
+fn check_advances(previous: &PlatformState, new_version: &Version) -> anyhow::Result<()> {
+    let last_posted_version = previous
+        .last_posted_tag
+        .to_version()
+        .context("could not parse version from last posted tag")?;
+
+    if *new_version <= last_posted_version {
+        bail!(
+            "{new_version} is not newer than the last posted tag {}, not advancing state",
+            previous.last_posted_tag.name
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the state of the platform to save after posting on top of `previous`, if any.
+fn state_to_save(
+    prepared_post: PreparedPost,
+    previous: Option<&PlatformState>,
+    new_tag: &Tag,
+    posted: &utils::DiscoursePost,
+    post_url: &str,
+    posted_at: &str,
+) -> Option<PlatformState> {
+    previous.map(|previous| {
+        prepared_post
+            .into_platform_state(previous, new_tag, posted, post_url, posted_at)
+            .with_status_of(previous)
+    })
+}
+
+/// Posts the commits between `old_tag` and `new_tag`, without consulting the state
+/// of `platform` and without changing it unless `advance_state` is set.
+///
+/// If it is, the post is made on top of the state like a regular one, and only if
+/// `new_tag` is newer than the last posted tag.
+pub async fn backfill(
+    env: &Env,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    topic_id: Option<u64>,
+    advance_state: bool,
+    force_dry_run: bool,
+) -> anyhow::Result<PlatformCheckOutcome> {
+    log_info!(
+        platform = platform,
+        "backfilling [old_tag: {:?}, new_tag: {:?}] for {platform}",
+        old_tag,
+        new_tag
+    );
+
+    let old_version = old_tag
+        .to_version()
+        .context("could not parse version from previous_tag")?;
+    let new_version = new_tag
+        .to_version()
+        .context("could not parse version from new_tag")?;
+
+    let is_dry_run = force_dry_run || utils::is_dry_run(env)?;
+    let discourse_api_key = utils::api_key(env)?;
+
+    let state_controller = match advance_state {
+        true => Some(StateController::from_kv(env, is_dry_run).await?),
+        false => None,
+    };
+    let previous_state = state_controller
+        .as_ref()
+        .map(|state_controller| state_controller.platform_state(platform));
+
+    if let Some(previous_state) = &previous_state {
+        check_advances(previous_state, &new_version)?;
+    }
+
+    let topic_id = match topic_id {
+        Some(topic_id) => topic_id,
+        None => {
+            match utils::get_topic_id_or_override(
+                &http::FetchClient,
+                env,
+                &discourse_api_key,
+                platform,
+                &new_version,
+            )
+            .await
+            .context("could not find topic_id")?
+            {
+                Some(topic_id) => topic_id,
+                None => return Ok(NewTopicNotFound),
+            }
+        }
+    };
+
+    log_info!(platform = platform, "topic_id = {topic_id}");
+
+    let github_token = utils::github_token(env);
+
+    let comparison = utils::get_github_comparison(
+        &http::FetchClient,
+        github_token.as_deref(),
+        platform,
+        &old_tag.name,
+        &new_tag.name,
+    )
+    .await
+    .context("could not get build comparison from GitHub")?;
+
+    let (previous_release_state, release_comparison) = match &previous_state {
+        Some(previous_state) => {
+            release_localization_inputs(
+                env,
+                &http::FetchClient,
+                github_token.as_deref(),
+                platform,
+                previous_state,
+                new_tag,
+                is_same_release(&old_version, &new_version),
+                false,
+            )
+            .await
+        }
+        None => (None, None),
+    };
+
+    let dependency_bump_prefixes = utils::dependency_bump_prefixes(env)?;
+    let header_template = utils::post_header_template(env, platform)?;
+
+    let Some(prepared_post) = prepare_post_from_comparison(
+        &http::FetchClient,
+        github_token.as_deref(),
+        platform,
+        old_tag,
+        new_tag,
+        &comparison,
+        previous_release_state,
+        release_comparison.as_ref(),
+        None,
+        &[],
+        PostOptions {
+            notice: Some(RETROACTIVE_NOTICE),
+            show_commit_verification: utils::show_commit_verification(env)?,
+            collapse_threshold: utils::collapse_threshold(env)?,
+            dependency_bump_prefixes: dependency_bump_prefixes.as_deref(),
+            inline_dependency_bumps: utils::inline_dependency_bumps(env)?,
+            commit_list_style: utils::commit_list_style(env)?,
+            commit_order: utils::commit_order(env, platform)?,
+            show_cycle_stats: false,
+            show_contributors: utils::show_contributors(env)?,
+            header_template: header_template.as_ref(),
+        },
+    )
+    .await?
+    else {
+        return Ok(NewTagIsBehind);
+    };
+
+    if is_dry_run {
+        log_warn!(
+            platform = platform,
+            "dry run; not posting to Discourse, would post to topic_id = {topic_id}"
+        );
+        log_info!(
+            platform = platform,
+            "markdown_text = {}",
+            prepared_post.markdown_text
+        );
+
+        return Ok(RenderedPostInDryRun {
+            topic_id,
+            markdown_text: prepared_post.markdown_text,
+        });
+    }
+
+    let posted = utils::post_to_discourse(
+        &http::FetchClient,
+        &prepared_post.markdown_text,
+        &discourse_api_key,
+        topic_id,
+        None,
+    )
+    .await
+    .context("could not post commits to Discourse")?;
+
+    log_info!(platform = platform, "posted = {:?}", posted);
+
+    let post_url = utils::discourse_post_url(topic_id, posted.number);
+    let commits_len = prepared_post.commits_len;
+    let commit_subjects: Vec<String> = prepared_post
+        .posted_commits
+        .iter()
+        .map(|commit| commit.subject.clone())
+        .collect();
+    let localization_changes_len = prepared_post.build_localization_changes_len;
+
+    let state = state_to_save(
+        prepared_post,
+        previous_state.as_ref(),
+        new_tag,
+        &posted,
+        &post_url,
+        &utils::now_iso_string(),
+    );
+
+    commit_and_announce(
+        async {
+            match (state, &state_controller) {
+                (Some(state), Some(state_controller)) => state_controller
+                    .set_platform_state(platform, state)
+                    .await
+                    .context("could not set platform state after backfilling"),
+                _ => {
+                    log_info!(platform = platform, "not advancing state after backfilling");
+                    Ok(())
+                }
+            }
+        },
+        announce_post(
+            utils::owned_env(env),
+            platform,
+            old_tag.clone(),
+            new_tag.clone(),
+            commits_len,
+            commit_subjects,
+            localization_changes_len,
+            topic_id,
+            posted.number,
+            post_url,
+        ),
+    )
+    .await?;
+
+    Ok(PostedCommits)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{
+        accumulates_localization_changes,
+        core::{poll_once, state::LocalizationState},
+        localization::{self, Completeness, LocalizationChange, UnsortedChanges},
+        platform::Platform::*,
+        types,
+    };
+
+    fn prepared_post() -> PreparedPost {
+        PreparedPost {
+            markdown_text: String::from("New version: 1.2.4"),
+            commits_len: 3,
+            build_localization_changes_len: 0,
+            last_posted_tag_previous_release: Tag::new("v1.2.3"),
+            localization_changes_completeness: Completeness::Complete,
+            localization_changes_completeness_rationale: None,
+            localization_changes: UnsortedChanges::default(),
+            localization_changes_cycle: None,
+            posted_commits: Vec::new(),
+            same_code: false,
+        }
+    }
+
+    const POSTED: utils::DiscoursePost = utils::DiscoursePost { id: 42, number: 5 };
+    const POST_URL: &str = "https://community.signalusers.org/t/123/5";
+    const POSTED_AT: &str = "2022-07-02T00:00:00.000Z";
+
+    #[test]
+    fn request() {
+        assert_eq!(
+            serde_json::from_str::<BackfillRequest>(
+                r#"{"platform":"android","previous_tag":"v1.2.3","new_tag":"v1.2.4"}"#
+            )
+            .unwrap(),
+            BackfillRequest {
+                platform: String::from("android"),
+                previous_tag: String::from("v1.2.3"),
+                new_tag: String::from("v1.2.4"),
+                topic_id: None,
+                advance_state: false,
+            }
+        );
+    }
+
+    #[test]
+    fn state_untouched_by_default() {
+        assert_eq!(
+            state_to_save(
+                prepared_post(),
+                None,
+                &Tag::new("v1.2.4"),
+                &POSTED,
+                POST_URL,
+                POSTED_AT,
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn state_advanced_if_requested() {
+        assert_eq!(
+            state_to_save(
+                prepared_post(),
+                Some(&PlatformState::initial(
+                    Tag::new("v1.1.0"),
+                    Tag::new("v1.2.3")
+                )),
+                &Tag::new("v1.2.4"),
+                &POSTED,
+                POST_URL,
+                POSTED_AT,
+            ),
+            Some(PlatformState {
+                last_posted_tag_previous_release: Tag::new("v1.2.3"),
+                last_posted_tag: Tag::new("v1.2.4"),
+                last_post_number: Some(5),
+                last_post_id: Some(42),
+                last_post_url: Some(POST_URL.to_string()),
+                last_posted_at: Some(POSTED_AT.to_string()),
+                post_attempts: 0,
+                pending_tag: None,
+                posted_archiving_message: false,
+                posted_cycle_summary: false,
+                tag_moved: false,
+                localization_changes_completeness: Completeness::Complete,
+                localization_changes_completeness_rationale: None,
+                localization_changes: UnsortedChanges::default(),
+                localization_changes_cycle: None,
+                localization_before_last_posted_tag: Some(LocalizationState {
+                    last_posted_tag_previous_release: Tag::new("v1.1.0"),
+                    completeness: Completeness::default(),
+                    completeness_rationale: None,
+                    changes: UnsortedChanges::default(),
+                    cycle: None,
+                }),
+                enabled: true,
+                last_checked_at: None,
+                last_error: None,
+                consecutive_failures: 0,
+                next_attempt_after: None,
+                deferred_reason: None,
+                run_subrequests: None,
+            })
+        );
+    }
+
+    fn current_state() -> PlatformState {
+        let mut current = PlatformState::initial(Tag::new("v1.1.0"), Tag::new("v1.2.4"));
+        current.localization_changes_completeness = Completeness::Complete;
+        current.localization_changes =
+            LocalizationChange::unsorted_changes(vec![LocalizationChange::test_change(
+                "en",
+                vec![localization::StringsFileKind::Main],
+            )]);
+        current.localization_changes_cycle = Some(String::from("1.2"));
+        current
+    }
+
+    #[test_case("v1.2.5", true; "newer")]
+    #[test_case("v1.2.4", false; "last posted")]
+    #[test_case("v1.2.3", false; "older")]
+    fn advances(new_tag: &str, result: bool) {
+        let new_version = Tag::new(new_tag).to_version().unwrap();
+
+        assert_eq!(
+            check_advances(&current_state(), &new_version).is_ok(),
+            result
+        );
+    }
+
+    #[test]
+    fn advancing_keeps_release_of_current_state() {
+        let current = current_state();
+        let old_tag = Tag::new("v1.2.4");
+        let new_tag = Tag::new("v1.2.5");
+
+        // as chosen by `release_localization_inputs`
+        let previous_release_state = Some(&current)
+            .filter(|current| accumulates_localization_changes(current, &new_tag, true));
+
+        let prepared_post = poll_once(prepare_post_from_comparison(
+            &http::FakeClient::default(),
+            None,
+            Android,
+            &old_tag,
+            &new_tag,
+            &types::github::Comparison {
+                status: types::github::ComparisonStatus::Ahead,
+                ahead_by: 0,
+                total_commits: 0,
+                commits: Vec::new(),
+                files: Some(Vec::new()),
+                merge_base_commit: None,
+            },
+            previous_release_state,
+            None,
+            None,
+            &[],
+            PostOptions::default(),
+        ))
+        .unwrap()
+        .unwrap();
+
+        let state = state_to_save(
+            prepared_post,
+            Some(&current),
+            &new_tag,
+            &POSTED,
+            POST_URL,
+            POSTED_AT,
+        )
+        .unwrap();
+
+        assert_eq!(state.last_posted_tag, new_tag);
+        assert_eq!(state.last_posted_tag_previous_release, Tag::new("v1.1.0"));
+        assert_eq!(state.localization_changes, current.localization_changes);
+        assert_eq!(
+            state.localization_changes_completeness,
+            Completeness::Complete
+        );
+        assert_eq!(state.localization_changes_cycle, Some(String::from("1.2")));
+        assert_eq!(state.last_post_id, Some(42));
+    }
+}
@@ -0,0 +1,156 @@
+use std::{future::Future, pin::Pin};
+
+use worker::{Context, ScheduleContext};
+
+use crate::invocation;
+
+/// Best-effort work, e.g. history and notifications after a post.
+pub type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// Where best-effort work is handed over to, to be finished after the handler returns.
+pub trait Spawner {
+    fn spawn(&self, task: Task);
+}
+
+impl Spawner for ScheduleContext {
+    fn spawn(&self, task: Task) {
+        self.wait_until(task);
+    }
+}
+
+impl Spawner for Context {
+    fn spawn(&self, task: Task) {
+        self.wait_until(task);
+    }
+}
+
+/// Hands `future` over to the spawner of the current invocation (see
+/// [`invocation::Invocation::spawner`]), or waits for it if there is none.
+///
+/// Only for work that nothing depends on, as it may be finished after the handler returns;
+/// never for committing the state.
+pub async fn defer(future: impl Future<Output = ()> + 'static) {
+    // polled by the runtime after the handler returns, so it is kept part of the invocation
+    let task = Box::pin(invocation::in_current_scope(future)) as Task;
+
+    let task = invocation::with(|invocation| {
+        match invocation.and_then(|invocation| invocation.spawner.as_ref()) {
+            Some(spawner) => {
+                spawner.spawn(task);
+                None
+            }
+            None => Some(task),
+        }
+    });
+
+    if let Some(task) = task {
+        task.await;
+    }
+}
+
+/// Keeps spawned tasks, so that tests can check what was deferred and run it.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct FakeSpawner {
+    tasks: std::rc::Rc<std::cell::RefCell<Vec<Task>>>,
+}
+
+#[cfg(test)]
+impl FakeSpawner {
+    /// An invocation that hands best-effort work over to this spawner.
+    pub fn invocation(&self) -> invocation::Invocation {
+        invocation::Invocation {
+            spawner: Some(Box::new(self.clone())),
+            ..Default::default()
+        }
+    }
+
+    /// Number of tasks spawned and not run yet.
+    pub fn spawned(&self) -> usize {
+        self.tasks.borrow().len()
+    }
+
+    /// Runs the spawned tasks, which must never wait on anything.
+    pub fn run_all(&self) {
+        for task in self.tasks.take() {
+            crate::core::poll_once(task);
+        }
+    }
+}
+
+#[cfg(test)]
+impl Spawner for FakeSpawner {
+    fn spawn(&self, task: Task) {
+        self.tasks.borrow_mut().push(task);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, pin::pin, rc::Rc, task::Poll};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::core::poll_once;
+
+    fn flagging(flag: &Rc<Cell<bool>>) -> impl Future<Output = ()> + 'static {
+        let flag = Rc::clone(flag);
+        async move { flag.set(true) }
+    }
+
+    #[test]
+    fn deferred_to_spawner() {
+        let spawner = FakeSpawner::default();
+        let done = Rc::new(Cell::new(false));
+
+        poll_once(invocation::scope(
+            spawner.invocation(),
+            defer(flagging(&done)),
+        ));
+
+        assert!(!done.get());
+        assert_eq!(spawner.spawned(), 1);
+
+        spawner.run_all();
+
+        assert!(done.get());
+        assert_eq!(spawner.spawned(), 0);
+    }
+
+    #[test]
+    fn waited_for_without_spawner() {
+        let done = Rc::new(Cell::new(false));
+
+        poll_once(invocation::scope(
+            invocation::Invocation::default(),
+            defer(flagging(&done)),
+        ));
+
+        assert!(done.get());
+    }
+
+    #[test]
+    fn concurrent_invocations_keep_their_spawners() {
+        let (first, second) = (FakeSpawner::default(), FakeSpawner::default());
+        let done = Rc::new(Cell::new(false));
+
+        // the first invocation defers work after waiting, e.g. on posting
+        let mut waited = false;
+        let mut first_invocation = pin!(invocation::scope(first.invocation(), async {
+            std::future::poll_fn(|_| match std::mem::replace(&mut waited, true) {
+                true => Poll::Ready(()),
+                false => Poll::Pending,
+            })
+            .await;
+            defer(flagging(&done)).await;
+        }));
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+
+        assert!(first_invocation.as_mut().poll(&mut cx).is_pending());
+        poll_once(invocation::scope(second.invocation(), async {}));
+        assert!(first_invocation.as_mut().poll(&mut cx).is_ready());
+
+        assert_eq!((first.spawned(), second.spawned()), (1, 0));
+    }
+}
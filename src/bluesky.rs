@@ -0,0 +1,637 @@
+use std::fmt;
+
+use anyhow::{anyhow, Context};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use worker::{Env, Method, Url};
+use worker_kv::KvStore;
+
+use crate::{
+    http::{self, HttpClient},
+    log::{log_error, log_info},
+    mastodon,
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+    types::github::Tag,
+    utils,
+};
+
+/// Used if `BLUESKY_SERVICE_URL` is not configured.
+const DEFAULT_SERVICE_URL: &str = "https://bsky.social";
+
+/// Character limit of a post on Bluesky, which counts graphemes; characters are close enough.
+pub const MAX_POST_CHARS: usize = 300;
+
+const SESSION_KV_KEY: &str = "bluesky_session";
+
+/// Access tokens are valid for about two hours; cached ones are used for less than that,
+/// so that they do not expire while in use.
+const SESSION_TTL_SECONDS: u64 = 90 * 60;
+
+/// Identifier (handle or email) and app password of the account that posts.
+pub struct Credentials {
+    pub identifier: String,
+    pub app_password: String,
+}
+
+/// A session, cached in KV until [`Session::expires_at`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub access_jwt: String,
+    pub did: String,
+    /// ISO 8601 timestamp after which a new session is created instead.
+    pub expires_at: String,
+}
+
+impl Session {
+    fn is_usable(&self, now: &str) -> bool {
+        now < self.expires_at.as_str()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateSessionResponse {
+    access_jwt: String,
+    did: String,
+}
+
+/// An XRPC request failed, e.g. with the `ExpiredToken` error.
+#[derive(Debug)]
+struct XrpcError {
+    status_code: u16,
+    error: Option<String>,
+    message: Option<String>,
+}
+
+impl fmt::Display for XrpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Bluesky responded with status code {}, error = {:?}, message = {:?}",
+            self.status_code, self.error, self.message
+        )
+    }
+}
+
+impl std::error::Error for XrpcError {}
+
+#[derive(Deserialize)]
+struct XrpcErrorResponse {
+    error: Option<String>,
+    message: Option<String>,
+}
+
+/// Whether `error` was caused by the access token of the session being rejected,
+/// so that a new session may help.
+fn is_expired_session(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<XrpcError>())
+        .any(|xrpc_error| {
+            xrpc_error.status_code == 401
+                || matches!(
+                    xrpc_error.error.as_deref(),
+                    Some("ExpiredToken" | "InvalidToken")
+                )
+        })
+}
+
+/// Text of the post about the new version, see [`mastodon::announcement_text`].
+pub fn post_text(
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) -> String {
+    mastodon::announcement_text(
+        MAX_POST_CHARS,
+        platform,
+        old_tag,
+        new_tag,
+        commits_count,
+        post_url,
+    )
+}
+
+/// Facet that makes the last occurrence of `url` in `text` a link.
+///
+/// Facets are indexed by UTF-8 bytes, not characters, so the text before the link
+/// may have multi-byte characters such as "—".
+pub fn link_facet(text: &str, url: &str) -> Option<Value> {
+    let byte_start = text.rfind(url)?;
+
+    Some(json!({
+        "index": {
+            "byteStart": byte_start,
+            "byteEnd": byte_start + url.len(),
+        },
+        "features": [{
+            "$type": "app.bsky.richtext.facet#link",
+            "uri": url,
+        }],
+    }))
+}
+
+/// `app.bsky.feed.post` record with `text`, which links to `post_url`.
+pub fn post_record(text: &str, post_url: &str, created_at: &str) -> Value {
+    let facets: Vec<Value> = link_facet(text, post_url).into_iter().collect();
+
+    json!({
+        "$type": "app.bsky.feed.post",
+        "text": text,
+        "createdAt": created_at,
+        "facets": facets,
+    })
+}
+
+fn xrpc_url(service_url: &str, method: &str) -> anyhow::Result<Url> {
+    Url::parse(&format!(
+        "{}/xrpc/{method}",
+        service_url.trim_end_matches('/')
+    ))
+    .context("could not parse URL")
+}
+
+async fn xrpc<T: DeserializeOwned>(
+    client: &impl HttpClient,
+    url: Url,
+    access_jwt: Option<&str>,
+    body: &Value,
+) -> anyhow::Result<T> {
+    let mut builder = utils::RequestBuilder::new(url, Method::Post).json(body);
+    if let Some(access_jwt) = access_jwt {
+        builder = builder.header("Authorization", format!("Bearer {access_jwt}"));
+    }
+
+    let response = client.send(builder.prepare()).await?;
+
+    if !(200..=299).contains(&response.status_code) {
+        let error_response = serde_json::from_str::<XrpcErrorResponse>(&response.body).ok();
+
+        return Err(XrpcError {
+            status_code: response.status_code,
+            error: error_response
+                .as_ref()
+                .and_then(|error_response| error_response.error.clone()),
+            message: error_response.and_then(|error_response| error_response.message),
+        }
+        .into());
+    }
+
+    serde_json::from_str(&response.body).context("could not parse Bluesky response")
+}
+
+/// Creates a session that is used until `expires_at`.
+async fn create_session(
+    client: &impl HttpClient,
+    service_url: &str,
+    credentials: &Credentials,
+    expires_at: &str,
+) -> anyhow::Result<Session> {
+    let response: CreateSessionResponse = xrpc(
+        client,
+        xrpc_url(service_url, "com.atproto.server.createSession")?,
+        None,
+        &json!({
+            "identifier": credentials.identifier,
+            "password": credentials.app_password,
+        }),
+    )
+    .await
+    .context("could not create Bluesky session")?;
+
+    Ok(Session {
+        access_jwt: response.access_jwt,
+        did: response.did,
+        expires_at: expires_at.to_string(),
+    })
+}
+
+async fn create_record(
+    client: &impl HttpClient,
+    service_url: &str,
+    session: &Session,
+    record: &Value,
+) -> anyhow::Result<()> {
+    xrpc::<Value>(
+        client,
+        xrpc_url(service_url, "com.atproto.repo.createRecord")?,
+        Some(&session.access_jwt),
+        &json!({
+            "repo": session.did,
+            "collection": "app.bsky.feed.post",
+            "record": record,
+        }),
+    )
+    .await
+    .context("could not create Bluesky post")?;
+
+    Ok(())
+}
+
+/// Posts `record` with the `cached` session if it is usable at `now`, otherwise with a new one
+/// (used until `new_session_expires_at`), which is also created if the cached one is rejected.
+///
+/// Returns the new session, if one was created, to be cached.
+async fn post_with_session(
+    client: &impl HttpClient,
+    service_url: &str,
+    credentials: &Credentials,
+    cached: Option<Session>,
+    now: &str,
+    new_session_expires_at: &str,
+    record: &Value,
+) -> anyhow::Result<Option<Session>> {
+    if let Some(session) = cached.filter(|session| session.is_usable(now)) {
+        match create_record(client, service_url, &session, record).await {
+            Err(e) if is_expired_session(&e) => {
+                log_info!("cached Bluesky session was rejected; creating a new one");
+            }
+            result => return result.map(|()| None),
+        }
+    }
+
+    let session = create_session(client, service_url, credentials, new_session_expires_at).await?;
+    create_record(client, service_url, &session, record).await?;
+
+    Ok(Some(session))
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Returns the session cached at `kv_key`, if any; failures are only logged, as a new session is created then.
+async fn cached_session(kv_store: &KvStore, kv_key: &str) -> Option<Session> {
+    let result = kv_store
+        .get(kv_key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {kv_key}"));
+
+    match result {
+        Ok(session) => session,
+        Err(e) => {
+            log_error!("could not get cached Bluesky session: {e:?}");
+            None
+        }
+    }
+}
+
+async fn cache_session(kv_store: &KvStore, kv_key: &str, session: &Session) -> anyhow::Result<()> {
+    kv_store
+        .put(kv_key, session)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .expiration_ttl(SESSION_TTL_SECONDS)
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+/// Posts about the new version to Bluesky, if it is configured.
+///
+/// Failures are only logged, as posting to Bluesky is not essential.
+pub async fn publish(
+    env: &Env,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) {
+    let text = post_text(platform, old_tag, new_tag, commits_count, post_url);
+
+    if let Err(e) = try_publish(env, &text, post_url).await {
+        log_error!(platform = platform, "could not post to Bluesky: {e:?}");
+    }
+}
+
+async fn try_publish(env: &Env, text: &str, post_url: &str) -> anyhow::Result<()> {
+    let Some(identifier) = utils::bluesky_identifier(env) else {
+        log_info!("no Bluesky identifier configured; not posting");
+        return Ok(());
+    };
+
+    let credentials = Credentials {
+        identifier,
+        app_password: utils::bluesky_app_password(env)?,
+    };
+    let service_url =
+        utils::bluesky_service_url(env)?.unwrap_or_else(|| DEFAULT_SERVICE_URL.to_string());
+
+    let kv_store = kv_store(env)?;
+    let session_kv_key = state::kv_key(env, SESSION_KV_KEY);
+    let now = utils::now_iso_string();
+    let record = post_record(text, post_url, &now);
+
+    let new_session = post_with_session(
+        &http::FetchClient,
+        &service_url,
+        &credentials,
+        cached_session(&kv_store, &session_kv_key).await,
+        &now,
+        &utils::iso_string_in(SESSION_TTL_SECONDS),
+        &record,
+    )
+    .await?;
+
+    log_info!("posted to Bluesky");
+
+    if let Some(session) = new_session {
+        if let Err(e) = cache_session(&kv_store, &session_kv_key, &session).await {
+            log_error!("could not cache Bluesky session: {e:?}");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+
+    use super::*;
+    use crate::{core::poll_once, http::HttpResponse, platform::Platform::*};
+
+    const POST_URL: &str = "https://community.signalusers.org/t/123/45";
+    const SERVICE_URL: &str = "https://bsky.example";
+    const SESSION_URL: &str = "https://bsky.example/xrpc/com.atproto.server.createSession";
+    const RECORD_URL: &str = "https://bsky.example/xrpc/com.atproto.repo.createRecord";
+    const NOW: &str = "2022-07-02T00:00:00.000Z";
+
+    #[test]
+    fn text() {
+        assert_str_eq!(
+            post_text(
+                Android,
+                &Tag::new("v7.1.3"),
+                &Tag::new("v7.1.4"),
+                12,
+                POST_URL
+            ),
+            "Signal Android 7.1.4 is out — 12 commits since 7.1.3: https://community.signalusers.org/t/123/45"
+        );
+    }
+
+    #[test]
+    fn text_truncated() {
+        let post_url = format!("https://community.signalusers.org/t/{}", "1".repeat(250));
+
+        let text = post_text(
+            Ios,
+            &Tag::new("7.1.0.1-beta"),
+            &Tag::new("7.1.0.2-beta"),
+            3,
+            &post_url,
+        );
+
+        assert_eq!(text.chars().count(), MAX_POST_CHARS);
+        assert!(text.ends_with(&format!("…: {post_url}")));
+    }
+
+    #[test]
+    fn facet_uses_byte_offsets() {
+        let text = format!("Signal Android 7.1.4 is out — 12 commits since 7.1.3: {POST_URL}");
+
+        let facet = link_facet(&text, POST_URL).unwrap();
+        let byte_start = facet["index"]["byteStart"].as_u64().unwrap() as usize;
+        let byte_end = facet["index"]["byteEnd"].as_u64().unwrap() as usize;
+
+        // "—" takes 3 bytes
+        assert_eq!(byte_start, text.chars().count() - POST_URL.len() + 2);
+        assert_eq!(byte_end, text.len());
+        assert_str_eq!(&text[byte_start..byte_end], POST_URL);
+        assert_eq!(
+            facet["features"],
+            json!([{ "$type": "app.bsky.richtext.facet#link", "uri": POST_URL }])
+        );
+    }
+
+    #[test]
+    fn facet_after_truncation() {
+        let text = format!("Signal iOS 7.1.0.2-beta is out — 3 commit…: {POST_URL}");
+
+        let facet = link_facet(&text, POST_URL).unwrap();
+
+        assert_eq!(
+            facet["index"],
+            json!({ "byteStart": text.len() - POST_URL.len(), "byteEnd": text.len() })
+        );
+    }
+
+    #[test]
+    fn no_facet_without_url() {
+        assert_eq!(link_facet("Signal Android 7.1.4 is out", POST_URL), None);
+    }
+
+    #[test]
+    fn record() {
+        let text = format!("Signal Android 7.1.4 is out: {POST_URL}");
+
+        assert_eq!(
+            post_record(&text, POST_URL, NOW),
+            json!({
+                "$type": "app.bsky.feed.post",
+                "text": text,
+                "createdAt": NOW,
+                "facets": [{
+                    "index": { "byteStart": 29, "byteEnd": 29 + POST_URL.len() },
+                    "features": [{ "$type": "app.bsky.richtext.facet#link", "uri": POST_URL }],
+                }],
+            })
+        );
+    }
+
+    fn credentials() -> Credentials {
+        Credentials {
+            identifier: String::from("bot.example"),
+            app_password: String::from("app-password"),
+        }
+    }
+
+    fn session(access_jwt: &str, expires_at: &str) -> Session {
+        Session {
+            access_jwt: access_jwt.to_string(),
+            did: String::from("did:plc:bot"),
+            expires_at: expires_at.to_string(),
+        }
+    }
+
+    fn response(status_code: u16, body: Value) -> HttpResponse {
+        HttpResponse {
+            status_code,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    fn session_response() -> HttpResponse {
+        response(200, json!({ "accessJwt": "new-jwt", "did": "did:plc:bot" }))
+    }
+
+    fn record_response() -> HttpResponse {
+        response(
+            200,
+            json!({ "uri": "at://did:plc:bot/app.bsky.feed.post/1", "cid": "abc" }),
+        )
+    }
+
+    fn post(client: &http::FakeClient, cached: Option<Session>) -> anyhow::Result<Option<Session>> {
+        poll_once(post_with_session(
+            client,
+            SERVICE_URL,
+            &credentials(),
+            cached,
+            NOW,
+            "2022-07-02T01:30:00.000Z",
+            &json!({ "text": "Signal Android 7.1.4 is out" }),
+        ))
+    }
+
+    fn authorizations(client: &http::FakeClient) -> Vec<Option<String>> {
+        client
+            .requests()
+            .iter()
+            .map(|request| {
+                request
+                    .headers
+                    .iter()
+                    .find(|(name, _)| *name == "Authorization")
+                    .map(|(_, value)| value.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cached_session_reused() {
+        let client =
+            http::FakeClient::default().respond(Method::Post, RECORD_URL, record_response());
+
+        let new_session = post(
+            &client,
+            Some(session("cached-jwt", "2022-07-02T00:30:00.000Z")),
+        );
+
+        assert_eq!(new_session.unwrap(), None);
+        assert_eq!(client.requested_urls(), [RECORD_URL]);
+        assert_eq!(
+            authorizations(&client),
+            [Some(String::from("Bearer cached-jwt"))]
+        );
+    }
+
+    #[test]
+    fn session_created_without_cached_one() {
+        let client = http::FakeClient::default()
+            .respond(Method::Post, SESSION_URL, session_response())
+            .respond(Method::Post, RECORD_URL, record_response());
+
+        let new_session = post(&client, None);
+
+        assert_eq!(
+            new_session.unwrap(),
+            Some(session("new-jwt", "2022-07-02T01:30:00.000Z"))
+        );
+        assert_eq!(client.requested_urls(), [SESSION_URL, RECORD_URL]);
+        assert_eq!(
+            serde_json::from_str::<Value>(client.requests()[0].body.as_deref().unwrap()).unwrap(),
+            json!({ "identifier": "bot.example", "password": "app-password" })
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(client.requests()[1].body.as_deref().unwrap()).unwrap()
+                ["repo"],
+            "did:plc:bot"
+        );
+    }
+
+    #[test]
+    fn expired_cached_session_not_used() {
+        let client = http::FakeClient::default()
+            .respond(Method::Post, SESSION_URL, session_response())
+            .respond(Method::Post, RECORD_URL, record_response());
+
+        let new_session = post(&client, Some(session("cached-jwt", NOW)));
+
+        assert!(new_session.unwrap().is_some());
+        assert_eq!(client.requested_urls(), [SESSION_URL, RECORD_URL]);
+    }
+
+    #[test]
+    fn rejected_cached_session_refreshed() {
+        let client = http::FakeClient::default()
+            .respond(Method::Post, SESSION_URL, session_response())
+            .respond(Method::Post, RECORD_URL, record_response())
+            .respond_once(
+                Method::Post,
+                RECORD_URL,
+                response(
+                    400,
+                    json!({ "error": "ExpiredToken", "message": "Token has expired" }),
+                ),
+            );
+
+        let new_session = post(
+            &client,
+            Some(session("cached-jwt", "2022-07-02T00:30:00.000Z")),
+        );
+
+        assert!(new_session.unwrap().is_some());
+        assert_eq!(
+            client.requested_urls(),
+            [RECORD_URL, SESSION_URL, RECORD_URL]
+        );
+        assert_eq!(
+            authorizations(&client),
+            [
+                Some(String::from("Bearer cached-jwt")),
+                None,
+                Some(String::from("Bearer new-jwt"))
+            ]
+        );
+    }
+
+    #[test]
+    fn other_errors_not_retried() {
+        let client = http::FakeClient::default().respond(
+            Method::Post,
+            RECORD_URL,
+            response(
+                400,
+                json!({ "error": "InvalidRequest", "message": "Record/text must not be longer than 300 graphemes" }),
+            ),
+        );
+
+        let error = post(
+            &client,
+            Some(session("cached-jwt", "2022-07-02T00:30:00.000Z")),
+        )
+        .unwrap_err();
+
+        assert!(!is_expired_session(&error));
+        assert_eq!(client.requested_urls(), [RECORD_URL]);
+    }
+
+    #[test]
+    fn session_creation_fails() {
+        let client = http::FakeClient::default().respond(
+            Method::Post,
+            SESSION_URL,
+            response(
+                401,
+                json!({ "error": "AuthenticationRequired", "message": "Invalid identifier or password" }),
+            ),
+        );
+
+        assert_eq!(
+            post(&client, None).unwrap_err().to_string(),
+            "could not create Bluesky session"
+        );
+    }
+}
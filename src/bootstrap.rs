@@ -0,0 +1,251 @@
+use std::future::Future;
+
+use anyhow::Context;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+
+use crate::{
+    platform::Platform::{self, *},
+    state::{PlatformState, State},
+    types::github::Tag,
+};
+
+/// Tags to start posting after for a platform.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapTags {
+    /// Last tag of the release before the one of `last_tag`.
+    pub previous_tag: String,
+    /// Last tag that is considered posted; the next one is posted first.
+    pub last_tag: String,
+}
+
+/// Body of a request to set up the initial state.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+pub struct BootstrapRequest {
+    pub android: BootstrapTags,
+    pub ios: BootstrapTags,
+    pub desktop: BootstrapTags,
+
+    /// Whether to replace the state if there already is one.
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl BootstrapRequest {
+    fn tags(&self, platform: Platform) -> &BootstrapTags {
+        match platform {
+            Android => &self.android,
+            Ios => &self.ios,
+            Desktop => &self.desktop,
+        }
+    }
+}
+
+/// Why the state could not be built from a request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InvalidBootstrap {
+    InvalidState(String),
+    TagNotFound { platform: Platform, tag: String },
+}
+
+impl InvalidBootstrap {
+    pub fn message(&self) -> String {
+        match self {
+            Self::InvalidState(message) => message.clone(),
+            Self::TagNotFound { platform, tag } => format!("tag {tag} not found for {platform}"),
+        }
+    }
+}
+
+fn state_without_shas(request: &BootstrapRequest) -> State {
+    let platform_state = |platform| {
+        let tags = request.tags(platform);
+        PlatformState::initial(Tag::new(&tags.previous_tag), Tag::new(&tags.last_tag))
+    };
+
+    State {
+        android: platform_state(Android),
+        ios: platform_state(Ios),
+        desktop: platform_state(Desktop),
+    }
+}
+
+/// Builds the initial state from `request`, with the commit of each tag found by `get_tag_sha`
+/// (`None` if there is no such tag).
+///
+/// Tags are only looked up once the state is known to be valid.
+pub async fn state_from_request<G, Fut>(
+    request: &BootstrapRequest,
+    get_tag_sha: G,
+) -> anyhow::Result<Result<State, InvalidBootstrap>>
+where
+    G: Fn(Platform, String) -> Fut,
+    Fut: Future<Output = anyhow::Result<Option<String>>>,
+{
+    let mut state = state_without_shas(request);
+
+    if let Err(e) = state.validate() {
+        return Ok(Err(InvalidBootstrap::InvalidState(format!("{e:#}"))));
+    }
+
+    for platform in Platform::iter() {
+        let platform_state = state.platform_state_mut(platform);
+
+        for tag in [
+            &mut platform_state.last_posted_tag_previous_release,
+            &mut platform_state.last_posted_tag,
+        ] {
+            let sha = get_tag_sha(platform, tag.name.clone())
+                .await
+                .with_context(|| format!("could not get commit of {} for {platform}", tag.name))?;
+
+            match sha {
+                Some(sha) => *tag = Tag::with_sha(&tag.name, sha),
+                None => {
+                    return Ok(Err(InvalidBootstrap::TagNotFound {
+                        platform,
+                        tag: tag.name.clone(),
+                    }))
+                }
+            }
+        }
+    }
+
+    Ok(Ok(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::poll_once;
+
+    fn tags(previous_tag: &str, last_tag: &str) -> BootstrapTags {
+        BootstrapTags {
+            previous_tag: previous_tag.to_string(),
+            last_tag: last_tag.to_string(),
+        }
+    }
+
+    fn request(desktop: BootstrapTags) -> BootstrapRequest {
+        BootstrapRequest {
+            android: tags("v1.1.0", "v1.2.3"),
+            ios: tags("7.1.0.5-beta", "7.2.0.3-beta"),
+            desktop,
+            force: false,
+        }
+    }
+
+    fn sha_of(
+        _platform: Platform,
+        tag: String,
+    ) -> std::future::Ready<anyhow::Result<Option<String>>> {
+        std::future::ready(Ok(Some(format!("sha of {tag}"))))
+    }
+
+    #[test]
+    fn deserialization() {
+        let input = r#"{
+            "android": { "previous_tag": "v1.1.0", "last_tag": "v1.2.3" },
+            "ios": { "previous_tag": "7.1.0.5-beta", "last_tag": "7.2.0.3-beta" },
+            "desktop": { "previous_tag": "v6.1.0-beta.1", "last_tag": "v6.2.0-beta.2" }
+        }"#;
+
+        assert_eq!(
+            serde_json::from_str::<BootstrapRequest>(input).unwrap(),
+            request(tags("v6.1.0-beta.1", "v6.2.0-beta.2"))
+        );
+    }
+
+    #[test]
+    fn platform_missing() {
+        let input = r#"{
+            "android": { "previous_tag": "v1.1.0", "last_tag": "v1.2.3" },
+            "ios": { "previous_tag": "7.1.0.5-beta", "last_tag": "7.2.0.3-beta" },
+            "force": true
+        }"#;
+
+        assert!(serde_json::from_str::<BootstrapRequest>(input).is_err());
+    }
+
+    #[test]
+    fn state_with_shas() {
+        let state = poll_once(state_from_request(
+            &request(tags("v6.1.0-beta.1", "v6.2.0-beta.2")),
+            sha_of,
+        ))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            state.desktop,
+            PlatformState::initial(
+                Tag::with_sha("v6.1.0-beta.1", "sha of v6.1.0-beta.1"),
+                Tag::with_sha("v6.2.0-beta.2", "sha of v6.2.0-beta.2"),
+            )
+        );
+        assert_eq!(
+            state.android.last_posted_tag,
+            Tag::with_sha("v1.2.3", "sha of v1.2.3")
+        );
+    }
+
+    #[test_case("v6.2.0-beta.2", "v6.1.0-beta.1", "invalid state for Desktop: last_posted_version_previous_release >= last_posted_version"; "wrong order")]
+    #[test_case("v6.2.0-beta.2", "v6.2.0-beta.2", "invalid state for Desktop: last_posted_version_previous_release >= last_posted_version"; "same tag")]
+    #[test_case("latest", "v6.2.0-beta.2", "invalid state for Desktop: couldn't convert last_posted_tag_previous_release to a Version: could not parse version from tag: "; "not a version")]
+    fn invalid_state(previous_tag: &str, last_tag: &str, message_start: &str) {
+        let result = poll_once(state_from_request(
+            &request(tags(previous_tag, last_tag)),
+            |_, _| async { panic!("should not look up tags of an invalid state") },
+        ))
+        .unwrap();
+
+        match result {
+            Err(InvalidBootstrap::InvalidState(message)) => {
+                assert!(message.starts_with(message_start), "{message}")
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn tag_not_found() {
+        let result =
+            poll_once(state_from_request(
+                &request(tags("v6.1.0-beta.1", "v6.2.0-beta.2")),
+                |platform, tag| async move {
+                    Ok((platform != Desktop || tag != "v6.2.0-beta.2")
+                        .then(|| format!("sha of {tag}")))
+                },
+            ))
+            .unwrap();
+
+        assert_eq!(
+            result,
+            Err(InvalidBootstrap::TagNotFound {
+                platform: Desktop,
+                tag: String::from("v6.2.0-beta.2"),
+            })
+        );
+        assert_eq!(
+            result.unwrap_err().message(),
+            "tag v6.2.0-beta.2 not found for Desktop"
+        );
+    }
+
+    #[test]
+    fn lookup_failure() {
+        let result = poll_once(state_from_request(
+            &request(tags("v6.1.0-beta.1", "v6.2.0-beta.2")),
+            |_, _| async { Err(anyhow!("rate limit exceeded")) },
+        ));
+
+        assert_eq!(
+            format!("{:#}", result.unwrap_err()),
+            "could not get commit of v1.1.0 for Android: rate limit exceeded"
+        );
+    }
+}
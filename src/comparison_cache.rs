@@ -0,0 +1,275 @@
+use std::future::Future;
+
+use anyhow::{anyhow, Context};
+use worker::Env;
+use worker_kv::KvStore;
+
+use crate::{
+    hash,
+    http::HttpClient,
+    log::{log_error, log_info},
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+    types::github::{Comparison, Tag},
+    utils,
+};
+
+const COMPARISON_KV_KEY_PREFIX: &str = "cmp";
+
+/// Files-only comparisons (see [`utils::get_github_comparison_files`]) are kept apart from
+/// full ones, as they do not include all of the commits.
+const COMPARISON_FILES_KV_KEY_PREFIX: &str = "cmp-files";
+
+/// Cached comparisons expire after this many seconds.
+const TTL_SECONDS: u64 = 3 * 24 * 60 * 60;
+
+/// Includes the commit of each tag if known, so that a re-cut tag is compared anew.
+fn tag_key(tag: &Tag) -> String {
+    match tag.sha() {
+        Some(sha) => format!("{}@{sha}", tag.name),
+        None => tag.name.clone(),
+    }
+}
+
+fn kv_key(prefix: &str, platform: Platform, old_tag: &Tag, new_tag: &Tag) -> String {
+    format!(
+        "{prefix}:{}:{}",
+        platform.to_string().to_ascii_lowercase(),
+        hash::sha256_string(&format!("{}..{}", tag_key(old_tag), tag_key(new_tag)))
+    )
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Looks for the comparison at `key` in KV if `use_cache` is set, otherwise gets it
+/// with `get_from_github` and saves it there.
+///
+/// Failures to use KV are only logged, as GitHub can always be asked instead.
+async fn cached(
+    env: &Env,
+    platform: Platform,
+    key: &str,
+    use_cache: bool,
+    get_from_github: impl Future<Output = anyhow::Result<Comparison>>,
+) -> anyhow::Result<Comparison> {
+    if use_cache {
+        match get(env, key).await {
+            Ok(Some(comparison)) => {
+                log_info!(platform = platform, "using cached comparison ({key})");
+                return Ok(comparison);
+            }
+            Ok(None) => log_info!(platform = platform, "no cached comparison ({key})"),
+            Err(e) => log_error!(
+                platform = platform,
+                "could not get cached comparison: {e:?}"
+            ),
+        }
+    } else {
+        log_info!(platform = platform, "not using cached comparison ({key})");
+    }
+
+    let comparison = get_from_github.await?;
+
+    if let Err(e) = put(env, key, &comparison).await {
+        log_error!(platform = platform, "could not cache comparison: {e:?}");
+    }
+
+    Ok(comparison)
+}
+
+/// Like [`utils::get_github_comparison`], but first looks for the comparison in KV
+/// if `use_cache` is set, and saves it there after getting it from GitHub.
+pub async fn get_github_comparison(
+    env: &Env,
+    http_client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    use_cache: bool,
+) -> anyhow::Result<Comparison> {
+    // only complete comparisons are returned, so only those are cached
+    cached(
+        env,
+        platform,
+        &state::kv_key(
+            env,
+            &kv_key(COMPARISON_KV_KEY_PREFIX, platform, old_tag, new_tag),
+        ),
+        use_cache,
+        utils::get_github_comparison(
+            http_client,
+            github_token,
+            platform,
+            &old_tag.name,
+            &new_tag.name,
+        ),
+    )
+    .await
+}
+
+/// Like [`utils::get_github_comparison_files`], but cached like [`get_github_comparison`].
+pub async fn get_github_comparison_files(
+    env: &Env,
+    http_client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    use_cache: bool,
+) -> anyhow::Result<Comparison> {
+    cached(
+        env,
+        platform,
+        &state::kv_key(
+            env,
+            &kv_key(COMPARISON_FILES_KV_KEY_PREFIX, platform, old_tag, new_tag),
+        ),
+        use_cache,
+        utils::get_github_comparison_files(
+            http_client,
+            github_token,
+            platform,
+            &old_tag.name,
+            &new_tag.name,
+        ),
+    )
+    .await
+}
+
+async fn get(env: &Env, key: &str) -> anyhow::Result<Option<Comparison>> {
+    kv_store(env)?
+        .get(key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))
+}
+
+async fn put(env: &Env, key: &str, comparison: &Comparison) -> anyhow::Result<()> {
+    kv_store(env)?
+        .put(key, comparison)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .expiration_ttl(TTL_SECONDS)
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use super::*;
+
+    #[test]
+    fn key() {
+        let key = kv_key(
+            COMPARISON_KV_KEY_PREFIX,
+            Platform::Android,
+            &Tag::new("v1.2.3"),
+            &Tag::new("v1.2.4"),
+        );
+
+        assert_eq!(
+            key,
+            format!("cmp:android:{}", hash::sha256_string("v1.2.3..v1.2.4"))
+        );
+    }
+
+    #[test]
+    fn key_changes_for_recut_tag() {
+        let old_tag = Tag::with_sha("v1.2.3", "abc");
+
+        assert_ne!(
+            kv_key(
+                COMPARISON_KV_KEY_PREFIX,
+                Platform::Desktop,
+                &old_tag,
+                &Tag::with_sha("v1.2.4", "def")
+            ),
+            kv_key(
+                COMPARISON_KV_KEY_PREFIX,
+                Platform::Desktop,
+                &old_tag,
+                &Tag::with_sha("v1.2.4", "123")
+            )
+        );
+    }
+
+    #[test]
+    fn key_differs_between_platforms() {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        assert_ne!(
+            kv_key(COMPARISON_KV_KEY_PREFIX, Platform::Ios, &old_tag, &new_tag),
+            kv_key(
+                COMPARISON_KV_KEY_PREFIX,
+                Platform::Desktop,
+                &old_tag,
+                &new_tag
+            )
+        );
+    }
+
+    #[test]
+    fn files_key() {
+        let key = kv_key(
+            COMPARISON_FILES_KV_KEY_PREFIX,
+            Platform::Android,
+            &Tag::new("v1.2.0"),
+            &Tag::new("v1.3.0"),
+        );
+
+        assert_eq!(
+            key,
+            format!(
+                "cmp-files:android:{}",
+                hash::sha256_string("v1.2.0..v1.3.0")
+            )
+        );
+    }
+
+    #[test]
+    fn files_key_differs_from_full_key() {
+        let old_tag = Tag::new("v1.2.0");
+        let new_tag = Tag::new("v1.3.0");
+
+        assert_ne!(
+            kv_key(
+                COMPARISON_FILES_KV_KEY_PREFIX,
+                Platform::Ios,
+                &old_tag,
+                &new_tag
+            ),
+            kv_key(COMPARISON_KV_KEY_PREFIX, Platform::Ios, &old_tag, &new_tag)
+        );
+    }
+
+    #[test]
+    fn files_key_changes_with_base() {
+        let new_tag = Tag::new("v1.3.0-beta.2");
+
+        assert_ne!(
+            kv_key(
+                COMPARISON_FILES_KV_KEY_PREFIX,
+                Platform::Desktop,
+                &Tag::new("v1.2.0"),
+                &new_tag
+            ),
+            kv_key(
+                COMPARISON_FILES_KV_KEY_PREFIX,
+                Platform::Desktop,
+                &Tag::new("v1.2.1"),
+                &new_tag
+            )
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use std::future::Future;
+
+use anyhow::{bail, Context};
+use futures_util::future::join_all;
+use url::Url;
+
+use crate::core::types::github::{Commit, Comparison, ComparisonStatus};
+
+fn empty_comparison() -> Comparison {
+    Comparison {
+        status: ComparisonStatus::Identical,
+        ahead_by: 0,
+        total_commits: 0,
+        commits: Vec::new(),
+        files: Some(Vec::new()),
+        merge_base_commit: None,
+    }
+}
+
+fn merge_comparisons(target: &mut Comparison, source: &mut Comparison) {
+    target.status = source.status;
+    target.ahead_by = source.ahead_by;
+    target.total_commits = source.total_commits; // always the total number of commits
+    target.merge_base_commit = source.merge_base_commit.take();
+    target.commits.append(&mut source.commits);
+    if let Some(part_files) = &mut source.files {
+        target.files.as_mut().unwrap().append(part_files);
+    }
+}
+
+/// Commits per page of a comparison.
+const COMPARISON_PER_PAGE: usize = 100;
+
+fn page_url(initial_url: &Url, page: usize, per_page: usize) -> String {
+    let mut url = initial_url.clone();
+    url.query_pairs_mut()
+        .append_pair("page", &page.to_string())
+        .append_pair("per_page", &per_page.to_string());
+    url.to_string()
+}
+
+fn comparison_page_url(initial_url: &Url, page: usize) -> String {
+    page_url(initial_url, page, COMPARISON_PER_PAGE)
+}
+
+/// URL of the first page of the comparison at `initial_url`, with a single commit;
+/// GitHub lists the changed files of the whole comparison on the first page only,
+/// so this is enough to get all of them (up to [`Comparison::GITHUB_API_MAX_FILES`]).
+pub fn comparison_files_url(initial_url: &Url) -> String {
+    page_url(initial_url, 1, 1)
+}
+
+/// Number of pages of a comparison with `total_commits`, of which GitHub returns
+/// at most [`Comparison::GITHUB_API_MAX_COMMITS`].
+fn comparison_pages(total_commits: usize) -> usize {
+    total_commits
+        .min(Comparison::GITHUB_API_MAX_COMMITS)
+        .div_ceil(COMPARISON_PER_PAGE)
+        .max(1)
+}
+
+/// Gets the first page of the comparison at `initial_url`, then all remaining pages at once,
+/// as their number is known from `total_commits`. The pages are merged in order.
+///
+/// If the first page does not link to a next one, it is the only page.
+pub async fn collect_comparison_pages<G, Fut>(
+    initial_url: &Url,
+    get_page: G,
+) -> anyhow::Result<Comparison>
+where
+    G: Fn(String) -> Fut,
+    Fut: Future<Output = anyhow::Result<Page<Comparison>>>,
+{
+    let mut comparison = empty_comparison();
+
+    let mut first_page = get_page(comparison_page_url(initial_url, 1))
+        .await
+        .context("could not get page 1")?;
+
+    merge_comparisons(&mut comparison, &mut first_page.part);
+
+    if next_page_url(first_page.link_header.as_deref())?.is_none() {
+        return Ok(comparison);
+    }
+
+    // the first page links to a next one, so there are at least two
+    let pages = 2..=comparison_pages(comparison.total_commits).max(2);
+    let remaining_pages = join_all(
+        pages
+            .clone()
+            .map(|page| get_page(comparison_page_url(initial_url, page))),
+    )
+    .await;
+
+    for (page, result) in pages.zip(remaining_pages) {
+        let mut response = result.with_context(|| format!("could not get page {page}"))?;
+        merge_comparisons(&mut comparison, &mut response.part);
+    }
+
+    Ok(comparison)
+}
+
+/// Pages are only required to link to the next one while commits are missing,
+/// so an incomplete comparison is only detected here.
+pub fn complete_comparison(comparison: Comparison) -> anyhow::Result<Comparison> {
+    if comparison.total_commits != comparison.commits.len() {
+        bail!(
+            "incomplete full comparison: total_commits = {} but commits.len() = {}, commits = {:?}",
+            comparison.total_commits,
+            comparison.commits.len(),
+            comparison.commits
+        )
+    };
+
+    Ok(comparison)
+}
+
+/// Indicates whether `commits` (newest first) reach `merge_base_sha`, or already
+/// include more than `total_commits`, in which case `merge_base_sha` is not getting any closer.
+pub fn has_commits_since(commits: &[Commit], merge_base_sha: &str, total_commits: usize) -> bool {
+    commits.len() > total_commits || commits.iter().any(|commit| commit.sha == merge_base_sha)
+}
+
+/// Turns `commits` (newest first) into the commits after `merge_base_sha`, oldest first.
+pub fn commits_since(commits: Vec<Commit>, merge_base_sha: &str) -> anyhow::Result<Vec<Commit>> {
+    let merge_base_index = commits
+        .iter()
+        .position(|commit| commit.sha == merge_base_sha)
+        .with_context(|| format!("merge base commit {merge_base_sha} not found"))?;
+
+    let mut commits = commits;
+    commits.truncate(merge_base_index);
+    commits.reverse();
+
+    Ok(commits)
+}
+
+/// A page of a paginated response, with the `Link` header that points to the next one.
+pub struct Page<T> {
+    pub part: T,
+    pub link_header: Option<String>,
+}
+
+/// Gets pages starting from `initial_url` and merges them into `initial_result`,
+/// until a page does not link to a next one or the result `is_complete`.
+pub async fn collect_pages<T, F, G, Fut>(
+    initial_url: String,
+    initial_result: T,
+    merge: F,
+    is_complete: impl Fn(&T) -> bool,
+    mut get_page: G,
+) -> anyhow::Result<T>
+where
+    F: Fn(&mut T, &mut T),
+    G: FnMut(String) -> Fut,
+    Fut: Future<Output = anyhow::Result<Page<T>>>,
+{
+    let mut result = initial_result;
+    let mut next_url = Some(initial_url);
+    let mut page = 1;
+
+    while let Some(url_string) = next_url {
+        let mut response = get_page(url_string)
+            .await
+            .with_context(|| format!("could not get page {page}"))?;
+
+        merge(&mut result, &mut response.part);
+
+        if is_complete(&result) {
+            break;
+        }
+
+        next_url = next_page_url(response.link_header.as_deref())?;
+        page += 1;
+    }
+
+    Ok(result)
+}
+
+/// Returns the `next` link of a `Link` header, if any;
+/// a missing header means that there is only one page.
+fn next_page_url(link_header: Option<&str>) -> anyhow::Result<Option<String>> {
+    let Some(link_header) = link_header else {
+        return Ok(None);
+    };
+
+    let link_header =
+        parse_link_header::parse_with_rel(link_header).context("could not parse `Link` header")?;
+
+    Ok(link_header.get("next").map(|link| link.raw_uri.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::{poll_once, types::github::CommitData};
+
+    const COMPARISON_URL: &str =
+        "https://api.github.com/repos/signalapp/Signal-Desktop/compare/v1.2.3-beta.1...v1.2.3-beta.2";
+
+    fn comparison_page(total_commits: usize, shas: &[&str]) -> Comparison {
+        serde_json::from_value(json!({
+            "status": "ahead",
+            "ahead_by": total_commits,
+            "total_commits": total_commits,
+            "commits": shas
+                .iter()
+                .map(|sha| json!({ "sha": sha, "commit": { "message": sha }, "files": null }))
+                .collect::<Vec<_>>(),
+            "files": [{ "filename": format!("{}.txt", shas.join("-")) }],
+        }))
+        .unwrap()
+    }
+
+    fn commit(sha: &str) -> Commit {
+        Commit {
+            sha: sha.to_string(),
+            commit: CommitData {
+                message: format!("Commit {sha}"),
+                author: None,
+                verification: None,
+            },
+            author: None,
+            files: None,
+        }
+    }
+
+    #[test]
+    fn comparison_stitched_from_list_of_commits() {
+        const COMMITS_URL: &str =
+            "https://api.github.com/repos/signalapp/Signal-Android/commits?sha=v1.2.4";
+
+        let total_commits = Comparison::GITHUB_API_MAX_COMMITS + 10;
+
+        // newest first, like the list of commits; the merge base is on the third of five pages
+        let shas: Vec<String> = (0..total_commits + 150)
+            .rev()
+            .map(|index| format!("sha{index}"))
+            .collect();
+        let merge_base_sha = String::from("sha149");
+
+        let mut pages = shas.chunks(100).enumerate().map(|(index, chunk)| Page {
+            part: chunk.iter().map(|sha| commit(sha)).collect::<Vec<_>>(),
+            link_header: Some(format!(
+                r#"<{COMMITS_URL}&page={}&per_page=100>; rel="next""#,
+                index + 2
+            )),
+        });
+
+        let urls = RefCell::new(Vec::new());
+
+        let commits = poll_once(collect_pages(
+            format!("{COMMITS_URL}&page=1&per_page=100"),
+            Vec::new(),
+            |target: &mut Vec<Commit>, source| target.append(source),
+            |commits| has_commits_since(commits, &merge_base_sha, total_commits),
+            |url| {
+                urls.borrow_mut().push(url);
+                std::future::ready(pages.next().context("no more pages"))
+            },
+        ))
+        .unwrap();
+
+        assert_eq!(
+            urls.into_inner(),
+            vec![
+                format!("{COMMITS_URL}&page=1&per_page=100"),
+                format!("{COMMITS_URL}&page=2&per_page=100"),
+                format!("{COMMITS_URL}&page=3&per_page=100"),
+            ]
+        );
+
+        let mut comparison = comparison_page(total_commits, &[]);
+        comparison.commits = commits_since(commits, &merge_base_sha).unwrap();
+
+        let comparison = complete_comparison(comparison).unwrap();
+        assert_eq!(
+            comparison
+                .commits
+                .into_iter()
+                .map(|commit| commit.sha)
+                .collect::<Vec<_>>(),
+            (150..410)
+                .map(|index| format!("sha{index}"))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn merge_base_not_in_list_of_commits() {
+        let commits = vec![commit("c"), commit("b"), commit("a")];
+
+        assert!(has_commits_since(&commits, "z", 2));
+        assert!(commits_since(commits, "z").is_err());
+    }
+
+    #[test]
+    fn invalid_link_header() {
+        assert!(next_page_url(Some("not a link header")).is_err());
+    }
+
+    #[test_case(0, 1; "no commits")]
+    #[test_case(100, 1; "one full page")]
+    #[test_case(101, 2; "one more than a page")]
+    #[test_case(250, 3; "most commits GitHub returns")]
+    #[test_case(400, 3; "more commits than GitHub returns")]
+    fn pages_of_comparison(total_commits: usize, result: usize) {
+        assert_eq!(comparison_pages(total_commits), result);
+    }
+
+    /// Is pending as many times as it holds before being ready.
+    struct PendingTimes(usize);
+
+    impl Future for PendingTimes {
+        type Output = ();
+
+        fn poll(
+            mut self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<()> {
+            if self.0 == 0 {
+                return std::task::Poll::Ready(());
+            }
+
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            std::task::Poll::Pending
+        }
+    }
+
+    fn poll_until_ready<T>(future: impl Future<Output = T>) -> T {
+        let mut future = std::pin::pin!(future);
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+
+        loop {
+            if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    fn shas(page: usize, count: usize) -> Vec<String> {
+        (0..count).map(|index| format!("{page}-{index}")).collect()
+    }
+
+    #[test]
+    fn comparison_pages_are_stitched_in_order() {
+        let initial_url = Url::parse(COMPARISON_URL).unwrap();
+        let urls = RefCell::new(Vec::new());
+        let finished = RefCell::new(Vec::new());
+
+        // later pages finish first
+        let page_shas = [shas(1, 100), shas(2, 100), shas(3, 50)];
+        let pending_times = [0, 5, 2];
+
+        let comparison = poll_until_ready(collect_comparison_pages(&initial_url, |url| {
+            urls.borrow_mut().push(url.clone());
+
+            let page: usize = Url::parse(&url)
+                .unwrap()
+                .query_pairs()
+                .find(|(name, _)| name == "page")
+                .unwrap()
+                .1
+                .parse()
+                .unwrap();
+            let page_shas: Vec<&str> = page_shas[page - 1].iter().map(String::as_str).collect();
+            let part = comparison_page(250, &page_shas);
+            let finished = &finished;
+
+            async move {
+                PendingTimes(pending_times[page - 1]).await;
+                finished.borrow_mut().push(page);
+
+                Ok(Page {
+                    part,
+                    link_header: Some(format!(
+                        r#"<{COMPARISON_URL}?page={}&per_page=100>; rel="next""#,
+                        page + 1
+                    )),
+                })
+            }
+        }))
+        .and_then(complete_comparison)
+        .unwrap();
+
+        assert_eq!(finished.into_inner(), vec![1, 3, 2]);
+        assert_eq!(
+            urls.into_inner(),
+            (1..=3)
+                .map(|page| format!("{COMPARISON_URL}?page={page}&per_page=100"))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            comparison
+                .commits
+                .iter()
+                .map(|commit| commit.sha.as_str())
+                .collect::<Vec<_>>(),
+            page_shas.concat()
+        );
+    }
+
+    #[test]
+    fn comparison_without_link_header_is_one_page() {
+        let initial_url = Url::parse(COMPARISON_URL).unwrap();
+        let urls = RefCell::new(Vec::new());
+
+        let comparison = poll_once(collect_comparison_pages(&initial_url, |url| {
+            urls.borrow_mut().push(url);
+
+            std::future::ready(Ok(Page {
+                part: comparison_page(250, &["a", "b"]),
+                link_header: None,
+            }))
+        }))
+        .unwrap();
+
+        assert_eq!(comparison.commits.len(), 2);
+        assert_eq!(
+            urls.into_inner(),
+            vec![format!("{COMPARISON_URL}?page=1&per_page=100")]
+        );
+    }
+}
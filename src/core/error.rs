@@ -0,0 +1,141 @@
+use std::fmt;
+
+/// Errors that callers need to tell apart, most importantly to decide whether trying again
+/// later may help ([`Error::is_retriable`]) or someone has to look into it.
+///
+/// These are put at the root of an `anyhow::Error` chain where they happen,
+/// and the context added on the way up is kept as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// GitHub refused a request because a rate limit was exceeded,
+    /// `resets_in_seconds` is until requests are allowed again.
+    GitHubRateLimited { resets_in_seconds: Option<u64> },
+    /// Discourse refused a request with a client error, e.g. a post too similar to a recent one.
+    DiscourseRejected {
+        status_code: u16,
+        error_type: Option<String>,
+        errors: Vec<String>,
+    },
+    /// Discourse refused a request because the forum is read-only, e.g. during maintenance.
+    ForumReadOnly,
+    /// A request got no response, or a server error.
+    TransientNetwork { message: String },
+    /// The state does not hold together, e.g. the previous release is after the last one.
+    InvalidState { message: String },
+    /// Even without localization changes, the post is longer than Discourse allows.
+    PostTooLong,
+}
+
+impl Error {
+    /// `error`, with its whole chain, as the reason why the state is invalid.
+    pub fn invalid_state(error: anyhow::Error) -> Self {
+        Self::InvalidState {
+            message: format!("{error:#}"),
+        }
+    }
+
+    /// Whether trying again later may succeed without anyone changing anything.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Self::GitHubRateLimited { .. }
+            | Self::ForumReadOnly
+            | Self::TransientNetwork { .. } => true,
+            Self::DiscourseRejected { .. } | Self::InvalidState { .. } | Self::PostTooLong => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GitHubRateLimited {
+                resets_in_seconds: Some(seconds),
+            } => write!(f, "GitHub rate limited for {seconds} more seconds"),
+            Self::GitHubRateLimited {
+                resets_in_seconds: None,
+            } => write!(f, "GitHub rate limited"),
+            Self::DiscourseRejected {
+                status_code,
+                error_type,
+                errors,
+            } => {
+                write!(
+                    f,
+                    "discourse rejected the request with status code {status_code}"
+                )?;
+
+                if let Some(error_type) = error_type {
+                    write!(f, " ({error_type})")?;
+                }
+
+                if !errors.is_empty() {
+                    write!(f, ": {}", errors.join("; "))?;
+                }
+
+                Ok(())
+            }
+            Self::ForumReadOnly => write!(f, "forum is read-only"),
+            Self::TransientNetwork { message } => write!(f, "network error: {message}"),
+            Self::InvalidState { message } => write!(f, "invalid state: {message}"),
+            Self::PostTooLong => write!(
+                f,
+                "could not make a post that fits within the allowed character count"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{anyhow, Context};
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(Error::GitHubRateLimited { resets_in_seconds: Some(60) }, true; "rate limited")]
+    #[test_case(Error::ForumReadOnly, true; "read-only")]
+    #[test_case(Error::TransientNetwork { message: String::from("connection reset") }, true; "network")]
+    #[test_case(Error::DiscourseRejected { status_code: 422, error_type: None, errors: vec![] }, false; "rejected")]
+    #[test_case(Error::InvalidState { message: String::from("no tag") }, false; "invalid state")]
+    #[test_case(Error::PostTooLong, false; "too long")]
+    fn retriable(error: Error, result: bool) {
+        assert_eq!(error.is_retriable(), result);
+    }
+
+    #[test_case(Error::GitHubRateLimited { resets_in_seconds: Some(60) }, "GitHub rate limited for 60 more seconds"; "rate limited")]
+    #[test_case(Error::GitHubRateLimited { resets_in_seconds: None }, "GitHub rate limited"; "rate limited without reset")]
+    #[test_case(Error::DiscourseRejected { status_code: 422, error_type: Some(String::from("invalid_parameters")), errors: vec![String::from("Title is too short"), String::from("Body is too short")] }, "discourse rejected the request with status code 422 (invalid_parameters): Title is too short; Body is too short"; "rejected")]
+    #[test_case(Error::DiscourseRejected { status_code: 403, error_type: None, errors: vec![] }, "discourse rejected the request with status code 403"; "rejected without details")]
+    #[test_case(Error::PostTooLong, "could not make a post that fits within the allowed character count"; "too long")]
+    fn display(error: Error, result: &str) {
+        assert_eq!(error.to_string(), result);
+    }
+
+    #[test]
+    fn invalid_state_keeps_chain() {
+        let error = anyhow!("last_posted_version_previous_release >= last_posted_version")
+            .context("invalid state for iOS");
+
+        assert_eq!(
+            Error::invalid_state(error).to_string(),
+            "invalid state: invalid state for iOS: last_posted_version_previous_release >= last_posted_version"
+        );
+    }
+
+    #[test]
+    fn found_under_context() {
+        let error = Err::<(), _>(Error::PostTooLong)
+            .context("could not render post")
+            .unwrap_err();
+
+        assert_eq!(
+            error
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<Error>()),
+            Some(&Error::PostTooLong)
+        );
+    }
+}
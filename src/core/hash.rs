@@ -0,0 +1,73 @@
+use sha2::{Digest, Sha256};
+
+pub fn sha256_string(input: &str) -> String {
+    let result = Sha256::digest(input.as_bytes());
+    base16ct::lower::encode_string(&result)
+}
+
+/// HMAC-SHA256 (https://datatracker.ietf.org/doc/html/rfc2104) of `message` with `key`,
+/// encoded like [`sha256_string`].
+pub fn hmac_sha256_string(key: &str, message: &str) -> String {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut block_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block_key[..32].copy_from_slice(&Sha256::digest(key.as_bytes()));
+    } else {
+        block_key[..key.len()].copy_from_slice(key.as_bytes());
+    }
+
+    let inner = Sha256::new()
+        .chain_update(block_key.map(|byte| byte ^ 0x36))
+        .chain_update(message.as_bytes())
+        .finalize();
+
+    let result = Sha256::new()
+        .chain_update(block_key.map(|byte| byte ^ 0x5c))
+        .chain_update(inner)
+        .finalize();
+
+    base16ct::lower::encode_string(&result)
+}
+
+/// Compares `lhs` and `rhs` in time that does not depend on their contents (only on their lengths).
+pub fn constant_time_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    lhs.len() == rhs.len()
+        && lhs
+            .iter()
+            .zip(rhs.iter())
+            .fold(0, |acc, (lhs, rhs)| acc | (lhs ^ rhs))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+
+    // https://datatracker.ietf.org/doc/html/rfc4231#section-4
+    #[test_case(
+        "Jefe", "what do ya want for nothing?",
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+        "RFC 4231 test case 2"
+    )]
+    #[test_case(
+        &"k".repeat(100), r#"{"platform":"Android"}"#,
+        "24ec71ad16e61468b239a6a40545a4bfb8f2be772d128de81b977387de9faf21";
+        "key longer than block size"
+    )]
+    fn hmac_sha256(key: &str, message: &str, result: &str) {
+        assert_str_eq!(hmac_sha256_string(key, message), result);
+    }
+
+    #[test_case("secret", "secret", true; "equal")]
+    #[test_case("secret", "secreT", false; "different last byte")]
+    #[test_case("secret", "Secret", false; "different first byte")]
+    #[test_case("secret", "secret2", false; "different length")]
+    #[test_case("", "", true; "empty")]
+    fn constant_time_equality(lhs: &str, rhs: &str, result: bool) {
+        assert_eq!(constant_time_eq(lhs.as_bytes(), rhs.as_bytes()), result);
+    }
+}
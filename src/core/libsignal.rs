@@ -0,0 +1,234 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::core::{
+    platform::Platform::{self, *},
+    types::github::{Comparison, File},
+};
+
+lazy_static! {
+    /// A version like `0.30.2` on a line of a patch.
+    static ref VERSION_REGEX: Regex = Regex::new(r"\d+\.\d+\.\d+").unwrap();
+}
+
+/// Paths of the dependency manifests of `platform` that pin the version of libsignal.
+pub const fn manifest_paths(platform: Platform) -> &'static [&'static str] {
+    match platform {
+        Android => &["dependencies.gradle", "gradle/libs.versions.toml"],
+        Desktop => &["package.json"],
+        Ios => &[],
+    }
+}
+
+fn is_manifest(platform: Platform, file: &File) -> bool {
+    manifest_paths(platform).contains(&file.filename.as_str())
+}
+
+/// Whether libsignal may have been bumped in `comparison`, i.e. unless its files are
+/// all known and do not include any manifests.
+pub fn may_be_bumped_in(platform: Platform, comparison: &Comparison) -> bool {
+    match (&comparison.files, comparison.are_files_likely_complete()) {
+        (Some(files), Some(true)) => files.iter().any(|file| is_manifest(platform, file)),
+        _ => !manifest_paths(platform).is_empty(),
+    }
+}
+
+/// A change of the libsignal dependency, with the versions if they were found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibsignalBump {
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+}
+
+impl LibsignalBump {
+    /// Finds the change of libsignal in the manifests among `files` of a commit with
+    /// the subject `subject`.
+    ///
+    /// Patches are left out by GitHub for large diffs; if that is the case for a manifest,
+    /// the commit only counts as a bump if its subject mentions libsignal.
+    pub fn from_files(platform: Platform, subject: &str, files: &[File]) -> Option<Self> {
+        files
+            .iter()
+            .filter(|file| is_manifest(platform, file))
+            .find_map(|file| match &file.patch {
+                Some(patch) => Self::from_patch(patch),
+                None if subject.to_lowercase().contains("libsignal") => Some(Self {
+                    old_version: None,
+                    new_version: None,
+                }),
+                None => None,
+            })
+    }
+
+    /// Finds the change of libsignal in the removed and added lines of `patch`.
+    pub fn from_patch(patch: &str) -> Option<Self> {
+        let changed_lines: Vec<(char, &str)> = patch
+            .lines()
+            .filter(|line| !line.starts_with("---") && !line.starts_with("+++"))
+            .filter_map(|line| {
+                let mut chars = line.chars();
+                let sign = chars.next().filter(|sign| ['-', '+'].contains(sign))?;
+                Some((sign, chars.as_str()))
+            })
+            .filter(|(_, line)| line.to_lowercase().contains("libsignal"))
+            .collect();
+
+        if changed_lines.is_empty() {
+            return None;
+        }
+
+        let version = |sign: char| {
+            changed_lines
+                .iter()
+                .filter(|(line_sign, _)| *line_sign == sign)
+                .find_map(|(_, line)| VERSION_REGEX.find_iter(line).last())
+                .map(|version| version.as_str().to_string())
+        };
+
+        Some(Self {
+            old_version: version('-'),
+            new_version: version('+'),
+        })
+    }
+
+    /// Badge shown after the commit, like ":link: libsignal 0.30.2 → 0.31.0".
+    pub fn badge(&self) -> String {
+        match (&self.old_version, &self.new_version) {
+            (Some(old_version), Some(new_version)) if old_version != new_version => {
+                format!(":link: libsignal {old_version} → {new_version}")
+            }
+            (_, Some(new_version)) => format!(":link: libsignal {new_version}"),
+            _ => String::from(":link: libsignal"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+
+    const DESKTOP_PATCH: &str = r#"@@ -100,7 +100,7 @@
+     "@react-spring/web": "9.5.5",
+-    "@signalapp/libsignal-client": "0.30.2",
++    "@signalapp/libsignal-client": "0.31.0",
+     "@signalapp/ringrtc": "2.31.1","#;
+
+    const ANDROID_TOML_PATCH: &str = r#"@@ -14,7 +14,7 @@ kotlin = "1.8.10"
+-libsignal-client = "0.30.2"
++libsignal-client = "0.31.0"
+ mp4parser = "1.9.39""#;
+
+    const ANDROID_GRADLE_PATCH: &str = r#"@@ -5,4 +5,4 @@
+-    implementation "org.signal:libsignal-android:0.29.0"
++    implementation "org.signal:libsignal-android:0.30.0"
+     implementation "org.signal:ringrtc-android:2.28.0""#;
+
+    #[test_case(DESKTOP_PATCH, Some("0.30.2"), Some("0.31.0"); "Desktop package.json")]
+    #[test_case(ANDROID_TOML_PATCH, Some("0.30.2"), Some("0.31.0"); "Android version catalog")]
+    #[test_case(ANDROID_GRADLE_PATCH, Some("0.29.0"), Some("0.30.0"); "Android dependencies.gradle")]
+    #[test_case("@@ -1 +1,2 @@\n+    \"@signalapp/libsignal-client\": \"0.31.0\",", None, Some("0.31.0"); "added")]
+    #[test_case("@@ -1 +1 @@\n-libsignal = \"main\"\n+libsignal = \"feature\"", None, None; "no versions")]
+    fn bump_from_patch(patch: &str, old_version: Option<&str>, new_version: Option<&str>) {
+        assert_eq!(
+            LibsignalBump::from_patch(patch),
+            Some(LibsignalBump {
+                old_version: old_version.map(ToOwned::to_owned),
+                new_version: new_version.map(ToOwned::to_owned),
+            })
+        );
+    }
+
+    #[test_case("@@ -1,3 +1,3 @@\n \"@signalapp/libsignal-client\": \"0.31.0\",\n-\"zod\": \"3.21.0\",\n+\"zod\": \"3.22.0\","; "only in context")]
+    #[test_case("--- a/libsignal.txt\n+++ b/libsignal.txt\n@@ -1 +1 @@\n-a\n+b"; "only in file names")]
+    #[test_case(""; "empty")]
+    fn no_bump_from_patch(patch: &str) {
+        assert_eq!(LibsignalBump::from_patch(patch), None);
+    }
+
+    fn file(filename: &str, patch: Option<&str>) -> File {
+        File {
+            patch: patch.map(ToOwned::to_owned),
+            ..File::new(filename)
+        }
+    }
+
+    #[test]
+    fn bump_from_manifest_files() {
+        let files = vec![
+            file("ts/main.ts", Some("-libsignal 0.1.0\n+libsignal 0.2.0")),
+            file("package.json", Some(DESKTOP_PATCH)),
+        ];
+
+        assert_eq!(
+            LibsignalBump::from_files(Desktop, "Update dependencies", &files),
+            Some(LibsignalBump {
+                old_version: Some(String::from("0.30.2")),
+                new_version: Some(String::from("0.31.0")),
+            })
+        );
+    }
+
+    #[test]
+    fn no_bump_from_other_files() {
+        let files = vec![file(
+            "ts/main.ts",
+            Some("-libsignal 0.1.0\n+libsignal 0.2.0"),
+        )];
+
+        assert_eq!(
+            LibsignalBump::from_files(Desktop, "Update libsignal", &files),
+            None
+        );
+    }
+
+    #[test_case("Update libsignal to 0.31.0", true; "subject mentions libsignal")]
+    #[test_case("Update dependencies", false; "subject does not mention libsignal")]
+    fn bump_without_patch(subject: &str, is_bump: bool) {
+        let files = vec![file("gradle/libs.versions.toml", None)];
+
+        assert_eq!(
+            LibsignalBump::from_files(Android, subject, &files),
+            is_bump.then_some(LibsignalBump {
+                old_version: None,
+                new_version: None,
+            })
+        );
+    }
+
+    #[test_case(Some("0.30.2"), Some("0.31.0"), ":link: libsignal 0.30.2 → 0.31.0"; "both versions")]
+    #[test_case(None, Some("0.31.0"), ":link: libsignal 0.31.0"; "new version")]
+    #[test_case(Some("0.31.0"), Some("0.31.0"), ":link: libsignal 0.31.0"; "same version")]
+    #[test_case(Some("0.30.2"), None, ":link: libsignal"; "old version")]
+    #[test_case(None, None, ":link: libsignal"; "no versions")]
+    fn badge(old_version: Option<&str>, new_version: Option<&str>, result: &str) {
+        let bump = LibsignalBump {
+            old_version: old_version.map(ToOwned::to_owned),
+            new_version: new_version.map(ToOwned::to_owned),
+        };
+
+        assert_str_eq!(bump.badge(), result);
+    }
+
+    fn comparison(files: Option<Vec<File>>) -> Comparison {
+        Comparison {
+            status: crate::core::types::github::ComparisonStatus::Ahead,
+            ahead_by: 1,
+            total_commits: 1,
+            commits: Vec::new(),
+            files,
+            merge_base_commit: None,
+        }
+    }
+
+    #[test_case(Desktop, Some(vec![File::new("package.json")]), true; "manifest changed")]
+    #[test_case(Desktop, Some(vec![File::new("ts/main.ts")]), false; "manifest not changed")]
+    #[test_case(Desktop, Some(vec![File::new("ts/main.ts"); Comparison::GITHUB_API_MAX_FILES]), true; "files incomplete")]
+    #[test_case(Android, None, true; "files unknown")]
+    #[test_case(Ios, None, false; "no manifests")]
+    fn may_be_bumped(platform: Platform, files: Option<Vec<File>>, result: bool) {
+        assert_eq!(may_be_bumped_in(platform, &comparison(files)), result);
+    }
+}
@@ -0,0 +1,926 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    localization::{
+        Language,
+        StringsFileKind::{self, *},
+    },
+    platform::{
+        Platform::{self, *},
+        ANDROID_DEFAULT_STRINGS_FILENAME,
+    },
+    types::github::{File, FileStatus, Tag},
+};
+
+pub type UnsortedChanges = HashMap<Language, ChangedFiles>;
+
+/// Strings files of a language that were changed, and how the language was changed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(from = "StoredChangedFiles")]
+pub struct ChangedFiles {
+    pub kinds: HashSet<StringsFileKind>,
+    pub status: FileStatus,
+}
+
+/// [`ChangedFiles`] as stored; changes saved before statuses were recorded are only kinds.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredChangedFiles {
+    Kinds(HashSet<StringsFileKind>),
+    ChangedFiles {
+        kinds: HashSet<StringsFileKind>,
+        #[serde(default)]
+        status: FileStatus,
+    },
+}
+
+impl From<StoredChangedFiles> for ChangedFiles {
+    fn from(stored: StoredChangedFiles) -> Self {
+        match stored {
+            StoredChangedFiles::Kinds(kinds) => Self {
+                kinds,
+                status: FileStatus::Modified,
+            },
+            StoredChangedFiles::ChangedFiles { kinds, status } => Self { kinds, status },
+        }
+    }
+}
+
+/// Status of a language whose files were changed with `status` and `other` in the same
+/// comparison; a language is only added (or removed) if all of its files are.
+fn status_together(status: FileStatus, other: FileStatus) -> FileStatus {
+    if status == other {
+        status
+    } else {
+        FileStatus::Modified
+    }
+}
+
+/// The stronger of the statuses of a language that was changed more than once,
+/// where added beats removed, which beats renamed, which beats modified; so that merging
+/// changes does not depend on how they are grouped.
+fn stronger_status(status: FileStatus, other: FileStatus) -> FileStatus {
+    use FileStatus::*;
+
+    let strength = |status| match status {
+        Modified => 0,
+        Renamed => 1,
+        Removed => 2,
+        Added => 3,
+    };
+
+    if strength(other) > strength(status) {
+        other
+    } else {
+        status
+    }
+}
+
+// TODO: the '.' from StringsFileKind::path() will be interpreted as "any character", not just '.'
+
+const ANDROID_LANGUAGE_CODE_PATTERN: &str = "([a-zA-Z]{2,3}(-r[A-Z]{2})?)";
+const IOS_DESKTOP_LANGUAGE_CODE_PATTERN: &str = "([a-zA-Z]{2,3}(_[A-Z]{2})?)";
+const IOS_APP_STORE_LANGUAGE_CODE_PATTERN: &str = "([a-zA-Z]{2,3}(-[a-zA-Z]{2,4})?)";
+
+fn regex(platform: Platform, kind: StringsFileKind, pattern: &str) -> Regex {
+    Regex::new(&kind.path(platform, pattern)).unwrap()
+}
+
+lazy_static! {
+    // Android
+    static ref ANDROID_REGEX: Regex =
+        regex(Android, Main, ANDROID_LANGUAGE_CODE_PATTERN);
+
+    // iOS
+    static ref IOS_MAIN_REGEX: Regex =
+        regex(Ios, Main, IOS_DESKTOP_LANGUAGE_CODE_PATTERN);
+    static ref IOS_INFO_PLIST_REGEX: Regex =
+        regex(Ios, InfoPlist, IOS_DESKTOP_LANGUAGE_CODE_PATTERN);
+    static ref IOS_PLURAL_AWARE_REGEX: Regex =
+        regex(Ios, PluralAware, IOS_DESKTOP_LANGUAGE_CODE_PATTERN);
+    static ref IOS_APP_STORE_DESCRIPTION_REGEX: Regex =
+        regex(Ios, AppStoreDescription, IOS_APP_STORE_LANGUAGE_CODE_PATTERN);
+    static ref IOS_APP_STORE_RELEASE_NOTES_REGEX: Regex =
+        regex(Ios, AppStoreReleaseNotes, IOS_APP_STORE_LANGUAGE_CODE_PATTERN);
+
+    // Desktop
+    static ref DESKTOP_REGEX: Regex =
+        regex(Desktop, Main, IOS_DESKTOP_LANGUAGE_CODE_PATTERN);
+}
+
+/// Ordered by status first, so that added languages are listed first and removed ones last.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LocalizationChange {
+    status: FileStatus,
+    language: Language,
+    kinds: Vec<StringsFileKind>,
+}
+
+impl LocalizationChange {
+    /// Language and kind of the strings file at `filename`, if it is one.
+    fn strings_file(platform: Platform, filename: &str) -> Option<(Language, StringsFileKind)> {
+        StringsFileKind::applicable_iter(platform).find_map(move |kind| {
+            let regex = match platform {
+                Android => {
+                    if filename == ANDROID_DEFAULT_STRINGS_FILENAME {
+                        return Some((Language::default(), Main));
+                    }
+
+                    &*ANDROID_REGEX
+                }
+                Ios => match kind {
+                    Main => &*IOS_MAIN_REGEX,
+                    InfoPlist => &*IOS_INFO_PLIST_REGEX,
+                    PluralAware => &*IOS_PLURAL_AWARE_REGEX,
+                    AppStoreDescription => &*IOS_APP_STORE_DESCRIPTION_REGEX,
+                    AppStoreReleaseNotes => &*IOS_APP_STORE_RELEASE_NOTES_REGEX,
+                },
+                Desktop => &*DESKTOP_REGEX,
+            };
+
+            regex
+                .captures_iter(filename)
+                .filter_map(|captures| captures.get(1))
+                .map(|capture| capture.as_str())
+                .find_map(|language_code| Language::try_from(language_code).ok())
+                .map(|language| (language, kind))
+        })
+    }
+
+    fn unsorted_changes_from_statuses<'f>(
+        platform: Platform,
+        files: impl Iterator<Item = (&'f str, FileStatus)>,
+    ) -> UnsortedChanges {
+        let mut map: UnsortedChanges = HashMap::new();
+
+        for (filename, status) in files {
+            if let Some((language, kind)) = Self::strings_file(platform, filename) {
+                let changed_files = map.entry(language).or_insert_with(|| ChangedFiles {
+                    kinds: HashSet::new(),
+                    status,
+                });
+
+                changed_files.kinds.insert(kind);
+                changed_files.status = status_together(changed_files.status, status);
+            }
+        }
+
+        map
+    }
+
+    /// Changes in the files at `file_paths`, all of which are taken as modified.
+    #[cfg(test)]
+    pub fn unsorted_changes_from_file_paths(
+        platform: Platform,
+        file_paths: &[&str],
+    ) -> UnsortedChanges {
+        Self::unsorted_changes_from_statuses(
+            platform,
+            file_paths
+                .iter()
+                .map(|&file_path| (file_path, FileStatus::Modified)),
+        )
+    }
+
+    /// A file renamed from the strings file of another language removes that language,
+    /// and adds the language of the new name as renamed.
+    pub fn unsorted_changes_from_files(
+        platform: Platform,
+        files: &Option<Vec<File>>,
+    ) -> UnsortedChanges {
+        let statuses = files.as_ref().unwrap().iter().flat_map(|file| {
+            let filename = file.filename.as_str();
+
+            match (file.status, &file.previous_filename) {
+                (FileStatus::Renamed, Some(previous_filename)) => {
+                    let language = |filename| {
+                        Self::strings_file(platform, filename).map(|(language, _)| language)
+                    };
+
+                    if language(previous_filename).is_some()
+                        && language(previous_filename) == language(filename)
+                    {
+                        vec![(filename, FileStatus::Modified)]
+                    } else {
+                        vec![
+                            (previous_filename.as_str(), FileStatus::Removed),
+                            (filename, FileStatus::Renamed),
+                        ]
+                    }
+                }
+                (status, _) => vec![(filename, status)],
+            }
+        });
+
+        Self::unsorted_changes_from_statuses(platform, statuses)
+    }
+
+    /// Merges `items` in order, each one with changes made after the ones before it.
+    ///
+    /// Languages are the same if their [`Language::canonical_code`]s are, as the paths of
+    /// their files may change between the items; the language of the last item is kept,
+    /// along with all of the kinds and the stronger status (see [`stronger_status`]).
+    pub fn merge_unsorted_changes(items: &[&UnsortedChanges]) -> UnsortedChanges {
+        let mut map: HashMap<String, (Language, ChangedFiles)> = HashMap::new();
+
+        for unsorted_changes in items {
+            for (language, changed_files) in *unsorted_changes {
+                map.entry(language.canonical_code())
+                    .and_modify(|(merged_language, merged)| {
+                        *merged_language = language.clone();
+                        merged.kinds.extend(changed_files.kinds.iter().copied());
+                        merged.status = stronger_status(merged.status, changed_files.status);
+                    })
+                    .or_insert_with(|| (language.clone(), changed_files.clone()));
+            }
+        }
+
+        map.into_values().collect()
+    }
+
+    pub fn sorted_changes(unsorted_changes: UnsortedChanges) -> Vec<Self> {
+        let mut changes: Vec<_> = unsorted_changes
+            .into_iter()
+            .map(|(language, changed_files)| {
+                let mut kinds: Vec<_> = changed_files.kinds.into_iter().collect();
+                kinds.sort_unstable();
+                LocalizationChange {
+                    status: changed_files.status,
+                    language,
+                    kinds,
+                }
+            })
+            .collect();
+
+        changes.sort_unstable();
+
+        changes
+    }
+
+    /// Groups `changes` by status, in the order of [`FileStatus`]; changes keep their
+    /// order within each group, and there are no empty groups.
+    pub fn grouped_by_status(changes: Vec<Self>) -> Vec<(FileStatus, Vec<Self>)> {
+        let mut groups: BTreeMap<FileStatus, Vec<Self>> = BTreeMap::new();
+
+        for change in changes {
+            groups.entry(change.status).or_default().push(change);
+        }
+
+        groups.into_iter().collect()
+    }
+
+    /// Marks added languages with `:new:`, renamed ones with `(renamed)`,
+    /// and strikes through removed ones.
+    fn with_status_marker(&self, text: String) -> String {
+        match self.status {
+            FileStatus::Added => format!(":new: {text}"),
+            FileStatus::Renamed => format!(":new: {text} (renamed)"),
+            FileStatus::Modified => text,
+            FileStatus::Removed => format!("~~{text}~~"),
+        }
+    }
+
+    /// A link to the diff of the file at `path`, followed by a link to the file itself
+    /// (at the new tag, or at the old one if the language was removed).
+    fn linked(
+        &self,
+        platform: Platform,
+        old_tag: &Tag,
+        new_tag: &Tag,
+        text: &str,
+        path: &str,
+    ) -> String {
+        let file_tag = match self.status {
+            FileStatus::Removed => old_tag,
+            _ => new_tag,
+        };
+
+        format!(
+            "[{text}]({}) ([file]({}))",
+            platform.github_comparison_url(&old_tag.name, &new_tag.name, Some(path)),
+            platform.github_file_url(&file_tag.name, path)
+        )
+    }
+
+    pub fn string(&self, platform: Platform, old_tag: &Tag, new_tag: &Tag) -> String {
+        let file_paths = self.file_paths(platform);
+
+        let text = match (platform, &self.kinds[..]) {
+            (Android | Desktop, &[Main]) => self.linked(
+                platform,
+                old_tag,
+                new_tag,
+                &self.language.to_string(),
+                &file_paths[0],
+            ),
+            _ => format!(
+                "{}: {}",
+                self.language,
+                self.kinds
+                    .iter()
+                    .zip(file_paths.iter())
+                    .map(|(kind, path)| {
+                        self.linked(platform, old_tag, new_tag, &kind.to_string(), path)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" • ")
+            ),
+        };
+
+        self.with_status_marker(text)
+    }
+
+    pub fn file_paths(&self, platform: Platform) -> Vec<String> {
+        self.kinds
+            .iter()
+            .map(|kind| {
+                if !kind.applicable_for_platform(platform) {
+                    panic!("unexpected strings file for {platform}: {kind:?}")
+                }
+
+                match (platform, kind) {
+                    (Android, _) => match self.language.full_code().as_str() {
+                        "en" => ANDROID_DEFAULT_STRINGS_FILENAME.to_owned(),
+                        _ => kind.path(platform, &self.language.full_code().replace('-', "-r")),
+                    },
+                    (Ios, Main | InfoPlist | PluralAware) | (Desktop, _) => {
+                        kind.path(platform, &self.language.full_code().replace('-', "_"))
+                    }
+                    (Ios, AppStoreDescription | AppStoreReleaseNotes) => {
+                        kind.path(platform, &self.language.full_code())
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl LocalizationChange {
+    pub fn test_change(language_code: &str, kinds: Vec<StringsFileKind>) -> LocalizationChange {
+        LocalizationChange {
+            status: FileStatus::Modified,
+            language: Language::try_from(language_code).unwrap(),
+            kinds,
+        }
+    }
+
+    pub fn with_status(self, status: FileStatus) -> LocalizationChange {
+        LocalizationChange { status, ..self }
+    }
+
+    pub fn status(&self) -> FileStatus {
+        self.status
+    }
+
+    pub fn language(&self) -> &Language {
+        &self.language
+    }
+
+    pub fn kinds(&self) -> &[StringsFileKind] {
+        &self.kinds
+    }
+
+    pub fn unsorted_changes(sorted_changes: Vec<LocalizationChange>) -> UnsortedChanges {
+        sorted_changes
+            .into_iter()
+            .map(|change| {
+                (
+                    change.language().clone(),
+                    ChangedFiles {
+                        kinds: change.kinds().iter().copied().collect(),
+                        status: change.status(),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+
+    #[ignore = "online and doesn't actually test"]
+    #[test]
+    fn online_localization_change_all() {
+        use strum::IntoEnumIterator;
+
+        use crate::core::{platform::Platform, types::github};
+
+        #[allow(clippy::type_complexity)]
+        let platforms: HashMap<Platform, HashMap<&str, Vec<&str>>> = Platform::iter()
+            .map(|platform| {
+                let mut map = HashMap::new();
+
+                for kind in StringsFileKind::applicable_iter(platform) {
+                    map.entry(kind.path_base(platform))
+                        .or_insert_with(Vec::new)
+                        .push(kind.path_file_name(platform));
+                }
+
+                (platform, map)
+            })
+            .collect();
+
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(crate::utils::user_agent())
+            .build()
+            .expect("client should be built successfully");
+
+        for (platform, pairs) in platforms {
+            for (path, filenames) in pairs {
+                let url = format!(
+                    "https://api.github.com/repos/signalapp/Signal-{platform}/contents/{path}"
+                );
+
+                let entries: Vec<github::ContentsEntry> = client
+                    .get(url)
+                    .send()
+                    .expect("request should succeed")
+                    .json()
+                    .expect("json should be successfully deserialized");
+
+                let file_paths: Vec<_> = entries
+                    .into_iter()
+                    .map(|entry| entry.path)
+                    .flat_map(|path| {
+                        filenames
+                            .iter()
+                            .map(move |filename| format!("{path}/{filename}"))
+                    })
+                    .collect();
+
+                let file_paths: Vec<_> = file_paths
+                    .iter()
+                    .map(|full_path| full_path.as_str())
+                    .collect();
+
+                let changes = LocalizationChange::unsorted_changes_from_file_paths(
+                    platform,
+                    file_paths.as_slice(),
+                );
+
+                let sorted = LocalizationChange::sorted_changes(changes);
+
+                println!(
+                    "{platform} ({path}): {:#?}",
+                    sorted
+                        .iter()
+                        .map(|change| change.language.to_string())
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+
+        panic!("testing");
+    }
+
+    #[test_case(Android, "app/src/main/res/values/strings.xml", "English (`en`)"; "Android: en")]
+    #[test_case(Android, "app/src/main/res/values-kab/strings.xml", "Kabyle (`kab`)"; "Android: kab")]
+    #[test_case(Android, "app/src/main/res/values-pa-rPK/strings.xml", "Punjabi (Pakistan) (`pa-PK`)"; "Android: pa dash r PK")]
+    #[test_case(Desktop, "_locales/en/messages.json", "English (`en`)"; "Desktop: en")]
+    #[test_case(Desktop, "_locales/kab/messages.json", "Kabyle (`kab`)"; "Desktop: kab")]
+    #[test_case(Desktop, "_locales/pa_PK/messages.json", "Punjabi (Pakistan) (`pa-PK`)"; "Desktop: pa underscore PK")]
+    #[test_case(Ios, "Signal/translations/en.lproj/Localizable.strings", "English (`en`)"; "iOS main: en")]
+    #[test_case(Ios, "Signal/translations/kab.lproj/Localizable.strings", "Kabyle (`kab`)"; "iOS main: kab")]
+    #[test_case(Ios, "Signal/translations/pa_PK.lproj/Localizable.strings", "Punjabi (Pakistan) (`pa-PK`)"; "iOS main: pa underscore PK")]
+    #[test_case(Ios, "Signal/translations/en.lproj/InfoPlist.strings", "English (`en`)"; "iOS info plist: en")]
+    #[test_case(Ios, "Signal/translations/en.lproj/PluralAware.stringsdict", "English (`en`)"; "iOS plural aware: en")]
+    #[test_case(Ios, "fastlane/metadata/en-US/description.txt", "English (United States) (`en-US`)"; "iOS app store description: en dash US")]
+    #[test_case(Ios, "fastlane/metadata/en-US/release_notes.txt", "English (United States) (`en-US`)"; "iOS app store release notes: en dash US")]
+    fn localization_change_language(platform: Platform, file_path: &str, result: &str) {
+        assert_eq!(
+            LocalizationChange::sorted_changes(
+                LocalizationChange::unsorted_changes_from_file_paths(platform, &[file_path])
+            )
+            .iter()
+            .map(LocalizationChange::language)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+            vec![result]
+        );
+    }
+
+    #[test_case(Android, "app/src/main/res/values-pt-rBR/strings.xml", "Portuguese (Brazil)"; "Android: pt dash r BR")]
+    #[test_case(Android, "app/src/main/res/values-nb/strings.xml", "Norwegian Bokmål"; "Android: nb")]
+    #[test_case(Android, "app/src/main/res/values-iw/strings.xml", "Hebrew"; "Android: legacy iw")]
+    #[test_case(Android, "app/src/main/res/values-in/strings.xml", "Indonesian"; "Android: legacy in")]
+    #[test_case(Android, "app/src/main/res/values-zh-rCN/strings.xml", "Chinese (Simplified)"; "Android: zh dash r CN")]
+    #[test_case(Ios, "Signal/translations/pt_BR.lproj/Localizable.strings", "Portuguese (Brazil)"; "iOS main: pt underscore BR")]
+    #[test_case(Ios, "Signal/translations/nb_NO.lproj/Localizable.strings", "Norwegian Bokmål"; "iOS main: nb underscore NO")]
+    #[test_case(Ios, "fastlane/metadata/pt-BR/release_notes.txt", "Portuguese (Brazil)"; "iOS app store: pt dash BR")]
+    #[test_case(Ios, "fastlane/metadata/zh-Hant/description.txt", "Chinese (Traditional)"; "iOS app store: zh dash Hant")]
+    #[test_case(Desktop, "_locales/pt_BR/messages.json", "Portuguese (Brazil)"; "Desktop: pt underscore BR")]
+    #[test_case(Desktop, "_locales/zh_TW/messages.json", "Chinese (Traditional)"; "Desktop: zh underscore TW")]
+    #[test_case(Desktop, "_locales/yue/messages.json", "Cantonese"; "Desktop: yue")]
+    fn localization_change_language_name(platform: Platform, file_path: &str, result: &str) {
+        assert_eq!(
+            LocalizationChange::sorted_changes(
+                LocalizationChange::unsorted_changes_from_file_paths(platform, &[file_path])
+            )
+            .iter()
+            .map(|change| change.language().language_name())
+            .collect::<Vec<_>>(),
+            vec![result]
+        );
+    }
+
+    #[test_case(Ios, &[("en", 5)], &[
+        "Signal/translations/en.lproj/Localizable.strings",
+        "Signal/translations/en.lproj/InfoPlist.strings",
+        "fastlane/metadata/en/description.txt",
+        "Signal/translations/en.lproj/PluralAware.stringsdict",
+        "fastlane/metadata/en/release_notes.txt",
+    ]; "iOS all en")]
+    #[test_case(Ios, &[("en", 3), ("en-US", 2)], &[
+        "Signal/translations/en.lproj/InfoPlist.strings",
+        "fastlane/metadata/en-US/description.txt",
+        "Signal/translations/en.lproj/Localizable.strings",
+        "fastlane/metadata/en-US/release_notes.txt",
+        "Signal/translations/en.lproj/PluralAware.stringsdict",
+    ]; "iOS en and en dash US")]
+    fn localization_change_multiple_languages_multiple_kinds(
+        platform: Platform,
+        languages: &[(&str, usize)],
+        file_paths: &[&str],
+    ) {
+        let result = LocalizationChange::sorted_changes(
+            LocalizationChange::unsorted_changes_from_file_paths(platform, file_paths),
+        );
+
+        dbg!(&result);
+        assert_eq!(result.len(), languages.len());
+
+        let mut total_kinds_count = 0;
+
+        for (language, kinds_count) in languages {
+            total_kinds_count += kinds_count;
+
+            assert_eq!(
+                &result
+                    .iter()
+                    .find(|change| &change.language().full_code() == language)
+                    .unwrap()
+                    .kinds()
+                    .len(),
+                kinds_count
+            );
+        }
+
+        assert_eq!(total_kinds_count, file_paths.len(), "invalid test");
+    }
+
+    #[test_case(Android, "app/src/main/res/values-land/strings.xml"; "Android: land")]
+    #[test_case(Android, "app/src/main/res/values-v9/strings.xml"; "Android: v9")]
+    #[test_case(Ios, "Signal/translations/.tx/signal-ios.localizablestrings-30/de_translation"; "iOS: dot tx 1")]
+    #[test_case(Ios, "Signal/translations/.tx/signal-ios.localizablestrings-30/zh_CN_translation"; "iOS: dot tx 2")]
+    #[test_case(Ios, "Signal/translations/.tx/signal-ios.localizablestrings-30/zh_TW.Big5_translation"; "iOS: dot tx 3")]
+    fn localization_change_none(platform: Platform, file_path: &str) {
+        assert_eq!(
+            LocalizationChange::sorted_changes(
+                LocalizationChange::unsorted_changes_from_file_paths(platform, &[file_path])
+            ),
+            vec![]
+        );
+    }
+
+    #[test_case(Android, Main, "en", "app/src/main/res/values/strings.xml"; "Android: en")]
+    #[test_case(Android, Main, "en-US", "app/src/main/res/values-en-rUS/strings.xml"; "Android: en dash US")]
+    #[test_case(Desktop, Main, "en", "_locales/en/messages.json"; "Desktop: en")]
+    #[test_case(Desktop, Main, "en-US", "_locales/en_US/messages.json"; "Desktop: en dash US")]
+    #[test_case(Ios, Main, "en", "Signal/translations/en.lproj/Localizable.strings"; "iOS main: en")]
+    #[test_case(Ios, Main, "en-US", "Signal/translations/en_US.lproj/Localizable.strings"; "iOS main: en dash US")]
+    #[test_case(Ios, InfoPlist, "en", "Signal/translations/en.lproj/InfoPlist.strings"; "iOS info plist: en")]
+    #[test_case(Ios, PluralAware, "en", "Signal/translations/en.lproj/PluralAware.stringsdict"; "iOS plural aware: en")]
+    #[test_case(Ios, AppStoreDescription, "en-US", "fastlane/metadata/en-US/description.txt"; "iOS app store description: en dash US")]
+    #[test_case(Ios, AppStoreReleaseNotes, "en-US", "fastlane/metadata/en-US/release_notes.txt"; "iOS app store release notes: en dash US")]
+    fn path_for_language_code_and_reverse(
+        platform: Platform,
+        kind: StringsFileKind,
+        language_code: &str,
+        result: &str,
+    ) {
+        let localization_change = LocalizationChange::test_change(language_code, vec![kind]);
+
+        let file_paths = localization_change.file_paths(platform);
+        assert_eq!(file_paths, vec![result]);
+
+        let changes = LocalizationChange::sorted_changes(
+            LocalizationChange::unsorted_changes_from_file_paths(
+                platform,
+                &file_paths
+                    .iter()
+                    .map(|string| string.as_str())
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        assert_eq!(changes, vec![localization_change]);
+    }
+
+    #[test_case(
+        Android, &[Main], "en",
+        "[English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.2.3..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))";
+        "Android: en"
+    )]
+    #[test_case(
+        Desktop, &[Main], "en",
+        "[English (`en`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-4362c7f7032e9687a0a5910cadc127afbe8259b2b941de40dd4246c35b1446f0) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/en/messages.json))";
+        "Desktop: en"
+    )]
+    #[test_case(
+        Ios, &[Main, InfoPlist, PluralAware], "en",
+        "English (`en`): [main](https://github.com/signalapp/Signal-iOS/compare/v1.2.3..v1.2.4#diff-e51dc1f3b323f252674c72d0a8c33e70ea2a9c4f0c7784bdc39bdf2bf166233b) ([file](https://github.com/signalapp/Signal-iOS/blob/v1.2.4/Signal/translations/en.lproj/Localizable.strings)) • [info plist](https://github.com/signalapp/Signal-iOS/compare/v1.2.3..v1.2.4#diff-fa966e7c12e08d6d541dc0cc19dac11cc749da30a4c855f48eaea6d38ba6e370) ([file](https://github.com/signalapp/Signal-iOS/blob/v1.2.4/Signal/translations/en.lproj/InfoPlist.strings)) • [plural aware](https://github.com/signalapp/Signal-iOS/compare/v1.2.3..v1.2.4#diff-b1406c86358c13ed48eee0e5f535316b4754e72e30b1318e2c85ca1d75125262) ([file](https://github.com/signalapp/Signal-iOS/blob/v1.2.4/Signal/translations/en.lproj/PluralAware.stringsdict))";
+        "iOS main, info plist, plural aware: en"
+    )]
+    #[test_case(
+        Ios, &[AppStoreDescription, AppStoreReleaseNotes], "en-US",
+        "English (United States) (`en-US`): [description](https://github.com/signalapp/Signal-iOS/compare/v1.2.3..v1.2.4#diff-e7a69d0898d3b2197f77bec55cad6b6d2ff8c973b873bfbe0fe568a1c710ef9c) ([file](https://github.com/signalapp/Signal-iOS/blob/v1.2.4/fastlane/metadata/en-US/description.txt)) • [release notes](https://github.com/signalapp/Signal-iOS/compare/v1.2.3..v1.2.4#diff-4256fffd9552dba2d12fe36150428ff03b2ede950c4040c5d840a6d6b1240df8) ([file](https://github.com/signalapp/Signal-iOS/blob/v1.2.4/fastlane/metadata/en-US/release_notes.txt))";
+        "iOS app store: en dash US"
+    )]
+    fn string(platform: Platform, kinds: &[StringsFileKind], language_code: &str, result: &str) {
+        let localization_change = LocalizationChange::test_change(language_code, kinds.to_vec());
+
+        assert_str_eq!(
+            localization_change.string(platform, &Tag::new("v1.2.3"), &Tag::new("v1.2.4")),
+            result
+        )
+    }
+
+    fn file(filename: &str, status: FileStatus, previous_filename: Option<&str>) -> File {
+        File {
+            filename: filename.to_string(),
+            status,
+            previous_filename: previous_filename.map(ToOwned::to_owned),
+            patch: None,
+        }
+    }
+
+    #[test_case(Desktop, file("_locales/de/messages.json", FileStatus::Added, None), vec![("de", FileStatus::Added)]; "added")]
+    #[test_case(Desktop, file("_locales/de/messages.json", FileStatus::Modified, None), vec![("de", FileStatus::Modified)]; "modified")]
+    #[test_case(Desktop, file("_locales/de/messages.json", FileStatus::Removed, None), vec![("de", FileStatus::Removed)]; "removed")]
+    #[test_case(
+        Desktop, file("_locales/pt_BR/messages.json", FileStatus::Renamed, Some("_locales/pt/messages.json")),
+        vec![("pt-BR", FileStatus::Renamed), ("pt", FileStatus::Removed)];
+        "renamed from another language"
+    )]
+    #[test_case(
+        Desktop, file("_locales/de/messages.json", FileStatus::Renamed, Some("_locales/de/strings.json")),
+        vec![("de", FileStatus::Renamed)];
+        "renamed from another file"
+    )]
+    #[test_case(
+        Ios,
+        file(
+            "Signal/translations/de.lproj/Localizable.strings",
+            FileStatus::Renamed,
+            Some("Signal/translations/de.lproj/InfoPlist.strings"),
+        ),
+        vec![("de", FileStatus::Modified)];
+        "renamed within the same language"
+    )]
+    fn status_from_files(platform: Platform, file: File, result: Vec<(&str, FileStatus)>) {
+        assert_eq!(
+            LocalizationChange::sorted_changes(LocalizationChange::unsorted_changes_from_files(
+                platform,
+                &Some(vec![file])
+            ))
+            .iter()
+            .map(|change| (change.language().full_code(), change.status()))
+            .collect::<Vec<_>>(),
+            result
+                .into_iter()
+                .map(|(code, status)| (code.to_string(), status))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test_case(FileStatus::Added, FileStatus::Added, FileStatus::Added; "all files added")]
+    #[test_case(FileStatus::Added, FileStatus::Modified, FileStatus::Modified; "some files added")]
+    #[test_case(FileStatus::Removed, FileStatus::Modified, FileStatus::Modified; "some files removed")]
+    fn status_of_files_of_language(first: FileStatus, second: FileStatus, result: FileStatus) {
+        let files = Some(vec![
+            file(
+                "Signal/translations/de.lproj/Localizable.strings",
+                first,
+                None,
+            ),
+            file(
+                "Signal/translations/de.lproj/InfoPlist.strings",
+                second,
+                None,
+            ),
+        ]);
+
+        let changes = LocalizationChange::unsorted_changes_from_files(Ios, &files);
+
+        assert_eq!(changes[&Language::try_from("de").unwrap()].status, result);
+    }
+
+    #[test_case(FileStatus::Added, FileStatus::Modified, FileStatus::Added; "added, then modified")]
+    #[test_case(FileStatus::Modified, FileStatus::Added, FileStatus::Added; "modified, then added")]
+    #[test_case(FileStatus::Renamed, FileStatus::Modified, FileStatus::Renamed; "renamed, then modified")]
+    #[test_case(FileStatus::Modified, FileStatus::Removed, FileStatus::Removed; "modified, then removed")]
+    #[test_case(FileStatus::Removed, FileStatus::Added, FileStatus::Added; "removed, then added again")]
+    #[test_case(FileStatus::Added, FileStatus::Removed, FileStatus::Added; "added, then removed")]
+    #[test_case(FileStatus::Renamed, FileStatus::Removed, FileStatus::Removed; "renamed, then removed")]
+    #[test_case(FileStatus::Modified, FileStatus::Modified, FileStatus::Modified; "modified twice")]
+    fn status_of_merged_changes(earlier: FileStatus, later: FileStatus, result: FileStatus) {
+        let changes = |status| {
+            LocalizationChange::unsorted_changes(vec![LocalizationChange::test_change(
+                "de",
+                vec![Main],
+            )
+            .with_status(status)])
+        };
+
+        let merged =
+            LocalizationChange::merge_unsorted_changes(&[&changes(earlier), &changes(later)]);
+
+        assert_eq!(merged[&Language::try_from("de").unwrap()].status, result);
+    }
+
+    fn changes_of(items: &[(&str, FileStatus, Vec<StringsFileKind>)]) -> UnsortedChanges {
+        LocalizationChange::unsorted_changes(
+            items
+                .iter()
+                .map(|(language_code, status, kinds)| {
+                    LocalizationChange::test_change(language_code, kinds.clone())
+                        .with_status(*status)
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn merged_by_canonical_language() {
+        let earlier = changes_of(&[("iw", FileStatus::Modified, vec![Main])]);
+        let later = changes_of(&[("he", FileStatus::Added, vec![InfoPlist])]);
+
+        let merged = LocalizationChange::merge_unsorted_changes(&[&earlier, &later]);
+
+        assert_eq!(
+            merged,
+            changes_of(&[("he", FileStatus::Added, vec![Main, InfoPlist])])
+        );
+    }
+
+    #[test]
+    fn merge_is_idempotent() {
+        let changes = changes_of(&[
+            ("de", FileStatus::Added, vec![Main]),
+            ("fr", FileStatus::Removed, vec![Main, InfoPlist]),
+            ("pt-BR", FileStatus::Modified, vec![Main]),
+        ]);
+
+        assert_eq!(
+            LocalizationChange::merge_unsorted_changes(&[&changes, &changes]),
+            changes
+        );
+        assert_eq!(
+            LocalizationChange::merge_unsorted_changes(&[&changes]),
+            changes
+        );
+    }
+
+    #[test]
+    fn merge_is_associative() {
+        use FileStatus::*;
+
+        let statuses = [Added, Renamed, Modified, Removed];
+
+        for &first in &statuses {
+            for &second in &statuses {
+                for &third in &statuses {
+                    let a = changes_of(&[("de", first, vec![Main]), ("fr", second, vec![Main])]);
+                    let b =
+                        changes_of(&[("de", second, vec![InfoPlist]), ("it", third, vec![Main])]);
+                    let c =
+                        changes_of(&[("de", third, vec![Main]), ("fr", first, vec![InfoPlist])]);
+
+                    let left = LocalizationChange::merge_unsorted_changes(&[
+                        &LocalizationChange::merge_unsorted_changes(&[&a, &b]),
+                        &c,
+                    ]);
+                    let right = LocalizationChange::merge_unsorted_changes(&[
+                        &a,
+                        &LocalizationChange::merge_unsorted_changes(&[&b, &c]),
+                    ]);
+
+                    assert_eq!(left, right, "{first:?}, {second:?}, {third:?}");
+                    assert_eq!(
+                        left,
+                        LocalizationChange::merge_unsorted_changes(&[&a, &b, &c]),
+                        "{first:?}, {second:?}, {third:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sorted_by_status_then_language() {
+        let changes = LocalizationChange::unsorted_changes(vec![
+            LocalizationChange::test_change("fr", vec![Main]).with_status(FileStatus::Removed),
+            LocalizationChange::test_change("eo", vec![Main]),
+            LocalizationChange::test_change("de", vec![Main]).with_status(FileStatus::Added),
+            LocalizationChange::test_change("pt", vec![Main]).with_status(FileStatus::Renamed),
+            LocalizationChange::test_change("ca", vec![Main]).with_status(FileStatus::Added),
+            LocalizationChange::test_change("ar", vec![Main]).with_status(FileStatus::Removed),
+            LocalizationChange::test_change("cs", vec![Main]),
+        ]);
+
+        assert_eq!(
+            LocalizationChange::sorted_changes(changes)
+                .iter()
+                .map(|change| change.language().full_code())
+                .collect::<Vec<_>>(),
+            vec!["ca", "de", "pt", "cs", "eo", "ar", "fr"]
+        );
+    }
+
+    #[test_case(vec![], vec![]; "no changes")]
+    #[test_case(vec![("de", FileStatus::Modified)], vec![(FileStatus::Modified, vec!["de"])]; "one change")]
+    #[test_case(vec![("de", FileStatus::Modified), ("fr", FileStatus::Modified)], vec![(FileStatus::Modified, vec!["de", "fr"])]; "one status")]
+    #[test_case(
+        vec![("de", FileStatus::Added), ("fr", FileStatus::Renamed), ("it", FileStatus::Modified), ("pt", FileStatus::Removed)],
+        vec![(FileStatus::Added, vec!["de"]), (FileStatus::Renamed, vec!["fr"]), (FileStatus::Modified, vec!["it"]), (FileStatus::Removed, vec!["pt"])];
+        "every status"
+    )]
+    #[test_case(
+        vec![("ar", FileStatus::Removed), ("de", FileStatus::Modified), ("fr", FileStatus::Added), ("it", FileStatus::Removed), ("ca", FileStatus::Modified)],
+        vec![(FileStatus::Added, vec!["fr"]), (FileStatus::Modified, vec!["de", "ca"]), (FileStatus::Removed, vec!["ar", "it"])];
+        "unsorted: groups in status order, changes keep their order"
+    )]
+    #[test_case(
+        vec![("de", FileStatus::Removed), ("fr", FileStatus::Removed)],
+        vec![(FileStatus::Removed, vec!["de", "fr"])];
+        "only removed"
+    )]
+    fn grouped_by_status(changes: Vec<(&str, FileStatus)>, result: Vec<(FileStatus, Vec<&str>)>) {
+        let changes = changes
+            .into_iter()
+            .map(|(language_code, status)| {
+                LocalizationChange::test_change(language_code, vec![Main]).with_status(status)
+            })
+            .collect();
+
+        let groups: Vec<_> = LocalizationChange::grouped_by_status(changes)
+            .into_iter()
+            .map(|(status, changes)| {
+                let language_codes: Vec<_> = changes
+                    .iter()
+                    .map(|change| change.language().full_code())
+                    .collect();
+
+                (status, language_codes)
+            })
+            .collect();
+
+        assert_eq!(
+            groups
+                .iter()
+                .map(|(status, language_codes)| (
+                    *status,
+                    language_codes
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            result
+        );
+    }
+
+    #[test_case(FileStatus::Added, ":new: [German (`de`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-537dd75b6b02ee0cbf11183aab34b9842f47db0f5b7fea2a82174860150836e5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/de/messages.json))"; "added")]
+    #[test_case(FileStatus::Renamed, ":new: [German (`de`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-537dd75b6b02ee0cbf11183aab34b9842f47db0f5b7fea2a82174860150836e5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/de/messages.json)) (renamed)"; "renamed")]
+    #[test_case(FileStatus::Removed, "~~[German (`de`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-537dd75b6b02ee0cbf11183aab34b9842f47db0f5b7fea2a82174860150836e5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.3/_locales/de/messages.json))~~"; "removed")]
+    fn string_with_status(status: FileStatus, result: &str) {
+        let localization_change =
+            LocalizationChange::test_change("de", vec![Main]).with_status(status);
+
+        assert_str_eq!(
+            localization_change.string(Desktop, &Tag::new("v1.2.3"), &Tag::new("v1.2.4")),
+            result
+        )
+    }
+
+    #[test_case(r#"["Main"]"#, FileStatus::Modified; "kinds only")]
+    #[test_case(r#"{"kinds":["Main"],"status":"added"}"#, FileStatus::Added; "kinds and status")]
+    fn changed_files_deserialization(json: &str, status: FileStatus) {
+        assert_eq!(
+            serde_json::from_str::<ChangedFiles>(json).unwrap(),
+            ChangedFiles {
+                kinds: HashSet::from([Main]),
+                status,
+            }
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+
+use crate::core::{
+    localization::{
+        Completeness::{self, *},
+        LocalizationChange, UnsortedChanges,
+    },
+    log::log_info,
+    platform::Platform,
+    types::github::{Comparison, FileStatus, Tag},
+};
+
+/// Lists (of commits, or of localization changes) longer than this are collapsed,
+/// unless configured otherwise.
+pub const DEFAULT_COLLAPSE_THRESHOLD: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizationChanges<'a> {
+    pub platform: Platform,
+    pub old_tag: &'a Tag,
+    pub new_tag: &'a Tag,
+    pub completeness: Completeness,
+    /// Why the changes have their `completeness`, if known.
+    pub completeness_rationale: Option<String>,
+    pub unsorted_changes: UnsortedChanges,
+}
+
+impl<'a> LocalizationChanges<'a> {
+    /// Note: assumes `comparison.files` is not `None`.
+    pub fn from_comparison(
+        platform: Platform,
+        old_tag: &'a Tag,
+        new_tag: &'a Tag,
+        comparison: &'a Comparison,
+    ) -> LocalizationChanges<'a> {
+        let (completeness, completeness_rationale) = Completeness::of_comparison(comparison);
+        log_info!(
+            platform = platform,
+            "completeness = {completeness:?}: {completeness_rationale}"
+        );
+
+        let changes = LocalizationChange::unsorted_changes_from_files(platform, &comparison.files);
+
+        log_info!(platform = platform, "changes.len() = {:?}", changes.len());
+
+        Self {
+            platform,
+            old_tag,
+            new_tag,
+            completeness,
+            completeness_rationale: Some(completeness_rationale),
+            unsorted_changes: changes,
+        }
+    }
+
+    /// Merges `unsorted_changes`, made after these changes, into them
+    /// (see [`LocalizationChange::merge_unsorted_changes`]).
+    pub fn add_unsorted_changes(&mut self, unsorted_changes: &UnsortedChanges) {
+        self.unsorted_changes =
+            LocalizationChange::merge_unsorted_changes(&[&self.unsorted_changes, unsorted_changes]);
+    }
+
+    pub fn full_comparison_notice(&self) -> String {
+        format!(
+            "You can view the full comparison to {} so far [here]({}).",
+            self.old_tag.exact_version_string(),
+            self.platform
+                .github_comparison_url(&self.old_tag.name, &self.new_tag.name, None)
+        )
+    }
+
+    /// Number of changed languages, e.g. "at least 12" if the changes may be incomplete.
+    pub fn count_text(&self) -> String {
+        let at_least = match self.completeness {
+            Incomplete | LikelyComplete => "at least ",
+            Complete => "",
+        };
+
+        format!("{at_least}{}", self.unsorted_changes.len())
+    }
+
+    fn language_links(
+        changes: &[LocalizationChange],
+        platform: Platform,
+        old_tag: &Tag,
+        new_tag: &Tag,
+    ) -> String {
+        changes
+            .iter()
+            .map(|change| format!("\n- {}", change.string(platform, old_tag, new_tag)))
+            .collect()
+    }
+
+    /// Languages grouped by status, each group under a heading with its count.
+    fn grouped_language_links(&self, changes: Vec<LocalizationChange>) -> String {
+        LocalizationChange::grouped_by_status(changes)
+            .iter()
+            .map(|(status, changes)| {
+                format!(
+                    "\n**{} ({})**{}",
+                    status_heading(*status),
+                    changes.len(),
+                    Self::language_links(changes, self.platform, self.old_tag, self.new_tag)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the changes, collapsing them and grouping them by status if there are
+    /// more than `collapse_threshold` languages.
+    pub fn markdown(&self, collapse_threshold: usize) -> String {
+        let (at_least, warning) = match self.completeness {
+            Incomplete | LikelyComplete => (
+                "At least ",
+                format!(
+                    "\n{} {}",
+                    self.completeness.warning_text(),
+                    self.full_comparison_notice()
+                ),
+            ),
+            Complete => ("", String::new()),
+        };
+
+        let old_version = self.old_tag.exact_version_string();
+        let changes_len = self.unsorted_changes.len();
+        let changes = LocalizationChange::sorted_changes(self.unsorted_changes.clone());
+
+        let changes = match changes_len {
+            0 => String::from("\n*No localization changes found*"),
+            len if len <= collapse_threshold => {
+                Self::language_links(&changes, self.platform, self.old_tag, self.new_tag)
+            }
+            _ => format!(
+                "\n[details=\"Show changes\"]{}\n[/details]",
+                self.grouped_language_links(changes)
+            ),
+        };
+
+        let languages_word_suffix = if changes_len == 1 { "" } else { "s" };
+
+        format!(
+            "#### {at_least}{changes_len} language{languages_word_suffix} changed since {old_version}:{warning}{changes}"
+        )
+    }
+}
+
+const fn status_heading(status: FileStatus) -> &'static str {
+    match status {
+        FileStatus::Added => "Added",
+        FileStatus::Renamed => "Renamed",
+        FileStatus::Modified => "Modified",
+        FileStatus::Removed => "Removed",
+    }
+}
+
+impl fmt::Display for LocalizationChanges<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.markdown(DEFAULT_COLLAPSE_THRESHOLD))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::{
+        localization::{LocalizationChange, LocalizationChanges, StringsFileKind::*},
+        platform::Platform::{self, *},
+    };
+
+    #[test_case(Android, "v1.2.3", "v1.2.4", Complete, vec![
+        LocalizationChange::test_change("en", vec![Main])
+    ], "#### 1 language changed since 1.2.3:
+- [English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.2.3..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))"; "Android: 1 language changed, complete")]
+    #[test_case(Android, "v1.2.3", "v1.2.4", Complete, vec![
+        LocalizationChange::test_change("en", vec![Main]),
+        LocalizationChange::test_change("en-US", vec![Main])
+    ], "#### 2 languages changed since 1.2.3:
+- [English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.2.3..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))
+- [English (United States) (`en-US`)](https://github.com/signalapp/Signal-Android/compare/v1.2.3..v1.2.4#diff-33a220e7f3b2e559ebec12cbf273da0c135bfade5a547e41e2bb5a66d75a01d2) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rUS/strings.xml))"; "Android: 2 languages changed, complete")]
+    #[test_case(Android, "v1.1.5", "v1.2.4", Incomplete, vec![
+        LocalizationChange::test_change("en", vec![Main]),
+        LocalizationChange::test_change("en-US", vec![Main]),
+        LocalizationChange::test_change("en-CA", vec![Main]),
+    ], "#### At least 3 languages changed since 1.1.5:
+:warning: For technical reasons, not all languages may be listed below. You can view the full comparison to 1.1.5 so far [here](https://github.com/signalapp/Signal-Android/compare/v1.1.5...v1.2.4).
+- [English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))
+- [English (Canada) (`en-CA`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-11b72e6873b6a42a2a1b92999e4225d7ff2921e323baa5e7b31fdc49471d9724) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rCA/strings.xml))
+- [English (United States) (`en-US`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-33a220e7f3b2e559ebec12cbf273da0c135bfade5a547e41e2bb5a66d75a01d2) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rUS/strings.xml))"; "Android: 3 languages changed, incomplete")]
+    #[test_case(Android, "v1.1.5", "v1.2.4", LikelyComplete, vec![
+        LocalizationChange::test_change("en", vec![Main]),
+        LocalizationChange::test_change("en-US", vec![Main]),
+        LocalizationChange::test_change("en-CA", vec![Main]),
+    ], "#### At least 3 languages changed since 1.1.5:
+For technical reasons, not all languages may be listed below. However, everything from \"Updated language translations\" and similar commits is listed, so the list is likely complete. You can view the full comparison to 1.1.5 so far [here](https://github.com/signalapp/Signal-Android/compare/v1.1.5...v1.2.4).
+- [English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))
+- [English (Canada) (`en-CA`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-11b72e6873b6a42a2a1b92999e4225d7ff2921e323baa5e7b31fdc49471d9724) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rCA/strings.xml))
+- [English (United States) (`en-US`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-33a220e7f3b2e559ebec12cbf273da0c135bfade5a547e41e2bb5a66d75a01d2) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rUS/strings.xml))"; "Android: 3 languages changed, likely complete")]
+    #[test_case(Android, "v1.1.5", "v1.2.4", Incomplete, vec![
+        LocalizationChange::test_change("en", vec![Main]),
+        LocalizationChange::test_change("en-US", vec![Main]),
+        LocalizationChange::test_change("en-CA", vec![Main]),
+        LocalizationChange::test_change("en-AA", vec![Main]),
+        LocalizationChange::test_change("en-BB", vec![Main]),
+
+        LocalizationChange::test_change("en-CC", vec![Main]),
+        LocalizationChange::test_change("en-DD", vec![Main]),
+        LocalizationChange::test_change("en-EE", vec![Main]),
+        LocalizationChange::test_change("en-FF", vec![Main]),
+        LocalizationChange::test_change("en-GG", vec![Main]),
+
+        LocalizationChange::test_change("en-HH", vec![Main]),
+        LocalizationChange::test_change("en-II", vec![Main]),
+        LocalizationChange::test_change("en-JJ", vec![Main]),
+        LocalizationChange::test_change("en-KK", vec![Main]),
+        LocalizationChange::test_change("en-LL", vec![Main]),
+
+        LocalizationChange::test_change("en-MM", vec![Main]),
+        LocalizationChange::test_change("en-NN", vec![Main]),
+        LocalizationChange::test_change("en-OO", vec![Main]),
+        LocalizationChange::test_change("en-PP", vec![Main]),
+        LocalizationChange::test_change("en-QQ", vec![Main]),
+
+        LocalizationChange::test_change("en-RR", vec![Main]),
+    ], "#### At least 21 languages changed since 1.1.5:
+:warning: For technical reasons, not all languages may be listed below. You can view the full comparison to 1.1.5 so far [here](https://github.com/signalapp/Signal-Android/compare/v1.1.5...v1.2.4).
+[details=\"Show changes\"]
+**Modified (21)**
+- [English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))
+- [English (AA) (`en-AA`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-90d83c43cd0b4a891fa32e19f20c6c1c933d4627e2d70466c6de771fd8e7d5ea) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rAA/strings.xml))
+- [English (BB) (`en-BB`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-0538051d72d1e122563776d98638f915391c2b0e0627965fab1fd37d79bbe027) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rBB/strings.xml))
+- [English (Canada) (`en-CA`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-11b72e6873b6a42a2a1b92999e4225d7ff2921e323baa5e7b31fdc49471d9724) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rCA/strings.xml))
+- [English (CC) (`en-CC`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-6d1f3e2b47a24280d0c1a2244667656c6ca5ac429d4e5ca4fa6e0dd9d21883f9) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rCC/strings.xml))
+- [English (DD) (`en-DD`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-835e31924911b451487467f65445296e538556689f0a349049deb46e54b4efdc) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rDD/strings.xml))
+- [English (EE) (`en-EE`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-5b62998ff46298e26bc02db71baa67c05eb294535820e9b131793eb8f40111ae) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rEE/strings.xml))
+- [English (FF) (`en-FF`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-41bde68259269b61d56f8011d6a6c0ced60a7509dd1f545e053f91ab254befa0) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rFF/strings.xml))
+- [English (GG) (`en-GG`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-a2c8f8883faf8422107e483d04d0f3135d7449581c8a03de7f2d5507404a264e) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rGG/strings.xml))
+- [English (HH) (`en-HH`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-19ee1c6b50a70f7ed36431d28d8537aa9ce6f11e0374b3c0976f65820ce55778) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rHH/strings.xml))
+- [English (II) (`en-II`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-42c898889f9fc7175eecc63898739f5024b39e482b2919380c176437ac9b22fc) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rII/strings.xml))
+- [English (JJ) (`en-JJ`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-60a126ddd8b5b391468f28469cc0953c3d4d21020d4dc34b1f9ff6f67c6c680e) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rJJ/strings.xml))
+- [English (KK) (`en-KK`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-7342487967d03331e2bad1197a40b1e7d1f34da6235c0736d32460e6351aaa50) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rKK/strings.xml))
+- [English (LL) (`en-LL`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-766a216d5eb0aa87ffed36c559ec6674f5c50ab234e2e91e71da6bfb86de939b) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rLL/strings.xml))
+- [English (MM) (`en-MM`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-b1ff359d6a6d77649a4261b2b05a515af6aedc44f7e575e950af537a4552c738) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rMM/strings.xml))
+- [English (NN) (`en-NN`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-fa942664e77daed786916de43eae073d6d01776eedd2c5010ec18d0ddcc5371d) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rNN/strings.xml))
+- [English (OO) (`en-OO`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-f2a68ac478f5458f61b8f99b92d7177f2bffab4a255b9299cb291d72ae6ef204) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rOO/strings.xml))
+- [English (PP) (`en-PP`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-2204ce5d73475a889fa5d44bfcb635868d9a4cad744767db8b91c79be1e989fd) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rPP/strings.xml))
+- [English (QQ) (`en-QQ`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-e31e2b8f412ef0d7d8209f44c2efd3686cd9f9d51b71b9f83916dac0f7a3b144) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rQQ/strings.xml))
+- [English (RR) (`en-RR`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-8e1942b7f79ae365c0629628ff5196b184f83b74ce16291f07a9efcc8941c597) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rRR/strings.xml))
+- [English (United States) (`en-US`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-33a220e7f3b2e559ebec12cbf273da0c135bfade5a547e41e2bb5a66d75a01d2) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rUS/strings.xml))
+[/details]"; "Android: 21 languages changed, incomplete")]
+    fn to_string(
+        platform: Platform,
+        old_tag: &str,
+        new_tag: &str,
+        completeness: Completeness,
+        changes: Vec<LocalizationChange>,
+        result: &str,
+    ) {
+        let old_tag = Tag::new(old_tag);
+        let new_tag = Tag::new(new_tag);
+
+        let changes = LocalizationChanges {
+            platform,
+            old_tag: &old_tag,
+            new_tag: &new_tag,
+            completeness,
+            completeness_rationale: None,
+            unsorted_changes: LocalizationChange::unsorted_changes(changes),
+        };
+
+        assert_str_eq!(changes.to_string(), result);
+    }
+
+    #[test_case(3, "#### 3 languages changed since 1.2.3:
+- :new: [German (`de`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-537dd75b6b02ee0cbf11183aab34b9842f47db0f5b7fea2a82174860150836e5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/de/messages.json))
+- [French (`fr`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-4fee12151ea49a5007142ae55129100fd236810c59ca5430e407ed62e49f289a) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/fr/messages.json))
+- ~~[Italian (`it`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-8626d65bbdc4dc22689349ed5390786953c10ed658a5dff219d23cd974275ce5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.3/_locales/it/messages.json))~~"; "at threshold: not collapsed")]
+    #[test_case(2, "#### 3 languages changed since 1.2.3:
+[details=\"Show changes\"]
+**Added (1)**
+- :new: [German (`de`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-537dd75b6b02ee0cbf11183aab34b9842f47db0f5b7fea2a82174860150836e5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/de/messages.json))
+
+**Modified (1)**
+- [French (`fr`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-4fee12151ea49a5007142ae55129100fd236810c59ca5430e407ed62e49f289a) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/fr/messages.json))
+
+**Removed (1)**
+- ~~[Italian (`it`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-8626d65bbdc4dc22689349ed5390786953c10ed658a5dff219d23cd974275ce5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.3/_locales/it/messages.json))~~
+[/details]"; "over threshold: collapsed and grouped")]
+    #[test_case(0, "#### 3 languages changed since 1.2.3:
+[details=\"Show changes\"]
+**Added (1)**
+- :new: [German (`de`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-537dd75b6b02ee0cbf11183aab34b9842f47db0f5b7fea2a82174860150836e5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/de/messages.json))
+
+**Modified (1)**
+- [French (`fr`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-4fee12151ea49a5007142ae55129100fd236810c59ca5430e407ed62e49f289a) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.4/_locales/fr/messages.json))
+
+**Removed (1)**
+- ~~[Italian (`it`)](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3..v1.2.4#diff-8626d65bbdc4dc22689349ed5390786953c10ed658a5dff219d23cd974275ce5) ([file](https://github.com/signalapp/Signal-Desktop/blob/v1.2.3/_locales/it/messages.json))~~
+[/details]"; "zero threshold: always collapsed")]
+    fn markdown_with_collapse_threshold(collapse_threshold: usize, result: &str) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        let changes = LocalizationChanges {
+            platform: Desktop,
+            old_tag: &old_tag,
+            new_tag: &new_tag,
+            completeness: Complete,
+            completeness_rationale: None,
+            unsorted_changes: LocalizationChange::unsorted_changes(vec![
+                LocalizationChange::test_change("it", vec![Main]).with_status(FileStatus::Removed),
+                LocalizationChange::test_change("fr", vec![Main]),
+                LocalizationChange::test_change("de", vec![Main]).with_status(FileStatus::Added),
+            ]),
+        };
+
+        assert_str_eq!(changes.markdown(collapse_threshold), result);
+    }
+}
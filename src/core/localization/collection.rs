@@ -0,0 +1,209 @@
+use super::{
+    LocalizationChangeRenderMode::{self, *},
+    LocalizationChanges,
+};
+
+#[derive(Debug)]
+pub struct LocalizationChangeCollection<'a> {
+    pub build_changes: LocalizationChanges<'a>,
+    pub release_changes: Option<LocalizationChanges<'a>>,
+}
+
+impl<'a> LocalizationChangeCollection<'a> {
+    /// Both counts of changed languages, e.g. "12 languages changed since 7.1.0-beta.1,
+    /// 47 since 7.0.5.", if there are release changes.
+    fn counts_summary(&self) -> Option<String> {
+        let release_changes = self.release_changes.as_ref()?;
+
+        let build_count = self.build_changes.count_text();
+        let languages_word_suffix = if self.build_changes.unsorted_changes.len() == 1 {
+            ""
+        } else {
+            "s"
+        };
+
+        let mut chars = build_count.chars();
+        let build_count = match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => build_count,
+        };
+
+        Some(format!(
+            "{build_count} language{languages_word_suffix} changed since {}, {} since {}.",
+            self.build_changes.old_tag.exact_version_string(),
+            release_changes.count_text(),
+            release_changes.old_tag.exact_version_string()
+        ))
+    }
+
+    /// See [`LocalizationChanges::markdown`] for `collapse_threshold`.
+    pub fn to_string(
+        &self,
+        mode: LocalizationChangeRenderMode,
+        collapse_threshold: usize,
+    ) -> String {
+        let changes = match (mode, &self.release_changes) {
+            (Full, Some(changes)) => vec![&self.build_changes, changes],
+            (Full, None) | (WithoutRelease, _) => {
+                vec![&self.build_changes]
+            }
+            (Nothing, _) => vec![],
+        }
+        .iter()
+        .map(|changes| changes.markdown(collapse_threshold))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+        let usage_instructions = match mode {
+            Nothing => "",
+            _ => "Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).\n\n",
+        };
+
+        let none_fit_notice = "No localization changes fit in character limit.";
+        let same_notice = "Localization changes for the release are the same, as this is the first build of the release.";
+
+        let notice = match (mode, &self.release_changes) {
+            (Full, Some(_)) => String::from(""),
+            (Full | WithoutRelease, None) => format!("\n\n{}", same_notice),
+            (WithoutRelease, Some(release_changes)) => format!(
+                "\n\nLocalization changes for the release didn't fit in character limit. {}",
+                release_changes.full_comparison_notice()
+            ),
+            (Nothing, Some(release_changes)) => format!(
+                "\n\n{} {} {}",
+                none_fit_notice,
+                self.build_changes.full_comparison_notice(),
+                release_changes.full_comparison_notice()
+            ),
+            (Nothing, None) => format!(
+                "\n\n{} {} {}",
+                none_fit_notice,
+                self.build_changes.full_comparison_notice(),
+                same_notice
+            ),
+        };
+
+        let counts_summary = self
+            .counts_summary()
+            .map(|summary| format!("{summary}\n\n"))
+            .unwrap_or_default();
+
+        format!(
+            "[details=\"Localization changes\"]
+[quote]
+{counts_summary}{usage_instructions}{changes}{notice}
+[/quote]
+[/details]"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::{
+        localization::{
+            Completeness::{self, *},
+            LocalizationChange,
+            StringsFileKind::*,
+            UnsortedChanges, DEFAULT_COLLAPSE_THRESHOLD,
+        },
+        platform::Platform::Android,
+        types::github::Tag,
+    };
+
+    fn unsorted_changes(language_codes: &[&str]) -> UnsortedChanges {
+        LocalizationChange::unsorted_changes(
+            language_codes
+                .iter()
+                .map(|code| LocalizationChange::test_change(code, vec![Main]))
+                .collect(),
+        )
+    }
+
+    fn changes<'a>(
+        old_tag: &'a Tag,
+        new_tag: &'a Tag,
+        completeness: Completeness,
+        language_codes: &[&str],
+    ) -> LocalizationChanges<'a> {
+        LocalizationChanges {
+            platform: Android,
+            old_tag,
+            new_tag,
+            completeness,
+            completeness_rationale: None,
+            unsorted_changes: unsorted_changes(language_codes),
+        }
+    }
+
+    #[test_case(Complete, &["en", "de"], Complete, &["en", "de", "fr"], "2 languages changed since 1.1.0-beta.1, 3 since 1.0.5."; "complete")]
+    #[test_case(Complete, &["en"], Complete, &["en"], "1 language changed since 1.1.0-beta.1, 1 since 1.0.5."; "one language")]
+    #[test_case(Complete, &[], Complete, &["en"], "0 languages changed since 1.1.0-beta.1, 1 since 1.0.5."; "no build changes")]
+    #[test_case(Incomplete, &["en"], LikelyComplete, &["en", "de"], "At least 1 language changed since 1.1.0-beta.1, at least 2 since 1.0.5."; "incomplete")]
+    #[test_case(Complete, &["en"], Incomplete, &["en", "de"], "1 language changed since 1.1.0-beta.1, at least 2 since 1.0.5."; "incomplete release")]
+    fn counts_summary(
+        build_completeness: Completeness,
+        build_language_codes: &[&str],
+        release_completeness: Completeness,
+        release_language_codes: &[&str],
+        result: &str,
+    ) {
+        let previous_release_tag = Tag::new("v1.0.5");
+        let old_tag = Tag::new("v1.1.0-beta.1");
+        let new_tag = Tag::new("v1.1.0-beta.2");
+
+        let collection = LocalizationChangeCollection {
+            build_changes: changes(&old_tag, &new_tag, build_completeness, build_language_codes),
+            release_changes: Some(changes(
+                &previous_release_tag,
+                &new_tag,
+                release_completeness,
+                release_language_codes,
+            )),
+        };
+
+        assert_eq!(collection.counts_summary().as_deref(), Some(result));
+    }
+
+    #[test]
+    fn no_counts_summary_without_release_changes() {
+        let old_tag = Tag::new("v1.0.5");
+        let new_tag = Tag::new("v1.1.0-beta.1");
+
+        let collection = LocalizationChangeCollection {
+            build_changes: changes(&old_tag, &new_tag, Complete, &["en"]),
+            release_changes: None,
+        };
+
+        assert_eq!(collection.counts_summary(), None);
+    }
+
+    #[test_case(Full; "full")]
+    #[test_case(WithoutRelease; "without release")]
+    #[test_case(Nothing; "nothing")]
+    fn counts_summary_is_rendered(mode: LocalizationChangeRenderMode) {
+        let previous_release_tag = Tag::new("v1.0.5");
+        let old_tag = Tag::new("v1.1.0-beta.1");
+        let new_tag = Tag::new("v1.1.0-beta.2");
+
+        let collection = LocalizationChangeCollection {
+            build_changes: changes(&old_tag, &new_tag, Complete, &["en"]),
+            release_changes: Some(changes(
+                &previous_release_tag,
+                &new_tag,
+                Complete,
+                &["en", "de"],
+            )),
+        };
+
+        assert!(collection
+            .to_string(mode, DEFAULT_COLLAPSE_THRESHOLD)
+            .starts_with(
+                "[details=\"Localization changes\"]\n[quote]\n1 language changed since 1.1.0-beta.1, 2 since 1.0.5.\n\n"
+            ));
+    }
+}
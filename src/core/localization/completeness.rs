@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::types::github::Comparison;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Completeness {
+    #[default]
+    Incomplete,
+    LikelyComplete,
+    Complete,
+}
+
+use Completeness::*;
+
+impl Completeness {
+    pub const fn warning_text(&self) -> &'static str {
+        match self {
+            Incomplete => ":warning: For technical reasons, not all languages may be listed below.",
+            LikelyComplete =>
+                "For technical reasons, not all languages may be listed below. However, everything from \"Updated language translations\" and similar commits is listed, so the list is likely complete.",
+            Complete => "",
+        }
+    }
+
+    /// Completeness of the files of `comparison`, with a rationale for readers.
+    ///
+    /// Even if fewer files than GitHub's limit are listed, a comparison with at least
+    /// as many commits as GitHub lists may have been cut short, so it is only likely complete.
+    ///
+    /// Note: assumes `comparison.files` is not `None`.
+    pub fn of_comparison(comparison: &Comparison) -> (Self, String) {
+        let files_len = comparison.files.as_ref().unwrap().len();
+        let commits_len = comparison.total_commits;
+        let max_files = Comparison::GITHUB_API_MAX_FILES;
+
+        if !comparison.are_files_likely_complete().unwrap() {
+            (
+                Incomplete,
+                format!("GitHub listed {files_len} changed files, its limit for a comparison, so some may be missing."),
+            )
+        } else if commits_len >= Comparison::GITHUB_API_MAX_COMMITS {
+            (
+                LikelyComplete,
+                format!("GitHub listed {files_len} changed files, below its limit of {max_files} for a comparison, but the comparison has {commits_len} commits, so some files may have been left out."),
+            )
+        } else {
+            (
+                Complete,
+                format!("GitHub listed {files_len} changed files, below its limit of {max_files} for a comparison."),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::types::github::{ComparisonStatus, File};
+
+    #[test_case(12, 40, Complete, "GitHub listed 12 changed files, below its limit of 300 for a comparison."; "few files and commits")]
+    #[test_case(300, 40, Incomplete, "GitHub listed 300 changed files, its limit for a comparison, so some may be missing."; "files at limit")]
+    #[test_case(300, 400, Incomplete, "GitHub listed 300 changed files, its limit for a comparison, so some may be missing."; "files and commits at limit")]
+    #[test_case(12, 249, Complete, "GitHub listed 12 changed files, below its limit of 300 for a comparison."; "commits below limit")]
+    #[test_case(12, 250, LikelyComplete, "GitHub listed 12 changed files, below its limit of 300 for a comparison, but the comparison has 250 commits, so some files may have been left out."; "commits at limit")]
+    fn of_comparison(
+        files_len: usize,
+        total_commits: usize,
+        completeness: Completeness,
+        rationale: &str,
+    ) {
+        let comparison = Comparison {
+            status: ComparisonStatus::Ahead,
+            ahead_by: total_commits,
+            total_commits,
+            commits: Vec::new(),
+            files: Some(
+                (0..files_len)
+                    .map(|index| File::new(format!("file-{index}.txt")))
+                    .collect(),
+            ),
+            merge_base_commit: None,
+        };
+
+        assert_eq!(
+            Completeness::of_comparison(&comparison),
+            (completeness, String::from(rationale))
+        );
+    }
+
+    #[test_case(r#""Incomplete""#, Incomplete; "incomplete")]
+    #[test_case(r#""LikelyComplete""#, LikelyComplete; "likely complete")]
+    #[test_case(r#""Complete""#, Complete; "complete")]
+    fn deserialization(json: &str, result: Completeness) {
+        assert_eq!(serde_json::from_str::<Completeness>(json).unwrap(), result);
+    }
+}
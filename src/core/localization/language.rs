@@ -0,0 +1,251 @@
+use std::fmt;
+
+use anyhow::{anyhow, bail};
+use locale_codes::language;
+use serde::{Deserialize, Serialize};
+
+/// English names of languages, by language code or full code, where the ISO 639 reference
+/// names are unfamiliar to readers ("Panjabi") or missing, as for the legacy codes Android uses.
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("ckb", "Kurdish (Sorani)"),
+    ("in", "Indonesian"),
+    ("iw", "Hebrew"),
+    ("ji", "Yiddish"),
+    ("ky", "Kyrgyz"),
+    ("ms", "Malay"),
+    ("nb", "Norwegian Bokmål"),
+    ("nb-NO", "Norwegian Bokmål"),
+    ("pa", "Punjabi"),
+    ("ps", "Pashto"),
+    ("sw", "Swahili"),
+    ("ug", "Uyghur"),
+    ("yue", "Cantonese"),
+    ("zh-CN", "Chinese (Simplified)"),
+    ("zh-Hans", "Chinese (Simplified)"),
+    ("zh-Hant", "Chinese (Traditional)"),
+    ("zh-TW", "Chinese (Traditional)"),
+];
+
+/// English names of the regions and scripts in the codes of localization files.
+const REGION_NAMES: &[(&str, &str)] = &[
+    ("AR", "Argentina"),
+    ("AT", "Austria"),
+    ("AU", "Australia"),
+    ("BE", "Belgium"),
+    ("BR", "Brazil"),
+    ("CA", "Canada"),
+    ("CH", "Switzerland"),
+    ("CN", "China"),
+    ("Cyrl", "Cyrillic"),
+    ("DE", "Germany"),
+    ("EC", "Ecuador"),
+    ("ES", "Spain"),
+    ("FR", "France"),
+    ("GB", "United Kingdom"),
+    ("HK", "Hong Kong"),
+    ("IN", "India"),
+    ("Latn", "Latin"),
+    ("MX", "Mexico"),
+    ("NL", "Netherlands"),
+    ("NO", "Norway"),
+    ("PK", "Pakistan"),
+    ("PT", "Portugal"),
+    ("TW", "Taiwan"),
+    ("US", "United States"),
+];
+
+/// Codes that name the same language as another code, as the legacy codes Android uses,
+/// or scripts that stand for regions, and the code they are the same as.
+const CANONICAL_CODES: &[(&str, &str)] = &[
+    ("in", "id"),
+    ("iw", "he"),
+    ("ji", "yi"),
+    ("zh-Hans", "zh-CN"),
+    ("zh-Hant", "zh-TW"),
+];
+
+fn lookup(table: &[(&str, &'static str)], code: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(table_code, _)| *table_code == code)
+        .map(|(_, name)| *name)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "&str", into = "String")]
+pub struct Language {
+    pub language_reference_name: String,
+    pub language_code: String,
+    pub region_code: Option<String>,
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Self {
+            language_reference_name: String::from("English"),
+            language_code: String::from("en"),
+            region_code: None,
+        }
+    }
+}
+
+impl Language {
+    pub fn full_code(&self) -> String {
+        match &self.region_code {
+            Some(region_code) => format!("{}-{}", self.language_code, region_code),
+            None => self.language_code.to_string(),
+        }
+    }
+
+    /// Full code that identifies the language regardless of how its files are named,
+    /// e.g. "he" for both Android's legacy "iw" and "he".
+    pub fn canonical_code(&self) -> String {
+        let full_code = self.full_code();
+
+        if let Some(code) = lookup(CANONICAL_CODES, &full_code) {
+            return code.to_string();
+        }
+
+        match (
+            lookup(CANONICAL_CODES, &self.language_code),
+            &self.region_code,
+        ) {
+            (Some(language_code), Some(region_code)) => format!("{language_code}-{region_code}"),
+            (Some(language_code), None) => language_code.to_string(),
+            (None, _) => full_code,
+        }
+    }
+
+    /// English name of the language, with the region (or script) if there is one,
+    /// like "Portuguese (Brazil)"; regions missing from the table are shown as their code.
+    pub fn language_name(&self) -> String {
+        if let Some(name) = lookup(LANGUAGE_NAMES, &self.full_code()) {
+            return name.to_string();
+        }
+
+        match &self.region_code {
+            Some(region_code) => format!(
+                "{} ({})",
+                self.language_reference_name,
+                lookup(REGION_NAMES, region_code).unwrap_or(region_code)
+            ),
+            None => self.language_reference_name.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (`{}`)", self.language_name(), self.full_code())
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.full_code()
+    }
+}
+
+impl TryFrom<&str> for Language {
+    type Error = anyhow::Error;
+
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        let canonical_code = code.replace("-r", "_").replace('-', "_");
+        let parts = canonical_code.split('_').collect::<Vec<_>>();
+
+        match parts.len() {
+            1..=2 => {
+                let language_code = parts[0].to_string();
+                if !(2..=3).contains(&language_code.len()) {
+                    bail!("language code length is not in range 2..=3");
+                }
+
+                let language_reference_name = match lookup(LANGUAGE_NAMES, &language_code) {
+                    Some(name) => name.to_string(),
+                    None => language::lookup(&language_code)
+                        .ok_or_else(|| anyhow!("could not look up language by code"))?
+                        .reference_name
+                        .clone(),
+                };
+
+                Ok(Self {
+                    language_reference_name,
+                    language_code,
+                    region_code: parts.get(1).map(|code| code.to_string()),
+                })
+            }
+            _ => bail!("incorrect count of code parts"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("en", "English (`en`)"; "en")]
+    #[test_case("en_US", "English (United States) (`en-US`)"; "en underscore US")]
+    #[test_case("en-US", "English (United States) (`en-US`)"; "en dash US")]
+    #[test_case("en-rUS", "English (United States) (`en-US`)"; "en dash r US")]
+    #[test_case("eo", "Esperanto (`eo`)"; "eo")]
+    #[test_case("yue", "Cantonese (`yue`)"; "yue")]
+    #[test_case("kab", "Kabyle (`kab`)"; "kab")]
+    #[test_case("pt_BR", "Portuguese (Brazil) (`pt-BR`)"; "pt underscore BR")]
+    #[test_case("pt_PT", "Portuguese (Portugal) (`pt-PT`)"; "pt underscore PT")]
+    #[test_case("zh_CN", "Chinese (Simplified) (`zh-CN`)"; "zh underscore CN")]
+    #[test_case("zh_TW", "Chinese (Traditional) (`zh-TW`)"; "zh underscore TW")]
+    #[test_case("pa-rPK", "Punjabi (Pakistan) (`pa-PK`)"; "pa dash r PK")]
+    #[test_case("qu-rEC", "Quechua (Ecuador) (`qu-EC`)"; "qu dash r EC")]
+    fn from_code_some(code: &str, result: &str) {
+        assert_str_eq!(Language::try_from(code).unwrap().to_string(), result);
+    }
+
+    #[test_case("pt", "Portuguese"; "language only")]
+    #[test_case("pt_BR", "Portuguese (Brazil)"; "region")]
+    #[test_case("sr-Latn", "Serbian (Latin)"; "script")]
+    #[test_case("nb_NO", "Norwegian Bokmål"; "full code in table")]
+    #[test_case("pa", "Punjabi"; "language in table")]
+    #[test_case("iw", "Hebrew"; "legacy code")]
+    #[test_case("en_XA", "English (XA)"; "unknown region")]
+    fn language_name(code: &str, result: &str) {
+        assert_str_eq!(Language::try_from(code).unwrap().language_name(), result);
+    }
+
+    #[test_case("de", "de"; "language only")]
+    #[test_case("pt_BR", "pt-BR"; "region")]
+    #[test_case("pt-rBR", "pt-BR"; "Android region")]
+    #[test_case("iw", "he"; "legacy code")]
+    #[test_case("in-rID", "id-ID"; "legacy code with region")]
+    #[test_case("zh-Hans", "zh-CN"; "script")]
+    #[test_case("zh_CN", "zh-CN"; "region instead of script")]
+    fn canonical_code(code: &str, result: &str) {
+        assert_str_eq!(Language::try_from(code).unwrap().canonical_code(), result);
+    }
+
+    // Some of the values-* folders in Signal Android are not for localization.
+    #[test_case("land")]
+    #[test_case("ldrtl")]
+    #[test_case("night")]
+    #[test_case("v9")]
+    fn from_code_none(code: &str) {
+        assert!(Language::try_from(code).is_err());
+    }
+
+    #[test_case(&["pt_PT", "pt_BR", "en_US", "eo", "en"], &["en", "en_US", "eo", "pt_BR", "pt_PT"]; "basic test")]
+    fn ord(input: &[&str], output: &[&str]) {
+        let map = |x: &[&str]| {
+            x.iter()
+                .map(|code| Language::try_from(*code).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let mut input = map(input);
+        let output = map(output);
+
+        input.sort_unstable();
+        assert_eq!(input, output);
+    }
+}
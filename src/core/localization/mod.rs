@@ -3,6 +3,7 @@ mod changes;
 mod collection;
 mod completeness;
 mod language;
+mod new_strings;
 mod render_mode;
 mod strings_file_kind;
 
@@ -11,5 +12,6 @@ pub use changes::*;
 pub use collection::*;
 pub use completeness::*;
 pub use language::*;
+pub use new_strings::*;
 pub use render_mode::*;
 pub use strings_file_kind::*;
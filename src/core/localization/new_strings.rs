@@ -0,0 +1,311 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::StringsFileKind;
+use crate::core::{
+    platform::{
+        Platform::{self, *},
+        ANDROID_DEFAULT_STRINGS_FILENAME,
+    },
+    types::github::Comparison,
+};
+
+lazy_static! {
+    /// `<string name="key">value</string>` in Android's `strings.xml`.
+    static ref ANDROID_STRING_REGEX: Regex =
+        Regex::new(r#"<string\s+name="([^"]+)"([^>]*)>(.*)</string>"#).unwrap();
+    /// `<plurals name="key">` in Android's `strings.xml`, followed by its items.
+    static ref ANDROID_PLURALS_REGEX: Regex = Regex::new(r#"<plurals\s+name="([^"]+)""#).unwrap();
+    /// `<item quantity="one">value</item>` of Android plurals.
+    static ref ANDROID_ITEM_REGEX: Regex = Regex::new(r"<item\b[^>]*>(.*)</item>").unwrap();
+    /// `"key" = "value";` in iOS's `Localizable.strings`.
+    static ref IOS_STRING_REGEX: Regex =
+        Regex::new(r#"^\s*"((?:[^"\\]|\\.)*)"\s*=\s*"((?:[^"\\]|\\.)*)"\s*;"#).unwrap();
+    /// `"key": {` in Desktop's `messages.json`, followed by its message.
+    static ref DESKTOP_KEY_REGEX: Regex = Regex::new(r#"^\s*"((?:[^"\\]|\\.)+)"\s*:\s*\{"#).unwrap();
+    /// `"messageformat": "value"` (or `"message"`) of a Desktop message.
+    static ref DESKTOP_MESSAGE_REGEX: Regex =
+        Regex::new(r#"^\s*"message(?:format)?"\s*:\s*("(?:[^"\\]|\\.)*")"#).unwrap();
+}
+
+/// At most this many new strings are listed in a post.
+pub const MAX_NEW_STRINGS_SHOWN: usize = 40;
+
+/// A string added to the base (English) strings file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewString {
+    pub key: String,
+    pub value: String,
+}
+
+impl NewString {
+    fn new(key: &str, value: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            value: value.into(),
+        }
+    }
+
+    /// Finds the strings added in `comparison` to the base strings file of `platform`,
+    /// in the order they appear in its patch.
+    ///
+    /// Returns `None` if the file was changed, but GitHub left out its patch
+    /// (as it does for large diffs).
+    pub fn from_comparison(platform: Platform, comparison: &Comparison) -> Option<Vec<Self>> {
+        let path = base_strings_path(platform);
+
+        match comparison
+            .files
+            .iter()
+            .flatten()
+            .find(|file| file.filename == path)
+        {
+            Some(file) => Some(Self::from_patch(platform, file.patch.as_deref()?)),
+            None => Some(Vec::new()),
+        }
+    }
+
+    /// Finds the strings added in `patch` of the base strings file of `platform`;
+    /// strings whose key was also removed (i.e. changed ones) do not count.
+    pub fn from_patch(platform: Platform, patch: &str) -> Vec<Self> {
+        let changed_lines = |sign: char| {
+            patch
+                .lines()
+                .filter(|line| !line.starts_with("---") && !line.starts_with("+++"))
+                .filter_map(move |line| line.strip_prefix(sign))
+        };
+
+        let removed_keys: Vec<String> = parse_lines(platform, changed_lines('-'))
+            .into_iter()
+            .map(|string| string.key)
+            .collect();
+
+        let mut new_strings: Vec<Self> = Vec::new();
+
+        for string in parse_lines(platform, changed_lines('+')) {
+            if !removed_keys.contains(&string.key)
+                && !new_strings.iter().any(|new| new.key == string.key)
+            {
+                new_strings.push(string);
+            }
+        }
+
+        new_strings
+    }
+}
+
+/// Path of the base (English) strings file of `platform`.
+pub fn base_strings_path(platform: Platform) -> String {
+    match platform {
+        Android => ANDROID_DEFAULT_STRINGS_FILENAME.to_owned(),
+        Ios | Desktop => StringsFileKind::Main.path(platform, "en"),
+    }
+}
+
+/// Parses the strings in `lines` of the base strings file of `platform`; entries spanning
+/// several lines (plurals, Desktop messages) are only found if all of their lines are there.
+fn parse_lines<'a>(platform: Platform, lines: impl Iterator<Item = &'a str>) -> Vec<NewString> {
+    let mut strings = Vec::new();
+    let mut pending_key: Option<String> = None;
+
+    for line in lines {
+        match platform {
+            Android => {
+                if let Some(captures) = ANDROID_STRING_REGEX.captures(line) {
+                    pending_key = None;
+
+                    if !captures[2].contains(r#"translatable="false""#) {
+                        strings.push(NewString::new(&captures[1], unescape_android(&captures[3])));
+                    }
+                } else if let Some(captures) = ANDROID_PLURALS_REGEX.captures(line) {
+                    pending_key = Some(captures[1].to_string());
+                } else if let Some(captures) = ANDROID_ITEM_REGEX.captures(line) {
+                    if let Some(key) = pending_key.take() {
+                        strings.push(NewString::new(&key, unescape_android(&captures[1])));
+                    }
+                }
+            }
+            Ios => {
+                if let Some(captures) = IOS_STRING_REGEX.captures(line) {
+                    strings.push(NewString::new(
+                        &unescape_ios(&captures[1]),
+                        unescape_ios(&captures[2]),
+                    ));
+                }
+            }
+            Desktop => {
+                if let Some(captures) = DESKTOP_KEY_REGEX.captures(line) {
+                    pending_key = Some(captures[1].to_string());
+                } else if let Some(captures) = DESKTOP_MESSAGE_REGEX.captures(line) {
+                    if let Some(key) = pending_key.take() {
+                        let value = serde_json::from_str::<String>(&captures[1])
+                            .unwrap_or_else(|_| captures[1].trim_matches('"').to_string());
+                        strings.push(NewString::new(&key, value));
+                    }
+                }
+            }
+        }
+    }
+
+    strings
+}
+
+fn unescape_android(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+        .replace(r"\'", "'")
+        .replace(r#"\""#, "\"")
+}
+
+fn unescape_ios(value: &str) -> String {
+    value.replace(r#"\""#, "\"").replace(r"\\", r"\")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::types::github::{ComparisonStatus, File};
+
+    fn strings(pairs: &[(&str, &str)]) -> Vec<NewString> {
+        pairs
+            .iter()
+            .map(|(key, value)| NewString::new(key, *value))
+            .collect()
+    }
+
+    const ANDROID_PATCH: &str = r#"@@ -100,6 +100,16 @@
+     <string name="ConversationFragment_cancel">Cancel</string>
++    <string name="ConversationFragment_delete_for_me">Delete for me</string>
++    <string name="ConversationFragment_you_cant">You can\'t edit &quot;this&quot; &amp; that</string>
++    <string name="app_name_internal" translatable="false">Signal</string>
++    <plurals name="ConversationFragment_n_messages">
++        <item quantity="one">%1$d message</item>
++        <item quantity="other">%1$d messages</item>
++    </plurals>
+-    <string name="ConversationFragment_edit">Edit</string>
++    <string name="ConversationFragment_edit">Edit message</string>"#;
+
+    const IOS_PATCH: &str = r#"@@ -200,6 +200,9 @@
+ "BACKUP_TITLE" = "Backups";
++"BACKUP_ENABLE" = "Turn on \"Backups\"";
++/* A comment */
++"BACKUP_DISABLE" = "Turn off";
+-"BACKUP_SUBTITLE" = "Old subtitle";
++"BACKUP_SUBTITLE" = "New subtitle";"#;
+
+    const DESKTOP_PATCH: &str = r#"@@ -300,6 +300,14 @@
+   "icu:Backups--title": {
+     "messageformat": "Backups",
+     "description": "Title of the backups page"
+   },
++  "icu:Backups--enable": {
++    "messageformat": "Turn on \"Backups\"",
++    "description": "Button to turn on backups"
++  },
++  "icu:Backups--legacy": {
++    "message": "Legacy",
++    "description": "Old style message"
++  },
+   "icu:Backups--subtitle": {
+-    "messageformat": "Old subtitle",
++    "messageformat": "New subtitle","#;
+
+    #[test]
+    fn android() {
+        assert_eq!(
+            NewString::from_patch(Android, ANDROID_PATCH),
+            strings(&[
+                ("ConversationFragment_delete_for_me", "Delete for me"),
+                (
+                    "ConversationFragment_you_cant",
+                    r#"You can't edit "this" & that"#
+                ),
+                ("ConversationFragment_n_messages", "%1$d message"),
+            ])
+        );
+    }
+
+    #[test]
+    fn ios() {
+        assert_eq!(
+            NewString::from_patch(Ios, IOS_PATCH),
+            strings(&[
+                ("BACKUP_ENABLE", r#"Turn on "Backups""#),
+                ("BACKUP_DISABLE", "Turn off"),
+            ])
+        );
+    }
+
+    #[test]
+    fn desktop() {
+        assert_eq!(
+            NewString::from_patch(Desktop, DESKTOP_PATCH),
+            strings(&[
+                ("icu:Backups--enable", r#"Turn on "Backups""#),
+                ("icu:Backups--legacy", "Legacy"),
+            ])
+        );
+    }
+
+    #[test_case(Android, "app/src/main/res/values/strings.xml"; "Android")]
+    #[test_case(Ios, "Signal/translations/en.lproj/Localizable.strings"; "iOS")]
+    #[test_case(Desktop, "_locales/en/messages.json"; "Desktop")]
+    fn base_path(platform: Platform, result: &str) {
+        assert_eq!(base_strings_path(platform), result);
+    }
+
+    fn comparison(files: Vec<File>) -> Comparison {
+        Comparison {
+            status: ComparisonStatus::Ahead,
+            ahead_by: 1,
+            total_commits: 1,
+            commits: Vec::new(),
+            files: Some(files),
+            merge_base_commit: None,
+        }
+    }
+
+    fn file(filename: &str, patch: Option<&str>) -> File {
+        File {
+            patch: patch.map(ToOwned::to_owned),
+            ..File::new(filename)
+        }
+    }
+
+    #[test]
+    fn from_comparison() {
+        let comparison = comparison(vec![
+            file("_locales/de/messages.json", Some(DESKTOP_PATCH)),
+            file("_locales/en/messages.json", Some(DESKTOP_PATCH)),
+        ]);
+
+        assert_eq!(
+            NewString::from_comparison(Desktop, &comparison).map(|strings| strings.len()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn from_comparison_without_base_file() {
+        let comparison = comparison(vec![file("_locales/de/messages.json", Some(DESKTOP_PATCH))]);
+
+        assert_eq!(
+            NewString::from_comparison(Desktop, &comparison),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn from_comparison_without_patch() {
+        let comparison = comparison(vec![file("_locales/en/messages.json", None)]);
+
+        assert_eq!(NewString::from_comparison(Desktop, &comparison), None);
+    }
+}
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use crate::platform::Platform::{self, *};
+use crate::core::platform::Platform::{self, *};
 
 #[derive(
     Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter,
@@ -37,6 +37,16 @@ impl StringsFileKind {
         }
     }
 
+    /// Directories with the strings files of all applicable kinds for `platform`.
+    pub fn path_bases(platform: Platform) -> Vec<&'static str> {
+        let mut path_bases: Vec<_> = Self::applicable_iter(platform)
+            .map(|kind| kind.path_base(platform))
+            .collect();
+
+        path_bases.dedup();
+        path_bases
+    }
+
     /// Expects `language_placeholder_value` to be in the expected format for `platform` and `kind`.
     fn path_folder_name(&self, platform: Platform, language_placeholder_value: &str) -> String {
         if !self.applicable_for_platform(platform) {
@@ -0,0 +1,388 @@
+use std::{cell::RefCell, fmt, future::Future, rc::Rc};
+
+use serde_json::json;
+
+use crate::core::{
+    platform::Platform,
+    task_local::{self, Scoped},
+};
+
+/// Logs a line at `$level`, optionally about a platform:
+/// `log!(Level::Info, platform = platform, "message {}", argument)`.
+macro_rules! log {
+    ($level:expr, platform = $platform:expr, $($arg:tt)+) => {
+        $crate::core::log::write($level, Some($platform), format_args!($($arg)+))
+    };
+    ($level:expr, $($arg:tt)+) => {
+        $crate::core::log::write($level, None, format_args!($($arg)+))
+    };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)+) => {
+        $crate::core::log::log!($crate::core::log::Level::Info, $($arg)+)
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        $crate::core::log::log!($crate::core::log::Level::Warn, $($arg)+)
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)+) => {
+        $crate::core::log::log!($crate::core::log::Level::Error, $($arg)+)
+    };
+}
+
+pub(crate) use log;
+pub(crate) use log_error;
+pub(crate) use log_info;
+pub(crate) use log_warn;
+
+/// Severity of a log line; lines below the level set with `LOG_LEVEL` are suppressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+
+    /// Finds the level called `name`, ignoring case; an empty `name` means [`Level::Info`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "" | "info" => Some(Self::Info),
+            "warn" => Some(Self::Warn),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Where formatted lines are written to.
+pub type Output = fn(Level, &str);
+
+fn write_to_stderr(_level: Level, line: &str) {
+    eprintln!("{line}");
+}
+
+/// How an invocation logs: lines get `trace_id`, lines below `min_level` are suppressed,
+/// `secrets` are redacted, and lines are written to `output`.
+#[derive(Clone)]
+pub struct Context {
+    pub output: Output,
+    pub trace_id: String,
+    pub min_level: Level,
+    pub secrets: Vec<String>,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            output: write_to_stderr,
+            trace_id: String::new(),
+            min_level: Level::Info,
+            secrets: Vec::new(),
+        }
+    }
+}
+
+thread_local! {
+    /// Context of the future being polled, see [`scope`].
+    static CURRENT: task_local::Current<Context> = const { RefCell::new(None) };
+}
+
+fn current() -> Rc<Context> {
+    task_local::current(&CURRENT).unwrap_or_default()
+}
+
+/// Runs `future` with `context`, see [`task_local::scope`].
+pub fn scope<F: Future>(context: Context, future: F) -> Scoped<Context, F> {
+    task_local::scope(&CURRENT, context, future)
+}
+
+/// Runs `future` with the context that is current now, e.g. for work that
+/// is finished after the handler returns.
+pub fn in_current_scope<F: Future>(future: F) -> Scoped<Context, F> {
+    task_local::in_current_scope(&CURRENT, future)
+}
+
+/// Replaces secret values in logged text.
+const REDACTED: &str = "[REDACTED]";
+
+/// Returns the trace id of the current invocation.
+pub fn trace_id() -> String {
+    current().trace_id.clone()
+}
+
+/// Masks every occurrence of `secrets` in `text`, longest first so that a secret
+/// containing another one is masked entirely.
+fn redact_with(text: &str, secrets: &[String]) -> String {
+    let mut secrets: Vec<&str> = secrets
+        .iter()
+        .map(String::as_str)
+        .filter(|secret| !secret.is_empty())
+        .collect();
+    secrets.sort_unstable_by_key(|secret| std::cmp::Reverse(secret.len()));
+
+    secrets.into_iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret, REDACTED)
+    })
+}
+
+/// Masks the values of the secrets of the current invocation in `text`.
+pub fn redact(text: &str) -> String {
+    redact_with(text, &current().secrets)
+}
+
+/// Formats a log line as a JSON object.
+fn line(level: Level, message: &str, platform: Option<Platform>, trace_id: &str) -> String {
+    let mut line = json!({
+        "level": level.name(),
+        "message": message,
+        "trace_id": trace_id,
+    });
+
+    if let Some(platform) = platform {
+        line["platform"] = json!(platform.to_string());
+    }
+
+    line.to_string()
+}
+
+/// Writes a log line with secrets redacted, unless `level` is suppressed;
+/// use the `log_*!` macros instead.
+pub fn write(level: Level, platform: Option<Platform>, message: fmt::Arguments) {
+    let context = current();
+
+    if level < context.min_level {
+        return;
+    }
+
+    let message = redact_with(&message.to_string(), &context.secrets);
+    let line = line(level, &message, platform, &context.trace_id);
+
+    (context.output)(level, &line);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::pin,
+        task::{self, Poll},
+    };
+
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::poll_once;
+
+    #[test_case("", Some(Level::Info); "empty")]
+    #[test_case("info", Some(Level::Info); "info")]
+    #[test_case("WARN", Some(Level::Warn); "uppercase")]
+    #[test_case("error", Some(Level::Error); "error")]
+    #[test_case("debug", None; "unknown")]
+    fn level_from_name(name: &str, level: Option<Level>) {
+        assert_eq!(Level::from_name(name), level);
+    }
+
+    #[test]
+    fn levels_are_ordered_by_severity() {
+        assert!(Level::Info < Level::Warn);
+        assert!(Level::Warn < Level::Error);
+    }
+
+    #[test]
+    fn line_with_platform() {
+        let line = line(
+            Level::Warn,
+            "deferring v1.2.4",
+            Some(Platform::Android),
+            "0123456789abcdef",
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&line).unwrap(),
+            json!({
+                "level": "warn",
+                "message": "deferring v1.2.4",
+                "platform": "Android",
+                "trace_id": "0123456789abcdef",
+            })
+        );
+        assert!(!line.contains('\n'));
+    }
+
+    #[test]
+    fn line_without_platform() {
+        let line = line(Level::Info, "cron = \"0 * * * *\"\nnext", None, "id");
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&line).unwrap(),
+            json!({
+                "level": "info",
+                "message": "cron = \"0 * * * *\"\nnext",
+                "trace_id": "id",
+            })
+        );
+        assert!(!line.contains('\n'));
+    }
+
+    thread_local! {
+        static WRITTEN: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn write_to_memory(_level: Level, line: &str) {
+        WRITTEN.with(|written| written.borrow_mut().push(line.to_string()));
+    }
+
+    fn context(trace_id: &str) -> Context {
+        Context {
+            output: write_to_memory,
+            trace_id: trace_id.to_string(),
+            min_level: Level::Warn,
+            secrets: vec![String::from("ghp_token")],
+        }
+    }
+
+    #[test]
+    fn lines_are_written_to_output() {
+        poll_once(scope(context("id"), async {
+            log_info!("suppressed");
+            log_warn!(platform = Platform::Ios, "token = ghp_token");
+        }));
+
+        assert_eq!(
+            WRITTEN.with(|written| written.take()),
+            vec![String::from(
+                r#"{"level":"warn","message":"token = [REDACTED]","platform":"iOS","trace_id":"id"}"#
+            )]
+        );
+    }
+
+    /// Logs, waits once, and logs again, like an invocation that waits on a request.
+    async fn log_around_wait() {
+        let mut waited = false;
+
+        log_warn!("before");
+        std::future::poll_fn(|_| {
+            if std::mem::replace(&mut waited, true) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        log_warn!("after {}", trace_id());
+    }
+
+    #[test]
+    fn interleaved_scopes_keep_their_context() {
+        let mut first = pin!(scope(context("first"), log_around_wait()));
+        let mut second = pin!(scope(context("second"), log_around_wait()));
+        let mut cx = task::Context::from_waker(task::Waker::noop());
+
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+        assert!(second.as_mut().poll(&mut cx).is_pending());
+        assert!(first.as_mut().poll(&mut cx).is_ready());
+        assert!(second.as_mut().poll(&mut cx).is_ready());
+
+        let trace_ids: Vec<(String, String)> = WRITTEN
+            .with(|written| written.take())
+            .iter()
+            .map(|line| {
+                let line: Value = serde_json::from_str(line).unwrap();
+                (
+                    line["message"].as_str().unwrap().to_string(),
+                    line["trace_id"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            trace_ids,
+            [
+                ("before", "first"),
+                ("before", "second"),
+                ("after first", "first"),
+                ("after second", "second"),
+            ]
+            .map(|(message, trace_id)| (message.to_string(), trace_id.to_string()))
+        );
+        assert_eq!(trace_id(), "");
+    }
+
+    #[test]
+    fn deferred_work_keeps_context() {
+        let mut deferred = None;
+        poll_once(scope(context("id"), async {
+            deferred = Some(in_current_scope(async { log_warn!("deferred") }));
+        }));
+
+        log_warn!("outside");
+        poll_once(deferred.unwrap());
+
+        assert_eq!(
+            WRITTEN.with(|written| written.take()),
+            vec![String::from(
+                r#"{"level":"warn","message":"deferred","trace_id":"id"}"#
+            )]
+        );
+    }
+
+    fn secrets() -> Vec<String> {
+        vec![String::from("discourse-key"), String::from("ghp_token")]
+    }
+
+    #[test]
+    fn body_with_key_is_redacted() {
+        let body = r#"{"raw":"New version","api_key":"discourse-key","topic_id":123}"#;
+
+        assert_eq!(
+            redact_with(body, &secrets()),
+            r#"{"raw":"New version","api_key":"[REDACTED]","topic_id":123}"#
+        );
+    }
+
+    #[test]
+    fn every_occurrence_is_redacted() {
+        assert_eq!(
+            redact_with(
+                "https://api.github.com/?token=ghp_token&again=ghp_token, key discourse-key",
+                &secrets()
+            ),
+            "https://api.github.com/?token=[REDACTED]&again=[REDACTED], key [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn longer_secret_is_redacted_entirely() {
+        assert_eq!(
+            redact_with(
+                "key = discourse-key-2",
+                &[
+                    String::from("discourse-key"),
+                    String::from("discourse-key-2")
+                ]
+            ),
+            "key = [REDACTED]"
+        );
+    }
+
+    #[test_case(&[]; "no secrets")]
+    #[test_case(&[String::new()]; "empty secret")]
+    fn nothing_to_redact(secrets: &[String]) {
+        assert_eq!(redact_with("posted = 123", secrets), "posted = 123");
+    }
+}
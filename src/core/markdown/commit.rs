@@ -0,0 +1,647 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{
+    sanitize, Author,
+    CommitStatus::{self, *},
+};
+use crate::core::{libsignal::LibsignalBump, platform::Platform, types::github};
+
+/// Commit subjects starting with one of these (ignoring case) are dependency bumps,
+/// unless other prefixes are configured.
+pub const DEFAULT_DEPENDENCY_BUMP_PREFIXES: &[&str] = &[
+    "chore(deps)",
+    "chore(deps-dev)",
+    "build(deps)",
+    "build(deps-dev)",
+];
+
+/// Commits that only change files with these names are dependency bumps.
+const DEPENDENCY_FILENAMES: &[&str] = &[
+    "package.json",
+    "yarn.lock",
+    "package-lock.json",
+    "pnpm-lock.yaml",
+    "Cargo.lock",
+    "build.gradle",
+];
+
+lazy_static! {
+    static ref DEPENDENCY_BUMP_REGEX: Regex =
+        Regex::new(r"(?i)\bbump\s+`?(\S+?)`?\s+from\s+`?(\S+?)`?\s+to\s+`?([^\s`]+)").unwrap();
+}
+
+/// Dependency and versions of a commit subject like "chore(deps): bump electron from 25.0.0 to 25.1.0".
+#[derive(Debug, PartialEq, Eq)]
+pub struct DependencyBump<'a> {
+    pub name: &'a str,
+    pub from: &'a str,
+    pub to: &'a str,
+}
+
+impl<'a> DependencyBump<'a> {
+    pub fn from_subject(subject: &'a str) -> Option<Self> {
+        DEPENDENCY_BUMP_REGEX
+            .captures(subject)
+            .map(|captures| Self {
+                name: captures.get(1).unwrap().as_str(),
+                from: captures.get(2).unwrap().as_str(),
+                to: captures.get(3).unwrap().as_str(),
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Commit<'a> {
+    platform: Platform,
+    full_message: &'a str,
+    sha: &'a str,
+    /// Whether GitHub verified the signature of the commit.
+    verified: bool,
+    /// Changed files, if GitHub listed them.
+    files: Option<&'a [github::File]>,
+    /// Change of the libsignal dependency, if the commit changed it.
+    libsignal_bump: Option<LibsignalBump>,
+    /// Version of an earlier post that already listed the commit (e.g. before it was cherry-picked).
+    also_in: Option<String>,
+    author: Author<'a>,
+}
+
+impl<'a> Commit<'a> {
+    pub fn new(platform: Platform, full_message: &'a str, sha: &'a str, verified: bool) -> Self {
+        Self {
+            platform,
+            full_message,
+            sha,
+            verified,
+            files: None,
+            libsignal_bump: None,
+            also_in: None,
+            author: Author::default(),
+        }
+    }
+
+    pub fn with_libsignal_bump(self, libsignal_bump: LibsignalBump) -> Self {
+        Self {
+            libsignal_bump: Some(libsignal_bump),
+            ..self
+        }
+    }
+
+    pub fn with_also_in(self, version: impl Into<String>) -> Self {
+        Self {
+            also_in: Some(version.into()),
+            ..self
+        }
+    }
+
+    pub fn with_author(self, author: Author<'a>) -> Self {
+        Self { author, ..self }
+    }
+
+    pub fn author(&self) -> Author<'a> {
+        self.author
+    }
+
+    pub fn libsignal_bump(&self) -> Option<&LibsignalBump> {
+        self.libsignal_bump.as_ref()
+    }
+
+    pub fn with_files(self, files: &'a [github::File]) -> Self {
+        Self {
+            files: Some(files),
+            ..self
+        }
+    }
+
+    /// Commits without verification data are treated as unverified.
+    pub fn from_github_commit(platform: Platform, github_commit: &'a github::Commit) -> Self {
+        let verified = github_commit
+            .commit
+            .verification
+            .as_ref()
+            .is_some_and(|verification| verification.verified);
+
+        let git_author = github_commit.commit.author.as_ref();
+
+        let commit = Self::new(
+            platform,
+            &github_commit.commit.message,
+            &github_commit.sha,
+            verified,
+        )
+        .with_author(Author {
+            login: github_commit
+                .author
+                .as_ref()
+                .map(|account| account.login.as_str()),
+            name: git_author.map(|author| author.name.as_str()),
+            email: git_author.map(|author| author.email.as_str()),
+        });
+
+        match &github_commit.files {
+            Some(files) => commit.with_files(files),
+            None => commit,
+        }
+    }
+
+    /// Whether the commit only bumps the version of the app, like "Bump version to 6.30.2".
+    pub fn is_version_bump(&self) -> bool {
+        let lowercase = self.subject().to_lowercase();
+
+        lowercase.starts_with("bump version") || lowercase.starts_with("bump build")
+    }
+
+    pub fn is_likely_localization_change(&self) -> bool {
+        let lowercase = self.full_message.to_lowercase();
+
+        lowercase.contains("language")
+            || lowercase.contains("translation")
+            || lowercase.contains("string")
+            || lowercase.contains("release note")
+            || lowercase.contains("i18n")
+            || lowercase.contains("l10n")
+    }
+
+    pub fn subject(&self) -> &'a str {
+        self.full_message
+            .split('\n')
+            .next()
+            .unwrap_or_default()
+            .trim()
+    }
+
+    /// Whether the subject starts with one of `prefixes` (ignoring case), or the commit
+    /// only changes dependency manifests and lockfiles (if its files are known).
+    pub fn is_dependency_bump(&self, prefixes: &[impl AsRef<str>]) -> bool {
+        let subject = self.subject().to_lowercase();
+
+        let has_prefix = prefixes
+            .iter()
+            .any(|prefix| subject.starts_with(&prefix.as_ref().to_lowercase()));
+
+        let only_dependency_files = self.files.is_some_and(|files| {
+            !files.is_empty()
+                && files.iter().all(|file| {
+                    let filename = file.filename.rsplit('/').next().unwrap_or_default();
+                    DEPENDENCY_FILENAMES.contains(&filename)
+                })
+        });
+
+        has_prefix || only_dependency_files
+    }
+
+    pub fn dependency_bump(&self) -> Option<DependencyBump<'a>> {
+        DependencyBump::from_subject(self.subject())
+    }
+
+    pub fn commit_url(&self) -> String {
+        self.platform.github_commit_url(self.sha)
+    }
+
+    pub fn sha(&self) -> &str {
+        self.sha
+    }
+
+    pub fn full_message(&self) -> &str {
+        self.full_message
+    }
+
+    pub fn reverted_commit_sha(&self) -> Option<&str> {
+        lazy_static! {
+            static ref REVERTS_COMMIT_REGEX: Regex =
+                Regex::new(r"This reverts commit ([a-zA-Z0-9]+).").unwrap();
+        }
+
+        REVERTS_COMMIT_REGEX
+            .captures_iter(self.full_message)
+            .filter_map(|capture| capture.get(1))
+            .map(|group| group.as_str())
+            .next()
+    }
+
+    /// Parts of the rendered commit that both list styles use.
+    fn rendered_parts(
+        &self,
+        number: usize,
+        status: CommitStatus,
+        show_verification: bool,
+    ) -> RenderedParts {
+        let mut message_lines = sanitize::commit_message_lines(self.full_message);
+
+        let message = match message_lines.remove(0) {
+            first_line if first_line.trim().is_empty() => String::from("*Empty commit message*"),
+            first_line => first_line,
+        };
+
+        let details = match message_lines.len() {
+            (1..) if self.platform.should_show_commit_details() => message_lines,
+            _ => Vec::new(),
+        };
+
+        let (prefix, suffix) = match status {
+            Both {
+                reverts,
+                is_reverted_by,
+            } => (
+                "<del>",
+                format!("</del> (reverts [{reverts}], reverted by [{is_reverted_by}])"),
+            ),
+            IsRevertedBy(other_number) => {
+                ("<del>", format!("</del> (reverted by [{other_number}])"))
+            }
+            Reverts(other_number) => ("<ins>", format!("</ins> (reverts [{other_number}])")),
+            Normal => ("", String::new()),
+        };
+
+        let verified_mark = if show_verification && self.verified {
+            " ✔"
+        } else {
+            ""
+        };
+
+        let libsignal_badge = match &self.libsignal_bump {
+            Some(libsignal_bump) => format!(" {}", libsignal_bump.badge()),
+            None => String::new(),
+        };
+
+        let also_in = match &self.also_in {
+            Some(version) => format!(" (also in {version})"),
+            None => String::new(),
+        };
+
+        RenderedParts {
+            prefix,
+            message,
+            link: format!("[[{number}]]({}){verified_mark}", self.commit_url()),
+            annotations: libsignal_badge + &also_in,
+            suffix,
+            details,
+        }
+    }
+
+    /// `show_verification`: whether to mark the commit if it is verified.
+    pub fn markdown_text(
+        &self,
+        number: usize,
+        status: CommitStatus,
+        show_verification: bool,
+    ) -> String {
+        let RenderedParts {
+            prefix,
+            message,
+            link,
+            annotations,
+            suffix,
+            details,
+        } = self.rendered_parts(number, status, show_verification);
+
+        let main_content = format!("- {prefix}{message} {link}{annotations}{suffix}\n");
+        let details: String = details.iter().map(|line| format!("\n    {line}")).collect();
+
+        let text = main_content + &details;
+        debug_assert!(
+            !sanitize::has_bare_url_line(&text),
+            "commit has a bare URL line: {text}"
+        );
+        text
+    }
+
+    /// Renders the commit as a row of the table with the header [`TABLE_HEADER`], followed by
+    /// a row with the rest of its message, if any.
+    pub fn table_rows(
+        &self,
+        number: usize,
+        status: CommitStatus,
+        show_verification: bool,
+    ) -> String {
+        let RenderedParts {
+            prefix,
+            message,
+            link,
+            annotations,
+            suffix,
+            details,
+        } = self.rendered_parts(number, status, show_verification);
+
+        let message = escape_table_cell(&message);
+        let main_row = format!("| {number} | {prefix}{message}{annotations}{suffix} | {link} |\n");
+
+        let details: Vec<String> = details
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| escape_table_cell(line.trim()))
+            .collect();
+
+        match details.is_empty() {
+            true => main_row,
+            false => main_row + &format!("| | {} | |\n", details.join("<br>")),
+        }
+    }
+}
+
+/// Header of the table that commits are listed in with [`Commit::table_rows`].
+pub const TABLE_HEADER: &str = "| # | Commit | Link |\n| --: | --- | --- |\n";
+
+/// The rendered pieces of a commit, see [`Commit::markdown_text`].
+struct RenderedParts {
+    prefix: &'static str,
+    message: String,
+    /// Link to the commit, with the verified mark.
+    link: String,
+    /// libsignal badge and earlier post of the commit.
+    annotations: String,
+    suffix: String,
+    /// Lines of the message after the first one, if they are shown.
+    details: Vec<String>,
+}
+
+/// Keeps `text` from ending its table cell early.
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', r"\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use proptest::prelude::*;
+    use strum::IntoEnumIterator;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::platform::Platform::{self, *};
+
+    #[test_case(true, "Updated language translations.")]
+    #[test_case(true, "Update strings")]
+    #[test_case(true, "Updates strings")]
+    #[test_case(true, "Updates i18n strings")]
+    #[test_case(true, "Update translations")]
+    #[test_case(true, "Update release notes")]
+    #[test_case(true, "Update release notes & App Store descriptions")]
+    #[test_case(false, "Update GitHub Actions")]
+    #[test_case(false, "Test commit.")]
+    fn is_likely_localization_change(result: bool, message: &str) {
+        for platform in Platform::iter() {
+            assert_eq!(
+                Commit::new(platform, message, "abcdef", false).is_likely_localization_change(),
+                result
+            );
+        }
+    }
+
+    #[test_case("Bump version to 6.30.2", true; "version")]
+    #[test_case("Bump build to 6.45.0.3.", true; "build")]
+    #[test_case("bump version to 7.1.0-beta.1", true; "lowercase")]
+    #[test_case("chore(deps): bump electron from 25.0.0 to 25.1.0", false; "dependency")]
+    #[test_case("Fix version bump dialog.", false; "mentions version bump")]
+    fn is_version_bump(message: &str, result: bool) {
+        assert_eq!(
+            Commit::new(Android, message, "abcdef", false).is_version_bump(),
+            result
+        );
+    }
+
+    #[test_case("chore(deps): bump electron from 25.0.0 to 25.1.0", Some(("electron", "25.0.0", "25.1.0")); "dependabot")]
+    #[test_case("chore(deps-dev): bump @types/node from 18.0.0 to 18.1.2 (#1234)", Some(("@types/node", "18.0.0", "18.1.2")); "scoped package and pull request")]
+    #[test_case("Bump `zod` from `3.21.0` to `3.22.0`", Some(("zod", "3.21.0", "3.22.0")); "backticks")]
+    #[test_case("build(deps): Bump actions/checkout from 3 to 4 in /sticker-creator", Some(("actions/checkout", "3", "4")); "directory")]
+    #[test_case("chore(deps): bump the npm group with 3 updates", None; "group")]
+    #[test_case("Bump version to 6.30.0", None; "version bump")]
+    fn dependency_bump_from_subject(subject: &str, result: Option<(&str, &str, &str)>) {
+        assert_eq!(
+            DependencyBump::from_subject(subject),
+            result.map(|(name, from, to)| DependencyBump { name, from, to })
+        );
+    }
+
+    fn files(filenames: &[&str]) -> Vec<github::File> {
+        filenames
+            .iter()
+            .map(|filename| github::File::new(*filename))
+            .collect()
+    }
+
+    #[test_case("chore(deps): bump electron from 25.0.0 to 25.1.0", None, true; "prefix")]
+    #[test_case("Chore(Deps): bump electron from 25.0.0 to 25.1.0", None, true; "prefix in any case")]
+    #[test_case("Update dependencies", Some(&["package.json", "yarn.lock"]), true; "only dependency files")]
+    #[test_case("Update dependencies", Some(&["sticker-creator/package.json"]), true; "dependency file in directory")]
+    #[test_case("Update dependencies", Some(&["package.json", "ts/main.ts"]), false; "other files")]
+    #[test_case("Update dependencies", Some(&[]), false; "no files")]
+    #[test_case("Update dependencies", None, false; "unknown files")]
+    #[test_case("Fix chore(deps) handling", None, false; "prefix not at start")]
+    fn is_dependency_bump(message: &str, filenames: Option<&[&str]>, result: bool) {
+        let files = filenames.map(files);
+        let commit = Commit::new(Desktop, message, "abcdef", false);
+        let commit = match &files {
+            Some(files) => commit.with_files(files),
+            None => commit,
+        };
+
+        assert_eq!(
+            commit.is_dependency_bump(DEFAULT_DEPENDENCY_BUMP_PREFIXES),
+            result
+        );
+    }
+
+    #[test]
+    fn is_dependency_bump_with_configured_prefixes() {
+        let commit = Commit::new(Desktop, "deps: update electron", "abcdef", false);
+
+        assert!(commit.is_dependency_bump(&["deps:"]));
+        assert!(!commit.is_dependency_bump(DEFAULT_DEPENDENCY_BUMP_PREFIXES));
+    }
+
+    #[test_case(
+        Android, "Test commit.", "abcdef", Normal,
+        "- Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)\n";
+        "Android: one line"
+    )]
+    #[test_case(
+        Android, "Test commit.\nAnother line.", "abcdef", Normal,
+        "- Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)\n\n    Another line.";
+        "Android: two lines"
+    )]
+    #[test_case(
+        Android, "Test commit.\nAnother line.\nAnd another line.", "abcdef", Normal,
+        "- Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)\n\n    Another line.\n    And another line.";
+        "Android: three lines"
+    )]
+    #[test_case(
+        Android, "Test commit.\nCo-Authored-By: user", "abcdef", Normal,
+        "- Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)\n";
+        "Android: Co-Authored-By is removed"
+    )]
+    #[test_case(
+        Android, "Test commit.\nCo-authored-by: user", "abcdef", Normal,
+        "- Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)\n";
+        "Android: Co-Authored-By in any case is removed"
+    )]
+    #[test_case(
+        Android, "Revert \"Test commit\".\nThis reverts commit fedcba.", "abcdef", Reverts(1),
+        "- <ins>Revert \"Test commit\". [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)</ins> (reverts [1])\n";
+        "Android: reverts commit"
+    )]
+    #[test_case(
+        Android, "Test commit.", "abcdef", IsRevertedBy(3),
+        "- <del>Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)</del> (reverted by [3])\n";
+        "Android: reverted commit"
+    )]
+    #[test_case(
+        Android, "Test commit.", "abcdef", Both { reverts: 1, is_reverted_by: 3 },
+        "- <del>Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)</del> (reverts [1], reverted by [3])\n";
+        "Android: reverted commit that reverts"
+    )]
+    #[test_case(
+        Desktop, "Test commit.", "abcdef", Normal,
+        "- Test commit. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n";
+        "Desktop: one line"
+    )]
+    #[test_case(
+        Desktop, "Test commit. Test @mention!\nTest@mention2.", "abcdef", Normal,
+        "- Test commit. Test `@mention`! [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n\n    Test`@mention2`.";
+        "Desktop: two lines with mention"
+    )]
+    #[test_case(
+        Ios, "Test commit. Continuation.\nContinuation 2.\nContinuation 3.", "abcdef", Normal,
+        "- Test commit. Continuation. [[2]](https://github.com/signalapp/Signal-iOS/commit/abcdef)\n";
+        "iOS: three lines, details are not shown"
+    )]
+    #[test_case(
+        Desktop, "Fix crash.\nhttps://github.com/signalapp/Signal-Desktop/issues/1", "abcdef", Normal,
+        "- Fix crash. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n\n    [https://github.com/signalapp/Signal-Desktop/issues/1](https://github.com/signalapp/Signal-Desktop/issues/1)";
+        "Desktop: bare URL in details is not oneboxed"
+    )]
+    fn commit_markdown(
+        platform: Platform,
+        full_message: &str,
+        sha: &str,
+        status: CommitStatus,
+        result: &str,
+    ) {
+        assert_str_eq!(
+            Commit::new(platform, full_message, sha, false).markdown_text(2, status, false),
+            result
+        );
+    }
+
+    fn github_commit(verification: Option<bool>) -> github::Commit {
+        github::Commit {
+            sha: String::from("abcdef"),
+            commit: github::CommitData {
+                message: String::from("Test commit."),
+                author: None,
+                verification: verification.map(|verified| github::Verification { verified }),
+            },
+            author: None,
+            files: None,
+        }
+    }
+
+    #[test_case(Some(true), true, "- Test commit. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef) ✔\n"; "verified")]
+    #[test_case(Some(false), true, "- Test commit. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n"; "unverified")]
+    #[test_case(None, true, "- Test commit. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n"; "missing verification data")]
+    #[test_case(Some(true), false, "- Test commit. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n"; "verified, not shown")]
+    fn commit_markdown_verification(
+        verification: Option<bool>,
+        show_verification: bool,
+        result: &str,
+    ) {
+        let github_commit = github_commit(verification);
+
+        assert_str_eq!(
+            Commit::from_github_commit(Desktop, &github_commit).markdown_text(
+                2,
+                Normal,
+                show_verification
+            ),
+            result
+        );
+    }
+
+    #[test_case(None, Some("0.31.0"), false, "- Update libsignal. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef) :link: libsignal 0.31.0\n"; "new version")]
+    #[test_case(Some("0.30.2"), Some("0.31.0"), true, "- Update libsignal. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef) ✔ :link: libsignal 0.30.2 → 0.31.0\n"; "both versions, verified")]
+    fn libsignal_bump_markdown(
+        old_version: Option<&str>,
+        new_version: Option<&str>,
+        verified: bool,
+        result: &str,
+    ) {
+        let commit = Commit::new(Android, "Update libsignal.", "abcdef", verified)
+            .with_libsignal_bump(LibsignalBump {
+                old_version: old_version.map(ToOwned::to_owned),
+                new_version: new_version.map(ToOwned::to_owned),
+            });
+
+        assert_str_eq!(commit.markdown_text(2, Normal, true), result);
+    }
+
+    #[test_case(Normal, "- Fix crash. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef) (also in 1.2.3)\n"; "normal")]
+    #[test_case(IsRevertedBy(3), "- <del>Fix crash. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef) (also in 1.2.3)</del> (reverted by [3])\n"; "reverted")]
+    fn also_in_markdown(status: CommitStatus, result: &str) {
+        let commit = Commit::new(Android, "Fix crash.", "abcdef", false).with_also_in("1.2.3");
+
+        assert_str_eq!(commit.markdown_text(2, status, true), result);
+    }
+
+    #[test_case("Fix a | b.", Normal, "| 2 | Fix a \\| b. | [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef) ✔ |\n"; "pipe is escaped")]
+    #[test_case("Fix crash.\n\nFirst | line.\nSecond line.", Normal, "| 2 | Fix crash. | [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef) ✔ |\n| | First \\| line.<br>Second line. | |\n"; "details row")]
+    #[test_case("Fix crash.", IsRevertedBy(3), "| 2 | <del>Fix crash.</del> (reverted by [3]) | [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef) ✔ |\n"; "reverted")]
+    fn table_rows(full_message: &str, status: CommitStatus, result: &str) {
+        assert_str_eq!(
+            Commit::new(Desktop, full_message, "abcdef", true).table_rows(2, status, true),
+            result
+        );
+    }
+
+    #[test]
+    fn verified_reverted_commit_markdown() {
+        assert_str_eq!(
+            Commit::new(Android, "Test commit.", "abcdef", true).markdown_text(2, IsRevertedBy(3), true),
+            "- <del>Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef) ✔</del> (reverted by [3])\n"
+        );
+    }
+
+    #[test_case("Co-authored-by: user\nTest commit.", "- Co-authored-by: user [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n\n    Test commit."; "first line is kept")]
+    #[test_case("Close [/quote] early", "- Close \\[/quote] early [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n"; "closing quote is escaped")]
+    #[test_case("", "- *Empty commit message* [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n"; "empty")]
+    #[test_case(" \nDetails.", "- *Empty commit message* [[2]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)\n\n    Details."; "blank first line")]
+    fn sanitized_commit_markdown(full_message: &str, result: &str) {
+        assert_str_eq!(
+            Commit::new(Desktop, full_message, "abcdef", false).markdown_text(2, Normal, false),
+            result
+        );
+    }
+
+    fn status() -> impl Strategy<Value = CommitStatus> {
+        prop_oneof![
+            Just(Normal),
+            (1..300usize).prop_map(Reverts),
+            (1..300usize).prop_map(IsRevertedBy),
+            (1..300usize, 1..300usize).prop_map(|(reverts, is_reverted_by)| Both {
+                reverts,
+                is_reverted_by
+            }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn commit_markdown_invariants(
+            platform in prop::sample::select(Platform::iter().collect::<Vec<_>>()),
+            full_message in sanitize::message(),
+            number in 1..300usize,
+            status in status(),
+            verified: bool,
+        ) {
+            let markdown =
+                Commit::new(platform, &full_message, "abcdef", verified).markdown_text(number, status, true);
+            let first_line = markdown.split('\n').next().unwrap();
+            let index_link = format!(" [[{number}]]({})", platform.github_commit_url("abcdef"));
+
+            prop_assert!(sanitize::closing_quotes_are_escaped(&markdown));
+            prop_assert!(first_line.starts_with("- "));
+            prop_assert!(first_line.contains(&index_link));
+            prop_assert!(!sanitize::has_bare_url_line(&markdown));
+        }
+    }
+}
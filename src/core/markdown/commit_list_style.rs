@@ -0,0 +1,35 @@
+/// How the commits of a post are listed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommitListStyle {
+    /// One bullet per commit, with its details indented below.
+    #[default]
+    Bullets,
+    /// One table row per commit, with its details in a row below.
+    Table,
+}
+
+impl CommitListStyle {
+    /// Finds the style whose name (`bullets` or `table`) matches `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "bullets" => Some(Self::Bullets),
+            "table" => Some(Self::Table),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::{CommitListStyle::*, *};
+
+    #[test_case("bullets", Some(Bullets); "bullets")]
+    #[test_case("Table", Some(Table); "table")]
+    #[test_case("list", None; "unknown")]
+    fn from_name(name: &str, result: Option<CommitListStyle>) {
+        assert_eq!(CommitListStyle::from_name(name), result);
+    }
+}
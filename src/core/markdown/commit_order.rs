@@ -0,0 +1,35 @@
+/// Order of the commits listed in a post.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommitOrder {
+    /// The order of the comparison.
+    #[default]
+    OldestFirst,
+    /// The reverse order of the comparison, except that version bumps stay at the end.
+    NewestFirst,
+}
+
+impl CommitOrder {
+    /// Finds the order whose name (`oldest-first` or `newest-first`) matches `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "oldest-first" => Some(Self::OldestFirst),
+            "newest-first" => Some(Self::NewestFirst),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::{CommitOrder::*, *};
+
+    #[test_case("oldest-first", Some(OldestFirst); "oldest first")]
+    #[test_case("Newest-First", Some(NewestFirst); "newest first")]
+    #[test_case("newest", None; "unknown")]
+    fn from_name(name: &str, result: Option<CommitOrder>) {
+        assert_eq!(CommitOrder::from_name(name), result);
+    }
+}
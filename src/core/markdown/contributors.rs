@@ -0,0 +1,266 @@
+use super::{sanitize, Commit};
+
+/// Authors with these logins or names (ignoring case) are bots, not contributors;
+/// so is any login ending with `[bot]`.
+const BOT_DENY_LIST: &[&str] = &[
+    "dependabot",
+    "github-actions",
+    "GitHub Actions",
+    "renovate",
+    "web-flow",
+];
+
+/// Identity of the author of a commit, as far as GitHub knows it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Author<'a> {
+    pub login: Option<&'a str>,
+    pub name: Option<&'a str>,
+    pub email: Option<&'a str>,
+}
+
+impl<'a> Author<'a> {
+    /// Without empty values, which GitHub uses for some unknown ones.
+    fn normalized(self) -> Self {
+        let non_empty = |value: Option<&'a str>| value.filter(|value| !value.trim().is_empty());
+
+        Self {
+            login: non_empty(self.login),
+            name: non_empty(self.name),
+            email: non_empty(self.email),
+        }
+    }
+
+    /// Local part of the email, unless it is one shared by unrelated people, like `noreply`.
+    fn email_local_part(&self) -> Option<&'a str> {
+        self.email
+            .and_then(|email| email.split('@').next())
+            .filter(|local_part| {
+                !local_part.is_empty()
+                    && !["noreply", "no-reply"].contains(&local_part.to_lowercase().as_str())
+            })
+    }
+
+    /// Keys that identify the author, any of which is enough to tell that two authors are the same.
+    fn keys(&self) -> Vec<String> {
+        [
+            self.login.map(|login| format!("login:{login}")),
+            self.name.map(|name| format!("name:{name}")),
+            self.email_local_part()
+                .map(|local_part| format!("email:{local_part}")),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|key| key.to_lowercase())
+        .collect()
+    }
+
+    fn is_bot(&self) -> bool {
+        let is_denied = |value: &str| {
+            BOT_DENY_LIST
+                .iter()
+                .any(|denied| denied.eq_ignore_ascii_case(value.trim_end_matches("[bot]")))
+        };
+
+        self.login
+            .is_some_and(|login| login.ends_with("[bot]") || is_denied(login))
+            || self.name.is_some_and(is_denied)
+    }
+}
+
+#[derive(Debug)]
+struct Contributor<'a> {
+    keys: Vec<String>,
+    author: Author<'a>,
+}
+
+impl<'a> Contributor<'a> {
+    /// The login if known, as it is the same for all commits, or else the name
+    /// or the local part of the email.
+    fn display_name(&self) -> &'a str {
+        self.author
+            .login
+            .or(self.author.name)
+            .or(self.author.email_local_part())
+            .unwrap_or_default()
+    }
+}
+
+/// Distinct authors of the commits of a post, except for bots.
+#[derive(Debug)]
+pub struct Contributors<'a> {
+    contributors: Vec<Contributor<'a>>,
+}
+
+impl<'a> Contributors<'a> {
+    /// Authors with the same login, name or local part of the email
+    /// (e.g. the same person committing with a work and a personal email) are counted once.
+    pub fn from_commits(commits: &[Commit<'a>]) -> Self {
+        let mut contributors: Vec<Contributor> = Vec::new();
+
+        for author in commits.iter().map(|commit| commit.author().normalized()) {
+            let keys = author.keys();
+
+            if keys.is_empty() || author.is_bot() {
+                continue;
+            }
+
+            match contributors
+                .iter_mut()
+                .find(|contributor| keys.iter().any(|key| contributor.keys.contains(key)))
+            {
+                Some(contributor) => {
+                    for key in keys {
+                        if !contributor.keys.contains(&key) {
+                            contributor.keys.push(key);
+                        }
+                    }
+
+                    contributor.author.login = contributor.author.login.or(author.login);
+                }
+                None => contributors.push(Contributor { keys, author }),
+            }
+        }
+
+        Self { contributors }
+    }
+
+    pub fn len(&self) -> usize {
+        self.contributors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contributors.is_empty()
+    }
+
+    /// Sorted names of the contributors, which are never mentions.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .contributors
+            .iter()
+            .map(|contributor| {
+                sanitize::escape_block_tags(&contributor.display_name().replace('@', ""))
+                    .into_owned()
+            })
+            .collect();
+
+        names.sort_by_key(|name| name.to_lowercase());
+        names
+    }
+
+    /// Like "Changes by 14 contributors", followed by a collapsed list of their names,
+    /// or nothing if there are none.
+    pub fn markdown(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+
+        let suffix = if self.len() == 1 { "" } else { "s" };
+
+        format!(
+            "Changes by {} contributor{suffix}\n[details=\"Contributors\"]\n{}\n[/details]\n",
+            self.len(),
+            self.names().join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::platform::Platform::*;
+
+    fn author(
+        login: Option<&'static str>,
+        name: &'static str,
+        email: &'static str,
+    ) -> Author<'static> {
+        Author {
+            login,
+            name: Some(name),
+            email: Some(email),
+        }
+    }
+
+    fn contributors(authors: Vec<Author<'static>>) -> Contributors<'static> {
+        let commits: Vec<Commit> = authors
+            .into_iter()
+            .map(|author| Commit::new(Desktop, "Test commit.", "abcdef", false).with_author(author))
+            .collect();
+
+        Contributors::from_commits(&commits)
+    }
+
+    #[test]
+    fn same_person_with_different_emails() {
+        let contributors = contributors(vec![
+            author(Some("jdoe-signal"), "Jane Doe", "jane@signal.org"),
+            author(None, "Jane Doe", "jane.doe@example.com"),
+            author(None, "J. Doe", "jane@example.com"),
+        ]);
+
+        assert_eq!(contributors.names(), vec!["jdoe-signal"]);
+    }
+
+    #[test]
+    fn login_is_preferred_even_if_found_later() {
+        let contributors = contributors(vec![
+            author(None, "Jane Doe", "jane@example.com"),
+            author(Some("jdoe-signal"), "Jane Doe", "jane@signal.org"),
+        ]);
+
+        assert_eq!(contributors.names(), vec!["jdoe-signal"]);
+    }
+
+    #[test]
+    fn different_people() {
+        let contributors = contributors(vec![
+            author(Some("jdoe-signal"), "Jane Doe", "jane@signal.org"),
+            author(Some("alex-signal"), "Alex", "alex@signal.org"),
+            author(None, "Sam Smith", "sam@example.com"),
+            author(None, "", "noreply@example.com"),
+        ]);
+
+        assert_eq!(
+            contributors.names(),
+            vec!["alex-signal", "jdoe-signal", "Sam Smith"]
+        );
+    }
+
+    #[test_case(author(Some("dependabot[bot]"), "dependabot[bot]", "49699333+dependabot[bot]@users.noreply.github.com"); "dependabot")]
+    #[test_case(author(Some("github-actions[bot]"), "github-actions[bot]", "actions@github.com"); "github actions app")]
+    #[test_case(author(None, "GitHub Actions", "actions@github.com"); "github actions name")]
+    #[test_case(author(Some("renovate-bot[bot]"), "Renovate", "bot@renovateapp.com"); "any bot login")]
+    fn bots_are_excluded(bot: Author<'static>) {
+        let contributors = contributors(vec![
+            bot,
+            author(Some("jdoe-signal"), "Jane Doe", "jane@signal.org"),
+        ]);
+
+        assert_eq!(contributors.names(), vec!["jdoe-signal"]);
+    }
+
+    #[test]
+    fn unknown_authors_are_skipped() {
+        assert!(contributors(vec![Author::default()]).is_empty());
+    }
+
+    #[test]
+    fn names_are_not_mentions() {
+        let contributors = contributors(vec![author(None, "@everyone", "x@example.com")]);
+
+        assert_eq!(contributors.names(), vec!["everyone"]);
+    }
+
+    #[test_case(vec![], ""; "none")]
+    #[test_case(vec![author(Some("jdoe-signal"), "Jane Doe", "jane@signal.org")], "Changes by 1 contributor\n[details=\"Contributors\"]\njdoe-signal\n[/details]\n"; "one")]
+    #[test_case(vec![
+        author(Some("jdoe-signal"), "Jane Doe", "jane@signal.org"),
+        author(None, "Alex", "alex@example.com"),
+    ], "Changes by 2 contributors\n[details=\"Contributors\"]\nAlex, jdoe-signal\n[/details]\n"; "two")]
+    fn markdown(authors: Vec<Author<'static>>, result: &str) {
+        assert_str_eq!(contributors(authors).markdown(), result);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::core::{state::release_cycle, types::github::Tag};
+
+/// A build that was already posted, from the history of posts.
+#[derive(Debug, Clone, Copy)]
+pub struct PostedBuild<'a> {
+    pub tag: &'a str,
+    /// ISO 8601 timestamp of when the post was made.
+    pub date: &'a str,
+    pub commits_count: usize,
+}
+
+/// Where a new build stands in its release cycle, e.g. the 6th build of 7.1.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CycleStats {
+    pub cycle: String,
+    /// 1 for the first build of the cycle.
+    pub build_number: usize,
+    /// Version of the first build of the cycle.
+    pub first_version: String,
+    /// Days since the first build of the cycle was posted, if its date is known.
+    pub days_since_first: Option<i64>,
+    /// Commits of all builds of the cycle, including the new one.
+    pub commits_count: usize,
+}
+
+impl CycleStats {
+    /// Computes the stats of `new_tag` with `commits_count` commits, at ISO 8601 timestamp `now`,
+    /// from `posted_builds` (in any order, of any cycle).
+    ///
+    /// Returns `None` for the first build of a cycle, or if the version of `new_tag` is unknown.
+    pub fn new(
+        new_tag: &Tag,
+        commits_count: usize,
+        now: &str,
+        posted_builds: &[PostedBuild],
+    ) -> Option<Self> {
+        let cycle = release_cycle(&new_tag.to_version().ok()?);
+
+        let mut cycle_builds: Vec<(Tag, &PostedBuild)> = posted_builds
+            .iter()
+            .filter(|build| build.tag != new_tag.name)
+            .map(|build| (Tag::new(build.tag), build))
+            .filter(|(tag, _)| {
+                tag.to_version()
+                    .is_ok_and(|version| release_cycle(&version) == cycle)
+            })
+            .collect();
+
+        cycle_builds.sort_by(|(_, a), (_, b)| a.date.cmp(b.date));
+
+        let (first_tag, first_build) = cycle_builds.first()?;
+
+        Some(Self {
+            cycle,
+            build_number: cycle_builds.len() + 1,
+            first_version: first_tag.exact_version_string(),
+            days_since_first: days_between(first_build.date, now),
+            commits_count: commits_count
+                + cycle_builds
+                    .iter()
+                    .map(|(_, build)| build.commits_count)
+                    .sum::<usize>(),
+        })
+    }
+
+    /// Like "6th build of the 7.1 cycle, 9 days since 7.1.0-beta.1, 214 commits in total".
+    pub fn markdown(&self) -> String {
+        let days = match self.days_since_first {
+            Some(1) => String::from("1 day"),
+            Some(days) => format!("{days} days"),
+            None => String::from("some time"),
+        };
+
+        format!(
+            "{} build of the {} cycle, {days} since {}, {} commits in total",
+            ordinal(self.build_number),
+            self.cycle,
+            self.first_version,
+            self.commits_count
+        )
+    }
+}
+
+/// Like "1st", "22nd" or "13th".
+fn ordinal(number: usize) -> String {
+    let suffix = match (number % 10, number % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{number}{suffix}")
+}
+
+/// Whole days between the dates (in UTC) of ISO 8601 timestamps `from` and `to`.
+fn days_between(from: &str, to: &str) -> Option<i64> {
+    Some(days_since_epoch(to)? - days_since_epoch(from)?)
+}
+
+/// Days from 1970-01-01 to the date of ISO 8601 timestamp `timestamp`.
+fn days_since_epoch(timestamp: &str) -> Option<i64> {
+    let mut parts = timestamp.get(..10)?.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+
+    const NOW: &str = "2023-07-10T12:00:00.000Z";
+
+    fn build(tag: &'static str, date: &'static str, commits_count: usize) -> PostedBuild<'static> {
+        PostedBuild {
+            tag,
+            date,
+            commits_count,
+        }
+    }
+
+    fn posted_builds() -> Vec<PostedBuild<'static>> {
+        vec![
+            build("v7.1.0-beta.2", "2023-07-05T10:00:00.000Z", 30),
+            build("v7.1.0-beta.1", "2023-07-01T10:00:00.000Z", 150),
+            build("v7.0.2", "2023-06-28T10:00:00.000Z", 5),
+            build("v7.0.0-beta.1", "2023-06-01T10:00:00.000Z", 100),
+        ]
+    }
+
+    #[test]
+    fn stats() {
+        assert_eq!(
+            CycleStats::new(&Tag::new("v7.1.0-beta.3"), 34, NOW, &posted_builds()),
+            Some(CycleStats {
+                cycle: String::from("7.1"),
+                build_number: 3,
+                first_version: String::from("7.1.0-beta.1"),
+                days_since_first: Some(9),
+                commits_count: 214,
+            })
+        );
+    }
+
+    #[test]
+    fn reposted_build_is_not_counted_twice() {
+        let stats = CycleStats::new(&Tag::new("v7.1.0-beta.2"), 30, NOW, &posted_builds()).unwrap();
+
+        assert_eq!((stats.build_number, stats.commits_count), (2, 180));
+    }
+
+    #[test_case("v7.2.0-beta.1"; "first build of cycle")]
+    #[test_case("latest"; "not a version")]
+    fn no_stats(new_tag: &str) {
+        assert_eq!(
+            CycleStats::new(&Tag::new(new_tag), 10, NOW, &posted_builds()),
+            None
+        );
+    }
+
+    #[test]
+    fn markdown() {
+        let stats = CycleStats::new(&Tag::new("v7.1.0-beta.3"), 34, NOW, &posted_builds()).unwrap();
+
+        assert_str_eq!(
+            stats.markdown(),
+            "3rd build of the 7.1 cycle, 9 days since 7.1.0-beta.1, 214 commits in total"
+        );
+    }
+
+    #[test_case(1, "1st")]
+    #[test_case(2, "2nd")]
+    #[test_case(3, "3rd")]
+    #[test_case(4, "4th")]
+    #[test_case(11, "11th")]
+    #[test_case(12, "12th")]
+    #[test_case(13, "13th")]
+    #[test_case(21, "21st")]
+    #[test_case(102, "102nd")]
+    #[test_case(111, "111th")]
+    fn ordinals(number: usize, result: &str) {
+        assert_str_eq!(ordinal(number), result);
+    }
+
+    #[test_case("1970-01-01T00:00:00.000Z", Some(0); "epoch")]
+    #[test_case("2000-03-01T00:00:00.000Z", Some(11_017); "after leap day")]
+    #[test_case("2023-07-10T23:59:59.999Z", Some(19_548); "end of day")]
+    #[test_case("2023-13-01T00:00:00.000Z", None; "invalid month")]
+    #[test_case("yesterday", None; "not a timestamp")]
+    fn days(timestamp: &str, result: Option<i64>) {
+        assert_eq!(days_since_epoch(timestamp), result);
+    }
+
+    #[test]
+    fn days_across_years() {
+        assert_eq!(
+            days_between("2022-12-30T23:00:00.000Z", "2023-01-02T01:00:00.000Z"),
+            Some(3)
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::core::localization::{Completeness::*, LocalizationChanges};
+
+/// Summary of the localization changes of a whole release cycle, posted in the topic
+/// of the cycle once the next one starts.
+#[derive(Debug)]
+pub struct CycleSummary<'a> {
+    cycle: &'a str,
+    changes: LocalizationChanges<'a>,
+}
+
+impl<'a> CycleSummary<'a> {
+    /// `changes` are the ones accumulated from the last tag of the previous release
+    /// to the last tag posted in `cycle`.
+    pub fn new(cycle: &'a str, changes: LocalizationChanges<'a>) -> Self {
+        Self { cycle, changes }
+    }
+
+    pub fn markdown(&self) -> String {
+        let cycle = self.cycle;
+        let changes = &self.changes;
+
+        let platform = changes.platform;
+        let old_version = changes.old_tag.exact_version_string();
+        let new_version = changes.new_tag.exact_version_string();
+        let comparison_url =
+            platform.github_comparison_url(&changes.old_tag.name, &changes.new_tag.name, None);
+
+        let completeness = match changes.completeness {
+            Complete => "The list is complete.",
+            LikelyComplete => "The list is likely complete.",
+            Incomplete => "The list may be partial.",
+        };
+
+        let completeness = match &changes.completeness_rationale {
+            Some(rationale) => format!("{completeness} {rationale}"),
+            None => completeness.to_string(),
+        };
+
+        format!(
+            "## Localization Changes in {cycle}
+[quote]
+Languages changed during the beta cycle of {cycle}, from {old_version} to {new_version}. {completeness}
+
+{changes}
+---
+Gathered from [signalapp/Signal-{platform}]({comparison_url})
+[/quote]"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+
+    use super::*;
+    use crate::core::{
+        localization::{Completeness, LocalizationChange, StringsFileKind::*},
+        platform::Platform::*,
+        types::github::Tag,
+    };
+
+    #[test]
+    fn complete_summary() {
+        let (old_tag, new_tag) = (Tag::new("v7.0.3"), Tag::new("v7.1.4"));
+
+        let summary = CycleSummary::new(
+            "7.1",
+            LocalizationChanges {
+                platform: Android,
+                old_tag: &old_tag,
+                new_tag: &new_tag,
+                completeness: Completeness::Complete,
+                completeness_rationale: None,
+                unsorted_changes: LocalizationChange::unsorted_changes(vec![
+                    LocalizationChange::test_change("de", vec![Main]),
+                    LocalizationChange::test_change("pt-BR", vec![Main]),
+                ]),
+            },
+        );
+
+        assert_str_eq!(
+            summary.markdown(),
+            "## Localization Changes in 7.1
+[quote]
+Languages changed during the beta cycle of 7.1, from 7.0.3 to 7.1.4. The list is complete.
+
+#### 2 languages changed since 7.0.3:
+- [German (`de`)](https://github.com/signalapp/Signal-Android/compare/v7.0.3..v7.1.4#diff-7049c01dbaca09e41462205bc840bb2bdf6766f0b832e1ff68059c7fdfafadc8) ([file](https://github.com/signalapp/Signal-Android/blob/v7.1.4/app/src/main/res/values-de/strings.xml))
+- [Portuguese (Brazil) (`pt-BR`)](https://github.com/signalapp/Signal-Android/compare/v7.0.3..v7.1.4#diff-33eb97878bc1c93d6b6371526b256ce286ecbff23bda6bb385c80bc5104bf1f4) ([file](https://github.com/signalapp/Signal-Android/blob/v7.1.4/app/src/main/res/values-pt-rBR/strings.xml))
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v7.0.3...v7.1.4)
+[/quote]"
+        );
+    }
+
+    #[test]
+    fn partial_summary() {
+        let (old_tag, new_tag) = (Tag::new("v7.0.0-beta.3"), Tag::new("v7.1.0-beta.2"));
+
+        let summary = CycleSummary::new(
+            "7.1",
+            LocalizationChanges {
+                platform: Desktop,
+                old_tag: &old_tag,
+                new_tag: &new_tag,
+                completeness: Completeness::Incomplete,
+                completeness_rationale: Some(String::from(
+                    "A commit has 3000 changed files, GitHub's limit for a commit, so some may be missing.",
+                )),
+                unsorted_changes: LocalizationChange::unsorted_changes(vec![]),
+            },
+        );
+
+        assert_str_eq!(
+            summary.markdown(),
+            "## Localization Changes in 7.1
+[quote]
+Languages changed during the beta cycle of 7.1, from 7.0.0-beta.3 to 7.1.0-beta.2. The list may be partial. A commit has 3000 changed files, GitHub's limit for a commit, so some may be missing.
+
+#### At least 0 languages changed since 7.0.0-beta.3:
+:warning: For technical reasons, not all languages may be listed below. You can view the full comparison to 7.0.0-beta.3 so far [here](https://github.com/signalapp/Signal-Desktop/compare/v7.0.0-beta.3...v7.1.0-beta.2).
+*No localization changes found*
+---
+Gathered from [signalapp/Signal-Desktop](https://github.com/signalapp/Signal-Desktop/compare/v7.0.0-beta.3...v7.1.0-beta.2)
+[/quote]"
+        );
+    }
+}
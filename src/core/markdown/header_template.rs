@@ -0,0 +1,146 @@
+use anyhow::{bail, Context};
+
+use crate::core::platform::Platform;
+
+/// Names of the placeholders that a header template can have, like `{version}`.
+pub const HEADER_PLACEHOLDERS: &[&str] =
+    &["version", "platform", "commit_count", "previous_version"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Version,
+    Platform,
+    CommitCount,
+    PreviousVersion,
+}
+
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "version" => Some(Self::Version),
+            "platform" => Some(Self::Platform),
+            "commit_count" => Some(Self::CommitCount),
+            "previous_version" => Some(Self::PreviousVersion),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// What the placeholders of a header template are replaced with.
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderValues<'a> {
+    pub version: &'a str,
+    pub platform: Platform,
+    pub commit_count: usize,
+    pub previous_version: &'a str,
+}
+
+/// Wording of the heading of a post, like `{platform} {version} is out`,
+/// with the placeholders in [`HEADER_PLACEHOLDERS`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderTemplate {
+    parts: Vec<Part>,
+}
+
+impl HeaderTemplate {
+    /// Fails for unknown or unclosed placeholders, and for templates of more than one line.
+    pub fn parse(template: &str) -> anyhow::Result<Self> {
+        if template.contains('\n') {
+            bail!("header template must be a single line");
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .with_context(|| format!("unclosed placeholder in header template {template}"))?;
+            let name = &rest[start + 1..end];
+
+            let placeholder = Placeholder::from_name(name).with_context(|| {
+                format!(
+                    "unknown placeholder {{{name}}} in header template, expected one of {}",
+                    HEADER_PLACEHOLDERS.join(", ")
+                )
+            })?;
+            parts.push(Part::Placeholder(placeholder));
+
+            rest = &rest[end + 1..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, values: &HeaderValues) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(Placeholder::Version) => values.version.to_string(),
+                Part::Placeholder(Placeholder::Platform) => values.platform.to_string(),
+                Part::Placeholder(Placeholder::CommitCount) => values.commit_count.to_string(),
+                Part::Placeholder(Placeholder::PreviousVersion) => {
+                    values.previous_version.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::platform::Platform::*;
+
+    const VALUES: HeaderValues = HeaderValues {
+        version: "1.2.4",
+        platform: Android,
+        commit_count: 12,
+        previous_version: "1.2.3",
+    };
+
+    #[test_case("New Version: {version}", "New Version: 1.2.4"; "version")]
+    #[test_case("Signal {platform} {version}", "Signal Android 1.2.4"; "platform")]
+    #[test_case("{version} ({commit_count} commits since {previous_version})", "1.2.4 (12 commits since 1.2.3)"; "all placeholders")]
+    #[test_case("{version}{version}", "1.2.41.2.4"; "adjacent placeholders")]
+    #[test_case("A new build :tada:", "A new build :tada:"; "no placeholders")]
+    #[test_case("Closing } only", "Closing } only"; "closing brace")]
+    #[test_case("", ""; "empty")]
+    fn render(template: &str, result: &str) {
+        assert_eq!(
+            HeaderTemplate::parse(template).unwrap().render(&VALUES),
+            result
+        );
+    }
+
+    #[test_case("{name}", "unknown placeholder {name} in header template, expected one of version, platform, commit_count, previous_version"; "unknown placeholder")]
+    #[test_case("{Version}", "unknown placeholder {Version} in header template, expected one of version, platform, commit_count, previous_version"; "placeholders are case sensitive")]
+    #[test_case("{}", "unknown placeholder {} in header template, expected one of version, platform, commit_count, previous_version"; "empty placeholder")]
+    #[test_case("New {version", "unclosed placeholder in header template New {version"; "unclosed placeholder")]
+    #[test_case("{version}\n[/quote]", "header template must be a single line"; "several lines")]
+    fn parse_fails(template: &str, message: &str) {
+        assert_eq!(
+            HeaderTemplate::parse(template).unwrap_err().to_string(),
+            message
+        );
+    }
+}
@@ -0,0 +1,22 @@
+mod commit;
+mod commit_list_style;
+mod commit_order;
+mod commit_status;
+mod contributors;
+mod cycle_stats;
+mod cycle_summary;
+mod header_template;
+mod post;
+mod post_metadata;
+mod sanitize;
+
+pub use commit::*;
+pub use commit_list_style::*;
+pub use commit_order::*;
+pub use commit_status::*;
+pub use contributors::*;
+pub use cycle_stats::*;
+pub use cycle_summary::*;
+pub use header_template::*;
+pub use post::*;
+pub use post_metadata::*;
@@ -0,0 +1,1654 @@
+use std::collections::HashMap;
+
+use strum::IntoEnumIterator;
+
+use super::{
+    sanitize, Commit, CommitListStyle, CommitOrder, CommitStatus, Contributors, CycleStats,
+    DependencyBump, HeaderTemplate, HeaderValues, PostMetadata, DEFAULT_DEPENDENCY_BUMP_PREFIXES,
+    TABLE_HEADER,
+};
+use crate::core::{
+    error::Error,
+    localization::{
+        LocalizationChangeCollection, LocalizationChangeRenderMode, NewString,
+        DEFAULT_COLLAPSE_THRESHOLD, MAX_NEW_STRINGS_SHOWN,
+    },
+    log::{log_info, log_warn},
+    platform::{prerelease_channel, Platform},
+    types::github::{Release, Tag},
+};
+
+/// Release notes are cut off after this many characters, so that they do not crowd out the commits.
+const MAX_RELEASE_NOTES_CHARS: usize = 5000;
+
+/// Optional additions to a post.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostOptions<'a> {
+    /// Shown right below the heading of the post.
+    pub notice: Option<&'a str>,
+    /// Whether to mark commits with a verified signature.
+    pub show_commit_verification: bool,
+    /// Lists of commits and of localization changes longer than this are collapsed;
+    /// [`DEFAULT_COLLAPSE_THRESHOLD`] if `None`.
+    pub collapse_threshold: Option<usize>,
+    /// Subject prefixes of dependency bumps (see [`Commit::is_dependency_bump`]);
+    /// [`DEFAULT_DEPENDENCY_BUMP_PREFIXES`] if `None`.
+    pub dependency_bump_prefixes: Option<&'a [String]>,
+    /// Whether to list dependency bumps among the other commits,
+    /// instead of summarizing them at the end.
+    pub inline_dependency_bumps: bool,
+    pub commit_list_style: CommitListStyle,
+    pub commit_order: CommitOrder,
+    /// Whether to show where the build stands in its release cycle (see [`Post::with_cycle_stats`]).
+    pub show_cycle_stats: bool,
+    /// Whether to count and list the authors of the commits (see [`Contributors`]).
+    pub show_contributors: bool,
+    /// Wording of the heading, instead of e.g. "New Version: 1.2.4".
+    pub header_template: Option<&'a HeaderTemplate>,
+}
+
+#[derive(Debug)]
+pub struct Post<'a> {
+    platform: Platform,
+    old_tag: &'a Tag,
+    new_tag: &'a Tag,
+    commits: Vec<Commit<'a>>,
+    unfiltered_commits_len: usize,
+    localization_change_collection: LocalizationChangeCollection<'a>,
+    notices: Vec<&'a str>,
+    release: Option<&'a Release>,
+    new_strings: &'a [NewString],
+    show_commit_verification: bool,
+    collapse_threshold: usize,
+    dependency_bump_prefixes: Option<&'a [String]>,
+    summarize_dependency_bumps: bool,
+    commit_list_style: CommitListStyle,
+    commit_order: CommitOrder,
+    show_cycle_stats: bool,
+    cycle_stats: Option<CycleStats>,
+    show_contributors: bool,
+    /// How many commits the comparison listed, and how many GitHub counted in total.
+    github_commits_counts: Option<(usize, usize)>,
+    header_template: Option<&'a HeaderTemplate>,
+}
+
+impl<'a> Post<'a> {
+    pub fn new(
+        platform: Platform,
+        old_tag: &'a Tag,
+        new_tag: &'a Tag,
+        commits: Vec<Commit<'a>>,
+        unfiltered_commits_len: usize,
+        localization_change_collection: LocalizationChangeCollection<'a>,
+    ) -> Self {
+        assert!(commits.len() <= unfiltered_commits_len);
+
+        Self {
+            platform,
+            old_tag,
+            new_tag,
+            commits,
+            unfiltered_commits_len,
+            localization_change_collection,
+            notices: Vec::new(),
+            release: None,
+            new_strings: &[],
+            show_commit_verification: false,
+            collapse_threshold: DEFAULT_COLLAPSE_THRESHOLD,
+            dependency_bump_prefixes: None,
+            summarize_dependency_bumps: true,
+            commit_list_style: CommitListStyle::Bullets,
+            commit_order: CommitOrder::OldestFirst,
+            show_cycle_stats: false,
+            cycle_stats: None,
+            show_contributors: false,
+            github_commits_counts: None,
+            header_template: None,
+        }
+    }
+
+    pub fn with_options(mut self, options: PostOptions<'a>) -> Self {
+        if let Some(notice) = options.notice {
+            self.notices.push(notice);
+        }
+
+        self.show_commit_verification = options.show_commit_verification;
+        self.collapse_threshold = options
+            .collapse_threshold
+            .unwrap_or(DEFAULT_COLLAPSE_THRESHOLD);
+        self.dependency_bump_prefixes = options.dependency_bump_prefixes;
+        self.summarize_dependency_bumps = !options.inline_dependency_bumps;
+        self.commit_list_style = options.commit_list_style;
+        self.commit_order = options.commit_order;
+        self.show_cycle_stats = options.show_cycle_stats;
+        self.show_contributors = options.show_contributors;
+        self.header_template = options.header_template;
+        self
+    }
+
+    /// Adds `notice` right below the heading of the post, after any previously added ones.
+    pub fn with_notice(mut self, notice: &'a str) -> Self {
+        self.notices.push(notice);
+        self
+    }
+
+    /// Adds `cycle_stats` below the heading, if [`PostOptions::show_cycle_stats`] is set.
+    pub fn with_cycle_stats(mut self, cycle_stats: CycleStats) -> Self {
+        self.cycle_stats = Some(cycle_stats);
+        self
+    }
+
+    /// Warns next to the comparison link if the comparison listed fewer (or more) than the
+    /// `total` commits counted by GitHub, as the post then has a best-effort list.
+    pub fn with_github_commits_counts(mut self, listed: usize, total: usize) -> Self {
+        self.github_commits_counts = Some((listed, total));
+        self
+    }
+
+    fn commits_count_warning(&self, comparison_url: &str) -> String {
+        match self.github_commits_counts {
+            Some((listed, total)) if listed != total => format!(
+                "*Note: showing {listed} of {total} commits; see the [full comparison on GitHub]({comparison_url})*\n"
+            ),
+            _ => String::new(),
+        }
+    }
+
+    /// Adds the notes of `release` above the commits, and links to it.
+    pub fn with_release(mut self, release: &'a Release) -> Self {
+        self.release = Some(release);
+        self
+    }
+
+    /// Lists `new_strings` (added to the base strings file) in a collapsed section below the commits.
+    pub fn with_new_strings(mut self, new_strings: &'a [NewString]) -> Self {
+        self.new_strings = new_strings;
+        self
+    }
+
+    fn release_notes_markdown(&self) -> String {
+        match self.release.and_then(|release| release.body.as_deref()) {
+            Some(body) if !body.trim().is_empty() => format!(
+                "[details=\"Release notes\"]\n{}\n[/details]\n",
+                sanitize_release_notes(body)
+            ),
+            _ => String::new(),
+        }
+    }
+
+    /// Whether `commit` is summarized with the other dependency bumps at the end;
+    /// bumps of libsignal are not, so that they stand out.
+    fn is_summarized_dependency_bump(&self, commit: &Commit) -> bool {
+        self.summarize_dependency_bumps
+            && commit.libsignal_bump().is_none()
+            && match self.dependency_bump_prefixes {
+                Some(prefixes) => commit.is_dependency_bump(prefixes),
+                None => commit.is_dependency_bump(DEFAULT_DEPENDENCY_BUMP_PREFIXES),
+            }
+    }
+
+    /// The commits in the order they are listed (and numbered) in.
+    fn ordered_commits(&self) -> Vec<&Commit<'a>> {
+        match self.commit_order {
+            CommitOrder::OldestFirst => self.commits.iter().collect(),
+            CommitOrder::NewestFirst => {
+                // version bumps usually come last, and still read as the end of the list
+                let (version_bumps, commits): (Vec<&Commit>, Vec<&Commit>) = self
+                    .commits
+                    .iter()
+                    .rev()
+                    .partition(|commit| commit.is_version_bump());
+
+                commits
+                    .into_iter()
+                    .chain(version_bumps.into_iter().rev())
+                    .collect()
+            }
+        }
+    }
+
+    fn commits_markdown(&self) -> String {
+        let (commits, dependency_bumps): (Vec<&Commit>, Vec<&Commit>) = self
+            .ordered_commits()
+            .into_iter()
+            .partition(|commit| !self.is_summarized_dependency_bump(commit));
+
+        let mut map = HashMap::new();
+
+        for commit in commits.iter() {
+            if let Some(sha) = commit.reverted_commit_sha() {
+                map.insert(sha, commit.sha());
+            }
+        }
+
+        let reverse_map: HashMap<_, _> = map
+            .iter()
+            .map(|(reverted, reverted_by)| (reverted_by, reverted))
+            .collect();
+
+        // dependency bumps are numbered after the other commits, as they are listed after them
+        let commit_numbers: HashMap<&str, usize> = commits
+            .iter()
+            .chain(&dependency_bumps)
+            .map(|commit| commit.sha())
+            .zip(1..)
+            .collect();
+
+        let rendered_commits = commits
+            .iter()
+            .zip(1..)
+            .map(|(commit, number)| {
+                let status = match (
+                    map.get(commit.sha())
+                        .and_then(|sha| commit_numbers.get(sha) /* there should always be a commit number for this sha, but leaving as is */),
+                    reverse_map
+                        .get(&commit.sha())
+                        .and_then(|&sha| commit_numbers.get(sha)),
+                ) {
+                    (Some(&reverted_by), Some(&reverted)) => CommitStatus::Both {
+                        reverts: reverted,
+                        is_reverted_by: reverted_by,
+                    },
+                    (Some(&reverted_by), None) => CommitStatus::IsRevertedBy(reverted_by),
+                    (None, Some(&reverted)) => CommitStatus::Reverts(reverted),
+                    (None, None) => CommitStatus::Normal,
+                };
+
+                match self.commit_list_style {
+                    CommitListStyle::Bullets => {
+                        commit.markdown_text(number, status, self.show_commit_verification)
+                    }
+                    CommitListStyle::Table => {
+                        commit.table_rows(number, status, self.show_commit_verification)
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let commits_markdown = match self.commit_list_style {
+            CommitListStyle::Bullets => rendered_commits.join("\n"),
+            // a table has to be separated from the line before it
+            CommitListStyle::Table if !rendered_commits.is_empty() => {
+                format!("\n{TABLE_HEADER}{}", rendered_commits.concat())
+            }
+            CommitListStyle::Table => String::new(),
+        };
+
+        match (commits.is_empty(), dependency_bumps.is_empty()) {
+            (_, true) => commits_markdown,
+            (true, false) => dependency_bumps_markdown(&dependency_bumps, commits.len() + 1),
+            (false, false) => format!(
+                "{commits_markdown}\n{}",
+                dependency_bumps_markdown(&dependency_bumps, commits.len() + 1)
+            ),
+        }
+    }
+
+    fn markdown_text(&self, commits_markdown: &str, mode: LocalizationChangeRenderMode) -> String {
+        let old_version = &self.old_tag.exact_version_string();
+        let new_version = &self.new_tag.exact_version_string();
+
+        let platform = self.platform;
+        let availability_notice = platform.availability_notice();
+        let comparison_url =
+            platform.github_comparison_url(&self.old_tag.name, &self.new_tag.name, None);
+
+        let commits_count = self.commits.len();
+        let (commits_prefix, commits_postfix) = if commits_count <= self.collapse_threshold {
+            ("", "")
+        } else {
+            ("[details=\"Show commits\"]\n", "\n[/details]")
+        };
+
+        let commits_word_suffix = if commits_count == 1 { "" } else { "s" };
+
+        let localization_changes_string = self
+            .localization_change_collection
+            .to_string(mode, self.collapse_threshold);
+
+        let difference = self.unfiltered_commits_len - self.commits.len();
+        let filtered_notice = if difference != 0 {
+            let suffix = if difference == 1 { "" } else { "s" };
+            format!(" (+ {} commit{suffix} omitted)", difference)
+        } else {
+            String::new()
+        };
+
+        let notices: String = self
+            .notices
+            .iter()
+            .map(|notice| format!("*{notice}*\n"))
+            .collect();
+
+        let release_notes = self.release_notes_markdown();
+        let release_link = match self.release {
+            Some(release) => format!(" ([release]({}))", release.html_url),
+            None => String::new(),
+        };
+
+        let header = match self.header_template {
+            Some(header_template) => header_template.render(&HeaderValues {
+                version: new_version,
+                platform,
+                commit_count: commits_count,
+                previous_version: old_version,
+            }),
+            None => format!("{}: {new_version}", version_heading(self.new_tag)),
+        };
+        let contributors = match self.show_contributors {
+            true => Contributors::from_commits(&self.commits).markdown(),
+            false => String::new(),
+        };
+        let cycle_stats = match &self.cycle_stats {
+            Some(cycle_stats) if self.show_cycle_stats => format!("*{}*\n", cycle_stats.markdown()),
+            _ => String::new(),
+        };
+        let new_strings = new_strings_markdown(self.new_strings);
+        let commits_count_warning = self.commits_count_warning(&comparison_url);
+
+        let text = format!(
+            "## {header}{availability_notice}
+{cycle_stats}{notices}[quote]
+{release_notes}{commits_count} new commit{commits_word_suffix} since {old_version}{filtered_notice}:
+{commits_prefix}{commits_markdown}{commits_postfix}
+{contributors}---
+{commits_count_warning}Gathered from [signalapp/Signal-{platform}]({comparison_url}){release_link}
+[/quote]
+{new_strings}{localization_changes_string}"
+        );
+
+        // every URL of the templates should be in a link or next to text, see [`sanitize::prevent_oneboxes`]
+        debug_assert!(
+            !sanitize::has_bare_url_line(&text),
+            "post has a bare URL line: {text}"
+        );
+        text
+    }
+
+    /// Metadata of the post, from the same commits and localization changes as its text.
+    pub fn metadata(&self) -> PostMetadata {
+        PostMetadata::new(
+            self.platform,
+            self.old_tag,
+            self.new_tag,
+            self.commits
+                .iter()
+                .map(|commit| commit.sha().to_string())
+                .collect(),
+            self.localization_change_collection
+                .build_changes
+                .unsorted_changes
+                .len(),
+        )
+    }
+
+    /// Renders the post, followed by its metadata (see [`PostMetadata::markdown`]),
+    /// omitting localization changes as necessary to fit within the allowed character count.
+    pub fn markdown(&self) -> anyhow::Result<String> {
+        let commits_markdown = self.commits_markdown();
+        let metadata = self.metadata().markdown();
+
+        for mode in LocalizationChangeRenderMode::iter() {
+            log_info!("trying localization change collection render mode = {mode:?}");
+
+            let text = format!(
+                "{}\n\n{metadata}",
+                self.markdown_text(&commits_markdown, mode)
+            );
+            log_info!("text.len() = {}", text.len());
+
+            if text.len() > 32_000 {
+                log_warn!("text is likely too long to post");
+            } else {
+                return Ok(text);
+            }
+        }
+
+        Err(Error::PostTooLong.into())
+    }
+}
+
+/// Summarizes `dependency_bumps`, numbered from `first_number`, with one line per dependency
+/// from its first old version to its last new version; bumps of unknown dependencies
+/// get a line of their own.
+fn dependency_bumps_markdown(dependency_bumps: &[&Commit], first_number: usize) -> String {
+    // the bump (if known) or subject of each line, with the links to its commits
+    let mut lines: Vec<(Result<DependencyBump, &str>, Vec<String>)> = Vec::new();
+
+    for (commit, number) in dependency_bumps.iter().zip(first_number..) {
+        let link = format!("[[{number}]]({})", commit.commit_url());
+
+        let bump = commit.dependency_bump();
+        let same_dependency = bump.as_ref().and_then(|bump| {
+            lines.iter_mut().find(|(line_bump, _)| {
+                line_bump
+                    .as_ref()
+                    .is_ok_and(|line_bump| line_bump.name == bump.name)
+            })
+        });
+
+        match (bump, same_dependency) {
+            (Some(bump), Some((Ok(line_bump), links))) => {
+                line_bump.to = bump.to;
+                links.push(link);
+            }
+            (bump, _) => lines.push((bump.ok_or(commit.subject()), vec![link])),
+        }
+    }
+
+    let lines: String = lines
+        .iter()
+        .map(|(bump, links)| {
+            let text = match bump {
+                Ok(bump) => format!("{}: {} → {}", bump.name, bump.from, bump.to),
+                Err(subject) => subject.to_string(),
+            };
+
+            format!("\n- {} {}", sanitize::sanitize(&text), links.join(" "))
+        })
+        .collect();
+
+    let commits_word_suffix = if dependency_bumps.len() == 1 { "" } else { "s" };
+
+    format!(
+        "**Dependency updates ({} commit{commits_word_suffix}):**{lines}\n",
+        dependency_bumps.len()
+    )
+}
+
+/// Lists up to [`MAX_NEW_STRINGS_SHOWN`] of `new_strings` in a collapsed section,
+/// or nothing if there are none.
+fn new_strings_markdown(new_strings: &[NewString]) -> String {
+    if new_strings.is_empty() {
+        return String::new();
+    }
+
+    let lines: String = new_strings
+        .iter()
+        .take(MAX_NEW_STRINGS_SHOWN)
+        .map(|string| {
+            format!(
+                "- `{}`: {}\n",
+                sanitize::escape_block_tags(&string.key.replace('`', "")),
+                sanitize::sanitize(&string.value.replace('\n', " "))
+            )
+        })
+        .collect();
+
+    let more = match new_strings.len().saturating_sub(MAX_NEW_STRINGS_SHOWN) {
+        0 => String::new(),
+        more => format!("…and {more} more\n"),
+    };
+
+    format!("[details=\"New strings\"]\n{lines}{more}[/details]\n")
+}
+
+/// "New Version", or e.g. "New Alpha Version" for prereleases of channels other than beta.
+fn version_heading(new_tag: &Tag) -> String {
+    let version = new_tag.to_version().ok();
+
+    match version
+        .as_ref()
+        .and_then(|version| prerelease_channel(&version.pre))
+    {
+        Some(channel) if !channel.eq_ignore_ascii_case("beta") => {
+            format!("New {} Version", channel_name(channel))
+        }
+        _ => String::from("New Version"),
+    }
+}
+
+/// Name of a prerelease channel in a heading: short ones (`rc`) are uppercased,
+/// others (`alpha`) are capitalized.
+fn channel_name(channel: &str) -> String {
+    if channel.len() <= 2 {
+        return channel.to_ascii_uppercase();
+    }
+
+    let mut chars = channel.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Keeps release notes from closing or opening the blocks of the post, and from mentioning users.
+fn sanitize_release_notes(body: &str) -> String {
+    let body = body.replace("\r\n", "\n");
+    let body = body.trim();
+
+    let body = match body.char_indices().nth(MAX_RELEASE_NOTES_CHARS) {
+        Some((index, _)) => format!("{}…", &body[..index]),
+        None => body.to_string(),
+    };
+
+    sanitize::sanitize(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+    use test_case::test_case;
+
+    use super::{super::Author, CommitListStyle::*, *};
+    use crate::core::{
+        localization::{
+            Completeness, LocalizationChange, LocalizationChanges, StringsFileKind::*,
+            UnsortedChanges,
+        },
+        platform::Platform::{self, *},
+        types::github::Comparison,
+    };
+
+    #[test_case(Android, "v1.2.3", "v1.2.4", vec![
+        Commit::new(Android, "Test commit.", "abcdef", false)
+    ], 1, None, "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+1 new commit since 1.2.3:
+- Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"; "Android: one commit")]
+    #[test_case(Android, "v1.2.3", "v1.2.4", vec![
+        Commit::new(Android, "Test commit.", "abcdef", false),
+        Commit::new(Android, "Bump version to 1.2.4", "abc123", false)
+    ], 2, None, "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+2 new commits since 1.2.3:
+- Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Bump version to 1.2.4 [[2]](https://github.com/signalapp/Signal-Android/commit/abc123)
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"; "Android: two commits")]
+    #[test_case(Android, "v1.2.3", "v1.2.4", vec![
+        Commit::new(Android, "Test commit.", "abcdef", false),
+        Commit::new(Android, "Bump version to 1.2.4", "abc123", false)
+    ], 3, None, "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+2 new commits since 1.2.3 (+ 1 commit omitted):
+- Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Bump version to 1.2.4 [[2]](https://github.com/signalapp/Signal-Android/commit/abc123)
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"; "Android: two commits, one omitted")]
+    #[test_case(Android, "v1.2.3", "v1.2.4", vec![
+        Commit::new(Android, "Test commit.", "abcdef", false),
+        Commit::new(Android, "Bump version to 1.2.4", "abc123", false)
+    ], 4, None, "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+2 new commits since 1.2.3 (+ 2 commits omitted):
+- Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Bump version to 1.2.4 [[2]](https://github.com/signalapp/Signal-Android/commit/abc123)
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"; "Android: two commits, two omitted")]
+    #[test_case(Android, "v1.2.3", "v1.2.4", vec![
+        Commit::new(Android, "Test commit.", "abc111", false),
+        Commit::new(Android, "Revert \"Test commit.\".\nThis reverts commit abc111.", "abc222", false),
+        Commit::new(Android, "Revert \"Revert \"Test commit.\".\".\nThis reverts commit abc222.", "abc333", false),
+        Commit::new(Android, "Revert \"Test commit 0.\".\nThis reverts commit abc000.", "abc444", false),
+        Commit::new(Android, "Test commit 2.", "abc555", false),
+    ], 5, None, "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+5 new commits since 1.2.3:
+- <del>Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abc111)</del> (reverted by [2])
+
+- <del>Revert \"Test commit.\". [[2]](https://github.com/signalapp/Signal-Android/commit/abc222)</del> (reverts [1], reverted by [3])
+
+- <ins>Revert \"Revert \"Test commit.\".\". [[3]](https://github.com/signalapp/Signal-Android/commit/abc333)</ins> (reverts [2])
+
+- Revert \"Test commit 0.\". [[4]](https://github.com/signalapp/Signal-Android/commit/abc444)
+
+- Test commit 2. [[5]](https://github.com/signalapp/Signal-Android/commit/abc555)
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"; "Android: five commits with reverts")]
+    #[test_case(Android, "v1.2.3", "v1.2.4",
+    std::iter::repeat(Commit::new(Android, "Test commit.", "abcdef", false))
+        .take(20)
+        .chain(vec![Commit::new(Android, "Bump version to 1.2.4", "abc123", false)].iter().cloned())
+        .collect(),
+    21, None, "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+21 new commits since 1.2.3:
+[details=\"Show commits\"]
+- Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[2]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[3]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[4]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[5]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[6]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[7]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[8]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[9]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[10]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[11]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[12]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[13]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[14]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[15]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[16]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[17]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[18]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[19]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Test commit. [[20]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+- Bump version to 1.2.4 [[21]](https://github.com/signalapp/Signal-Android/commit/abc123)
+
+[/details]
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"; "Android: twenty one commits")]
+    #[test_case(Desktop, "v1.2.3-beta.1", "v1.2.3-beta.2", vec![
+        Commit::new(Desktop, "Test commit.", "abcdef", false)
+    ], 1, None, "## New Version: 1.2.3-beta.2
+[quote]
+1 new commit since 1.2.3-beta.1:
+- Test commit. [[1]](https://github.com/signalapp/Signal-Desktop/commit/abcdef)
+
+---
+Gathered from [signalapp/Signal-Desktop](https://github.com/signalapp/Signal-Desktop/compare/v1.2.3-beta.1...v1.2.3-beta.2)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3-beta.1:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"; "Desktop: one commit")]
+    #[test_case(Android, "v1.2.3", "v1.2.4", vec![
+        Commit::new(Android, "Test commit.", "abcdef", false)
+    ], 1, Some(Completeness::Complete), "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+1 new commit since 1.2.3:
+- Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+2 languages changed since 1.2.3, 3 since 1.1.5.
+
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 2 languages changed since 1.2.3:
+- [English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.2.3..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))
+- [English (United States) (`en-US`)](https://github.com/signalapp/Signal-Android/compare/v1.2.3..v1.2.4#diff-33a220e7f3b2e559ebec12cbf273da0c135bfade5a547e41e2bb5a66d75a01d2) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rUS/strings.xml))
+
+#### 3 languages changed since 1.1.5:
+- [English (`en`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-5e01f7d37a66e4ca03deefc205d8e7008661cdd0284a05aaba1858e6b7bf9103) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values/strings.xml))
+- [English (Canada) (`en-CA`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-11b72e6873b6a42a2a1b92999e4225d7ff2921e323baa5e7b31fdc49471d9724) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rCA/strings.xml))
+- [English (United States) (`en-US`)](https://github.com/signalapp/Signal-Android/compare/v1.1.5..v1.2.4#diff-33a220e7f3b2e559ebec12cbf273da0c135bfade5a547e41e2bb5a66d75a01d2) ([file](https://github.com/signalapp/Signal-Android/blob/v1.2.4/app/src/main/res/values-en-rUS/strings.xml))
+[/quote]
+[/details]"; "Android: one commit with localization changes")]
+    fn post_markdown(
+        platform: Platform,
+        old_tag: &str,
+        new_tag: &str,
+        commits: Vec<Commit>,
+        unfiltered_commits_len: usize,
+        localization_change_collection: Option<Completeness>,
+        result: &str,
+    ) {
+        let older_tag = Tag::new("v1.1.5");
+        let old_tag = Tag::new(old_tag);
+        let new_tag = Tag::new(new_tag);
+
+        let localization_change_collection = match localization_change_collection {
+            Some(completeness) => LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Android,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![
+                        LocalizationChange::test_change("en", vec![Main]),
+                        LocalizationChange::test_change("en-US", vec![Main]),
+                    ]),
+                },
+                release_changes: Some(LocalizationChanges {
+                    platform: Android,
+                    old_tag: &older_tag,
+                    new_tag: &new_tag,
+                    completeness,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![
+                        LocalizationChange::test_change("en", vec![Main]),
+                        LocalizationChange::test_change("en-US", vec![Main]),
+                        LocalizationChange::test_change("en-CA", vec![Main]),
+                    ]),
+                }),
+            },
+            None => LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![]),
+                },
+                release_changes: None,
+            },
+        };
+
+        let post = Post::new(
+            platform,
+            &old_tag,
+            &new_tag,
+            commits,
+            unfiltered_commits_len,
+            localization_change_collection,
+        );
+
+        assert_str_eq!(
+            post.markdown_text(&post.commits_markdown(), LocalizationChangeRenderMode::Full),
+            result
+        );
+    }
+
+    #[test_case(Some((250, 312)), Some("*Note: showing 250 of 312 commits; see the [full comparison on GitHub](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)*"); "more commits than listed")]
+    #[test_case(Some((3, 2)), Some("*Note: showing 3 of 2 commits; see the [full comparison on GitHub](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)*"); "fewer commits than listed")]
+    #[test_case(Some((2, 2)), None; "complete")]
+    #[test_case(None, None; "unknown")]
+    fn commits_count_warning(github_commits_counts: Option<(usize, usize)>, result: Option<&str>) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        let mut post = Post::new(
+            Android,
+            &old_tag,
+            &new_tag,
+            vec![
+                Commit::new(Android, "Test commit.", "abcdef", false),
+                Commit::new(Android, "Another commit.", "fedcba", false),
+            ],
+            2,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Android,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![]),
+                },
+                release_changes: None,
+            },
+        );
+
+        if let Some((listed, total)) = github_commits_counts {
+            post = post.with_github_commits_counts(listed, total);
+        }
+
+        let text = post.markdown_text(&post.commits_markdown(), LocalizationChangeRenderMode::Full);
+        let warning_line = text.lines().find(|line| line.starts_with("*Note: showing"));
+
+        assert_eq!(warning_line, result);
+
+        if let Some(result) = result {
+            assert!(text.contains(&format!("---\n{result}\nGathered from")));
+        }
+    }
+
+    #[test_case(None, "## New Version: 1.2.4"; "default")]
+    #[test_case(Some("Signal {platform} {version}: {commit_count} commits since {previous_version}"), "## Signal Android 1.2.4: 1 commits since 1.2.3"; "template")]
+    fn post_header(template: Option<&str>, result: &str) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+        let release = Release {
+            html_url: String::from(
+                "https://github.com/signalapp/Signal-Android/releases/tag/v1.2.4",
+            ),
+            body: None,
+        };
+        let header_template = template.map(|template| HeaderTemplate::parse(template).unwrap());
+
+        let post = post_with_release(&old_tag, &new_tag, &release).with_options(PostOptions {
+            header_template: header_template.as_ref(),
+            ..Default::default()
+        });
+        let text = post.markdown_text(&post.commits_markdown(), LocalizationChangeRenderMode::Full);
+
+        assert_eq!(text.lines().next(), Some(result));
+        assert!(text.contains(
+            "\n(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)\n[quote]\n1 new commit since 1.2.3:\n"
+        ));
+    }
+
+    #[test]
+    fn post_markdown_with_notice() {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        let post = Post::new(
+            Android,
+            &old_tag,
+            &new_tag,
+            vec![],
+            0,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Android,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![]),
+                },
+                release_changes: None,
+            },
+        )
+        .with_notice("This version was posted retroactively.")
+        .with_notice("Branch history diverged; commit list may be incomplete.");
+
+        assert_str_eq!(
+            post.markdown_text(&post.commits_markdown(), LocalizationChangeRenderMode::Full),
+            "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+*This version was posted retroactively.*
+*Branch history diverged; commit list may be incomplete.*
+[quote]
+0 new commits since 1.2.3:
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"
+        );
+    }
+
+    #[test_case(None, false; "default threshold")]
+    #[test_case(Some(2), false; "at threshold")]
+    #[test_case(Some(1), true; "over threshold")]
+    fn post_markdown_with_collapse_threshold(collapse_threshold: Option<usize>, collapsed: bool) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        let post = Post::new(
+            Android,
+            &old_tag,
+            &new_tag,
+            vec![
+                Commit::new(Android, "First commit.", "abcdef", false),
+                Commit::new(Android, "Second commit.", "fedcba", false),
+            ],
+            2,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Android,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![
+                        LocalizationChange::test_change("en", vec![Main]),
+                        LocalizationChange::test_change("en-US", vec![Main]),
+                    ]),
+                },
+                release_changes: None,
+            },
+        )
+        .with_options(PostOptions {
+            collapse_threshold,
+            ..Default::default()
+        });
+
+        let text = post.markdown_text(&post.commits_markdown(), LocalizationChangeRenderMode::Full);
+
+        assert_eq!(text.contains("[details=\"Show commits\"]"), collapsed);
+        assert_eq!(text.contains("[details=\"Show changes\"]"), collapsed);
+    }
+
+    fn post_with_release<'a>(old_tag: &'a Tag, new_tag: &'a Tag, release: &'a Release) -> Post<'a> {
+        Post::new(
+            Android,
+            old_tag,
+            new_tag,
+            vec![Commit::new(Android, "Test commit.", "abcdef", false)],
+            1,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Android,
+                    old_tag,
+                    new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![]),
+                },
+                release_changes: None,
+            },
+        )
+        .with_release(release)
+    }
+
+    #[test]
+    fn post_markdown_with_release_notes() {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+        let release = Release {
+            html_url: String::from(
+                "https://github.com/signalapp/Signal-Android/releases/tag/v1.2.4",
+            ),
+            body: Some(String::from("Fixed a crash.\r\nThanks @contributor!\r\n")),
+        };
+
+        let post = post_with_release(&old_tag, &new_tag, &release);
+
+        assert_str_eq!(
+            post.markdown_text(&post.commits_markdown(), LocalizationChangeRenderMode::Full),
+            "## New Version: 1.2.4
+(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)
+[quote]
+[details=\"Release notes\"]
+Fixed a crash.
+Thanks `@contributor`!
+[/details]
+1 new commit since 1.2.3:
+- Test commit. [[1]](https://github.com/signalapp/Signal-Android/commit/abcdef)
+
+---
+Gathered from [signalapp/Signal-Android](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4) ([release](https://github.com/signalapp/Signal-Android/releases/tag/v1.2.4))
+[/quote]
+[details=\"Localization changes\"]
+[quote]
+Note: after clicking a link, it may take a few seconds before GitHub jumps to the file (try scrolling a bit if it doesn't).
+
+#### 0 languages changed since 1.2.3:
+*No localization changes found*
+
+Localization changes for the release are the same, as this is the first build of the release.
+[/quote]
+[/details]"
+        );
+    }
+
+    #[test_case(Android; "Android")]
+    #[test_case(Ios; "iOS")]
+    #[test_case(Desktop; "Desktop")]
+    fn post_markdown_has_no_bare_urls_and_has_metadata(platform: Platform) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+        let release = Release {
+            html_url: platform.github_comparison_url("v1.2.3", "v1.2.4", None),
+            body: Some(platform.github_commit_url("abcdef")),
+        };
+        let full_message = format!("Test commit.\n{}", platform.github_commit_url("fedcba"));
+
+        let post = Post::new(
+            platform,
+            &old_tag,
+            &new_tag,
+            vec![Commit::new(platform, &full_message, "abcdef", false)],
+            2,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Incomplete,
+                    completeness_rationale: None,
+                    unsorted_changes: LocalizationChange::unsorted_changes(vec![
+                        LocalizationChange::test_change("de", vec![Main]),
+                    ]),
+                },
+                release_changes: None,
+            },
+        )
+        .with_release(&release)
+        .with_github_commits_counts(1, 2);
+
+        let markdown = post.markdown().unwrap();
+
+        assert!(!sanitize::has_bare_url_line(&markdown));
+        assert_eq!(
+            PostMetadata::from_markdown(&markdown),
+            Some(post.metadata())
+        );
+    }
+
+    #[test_case(None; "no notes")]
+    #[test_case(Some("  \r\n "); "empty notes")]
+    fn post_markdown_with_release_without_notes(body: Option<&str>) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+        let release = Release {
+            html_url: String::from(
+                "https://github.com/signalapp/Signal-Android/releases/tag/v1.2.4",
+            ),
+            body: body.map(String::from),
+        };
+
+        let post = post_with_release(&old_tag, &new_tag, &release);
+        let markdown =
+            post.markdown_text(&post.commits_markdown(), LocalizationChangeRenderMode::Full);
+
+        assert!(!markdown.contains("Release notes"));
+        assert!(markdown.contains("[quote]\n1 new commit since 1.2.3:\n"));
+        assert!(markdown.contains("/compare/v1.2.3...v1.2.4) ([release](https://github.com/signalapp/Signal-Android/releases/tag/v1.2.4))\n"));
+    }
+
+    fn desktop_post<'a>(old_tag: &'a Tag, new_tag: &'a Tag, commits: Vec<Commit<'a>>) -> Post<'a> {
+        let commits_len = commits.len();
+
+        Post::new(
+            Desktop,
+            old_tag,
+            new_tag,
+            commits,
+            commits_len,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Desktop,
+                    old_tag,
+                    new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: UnsortedChanges::default(),
+                },
+                release_changes: None,
+            },
+        )
+    }
+
+    #[test_case(true, true; "shown")]
+    #[test_case(false, false; "not shown")]
+    fn post_markdown_with_cycle_stats(show_cycle_stats: bool, shown: bool) {
+        let old_tag = Tag::new("v1.2.0-beta.1");
+        let new_tag = Tag::new("v1.2.0-beta.2");
+
+        let post = desktop_post(
+            &old_tag,
+            &new_tag,
+            vec![Commit::new(Desktop, "Test commit.", "abcdef", false)],
+        )
+        .with_options(PostOptions {
+            show_cycle_stats,
+            ..Default::default()
+        })
+        .with_cycle_stats(CycleStats {
+            cycle: String::from("1.2"),
+            build_number: 2,
+            first_version: String::from("1.2.0-beta.1"),
+            days_since_first: Some(3),
+            commits_count: 12,
+        });
+
+        assert_eq!(
+            post.markdown().unwrap().starts_with(
+                "## New Version: 1.2.0-beta.2\n*2nd build of the 1.2 cycle, 3 days since 1.2.0-beta.1, 12 commits in total*\n[quote]\n"
+            ),
+            shown
+        );
+    }
+
+    #[test_case(true, true; "shown")]
+    #[test_case(false, false; "not shown")]
+    fn post_markdown_with_contributors(show_contributors: bool, shown: bool) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        let author = Author {
+            login: Some("jdoe-signal"),
+            name: Some("Jane Doe"),
+            email: Some("jane@signal.org"),
+        };
+
+        let post = desktop_post(
+            &old_tag,
+            &new_tag,
+            vec![Commit::new(Desktop, "Test commit.", "abcdef", false).with_author(author)],
+        )
+        .with_options(PostOptions {
+            show_contributors,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            post.markdown().unwrap().contains(
+                "/commit/abcdef)\n\nChanges by 1 contributor\n[details=\"Contributors\"]\njdoe-signal\n[/details]\n---\n"
+            ),
+            shown
+        );
+    }
+
+    fn commits_with_revert_and_version_bump() -> Vec<Commit<'static>> {
+        vec![
+            Commit::new(Desktop, "First.", "abc101", false),
+            Commit::new(Desktop, "Second.", "abc102", false),
+            Commit::new(
+                Desktop,
+                "Revert \"First.\"\nThis reverts commit abc101.",
+                "abc103",
+                false,
+            ),
+            Commit::new(Desktop, "Bump version to 1.2.4", "abc104", false),
+        ]
+    }
+
+    #[test_case(CommitOrder::OldestFirst, "- <del>First. [[1]](https://github.com/signalapp/Signal-Desktop/commit/abc101)</del> (reverted by [3])
+
+- Second. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abc102)
+
+- <ins>Revert \"First.\" [[3]](https://github.com/signalapp/Signal-Desktop/commit/abc103)</ins> (reverts [1])
+
+- Bump version to 1.2.4 [[4]](https://github.com/signalapp/Signal-Desktop/commit/abc104)
+"; "oldest first")]
+    #[test_case(CommitOrder::NewestFirst, "- <ins>Revert \"First.\" [[1]](https://github.com/signalapp/Signal-Desktop/commit/abc103)</ins> (reverts [3])
+
+- Second. [[2]](https://github.com/signalapp/Signal-Desktop/commit/abc102)
+
+- <del>First. [[3]](https://github.com/signalapp/Signal-Desktop/commit/abc101)</del> (reverted by [1])
+
+- Bump version to 1.2.4 [[4]](https://github.com/signalapp/Signal-Desktop/commit/abc104)
+"; "newest first")]
+    fn commit_order(commit_order: CommitOrder, result: &str) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        let post = desktop_post(&old_tag, &new_tag, commits_with_revert_and_version_bump())
+            .with_options(PostOptions {
+                commit_order,
+                ..Default::default()
+            });
+
+        assert_str_eq!(post.commits_markdown(), result);
+    }
+
+    #[test_case(CommitOrder::OldestFirst, CommitListStyle::Bullets; "oldest first, bullets")]
+    #[test_case(CommitOrder::NewestFirst, CommitListStyle::Bullets; "newest first, bullets")]
+    #[test_case(CommitOrder::OldestFirst, CommitListStyle::Table; "oldest first, table")]
+    #[test_case(CommitOrder::NewestFirst, CommitListStyle::Table; "newest first, table")]
+    fn commit_numbers_follow_displayed_order(
+        commit_order: CommitOrder,
+        commit_list_style: CommitListStyle,
+    ) {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+
+        let post = desktop_post(&old_tag, &new_tag, commits_with_revert_and_version_bump())
+            .with_options(PostOptions {
+                commit_order,
+                commit_list_style,
+                ..Default::default()
+            });
+
+        let commits_markdown = post.commits_markdown();
+        let links: Vec<&str> = commits_markdown
+            .lines()
+            .filter_map(|line| line.split("[[").nth(1))
+            .collect();
+
+        assert_eq!(links.len(), 4);
+
+        for (link, number) in links.iter().zip(1..) {
+            assert!(
+                link.starts_with(&format!("{number}]]")),
+                "{link} is not number {number}"
+            );
+        }
+
+        assert!(
+            links[3].contains("/commit/abc104)"),
+            "version bump is not last"
+        );
+    }
+
+    fn commits_with_dependency_bumps() -> Vec<Commit<'static>> {
+        vec![
+            Commit::new(
+                Desktop,
+                "chore(deps): bump electron from 25.0.0 to 25.1.0",
+                "abc001",
+                false,
+            ),
+            Commit::new(Desktop, "Test commit.", "abc002", false),
+            Commit::new(
+                Desktop,
+                "chore(deps-dev): bump @types/node from 18.0.0 to 18.1.0 (#123)",
+                "abc003",
+                false,
+            ),
+            Commit::new(
+                Desktop,
+                "chore(deps): bump electron from 25.1.0 to 25.2.0",
+                "abc004",
+                false,
+            ),
+            Commit::new(
+                Desktop,
+                "chore(deps): bump the npm group with 3 updates",
+                "abc005",
+                false,
+            ),
+            Commit::new(
+                Desktop,
+                "Revert \"Test commit.\"\nThis reverts commit abc002.",
+                "abc006",
+                false,
+            ),
+        ]
+    }
+
+    #[test]
+    fn dependency_bumps_are_summarized() {
+        let old_tag = Tag::new("v1.2.3-beta.1");
+        let new_tag = Tag::new("v1.2.3-beta.2");
+
+        let post = desktop_post(&old_tag, &new_tag, commits_with_dependency_bumps());
+
+        assert_str_eq!(
+            post.commits_markdown(),
+            "- <del>Test commit. [[1]](https://github.com/signalapp/Signal-Desktop/commit/abc002)</del> (reverted by [2])
+
+- <ins>Revert \"Test commit.\" [[2]](https://github.com/signalapp/Signal-Desktop/commit/abc006)</ins> (reverts [1])
+
+**Dependency updates (4 commits):**
+- electron: 25.0.0 → 25.2.0 [[3]](https://github.com/signalapp/Signal-Desktop/commit/abc001) [[5]](https://github.com/signalapp/Signal-Desktop/commit/abc004)
+- `@types`/node: 18.0.0 → 18.1.0 [[4]](https://github.com/signalapp/Signal-Desktop/commit/abc003)
+- chore(deps): bump the npm group with 3 updates [[6]](https://github.com/signalapp/Signal-Desktop/commit/abc005)
+"
+        );
+        assert!(post
+            .markdown()
+            .unwrap()
+            .contains("[quote]\n6 new commits since 1.2.3-beta.1:\n"));
+    }
+
+    #[test]
+    fn only_dependency_bumps() {
+        let old_tag = Tag::new("v1.2.3-beta.1");
+        let new_tag = Tag::new("v1.2.3-beta.2");
+
+        let post = desktop_post(
+            &old_tag,
+            &new_tag,
+            vec![Commit::new(
+                Desktop,
+                "chore(deps): bump electron from 25.0.0 to 25.1.0",
+                "abc001",
+                false,
+            )],
+        );
+
+        assert_str_eq!(
+            post.commits_markdown(),
+            "**Dependency updates (1 commit):**
+- electron: 25.0.0 → 25.1.0 [[1]](https://github.com/signalapp/Signal-Desktop/commit/abc001)
+"
+        );
+    }
+
+    #[test]
+    fn inline_dependency_bumps() {
+        let old_tag = Tag::new("v1.2.3-beta.1");
+        let new_tag = Tag::new("v1.2.3-beta.2");
+
+        let post = desktop_post(&old_tag, &new_tag, commits_with_dependency_bumps()).with_options(
+            PostOptions {
+                inline_dependency_bumps: true,
+                ..Default::default()
+            },
+        );
+
+        let markdown = post.commits_markdown();
+
+        assert!(!markdown.contains("Dependency updates"));
+        assert!(markdown.starts_with("- chore(deps): bump electron from 25.0.0 to 25.1.0 [[1]]"));
+    }
+
+    #[test]
+    fn dependency_bumps_with_configured_prefixes() {
+        let old_tag = Tag::new("v1.2.3-beta.1");
+        let new_tag = Tag::new("v1.2.3-beta.2");
+        let prefixes = vec![String::from("deps:")];
+
+        let post = desktop_post(
+            &old_tag,
+            &new_tag,
+            vec![
+                Commit::new(
+                    Desktop,
+                    "deps: bump zod from 3.21.0 to 3.22.0",
+                    "abc001",
+                    false,
+                ),
+                Commit::new(
+                    Desktop,
+                    "chore(deps): bump electron from 25.0.0 to 25.1.0",
+                    "abc002",
+                    false,
+                ),
+            ],
+        )
+        .with_options(PostOptions {
+            dependency_bump_prefixes: Some(&prefixes),
+            ..Default::default()
+        });
+
+        assert_str_eq!(
+            post.commits_markdown(),
+            "- chore(deps): bump electron from 25.0.0 to 25.1.0 [[1]](https://github.com/signalapp/Signal-Desktop/commit/abc002)
+
+**Dependency updates (1 commit):**
+- zod: 3.21.0 → 3.22.0 [[2]](https://github.com/signalapp/Signal-Desktop/commit/abc001)
+"
+        );
+    }
+
+    #[test_case("v1.2.4", "New Version"; "release")]
+    #[test_case("v1.2.4-beta.1", "New Version"; "beta")]
+    #[test_case("1.2.4.5-beta", "New Version"; "beta with build")]
+    #[test_case("v1.2.4-alpha.1", "New Alpha Version"; "alpha")]
+    #[test_case("v1.2.4-rc.2", "New RC Version"; "rc")]
+    #[test_case("v1.2.4-nightly1", "New Nightly Version"; "number right after channel")]
+    #[test_case("latest", "New Version"; "not a version")]
+    fn heading(new_tag: &str, result: &str) {
+        assert_str_eq!(version_heading(&Tag::new(new_tag)), result);
+    }
+
+    #[test]
+    fn alpha_post_markdown() {
+        let old_tag = Tag::new("v1.2.4-beta.2");
+        let new_tag = Tag::new("v1.2.4-alpha.1");
+
+        let post = Post::new(
+            Desktop,
+            &old_tag,
+            &new_tag,
+            vec![Commit::new(Desktop, "Test commit.", "abcdef", false)],
+            1,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Desktop,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: UnsortedChanges::default(),
+                },
+                release_changes: None,
+            },
+        );
+
+        assert!(post
+            .markdown()
+            .unwrap()
+            .starts_with("## New Alpha Version: 1.2.4-alpha.1\n"));
+    }
+
+    fn new_strings(len: usize) -> Vec<NewString> {
+        (1..=len)
+            .map(|i| NewString {
+                key: format!("String_{i}"),
+                value: format!("Value {i}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn new_strings_markdown_is_escaped() {
+        let new_strings = vec![NewString {
+            key: String::from("icu:Quote`[/quote]"),
+            value: String::from("Ask @user to [/details]\nnow"),
+        }];
+
+        assert_str_eq!(
+            super::new_strings_markdown(&new_strings),
+            r#"[details="New strings"]
+- `icu:Quote\[/quote]`: Ask `@user` to \[/details] now
+[/details]
+"#
+        );
+    }
+
+    #[test_case(0, ""; "none")]
+    #[test_case(2, "[details=\"New strings\"]\n- `String_1`: Value 1\n- `String_2`: Value 2\n[/details]\n"; "few")]
+    fn new_strings_markdown(len: usize, result: &str) {
+        assert_str_eq!(super::new_strings_markdown(&new_strings(len)), result);
+    }
+
+    #[test]
+    fn new_strings_markdown_is_capped() {
+        let text = super::new_strings_markdown(&new_strings(MAX_NEW_STRINGS_SHOWN + 3));
+
+        assert!(text.contains(&format!("- `String_{MAX_NEW_STRINGS_SHOWN}`:")));
+        assert!(!text.contains(&format!("- `String_{}`:", MAX_NEW_STRINGS_SHOWN + 1)));
+        assert!(text.ends_with("…and 3 more\n[/details]\n"));
+    }
+
+    #[test]
+    fn post_markdown_with_new_strings() {
+        let old_tag = Tag::new("v1.2.3");
+        let new_tag = Tag::new("v1.2.4");
+        let new_strings = new_strings(1);
+
+        let post = Post::new(
+            Desktop,
+            &old_tag,
+            &new_tag,
+            vec![Commit::new(Desktop, "Test commit.", "abcdef", false)],
+            1,
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges {
+                    platform: Desktop,
+                    old_tag: &old_tag,
+                    new_tag: &new_tag,
+                    completeness: Completeness::Complete,
+                    completeness_rationale: None,
+                    unsorted_changes: UnsortedChanges::default(),
+                },
+                release_changes: None,
+            },
+        )
+        .with_new_strings(&new_strings);
+
+        assert!(post
+            .markdown()
+            .unwrap()
+            .contains("[/quote]\n[details=\"New strings\"]\n- `String_1`: Value 1\n[/details]\n"));
+    }
+
+    #[test_case("Closes the quote early [/quote] and [QUOTE]", r"Closes the quote early \[/quote] and \[QUOTE]"; "quote")]
+    #[test_case("[/details]\n[details=\"Other\"]", "\\[/details]\n\\[details=\"Other\"]"; "details")]
+    #[test_case("[Link](https://signal.org)", "[Link](https://signal.org)"; "link")]
+    #[test_case("Full changelog:\nhttps://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4", "Full changelog:\n[https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4](https://github.com/signalapp/Signal-Android/compare/v1.2.3...v1.2.4)"; "bare link")]
+    fn sanitized_release_notes(body: &str, result: &str) {
+        assert_str_eq!(sanitize_release_notes(body), result);
+    }
+
+    #[test]
+    fn long_release_notes() {
+        let body = "a".repeat(MAX_RELEASE_NOTES_CHARS + 1);
+
+        assert_str_eq!(
+            sanitize_release_notes(&body),
+            format!("{}…", "a".repeat(MAX_RELEASE_NOTES_CHARS))
+        );
+    }
+
+    /// Builds a post from the comparison in `tests/fixtures/{name}.json` (see
+    /// `tests/fixtures/record.sh`) the same way as for posting, for the first build of a release.
+    fn snapshot_markdown(
+        platform: Platform,
+        old_tag: &Tag,
+        new_tag: &Tag,
+        fixture: &str,
+        commit_list_style: CommitListStyle,
+    ) -> String {
+        let path = format!(
+            "{}/tests/fixtures/{fixture}.json",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let comparison: Comparison =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let commits: Vec<Commit> = comparison
+            .commits
+            .iter()
+            .map(|github_commit| Commit::from_github_commit(platform, github_commit))
+            .filter(|commit| platform.should_show_commit(commit.full_message()))
+            .collect();
+
+        Post::new(
+            platform,
+            old_tag,
+            new_tag,
+            commits,
+            comparison.new_commits_len(),
+            LocalizationChangeCollection {
+                build_changes: LocalizationChanges::from_comparison(
+                    platform,
+                    old_tag,
+                    new_tag,
+                    &comparison,
+                ),
+                release_changes: None,
+            },
+        )
+        .with_options(PostOptions {
+            show_commit_verification: true,
+            commit_list_style,
+            ..Default::default()
+        })
+        .markdown()
+        .unwrap()
+    }
+
+    /// Compares the rendered post of `tests/fixtures/{fixture}.json` with `tests/snapshots/{name}.md`;
+    /// run with `UPDATE_SNAPSHOTS=1` to write the rendered post there instead.
+    #[test_case(Android, "v6.30.1", "v6.30.2", "android_small", Bullets, "android_small"; "Android: small release")]
+    #[test_case(Desktop, "v6.40.0-beta.1", "v6.40.0-beta.2", "desktop_large", Bullets, "desktop_large"; "Desktop: over 100 commits")]
+    #[test_case(Ios, "6.45.0.2", "6.45.0.3", "ios_localization", Bullets, "ios_localization"; "iOS: localization changes")]
+    #[test_case(Android, "v6.30.1", "v6.30.2", "android_small", Table, "android_small_table"; "Android: small release, table")]
+    #[test_case(Desktop, "v6.40.0-beta.1", "v6.40.0-beta.2", "desktop_large", Table, "desktop_large_table"; "Desktop: over 100 commits, table")]
+    fn post_markdown_snapshot(
+        platform: Platform,
+        old: &str,
+        new: &str,
+        fixture: &str,
+        commit_list_style: CommitListStyle,
+        name: &str,
+    ) {
+        let (old_tag, new_tag) = (Tag::new(old), Tag::new(new));
+        let markdown = snapshot_markdown(platform, &old_tag, &new_tag, fixture, commit_list_style);
+
+        let path = format!("{}/tests/snapshots/{name}.md", env!("CARGO_MANIFEST_DIR"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, &markdown).unwrap();
+        }
+
+        assert_str_eq!(
+            markdown,
+            std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("could not read {path}: {e}"))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::{platform::Platform, types::github::Tag};
+
+/// Marks the metadata comment of a post, see [`PostMetadata::markdown`].
+const METADATA_MARKER: &str = "signalupdates-bot metadata";
+
+/// The metadata comment is kept within this many bytes by leaving out commits.
+pub const MAX_METADATA_LEN: usize = 8192;
+
+/// Bumped whenever [`PostMetadata`] changes in a way that breaks its readers.
+pub const POST_METADATA_SCHEMA_VERSION: u32 = 1;
+
+/// What a post is about, for tools that read posts instead of people.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostMetadata {
+    pub schema_version: u32,
+    /// Version of the bot that made the post.
+    pub bot_version: String,
+    /// Lowercase, e.g. `android`.
+    pub platform: String,
+    pub old_tag: String,
+    pub new_tag: String,
+    /// Number of commits listed in the post, which `commits` may leave some out of.
+    pub commits_count: usize,
+    /// Full SHAs of the commits listed in the post, in the order of the comparison.
+    pub commits: Vec<String>,
+    /// Whether `commits` leaves out some commits to stay within [`MAX_METADATA_LEN`].
+    #[serde(default)]
+    pub commits_truncated: bool,
+    /// Number of languages changed in the build.
+    pub localization_changes_count: usize,
+}
+
+impl PostMetadata {
+    pub fn new(
+        platform: Platform,
+        old_tag: &Tag,
+        new_tag: &Tag,
+        commits: Vec<String>,
+        localization_changes_count: usize,
+    ) -> Self {
+        Self {
+            schema_version: POST_METADATA_SCHEMA_VERSION,
+            bot_version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: platform.to_string().to_ascii_lowercase(),
+            old_tag: old_tag.name.clone(),
+            new_tag: new_tag.name.clone(),
+            commits_count: commits.len(),
+            commits,
+            commits_truncated: false,
+            localization_changes_count,
+        }
+    }
+
+    /// HTML comment with the metadata as JSON, which is hidden when the post is shown.
+    ///
+    /// Commits are left out from the end as necessary to stay within [`MAX_METADATA_LEN`].
+    pub fn markdown(&self) -> String {
+        let mut metadata = self.clone();
+
+        loop {
+            let comment = metadata.comment();
+
+            if comment.len() <= MAX_METADATA_LEN || metadata.commits.is_empty() {
+                return comment;
+            }
+
+            // each commit takes its SHA, quotes and a comma
+            let excess_commits = (comment.len() - MAX_METADATA_LEN)
+                .div_ceil(metadata.commits[0].len() + 3)
+                .min(metadata.commits.len());
+
+            metadata
+                .commits
+                .truncate(metadata.commits.len() - excess_commits);
+            metadata.commits_truncated = true;
+        }
+    }
+
+    fn comment(&self) -> String {
+        let json = serde_json::to_string(self).expect("metadata should be serializable");
+
+        // `>` is only possible within strings, where it can be escaped, so the comment is never closed early
+        format!(
+            "<!-- {METADATA_MARKER} {} -->",
+            json.replace('>', r"\u003e")
+        )
+    }
+
+    /// Finds the metadata in the markdown of a post, as made by [`PostMetadata::markdown`].
+    pub fn from_markdown(markdown: &str) -> Option<Self> {
+        let start = markdown.find(&format!("<!-- {METADATA_MARKER} "))? + METADATA_MARKER.len() + 6;
+        let end = start + markdown[start..].find(" -->")?;
+
+        serde_json::from_str(&markdown[start..end]).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::platform::Platform::*;
+
+    fn metadata(commits_len: usize) -> PostMetadata {
+        PostMetadata::new(
+            Android,
+            &Tag::new("v1.2.3"),
+            &Tag::new("v1.2.4"),
+            (0..commits_len).map(|i| format!("{i:040x}")).collect(),
+            2,
+        )
+    }
+
+    #[test]
+    fn markdown() {
+        assert_str_eq!(
+            metadata(1).markdown(),
+            format!(
+                r#"<!-- signalupdates-bot metadata {{"schema_version":1,"bot_version":"{}","platform":"android","old_tag":"v1.2.3","new_tag":"v1.2.4","commits_count":1,"commits":["0000000000000000000000000000000000000000"],"commits_truncated":false,"localization_changes_count":2}} -->"#,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test_case(0; "no commits")]
+    #[test_case(3; "some commits")]
+    fn round_trip(commits_len: usize) {
+        let metadata = metadata(commits_len);
+        let markdown = format!(
+            "## New Version: 1.2.4\n[quote]\n[/quote]\n\n{}",
+            metadata.markdown()
+        );
+
+        assert_eq!(PostMetadata::from_markdown(&markdown), Some(metadata));
+    }
+
+    #[test]
+    fn closing_comment_is_escaped() {
+        let metadata = PostMetadata::new(
+            Desktop,
+            &Tag::new("v1.2.3-->"),
+            &Tag::new("v1.2.4"),
+            Vec::new(),
+            0,
+        );
+        let markdown = metadata.markdown();
+
+        assert_eq!(markdown.matches("-->").count(), 1);
+        assert_eq!(PostMetadata::from_markdown(&markdown), Some(metadata));
+    }
+
+    #[test_case(100; "under the cap")]
+    #[test_case(250; "over the cap")]
+    #[test_case(5000; "far over the cap")]
+    fn size_is_capped(commits_len: usize) {
+        let metadata = metadata(commits_len);
+        let markdown = metadata.markdown();
+
+        assert!(markdown.len() <= MAX_METADATA_LEN);
+
+        let parsed = PostMetadata::from_markdown(&markdown).unwrap();
+        assert_eq!(parsed.commits_count, commits_len);
+        assert_eq!(parsed.commits_truncated, parsed.commits.len() < commits_len);
+        assert_eq!(parsed.commits, metadata.commits[..parsed.commits.len()]);
+        assert!(parsed.commits.len() >= commits_len.min(150));
+    }
+
+    #[test]
+    fn not_found() {
+        assert_eq!(PostMetadata::from_markdown("## New Version: 1.2.4"), None);
+    }
+}
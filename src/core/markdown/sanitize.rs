@@ -0,0 +1,190 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+#[cfg(test)]
+use proptest::prelude::*;
+use regex::Regex;
+
+lazy_static! {
+    static ref BLOCK_TAG_REGEX: Regex = Regex::new(r"(?i)(\\*)\[(/?(?:details|quote))").unwrap();
+    static ref MENTION_REGEX: Regex = Regex::new(r"@([a-zA-Z0-9_-]+)").unwrap();
+    /// A line with nothing but a URL (possibly in angle brackets), which Discourse turns into a onebox.
+    static ref BARE_URL_LINE_REGEX: Regex =
+        Regex::new(r"(?m)^([ \t]*)<?(https?://[^\s<>]+)>?[ \t]*$").unwrap();
+}
+
+/// Keeps `text` from closing or opening the blocks of the post.
+///
+/// Backslashes right before a tag are escaped too, as they would otherwise escape
+/// the backslash that escapes the tag.
+pub fn escape_block_tags(text: &str) -> Cow<'_, str> {
+    BLOCK_TAG_REGEX.replace_all(text, r"${1}${1}\[$2")
+}
+
+/// Keeps `text` from mentioning (and notifying) users.
+pub fn defang_mentions(text: &str) -> Cow<'_, str> {
+    MENTION_REGEX.replace_all(text, "`@$1`")
+}
+
+/// Keeps the URLs alone on a line of `text` from being expanded into oneboxes,
+/// by making them explicit links.
+pub fn prevent_oneboxes(text: &str) -> Cow<'_, str> {
+    BARE_URL_LINE_REGEX.replace_all(text, "$1[$2]($2)")
+}
+
+/// Whether a line of `text` is a bare URL, which Discourse would expand into a onebox.
+pub fn has_bare_url_line(text: &str) -> bool {
+    BARE_URL_LINE_REGEX.is_match(text)
+}
+
+/// Escapes block tags, defangs mentions and prevents oneboxes in `text`.
+pub fn sanitize(text: &str) -> String {
+    prevent_oneboxes(&defang_mentions(&escape_block_tags(text))).into_owned()
+}
+
+/// Whether a line (after the first one) of a commit message is not worth showing.
+fn is_stripped_line(line: &str) -> bool {
+    let lowercase = line.to_lowercase();
+    lowercase.contains("co-authored-by") || lowercase.contains("this reverts commit")
+}
+
+/// Sanitized lines of a commit message, without co-author trailers and revert notes;
+/// the first line is always kept.
+pub fn commit_message_lines(full_message: &str) -> Vec<String> {
+    let mut lines = full_message.split('\n');
+
+    lines
+        .next()
+        .into_iter()
+        .chain(lines.filter(|line| !is_stripped_line(line)))
+        .map(sanitize)
+        .collect()
+}
+
+/// Fragments that exercise the sanitization rules, mixed with arbitrary text.
+#[cfg(test)]
+fn fragment() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<String>(),
+        Just(String::from("[/quote]")),
+        Just(String::from("[/QUOTE]")),
+        Just(String::from("[quote=\"user\"]")),
+        Just(String::from("[/details]")),
+        Just(String::from("\\")),
+        Just(String::from("co-authored-by")),
+        Just(String::from("This reverts commit ")),
+        Just(String::from("@user")),
+        Just(String::from(
+            "https://github.com/signalapp/Signal-Android/pull/1"
+        )),
+        Just(String::from("\n")),
+    ]
+}
+
+/// Arbitrary multi-line commit messages, see [`fragment`].
+#[cfg(test)]
+pub fn message() -> impl Strategy<Value = String> {
+    prop::collection::vec(fragment(), 0..12).prop_map(|fragments| fragments.concat())
+}
+
+/// Whether every closing quote tag in `text` is escaped, i.e. preceded by an odd number
+/// of backslashes.
+#[cfg(test)]
+pub fn closing_quotes_are_escaped(text: &str) -> bool {
+    let lowercase = text.to_lowercase();
+
+    lowercase.match_indices("[/quote").all(|(index, _)| {
+        lowercase[..index]
+            .chars()
+            .rev()
+            .take_while(|&c| c == '\\')
+            .count()
+            % 2
+            == 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use proptest::prelude::*;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("Test commit.", vec!["Test commit."]; "one line")]
+    #[test_case("Test commit.\nCo-Authored-By: user\nMore.", vec!["Test commit.", "More."]; "co-author is removed")]
+    #[test_case("Revert \"Test\".\nThis reverts commit abcdef.", vec!["Revert \"Test\"."]; "revert note is removed")]
+    #[test_case("Co-authored-by: user\nTest commit.", vec!["Co-authored-by: user", "Test commit."]; "first line is kept")]
+    #[test_case("Close [/quote] early\n[details=\"x\"] @user", vec![r"Close \[/quote] early", r#"\[details="x"] `@user`"#]; "lines are sanitized")]
+    #[test_case("", vec![""]; "empty")]
+    fn commit_message_lines(full_message: &str, result: Vec<&str>) {
+        assert_eq!(super::commit_message_lines(full_message), result);
+    }
+
+    #[test_case("[/quote]", r"\[/quote]"; "closing quote")]
+    #[test_case(r"\[/quote]", r"\\\[/quote]"; "escaped closing quote")]
+    #[test_case(r"\\[details]", r"\\\\\[details]"; "escaped backslash before tag")]
+    #[test_case(r"C:\path [quote]", r"C:\path \[quote]"; "backslash elsewhere")]
+    fn block_tags_are_escaped(text: &str, result: &str) {
+        assert_eq!(escape_block_tags(text), result);
+    }
+
+    #[test_case("[/quote]", false; "unescaped")]
+    #[test_case(r"\[/quote]", true; "escaped")]
+    #[test_case(r"\\[/quote]", false; "escaped backslash")]
+    #[test_case(r"\\\[/QUOTE]", true; "escaped backslash and tag")]
+    fn closing_quote_is_escaped(text: &str, result: bool) {
+        assert_eq!(closing_quotes_are_escaped(text), result);
+    }
+
+    #[test_case("https://github.com/signalapp/Signal-Android/pull/1", "[https://github.com/signalapp/Signal-Android/pull/1](https://github.com/signalapp/Signal-Android/pull/1)"; "bare URL")]
+    #[test_case("    https://example.com/a ", "    [https://example.com/a](https://example.com/a)"; "indented")]
+    #[test_case("<https://example.com/a>", "[https://example.com/a](https://example.com/a)"; "angle brackets")]
+    #[test_case("Text.\nhttps://example.com/a\nMore.", "Text.\n[https://example.com/a](https://example.com/a)\nMore."; "among other lines")]
+    #[test_case("See https://example.com/a", "See https://example.com/a"; "with text")]
+    #[test_case("[link](https://example.com/a)", "[link](https://example.com/a)"; "already a link")]
+    fn oneboxes_are_prevented(text: &str, result: &str) {
+        assert_eq!(prevent_oneboxes(text), result);
+        assert!(!has_bare_url_line(result));
+    }
+
+    proptest! {
+        #[test]
+        fn sanitized_text_has_no_unescaped_closing_quote(text in message()) {
+            prop_assert!(closing_quotes_are_escaped(&sanitize(&text)));
+        }
+
+        #[test]
+        fn sanitized_text_has_no_mentions(text in message()) {
+            let sanitized = sanitize(&text);
+
+            prop_assert!(MENTION_REGEX
+                .find_iter(&sanitized)
+                .all(|mention| sanitized[..mention.start()].ends_with('`')));
+        }
+
+        #[test]
+        fn sanitized_text_has_no_bare_urls(text in message()) {
+            prop_assert!(!has_bare_url_line(&sanitize(&text)));
+        }
+
+        #[test]
+        fn first_line_is_never_stripped(full_message in message()) {
+            let first_line = full_message.split('\n').next().unwrap();
+
+            prop_assert_eq!(
+                &super::commit_message_lines(&full_message)[0],
+                &sanitize(first_line)
+            );
+        }
+
+        #[test]
+        fn later_lines_are_never_added(full_message in message()) {
+            prop_assert!(
+                super::commit_message_lines(&full_message).len()
+                    <= full_message.split('\n').count()
+            );
+        }
+    }
+}
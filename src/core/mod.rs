@@ -0,0 +1,26 @@
+pub mod comparison;
+pub mod error;
+pub mod hash;
+pub mod libsignal;
+pub mod localization;
+pub mod log;
+pub mod markdown;
+pub mod platform;
+pub mod schedule;
+pub mod state;
+pub mod task_local;
+pub mod types;
+
+/// Polls `future` once, which is enough for futures that never wait on anything.
+#[cfg(test)]
+pub fn poll_once<T>(future: impl std::future::Future<Output = T>) -> T {
+    use std::{
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future is pending"),
+    }
+}
@@ -0,0 +1,270 @@
+use std::fmt;
+
+use semver::{Prerelease, Version};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use url::Url;
+
+use crate::core::hash;
+
+pub const ANDROID_DEFAULT_STRINGS_FILENAME: &str = "app/src/main/res/values/strings.xml";
+
+/// Prerelease identifiers of the versions that are posted for iOS and Desktop, unless configured.
+pub const DEFAULT_PRERELEASE_IDENTIFIERS: &[&str] = &["beta"];
+
+/// Identifier of `pre` that names its channel (e.g. `alpha` of `alpha.1`), without a number
+/// right after it (as in `beta1`); `None` if the first identifier is only a number.
+pub fn prerelease_channel(pre: &Prerelease) -> Option<&str> {
+    let channel = pre
+        .as_str()
+        .split('.')
+        .next()?
+        .trim_end_matches(|c: char| c.is_ascii_digit());
+
+    (!channel.is_empty()).then_some(channel)
+}
+
+/// Whether the channel of `pre` (see [`prerelease_channel`]) is one of `identifiers`.
+pub fn has_prerelease_identifier(pre: &Prerelease, identifiers: &[impl AsRef<str>]) -> bool {
+    prerelease_channel(pre).is_some_and(|channel| {
+        identifiers
+            .iter()
+            .any(|identifier| identifier.as_ref().eq_ignore_ascii_case(channel))
+    })
+}
+
+#[derive(Debug, Clone, Copy, EnumIter, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Android,
+    Ios,
+    Desktop,
+}
+
+use Platform::*;
+
+impl Platform {
+    /// Finds the platform whose name (as displayed) matches `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::iter().find(|platform| platform.to_string().eq_ignore_ascii_case(name))
+    }
+
+    pub fn github_api_tags_url(&self) -> String {
+        format!("https://api.github.com/repos/signalapp/Signal-{self}/tags")
+    }
+
+    /// `prerelease_identifiers` are the channels of the prereleases that are posted
+    /// for iOS and Desktop (see [`has_prerelease_identifier`]).
+    pub fn should_post_version(
+        &self,
+        version: &Version,
+        prerelease_identifiers: &[impl AsRef<str>],
+    ) -> bool {
+        match self {
+            Android => version.build.is_empty(), // versions like 1.2.3.4 are filtered out (the "4" is parsed into `build` by lenient_semver)
+            Ios | Desktop => has_prerelease_identifier(&version.pre, prerelease_identifiers),
+        }
+    }
+
+    pub fn should_show_commit(&self, full_message: &str) -> bool {
+        match self {
+            Android | Desktop => true,
+            Ios => {
+                !full_message.contains("Bump build to")
+                    && !full_message.contains("Feature flags for")
+            }
+        }
+    }
+
+    pub fn should_show_commit_details(&self) -> bool {
+        matches!(self, Android | Desktop)
+    }
+
+    pub fn github_api_comparison_url(&self, old: &str, new: &str) -> String {
+        format!("https://api.github.com/repos/signalapp/Signal-{self}/compare/{old}...{new}")
+    }
+
+    pub fn github_comparison_url(&self, old: &str, new: &str, file_path: Option<&str>) -> String {
+        match file_path {
+            Some(file_path) => format!(
+                "https://github.com/signalapp/Signal-{self}/compare/{old}..{new}#diff-{}", // note: using `..` instead of `...`
+                hash::sha256_string(file_path)
+            ),
+            None => format!("https://github.com/signalapp/Signal-{self}/compare/{old}...{new}"),
+        }
+    }
+
+    /// The file at `path` as of `tag`; each path segment is escaped, since
+    /// some (e.g. iOS `.lproj` bundles) may contain spaces or `#`.
+    pub fn github_file_url(&self, tag: &str, path: &str) -> String {
+        let mut url = Url::parse(&format!("https://github.com/signalapp/Signal-{self}/blob"))
+            .expect("base URL should be valid");
+
+        url.path_segments_mut()
+            .expect("base URL should have a path")
+            .extend(tag.split('/'))
+            .extend(path.split('/'));
+
+        url.into()
+    }
+
+    pub fn github_api_release_url(&self, tag: &str) -> String {
+        format!("https://api.github.com/repos/signalapp/Signal-{self}/releases/tags/{tag}")
+    }
+
+    /// Commits reachable from `sha`, newest first; only those that change files
+    /// under `path`, if given.
+    pub fn github_api_commits_url(&self, sha: &str, path: Option<&str>) -> String {
+        let url = format!("https://api.github.com/repos/signalapp/Signal-{self}/commits?sha={sha}");
+
+        match path {
+            Some(path) => format!("{url}&path={path}"),
+            None => url,
+        }
+    }
+
+    pub fn github_api_commit_url(&self, sha: &str) -> String {
+        format!("https://api.github.com/repos/signalapp/Signal-{self}/commits/{sha}")
+    }
+
+    pub fn github_commit_url(&self, sha: &str) -> String {
+        format!("https://github.com/signalapp/Signal-{self}/commit/{sha}")
+    }
+
+    pub const fn availability_notice(&self) -> &'static str {
+        match self {
+            Android => "\n(Not Yet) Available via [Firebase App Distribution](https://community.signalusers.org/t/17538)",
+            Ios | Desktop => "",
+        }
+    }
+
+    pub fn discourse_topic_slug_url(&self, version: &Version) -> String {
+        format!(
+            "https://community.signalusers.org/t/beta-feedback-for-the-upcoming-{}-{}-{}-release.json",
+            self.to_string().to_ascii_lowercase(), version.major, version.minor
+        )
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Android => "Android",
+                Ios => "iOS",
+                Desktop => "Desktop",
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("beta.1", Some("beta"); "numbered")]
+    #[test_case("beta", Some("beta"); "alone")]
+    #[test_case("beta1", Some("beta"); "number right after it")]
+    #[test_case("alpha.2", Some("alpha"); "alpha")]
+    #[test_case("rc.1", Some("rc"); "rc")]
+    #[test_case("3", None; "only a number")]
+    #[test_case("", None; "no prerelease")]
+    fn channel_of_prerelease(pre: &str, result: Option<&str>) {
+        assert_eq!(prerelease_channel(&Prerelease::new(pre).unwrap()), result);
+    }
+
+    #[test_case("beta.1", &["beta"], true; "beta")]
+    #[test_case("BETA.1", &["beta"], true; "uppercase")]
+    #[test_case("notbeta.1", &["beta"], false; "not beta")]
+    #[test_case("betamax", &["beta"], false; "starts with beta")]
+    #[test_case("alpha.1", &["beta"], false; "alpha by default")]
+    #[test_case("alpha.1", &["beta", "alpha"], true; "alpha when configured")]
+    #[test_case("rc.1", &["beta", "rc"], true; "rc when configured")]
+    #[test_case("nightly.beta", &["beta"], false; "beta is not the channel")]
+    #[test_case("", &["beta"], false; "release")]
+    fn prerelease_identifier(pre: &str, identifiers: &[&str], result: bool) {
+        assert_eq!(
+            has_prerelease_identifier(&Prerelease::new(pre).unwrap(), identifiers),
+            result
+        );
+    }
+
+    #[test_case(Android, "1.2.3", &["beta"], true; "Android: release")]
+    #[test_case(Android, "1.2.3+4", &["beta"], false; "Android: build")]
+    #[test_case(Desktop, "1.2.3-beta.1", &["beta"], true; "Desktop: beta")]
+    #[test_case(Desktop, "1.2.3-alpha.1", &["beta"], false; "Desktop: alpha by default")]
+    #[test_case(Desktop, "1.2.3-alpha.1", &["beta", "alpha"], true; "Desktop: alpha when configured")]
+    #[test_case(Desktop, "1.2.3", &["beta"], false; "Desktop: release")]
+    #[test_case(Ios, "1.2.3-beta+4", &["beta"], true; "iOS: beta")]
+    fn post_version(platform: Platform, version: &str, identifiers: &[&str], result: bool) {
+        assert_eq!(
+            platform.should_post_version(&Version::parse(version).unwrap(), identifiers),
+            result
+        );
+    }
+
+    #[test_case("Android", Some(Android); "Android: display name")]
+    #[test_case("android", Some(Android); "Android: lowercase")]
+    #[test_case("iOS", Some(Ios); "iOS: display name")]
+    #[test_case("IOS", Some(Ios); "iOS: uppercase")]
+    #[test_case("desktop", Some(Desktop); "Desktop: lowercase")]
+    #[test_case("windows", None; "unknown")]
+    #[test_case("", None; "empty")]
+    fn from_name(name: &str, result: Option<Platform>) {
+        assert_eq!(Platform::from_name(name), result);
+    }
+
+    #[test_case(None, "https://api.github.com/repos/signalapp/Signal-Desktop/commits?sha=v1.2.3"; "all commits")]
+    #[test_case(Some("_locales"), "https://api.github.com/repos/signalapp/Signal-Desktop/commits?sha=v1.2.3&path=_locales"; "commits touching path")]
+    fn github_api_commits_url(path: Option<&str>, result: &str) {
+        assert_eq!(Desktop.github_api_commits_url("v1.2.3", path), result);
+    }
+
+    #[test_case(
+        Android, "v7.1.4", "app/src/main/res/values-de/strings.xml",
+        "https://github.com/signalapp/Signal-Android/blob/v7.1.4/app/src/main/res/values-de/strings.xml";
+        "Android"
+    )]
+    #[test_case(
+        Ios, "7.1.0.5", "Signal/translations/pt_BR.lproj/Localizable.strings",
+        "https://github.com/signalapp/Signal-iOS/blob/7.1.0.5/Signal/translations/pt_BR.lproj/Localizable.strings";
+        "iOS"
+    )]
+    #[test_case(
+        Desktop, "v7.1.0-beta.1", "_locales/zh_CN/messages.json",
+        "https://github.com/signalapp/Signal-Desktop/blob/v7.1.0-beta.1/_locales/zh_CN/messages.json";
+        "Desktop"
+    )]
+    #[test_case(
+        Ios, "7.1.0.5", "Signal/translations/My Bundle #2.lproj/Localizable.strings",
+        "https://github.com/signalapp/Signal-iOS/blob/7.1.0.5/Signal/translations/My%20Bundle%20%232.lproj/Localizable.strings";
+        "iOS: space and hash are escaped"
+    )]
+    #[test_case(
+        Desktop, "release/7.1", "app/_locales/de/messages.json",
+        "https://github.com/signalapp/Signal-Desktop/blob/release/7.1/app/_locales/de/messages.json";
+        "Desktop: tag with slash, path outside the usual layout"
+    )]
+    #[test_case(
+        Android, "v7.1.4", "res/values-b+sr+Latn/strings?.xml",
+        "https://github.com/signalapp/Signal-Android/blob/v7.1.4/res/values-b+sr+Latn/strings%3F.xml";
+        "Android: question mark is escaped"
+    )]
+    fn github_file_url(platform: Platform, tag: &str, path: &str, result: &str) {
+        assert_eq!(platform.github_file_url(tag, path), result);
+    }
+
+    #[test_case(Android, vec!["app/src/main/res"]; "Android")]
+    #[test_case(Ios, vec!["Signal/translations", "fastlane/metadata"]; "iOS")]
+    #[test_case(Desktop, vec!["_locales"]; "Desktop")]
+    fn localization_path_bases(platform: Platform, result: Vec<&str>) {
+        assert_eq!(
+            crate::core::localization::StringsFileKind::path_bases(platform),
+            result
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context};
+use strum::IntoEnumIterator;
+
+use crate::core::platform::Platform;
+
+/// Returns the platforms to check for a scheduled event with `cron`.
+///
+/// `mapping` is a JSON object mapping cron expressions to lists of platform
+/// names, e.g. `{"*/5 * * * *": ["android"]}`. All platforms are checked for
+/// crons that are not in the mapping, or if there is no mapping.
+pub fn platforms_for_cron(mapping: Option<&str>, cron: &str) -> anyhow::Result<Vec<Platform>> {
+    let mapping: HashMap<String, Vec<String>> = match mapping {
+        Some(mapping) => serde_json::from_str(mapping).context("could not parse cron mapping")?,
+        None => return Ok(Platform::iter().collect()),
+    };
+
+    let names = match mapping.get(cron) {
+        Some(names) => names,
+        None => return Ok(Platform::iter().collect()),
+    };
+
+    let mut selected = Vec::new();
+
+    for name in names {
+        match Platform::from_name(name) {
+            Some(platform) => selected.push(platform),
+            None => bail!("unknown platform {name} for cron {cron}"),
+        }
+    }
+
+    Ok(Platform::iter()
+        .filter(|platform| selected.contains(platform))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::platform::Platform::*;
+
+    const MAPPING: &str =
+        r#"{"*/5 * * * *": ["android"], "*/30 * * * *": ["desktop", "Android", "iOS"]}"#;
+
+    #[test_case(Some(MAPPING), "*/5 * * * *", vec![Android]; "single platform")]
+    #[test_case(Some(MAPPING), "*/30 * * * *", vec![Android, Ios, Desktop]; "all platforms in order")]
+    #[test_case(Some(MAPPING), "0 * * * *", vec![Android, Ios, Desktop]; "unknown cron")]
+    #[test_case(None, "*/5 * * * *", vec![Android, Ios, Desktop]; "no mapping")]
+    #[test_case(Some(r#"{"*/5 * * * *": []}"#), "*/5 * * * *", vec![]; "no platforms")]
+    fn platforms(mapping: Option<&str>, cron: &str, result: Vec<Platform>) {
+        assert_eq!(platforms_for_cron(mapping, cron).unwrap(), result);
+    }
+
+    #[test_case(r#"{"*/5 * * * *": ["windows"]}"#; "unknown platform")]
+    #[test_case(r#"["android"]"#; "not an object")]
+    #[test_case("{"; "invalid JSON")]
+    fn invalid_mapping(mapping: &str) {
+        assert!(platforms_for_cron(Some(mapping), "*/5 * * * *").is_err());
+    }
+}
@@ -0,0 +1,1212 @@
+use std::{error::Error, fmt};
+
+use anyhow::{bail, Context};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum::IntoEnumIterator;
+
+use crate::core::{
+    error,
+    localization::{Completeness, UnsortedChanges},
+    platform::Platform::{self, *},
+    types::github::Tag,
+};
+
+/// Version of the shape of the state stored in KV; bumped along with a new migration
+/// in [`MIGRATIONS`] whenever a change would not be handled by `#[serde(default)]`.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// The migration at index `n` turns stored state of version `n` into version `n + 1`.
+const MIGRATIONS: [fn(Value) -> anyhow::Result<Value>; SCHEMA_VERSION as usize] =
+    [migrate_v0_to_v1];
+
+/// The state as stored in KV.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StoredState {
+    pub schema_version: u64,
+    /// Incremented with every write, to detect writes made since the state was loaded.
+    #[serde(default)]
+    pub revision: u64,
+    /// ISO 8601 timestamp of when the state was saved.
+    #[serde(default)]
+    pub saved_at: Option<String>,
+    pub platforms: State,
+}
+
+/// The state in KV was changed since it was loaded, e.g. by a concurrent run,
+/// so saving it would overwrite those changes. A later run starts from the new state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateConflict {
+    loaded_revision: u64,
+    stored_revision: u64,
+}
+
+impl fmt::Display for StateConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "state changed since it was loaded (revision {} instead of {}), not overwriting it; will be retried next run",
+            self.stored_revision, self.loaded_revision
+        )
+    }
+}
+
+impl Error for StateConflict {}
+
+/// Revision to save state loaded at `loaded_revision` with, if KV is still at that revision.
+pub fn next_revision(loaded_revision: u64, stored_revision: u64) -> Result<u64, StateConflict> {
+    if stored_revision != loaded_revision {
+        return Err(StateConflict {
+            loaded_revision,
+            stored_revision,
+        });
+    }
+
+    Ok(loaded_revision + 1)
+}
+
+/// Revision of the state stored as `text`; state saved before revisions has none.
+pub fn revision_of(text: &str) -> anyhow::Result<u64> {
+    let value: Value = serde_json::from_str(text).context("could not parse stored state")?;
+
+    Ok(value
+        .get("revision")
+        .and_then(Value::as_u64)
+        .unwrap_or_default())
+}
+
+/// Version 0: the state of each platform directly at the top level, without a version.
+///
+/// Platform states are kept as they are, as their shape is the same in version 1.
+#[derive(Serialize, Deserialize)]
+struct StateV0 {
+    android: Value,
+    ios: Value,
+    desktop: Value,
+}
+
+/// Version 1: the states of the platforms are under `platforms`, next to `schema_version`.
+#[derive(Serialize)]
+struct StateV1 {
+    schema_version: u64,
+    platforms: StateV0,
+}
+
+fn migrate_v0_to_v1(value: Value) -> anyhow::Result<Value> {
+    let state: StateV0 = serde_json::from_value(value).context("could not parse version 0")?;
+
+    serde_json::to_value(StateV1 {
+        schema_version: 1,
+        platforms: state,
+    })
+    .context("could not serialize version 1")
+}
+
+/// Schema version of stored state `value`; state saved before versioning has version 0.
+pub fn schema_version(value: &Value) -> anyhow::Result<u64> {
+    match value.get("schema_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .with_context(|| format!("schema_version is not a version: {version}")),
+    }
+}
+
+/// Runs the migrations needed to bring `value` to [`SCHEMA_VERSION`].
+///
+/// Returns the state, and whether it was migrated.
+pub fn migrate(mut value: Value) -> anyhow::Result<(StoredState, bool)> {
+    let version = schema_version(&value)?;
+
+    if version > SCHEMA_VERSION {
+        bail!("state has schema_version = {version}, newer than supported {SCHEMA_VERSION}");
+    }
+
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        value = migration(value)
+            .with_context(|| format!("could not migrate state from version {from_version}"))?;
+    }
+
+    let stored: StoredState =
+        serde_json::from_value(value).context("could not parse migrated state")?;
+
+    Ok((stored, version < SCHEMA_VERSION))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    pub android: PlatformState,
+    pub ios: PlatformState,
+    pub desktop: PlatformState,
+}
+
+/// The localization changes of a [`PlatformState`] as they were before its `last_posted_tag`
+/// was posted, so that the post can be made again from the same state if the tag is re-cut.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LocalizationState {
+    pub last_posted_tag_previous_release: Tag,
+    pub completeness: Completeness,
+    pub completeness_rationale: Option<String>,
+    pub changes: UnsortedChanges,
+    pub cycle: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlatformState {
+    pub last_posted_tag_previous_release: Tag,
+    pub last_posted_tag: Tag,
+
+    #[serde(default)]
+    pub last_post_number: Option<u64>,
+    #[serde(default)]
+    pub last_post_id: Option<u64>,
+    #[serde(default)]
+    pub last_post_url: Option<String>,
+    /// ISO 8601 timestamp of when the last post was made.
+    #[serde(default)]
+    pub last_posted_at: Option<String>,
+    /// Number of times posting the next version was tried since the last post.
+    #[serde(default)]
+    pub post_attempts: u32,
+    /// Next tag to post, if posting it was deferred because the last post was made too recently.
+    #[serde(default)]
+    pub pending_tag: Option<String>,
+
+    #[serde(default)]
+    pub posted_archiving_message: bool,
+    /// Whether the localization changes of the release cycle of `last_posted_tag`
+    /// were summarized in its topic, once the next cycle started.
+    #[serde(default)]
+    pub posted_cycle_summary: bool,
+
+    /// Whether `last_posted_tag` was found pointing at a different commit on GitHub
+    /// than the one the last post was generated for.
+    #[serde(default)]
+    pub tag_moved: bool,
+
+    #[serde(default)]
+    pub localization_changes_completeness: Completeness,
+    /// Why `localization_changes_completeness` was chosen, for readers; `None` if it was
+    /// recorded before rationales were.
+    #[serde(default)]
+    pub localization_changes_completeness_rationale: Option<String>,
+    #[serde(default)]
+    pub localization_changes: UnsortedChanges,
+    /// Release cycle (`major.minor`, see [`release_cycle`]) that `localization_changes`
+    /// were accumulated for.
+    #[serde(default)]
+    pub localization_changes_cycle: Option<String>,
+    /// See [`LocalizationState`]; `None` if `last_posted_tag` was posted before it was recorded.
+    #[serde(default)]
+    pub localization_before_last_posted_tag: Option<LocalizationState>,
+
+    /// Whether new versions are checked for and posted; can be switched off
+    /// to hold off posting for this platform.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// ISO 8601 timestamp of when this platform was last checked.
+    #[serde(default)]
+    pub last_checked_at: Option<String>,
+    /// Error of the last check, if it failed.
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Number of checks in a row that failed.
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// ISO 8601 timestamp before which this platform is not checked, after failures.
+    #[serde(default)]
+    pub next_attempt_after: Option<String>,
+    /// Why posting was put off to a later run in the last check, e.g. `forum read-only`.
+    #[serde(default)]
+    pub deferred_reason: Option<String>,
+    /// Subrequests made by the run that last checked this platform, as of when the check finished.
+    #[serde(default)]
+    pub run_subrequests: Option<u64>,
+}
+
+impl PlatformState {
+    /// State before anything is posted, starting from `last_posted_tag`.
+    pub fn initial(last_posted_tag_previous_release: Tag, last_posted_tag: Tag) -> Self {
+        Self {
+            last_posted_tag_previous_release,
+            last_posted_tag,
+            last_post_number: None,
+            last_post_id: None,
+            last_post_url: None,
+            last_posted_at: None,
+            post_attempts: 0,
+            pending_tag: None,
+            posted_archiving_message: false,
+            posted_cycle_summary: false,
+            tag_moved: false,
+            localization_changes_completeness: Completeness::default(),
+            localization_changes_completeness_rationale: None,
+            localization_changes: UnsortedChanges::default(),
+            localization_changes_cycle: None,
+            localization_before_last_posted_tag: None,
+            enabled: default_enabled(),
+            last_checked_at: None,
+            last_error: None,
+            consecutive_failures: 0,
+            next_attempt_after: None,
+            deferred_reason: None,
+            run_subrequests: None,
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let last_posted_version_previous_release: Version = self
+            .last_posted_tag_previous_release
+            .to_version()
+            .context("couldn't convert last_posted_tag_previous_release to a Version")?;
+
+        let last_posted_version: Version = self
+            .last_posted_tag
+            .to_version()
+            .context("couldn't convert last_posted_tag to a Version")?;
+
+        if last_posted_version_previous_release >= last_posted_version {
+            bail!("last_posted_version_previous_release >= last_posted_version");
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the fields that are set in `patch`.
+    pub fn patched(self, patch: PlatformStatePatch) -> Self {
+        Self {
+            last_posted_tag_previous_release: patch
+                .last_posted_tag_previous_release
+                .unwrap_or(self.last_posted_tag_previous_release),
+            last_posted_tag: patch.last_posted_tag.unwrap_or(self.last_posted_tag),
+            last_post_number: patch.last_post_number.unwrap_or(self.last_post_number),
+            last_post_id: patch.last_post_id.unwrap_or(self.last_post_id),
+            last_post_url: patch.last_post_url.unwrap_or(self.last_post_url),
+            last_posted_at: patch.last_posted_at.unwrap_or(self.last_posted_at),
+            post_attempts: patch.post_attempts.unwrap_or(self.post_attempts),
+            pending_tag: patch.pending_tag.unwrap_or(self.pending_tag),
+            posted_archiving_message: patch
+                .posted_archiving_message
+                .unwrap_or(self.posted_archiving_message),
+            posted_cycle_summary: patch
+                .posted_cycle_summary
+                .unwrap_or(self.posted_cycle_summary),
+            tag_moved: patch.tag_moved.unwrap_or(self.tag_moved),
+            localization_changes_completeness: patch
+                .localization_changes_completeness
+                .unwrap_or(self.localization_changes_completeness),
+            localization_changes_completeness_rationale: patch
+                .localization_changes_completeness_rationale
+                .unwrap_or(self.localization_changes_completeness_rationale),
+            localization_changes: patch
+                .localization_changes
+                .unwrap_or(self.localization_changes),
+            localization_changes_cycle: patch
+                .localization_changes_cycle
+                .unwrap_or(self.localization_changes_cycle),
+            localization_before_last_posted_tag: self.localization_before_last_posted_tag,
+            enabled: patch.enabled.unwrap_or(self.enabled),
+            last_checked_at: patch.last_checked_at.unwrap_or(self.last_checked_at),
+            last_error: patch.last_error.unwrap_or(self.last_error),
+            consecutive_failures: patch
+                .consecutive_failures
+                .unwrap_or(self.consecutive_failures),
+            next_attempt_after: patch.next_attempt_after.unwrap_or(self.next_attempt_after),
+            deferred_reason: patch.deferred_reason.unwrap_or(self.deferred_reason),
+            run_subrequests: patch.run_subrequests.unwrap_or(self.run_subrequests),
+        }
+    }
+
+    /// Takes the fields that are not about the last post from `previous`.
+    pub fn with_status_of(self, previous: &PlatformState) -> Self {
+        Self {
+            enabled: previous.enabled,
+            last_checked_at: previous.last_checked_at.clone(),
+            last_error: previous.last_error.clone(),
+            consecutive_failures: previous.consecutive_failures,
+            next_attempt_after: previous.next_attempt_after.clone(),
+            deferred_reason: previous.deferred_reason.clone(),
+            run_subrequests: previous.run_subrequests,
+            ..self
+        }
+    }
+
+    /// Release cycle that `localization_changes` belong to; for state saved before cycles
+    /// were recorded, the cycle of `last_posted_tag`.
+    pub fn localization_cycle(&self) -> Option<String> {
+        self.localization_changes_cycle.clone().or_else(|| {
+            self.last_posted_tag
+                .to_version()
+                .ok()
+                .map(|version| release_cycle(&version))
+        })
+    }
+
+    /// The localization changes of this state, to be kept when the next tag is posted.
+    pub fn localization_state(&self) -> LocalizationState {
+        LocalizationState {
+            last_posted_tag_previous_release: self.last_posted_tag_previous_release.clone(),
+            completeness: self.localization_changes_completeness,
+            completeness_rationale: self.localization_changes_completeness_rationale.clone(),
+            changes: self.localization_changes.clone(),
+            cycle: self.localization_changes_cycle.clone(),
+        }
+    }
+
+    /// This state with the localization changes as they were before `last_posted_tag`
+    /// was posted, if they were recorded.
+    pub fn before_last_posted_tag(&self) -> Option<Self> {
+        let before = self.localization_before_last_posted_tag.clone()?;
+
+        Some(Self {
+            last_posted_tag_previous_release: before.last_posted_tag_previous_release,
+            localization_changes_completeness: before.completeness,
+            localization_changes_completeness_rationale: before.completeness_rationale,
+            localization_changes: before.changes,
+            localization_changes_cycle: before.cycle,
+            ..self.clone()
+        })
+    }
+
+    /// Whether posting is deferred because the last post was made after ISO 8601 timestamp
+    /// `earliest_last_posted_at`, the latest time that allows posting again.
+    pub fn is_posting_deferred(&self, earliest_last_posted_at: &str) -> bool {
+        matches!(&self.last_posted_at, Some(last_posted_at) if last_posted_at.as_str() > earliest_last_posted_at)
+    }
+
+    /// Whether checking is held off at ISO 8601 timestamp `now` after failures.
+    pub fn is_cooling_down(&self, now: &str) -> bool {
+        // ISO 8601 timestamps in the same format sort the same as the times they stand for
+        matches!(&self.next_attempt_after, Some(next_attempt_after) if now < next_attempt_after.as_str())
+    }
+
+    /// Counts a failed check and holds off checking until the timestamp `after` returns
+    /// for the cool-down in seconds.
+    pub fn record_failure(&mut self, after: impl FnOnce(u64) -> String) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.next_attempt_after = Some(after(cool_down_seconds(self.consecutive_failures)));
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.next_attempt_after = None;
+    }
+}
+
+/// Cool-down after the first failure; doubled after each one after it.
+const BASE_COOL_DOWN_SECONDS: u64 = 5 * 60;
+const MAX_COOL_DOWN_SECONDS: u64 = 4 * 60 * 60;
+
+fn cool_down_seconds(consecutive_failures: u32) -> u64 {
+    consecutive_failures
+        .checked_sub(1)
+        .and_then(|doublings| 1u64.checked_shl(doublings))
+        .and_then(|factor| BASE_COOL_DOWN_SECONDS.checked_mul(factor))
+        .map_or(MAX_COOL_DOWN_SECONDS, |seconds| {
+            seconds.min(MAX_COOL_DOWN_SECONDS)
+        })
+}
+
+/// Release cycle of `version`, e.g. `6.2` for `6.2.0-beta.1`.
+pub fn release_cycle(version: &Version) -> String {
+    format!("{}.{}", version.major, version.minor)
+}
+
+/// How the value of a field is shown in a [`diff`].
+trait DiffValue {
+    fn diff_value(&self) -> String;
+}
+
+impl DiffValue for Tag {
+    fn diff_value(&self) -> String {
+        match self.short_sha() {
+            Some(sha) => format!("{} ({sha})", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl<T: DiffValue> DiffValue for Option<T> {
+    fn diff_value(&self) -> String {
+        match self {
+            Some(value) => value.diff_value(),
+            None => String::from("none"),
+        }
+    }
+}
+
+impl DiffValue for String {
+    fn diff_value(&self) -> String {
+        self.clone()
+    }
+}
+
+impl DiffValue for Completeness {
+    fn diff_value(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+/// Only the number of languages is shown, as the changes themselves can be long.
+impl DiffValue for UnsortedChanges {
+    fn diff_value(&self) -> String {
+        format!("{} language(s)", self.len())
+    }
+}
+
+impl DiffValue for LocalizationState {
+    fn diff_value(&self) -> String {
+        format!(
+            "{} since {}",
+            self.changes.diff_value(),
+            self.last_posted_tag_previous_release.diff_value()
+        )
+    }
+}
+
+macro_rules! impl_diff_value_with_display {
+    ($($type:ty),*) => {
+        $(impl DiffValue for $type {
+            fn diff_value(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+impl_diff_value_with_display!(bool, u32, u64);
+
+/// Describes the fields that differ between `old` and `new`, one per field,
+/// e.g. `last_posted_tag: v7.1.3 → v7.1.4`.
+fn diff(old: &PlatformState, new: &PlatformState) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    macro_rules! diff_fields {
+        ($($field:ident),* $(,)?) => {
+            // without `..`, so that new fields have to be added here
+            let PlatformState { $($field),* } = new;
+
+            $(if old.$field != *$field {
+                lines.push(format!(
+                    "{}: {} → {}",
+                    stringify!($field),
+                    old.$field.diff_value(),
+                    $field.diff_value()
+                ));
+            })*
+        };
+    }
+
+    diff_fields!(
+        last_posted_tag_previous_release,
+        last_posted_tag,
+        last_post_number,
+        last_post_id,
+        last_post_url,
+        last_posted_at,
+        post_attempts,
+        pending_tag,
+        posted_archiving_message,
+        posted_cycle_summary,
+        tag_moved,
+        localization_changes_completeness,
+        localization_changes_completeness_rationale,
+        localization_changes,
+        localization_changes_cycle,
+        localization_before_last_posted_tag,
+        enabled,
+        last_checked_at,
+        last_error,
+        consecutive_failures,
+        next_attempt_after,
+        deferred_reason,
+        run_subrequests,
+    );
+
+    lines
+}
+
+const fn default_enabled() -> bool {
+    true
+}
+
+/// Changes to some fields of a [`PlatformState`]; fields that are left out are kept.
+///
+/// Optional fields of [`PlatformState`] are cleared with `null`.
+#[derive(Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PlatformStatePatch {
+    #[serde(default)]
+    pub last_posted_tag_previous_release: Option<Tag>,
+    #[serde(default)]
+    pub last_posted_tag: Option<Tag>,
+
+    #[serde(default, deserialize_with = "present")]
+    pub last_post_number: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "present")]
+    pub last_post_id: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "present")]
+    pub last_post_url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub last_posted_at: Option<Option<String>>,
+    #[serde(default)]
+    pub post_attempts: Option<u32>,
+    #[serde(default, deserialize_with = "present")]
+    pub pending_tag: Option<Option<String>>,
+
+    #[serde(default)]
+    pub posted_archiving_message: Option<bool>,
+    #[serde(default)]
+    pub posted_cycle_summary: Option<bool>,
+    #[serde(default)]
+    pub tag_moved: Option<bool>,
+
+    #[serde(default)]
+    pub localization_changes_completeness: Option<Completeness>,
+    #[serde(default, deserialize_with = "present")]
+    pub localization_changes_completeness_rationale: Option<Option<String>>,
+    #[serde(default)]
+    pub localization_changes: Option<UnsortedChanges>,
+    #[serde(default, deserialize_with = "present")]
+    pub localization_changes_cycle: Option<Option<String>>,
+
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    #[serde(default, deserialize_with = "present")]
+    pub last_checked_at: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub last_error: Option<Option<String>>,
+    #[serde(default)]
+    pub consecutive_failures: Option<u32>,
+    #[serde(default, deserialize_with = "present")]
+    pub next_attempt_after: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub deferred_reason: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub run_subrequests: Option<Option<u64>>,
+}
+
+/// Tells a field that is `null` (`Some(None)`) apart from one that is left out (`None`).
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlatformStateUpdate {
+    Changed,
+    Unchanged,
+    SkippedInDryRun,
+}
+
+impl State {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for platform in Platform::iter() {
+            self.platform_state(platform)
+                .validate()
+                .with_context(|| format!("invalid state for {platform}"))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn platform_state(&self, platform: Platform) -> &PlatformState {
+        match platform {
+            Android => &self.android,
+            Ios => &self.ios,
+            Desktop => &self.desktop,
+        }
+    }
+
+    pub fn platform_state_mut(&mut self, platform: Platform) -> &mut PlatformState {
+        match platform {
+            Android => &mut self.android,
+            Ios => &mut self.ios,
+            Desktop => &mut self.desktop,
+        }
+    }
+
+    pub fn is_paused(&self, platform: Platform) -> bool {
+        !self.platform_state(platform).enabled
+    }
+
+    /// Changes the state of `platform` with `update`, unless in dry run.
+    ///
+    /// Returns the changed fields (see [`diff`]) along with the outcome.
+    pub fn update_platform_state(
+        &mut self,
+        platform: Platform,
+        update: impl FnOnce(&mut PlatformState),
+        is_dry_run: bool,
+    ) -> (PlatformStateUpdate, Vec<String>) {
+        let mut new_state = self.platform_state(platform).clone();
+        update(&mut new_state);
+
+        let changes = diff(self.platform_state(platform), &new_state);
+
+        (
+            self.replace_platform_state(platform, new_state, is_dry_run),
+            changes,
+        )
+    }
+
+    /// Replaces the state of `platform`, unless in dry run.
+    fn replace_platform_state(
+        &mut self,
+        platform: Platform,
+        state: PlatformState,
+        is_dry_run: bool,
+    ) -> PlatformStateUpdate {
+        if is_dry_run {
+            return PlatformStateUpdate::SkippedInDryRun;
+        }
+
+        let platform_state = self.platform_state_mut(platform);
+
+        if *platform_state != state {
+            *platform_state = state;
+            PlatformStateUpdate::Changed
+        } else {
+            PlatformStateUpdate::Unchanged
+        }
+    }
+}
+
+/// Parses stored state, e.g. a backup, migrating it if needed.
+pub fn from_value(value: Value) -> anyhow::Result<State> {
+    let (stored, _) = migrate(value)?;
+    stored
+        .platforms
+        .validate()
+        .map_err(error::Error::invalid_state)?;
+
+    Ok(stored.platforms)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::{
+        localization::{ChangedFiles, Language, StringsFileKind},
+        types::github::FileStatus,
+    };
+
+    fn platform_state(last_posted_tag: &str) -> PlatformState {
+        PlatformState {
+            last_posted_tag_previous_release: Tag::new("v1.1.0"),
+            last_posted_tag: Tag::new(last_posted_tag),
+            last_post_number: Some(2),
+            last_post_id: Some(42),
+            last_post_url: None,
+            last_posted_at: None,
+            post_attempts: 0,
+            pending_tag: None,
+            posted_archiving_message: false,
+            posted_cycle_summary: false,
+            tag_moved: false,
+            localization_changes_completeness: Completeness::default(),
+            localization_changes_completeness_rationale: None,
+            localization_changes: UnsortedChanges::default(),
+            localization_changes_cycle: None,
+            localization_before_last_posted_tag: None,
+            enabled: true,
+            last_checked_at: None,
+            last_error: None,
+            consecutive_failures: 0,
+            next_attempt_after: None,
+            deferred_reason: None,
+            run_subrequests: None,
+        }
+    }
+
+    fn state() -> State {
+        State {
+            android: platform_state("v1.2.3"),
+            ios: platform_state("v1.2.3"),
+            desktop: platform_state("v1.2.3"),
+        }
+    }
+
+    #[test]
+    fn replace_platform_state() {
+        let mut state = state();
+
+        assert_eq!(
+            state.replace_platform_state(Android, platform_state("v1.2.4"), false),
+            PlatformStateUpdate::Changed
+        );
+        assert_eq!(state.android, platform_state("v1.2.4"));
+        assert_eq!(state.desktop, platform_state("v1.2.3"));
+
+        assert_eq!(
+            state.replace_platform_state(Android, platform_state("v1.2.4"), false),
+            PlatformStateUpdate::Unchanged
+        );
+    }
+
+    #[test]
+    fn replace_platform_state_in_dry_run() {
+        let mut state = state();
+
+        for platform in Platform::iter() {
+            assert_eq!(
+                state.replace_platform_state(platform, platform_state("v1.2.4"), true),
+                PlatformStateUpdate::SkippedInDryRun
+            );
+        }
+
+        assert_eq!(state, self::state());
+    }
+
+    #[test]
+    fn update_platform_state() {
+        let mut state = state();
+
+        let (update, changes) = state.update_platform_state(
+            Desktop,
+            |platform_state| {
+                platform_state.enabled = false;
+                platform_state.last_post_number = None;
+            },
+            false,
+        );
+
+        assert_eq!(update, PlatformStateUpdate::Changed);
+        assert_eq!(
+            changes,
+            vec!["last_post_number: 2 → none", "enabled: true → false"]
+        );
+        assert!(state.is_paused(Desktop));
+        assert_eq!(state.desktop.last_post_number, None);
+        assert_eq!(
+            state.desktop.localization_changes,
+            UnsortedChanges::default()
+        );
+    }
+
+    #[test_case(|_| {}; "nothing")]
+    #[test_case(|platform_state| platform_state.enabled = true; "same value")]
+    fn unchanged_update_is_not_saved(update: fn(&mut PlatformState)) {
+        let mut state = state();
+
+        assert_eq!(
+            state.update_platform_state(Android, update, false),
+            (PlatformStateUpdate::Unchanged, Vec::new())
+        );
+        assert_eq!(state, self::state());
+    }
+
+    #[test]
+    fn no_diff() {
+        assert_eq!(
+            diff(&platform_state("v1.2.3"), &platform_state("v1.2.3")),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn diff_of_tags() {
+        let mut old = platform_state("v7.1.3");
+        old.last_posted_tag_previous_release = Tag::with_sha("v1.1.0", "0123456789abcdef");
+        let mut new = platform_state("v7.1.4");
+        new.last_posted_tag_previous_release = Tag::with_sha("v1.1.0", "fedcba9876543210");
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                "last_posted_tag_previous_release: v1.1.0 (0123456) → v1.1.0 (fedcba9)",
+                "last_posted_tag: v7.1.3 → v7.1.4",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_of_localization_changes() {
+        let language = |code| Language::try_from(code).unwrap();
+
+        let mut old = platform_state("v1.2.3");
+        old.localization_changes = UnsortedChanges::from([(
+            language("de"),
+            ChangedFiles {
+                kinds: HashSet::from([StringsFileKind::Main]),
+                status: FileStatus::Modified,
+            },
+        )]);
+
+        let mut new = old.clone();
+        new.localization_changes.insert(
+            language("fr"),
+            ChangedFiles {
+                kinds: HashSet::from([StringsFileKind::Main, StringsFileKind::PluralAware]),
+                status: FileStatus::Added,
+            },
+        );
+        new.localization_changes_completeness = Completeness::Complete;
+        new.localization_changes_cycle = Some(String::from("1.2"));
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                "localization_changes_completeness: Incomplete → Complete",
+                "localization_changes: 1 language(s) → 2 language(s)",
+                "localization_changes_cycle: none → 1.2",
+            ]
+        );
+    }
+
+    #[test]
+    fn update_platform_state_in_dry_run() {
+        let mut state = state();
+
+        let (update, _) =
+            state.update_platform_state(Ios, |platform_state| platform_state.enabled = false, true);
+
+        assert_eq!(update, PlatformStateUpdate::SkippedInDryRun);
+        assert_eq!(state, self::state());
+    }
+
+    #[test]
+    fn paused() {
+        let mut state = state();
+        assert!(!state.is_paused(Android));
+
+        let mut paused = platform_state("v1.2.3");
+        paused.enabled = false;
+        state.replace_platform_state(Android, paused, false);
+
+        assert!(state.is_paused(Android));
+        assert!(!state.is_paused(Ios));
+        assert!(!state.is_paused(Desktop));
+    }
+
+    #[test]
+    fn enabled_by_default() {
+        let platform_state: PlatformState = serde_json::from_str(
+            r#"{"last_posted_tag_previous_release":{"name":"v1.1.0"},"last_posted_tag":{"name":"v1.2.3"}}"#,
+        )
+        .unwrap();
+
+        assert!(platform_state.enabled);
+    }
+
+    #[test]
+    fn with_status_of() {
+        let mut previous = platform_state("v1.2.3");
+        previous.enabled = false;
+        previous.last_checked_at = Some(String::from("2022-07-01T00:00:00.000Z"));
+        previous.last_error = Some(String::from("could not fetch tags from GitHub"));
+        previous.consecutive_failures = 2;
+        previous.next_attempt_after = Some(String::from("2022-07-01T00:10:00.000Z"));
+        previous.deferred_reason = Some(String::from("forum read-only"));
+        previous.run_subrequests = Some(12);
+
+        let mut expected = previous.clone();
+        expected.last_posted_tag = Tag::new("v1.2.4");
+
+        assert_eq!(platform_state("v1.2.4").with_status_of(&previous), expected);
+    }
+
+    #[test_case(1, 5 * 60; "first failure")]
+    #[test_case(2, 10 * 60; "second failure")]
+    #[test_case(4, 40 * 60; "fourth failure")]
+    #[test_case(7, 4 * 60 * 60; "capped")]
+    #[test_case(u32::MAX, 4 * 60 * 60; "many failures")]
+    fn cool_down(consecutive_failures: u32, result: u64) {
+        assert_eq!(cool_down_seconds(consecutive_failures), result);
+    }
+
+    #[test]
+    fn failures_back_off_until_success() {
+        let mut platform_state = platform_state("v1.2.3");
+        let after = |seconds| format!("in {seconds} seconds");
+
+        platform_state.record_failure(after);
+        platform_state.record_failure(after);
+
+        assert_eq!(platform_state.consecutive_failures, 2);
+        assert_eq!(
+            platform_state.next_attempt_after.as_deref(),
+            Some("in 600 seconds")
+        );
+
+        platform_state.record_success();
+
+        assert_eq!(platform_state, self::platform_state("v1.2.3"));
+    }
+
+    #[test_case(None, "2022-07-01T00:00:00.000Z", false; "no failures")]
+    #[test_case(Some("2022-07-01T00:05:00.000Z"), "2022-07-01T00:00:00.000Z", true; "before next attempt")]
+    #[test_case(Some("2022-07-01T00:05:00.000Z"), "2022-07-01T00:05:00.000Z", false; "at next attempt")]
+    #[test_case(Some("2022-07-01T00:05:00.000Z"), "2022-07-01T01:00:00.000Z", false; "after next attempt")]
+    fn cooling_down(next_attempt_after: Option<&str>, now: &str, result: bool) {
+        let mut platform_state = platform_state("v1.2.3");
+        platform_state.next_attempt_after = next_attempt_after.map(ToOwned::to_owned);
+
+        assert_eq!(platform_state.is_cooling_down(now), result);
+    }
+
+    fn stored(revision: u64) -> StoredState {
+        StoredState {
+            schema_version: SCHEMA_VERSION,
+            revision,
+            saved_at: None,
+            platforms: state(),
+        }
+    }
+
+    #[test]
+    fn lost_update_is_detected() {
+        // two runs load the state at the same revision
+        let first_loaded = 3;
+        let second_loaded = 3;
+
+        // the first one saves
+        let stored = next_revision(first_loaded, 3).unwrap();
+        assert_eq!(stored, 4);
+
+        // the second one would overwrite what the first one saved
+        assert_eq!(
+            next_revision(second_loaded, stored),
+            Err(StateConflict {
+                loaded_revision: 3,
+                stored_revision: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn successive_saves_of_one_run() {
+        let first = next_revision(3, 3).unwrap();
+
+        assert_eq!(next_revision(first, first), Ok(5));
+    }
+
+    #[test_case(r#"{"schema_version":1,"revision":7,"platforms":{}}"#, 7; "revision")]
+    #[test_case(r#"{"schema_version":1,"platforms":{}}"#, 0; "before revisions")]
+    #[test_case(r#"{"android":{},"ios":{},"desktop":{}}"#, 0; "unversioned")]
+    fn stored_revision(text: &str, result: u64) {
+        assert_eq!(revision_of(text).unwrap(), result);
+    }
+
+    const PLATFORM_STATE_JSON: &str = r#"{"last_posted_tag_previous_release":{"name":"v1.1.0"},"last_posted_tag":{"name":"v1.2.3"},"last_post_number":2,"last_post_id":42}"#;
+
+    #[test]
+    fn platform_state_without_post_details() {
+        assert_eq!(
+            serde_json::from_str::<PlatformState>(PLATFORM_STATE_JSON).unwrap(),
+            platform_state("v1.2.3")
+        );
+    }
+
+    #[test]
+    fn platform_state_without_completeness_rationale() {
+        let json = r#"{"last_posted_tag_previous_release":{"name":"v1.1.0"},"last_posted_tag":{"name":"v1.2.3"},"last_post_number":2,"last_post_id":42,"localization_changes_completeness":"LikelyComplete"}"#;
+
+        let platform_state = serde_json::from_str::<PlatformState>(json).unwrap();
+
+        assert_eq!(
+            platform_state.localization_changes_completeness,
+            Completeness::LikelyComplete
+        );
+        assert_eq!(
+            platform_state.localization_changes_completeness_rationale,
+            None
+        );
+    }
+
+    #[test]
+    fn platform_state_with_completeness_rationale_round_trip() {
+        let mut platform_state = platform_state("v1.2.3");
+        platform_state.localization_changes_completeness = Completeness::Incomplete;
+        platform_state.localization_changes_completeness_rationale = Some(String::from(
+            "GitHub listed 300 changed files, its limit for a comparison, so some may be missing.",
+        ));
+
+        let json = serde_json::to_string(&platform_state).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<PlatformState>(&json).unwrap(),
+            platform_state
+        );
+    }
+
+    #[test_case(None, Some("1.2"); "old state")]
+    #[test_case(Some("1.3"), Some("1.3"); "recorded")]
+    fn localization_cycle(cycle: Option<&str>, result: Option<&str>) {
+        let mut platform_state = platform_state("v1.2.3");
+        platform_state.localization_changes_cycle = cycle.map(ToOwned::to_owned);
+
+        assert_eq!(platform_state.localization_cycle().as_deref(), result);
+    }
+
+    #[test]
+    fn platform_state_with_post_details_round_trip() {
+        let mut platform_state = platform_state("v1.2.3");
+        platform_state.last_post_url =
+            Some(String::from("https://community.signalusers.org/t/123/2"));
+        platform_state.last_posted_at = Some(String::from("2022-07-02T00:00:00.000Z"));
+        platform_state.post_attempts = 3;
+
+        let json = serde_json::to_string(&platform_state).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<PlatformState>(&json).unwrap(),
+            platform_state
+        );
+    }
+
+    #[test]
+    fn migrate_unversioned_state() {
+        let value: Value = serde_json::from_str(&format!(
+            r#"{{"android":{PLATFORM_STATE_JSON},"ios":{PLATFORM_STATE_JSON},"desktop":{PLATFORM_STATE_JSON}}}"#
+        ))
+        .unwrap();
+
+        assert_eq!(migrate(value).unwrap(), (stored(0), true));
+    }
+
+    #[test]
+    fn current_state_is_not_migrated() {
+        let value: Value = serde_json::from_str(&format!(
+            r#"{{"schema_version":1,"platforms":{{"android":{PLATFORM_STATE_JSON},"ios":{PLATFORM_STATE_JSON},"desktop":{PLATFORM_STATE_JSON}}}}}"#
+        ))
+        .unwrap();
+
+        assert_eq!(migrate(value).unwrap(), (stored(0), false));
+    }
+
+    #[test]
+    fn newer_state_is_not_loaded() {
+        let value = serde_json::json!({ "schema_version": SCHEMA_VERSION + 1, "platforms": {} });
+
+        assert_eq!(
+            migrate(value).unwrap_err().to_string(),
+            "state has schema_version = 2, newer than supported 1"
+        );
+    }
+
+    #[test]
+    fn unversioned_state_missing_platform() {
+        let value: Value = serde_json::from_str(&format!(
+            r#"{{"android":{PLATFORM_STATE_JSON},"ios":{PLATFORM_STATE_JSON}}}"#
+        ))
+        .unwrap();
+
+        assert!(migrate(value).is_err());
+    }
+
+    #[test]
+    fn backup_from_value() {
+        let value = serde_json::to_value(StoredState {
+            schema_version: SCHEMA_VERSION,
+            revision: 0,
+            saved_at: None,
+            platforms: state(),
+        })
+        .unwrap();
+
+        assert_eq!(from_value(value).unwrap(), state());
+    }
+
+    #[test]
+    fn invalid_backup_from_value() {
+        let mut state = state();
+        state.ios.last_posted_tag = Tag::new("v1.0.0");
+
+        let value = serde_json::to_value(StoredState {
+            schema_version: SCHEMA_VERSION,
+            revision: 0,
+            saved_at: None,
+            platforms: state,
+        })
+        .unwrap();
+
+        assert_eq!(
+            format!("{:#}", from_value(value).unwrap_err()),
+            "invalid state: invalid state for iOS: last_posted_version_previous_release >= last_posted_version"
+        );
+    }
+
+    #[test]
+    fn stored_state_round_trip() {
+        let value = serde_json::to_value(stored(4)).unwrap();
+
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["revision"], 4);
+        assert_eq!(migrate(value).unwrap(), (stored(4), false));
+    }
+
+    fn patch(json: &str) -> PlatformStatePatch {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn empty_patch() {
+        assert_eq!(
+            platform_state("v1.2.3").patched(patch("{}")),
+            platform_state("v1.2.3")
+        );
+    }
+
+    #[test]
+    fn partial_patch() {
+        let patched = platform_state("v1.2.3").patched(patch(
+            r#"{"last_posted_tag":{"name":"v1.2.4"},"last_post_number":5,"last_error":null}"#,
+        ));
+
+        let mut expected = platform_state("v1.2.4");
+        expected.last_post_number = Some(5);
+
+        assert_eq!(patched, expected);
+    }
+
+    #[test]
+    fn patch_clears_optional_field() {
+        let patched = platform_state("v1.2.3").patched(patch(r#"{"last_post_id":null}"#));
+
+        let mut expected = platform_state("v1.2.3");
+        expected.last_post_id = None;
+
+        assert_eq!(patched, expected);
+    }
+
+    #[test]
+    fn patch_with_unknown_field() {
+        assert!(serde_json::from_str::<PlatformStatePatch>(r#"{"last_posted":5}"#).is_err());
+    }
+
+    #[test]
+    fn invalid_patch() {
+        let patched = platform_state("v1.2.3").patched(patch(
+            r#"{"last_posted_tag_previous_release":{"name":"v1.3.0"}}"#,
+        ));
+
+        assert_eq!(
+            patched.validate().unwrap_err().to_string(),
+            "last_posted_version_previous_release >= last_posted_version"
+        );
+    }
+
+    #[test]
+    fn paused_round_trip() {
+        let mut state = state();
+        state.desktop.enabled = false;
+
+        let deserialized: State =
+            serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+
+        assert_eq!(deserialized, state);
+        assert!(deserialized.is_paused(Desktop));
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+    thread::LocalKey,
+};
+
+/// Value of the future being polled, to be declared with `thread_local!`, see [`scope`].
+pub type Current<T> = RefCell<Option<Rc<T>>>;
+
+pub type Key<T> = LocalKey<Current<T>>;
+
+/// Future that has its own value of a [`Key`] while it is polled, see [`scope`].
+pub struct Scoped<T: 'static, F> {
+    key: &'static Key<T>,
+    value: Option<Rc<T>>,
+    future: Pin<Box<F>>,
+}
+
+impl<T, F: Future> Future for Scoped<T, F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = &mut *self;
+        let previous = this.key.with(|current| current.replace(this.value.clone()));
+
+        let poll = this.future.as_mut().poll(cx);
+
+        this.key.with(|current| *current.borrow_mut() = previous);
+        poll
+    }
+}
+
+/// Runs `future` with `value` as the value of `key`.
+///
+/// Invocations handled concurrently by the same isolate share its globals, so the value
+/// is only current while `future` is polled, instead of being set for the whole isolate.
+pub fn scope<T, F: Future>(key: &'static Key<T>, value: T, future: F) -> Scoped<T, F> {
+    Scoped {
+        key,
+        value: Some(Rc::new(value)),
+        future: Box::pin(future),
+    }
+}
+
+/// Runs `future` with the value of `key` that is current now, e.g. for work that
+/// is finished after the handler returns.
+pub fn in_current_scope<T, F: Future>(key: &'static Key<T>, future: F) -> Scoped<T, F> {
+    Scoped {
+        key,
+        value: current(key),
+        future: Box::pin(future),
+    }
+}
+
+/// Returns the value of `key` for the future being polled, or `None` outside of [`scope`].
+pub fn current<T>(key: &'static Key<T>) -> Option<Rc<T>> {
+    key.with(|current| current.borrow().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::core::poll_once;
+
+    thread_local! {
+        static VALUE: Current<&'static str> = const { RefCell::new(None) };
+    }
+
+    fn value() -> Option<&'static str> {
+        current(&VALUE).map(|value| *value)
+    }
+
+    /// Returns the value before and after waiting once, like an invocation that waits on a request.
+    async fn values_around_wait() -> (Option<&'static str>, Option<&'static str>) {
+        let before = value();
+
+        let mut waited = false;
+        std::future::poll_fn(|_| {
+            if std::mem::replace(&mut waited, true) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+
+        (before, value())
+    }
+
+    #[test]
+    fn interleaved_scopes_keep_their_values() {
+        let mut first = pin!(scope(&VALUE, "first", values_around_wait()));
+        let mut second = pin!(scope(&VALUE, "second", values_around_wait()));
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+
+        assert!(first.as_mut().poll(&mut cx).is_pending());
+        assert!(second.as_mut().poll(&mut cx).is_pending());
+
+        assert_eq!(
+            first.as_mut().poll(&mut cx),
+            Poll::Ready((Some("first"), Some("first")))
+        );
+        assert_eq!(
+            second.as_mut().poll(&mut cx),
+            Poll::Ready((Some("second"), Some("second")))
+        );
+        assert_eq!(value(), None);
+    }
+
+    #[test]
+    fn deferred_work_keeps_value() {
+        let mut deferred = None;
+        poll_once(scope(&VALUE, "outer", async {
+            deferred = Some(in_current_scope(&VALUE, async { value() }));
+        }));
+
+        assert_eq!(poll_once(deferred.unwrap()), Some("outer"));
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::*;
+
+#[derive(Deserialize, Debug)]
+pub struct PostApiResponse {
+    pub id: Option<u64>,
+    pub post_number: Option<u64>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EditPostApiResponse {
+    pub post: Option<PostApiResponse>,
+}
+
+/// Body of an error response, e.g. for a rejected post.
+#[derive(Deserialize, Debug)]
+pub struct ErrorResponse {
+    #[serde(default)]
+    pub errors: Vec<String>,
+    pub error_type: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PostRawResponse {
+    pub raw: Option<String>,
+    pub version: Option<u64>,
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(default)]
+    pub post_number: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TopicResponse {
+    pub post_stream: Option<PostStream>,
+    #[serde(default)]
+    pub highest_post_number: Option<u64>,
+
+    pub error_type: Option<String>,
+
+    #[serde(flatten)]
+    pub other_fields: HashMap<String, Value>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PostStream {
+    pub posts: Vec<Post>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Post {
+    pub topic_id: u64,
+}
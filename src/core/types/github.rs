@@ -0,0 +1,649 @@
+use anyhow::{anyhow, Context};
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::{BuildMetadata, Prerelease, Version};
+use serde::Serialize;
+
+use super::*;
+use crate::core::log::log_warn;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<TagCommit>,
+}
+
+lazy_static! {
+    /// A `YYYYMMDD` datestamp at the end of a prerelease, like in `6.0.0-nightly.20230101`.
+    static ref DATESTAMP_REGEX: Regex = Regex::new(
+        r"(?:^|[.-])((?:19|20)\d{2}(?:0[1-9]|1[0-2])(?:0[1-9]|[12]\d|3[01]))$"
+    )
+    .unwrap();
+}
+
+/// Moves the parts of `version` that lenient_semver parses into the prerelease, but that
+/// are not one, into the build metadata: a `-YYYYMMDD` datestamp (`6.0.0-20230101`),
+/// and the fifth component of internal builds (the `3` of `5.27.0.0-3`).
+fn normalized(version: Version) -> anyhow::Result<Version> {
+    let mut pre = version.pre.as_str();
+    let mut build: Vec<&str> = Some(version.build.as_str())
+        .filter(|build| !build.is_empty())
+        .into_iter()
+        .collect();
+
+    let mut datestamp = None;
+    if let Some(captures) = DATESTAMP_REGEX.captures(pre) {
+        let whole = captures.get(0).unwrap();
+        datestamp = Some(captures.get(1).unwrap().as_str());
+        pre = &pre[..whole.start()];
+    }
+
+    let is_fourth_component =
+        |build: &[&str]| build.len() == 1 && build[0].chars().all(|c| c.is_ascii_digit());
+
+    if !pre.is_empty() && pre.chars().all(|c| c.is_ascii_digit()) && is_fourth_component(&build) {
+        build.push(pre);
+        pre = "";
+    }
+
+    build.extend(datestamp);
+
+    Ok(Version {
+        pre: Prerelease::new(pre).context("could not normalize prerelease")?,
+        build: BuildMetadata::new(&build.join("."))
+            .context("could not normalize build metadata")?,
+        ..version
+    })
+}
+
+/// Strips only a leading `v` or `V` from `tag_name`, so that one elsewhere
+/// (as in `v7.2.0-preview`) is kept.
+pub fn version_string(tag_name: &str) -> &str {
+    tag_name.strip_prefix(['v', 'V']).unwrap_or(tag_name)
+}
+
+/// Length of the SHAs that are shown, as abbreviated by git.
+pub const SHORT_SHA_LEN: usize = 7;
+
+/// The first [`SHORT_SHA_LEN`] characters of `sha`, for showing it; the full SHA
+/// is still used in URLs.
+///
+/// Returns `sha` as is (and logs a warning) if it does not look like a SHA,
+/// i.e. it is shorter than that or is not lowercase hex.
+pub fn short_sha(sha: &str) -> &str {
+    let is_lowercase_hex = sha
+        .chars()
+        .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c));
+
+    if sha.len() >= SHORT_SHA_LEN && is_lowercase_hex {
+        &sha[..SHORT_SHA_LEN]
+    } else {
+        log_warn!("{sha:?} does not look like a SHA, showing it as is");
+        sha
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TagCommit {
+    pub sha: String,
+}
+
+impl Tag {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            commit: None,
+        }
+    }
+
+    /// The name of the tag without its leading `v` (see [`version_string`]).
+    pub fn exact_version_string(&self) -> String {
+        version_string(&self.name).to_string()
+    }
+
+    pub fn sha(&self) -> Option<&str> {
+        self.commit.as_ref().map(|commit| commit.sha.as_str())
+    }
+
+    /// The SHA of the tag for showing it (see [`short_sha`]).
+    pub fn short_sha(&self) -> Option<&str> {
+        self.sha().map(short_sha)
+    }
+
+    /// Indicates whether `self` is the same tag as `previous`, but re-cut
+    /// (deleted and pushed again) to point at a different commit.
+    ///
+    /// Returns `false` if the commit of either tag is unknown.
+    pub fn is_recut_of(&self, previous: &Tag) -> bool {
+        match (self.sha(), previous.sha()) {
+            (Some(sha), Some(previous_sha)) => self.name == previous.name && sha != previous_sha,
+            _ => false,
+        }
+    }
+
+    /// Parses the version of the tag, leniently (see [`normalized`]).
+    pub fn to_version(&self) -> anyhow::Result<Version> {
+        lenient_semver::parse(&self.name)
+            .map_err(|e| anyhow!(e.to_string()))
+            .and_then(normalized)
+            .context("could not parse version from tag")
+    }
+
+    pub fn with_sha(name: impl Into<String>, sha: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            commit: Some(TagCommit { sha: sha.into() }),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub status: ComparisonStatus,
+    pub ahead_by: usize,
+    pub total_commits: usize,
+    pub commits: Vec<Commit>,
+    pub files: Option<Vec<File>>,
+
+    #[serde(default)]
+    pub merge_base_commit: Option<MergeBaseCommit>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MergeBaseCommit {
+    pub sha: String,
+}
+
+/// How the new tag of a comparison relates to the old one.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonStatus {
+    Ahead,
+    Behind,
+    Diverged,
+    Identical,
+}
+
+impl ComparisonStatus {
+    pub const DIVERGED_NOTICE: &'static str =
+        "Branch history diverged; commit list may be incomplete.";
+
+    /// A new tag that is behind the old one has no new commits,
+    /// so the commits of the comparison are not worth posting.
+    pub fn should_post(&self) -> bool {
+        *self != Self::Behind
+    }
+
+    pub fn notice(&self) -> Option<&'static str> {
+        match self {
+            Self::Diverged => Some(Self::DIVERGED_NOTICE),
+            _ => None,
+        }
+    }
+}
+
+impl Comparison {
+    /// GitHub API only returns at most this many commits in a comparison, according to
+    /// https://docs.github.com/en/rest/commits/commits#compare-two-commits.
+    pub const GITHUB_API_MAX_COMMITS: usize = 250;
+
+    /// GitHub API only returns at most this many files in a comparison, despite
+    /// https://docs.github.com/en/rest/commits/commits#compare-two-commits
+    /// saying that it always returns all.
+    pub const GITHUB_API_MAX_FILES: usize = 300;
+
+    /// Indicates whether `files` in this comparison is likely complete or not.
+    /// (see also [`GITHUB_API_MAX_FILES`]).
+    ///
+    /// Returns `None` if `self.files` is `None`.
+    pub fn are_files_likely_complete(&self) -> Option<bool> {
+        self.files
+            .as_ref()
+            .map(|files| files.len() < Self::GITHUB_API_MAX_FILES)
+    }
+
+    /// Number of commits in the new tag that are not in the old one; if history diverged,
+    /// `commits` may not include all of them, so `ahead_by` is trusted instead.
+    pub fn new_commits_len(&self) -> usize {
+        match self.status {
+            ComparisonStatus::Diverged => self.ahead_by.max(self.commits.len()),
+            _ => self.commits.len(),
+        }
+    }
+
+    /// Whether there are no new commits, e.g. because the new tag was pushed
+    /// pointing at the same commit as the old one.
+    pub fn is_empty(&self) -> bool {
+        self.new_commits_len() == 0
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    pub sha: String,
+    pub commit: CommitData,
+    /// GitHub account of the author, if their email is linked to one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<Account>,
+    pub files: Option<Vec<File>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub login: String,
+}
+
+impl Commit {
+    /// GitHub API only returns at most this many files in a commit, according to
+    /// https://docs.github.com/en/rest/commits/commits#get-a-commit.
+    pub const GITHUB_API_MAX_FILES: usize = 3000;
+
+    /// Indicates whether `files` in this commit is likely complete or not.
+    /// (see also [`GITHUB_API_MAX_FILES`]).
+    ///
+    /// Returns `None` if `self.files` is `None`.
+    pub fn are_files_likely_complete(&self) -> Option<bool> {
+        self.files
+            .as_ref()
+            .map(|files| files.len() != Self::GITHUB_API_MAX_FILES)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommitData {
+    pub message: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<GitAuthor>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<Verification>,
+}
+
+/// Author of a commit as recorded by git.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct GitAuthor {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    pub verified: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct File {
+    pub filename: String,
+    #[serde(default)]
+    pub status: FileStatus,
+    /// Name of the file before it was renamed, if it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_filename: Option<String>,
+    /// Diff of the file, left out by GitHub for large or binary diffs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+#[cfg(test)]
+impl File {
+    /// A modified file.
+    pub fn new(filename: impl Into<String>) -> Self {
+        Self {
+            filename: filename.into(),
+            status: FileStatus::Modified,
+            previous_filename: None,
+            patch: None,
+        }
+    }
+}
+
+/// How a file was changed; the statuses that are not listed here
+/// (`copied`, `changed`, `unchanged`) count as modified.
+#[derive(
+    Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default,
+)]
+#[serde(rename_all = "lowercase", from = "String")]
+pub enum FileStatus {
+    Added,
+    Renamed,
+    #[default]
+    Modified,
+    Removed,
+}
+
+impl From<String> for FileStatus {
+    fn from(status: String) -> Self {
+        match status.as_str() {
+            "added" => Self::Added,
+            "renamed" => Self::Renamed,
+            "removed" => Self::Removed,
+            _ => Self::Modified,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub html_url: String,
+    /// Release notes.
+    pub body: Option<String>,
+}
+
+#[cfg(test)]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContentsEntry {
+    pub path: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::platform::{
+        Platform::{self, *},
+        DEFAULT_PRERELEASE_IDENTIFIERS,
+    };
+
+    fn test_version(pre: Option<&str>, build: Option<&str>) -> Version {
+        Version {
+            major: 1,
+            minor: 2,
+            patch: 3,
+            pre: match pre {
+                Some(pre) => Prerelease::new(pre).unwrap(),
+                None => Prerelease::EMPTY,
+            },
+            build: match build {
+                Some(build) => BuildMetadata::new(build).unwrap(),
+                None => BuildMetadata::EMPTY,
+            },
+        }
+    }
+
+    #[test_case("v1.2.3", test_version(None, None); "3 digits with v")]
+    #[test_case("1.2.3", test_version(None, None); "3 digits without v")]
+    #[test_case("v1.2.3.4", test_version(None, Some("4")); "4 digits with v")]
+    #[test_case("1.2.3.4", test_version(None, Some("4")); "4 digits without v")]
+    #[test_case("v1.2.3-beta.1", test_version(Some("beta.1"), None); "3 digits beta with v")]
+    #[test_case("1.2.3.4-beta", test_version(Some("beta"), Some("4")); "4 digits beta without v")]
+    #[test_case("v1.2.3-preview", test_version(Some("preview"), None); "v in prerelease")]
+    #[test_case("v1.2.3-beta.1+dev", test_version(Some("beta.1"), Some("dev")); "v in build")]
+    fn version_from_tag(tag: &str, result: Version) {
+        let version: Version = Tag::new(tag).to_version().unwrap();
+
+        assert_eq!(version, result);
+    }
+
+    #[test_case("v1.2.3", "1.2.3"; "leading v")]
+    #[test_case("V1.2.3", "1.2.3"; "leading uppercase v")]
+    #[test_case("1.2.3", "1.2.3"; "without v")]
+    #[test_case("v7.2.0-preview", "7.2.0-preview"; "v in prerelease")]
+    #[test_case("v7.2.0-beta.1+dev", "7.2.0-beta.1+dev"; "v in build")]
+    #[test_case("7.2.0-rev.2", "7.2.0-rev.2"; "v in prerelease without leading v")]
+    #[test_case("vv1.2.3", "v1.2.3"; "only one v is stripped")]
+    fn exact_version_string(tag: &str, result: &str) {
+        assert_eq!(Tag::new(tag).exact_version_string(), result);
+    }
+
+    #[test_case("6dcb09b5b57875f334f61aebed695e2e4193db5e", "6dcb09b"; "full")]
+    #[test_case("6dcb09b", "6dcb09b"; "already short")]
+    #[test_case("6dcb0", "6dcb0"; "shorter")]
+    #[test_case("6DCB09B5B57875F334F61AEBED695E2E4193DB5E", "6DCB09B5B57875F334F61AEBED695E2E4193DB5E"; "uppercase")]
+    #[test_case("not a sha at all", "not a sha at all"; "garbage")]
+    #[test_case("ünicode-sha", "ünicode-sha"; "multibyte garbage")]
+    #[test_case("", ""; "empty")]
+    fn short_sha(sha: &str, result: &str) {
+        assert_eq!(super::short_sha(sha), result);
+    }
+
+    #[test]
+    fn tag_short_sha() {
+        assert_eq!(
+            Tag::with_sha("v1.2.3", "6dcb09b5b57875f334f61aebed695e2e4193db5e").short_sha(),
+            Some("6dcb09b")
+        );
+        assert_eq!(Tag::new("v1.2.3").short_sha(), None);
+    }
+
+    #[test_case("v1.2.3.4-5", test_version(None, Some("4.5")); "5 components")]
+    #[test_case("v1.2.3-20230101", test_version(None, Some("20230101")); "datestamp")]
+    #[test_case("v1.2.3-nightly.20230101", test_version(Some("nightly"), Some("20230101")); "prerelease with datestamp")]
+    #[test_case("v1.2.3-nightly-20230101", test_version(Some("nightly"), Some("20230101")); "prerelease with dash datestamp")]
+    #[test_case("1.2.3.4-nightly.20240115", test_version(Some("nightly"), Some("4.20240115")); "4 digits prerelease with datestamp")]
+    #[test_case("v1.2.3-beta.12345678", test_version(Some("beta.12345678"), None); "number that is not a date")]
+    #[test_case("v1.2.3-5", test_version(Some("5"), None); "numeric prerelease without 4th component")]
+    fn normalized_version_from_tag(tag: &str, result: Version) {
+        assert_eq!(Tag::new(tag).to_version().unwrap(), result);
+    }
+
+    // tags of the Signal repositories, including internal and nightly builds
+    #[test_case(Android, "v7.7.0", "7.7.0", true; "Android release")]
+    #[test_case(Android, "v6.46.4", "6.46.4", true; "Android patch release")]
+    #[test_case(Android, "v5.27.0.0-3", "5.27.0+0.3", false; "Android internal build")]
+    #[test_case(Android, "v5.27.0.0", "5.27.0+0", false; "Android 4 components")]
+    #[test_case(Android, "v6.13.0-20230406", "6.13.0+20230406", false; "Android nightly")]
+    #[test_case(Ios, "7.8.0.17", "7.8.0+17", false; "iOS build")]
+    #[test_case(Ios, "6.44.0.5-beta", "6.44.0-beta+5", true; "iOS beta")]
+    #[test_case(Ios, "7.1.0.12-nightly.20240115", "7.1.0-nightly+12.20240115", false; "iOS nightly")]
+    #[test_case(Desktop, "v7.7.0-beta.1", "7.7.0-beta.1", true; "Desktop beta")]
+    #[test_case(Desktop, "v7.7.0", "7.7.0", false; "Desktop release")]
+    #[test_case(Desktop, "v1.40.0-beta.1", "1.40.0-beta.1", true; "Desktop old beta")]
+    #[test_case(Desktop, "v7.8.0-alpha.1-20240415", "7.8.0-alpha.1+20240415", false; "Desktop nightly")]
+    fn historical_tags(platform: Platform, tag: &str, version: &str, should_post: bool) {
+        let parsed = Tag::new(tag).to_version().unwrap();
+
+        assert_eq!(
+            (
+                parsed.to_string().as_str(),
+                platform.should_post_version(&parsed, DEFAULT_PRERELEASE_IDENTIFIERS)
+            ),
+            (version, should_post)
+        );
+    }
+
+    #[test_case("latest"; "word")]
+    #[test_case("release-candidate"; "words")]
+    fn tag_without_version(tag: &str) {
+        assert!(Tag::new(tag).to_version().is_err());
+    }
+
+    #[test_case(
+      &["v1.2.3", "v1.2.5", "v1.1.3", "v1.2.4"],
+      &["v1.1.3", "v1.2.3", "v1.2.4", "v1.2.5"];
+      "three digits"
+    )]
+    #[test_case(
+      &["1.2.3.3-beta", "1.1.3.3-beta", "1.2.3.5-beta", "1.2.3.4-beta"],
+      &["1.1.3.3-beta", "1.2.3.3-beta", "1.2.3.4-beta", "1.2.3.5-beta"];
+      "four digits beta"
+    )]
+    #[test_case(
+      &["v1.2.3-beta.1", "v1.2.3-beta.3", "v1.1.3-beta.1", "v1.2.3-beta.2"],
+      &["v1.1.3-beta.1", "v1.2.3-beta.1", "v1.2.3-beta.2", "v1.2.3-beta.3"];
+      "three digits beta dot digit"
+    )]
+    fn compare_versions(input: &[&str], output: &[&str]) {
+        let map = |x: &[&str]| {
+            x.iter()
+                .map(|tag_name| Tag::new(tag_name.to_string()).to_version().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let mut input: Vec<Version> = map(input);
+        let output = map(output);
+
+        input.sort_unstable();
+        assert_eq!(input, output);
+    }
+
+    #[test_case(Tag::with_sha("v1.2.3", "abc"), Tag::with_sha("v1.2.3", "abc"), false; "same sha")]
+    #[test_case(Tag::with_sha("v1.2.3", "def"), Tag::with_sha("v1.2.3", "abc"), true; "changed sha")]
+    #[test_case(Tag::with_sha("v1.2.4", "def"), Tag::with_sha("v1.2.3", "abc"), false; "different tag")]
+    #[test_case(Tag::with_sha("v1.2.3", "def"), Tag::new("v1.2.3"), false; "previous sha unknown")]
+    #[test_case(Tag::new("v1.2.3"), Tag::with_sha("v1.2.3", "abc"), false; "current sha unknown")]
+    fn is_recut_of(tag: Tag, previous: Tag, result: bool) {
+        assert_eq!(tag.is_recut_of(&previous), result);
+    }
+
+    #[test]
+    fn tag_deserialization() {
+        let input = r#"{
+            "name": "v1.2.3",
+            "commit": {
+                "sha": "c5b97d5ae6c19d5c5df71a34c7fbeeda2479ccbc",
+                "url": "https://api.github.com/repos/octocat/Hello-World/commits/c5b97d5ae6c19d5c5df71a34c7fbeeda2479ccbc"
+            },
+            "zipball_url": "https://github.com/octocat/Hello-World/zipball/v0.1",
+            "tarball_url": "https://github.com/octocat/Hello-World/tarball/v0.1",
+            "node_id": "MDQ6VXNlcjE="
+        }"#;
+
+        assert_eq!(
+            serde_json::from_str::<Tag>(input).unwrap(),
+            Tag::with_sha("v1.2.3", "c5b97d5ae6c19d5c5df71a34c7fbeeda2479ccbc")
+        );
+
+        // tags stored in state before commits were tracked
+        assert_eq!(
+            serde_json::from_str::<Tag>(r#"{"name": "v1.2.3"}"#).unwrap(),
+            Tag::new("v1.2.3")
+        );
+    }
+
+    fn comparison_with_status(status: &str, ahead_by: usize, commits: usize) -> Comparison {
+        serde_json::from_value(serde_json::json!({
+            "status": status,
+            "ahead_by": ahead_by,
+            "total_commits": commits,
+            "commits": (0..commits)
+                .map(|index| serde_json::json!({ "sha": format!("sha{index}"), "commit": { "message": "Test commit." } }))
+                .collect::<Vec<_>>(),
+            "files": [],
+        }))
+        .unwrap()
+    }
+
+    #[test_case("ahead", 3, 3, ComparisonStatus::Ahead, true, None, 3; "ahead")]
+    #[test_case("behind", 0, 0, ComparisonStatus::Behind, false, None, 0; "behind")]
+    #[test_case("diverged", 5, 3, ComparisonStatus::Diverged, true, Some(ComparisonStatus::DIVERGED_NOTICE), 5; "diverged")]
+    #[test_case("identical", 0, 0, ComparisonStatus::Identical, true, None, 0; "identical")]
+    fn comparison_status(
+        status: &str,
+        ahead_by: usize,
+        commits: usize,
+        expected_status: ComparisonStatus,
+        expected_should_post: bool,
+        expected_notice: Option<&str>,
+        expected_new_commits_len: usize,
+    ) {
+        let comparison = comparison_with_status(status, ahead_by, commits);
+
+        assert_eq!(comparison.status, expected_status);
+        assert_eq!(comparison.status.should_post(), expected_should_post);
+        assert_eq!(comparison.status.notice(), expected_notice);
+        assert_eq!(comparison.new_commits_len(), expected_new_commits_len);
+    }
+
+    #[test_case("ahead", 3, 3, false; "ahead")]
+    #[test_case("identical", 0, 0, true; "identical")]
+    #[test_case("ahead", 0, 0, true; "ahead without commits")]
+    #[test_case("diverged", 2, 0, false; "diverged without listed commits")]
+    fn comparison_is_empty(status: &str, ahead_by: usize, commits: usize, result: bool) {
+        assert_eq!(
+            comparison_with_status(status, ahead_by, commits).is_empty(),
+            result
+        );
+    }
+
+    #[test_case("ahead"; "ahead")]
+    #[test_case("diverged"; "diverged")]
+    fn comparison_round_trip(status: &str) {
+        let mut comparison = comparison_with_status(status, 3, 3);
+        comparison.merge_base_commit = Some(MergeBaseCommit {
+            sha: String::from("abcdef"),
+        });
+
+        let serialized = serde_json::to_string(&comparison).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Comparison>(&serialized).unwrap(),
+            comparison
+        );
+    }
+
+    #[test]
+    fn example_comparison_round_trip() {
+        let comparison: Comparison =
+            serde_json::from_str(include_str!("github_comparison_example.json")).unwrap();
+
+        let serialized = serde_json::to_string(&comparison).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Comparison>(&serialized).unwrap(),
+            comparison
+        );
+    }
+
+    #[test]
+    fn release_deserialization() {
+        assert_eq!(
+            serde_json::from_str::<Release>(
+                r#"{"html_url":"https://github.com/signalapp/Signal-Desktop/releases/tag/v1.2.3-beta.1","tag_name":"v1.2.3-beta.1","body":"Fixes."}"#
+            )
+            .unwrap(),
+            Release {
+                html_url: String::from(
+                    "https://github.com/signalapp/Signal-Desktop/releases/tag/v1.2.3-beta.1"
+                ),
+                body: Some(String::from("Fixes.")),
+            }
+        );
+    }
+
+    #[test]
+    fn comparison_deserialization() {
+        // Example from https://docs.github.com/en/rest/commits/commits#compare-two-commits
+        let input = include_str!("github_comparison_example.json");
+
+        assert_eq!(
+            serde_json::from_str::<Comparison>(input).unwrap(),
+            Comparison {
+                status: ComparisonStatus::Behind,
+                ahead_by: 1,
+                total_commits: 1,
+                commits: vec![Commit {
+                    sha: "6dcb09b5b57875f334f61aebed695e2e4193db5e".to_string(),
+                    commit: CommitData {
+                        message: "Fix all the bugs".to_string(),
+                        author: Some(GitAuthor {
+                            name: "Monalisa Octocat".to_string(),
+                            email: "mona@github.com".to_string(),
+                        }),
+                        verification: Some(Verification { verified: false }),
+                    },
+                    author: Some(Account {
+                        login: "octocat".to_string(),
+                    }),
+                    files: None,
+                }],
+                files: Some(vec![File {
+                    filename: "file1.txt".to_string(),
+                    status: FileStatus::Added,
+                    previous_filename: None,
+                    patch: Some(
+                        "@@ -132,7 +132,7 @@ module Test @@ -1000,7 +1000,7 @@ module Test"
+                            .to_string()
+                    ),
+                }]),
+                merge_base_commit: Some(MergeBaseCommit {
+                    sha: "6dcb09b5b57875f334f61aebed695e2e4193db5e".to_string()
+                }),
+            }
+        );
+    }
+}
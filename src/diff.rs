@@ -0,0 +1,302 @@
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// One line of the edit script; the indices are of the line in the respective text, or
+/// for lines only in the other text, of the line it comes before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edit {
+    change: Change,
+    old_index: usize,
+    new_index: usize,
+}
+
+/// Shortest edit script turning `old` into `new`, with deletions before insertions.
+fn edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+    let (n, m) = (old_middle.len(), new_middle.len());
+
+    // lcs[i][j] is the length of the longest common subsequence of old_middle[i..] and new_middle[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = match old_middle[i] == new_middle[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let mut edits: Vec<Edit> = (0..prefix)
+        .map(|i| Edit {
+            change: Change::Equal,
+            old_index: i,
+            new_index: i,
+        })
+        .collect();
+
+    let edit = |change, i, j| Edit {
+        change,
+        old_index: prefix + i,
+        new_index: prefix + j,
+    };
+    let (mut i, mut j) = (0, 0);
+
+    while i < n || j < m {
+        if i < n && j < m && old_middle[i] == new_middle[j] {
+            edits.push(edit(Change::Equal, i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            edits.push(edit(Change::Delete, i, j));
+            i += 1;
+        } else {
+            edits.push(edit(Change::Insert, i, j));
+            j += 1;
+        }
+    }
+
+    edits.extend((0..suffix).map(|k| edit(Change::Equal, n + k, m + k)));
+
+    edits
+}
+
+/// Ranges of `edits` that make up the hunks, each with up to `context` unchanged lines
+/// around the changes; hunks that would overlap are merged.
+fn hunks(edits: &[Edit], context: usize) -> Vec<(usize, usize)> {
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+
+    for (index, _) in edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| edit.change != Change::Equal)
+    {
+        let start = index.saturating_sub(context);
+        let end = (index + 1 + context).min(edits.len());
+
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    hunks
+}
+
+/// Range of a hunk header, where a range without lines starts at the line before it.
+fn range(index: usize, len: usize) -> String {
+    match len {
+        0 => format!("{index},0"),
+        1 => format!("{}", index + 1),
+        len => format!("{},{len}", index + 1),
+    }
+}
+
+/// Unified diff of the lines of `old` and `new`, with `context` unchanged lines around
+/// the changes; empty if there are no differences.
+///
+/// Whether the texts end with a newline is not compared.
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+    context: usize,
+) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let edits = edits(&old, &new);
+
+    let mut diff = String::new();
+
+    for (start, end) in hunks(&edits, context) {
+        if diff.is_empty() {
+            let _ = writeln!(diff, "--- {old_name}\n+++ {new_name}");
+        }
+
+        let hunk = &edits[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|edit| edit.change != Change::Insert)
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|edit| edit.change != Change::Delete)
+            .count();
+
+        let _ = writeln!(
+            diff,
+            "@@ -{} +{} @@",
+            range(hunk[0].old_index, old_len),
+            range(hunk[0].new_index, new_len)
+        );
+
+        for edit in hunk {
+            let _ = match edit.change {
+                Change::Equal => writeln!(diff, " {}", old[edit.old_index]),
+                Change::Delete => writeln!(diff, "-{}", old[edit.old_index]),
+                Change::Insert => writeln!(diff, "+{}", new[edit.new_index]),
+            };
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> String {
+        unified_diff(old, new, "posted", "rerendered", 1)
+    }
+
+    #[test_case("", ""; "empty")]
+    #[test_case("a\nb\nc", "a\nb\nc"; "same")]
+    #[test_case("a\nb\nc\n", "a\nb\nc"; "trailing newline")]
+    fn no_differences(old: &str, new: &str) {
+        assert_str_eq!(diff(old, new), "");
+    }
+
+    #[test]
+    fn changed_line() {
+        assert_str_eq!(
+            diff("a\nb\nc\nd\ne", "a\nb\nC\nd\ne"),
+            "--- posted
++++ rerendered
+@@ -2,3 +2,3 @@
+ b
+-c
++C
+ d
+"
+        );
+    }
+
+    #[test]
+    fn added_lines() {
+        assert_str_eq!(
+            diff("a\nb", "a\nb\nc\nd"),
+            "--- posted
++++ rerendered
+@@ -2 +2,3 @@
+ b
++c
++d
+"
+        );
+    }
+
+    #[test]
+    fn removed_first_line() {
+        assert_str_eq!(
+            diff("a\nb\nc", "b\nc"),
+            "--- posted
++++ rerendered
+@@ -1,2 +1 @@
+-a
+ b
+"
+        );
+    }
+
+    #[test]
+    fn from_empty() {
+        assert_str_eq!(
+            diff("", "a\nb"),
+            "--- posted
++++ rerendered
+@@ -0,0 +1,2 @@
++a
++b
+"
+        );
+    }
+
+    #[test]
+    fn to_empty() {
+        assert_str_eq!(
+            diff("a", ""),
+            "--- posted
++++ rerendered
+@@ -1 +0,0 @@
+-a
+"
+        );
+    }
+
+    #[test]
+    fn separate_hunks() {
+        assert_str_eq!(
+            diff("1\n2\n3\n4\n5\n6\n7", "1\nX\n3\n4\n5\nY\n7"),
+            "--- posted
++++ rerendered
+@@ -1,3 +1,3 @@
+ 1
+-2
++X
+ 3
+@@ -5,3 +5,3 @@
+ 5
+-6
++Y
+ 7
+"
+        );
+    }
+
+    #[test]
+    fn close_changes_merged() {
+        assert_str_eq!(
+            diff("1\n2\n3\n4\n5", "1\nX\n3\nY\n5"),
+            "--- posted
++++ rerendered
+@@ -1,5 +1,5 @@
+ 1
+-2
++X
+ 3
+-4
++Y
+ 5
+"
+        );
+    }
+
+    #[test]
+    fn longest_common_lines_kept() {
+        assert_str_eq!(
+            unified_diff(
+                "| Version |\n| 1.2.3 |\n- Fix crash\n- Bump version",
+                "| Version |\n- Fix crash\n| 1.2.3 |\n- Bump version",
+                "posted",
+                "rerendered",
+                0
+            ),
+            "--- posted
++++ rerendered
+@@ -2 +1,0 @@
+-| 1.2.3 |
+@@ -3,0 +3 @@
++| 1.2.3 |
+"
+        );
+    }
+}
@@ -0,0 +1,1596 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use worker::{Env, Headers, Method, Request, Response};
+
+use crate::{
+    backfill::{self, BackfillRequest},
+    bootstrap::{self, BootstrapRequest},
+    check_platforms, comparison_cache, diff, feed, hash, health,
+    history::{self, HistoryEntry},
+    http, liveness,
+    localization::Completeness,
+    log::{self, log_error, log_info},
+    markdown::{PostMetadata, PostOptions},
+    platform::Platform,
+    prepare_post_from_comparison, rate_limit,
+    state::{self, PlatformState, PlatformStatePatch, State, StateController},
+    state_backup,
+    types::github::Tag,
+    utils,
+    PlatformCheckOutcome::RenderedPostInDryRun,
+    PlatformCheckResults,
+};
+
+/// Handles `request` if it is for one of the endpoints.
+///
+/// Returns `None` if there is no endpoint for `request`.
+pub async fn handle(request: &mut Request, env: &Env) -> Option<worker::Result<Response>> {
+    let path = request.path();
+    log_info!("handling request: {:?} {path}", request.method());
+
+    let result = match (request.method(), path.as_str()) {
+        (Method::Get, "/feed.xml") => feed_response(env).await,
+        (Method::Get, "/versions.json") => versions_response(request, env).await,
+        (Method::Get, path) if path.starts_with("/badge/") => badge_response(path, env).await,
+        (Method::Get, path) if path.starts_with("/changelog/") => {
+            changelog_response(request, path, env).await
+        }
+        (Method::Get, path) if path.starts_with("/preview/") => {
+            preview_response(request, path, env).await
+        }
+        (Method::Get, path) if path.starts_with("/rerender/") => {
+            rerender_response(request, path, env).await
+        }
+        (Method::Post, "/trigger") => trigger_response(request, env).await,
+        (Method::Post, "/backfill") => backfill_response(request, env).await,
+        (Method::Post, "/bootstrap") => bootstrap_response(request, env).await,
+        (Method::Get, "/state") => state_response(request, env).await,
+        (Method::Get, "/state/backups") => state_backups_response(request, env).await,
+        (Method::Post, "/state/restore") => restore_state_response(request, env).await,
+        (Method::Patch, path) if path.starts_with("/state/") => {
+            patch_state_response(request, path, env).await
+        }
+        (Method::Post, path) if path.starts_with("/pause/") => {
+            set_enabled_response(request, path, env, false).await
+        }
+        (Method::Post, path) if path.starts_with("/resume/") => {
+            set_enabled_response(request, path, env, true).await
+        }
+        (Method::Get, "/status") => status_response(env).await,
+        (Method::Get, "/healthz") => health_response(request, env).await,
+        _ => return None,
+    };
+
+    Some(match result {
+        Ok(response) => Ok(response),
+        Err(e) => {
+            log_error!("{e:?}");
+            Response::error("Internal Server Error", 500)
+        }
+    })
+}
+
+fn response_with_headers(body: String, headers: &[(&str, &str)]) -> anyhow::Result<Response> {
+    let mut header_map = Headers::new();
+
+    for (name, value) in headers {
+        header_map
+            .set(name, value)
+            .map_err(|e| anyhow!(e.to_string()))
+            .with_context(|| format!("could not set header {name}"))?;
+    }
+
+    Response::ok(body)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create response")
+        .map(|response| response.with_headers(header_map))
+}
+
+async fn feed_response(env: &Env) -> anyhow::Result<Response> {
+    let mut entries = Vec::new();
+
+    for platform in Platform::iter() {
+        let platform_entries = history::entries(env, platform)
+            .await
+            .with_context(|| format!("could not get history for {platform}"))?;
+
+        entries.extend(platform_entries.into_iter().map(|entry| (platform, entry)));
+    }
+
+    response_with_headers(
+        feed::atom_feed(&entries),
+        &[
+            ("Content-Type", "application/atom+xml; charset=utf-8"),
+            ("Cache-Control", "public, max-age=600"),
+        ],
+    )
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct LatestVersion {
+    tag: String,
+    posted_at: Option<String>,
+    post_url: Option<String>,
+}
+
+impl LatestVersion {
+    /// `history` is expected to be newest first.
+    fn new(last_posted_tag: &Tag, history: &[HistoryEntry]) -> Self {
+        let entry = history
+            .first()
+            .filter(|entry| entry.tag == last_posted_tag.name);
+
+        Self {
+            tag: last_posted_tag.name.clone(),
+            posted_at: entry.map(|entry| entry.date.clone()),
+            post_url: entry.map(|entry| entry.post_url.clone()),
+        }
+    }
+
+    /// Like [`Self::new`], but from what the state has of the last post.
+    fn from_platform_state(platform_state: &PlatformState) -> Self {
+        Self {
+            tag: platform_state.last_posted_tag.name.clone(),
+            posted_at: platform_state.last_posted_at.clone(),
+            post_url: platform_state.last_post_url.clone(),
+        }
+    }
+}
+
+/// Loads the state for the public endpoints as it is in KV, see [`state::snapshot`],
+/// so that requests to them never repair or migrate it.
+async fn public_state(env: &Env) -> anyhow::Result<State> {
+    state::snapshot(env)
+        .await?
+        .map(|snapshot| snapshot.state)
+        .context("no state in KV")
+}
+
+fn etag(body: &str) -> String {
+    format!("\"{}\"", hash::sha256_string(body))
+}
+
+async fn versions_response(request: &Request, env: &Env) -> anyhow::Result<Response> {
+    let state = public_state(env).await?;
+
+    let mut versions = BTreeMap::new();
+
+    for platform in Platform::iter() {
+        let history = history::entries(env, platform)
+            .await
+            .with_context(|| format!("could not get history for {platform}"))?;
+
+        versions.insert(
+            platform.to_string().to_ascii_lowercase(),
+            LatestVersion::new(&state.platform_state(platform).last_posted_tag, &history),
+        );
+    }
+
+    let body = serde_json::to_string(&versions).context("could not serialize versions")?;
+    let etag = etag(&body);
+
+    let if_none_match = request
+        .headers()
+        .get("If-None-Match")
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get If-None-Match header")?;
+
+    if if_none_match.as_deref() == Some(etag.as_str()) {
+        log_info!("ETag matches, not modified");
+        return Ok(response_with_headers(String::new(), &[("ETag", &etag)])?.with_status(304));
+    }
+
+    response_with_headers(
+        body,
+        &[
+            ("Content-Type", "application/json"),
+            ("Cache-Control", "public, max-age=60"),
+            ("ETag", &etag),
+        ],
+    )
+}
+
+/// Entries returned by `/changelog/{platform}.json` unless `limit` is given.
+const DEFAULT_CHANGELOG_LIMIT: usize = 20;
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct ChangelogEntry {
+    version: String,
+    tag: String,
+    date: String,
+    commits_count: usize,
+    post_url: String,
+    /// Not recorded for older entries, in which case it is omitted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    commit_subjects: Vec<String>,
+}
+
+impl From<&HistoryEntry> for ChangelogEntry {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            version: entry.version.clone(),
+            tag: entry.tag.clone(),
+            date: entry.date.clone(),
+            commits_count: entry.commits_count,
+            post_url: entry.post_url.clone(),
+            commit_subjects: entry.commit_subjects.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct Changelog {
+    entries: Vec<ChangelogEntry>,
+    /// Value of `before` for the next page, if there are more entries.
+    next_before: Option<String>,
+}
+
+impl Changelog {
+    /// At most `limit` entries of `history`, newest first, that were posted before `before`.
+    ///
+    /// Dates are ISO 8601 timestamps in UTC, so they are compared as strings.
+    fn new(history: &[HistoryEntry], limit: usize, before: Option<&str>) -> Self {
+        let mut history: Vec<&HistoryEntry> = history
+            .iter()
+            .filter(|entry| !matches!(before, Some(before) if entry.date.as_str() >= before))
+            .collect();
+        history.sort_by(|a, b| b.date.cmp(&a.date));
+
+        let has_more = history.len() > limit;
+        history.truncate(limit);
+
+        Self {
+            next_before: match has_more {
+                true => history.last().map(|entry| entry.date.clone()),
+                false => None,
+            },
+            entries: history.into_iter().map(ChangelogEntry::from).collect(),
+        }
+    }
+}
+
+fn changelog_platform_name(path: &str) -> Option<&str> {
+    path.strip_prefix("/changelog/")
+        .and_then(|rest| rest.strip_suffix(".json"))
+}
+
+/// Parses `limit`, which must be between 1 and [`history::MAX_ENTRIES`].
+fn changelog_limit(limit: Option<&str>) -> Result<usize, String> {
+    match limit {
+        None => Ok(DEFAULT_CHANGELOG_LIMIT),
+        Some(limit) => match limit.parse() {
+            Ok(limit) if (1..=history::MAX_ENTRIES).contains(&limit) => Ok(limit),
+            _ => Err(format!(
+                "limit must be between 1 and {}",
+                history::MAX_ENTRIES
+            )),
+        },
+    }
+}
+
+async fn changelog_response(request: &Request, path: &str, env: &Env) -> anyhow::Result<Response> {
+    let platform = match changelog_platform_name(path).and_then(Platform::from_name) {
+        Some(platform) => platform,
+        None => return json_response(error_json("unknown platform"), 404),
+    };
+
+    let limit = match changelog_limit(query_parameter(request, "limit")?.as_deref()) {
+        Ok(limit) => limit,
+        Err(message) => return json_response(error_json(&message), 400),
+    };
+    let before = query_parameter(request, "before")?;
+
+    let history = history::entries(env, platform)
+        .await
+        .with_context(|| format!("could not get history for {platform}"))?;
+
+    let changelog = Changelog::new(&history, limit, before.as_deref());
+
+    response_with_headers(
+        serde_json::to_string(&changelog).context("could not serialize changelog")?,
+        &[
+            ("Content-Type", "application/json"),
+            ("Cache-Control", "public, max-age=60"),
+        ],
+    )
+}
+
+/// https://shields.io/endpoint
+#[derive(Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct Badge {
+    schema_version: u8,
+    label: String,
+    message: String,
+    color: &'static str,
+}
+
+impl Badge {
+    fn new(platform: Platform, last_posted_tag: &Tag) -> Self {
+        Self {
+            schema_version: 1,
+            label: format!("Signal {platform} beta"),
+            message: last_posted_tag.exact_version_string(),
+            color: "blue",
+        }
+    }
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn json_response(body: String, status_code: u16) -> anyhow::Result<Response> {
+    Ok(
+        response_with_headers(body, &[("Content-Type", "application/json")])?
+            .with_status(status_code),
+    )
+}
+
+fn query_parameter(request: &Request, name: &str) -> anyhow::Result<Option<String>> {
+    let url = request
+        .url()
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get request URL")?;
+
+    Ok(url
+        .query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned()))
+}
+
+/// Cached comparisons are bypassed with `?no_cache=1`.
+fn use_comparison_cache(request: &Request) -> anyhow::Result<bool> {
+    Ok(query_parameter(request, "no_cache")?.as_deref() != Some("1"))
+}
+
+fn bearer_token(request: &Request) -> anyhow::Result<Option<String>> {
+    let authorization = request
+        .headers()
+        .get("Authorization")
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get Authorization header")?;
+
+    Ok(authorization.and_then(|value| value.strip_prefix("Bearer ").map(ToOwned::to_owned)))
+}
+
+/// Whether `token` is `expected_token`; never if no token is expected, so that leaving
+/// `TRIGGER_TOKEN` empty does not open the endpoints to anyone.
+fn token_matches(token: Option<&str>, expected_token: &str) -> bool {
+    match token {
+        Some(token) if !expected_token.is_empty() => {
+            hash::constant_time_eq(token.as_bytes(), expected_token.as_bytes())
+        }
+        _ => false,
+    }
+}
+
+fn is_authorized(request: &Request, env: &Env) -> anyhow::Result<bool> {
+    let expected_token = utils::trigger_token(env).unwrap_or_default();
+
+    Ok(token_matches(
+        bearer_token(request)?.as_deref(),
+        &expected_token,
+    ))
+}
+
+fn badge_platform_name(path: &str) -> Option<&str> {
+    path.strip_prefix("/badge/")
+        .and_then(|rest| rest.strip_suffix(".json"))
+}
+
+async fn badge_response(path: &str, env: &Env) -> anyhow::Result<Response> {
+    let platform = match badge_platform_name(path).and_then(Platform::from_name) {
+        Some(platform) => platform,
+        None => {
+            return Ok(response_with_headers(
+                error_json("unknown platform"),
+                &[("Content-Type", "application/json")],
+            )?
+            .with_status(404))
+        }
+    };
+
+    let state = public_state(env).await?;
+    let badge = Badge::new(platform, &state.platform_state(platform).last_posted_tag);
+
+    response_with_headers(
+        serde_json::to_string(&badge).context("could not serialize badge")?,
+        &[
+            ("Content-Type", "application/json"),
+            ("Cache-Control", "public, max-age=600"),
+        ],
+    )
+}
+
+/// Comparisons with more commits than this are not previewed.
+const MAX_PREVIEW_COMMITS: usize = 500;
+
+/// Splits `/preview/{platform}/{old_tag}/{new_tag}` into its parts.
+fn preview_path_parts(path: &str) -> Option<(&str, &str, &str)> {
+    let parts: Vec<&str> = path.strip_prefix("/preview/")?.split('/').collect();
+
+    match parts[..] {
+        [platform, old_tag, new_tag]
+            if !platform.is_empty() && !old_tag.is_empty() && !new_tag.is_empty() =>
+        {
+            Some((platform, old_tag, new_tag))
+        }
+        _ => None,
+    }
+}
+
+fn text_response(body: String, status_code: u16) -> anyhow::Result<Response> {
+    Ok(
+        response_with_headers(body, &[("Content-Type", "text/plain; charset=utf-8")])?
+            .with_status(status_code),
+    )
+}
+
+/// Renders the post for an arbitrary pair of tags, without consulting or changing state.
+async fn preview_response(request: &Request, path: &str, env: &Env) -> anyhow::Result<Response> {
+    // previews are made with the GitHub token of the bot, so not everyone can use it up
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let (platform_name, old_tag, new_tag) = match preview_path_parts(path) {
+        Some(parts) => parts,
+        None => {
+            return text_response(
+                String::from("expected /preview/{platform}/{old_tag}/{new_tag}"),
+                400,
+            )
+        }
+    };
+
+    let platform = match Platform::from_name(platform_name) {
+        Some(platform) => platform,
+        None => return text_response(String::from("unknown platform"), 404),
+    };
+
+    let old_tag = Tag::new(old_tag);
+    let new_tag = Tag::new(new_tag);
+
+    for tag in [&old_tag, &new_tag] {
+        if let Err(e) = tag.to_version() {
+            return text_response(format!("invalid tag {}: {e:#}", tag.name), 400);
+        }
+    }
+
+    match render_post(
+        env,
+        platform,
+        &old_tag,
+        &new_tag,
+        use_comparison_cache(request)?,
+    )
+    .await?
+    {
+        Ok(markdown_text) => text_response(markdown_text, 200),
+        Err(message) => text_response(message, 400),
+    }
+}
+
+/// Why a comparison with `total_commits` is not rendered, if it has too many of them.
+fn too_many_commits(old_tag: &Tag, new_tag: &Tag, total_commits: usize) -> Option<String> {
+    (total_commits > MAX_PREVIEW_COMMITS).then(|| {
+        format!(
+            "comparison between {} and {} has {total_commits} commits, at most {MAX_PREVIEW_COMMITS} can be previewed",
+            old_tag.name, new_tag.name
+        )
+    })
+}
+
+/// Why the post with `posted_raw` is not the one of `new_tag`, if its metadata is for another tag;
+/// posts without metadata are made by older versions of the bot and taken as they are.
+fn other_version_posted(posted_raw: &str, new_tag: &Tag) -> Option<String> {
+    PostMetadata::from_markdown(posted_raw)
+        .filter(|metadata| metadata.new_tag != new_tag.name)
+        .map(|metadata| format!("is for {}, not {}", metadata.new_tag, new_tag.name))
+}
+
+/// Renders the post for `old_tag` and `new_tag` like a preview, i.e. without state, such as
+/// localization changes; returns `Err` with the reason if there is nothing to render.
+async fn render_post(
+    env: &Env,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    use_comparison_cache: bool,
+) -> anyhow::Result<Result<String, String>> {
+    let github_token = utils::github_token(env);
+
+    // a single request tells the number of commits, before getting all pages of them
+    let total_commits = comparison_cache::get_github_comparison_files(
+        env,
+        &http::FetchClient,
+        github_token.as_deref(),
+        platform,
+        old_tag,
+        new_tag,
+        use_comparison_cache,
+    )
+    .await
+    .context("could not get number of commits from GitHub")?
+    .total_commits;
+
+    if let Some(message) = too_many_commits(old_tag, new_tag, total_commits) {
+        return Ok(Err(message));
+    }
+
+    let comparison = comparison_cache::get_github_comparison(
+        env,
+        &http::FetchClient,
+        github_token.as_deref(),
+        platform,
+        old_tag,
+        new_tag,
+        use_comparison_cache,
+    )
+    .await
+    .context("could not get build comparison from GitHub")?;
+
+    let dependency_bump_prefixes = utils::dependency_bump_prefixes(env)?;
+    let header_template = utils::post_header_template(env, platform)?;
+
+    let prepared_post = prepare_post_from_comparison(
+        &http::FetchClient,
+        github_token.as_deref(),
+        platform,
+        old_tag,
+        new_tag,
+        &comparison,
+        None,
+        None,
+        None,
+        &[],
+        PostOptions {
+            show_commit_verification: utils::show_commit_verification(env)?,
+            collapse_threshold: utils::collapse_threshold(env)?,
+            dependency_bump_prefixes: dependency_bump_prefixes.as_deref(),
+            inline_dependency_bumps: utils::inline_dependency_bumps(env)?,
+            commit_list_style: utils::commit_list_style(env)?,
+            commit_order: utils::commit_order(env, platform)?,
+            show_cycle_stats: false,
+            show_contributors: utils::show_contributors(env)?,
+            header_template: header_template.as_ref(),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    Ok(match prepared_post {
+        Some(prepared_post) => Ok(prepared_post.markdown_text),
+        None => Err(format!(
+            "{} is behind {}, nothing to preview",
+            new_tag.name, old_tag.name
+        )),
+    })
+}
+
+/// Splits `/rerender/{platform}/{version}` into its parts.
+fn rerender_path_parts(path: &str) -> Option<(&str, &str)> {
+    let parts: Vec<&str> = path.strip_prefix("/rerender/")?.split('/').collect();
+
+    match parts[..] {
+        [platform, version] if !platform.is_empty() && !version.is_empty() => {
+            Some((platform, version))
+        }
+        _ => None,
+    }
+}
+
+/// A previously posted version, as recorded in its history entry.
+#[derive(Debug, PartialEq, Eq)]
+struct PostedVersion {
+    old_tag: Tag,
+    new_tag: Tag,
+    topic_id: u64,
+    post_number: u64,
+}
+
+impl PostedVersion {
+    /// Finds `version` (or tag) in `history`, failing with a status code and the reason
+    /// if it cannot be re-rendered.
+    fn find(history: &[HistoryEntry], version: &str) -> Result<Self, (u16, String)> {
+        let entry = history
+            .iter()
+            .find(|entry| entry.version == version || entry.tag == version)
+            .ok_or_else(|| (404, format!("no history entry for {version}")))?;
+
+        let old_tag = entry.previous_tag.as_deref().ok_or_else(|| {
+            (
+                422,
+                format!("history entry for {version} does not record the previous tag"),
+            )
+        })?;
+
+        match (entry.topic_id, entry.post_number) {
+            (Some(topic_id), Some(post_number)) => Ok(Self {
+                old_tag: Tag::new(old_tag),
+                new_tag: Tag::new(&entry.tag),
+                topic_id,
+                post_number,
+            }),
+            _ => Err((
+                422,
+                format!("history entry for {version} does not record the post"),
+            )),
+        }
+    }
+}
+
+/// Renders the post of a previously posted version again, and returns a unified diff
+/// from the post on the forum to it; the diff is empty if they are the same.
+async fn rerender_response(request: &Request, path: &str, env: &Env) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let (platform_name, version) = match rerender_path_parts(path) {
+        Some(parts) => parts,
+        None => return json_response(error_json("expected /rerender/{platform}/{version}"), 400),
+    };
+
+    let platform = match Platform::from_name(platform_name) {
+        Some(platform) => platform,
+        None => return json_response(error_json("unknown platform"), 404),
+    };
+
+    let history = history::entries(env, platform)
+        .await
+        .with_context(|| format!("could not get history for {platform}"))?;
+
+    let posted_version = match PostedVersion::find(&history, version) {
+        Ok(posted_version) => posted_version,
+        Err((status_code, message)) => return json_response(error_json(&message), status_code),
+    };
+
+    let markdown_text = match render_post(
+        env,
+        platform,
+        &posted_version.old_tag,
+        &posted_version.new_tag,
+        use_comparison_cache(request)?,
+    )
+    .await?
+    {
+        Ok(markdown_text) => markdown_text,
+        Err(message) => return json_response(error_json(&message), 422),
+    };
+
+    let api_key = utils::api_key(env)?;
+    let posted = match utils::get_post_raw_by_number_in_discourse(
+        &http::FetchClient,
+        &api_key,
+        posted_version.topic_id,
+        posted_version.post_number,
+    )
+    .await
+    {
+        Ok(posted) => posted,
+        Err(e) if utils::is_not_found(&e) => {
+            return json_response(
+                error_json(&format!(
+                    "post {}/{} no longer exists",
+                    posted_version.topic_id, posted_version.post_number
+                )),
+                404,
+            )
+        }
+        Err(e) => return Err(e.context("could not get posted raw from Discourse")),
+    };
+
+    if let Some(message) = other_version_posted(&posted.raw, &posted_version.new_tag) {
+        return json_response(
+            error_json(&format!(
+                "post {}/{} {message}",
+                posted_version.topic_id, posted_version.post_number
+            )),
+            409,
+        );
+    }
+
+    text_response(
+        diff::unified_diff(
+            &posted.raw,
+            &markdown_text,
+            &utils::discourse_post_url(posted_version.topic_id, posted_version.post_number),
+            "rerendered",
+            3,
+        ),
+        200,
+    )
+}
+
+fn results_json(results: &PlatformCheckResults) -> String {
+    let map: serde_json::Map<_, _> = results
+        .iter()
+        .map(|(platform, result)| {
+            let value = match result {
+                Ok(
+                    outcome @ RenderedPostInDryRun {
+                        topic_id,
+                        markdown_text,
+                    },
+                ) => serde_json::json!({
+                    "outcome": outcome.description(),
+                    "topic_id": topic_id,
+                    "markdown": markdown_text,
+                }),
+                Ok(outcome) => serde_json::json!({ "outcome": outcome.description() }),
+                Err(e) => serde_json::json!({ "outcome": "error", "error": format!("{e:#}") }),
+            };
+
+            (platform.to_string().to_ascii_lowercase(), value)
+        })
+        .collect();
+
+    serde_json::Value::Object(map).to_string()
+}
+
+async fn trigger_response(request: &Request, env: &Env) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let platforms: Vec<Platform> = match query_parameter(request, "platform")? {
+        Some(name) => match Platform::from_name(&name) {
+            Some(platform) => vec![platform],
+            None => return json_response(error_json("unknown platform"), 400),
+        },
+        None => Platform::iter().collect(),
+    };
+
+    let force_dry_run = query_parameter(request, "dry_run")?.as_deref() == Some("1");
+
+    match check_platforms(
+        env,
+        &platforms,
+        force_dry_run,
+        use_comparison_cache(request)?,
+        // a manual check is for what is there right now
+        true,
+    )
+    .await
+    {
+        Ok(results) => json_response(results_json(&results), 200),
+        Err(e) => {
+            log_error!("{e:?}");
+            json_response(error_json(&format!("{e:#}")), 500)
+        }
+    }
+}
+
+async fn backfill_response(request: &mut Request, env: &Env) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let body = request
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get request body")?;
+
+    let backfill_request: BackfillRequest = match serde_json::from_str(&body) {
+        Ok(backfill_request) => backfill_request,
+        Err(e) => return json_response(error_json(&format!("invalid request: {e}")), 400),
+    };
+
+    log_info!("backfill_request = {backfill_request:?}");
+
+    let platform = match Platform::from_name(&backfill_request.platform) {
+        Some(platform) => platform,
+        None => return json_response(error_json("unknown platform"), 400),
+    };
+
+    let force_dry_run = query_parameter(request, "dry_run")?.as_deref() == Some("1");
+
+    let result = backfill::backfill(
+        env,
+        platform,
+        &Tag::new(backfill_request.previous_tag),
+        &Tag::new(backfill_request.new_tag),
+        backfill_request.topic_id,
+        backfill_request.advance_state,
+        force_dry_run,
+    )
+    .await;
+
+    if let Err(e) = &result {
+        log_error!("{e:?}");
+    }
+
+    json_response(results_json(&vec![(platform, result)]), 200)
+}
+
+/// Sets up the initial state, which is only replaced if `force` is set.
+async fn bootstrap_response(request: &mut Request, env: &Env) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let body = request
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get request body")?;
+
+    let bootstrap_request: BootstrapRequest = match serde_json::from_str(&body) {
+        Ok(bootstrap_request) => bootstrap_request,
+        Err(e) => return json_response(error_json(&format!("invalid request: {e}")), 400),
+    };
+
+    log_info!("bootstrap_request = {bootstrap_request:?}");
+
+    if !bootstrap_request.force && state::exists(env).await? {
+        return json_response(
+            error_json("state already exists, set force to replace it"),
+            409,
+        );
+    }
+
+    let github_token = utils::github_token(env);
+
+    let state = match bootstrap::state_from_request(&bootstrap_request, |platform, tag| {
+        let github_token = github_token.clone();
+        async move { utils::get_tag_sha(github_token.as_deref(), platform, &tag).await }
+    })
+    .await?
+    {
+        Ok(state) => state,
+        Err(invalid) => return json_response(error_json(&invalid.message()), 400),
+    };
+
+    state::initialize(env, state.clone())
+        .await
+        .context("could not save initial state")?;
+
+    log_info!("saved initial state = {state:?}");
+
+    json_response(
+        serde_json::to_string(&state).context("could not serialize state")?,
+        201,
+    )
+}
+
+/// Renders `state` as pretty-printed JSON, with `validation_error` if it is not valid.
+fn state_json(state: &State, validation_error: Option<&str>) -> anyhow::Result<String> {
+    let mut value = serde_json::to_value(state).context("could not serialize state")?;
+
+    if let (Some(validation_error), Some(object)) = (validation_error, value.as_object_mut()) {
+        object.insert(
+            String::from("validation_error"),
+            serde_json::Value::from(validation_error),
+        );
+    }
+
+    serde_json::to_string_pretty(&value).context("could not serialize state")
+}
+
+/// Shows the state in KV as it is, even if it is not valid.
+async fn state_response(request: &Request, env: &Env) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let snapshot = match state::snapshot(env).await? {
+        Some(snapshot) => snapshot,
+        None => return json_response(error_json("no state in KV"), 404),
+    };
+
+    let validation_error = snapshot.state.validate().err().map(|e| format!("{e:#}"));
+    // in case a secret ended up in state, e.g. as part of an error
+    let body = log::redact(&state_json(&snapshot.state, validation_error.as_deref())?);
+
+    let schema_version = snapshot.schema_version.to_string();
+    let mut headers = vec![
+        ("Content-Type", "application/json"),
+        ("X-State-Schema-Version", schema_version.as_str()),
+    ];
+
+    if let Some(saved_at) = &snapshot.saved_at {
+        headers.push(("X-State-Last-Modified", saved_at));
+    }
+
+    response_with_headers(body, &headers)
+}
+
+async fn state_backups_response(request: &Request, env: &Env) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let names = state_backup::names(env).await?;
+
+    json_response(serde_json::json!({ "backups": names }).to_string(), 200)
+}
+
+/// Body of a request to restore a backup of the state.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+struct RestoreRequest {
+    /// Name of the backup, as listed by `/state/backups`.
+    backup: String,
+}
+
+async fn restore_state_response(request: &mut Request, env: &Env) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let body = request
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get request body")?;
+
+    let restore_request: RestoreRequest = match serde_json::from_str(&body) {
+        Ok(restore_request) => restore_request,
+        Err(e) => return json_response(error_json(&format!("invalid request: {e}")), 400),
+    };
+
+    log_info!("restore_request = {restore_request:?}");
+
+    let value = match state_backup::get(env, &restore_request.backup).await? {
+        Some(value) => value,
+        None => return json_response(error_json("backup not found"), 404),
+    };
+
+    let state = match state::from_value(value) {
+        Ok(state) => state,
+        Err(e) => {
+            return json_response(error_json(&format!("invalid backup: {e:#}")), 400);
+        }
+    };
+
+    state::initialize(env, state.clone())
+        .await
+        .context("could not save restored state")?;
+
+    log_info!("restored backup {}", restore_request.backup);
+
+    json_response(
+        serde_json::to_string(&state).context("could not serialize state")?,
+        200,
+    )
+}
+
+/// Changes some fields of the state of the platform in `/state/{platform}`.
+async fn patch_state_response(
+    request: &mut Request,
+    path: &str,
+    env: &Env,
+) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let platform = match path.strip_prefix("/state/").and_then(Platform::from_name) {
+        Some(platform) => platform,
+        None => return json_response(error_json("unknown platform"), 404),
+    };
+
+    let body = request
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get request body")?;
+
+    let patch: PlatformStatePatch = match serde_json::from_str(&body) {
+        Ok(patch) => patch,
+        Err(e) => return json_response(error_json(&format!("invalid request: {e}")), 400),
+    };
+
+    log_info!("patch = {patch:?}");
+
+    let state_controller = StateController::from_kv(env, false).await?;
+    let new_state = state_controller.platform_state(platform).patched(patch);
+
+    if let Err(e) = new_state.validate() {
+        return json_response(
+            error_json(&format!("invalid state for {platform}: {e:#}")),
+            422,
+        );
+    }
+
+    state_controller
+        .set_platform_state(platform, new_state.clone())
+        .await
+        .with_context(|| format!("could not set patched state for {platform}"))?;
+
+    json_response(
+        serde_json::to_string(&new_state).context("could not serialize state")?,
+        200,
+    )
+}
+
+/// Platform named in `/pause/{platform}` or `/resume/{platform}`.
+fn enabled_path_platform(path: &str) -> Option<Platform> {
+    path.strip_prefix("/pause/")
+        .or_else(|| path.strip_prefix("/resume/"))
+        .and_then(Platform::from_name)
+}
+
+async fn set_enabled_response(
+    request: &Request,
+    path: &str,
+    env: &Env,
+    enabled: bool,
+) -> anyhow::Result<Response> {
+    if !is_authorized(request, env)? {
+        return json_response(error_json("unauthorized"), 401);
+    }
+
+    let platform = match enabled_path_platform(path) {
+        Some(platform) => platform,
+        None => return json_response(error_json("unknown platform"), 404),
+    };
+
+    let state_controller = StateController::from_kv(env, false).await?;
+
+    state_controller
+        .update_platform_state(platform, |platform_state| platform_state.enabled = enabled)
+        .await
+        .with_context(|| format!("could not set enabled = {enabled} for {platform}"))?;
+
+    let status = PlatformStatus::new(&state_controller.platform_state(platform));
+
+    json_response(
+        serde_json::to_string(&status).context("could not serialize status")?,
+        200,
+    )
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct PlatformStatus {
+    #[serde(flatten)]
+    latest_version: LatestVersion,
+    last_checked_at: Option<String>,
+    last_error: Option<String>,
+    /// Why posting was put off in the last check, e.g. `forum read-only`.
+    deferred_reason: Option<String>,
+    /// Subrequests made by the run that last checked the platform.
+    run_subrequests: Option<u64>,
+    post_attempts: u32,
+    paused: bool,
+    localization_changes_completeness: Completeness,
+    localization_changes_completeness_rationale: Option<String>,
+}
+
+impl PlatformStatus {
+    fn new(platform_state: &PlatformState) -> Self {
+        Self {
+            latest_version: LatestVersion::from_platform_state(platform_state),
+            last_checked_at: platform_state.last_checked_at.clone(),
+            last_error: platform_state.last_error.clone(),
+            deferred_reason: platform_state.deferred_reason.clone(),
+            run_subrequests: platform_state.run_subrequests,
+            post_attempts: platform_state.post_attempts,
+            paused: !platform_state.enabled,
+            localization_changes_completeness: platform_state.localization_changes_completeness,
+            localization_changes_completeness_rationale: platform_state
+                .localization_changes_completeness_rationale
+                .clone(),
+        }
+    }
+}
+
+async fn status_response(env: &Env) -> anyhow::Result<Response> {
+    let state = public_state(env).await?;
+
+    let mut statuses = BTreeMap::new();
+
+    for platform in Platform::iter() {
+        statuses.insert(
+            platform.to_string().to_ascii_lowercase(),
+            PlatformStatus::new(state.platform_state(platform)),
+        );
+    }
+
+    let body = serde_json::to_string(&statuses).context("could not serialize status")?;
+    let last_successful_run = liveness::last_successful_run(env).await?;
+    let rate_limit = rate_limit::stored(env).await?;
+
+    let mut headers = vec![("Content-Type", "application/json")];
+
+    if let Some(last_successful_run) = &last_successful_run {
+        headers.push(("X-Last-Successful-Run", last_successful_run));
+    }
+
+    let rate_limit_remaining = rate_limit.map(|headroom| headroom.remaining.to_string());
+    let rate_limit_reset = rate_limit
+        .and_then(|headroom| headroom.reset)
+        .map(|reset| reset.to_string());
+
+    if let Some(remaining) = &rate_limit_remaining {
+        headers.push(("X-GitHub-Rate-Limit-Remaining", remaining));
+    }
+
+    if let Some(reset) = &rate_limit_reset {
+        headers.push(("X-GitHub-Rate-Limit-Reset", reset));
+    }
+
+    response_with_headers(body, &headers)
+}
+
+/// Checks the bindings, and with `?deep=1` the external services, without changing anything.
+async fn health_response(request: &Request, env: &Env) -> anyhow::Result<Response> {
+    let deep = health::is_deep(query_parameter(request, "deep")?.as_deref());
+    let report = health::run(env, deep).await;
+
+    Ok(response_with_headers(
+        serde_json::to_string(&report).context("could not serialize health report")?,
+        &[
+            ("Content-Type", "application/json"),
+            ("Cache-Control", "no-store"),
+        ],
+    )?
+    .with_status(report.status_code()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    #[test_case(Some("abc123"), "abc123", true; "matching")]
+    #[test_case(Some("abc124"), "abc123", false; "different")]
+    #[test_case(None, "abc123", false; "missing")]
+    #[test_case(Some(""), "", false; "empty token expected")]
+    #[test_case(Some("abc123"), "", false; "any token with empty token expected")]
+    fn token(token: Option<&str>, expected_token: &str, result: bool) {
+        assert_eq!(token_matches(token, expected_token), result);
+    }
+
+    #[test]
+    fn latest_version_with_history() {
+        let history = vec![
+            HistoryEntry::test_entry("v1.2.4", "2022-07-02T00:00:00.000Z"),
+            HistoryEntry::test_entry("v1.2.3", "2022-07-01T00:00:00.000Z"),
+        ];
+
+        assert_eq!(
+            LatestVersion::new(&Tag::new("v1.2.4"), &history),
+            LatestVersion {
+                tag: String::from("v1.2.4"),
+                posted_at: Some(String::from("2022-07-02T00:00:00.000Z")),
+                post_url: Some(String::from("https://community.signalusers.org/t/123/45")),
+            }
+        );
+    }
+
+    #[test]
+    fn latest_version_never_posted() {
+        let expected = LatestVersion {
+            tag: String::from("v1.2.4"),
+            posted_at: None,
+            post_url: None,
+        };
+
+        assert_eq!(LatestVersion::new(&Tag::new("v1.2.4"), &[]), expected);
+
+        // history does not match state, e.g. the tag was set manually
+        assert_eq!(
+            LatestVersion::new(
+                &Tag::new("v1.2.4"),
+                &[HistoryEntry::test_entry(
+                    "v1.2.3",
+                    "2022-07-01T00:00:00.000Z"
+                )]
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn latest_version_json() {
+        assert_eq!(
+            serde_json::to_string(&LatestVersion::new(&Tag::new("v1.2.4"), &[])).unwrap(),
+            r#"{"tag":"v1.2.4","posted_at":null,"post_url":null}"#
+        );
+    }
+
+    fn changelog_history() -> Vec<HistoryEntry> {
+        let mut history: Vec<HistoryEntry> = (1..=5)
+            .rev()
+            .map(|day| {
+                HistoryEntry::test_entry(
+                    &format!("v1.2.{day}"),
+                    &format!("2022-07-0{day}T00:00:00.000Z"),
+                )
+            })
+            .collect();
+        history[0].commit_subjects = vec![String::from("Fix crash"), String::from("Bump version")];
+
+        history
+    }
+
+    fn changelog_tags(changelog: &Changelog) -> Vec<&str> {
+        changelog
+            .entries
+            .iter()
+            .map(|entry| entry.tag.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn changelog_first_page() {
+        let changelog = Changelog::new(&changelog_history(), 2, None);
+
+        assert_eq!(changelog_tags(&changelog), vec!["v1.2.5", "v1.2.4"]);
+        assert_eq!(
+            changelog.next_before.as_deref(),
+            Some("2022-07-04T00:00:00.000Z")
+        );
+    }
+
+    #[test]
+    fn changelog_pages() {
+        let history = changelog_history();
+        let mut tags = Vec::new();
+        let mut before = None;
+
+        loop {
+            let changelog = Changelog::new(&history, 2, before.as_deref());
+            tags.extend(changelog_tags(&changelog).into_iter().map(String::from));
+
+            match changelog.next_before {
+                Some(next_before) => before = Some(next_before),
+                None => break,
+            }
+        }
+
+        assert_eq!(tags, vec!["v1.2.5", "v1.2.4", "v1.2.3", "v1.2.2", "v1.2.1"]);
+    }
+
+    #[test]
+    fn changelog_last_page() {
+        let changelog = Changelog::new(&changelog_history(), 2, Some("2022-07-02T00:00:00.000Z"));
+
+        assert_eq!(changelog_tags(&changelog), vec!["v1.2.1"]);
+        assert_eq!(changelog.next_before, None);
+    }
+
+    #[test]
+    fn changelog_exactly_one_page() {
+        let changelog = Changelog::new(&changelog_history(), 5, None);
+
+        assert_eq!(changelog.entries.len(), 5);
+        assert_eq!(changelog.next_before, None);
+    }
+
+    #[test]
+    fn changelog_sorted_newest_first() {
+        let mut history = changelog_history();
+        history.reverse();
+
+        assert_eq!(
+            changelog_tags(&Changelog::new(&history, 3, None)),
+            vec!["v1.2.5", "v1.2.4", "v1.2.3"]
+        );
+    }
+
+    #[test]
+    fn changelog_empty() {
+        let changelog = Changelog::new(&[], 20, None);
+
+        assert_eq!(changelog.entries, vec![]);
+        assert_eq!(changelog.next_before, None);
+    }
+
+    #[test]
+    fn changelog_json() {
+        let history = changelog_history();
+
+        assert_str_eq!(
+            serde_json::to_string(&Changelog::new(&history, 2, None)).unwrap(),
+            r#"{"entries":[{"version":"1.2.5","tag":"v1.2.5","date":"2022-07-05T00:00:00.000Z","commits_count":3,"post_url":"https://community.signalusers.org/t/123/45","commit_subjects":["Fix crash","Bump version"]},{"version":"1.2.4","tag":"v1.2.4","date":"2022-07-04T00:00:00.000Z","commits_count":3,"post_url":"https://community.signalusers.org/t/123/45"}],"next_before":"2022-07-04T00:00:00.000Z"}"#
+        );
+    }
+
+    #[test_case("/changelog/android.json", Some(Android); "lowercase")]
+    #[test_case("/changelog/iOS.json", Some(Ios); "display name")]
+    #[test_case("/changelog/windows.json", None; "unknown platform")]
+    #[test_case("/changelog/desktop", None; "no extension")]
+    fn changelog_platform(path: &str, result: Option<Platform>) {
+        assert_eq!(
+            changelog_platform_name(path).and_then(Platform::from_name),
+            result
+        );
+    }
+
+    #[test_case(None, Ok(20); "default")]
+    #[test_case(Some("1"), Ok(1); "minimum")]
+    #[test_case(Some("50"), Ok(50); "maximum")]
+    #[test_case(Some("0"), Err(()); "zero")]
+    #[test_case(Some("51"), Err(()); "too many")]
+    #[test_case(Some("-1"), Err(()); "negative")]
+    #[test_case(Some("ten"), Err(()); "not a number")]
+    fn changelog_limits(limit: Option<&str>, result: Result<usize, ()>) {
+        assert_eq!(changelog_limit(limit).map_err(|_| ()), result);
+    }
+
+    #[test_case("/rerender/android/7.1.4", Some(("android", "7.1.4")); "version")]
+    #[test_case("/rerender/desktop/v7.1.0-beta.2", Some(("desktop", "v7.1.0-beta.2")); "tag")]
+    #[test_case("/rerender/android", None; "missing version")]
+    #[test_case("/rerender/android/", None; "empty version")]
+    #[test_case("/rerender/android/7.1.4/extra", None; "extra part")]
+    fn rerender_path(path: &str, result: Option<(&str, &str)>) {
+        assert_eq!(rerender_path_parts(path), result);
+    }
+
+    fn rerender_history() -> Vec<HistoryEntry> {
+        let mut newest = HistoryEntry::test_entry("v1.2.4", "2022-07-02T00:00:00.000Z");
+        newest.previous_tag = Some(String::from("v1.2.3"));
+
+        let mut without_post = HistoryEntry::test_entry("v1.2.3", "2022-07-01T00:00:00.000Z");
+        without_post.previous_tag = Some(String::from("v1.2.2"));
+        without_post.post_number = None;
+
+        vec![
+            newest,
+            without_post,
+            HistoryEntry::test_entry("v1.2.2", "2022-06-30T00:00:00.000Z"),
+        ]
+    }
+
+    #[test_case("1.2.4"; "version")]
+    #[test_case("v1.2.4"; "tag")]
+    fn posted_version_found(version: &str) {
+        assert_eq!(
+            PostedVersion::find(&rerender_history(), version),
+            Ok(PostedVersion {
+                old_tag: Tag::new("v1.2.3"),
+                new_tag: Tag::new("v1.2.4"),
+                topic_id: 123,
+                post_number: 45,
+            })
+        );
+    }
+
+    #[test_case("1.2.5", 404, "no history entry for 1.2.5"; "not in history")]
+    #[test_case("1.2.3", 422, "history entry for 1.2.3 does not record the post"; "without post")]
+    #[test_case("1.2.2", 422, "history entry for 1.2.2 does not record the previous tag"; "without previous tag")]
+    fn posted_version_not_found(version: &str, status_code: u16, message: &str) {
+        assert_eq!(
+            PostedVersion::find(&rerender_history(), version),
+            Err((status_code, String::from(message)))
+        );
+    }
+
+    #[test]
+    fn badge_json() {
+        assert_str_eq!(
+            serde_json::to_string(&Badge::new(Android, &Tag::new("v7.2.0-beta.1"))).unwrap(),
+            r#"{"schemaVersion":1,"label":"Signal Android beta","message":"7.2.0-beta.1","color":"blue"}"#
+        );
+    }
+
+    #[test_case("/badge/android.json", Some(Android); "lowercase")]
+    #[test_case("/badge/iOS.json", Some(Ios); "display name")]
+    #[test_case("/badge/DESKTOP.json", Some(Desktop); "uppercase")]
+    #[test_case("/badge/windows.json", None; "unknown platform")]
+    #[test_case("/badge/android", None; "no extension")]
+    #[test_case("/badge/.json", None; "empty platform")]
+    fn badge_platform(path: &str, result: Option<Platform>) {
+        assert_eq!(
+            badge_platform_name(path).and_then(Platform::from_name),
+            result
+        );
+    }
+
+    #[test_case("/preview/android/v1.2.3/v1.2.4", Some(("android", "v1.2.3", "v1.2.4")); "valid")]
+    #[test_case("/preview/desktop/v1.2.3-beta.1/v1.2.3-beta.2", Some(("desktop", "v1.2.3-beta.1", "v1.2.3-beta.2")); "prerelease tags")]
+    #[test_case("/preview/android/v1.2.3", None; "missing new tag")]
+    #[test_case("/preview/android/v1.2.3/v1.2.4/extra", None; "extra part")]
+    #[test_case("/preview/android//v1.2.4", None; "empty old tag")]
+    fn preview_path(path: &str, result: Option<(&str, &str, &str)>) {
+        assert_eq!(preview_path_parts(path), result);
+    }
+
+    #[test_case(1, None; "one commit")]
+    #[test_case(500, None; "at most")]
+    #[test_case(501, Some("comparison between v1.2.3 and v1.3.0 has 501 commits, at most 500 can be previewed"); "too many")]
+    fn preview_commits(total_commits: usize, result: Option<&str>) {
+        assert_eq!(
+            too_many_commits(&Tag::new("v1.2.3"), &Tag::new("v1.3.0"), total_commits).as_deref(),
+            result
+        );
+    }
+
+    fn posted_raw(new_tag: &str) -> String {
+        let metadata = PostMetadata::new(
+            Android,
+            &Tag::new("v1.2.3"),
+            &Tag::new(new_tag),
+            Vec::new(),
+            0,
+        );
+        format!("## New Version: 1.2.4\n\n{}", metadata.markdown())
+    }
+
+    #[test_case(&posted_raw("v1.2.4"), None; "same version")]
+    #[test_case(&posted_raw("v1.2.5"), Some("is for v1.2.5, not v1.2.4"); "other version")]
+    #[test_case("## New Version: 1.2.4", None; "without metadata")]
+    fn rerendered_post_version(posted_raw: &str, result: Option<&str>) {
+        assert_eq!(
+            other_version_posted(posted_raw, &Tag::new("v1.2.4")).as_deref(),
+            result
+        );
+    }
+
+    #[test_case("/pause/android", Some(Android); "pause")]
+    #[test_case("/resume/Desktop", Some(Desktop); "resume")]
+    #[test_case("/pause/windows", None; "unknown platform")]
+    #[test_case("/pause/", None; "empty platform")]
+    fn enabled_platform(path: &str, result: Option<Platform>) {
+        assert_eq!(enabled_path_platform(path), result);
+    }
+
+    fn platform_state() -> PlatformState {
+        PlatformState {
+            last_posted_tag_previous_release: Tag::new("v1.2.3"),
+            last_posted_tag: Tag::new("v1.2.4"),
+            last_post_number: Some(45),
+            last_post_id: Some(1000),
+            last_post_url: None,
+            last_posted_at: None,
+            post_attempts: 0,
+            pending_tag: None,
+            posted_archiving_message: false,
+            posted_cycle_summary: false,
+            tag_moved: false,
+            localization_changes_completeness: Default::default(),
+            localization_changes_completeness_rationale: None,
+            localization_changes: Default::default(),
+            localization_changes_cycle: None,
+            localization_before_last_posted_tag: None,
+            enabled: true,
+            last_checked_at: Some(String::from("2022-07-02T00:10:00.000Z")),
+            last_error: None,
+            consecutive_failures: 0,
+            next_attempt_after: None,
+            deferred_reason: None,
+            run_subrequests: None,
+        }
+    }
+
+    #[test]
+    fn status_json() {
+        let platform_state = PlatformState {
+            last_post_url: Some(String::from("https://community.signalusers.org/t/123/45")),
+            last_posted_at: Some(String::from("2022-07-02T00:00:00.000Z")),
+            localization_changes_completeness: Completeness::LikelyComplete,
+            localization_changes_completeness_rationale: Some(String::from(
+                "Only the files of 2 commit(s) that look like translation updates were checked.",
+            )),
+            ..platform_state()
+        };
+
+        assert_str_eq!(
+            serde_json::to_string(&PlatformStatus::new(&platform_state)).unwrap(),
+            r#"{"tag":"v1.2.4","posted_at":"2022-07-02T00:00:00.000Z","post_url":"https://community.signalusers.org/t/123/45","last_checked_at":"2022-07-02T00:10:00.000Z","last_error":null,"deferred_reason":null,"run_subrequests":null,"post_attempts":0,"paused":false,"localization_changes_completeness":"LikelyComplete","localization_changes_completeness_rationale":"Only the files of 2 commit(s) that look like translation updates were checked."}"#
+        );
+    }
+
+    #[test]
+    fn status_json_with_error() {
+        let platform_state = PlatformState {
+            enabled: false,
+            last_error: Some(String::from(
+                "could not fetch tags from GitHub: rate limit exceeded",
+            )),
+            ..platform_state()
+        };
+
+        assert_str_eq!(
+            serde_json::to_string(&PlatformStatus::new(&platform_state)).unwrap(),
+            r#"{"tag":"v1.2.4","posted_at":null,"post_url":null,"last_checked_at":"2022-07-02T00:10:00.000Z","last_error":"could not fetch tags from GitHub: rate limit exceeded","deferred_reason":null,"run_subrequests":null,"post_attempts":0,"paused":true,"localization_changes_completeness":"Incomplete","localization_changes_completeness_rationale":null}"#
+        );
+    }
+
+    #[test]
+    fn status_json_while_forum_read_only() {
+        let platform_state = PlatformState {
+            deferred_reason: Some(String::from("forum read-only")),
+            run_subrequests: Some(23),
+            post_attempts: 1,
+            ..platform_state()
+        };
+
+        assert_str_eq!(
+            serde_json::to_string(&PlatformStatus::new(&platform_state)).unwrap(),
+            r#"{"tag":"v1.2.4","posted_at":null,"post_url":null,"last_checked_at":"2022-07-02T00:10:00.000Z","last_error":null,"deferred_reason":"forum read-only","run_subrequests":23,"post_attempts":1,"paused":false,"localization_changes_completeness":"Incomplete","localization_changes_completeness_rationale":null}"#
+        );
+    }
+
+    fn state() -> State {
+        State {
+            android: platform_state(),
+            ios: platform_state(),
+            desktop: platform_state(),
+        }
+    }
+
+    #[test]
+    fn valid_state_json() {
+        let json = state_json(&state(), None).unwrap();
+
+        assert!(json.starts_with("{\n  \"android\": {\n"), "{json}");
+        assert!(!json.contains("validation_error"));
+        assert_eq!(serde_json::from_str::<State>(&json).unwrap(), state());
+    }
+
+    #[test]
+    fn invalid_state_json() {
+        let mut state = state();
+        state.ios.last_posted_tag = Tag::new("v1.0.0");
+
+        let validation_error = format!("{:#}", state.validate().unwrap_err());
+        let json = state_json(&state, Some(&validation_error)).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_str_eq!(
+            value["validation_error"].as_str().unwrap(),
+            "invalid state for iOS: last_posted_version_previous_release >= last_posted_version"
+        );
+        assert_eq!(value["ios"]["last_posted_tag"]["name"], "v1.0.0");
+    }
+
+    #[test]
+    fn error() {
+        assert_str_eq!(
+            error_json("unknown platform"),
+            r#"{"error":"unknown platform"}"#
+        );
+    }
+
+    #[test]
+    fn trigger_results() {
+        use crate::PlatformCheckOutcome::*;
+
+        let results: PlatformCheckResults = vec![
+            (Android, Ok(LatestVersionIsAlreadyPosted)),
+            (Ios, Ok(PostedCommits)),
+            (
+                Desktop,
+                Ok(RenderedPostInDryRun {
+                    topic_id: 123,
+                    markdown_text: String::from("New version: 1.2.4"),
+                }),
+            ),
+        ];
+
+        assert_str_eq!(
+            results_json(&results),
+            r#"{"android":{"outcome":"nothing new"},"desktop":{"markdown":"New version: 1.2.4","outcome":"dry run","topic_id":123},"ios":{"outcome":"posted"}}"#
+        );
+    }
+
+    #[test]
+    fn trigger_results_with_error() {
+        use crate::PlatformCheckOutcome::*;
+
+        let results: PlatformCheckResults = vec![
+            (Android, Ok(LatestVersionIsAlreadyPosted)),
+            (Ios, Ok(PostedCommits)),
+            (
+                Desktop,
+                Err(anyhow!("no posts in topic").context("could not find new_topic_id")),
+            ),
+        ];
+
+        assert_str_eq!(
+            results_json(&results),
+            r#"{"android":{"outcome":"nothing new"},"desktop":{"error":"could not find new_topic_id: no posts in topic","outcome":"error"},"ios":{"outcome":"posted"}}"#
+        );
+    }
+}
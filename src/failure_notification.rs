@@ -0,0 +1,287 @@
+use anyhow::{anyhow, Context};
+use worker::Env;
+
+use crate::{
+    http,
+    log::{self, log_error, log_info, log_warn},
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+    utils,
+};
+
+const RATE_LIMIT_KV_KEY_PREFIX: &str = "failure_notification_sent";
+
+/// At most one notification is sent per platform within this many seconds.
+const RATE_LIMIT_SECONDS: u64 = 60 * 60;
+
+fn rate_limit_kv_key(platform: Option<Platform>) -> String {
+    let suffix = match platform {
+        Some(platform) => platform.to_string().to_ascii_lowercase(),
+        None => String::from("all"),
+    };
+
+    format!("{RATE_LIMIT_KV_KEY_PREFIX}_{suffix}")
+}
+
+fn title(platform: Option<Platform>) -> String {
+    match platform {
+        Some(platform) => format!("Bot run failed while checking {platform}"),
+        None => String::from("Bot run failed"),
+    }
+}
+
+/// The error is redacted, as it may include e.g. URLs with secrets.
+fn error_text(error: &anyhow::Error) -> String {
+    log::redact(&format!("{error:?}"))
+}
+
+fn markdown_text(platform: Option<Platform>, tag: Option<&str>, error: &anyhow::Error) -> String {
+    let platform = match platform {
+        Some(platform) => platform.to_string(),
+        None => String::from("*none* (failed before checking platforms)"),
+    };
+    let tag = match tag {
+        Some(tag) => format!("\n**Tag:** {tag}\n"),
+        None => String::new(),
+    };
+
+    format!(
+        "**Platform:** {platform}
+{tag}
+**Error:**
+```text
+{}
+```
+
+Further notifications for this platform are suppressed for {} minutes.",
+        error_text(error),
+        RATE_LIMIT_SECONDS / 60
+    )
+}
+
+fn down_markdown_text(last_successful_run: &str, error: &anyhow::Error) -> String {
+    format!(
+        "**Severity:** bot may be down
+
+There was no successful run since {last_successful_run}, and the latest run failed too.
+
+**Error:**
+```text
+{}
+```
+
+This is not sent again until there is another successful run.",
+        error_text(error)
+    )
+}
+
+/// Sends a private message that the bot may be down, as there was no successful run since
+/// `last_successful_run`, to the configured user; not rate limited, as the caller only
+/// sends it once for each `last_successful_run`.
+///
+/// Returns whether the message was sent.
+pub async fn notify_down(
+    env: &Env,
+    last_successful_run: &str,
+    error: &anyhow::Error,
+    is_dry_run: bool,
+) -> anyhow::Result<bool> {
+    let username = match utils::failure_notification_username(env)? {
+        Some(username) => username,
+        None => {
+            log_warn!("no failure notification username configured; not notifying");
+            return Ok(false);
+        }
+    };
+
+    if is_dry_run {
+        log_warn!("dry run; not sending failure notification");
+        return Ok(false);
+    }
+
+    let post = utils::send_private_message_in_discourse(
+        &http::FetchClient,
+        "Bot may be down",
+        &down_markdown_text(last_successful_run, error),
+        &utils::api_key(env)?,
+        &username,
+    )
+    .await
+    .context("could not send private message")?;
+
+    log_info!("sent bot may be down notification, post = {post:?}");
+
+    Ok(true)
+}
+
+/// Sends a private message about `error` while checking `tag` to the configured user,
+/// unless one was already sent for `platform` recently.
+///
+/// Failures are only logged, so that the original error is not masked.
+pub async fn notify(
+    env: &Env,
+    platform: Option<Platform>,
+    tag: Option<&str>,
+    error: &anyhow::Error,
+    is_dry_run: bool,
+) {
+    if let Err(e) = try_notify(env, platform, tag, error, is_dry_run).await {
+        log_error!("could not send failure notification: {e:?}");
+    }
+}
+
+async fn try_notify(
+    env: &Env,
+    platform: Option<Platform>,
+    tag: Option<&str>,
+    error: &anyhow::Error,
+    is_dry_run: bool,
+) -> anyhow::Result<()> {
+    let username = match utils::failure_notification_username(env)? {
+        Some(username) => username,
+        None => {
+            log_warn!("no failure notification username configured; not notifying");
+            return Ok(());
+        }
+    };
+
+    if is_dry_run {
+        log_warn!("dry run; not sending failure notification");
+        return Ok(());
+    }
+
+    let kv_store = env
+        .kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")?;
+
+    let key = state::kv_key(env, &rate_limit_kv_key(platform));
+
+    let already_sent = kv_store
+        .get(&key)
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))?
+        .is_some();
+
+    if already_sent {
+        log_warn!("failure notification was already sent recently ({key}); not notifying");
+        return Ok(());
+    }
+
+    let post = utils::send_private_message_in_discourse(
+        &http::FetchClient,
+        &title(platform),
+        &markdown_text(platform, tag, error),
+        &utils::api_key(env)?,
+        &username,
+    )
+    .await
+    .context("could not send private message")?;
+
+    log_info!("sent failure notification, post = {post:?}");
+
+    kv_store
+        .put(&key, true)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .expiration_ttl(RATE_LIMIT_SECONDS)
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{core::poll_once, platform::Platform::*};
+
+    #[test_case(Some(Android), "failure_notification_sent_android"; "Android")]
+    #[test_case(Some(Ios), "failure_notification_sent_ios"; "iOS")]
+    #[test_case(None, "failure_notification_sent_all"; "no platform")]
+    fn rate_limit_key(platform: Option<Platform>, result: &str) {
+        assert_str_eq!(rate_limit_kv_key(platform), result);
+    }
+
+    #[test]
+    fn markdown() {
+        let error = anyhow!("no posts in topic").context("could not find new_topic_id");
+
+        assert_str_eq!(
+            markdown_text(Some(Desktop), Some("v6.1.0"), &error),
+            "**Platform:** Desktop
+
+**Tag:** v6.1.0
+
+**Error:**
+```text
+could not find new_topic_id
+
+Caused by:
+    no posts in topic
+```
+
+Further notifications for this platform are suppressed for 60 minutes."
+        );
+    }
+
+    #[test]
+    fn markdown_without_tag() {
+        let error = anyhow!("could not get tags");
+
+        assert_str_eq!(
+            markdown_text(None, None, &error),
+            "**Platform:** *none* (failed before checking platforms)
+
+**Error:**
+```text
+could not get tags
+```
+
+Further notifications for this platform are suppressed for 60 minutes."
+        );
+    }
+
+    #[test]
+    fn markdown_error_is_redacted() {
+        let error = anyhow!("could not get https://example.com/?token=ghp_token");
+        let context = crate::core::log::Context {
+            secrets: vec![String::from("ghp_token")],
+            ..Default::default()
+        };
+
+        let markdown = poll_once(crate::core::log::scope(context, async {
+            markdown_text(Some(Ios), None, &error)
+        }));
+
+        assert!(markdown.contains("?token=[REDACTED]"));
+        assert!(!markdown.contains("ghp_token"));
+    }
+
+    #[test]
+    fn down_markdown() {
+        let error = anyhow!("401 Unauthorized").context("could not get tags");
+
+        assert_str_eq!(
+            down_markdown_text("2022-07-01T00:00:00.000Z", &error),
+            "**Severity:** bot may be down
+
+There was no successful run since 2022-07-01T00:00:00.000Z, and the latest run failed too.
+
+**Error:**
+```text
+could not get tags
+
+Caused by:
+    401 Unauthorized
+```
+
+This is not sent again until there is another successful run."
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{history::HistoryEntry, platform::Platform};
+
+const FEED_ID: &str = "https://community.signalusers.org/c/25";
+const EPOCH: &str = "1970-01-01T00:00:00.000Z";
+
+pub fn escape_xml(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+
+    for character in input.chars() {
+        match character {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            _ => result.push(character),
+        }
+    }
+
+    result
+}
+
+fn entry_xml(platform: Platform, entry: &HistoryEntry) -> String {
+    let title = escape_xml(&format!("Signal {platform} {}", entry.version));
+    let post_url = escape_xml(&entry.post_url);
+    let date = escape_xml(&entry.date);
+
+    let commits_word_suffix = if entry.commits_count == 1 { "" } else { "s" };
+    let summary = escape_xml(&format!(
+        "{} new commit{commits_word_suffix} in {}.",
+        entry.commits_count, entry.tag
+    ));
+
+    format!(
+        "  <entry>
+    <title>{title}</title>
+    <id>{post_url}</id>
+    <link href=\"{post_url}\"/>
+    <updated>{date}</updated>
+    <summary>{summary}</summary>
+  </entry>
+"
+    )
+}
+
+/// Renders an Atom feed (https://datatracker.ietf.org/doc/html/rfc4287) of `entries`,
+/// newest first.
+pub fn atom_feed(entries: &[(Platform, HistoryEntry)]) -> String {
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by(|(_, lhs), (_, rhs)| rhs.date.cmp(&lhs.date));
+
+    let updated = escape_xml(
+        entries
+            .first()
+            .map(|(_, entry)| entry.date.as_str())
+            .unwrap_or(EPOCH),
+    );
+
+    let entries_xml: String = entries
+        .iter()
+        .map(|(platform, entry)| entry_xml(*platform, entry))
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+  <title>Signal beta versions</title>
+  <id>{FEED_ID}</id>
+  <link href=\"{FEED_ID}\"/>
+  <updated>{updated}</updated>
+  <author><name>signalupdates-bot</name></author>
+{entries_xml}</feed>
+"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    #[test_case("plain", "plain"; "plain")]
+    #[test_case("a & b", "a &amp; b"; "ampersand")]
+    #[test_case("<tag attr=\"x\">'</tag>", "&lt;tag attr=&quot;x&quot;&gt;&apos;&lt;/tag&gt;"; "markup")]
+    fn escape(input: &str, result: &str) {
+        assert_str_eq!(escape_xml(input), result);
+    }
+
+    #[test]
+    fn empty_feed() {
+        assert_str_eq!(
+            atom_feed(&[]),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+  <title>Signal beta versions</title>
+  <id>https://community.signalusers.org/c/25</id>
+  <link href=\"https://community.signalusers.org/c/25\"/>
+  <updated>1970-01-01T00:00:00.000Z</updated>
+  <author><name>signalupdates-bot</name></author>
+</feed>
+"
+        );
+    }
+
+    #[test]
+    fn feed_sorted_by_date() {
+        let entries = vec![
+            (
+                Android,
+                HistoryEntry::test_entry("v1.2.3", "2022-07-01T00:00:00.000Z"),
+            ),
+            (
+                Desktop,
+                HistoryEntry::test_entry("v1.2.3-beta.1", "2022-07-02T00:00:00.000Z"),
+            ),
+        ];
+
+        assert_str_eq!(
+            atom_feed(&entries),
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+  <title>Signal beta versions</title>
+  <id>https://community.signalusers.org/c/25</id>
+  <link href=\"https://community.signalusers.org/c/25\"/>
+  <updated>2022-07-02T00:00:00.000Z</updated>
+  <author><name>signalupdates-bot</name></author>
+  <entry>
+    <title>Signal Desktop 1.2.3-beta.1</title>
+    <id>https://community.signalusers.org/t/123/45</id>
+    <link href=\"https://community.signalusers.org/t/123/45\"/>
+    <updated>2022-07-02T00:00:00.000Z</updated>
+    <summary>3 new commits in v1.2.3-beta.1.</summary>
+  </entry>
+  <entry>
+    <title>Signal Android 1.2.3</title>
+    <id>https://community.signalusers.org/t/123/45</id>
+    <link href=\"https://community.signalusers.org/t/123/45\"/>
+    <updated>2022-07-01T00:00:00.000Z</updated>
+    <summary>3 new commits in v1.2.3.</summary>
+  </entry>
+</feed>
+"
+        );
+    }
+}
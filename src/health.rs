@@ -0,0 +1,265 @@
+use anyhow::{anyhow, bail, Context};
+use serde::Serialize;
+use worker::{Env, Fetch, Method, Url};
+
+use crate::{liveness, log::log_warn, state, utils};
+
+const GITHUB_URL: &str = "https://api.github.com/rate_limit";
+const DISCOURSE_URL: &str = "https://community.signalusers.org/site/basic-info.json";
+
+/// Result of one of the checks of `/healthz`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: anyhow::Result<()>) -> Self {
+        match result {
+            Ok(()) => Self {
+                name,
+                ok: true,
+                error: None,
+            },
+            Err(e) => {
+                log_warn!("health check {name} failed: {e:#}");
+
+                Self {
+                    name,
+                    ok: false,
+                    error: Some(format!("{e:#}")),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub ok: bool,
+    /// Whether GitHub and Discourse were checked too.
+    pub deep: bool,
+    pub checks: Vec<Check>,
+    /// ISO 8601 timestamp of when a run last completed without errors, if known.
+    pub last_successful_run: Option<String>,
+}
+
+impl Report {
+    fn new(checks: Vec<Check>, deep: bool, last_successful_run: Option<String>) -> Self {
+        Self {
+            ok: checks.iter().all(|check| check.ok),
+            deep,
+            checks,
+            last_successful_run,
+        }
+    }
+
+    pub const fn status_code(&self) -> u16 {
+        if self.ok {
+            200
+        } else {
+            503
+        }
+    }
+}
+
+/// External services are only checked with `?deep=1`.
+pub fn is_deep(deep: Option<&str>) -> bool {
+    deep == Some("1")
+}
+
+/// Only whether the secret is set is checked, its value is never part of the result.
+fn secret_present(secret: anyhow::Result<String>) -> anyhow::Result<()> {
+    match secret {
+        Ok(secret) if !secret.is_empty() => Ok(()),
+        Ok(_) => bail!("secret is empty"),
+        Err(_) => bail!("secret is not set"),
+    }
+}
+
+/// Fails if there was no successful run for longer than the threshold of the
+/// "bot may be down" alert (see [`liveness`]).
+fn recent_successful_run(last_successful_run: Option<&str>, cutoff: &str) -> anyhow::Result<()> {
+    match liveness::is_overdue(last_successful_run, cutoff) {
+        true => bail!(
+            "no successful run since {}",
+            last_successful_run.unwrap_or_default()
+        ),
+        false => Ok(()),
+    }
+}
+
+fn check_recent_successful_run(env: &Env, last_successful_run: Option<&str>) -> anyhow::Result<()> {
+    recent_successful_run(last_successful_run, &liveness::cutoff(env)?)
+}
+
+async fn check_kv(env: &Env) -> anyhow::Result<()> {
+    state::exists(env).await.map(|_| ())
+}
+
+async fn check_state(env: &Env) -> anyhow::Result<()> {
+    match state::snapshot(env).await? {
+        Some(snapshot) => snapshot.state.validate(),
+        None => bail!("no state in KV"),
+    }
+}
+
+/// Makes an unauthenticated `GET` request to `url`, which must respond with 2xx.
+async fn check_reachable(url: &str) -> anyhow::Result<()> {
+    let url = Url::parse(url).context("could not parse URL")?;
+    let request = utils::RequestBuilder::new(url, Method::Get).build()?;
+
+    let response = utils::fetch(Fetch::Request(request)).await?;
+
+    match response.status_code() {
+        200..=299 => Ok(()),
+        status_code => bail!("responded with status code {status_code}"),
+    }
+}
+
+/// Runs the checks, none of which changes anything; `deep` adds the checks of
+/// GitHub and Discourse.
+pub async fn run(env: &Env, deep: bool) -> Report {
+    let last_successful_run = liveness::last_successful_run(env).await;
+
+    let mut checks = vec![
+        Check::new("kv", check_kv(env).await),
+        Check::new("state", check_state(env).await),
+        Check::new("discourse_api_key", secret_present(utils::api_key(env))),
+        Check::new(
+            "recent_successful_run",
+            match &last_successful_run {
+                Ok(last_successful_run) => {
+                    check_recent_successful_run(env, last_successful_run.as_deref())
+                }
+                Err(e) => Err(anyhow!("{e:#}")),
+            },
+        ),
+    ];
+
+    if deep {
+        checks.push(Check::new("github", check_reachable(GITHUB_URL).await));
+        checks.push(Check::new(
+            "discourse",
+            check_reachable(DISCOURSE_URL).await,
+        ));
+    }
+
+    Report::new(checks, deep, last_successful_run.ok().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+
+    fn passed(name: &'static str) -> Check {
+        Check::new(name, Ok(()))
+    }
+
+    fn failed(name: &'static str) -> Check {
+        Check::new(
+            name,
+            Err(anyhow!("could not get value").context("no state")),
+        )
+    }
+
+    #[test]
+    fn all_passed() {
+        let report = Report::new(
+            vec![passed("kv"), passed("state"), passed("discourse_api_key")],
+            false,
+            None,
+        );
+
+        assert!(report.ok);
+        assert_eq!(report.status_code(), 200);
+    }
+
+    #[test]
+    fn one_failed() {
+        let report = Report::new(
+            vec![passed("kv"), failed("state"), passed("discourse_api_key")],
+            false,
+            None,
+        );
+
+        assert!(!report.ok);
+        assert_eq!(report.status_code(), 503);
+    }
+
+    #[test]
+    fn error_includes_causes() {
+        assert_eq!(
+            failed("state").error.as_deref(),
+            Some("no state: could not get value")
+        );
+    }
+
+    #[test_case(None, false; "not given")]
+    #[test_case(Some("1"), true; "deep")]
+    #[test_case(Some("0"), false; "shallow")]
+    #[test_case(Some("true"), false; "other value")]
+    fn deep(deep: Option<&str>, result: bool) {
+        assert_eq!(is_deep(deep), result);
+    }
+
+    #[test]
+    fn secret_set() {
+        assert!(secret_present(Ok(String::from("abc123"))).is_ok());
+    }
+
+    #[test]
+    fn secret_empty() {
+        assert_eq!(
+            secret_present(Ok(String::new())).unwrap_err().to_string(),
+            "secret is empty"
+        );
+    }
+
+    #[test]
+    fn secret_not_set_without_value() {
+        let error = secret_present(Err(anyhow!("binding abc123 missing"))).unwrap_err();
+
+        assert_eq!(error.to_string(), "secret is not set");
+        assert!(!format!("{error:#}").contains("abc123"));
+    }
+
+    #[test]
+    fn report_json() {
+        let report = Report::new(
+            vec![passed("kv"), failed("state")],
+            true,
+            Some(String::from("2022-07-02T00:00:00.000Z")),
+        );
+
+        assert_str_eq!(
+            serde_json::to_string(&report).unwrap(),
+            r#"{"ok":false,"deep":true,"checks":[{"name":"kv","ok":true},{"name":"state","ok":false,"error":"no state: could not get value"}],"last_successful_run":"2022-07-02T00:00:00.000Z"}"#
+        );
+    }
+
+    #[test_case(Some("2022-07-02T05:00:00.000Z"), true; "recent")]
+    #[test_case(None, true; "never recorded")]
+    fn recent_run(last_successful_run: Option<&str>, ok: bool) {
+        assert_eq!(
+            recent_successful_run(last_successful_run, "2022-07-02T00:00:00.000Z").is_ok(),
+            ok
+        );
+    }
+
+    #[test]
+    fn no_recent_run() {
+        assert_eq!(
+            recent_successful_run(Some("2022-07-01T00:00:00.000Z"), "2022-07-02T00:00:00.000Z")
+                .unwrap_err()
+                .to_string(),
+            "no successful run since 2022-07-01T00:00:00.000Z"
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use worker::Env;
+use worker_kv::KvStore;
+
+use crate::{
+    log::{log_error, log_info},
+    markdown::PostedBuild,
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+};
+
+const HISTORY_KV_KEY_PREFIX: &str = "history";
+
+/// At most this many entries are kept for each platform.
+pub const MAX_ENTRIES: usize = 50;
+
+/// At most this many commit subjects are kept for each entry, so that the value stays small.
+pub const MAX_COMMIT_SUBJECTS: usize = 20;
+
+/// Longer commit subjects are cut short to this many characters, including the ellipsis.
+pub const MAX_COMMIT_SUBJECT_CHARS: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub tag: String,
+    pub version: String,
+    /// ISO 8601 timestamp of when the post was made.
+    pub date: String,
+    pub post_url: String,
+    pub commits_count: usize,
+
+    /// Tag that the commits were listed since; not recorded for older entries.
+    #[serde(default)]
+    pub previous_tag: Option<String>,
+    #[serde(default)]
+    pub topic_id: Option<u64>,
+    #[serde(default)]
+    pub post_number: Option<u64>,
+    /// The first commit subjects of the post (see [`trimmed_commit_subjects`]);
+    /// not recorded for older entries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commit_subjects: Vec<String>,
+}
+
+impl HistoryEntry {
+    pub fn posted_build(&self) -> PostedBuild<'_> {
+        PostedBuild {
+            tag: &self.tag,
+            date: &self.date,
+            commits_count: self.commits_count,
+        }
+    }
+}
+
+/// The first [`MAX_COMMIT_SUBJECTS`] of `commit_subjects`, each cut short to
+/// [`MAX_COMMIT_SUBJECT_CHARS`].
+pub fn trimmed_commit_subjects(commit_subjects: &[String]) -> Vec<String> {
+    commit_subjects
+        .iter()
+        .take(MAX_COMMIT_SUBJECTS)
+        .map(|subject| match subject.chars().count() {
+            count if count > MAX_COMMIT_SUBJECT_CHARS => {
+                let mut trimmed: String =
+                    subject.chars().take(MAX_COMMIT_SUBJECT_CHARS - 1).collect();
+                trimmed.push('…');
+                trimmed
+            }
+            _ => subject.clone(),
+        })
+        .collect()
+}
+
+fn kv_key(platform: Platform) -> String {
+    format!(
+        "{HISTORY_KV_KEY_PREFIX}_{}",
+        platform.to_string().to_ascii_lowercase()
+    )
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Inserts `entry` as the newest one, dropping the oldest entries beyond [`MAX_ENTRIES`].
+fn push_entry(entries: &mut Vec<HistoryEntry>, entry: HistoryEntry) {
+    entries.insert(0, entry);
+    entries.truncate(MAX_ENTRIES);
+}
+
+/// Returns the posted versions for `platform`, newest first.
+pub async fn entries(env: &Env, platform: Platform) -> anyhow::Result<Vec<HistoryEntry>> {
+    let key = state::kv_key(env, &kv_key(platform));
+
+    let entries: Option<Vec<HistoryEntry>> = kv_store(env)?
+        .get(&key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))?;
+
+    Ok(entries.unwrap_or_default())
+}
+
+/// Adds `entry` to the history of `platform`.
+///
+/// Failures are only logged, as the post was already made.
+pub async fn record(env: &Env, platform: Platform, entry: HistoryEntry) {
+    if let Err(e) = try_record(env, platform, entry).await {
+        log_error!(platform = platform, "could not record history entry: {e:?}");
+    }
+}
+
+async fn try_record(env: &Env, platform: Platform, entry: HistoryEntry) -> anyhow::Result<()> {
+    let mut entries = entries(env, platform).await?;
+    push_entry(&mut entries, entry);
+
+    kv_store(env)?
+        .put(&state::kv_key(env, &kv_key(platform)), &entries)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")?;
+
+    log_info!(platform = platform, "recorded history entry for {platform}");
+
+    Ok(())
+}
+
+#[cfg(test)]
+impl HistoryEntry {
+    pub fn test_entry(tag: &str, date: &str) -> Self {
+        Self {
+            tag: tag.to_string(),
+            version: crate::types::github::version_string(tag).to_string(),
+            date: date.to_string(),
+            post_url: String::from("https://community.signalusers.org/t/123/45"),
+            commits_count: 3,
+            previous_tag: None,
+            topic_id: Some(123),
+            post_number: Some(45),
+            commit_subjects: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn push_entry_keeps_newest() {
+        let mut entries: Vec<_> = (0..MAX_ENTRIES)
+            .map(|i| HistoryEntry::test_entry(&format!("v1.0.{i}"), "2022-07-01T00:00:00.000Z"))
+            .rev()
+            .collect();
+
+        push_entry(
+            &mut entries,
+            HistoryEntry::test_entry("v1.1.0", "2022-07-02T00:00:00.000Z"),
+        );
+
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries.first().unwrap().tag, "v1.1.0");
+        assert_eq!(entries.last().unwrap().tag, "v1.0.1");
+    }
+
+    #[test]
+    fn push_entry_below_limit() {
+        let mut entries = vec![HistoryEntry::test_entry(
+            "v1.0.0",
+            "2022-07-01T00:00:00.000Z",
+        )];
+
+        push_entry(
+            &mut entries,
+            HistoryEntry::test_entry("v1.1.0", "2022-07-02T00:00:00.000Z"),
+        );
+
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| &entry.tag[..])
+                .collect::<Vec<_>>(),
+            vec!["v1.1.0", "v1.0.0"]
+        );
+    }
+
+    #[test]
+    fn entry_round_trip() {
+        let mut entry = HistoryEntry::test_entry("v1.2.4", "2022-07-02T00:00:00.000Z");
+        entry.previous_tag = Some(String::from("v1.2.3"));
+
+        let json = serde_json::to_string(&entry).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"tag":"v1.2.4","version":"1.2.4","date":"2022-07-02T00:00:00.000Z","post_url":"https://community.signalusers.org/t/123/45","commits_count":3,"previous_tag":"v1.2.3","topic_id":123,"post_number":45}"#
+        );
+        assert_eq!(serde_json::from_str::<HistoryEntry>(&json).unwrap(), entry);
+    }
+
+    #[test]
+    fn entry_without_post_details() {
+        let entry: HistoryEntry = serde_json::from_str(
+            r#"{"tag":"v1.2.4","version":"1.2.4","date":"2022-07-02T00:00:00.000Z","post_url":"https://community.signalusers.org/t/123/45","commits_count":3}"#,
+        )
+        .unwrap();
+
+        let mut expected = HistoryEntry::test_entry("v1.2.4", "2022-07-02T00:00:00.000Z");
+        expected.topic_id = None;
+        expected.post_number = None;
+
+        assert_eq!(entry, expected);
+    }
+
+    #[test]
+    fn entry_with_commit_subjects_round_trip() {
+        let mut entry = HistoryEntry::test_entry("v1.2.4", "2022-07-02T00:00:00.000Z");
+        entry.commit_subjects = vec![String::from("Fix crash"), String::from("Bump version")];
+
+        let json = serde_json::to_string(&entry).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"tag":"v1.2.4","version":"1.2.4","date":"2022-07-02T00:00:00.000Z","post_url":"https://community.signalusers.org/t/123/45","commits_count":3,"previous_tag":null,"topic_id":123,"post_number":45,"commit_subjects":["Fix crash","Bump version"]}"#
+        );
+        assert_eq!(serde_json::from_str::<HistoryEntry>(&json).unwrap(), entry);
+    }
+
+    #[test]
+    fn commit_subjects_trimmed() {
+        let subjects: Vec<String> = (0..MAX_COMMIT_SUBJECTS + 5)
+            .map(|i| format!("Commit {i}"))
+            .collect();
+
+        let trimmed = trimmed_commit_subjects(&subjects);
+
+        assert_eq!(trimmed.len(), MAX_COMMIT_SUBJECTS);
+        assert_eq!(trimmed, subjects[..MAX_COMMIT_SUBJECTS]);
+    }
+
+    #[test]
+    fn long_commit_subject_trimmed() {
+        let exact = "ä".repeat(MAX_COMMIT_SUBJECT_CHARS);
+        let long = "ä".repeat(MAX_COMMIT_SUBJECT_CHARS + 1);
+
+        let trimmed = trimmed_commit_subjects(&[exact.clone(), long]);
+
+        assert_eq!(trimmed[0], exact);
+        assert_eq!(trimmed[1].chars().count(), MAX_COMMIT_SUBJECT_CHARS);
+        assert!(trimmed[1].ends_with("ää…"));
+    }
+}
@@ -0,0 +1,595 @@
+use anyhow::{anyhow, Context};
+use worker::{
+    wasm_bindgen::JsValue, Cache, Fetch, Headers, Method, Request, RequestInit, Response,
+};
+
+use crate::{
+    log::{log_info, log_warn},
+    metrics, utils,
+};
+
+/// A request that is ready to be sent, independent of the runtime that sends it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Option<String>,
+}
+
+impl PreparedRequest {
+    pub fn into_request(self) -> anyhow::Result<Request> {
+        let mut headers = Headers::new();
+        for (name, value) in &self.headers {
+            headers
+                .set(name, value)
+                .map_err(|e| anyhow!(e.to_string()))
+                .with_context(|| format!("could not set {name} header"))?;
+        }
+
+        let mut request_init = RequestInit::new();
+        request_init.with_method(self.method).with_headers(headers);
+
+        if let Some(body) = &self.body {
+            request_init.with_body(Some(JsValue::from_str(body)));
+        }
+
+        Request::new_with_init(&self.url, &request_init)
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not create request")
+    }
+}
+
+/// A response whose body has already been read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status_code: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Returns the value of the header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sends requests; [`FetchClient`] in the worker, a fake one in tests.
+pub trait HttpClient {
+    async fn send(&self, request: PreparedRequest) -> anyhow::Result<HttpResponse>;
+}
+
+/// Sends requests with the Fetch API, see [`utils::fetch`].
+pub struct FetchClient;
+
+impl HttpClient for FetchClient {
+    async fn send(&self, request: PreparedRequest) -> anyhow::Result<HttpResponse> {
+        let mut response = utils::fetch(Fetch::Request(request.into_request()?)).await?;
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not get response body")?;
+
+        Ok(HttpResponse {
+            status_code: response.status_code(),
+            headers: response.headers().entries().collect(),
+            body,
+        })
+    }
+}
+
+/// Keeps responses for a while; [`WorkersCache`] in the worker, a fake one in tests.
+pub trait ResponseCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<HttpResponse>>;
+    async fn put(&self, key: &str, response: &HttpResponse, ttl_seconds: u64)
+        -> anyhow::Result<()>;
+}
+
+/// Headers of responses that are kept in the cache; others, like `Content-Encoding`,
+/// may not match the body once it is read.
+const CACHED_HEADERS: &[&str] = &["Content-Type", "ETag", "Link"];
+
+/// Keeps responses in the default cache of the Workers Cache API, which is local to the data
+/// center and may evict them early, but does not count against KV limits.
+pub struct WorkersCache;
+
+impl ResponseCache for WorkersCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<HttpResponse>> {
+        let Some(mut response) = Cache::default()
+            .get(key, false)
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not get from cache")?
+        else {
+            return Ok(None);
+        };
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not get cached response body")?;
+
+        Ok(Some(HttpResponse {
+            status_code: response.status_code(),
+            headers: response.headers().entries().collect(),
+            body,
+        }))
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        response: &HttpResponse,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<()> {
+        let mut headers = Headers::new();
+        for (name, value) in cached_headers(response, ttl_seconds) {
+            headers
+                .set(&name, &value)
+                .map_err(|e| anyhow!(e.to_string()))
+                .with_context(|| format!("could not set {name} header"))?;
+        }
+
+        let cached = Response::ok(response.body.clone())
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not create response to cache")?
+            .with_status(response.status_code)
+            .with_headers(headers);
+
+        Cache::default()
+            .put(key, cached)
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not put to cache")
+    }
+}
+
+/// Headers that `response` is kept in the cache with, expiring after `ttl_seconds`.
+fn cached_headers(response: &HttpResponse, ttl_seconds: u64) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = CACHED_HEADERS
+        .iter()
+        .filter_map(|name| Some((name.to_string(), response.header(name)?.to_string())))
+        .collect();
+
+    headers.push((
+        String::from("Cache-Control"),
+        format!("max-age={ttl_seconds}"),
+    ));
+
+    headers
+}
+
+/// Key that the response to `request` is cached with: its URL, plus whether it is
+/// authenticated, as that can change what the response has (e.g. rate limits or private topics).
+pub fn cache_key(request: &PreparedRequest) -> String {
+    let authenticated = request.headers.iter().any(|(name, _)| {
+        name.eq_ignore_ascii_case("Authorization") || name.eq_ignore_ascii_case("User-Api-Key")
+    });
+
+    let separator = if request.url.contains('?') { '&' } else { '?' };
+
+    format!(
+        "{}{separator}cache-authenticated={authenticated}",
+        request.url
+    )
+}
+
+/// Sends `GET` requests to the hosts in `ttls` through `cache`, keeping successful
+/// responses for the number of seconds configured for their host; other requests are
+/// sent directly with `inner`.
+///
+/// With `bypass`, responses are never taken from the cache, but are still put in it.
+pub struct CachingClient<C, K> {
+    inner: C,
+    cache: K,
+    ttls: Vec<(String, u64)>,
+    bypass: bool,
+}
+
+impl<C: HttpClient, K: ResponseCache> CachingClient<C, K> {
+    pub fn new(inner: C, cache: K, ttls: Vec<(String, u64)>, bypass: bool) -> Self {
+        Self {
+            inner,
+            cache,
+            ttls,
+            bypass,
+        }
+    }
+
+    /// Seconds that the response to `request` is cached for, or `None` if it is not cached.
+    fn ttl_seconds(&self, request: &PreparedRequest) -> Option<u64> {
+        if request.method != Method::Get {
+            return None;
+        }
+
+        let url = worker::Url::parse(&request.url).ok()?;
+        let host = url.host_str()?;
+
+        self.ttls
+            .iter()
+            .find(|(ttl_host, _)| ttl_host.eq_ignore_ascii_case(host))
+            .map(|(_, ttl_seconds)| *ttl_seconds)
+    }
+}
+
+impl<C: HttpClient, K: ResponseCache> HttpClient for CachingClient<C, K> {
+    async fn send(&self, request: PreparedRequest) -> anyhow::Result<HttpResponse> {
+        let Some(ttl_seconds) = self.ttl_seconds(&request) else {
+            return self.inner.send(request).await;
+        };
+
+        let key = cache_key(&request);
+
+        let outcome = if self.bypass {
+            "bypass"
+        } else {
+            match self.cache.get(&key).await {
+                Ok(Some(response)) => {
+                    log_info!("cache hit for {key}");
+                    metrics::incr("http_cache", metrics::Dimensions::default().outcome("hit"));
+                    return Ok(response);
+                }
+                Ok(None) => "miss",
+                Err(e) => {
+                    log_warn!("could not get {key} from cache: {e:?}");
+                    "miss"
+                }
+            }
+        };
+
+        log_info!("cache {outcome} for {key}");
+        metrics::incr(
+            "http_cache",
+            metrics::Dimensions::default().outcome(outcome),
+        );
+
+        let response = self.inner.send(request).await?;
+
+        if (200..=299).contains(&response.status_code) {
+            if let Err(e) = self.cache.put(&key, &response, ttl_seconds).await {
+                log_warn!("could not put {key} to cache: {e:?}");
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+/// Responds with canned responses keyed by method and URL, recording the requests it gets.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeClient {
+    responses: std::collections::HashMap<(Method, String), HttpResponse>,
+    /// Responses that are each given once, before the ones in `responses`.
+    responses_once: std::cell::RefCell<
+        std::collections::HashMap<(Method, String), std::collections::VecDeque<HttpResponse>>,
+    >,
+    requests: std::cell::RefCell<Vec<PreparedRequest>>,
+}
+
+#[cfg(test)]
+impl FakeClient {
+    pub fn respond(mut self, method: Method, url: &str, response: HttpResponse) -> Self {
+        self.responses.insert((method, url.to_string()), response);
+        self
+    }
+
+    /// Responds with `response` to the next request to `method url` only, e.g. to fail once.
+    pub fn respond_once(self, method: Method, url: &str, response: HttpResponse) -> Self {
+        self.responses_once
+            .borrow_mut()
+            .entry((method, url.to_string()))
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// Responds to `GET url` with `status_code` and `body`, plus a `Link` header if given.
+    pub fn respond_to_get(
+        self,
+        url: &str,
+        status_code: u16,
+        body: &serde_json::Value,
+        link_header: Option<String>,
+    ) -> Self {
+        let headers = link_header
+            .map(|link_header| vec![(String::from("link"), link_header)])
+            .unwrap_or_default();
+
+        self.respond(
+            Method::Get,
+            url,
+            HttpResponse {
+                status_code,
+                headers,
+                body: body.to_string(),
+            },
+        )
+    }
+
+    pub fn requests(&self) -> Vec<PreparedRequest> {
+        self.requests.borrow().clone()
+    }
+
+    pub fn requested_urls(&self) -> Vec<String> {
+        self.requests
+            .borrow()
+            .iter()
+            .map(|request| request.url.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl HttpClient for FakeClient {
+    async fn send(&self, request: PreparedRequest) -> anyhow::Result<HttpResponse> {
+        let key = (request.method.clone(), request.url.clone());
+        let response_once = self
+            .responses_once
+            .borrow_mut()
+            .get_mut(&key)
+            .and_then(|responses| responses.pop_front());
+        let response = response_once
+            .or_else(|| self.responses.get(&key).cloned())
+            .with_context(|| format!("no response for {:?} {}", request.method, request.url));
+
+        self.requests.borrow_mut().push(request);
+
+        response
+    }
+}
+
+/// Keeps responses in memory, recording the TTL they were put with.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeCache {
+    responses: std::cell::RefCell<std::collections::HashMap<String, (HttpResponse, u64)>>,
+}
+
+#[cfg(test)]
+impl FakeCache {
+    pub fn ttl_seconds(&self, key: &str) -> Option<u64> {
+        self.responses
+            .borrow()
+            .get(key)
+            .map(|(_, ttl_seconds)| *ttl_seconds)
+    }
+}
+
+#[cfg(test)]
+impl ResponseCache for FakeCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<HttpResponse>> {
+        Ok(self
+            .responses
+            .borrow()
+            .get(key)
+            .map(|(response, _)| response.clone()))
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        response: &HttpResponse,
+        ttl_seconds: u64,
+    ) -> anyhow::Result<()> {
+        self.responses
+            .borrow_mut()
+            .insert(key.to_string(), (response.clone(), ttl_seconds));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::poll_once;
+
+    #[test]
+    fn header_ignores_case() {
+        let response = HttpResponse {
+            status_code: 200,
+            headers: vec![(String::from("link"), String::from("<https://example.com>"))],
+            body: String::new(),
+        };
+
+        assert_eq!(response.header("Link"), Some("<https://example.com>"));
+        assert_eq!(response.header("ETag"), None);
+    }
+
+    #[test]
+    fn fake_client_responds_by_method_and_url() {
+        let client = FakeClient::default().respond_to_get(
+            "https://example.com/a",
+            200,
+            &json!({ "a": 1 }),
+            None,
+        );
+
+        let get = PreparedRequest {
+            method: Method::Get,
+            url: String::from("https://example.com/a"),
+            headers: Vec::new(),
+            body: None,
+        };
+        let post = PreparedRequest {
+            method: Method::Post,
+            ..get.clone()
+        };
+
+        assert_eq!(
+            poll_once(client.send(get.clone())).unwrap().body,
+            r#"{"a":1}"#
+        );
+        assert_eq!(
+            poll_once(client.send(post.clone()))
+                .unwrap_err()
+                .to_string(),
+            "no response for Post https://example.com/a"
+        );
+        assert_eq!(client.requests(), vec![get, post]);
+    }
+
+    #[test]
+    fn fake_client_responds_once_first() {
+        let response = |body: &str| HttpResponse {
+            status_code: 200,
+            headers: Vec::new(),
+            body: body.to_string(),
+        };
+        let client = FakeClient::default()
+            .respond(Method::Post, "https://example.com/a", response("always"))
+            .respond_once(Method::Post, "https://example.com/a", response("once"));
+
+        let post = PreparedRequest {
+            method: Method::Post,
+            url: String::from("https://example.com/a"),
+            headers: Vec::new(),
+            body: None,
+        };
+
+        let bodies: Vec<String> = (0..3)
+            .map(|_| poll_once(client.send(post.clone())).unwrap().body)
+            .collect();
+
+        assert_eq!(bodies, ["once", "always", "always"]);
+    }
+
+    const TAGS_URL: &str = "https://api.github.com/repos/signalapp/Signal-Android/tags";
+
+    fn get(url: &str) -> PreparedRequest {
+        PreparedRequest {
+            method: Method::Get,
+            url: url.to_string(),
+            headers: vec![("Authorization", String::from("Bearer ghp_123"))],
+            body: None,
+        }
+    }
+
+    fn caching_client(client: FakeClient, bypass: bool) -> CachingClient<FakeClient, FakeCache> {
+        CachingClient::new(
+            client,
+            FakeCache::default(),
+            vec![(String::from("api.github.com"), 60)],
+            bypass,
+        )
+    }
+
+    #[test]
+    fn cached_response_is_reused() {
+        let client = caching_client(
+            FakeClient::default().respond_to_get(TAGS_URL, 200, &json!([]), None),
+            false,
+        );
+
+        for _ in 0..2 {
+            assert_eq!(poll_once(client.send(get(TAGS_URL))).unwrap().body, "[]");
+        }
+
+        assert_eq!(client.inner.requests().len(), 1);
+        assert_eq!(
+            client.cache.ttl_seconds(&cache_key(&get(TAGS_URL))),
+            Some(60)
+        );
+    }
+
+    #[test]
+    fn bypass_sends_every_request() {
+        let client = caching_client(
+            FakeClient::default().respond_to_get(TAGS_URL, 200, &json!([]), None),
+            true,
+        );
+
+        for _ in 0..2 {
+            poll_once(client.send(get(TAGS_URL))).unwrap();
+        }
+
+        assert_eq!(client.inner.requests().len(), 2);
+        assert_eq!(
+            client.cache.ttl_seconds(&cache_key(&get(TAGS_URL))),
+            Some(60)
+        );
+    }
+
+    #[test]
+    fn failed_response_is_not_cached() {
+        let client = caching_client(
+            FakeClient::default().respond_to_get(TAGS_URL, 502, &json!({}), None),
+            false,
+        );
+
+        for _ in 0..2 {
+            assert_eq!(
+                poll_once(client.send(get(TAGS_URL))).unwrap().status_code,
+                502
+            );
+        }
+
+        assert_eq!(client.inner.requests().len(), 2);
+        assert_eq!(client.cache.ttl_seconds(&cache_key(&get(TAGS_URL))), None);
+    }
+
+    #[test_case(get("https://community.signalusers.org/t/123.json"); "host without TTL")]
+    #[test_case(PreparedRequest { method: Method::Post, ..get(TAGS_URL) }; "not GET")]
+    fn not_cached(request: PreparedRequest) {
+        let client = caching_client(
+            FakeClient::default()
+                .respond_to_get(&request.url, 200, &json!({}), None)
+                .respond(
+                    Method::Post,
+                    &request.url,
+                    HttpResponse {
+                        status_code: 200,
+                        headers: Vec::new(),
+                        body: String::from("{}"),
+                    },
+                ),
+            false,
+        );
+
+        for _ in 0..2 {
+            poll_once(client.send(request.clone())).unwrap();
+        }
+
+        assert_eq!(client.inner.requests().len(), 2);
+        assert_eq!(client.cache.ttl_seconds(&cache_key(&request)), None);
+    }
+
+    #[test_case(get(TAGS_URL), "https://api.github.com/repos/signalapp/Signal-Android/tags?cache-authenticated=true"; "authenticated")]
+    #[test_case(PreparedRequest { headers: Vec::new(), ..get(TAGS_URL) }, "https://api.github.com/repos/signalapp/Signal-Android/tags?cache-authenticated=false"; "anonymous")]
+    #[test_case(PreparedRequest { headers: vec![("User-Api-Key", String::from("key"))], ..get("https://community.signalusers.org/t/123.json?print=true") }, "https://community.signalusers.org/t/123.json?print=true&cache-authenticated=true"; "with query")]
+    fn key(request: PreparedRequest, result: &str) {
+        assert_eq!(cache_key(&request), result);
+    }
+
+    #[test]
+    fn headers_to_cache() {
+        let response = HttpResponse {
+            status_code: 200,
+            headers: vec![
+                (String::from("etag"), String::from("W/\"abc\"")),
+                (String::from("content-encoding"), String::from("gzip")),
+                (String::from("set-cookie"), String::from("a=b")),
+            ],
+            body: String::new(),
+        };
+
+        assert_eq!(
+            cached_headers(&response, 60),
+            [
+                (String::from("ETag"), String::from("W/\"abc\"")),
+                (String::from("Cache-Control"), String::from("max-age=60")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+};
+
+use worker::Env;
+
+use crate::{
+    background::Spawner,
+    core::{
+        log as core_log,
+        task_local::{self, Scoped},
+    },
+    log, metrics,
+};
+
+/// What an invocation of the worker keeps for itself, rather than for the isolate,
+/// which may handle several invocations concurrently (see [`task_local::scope`]).
+#[derive(Default)]
+pub struct Invocation {
+    /// Where best-effort work is handed over to, see [`crate::background::defer`];
+    /// with `None`, it is waited for instead.
+    pub spawner: Option<Box<dyn Spawner>>,
+    /// Where data points are written to, see [`crate::metrics`]; with `None`, they are dropped.
+    pub metrics: Option<Box<dyn metrics::Sink>>,
+    /// Subrequests made so far, see [`crate::utils::subrequest_count`].
+    pub subrequests: Cell<usize>,
+    /// Requests to the GitHub API made so far, see [`crate::utils::github_request_count`].
+    pub github_requests: Cell<usize>,
+    /// GitHub rate limit headroom of the current run, see [`crate::rate_limit`].
+    pub rate_limit: Cell<crate::rate_limit::RunHeadroom>,
+}
+
+impl Invocation {
+    /// Starts an invocation with `env`, handing best-effort work over to `spawner`.
+    pub fn new(env: &Env, spawner: Option<Box<dyn Spawner>>) -> Self {
+        Self {
+            spawner,
+            metrics: metrics::sink(env),
+            ..Default::default()
+        }
+    }
+}
+
+thread_local! {
+    /// Invocation of the future being polled, see [`scope`].
+    static CURRENT: task_local::Current<Invocation> = const { RefCell::new(None) };
+}
+
+/// Runs `future` as `invocation`.
+pub fn scope<F: Future>(invocation: Invocation, future: F) -> Scoped<Invocation, F> {
+    task_local::scope(&CURRENT, invocation, future)
+}
+
+/// Runs `future`, an invocation of the worker, as `invocation` and with its own logging
+/// (see [`log::scope`]).
+pub async fn run<F: Future>(env: &Env, invocation: Invocation, future: F) -> F::Output {
+    log::scope(env, scope(invocation, future)).await
+}
+
+/// Runs `future` as part of the invocation that is current now, with its logging,
+/// e.g. for work that is finished after the handler returns.
+pub fn in_current_scope<F: Future>(future: F) -> Scoped<core_log::Context, Scoped<Invocation, F>> {
+    core_log::in_current_scope(task_local::in_current_scope(&CURRENT, future))
+}
+
+/// Calls `f` with the current invocation, or `None` outside of one, e.g. in tests.
+pub fn with<T>(f: impl FnOnce(Option<&Invocation>) -> T) -> T {
+    f(task_local::current(&CURRENT).as_deref())
+}
@@ -1,411 +1,3456 @@
-#![feature(array_windows)]
+use std::{collections::HashMap, future::Future};
 
 use anyhow::Context;
+use futures_util::future::join_all;
 use semver::Version;
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
-use worker::{
-    console_error, console_log, console_warn, event, Env, ScheduleContext, ScheduledEvent,
-};
-
-mod localization;
-mod markdown;
+use worker::{event, Env, ScheduleContext, ScheduledEvent};
+
+mod backfill;
+mod background;
+mod bluesky;
+mod bootstrap;
+mod comparison_cache;
+/// Logic that does not depend on the Workers runtime: nothing in it imports `worker`, so that
+/// it builds and is tested natively, while fetching, KV and environment variables are left
+/// to the other modules.
+mod core;
+mod diff;
+mod endpoints;
+mod failure_notification;
+mod feed;
+mod health;
+mod history;
+mod http;
+mod invocation;
+mod liveness;
+mod log;
+mod mastodon;
+mod matrix;
+mod metrics;
+mod notify;
 mod panic_hook;
-mod platform;
+mod pending_post;
+mod platform_queue;
+mod posted_commits;
+mod rate_limit;
+mod release_history;
+mod request_budget;
+mod sentry;
 mod state;
-mod types;
+mod state_backup;
+mod state_repair;
+mod tags_etag;
+mod telegram;
 mod utils;
 
+use http::HttpClient;
 use localization::{
-    Completeness, LocalizationChange, LocalizationChangeCollection, LocalizationChanges,
+    Completeness, LocalizationChange, LocalizationChangeCollection, LocalizationChanges, NewString,
+    UnsortedChanges,
 };
+use log::{log_error, log_info, log_warn};
 use platform::Platform;
+use request_budget::RequestBudget;
 use state::StateController;
+use types::github::Tag;
+use utils::Conditional;
+
+use crate::core::{hash, libsignal, localization, markdown, platform, schedule, types};
 
 const POSTING_DELAY_MILLISECONDS: u64 = 3000;
+const RECUT_TAG_EDIT_REASON: &str = "tag was re-cut";
+
+/// At most this many commits are looked at individually for localization changes,
+/// in addition to the ones likely changing localization.
+const MAX_LOCALIZATION_COMMITS_TO_INSPECT: usize = 50;
+
+/// At most this many commits that change dependency manifests are looked at individually
+/// for libsignal bumps, preferring the ones that mention libsignal.
+const MAX_LIBSIGNAL_COMMITS_TO_INSPECT: usize = 5;
 
 enum PlatformCheckOutcome {
     LatestVersionIsAlreadyPosted,
     NewTopicNotFound,
     PostedCommits,
+    PostDeferred,
+    EditedPostForRecutTag,
+    Paused,
+    CoolingDown,
+    RateLimited,
+    ForumReadOnly,
+    OverRequestBudget,
+    NewTagIsBehind,
+    SkippedSameCode,
+    RenderedPostInDryRun {
+        topic_id: u64,
+        markdown_text: String,
+    },
 }
 
 use PlatformCheckOutcome::*;
 
-// Used for debugging, to manually trigger the bot outside of schedule.
+impl PlatformCheckOutcome {
+    const fn description(&self) -> &'static str {
+        match self {
+            LatestVersionIsAlreadyPosted => "nothing new",
+            NewTopicNotFound => "topic not found",
+            PostedCommits => "posted",
+            PostDeferred => "deferred",
+            EditedPostForRecutTag => "edited",
+            Paused => "paused",
+            CoolingDown => "cooling down",
+            RateLimited => "rate limited",
+            ForumReadOnly => "deferred: forum read-only",
+            OverRequestBudget => "skipped: over request budget",
+            NewTagIsBehind => "new tag behind",
+            SkippedSameCode => "same code",
+            RenderedPostInDryRun { .. } => "dry run",
+        }
+    }
+}
+
+type PlatformCheckResults = Vec<(Platform, anyhow::Result<PlatformCheckOutcome>)>;
+
 #[event(fetch)]
 pub async fn fetch(
-    _req: worker::Request,
+    mut req: worker::Request,
     env: Env,
-    _ctx: worker::Context,
+    ctx: worker::Context,
 ) -> worker::Result<worker::Response> {
-    main(&env).await;
-    worker::Response::empty()
+    panic_hook::set_panic_hook();
+    let invocation = invocation::Invocation::new(&env, Some(Box::new(ctx)));
+    invocation::run(&env, invocation, handle_fetch(&mut req, &env)).await
 }
 
-#[event(scheduled)]
-pub async fn scheduled(_event: ScheduledEvent, env: Env, _ctx: ScheduleContext) {
-    main(&env).await;
+async fn handle_fetch(req: &mut worker::Request, env: &Env) -> worker::Result<worker::Response> {
+    utils::configure_requests(env);
+
+    match endpoints::handle(req, env).await {
+        Some(response) => response,
+        None => worker::Response::error("Not Found", 404),
+    }
 }
 
-async fn main(env: &Env) {
+#[event(scheduled)]
+pub async fn scheduled(event: ScheduledEvent, env: Env, ctx: ScheduleContext) {
     panic_hook::set_panic_hook();
-
-    match check_all_platforms(env).await {
-        Err(e) => console_error!("{e:?}"),
-        Ok(_) => console_log!("finished successfully"),
-    }
+    let invocation = invocation::Invocation::new(&env, Some(Box::new(ctx)));
+    invocation::run(&env, invocation, run_scheduled(event, &env)).await
 }
 
-async fn check_all_platforms(env: &Env) -> anyhow::Result<()> {
-    let mut state_controller = state::StateController::from_kv(env).await?;
+async fn run_scheduled(event: ScheduledEvent, env: &Env) {
+    utils::configure_requests(env);
 
-    for platform in Platform::iter() {
-        let outcome = check_platform(&mut state_controller, env, platform).await?;
+    let cron = event.cron();
+    log_info!("cron = {cron}");
 
-        match outcome {
-            LatestVersionIsAlreadyPosted => console_log!("latest version is already posted"),
-            NewTopicNotFound => console_warn!("no topic found, may be not created yet"),
-            PostedCommits => {
-                console_warn!("already posted for {platform} and currently doing only one \"commits\" post per invocation, done");
-                break;
-            }
+    let platforms = match utils::cron_platforms(env)
+        .and_then(|mapping| schedule::platforms_for_cron(mapping.as_deref(), &cron))
+    {
+        Ok(platforms) => platforms,
+        Err(e) => {
+            log_error!("{e:?}");
+            sentry::report(
+                env,
+                &e,
+                sentry::Report {
+                    platform: None,
+                    tag: None,
+                },
+            )
+            .await;
+            log_warn!("checking all platforms");
+            Platform::iter().collect()
         }
+    };
+
+    log_info!("platforms = {platforms:?}");
+
+    let started_at = worker::js_sys::Date::now();
+
+    if let Some(queue) = platform_queue::Queue::from_env(env) {
+        let outcome =
+            match platform_queue::enqueue(&queue, &platforms, event.schedule() as u64).await {
+                Ok(()) => "enqueued",
+                Err(e) => {
+                    log_error!("{e:?}");
+                    sentry::report(
+                        env,
+                        &e,
+                        sentry::Report {
+                            platform: None,
+                            tag: None,
+                        },
+                    )
+                    .await;
+                    "failure"
+                }
+            };
 
-        console_log!("----------------------------------------------------------------------");
+        metrics::timing(
+            "run_ms",
+            worker::js_sys::Date::now() - started_at,
+            metrics::Dimensions::default().outcome(outcome),
+        );
+        return;
     }
 
-    Ok(())
-}
+    let result = check_platforms(env, &platforms, false, true, false).await;
 
-async fn check_platform(
-    state_controller: &mut StateController,
-    env: &Env,
-    platform: Platform,
-) -> anyhow::Result<PlatformCheckOutcome> {
-    console_log!("checking platform = {platform}");
+    let outcome = match &result {
+        Err(e) => {
+            log_error!("{e:?}");
+            "failure"
+        }
+        Ok(results) => match results.iter().filter(|(_, result)| result.is_err()).count() {
+            0 => {
+                log_info!("finished successfully");
+                "success"
+            }
+            failed => {
+                log_error!("finished, checking {failed} platform(s) failed");
+                "failure"
+            }
+        },
+    };
 
-    let all_tags: Vec<types::github::Tag> =
-        utils::get_json_from_url(&platform.github_api_tags_url())
-            .await
-            .context("could not fetch tags from GitHub")?;
+    metrics::timing(
+        "run_ms",
+        worker::js_sys::Date::now() - started_at,
+        metrics::Dimensions::default().outcome(outcome),
+    );
 
-    console_log!("all_tags = {:?}", all_tags);
+    record_run(env, run_failure(result).as_ref()).await;
+}
 
-    let mut tags: Vec<(types::github::Tag, Version)> = all_tags
-        .iter()
-        .filter_map(|tag| tag.to_version().ok().map(|version| (tag.clone(), version)))
-        .filter(|(_, version)| platform.should_post_version(version))
+/// Error of a run that checked platforms, combining the errors of the platforms that
+/// failed, or `None` if it completed without errors.
+fn run_failure(result: anyhow::Result<PlatformCheckResults>) -> Option<anyhow::Error> {
+    let results = match result {
+        Ok(results) => results,
+        Err(e) => return Some(e),
+    };
+
+    let failures: Vec<String> = results
+        .into_iter()
+        .filter_map(|(platform, result)| result.err().map(|e| format!("{platform}: {e:?}")))
         .collect();
 
-    console_log!("tags = {:?}", tags);
+    match failures.is_empty() {
+        true => None,
+        false => Some(anyhow::anyhow!(failures.join("\n\n"))),
+    }
+}
 
-    tags.sort_unstable_by(|(_, lhs), (_, rhs)| lhs.cmp(rhs));
-    console_log!("after sorting, tags = {:?}", tags);
+/// Records a run that completed without errors, or else checks whether the bot may be down
+/// (see [`liveness`]).
+async fn record_run(env: &Env, failure: Option<&anyhow::Error>) {
+    match failure {
+        None => liveness::record_success(env).await,
+        Some(e) => {
+            liveness::check_after_failure(env, e, utils::is_dry_run(env).unwrap_or_default()).await
+        }
+    }
+}
 
-    // TODO: assumes the last posted tag can be found on this GitHub API page
-    let tags_to_post: Vec<(types::github::Tag, Version)> = tags
-        .iter()
-        .skip_while(|(tag, _)| tag != &state_controller.platform_state(platform).last_posted_tag)
-        .cloned()
-        .collect();
+/// Handles a batch of [`platform_queue::CheckMessage`]s, checking one platform at a time.
+///
+/// `worker` has no `#[event]` for queues yet, so this is exported like its handlers are.
+async fn queue(batch: worker::wasm_bindgen::JsValue, env: Env) {
+    panic_hook::set_panic_hook();
+    // nothing waits for the consumer, so best-effort work is simply done before acking
+    let invocation = invocation::Invocation::new(&env, None);
+    invocation::run(&env, invocation, check_batch(batch, &env)).await
+}
 
-    console_log!("tags_to_post = {:?}", tags_to_post);
+async fn check_batch(batch: worker::wasm_bindgen::JsValue, env: &Env) {
+    utils::configure_requests(env);
 
-    if let Some([(old_tag, old_version), (new_tag, new_version)]) =
-        tags_to_post.array_windows().next()
-    {
-        console_log!(
-            "looking at [old_tag: {:?}, new_tag: {:?}]",
-            old_tag,
-            new_tag
+    for message in platform_queue::messages(&batch) {
+        let disposition = check_queued(env, &message.body).await;
+        message.settle(disposition);
+    }
+}
+
+mod _worker_queue {
+    use ::worker::{wasm_bindgen, wasm_bindgen_futures};
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    pub async fn queue(
+        batch: wasm_bindgen::JsValue,
+        env: ::worker::Env,
+        _ctx: wasm_bindgen::JsValue,
+    ) {
+        super::queue(batch, env).await
+    }
+}
+
+/// Checks the platform of the queue message with `body`, unless the message was handled before.
+async fn check_queued(env: &Env, body: &str) -> platform_queue::Disposition {
+    let message = match platform_queue::CheckMessage::from_json(body) {
+        Ok(message) => message,
+        Err(e) => {
+            log_error!("dropping queue message: {e:?}");
+            return platform_queue::Disposition::Ack;
+        }
+    };
+
+    let platform = match message.platform() {
+        Ok(platform) => platform,
+        Err(e) => {
+            log_error!("dropping queue message: {e:?}");
+            return platform_queue::Disposition::Ack;
+        }
+    };
+
+    // delivery is at least once, so a message may come again after it was handled
+    if platform_queue::was_processed(env, &message.id).await {
+        log_info!(
+            platform = platform,
+            "queue message {} was already processed",
+            message.id
         );
+        return platform_queue::Disposition::Ack;
+    }
 
-        let discourse_api_key = utils::api_key(env)?;
+    let result = match check_platforms(env, &[platform], false, true, false).await {
+        Ok(mut results) => match results.pop() {
+            Some((_, result)) => result,
+            None => return platform_queue::Disposition::Ack,
+        },
+        Err(e) => Err(e),
+    };
+
+    record_run(env, result.as_ref().err()).await;
+
+    let disposition = platform_queue::disposition(&result);
+    log_info!(
+        platform = platform,
+        "queue message {}: {disposition:?}",
+        message.id
+    );
+
+    if disposition == platform_queue::Disposition::Ack {
+        platform_queue::mark_processed(env, &message.id).await;
+    }
 
-        let new_topic_id =
-            utils::get_topic_id_or_override(env, &discourse_api_key, platform, new_version)
-                .await
-                .context("could not find new_topic_id")?;
+    disposition
+}
 
-        match new_topic_id {
-            Some(new_topic_id) => {
-                console_log!("new_topic_id = {new_topic_id}");
+/// Checks `platforms` concurrently, doing at most one "commits" post for each; as they
+/// no longer wait for each other, one platform posting does not end the run for the others.
+///
+/// Runs in dry run if `force_dry_run` is set or `DRY_RUN` is configured.
+/// Comparisons are always fetched from GitHub unless `use_comparison_cache` is set,
+/// and tags and topics are not taken from the HTTP cache with `bypass_http_cache`.
+///
+/// Returns the result for each platform.
+async fn check_platforms(
+    env: &Env,
+    platforms: &[Platform],
+    force_dry_run: bool,
+    use_comparison_cache: bool,
+    bypass_http_cache: bool,
+) -> anyhow::Result<PlatformCheckResults> {
+    let is_dry_run = force_dry_run || utils::is_dry_run(env)?;
+    log_info!("is_dry_run = {is_dry_run}");
+
+    let state_controller = match state::StateController::from_kv(env, is_dry_run).await {
+        Ok(state_controller) => state_controller,
+        Err(e) => {
+            failure_notification::notify(env, None, None, &e, is_dry_run).await;
+            sentry::report(
+                env,
+                &e,
+                sentry::Report {
+                    platform: None,
+                    tag: None,
+                },
+            )
+            .await;
+            return Err(e);
+        }
+    };
+
+    let http_client = http::CachingClient::new(
+        http::FetchClient,
+        http::WorkersCache,
+        utils::http_cache_ttls(env)?,
+        bypass_http_cache,
+    );
+
+    let budget = RequestBudget::start(utils::subrequest_soft_cap(env)?);
+    log_info!("subrequest soft cap = {:?}", budget.soft_cap());
+
+    let github_requests_before = utils::github_request_count();
+    rate_limit::start_run(env).await;
+
+    let results = check_concurrently(platforms, |platform| {
+        check_and_record(
+            &state_controller,
+            env,
+            platform,
+            &http_client,
+            &budget,
+            use_comparison_cache,
+        )
+    })
+    .await;
+
+    log_info!(
+        "made {} GitHub request(s)",
+        utils::github_request_count() - github_requests_before
+    );
+
+    rate_limit::finish_run(env).await;
+
+    log_info!("made {} subrequest(s)", budget.used());
+    metrics::gauge(
+        "subrequests",
+        budget.used() as f64,
+        metrics::Dimensions::default(),
+    );
+
+    let skipped = skipped_over_budget(&results);
+    if !skipped.is_empty() {
+        log_warn!(
+            "subrequest soft cap of {:?} reached, skipped {skipped:?} for the next run",
+            budget.soft_cap()
+        );
+    }
 
-                let same_release = old_version.major == new_version.major
-                    && old_version.minor == new_version.minor;
-                console_log!("same_release = {}", same_release);
+    Ok(results)
+}
 
-                // Post archiving message to old topic if necessary and possible
-                post_archiving_message_if_necessary(
-                    same_release,
-                    state_controller,
-                    env,
-                    platform,
-                    &discourse_api_key,
-                    old_version,
-                    new_topic_id,
-                )
-                .await?;
+/// Platforms that were left for the next run because the request budget was used up.
+fn skipped_over_budget(results: &PlatformCheckResults) -> Vec<Platform> {
+    results
+        .iter()
+        .filter(|(_, result)| matches!(result, Ok(OverRequestBudget)))
+        .map(|(platform, _)| *platform)
+        .collect()
+}
 
-                // Post commits to new topic
+/// Runs `check` for all `platforms` at once, so that one platform failing
+/// or taking long does not hold up the others.
+async fn check_concurrently<F, Fut>(platforms: &[Platform], check: F) -> PlatformCheckResults
+where
+    F: Fn(Platform) -> Fut,
+    Fut: Future<Output = anyhow::Result<PlatformCheckOutcome>>,
+{
+    let results = join_all(platforms.iter().map(|&platform| check(platform))).await;
 
-                let reply_to_post_number = if same_release {
-                    state_controller.platform_state(platform).last_post_number
-                } else {
-                    None
-                };
-                console_log!("reply_to_post_number = {:?}", reply_to_post_number);
+    platforms.iter().copied().zip(results).collect()
+}
 
-                let comparison =
-                    utils::get_github_comparison(platform, &old_tag.name, &new_tag.name)
-                        .await
-                        .context("could not get build comparison from GitHub")?;
+async fn check_and_record(
+    state_controller: &StateController,
+    env: &Env,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    budget: &RequestBudget,
+    use_comparison_cache: bool,
+) -> anyhow::Result<PlatformCheckOutcome> {
+    if state_controller.is_paused(platform) {
+        log_warn!(platform = platform, "{platform} is paused, skipping");
+        return Ok(Paused);
+    }
 
-                console_log!("comparison = {:?}", comparison);
+    if let Some(next_attempt_after) = cooling_down_until(state_controller, platform) {
+        log_warn!(
+            platform = platform,
+            "{platform} failed recently, skipping until {next_attempt_after}"
+        );
+        return Ok(CoolingDown);
+    }
 
-                let unfiltered_commits: Vec<markdown::Commit> = comparison
-                    .commits
-                    .iter()
-                    .map(|github_commit| {
-                        markdown::Commit::from_github_commit(platform, github_commit)
-                    })
-                    .collect();
+    if budget.is_exhausted() {
+        log_warn!(
+            platform = platform,
+            "made {} subrequest(s), leaving {platform} to the next run",
+            budget.used()
+        );
+        return Ok(OverRequestBudget);
+    }
 
-                let unfiltered_commits_len = unfiltered_commits.len();
-                console_log!("unfiltered_commits_len = {:?}", unfiltered_commits_len);
+    let result = check_platform(
+        state_controller,
+        env,
+        platform,
+        http_client,
+        budget,
+        use_comparison_cache,
+    )
+    .await;
+    record_check(state_controller, platform, budget, &result).await;
+
+    // state is only saved after completed steps, so the platform is simply checked again next run
+    let result = match result {
+        Err(e) => match rate_limit::exceeded_in(&e) {
+            Some(exceeded) => {
+                log_warn!(
+                    platform = platform,
+                    "{exceeded}; skipping {platform} for this run"
+                );
+                Ok(RateLimited)
+            }
+            None if utils::is_forum_read_only(&e) => {
+                // the pending post is kept, so exactly the same post is retried next run
+                log_warn!(
+                    platform = platform,
+                    "forum is read-only; deferring posting for {platform} to the next run"
+                );
+                Ok(ForumReadOnly)
+            }
+            None => Err(e),
+        },
+        result => result,
+    };
+
+    let outcome = match &result {
+        Ok(outcome) => outcome.description(),
+        Err(_) => "failed",
+    };
+    metrics::incr(
+        "checks",
+        metrics::Dimensions::platform(platform).outcome(outcome),
+    );
+
+    match &result {
+        Ok(LatestVersionIsAlreadyPosted) => {
+            log_info!(
+                platform = platform,
+                "latest version is already posted for {platform}"
+            );
+        }
+        Ok(NewTopicNotFound) => {
+            log_warn!(
+                platform = platform,
+                "no topic found for {platform}, may be not created yet"
+            );
+        }
+        Ok(Paused)
+        | Ok(CoolingDown)
+        | Ok(RateLimited)
+        | Ok(ForumReadOnly)
+        | Ok(OverRequestBudget)
+        | Ok(PostDeferred) => {}
+        Ok(NewTagIsBehind) => {
+            log_warn!(
+                platform = platform,
+                "new tag is behind the last posted one for {platform}, not posting"
+            );
+        }
+        Ok(SkippedSameCode) => {
+            log_info!(
+                platform = platform,
+                "skipped tag with the same code as the last posted one for {platform}"
+            );
+        }
+        Ok(PostedCommits) => {
+            log_info!(platform = platform, "posted for {platform}");
+        }
+        Ok(EditedPostForRecutTag) => {
+            log_info!(
+                platform = platform,
+                "edited post for re-cut tag for {platform}"
+            );
+        }
+        Ok(RenderedPostInDryRun { .. }) => {
+            log_warn!(platform = platform, "dry run; rendered post for {platform}");
+        }
+        Err(e) => {
+            log_error!(platform = platform, "checking {platform} failed: {e:?}");
+            let tag = state_controller
+                .platform_state(platform)
+                .last_posted_tag
+                .name;
+
+            failure_notification::notify(
+                env,
+                Some(platform),
+                Some(&tag),
+                e,
+                state_controller.is_dry_run(),
+            )
+            .await;
+
+            let report = sentry::Report {
+                platform: Some(platform),
+                tag: Some(&tag),
+            };
+            sentry::report(env, e, report).await;
+        }
+    }
 
-                let commits: Vec<markdown::Commit> = unfiltered_commits
-                    .into_iter()
-                    .filter(|commit| platform.should_show_commit(commit.full_message()))
-                    .collect();
+    result
+}
 
-                let commits_len = commits.len();
-                console_log!("commits_len = {:?}", commits_len);
+/// Returns when `platform` may be checked again, if it is still cooling down after failures.
+fn cooling_down_until(state_controller: &StateController, platform: Platform) -> Option<String> {
+    let platform_state = state_controller.platform_state(platform);
 
-                let mut build_localization_changes =
-                    LocalizationChanges::from_comparison(platform, old_tag, new_tag, &comparison);
+    platform_state
+        .is_cooling_down(&utils::now_iso_string())
+        .then_some(platform_state.next_attempt_after)
+        .flatten()
+}
 
-                if let Completeness::Incomplete = build_localization_changes.completeness {
-                    let localization_change_commits: Vec<_> = commits
-                        .iter()
-                        .filter(|commit| commit.is_likely_localization_change())
-                        .collect();
+/// Records when `platform` was checked and the error, if any, for the status endpoint,
+/// and counts failures in a row that are not retriable, to back off from checking
+/// a failing platform.
+///
+/// This is committed for failed checks too, so that the cool-down is in place for the next run.
+/// Failures are only logged, so that the result of the check is not masked.
+async fn record_check(
+    state_controller: &StateController,
+    platform: Platform,
+    budget: &RequestBudget,
+    result: &anyhow::Result<PlatformCheckOutcome>,
+) {
+    let update = state_controller.update_platform_state(platform, |platform_state| {
+        platform_state.last_checked_at = Some(utils::now_iso_string());
+        platform_state.run_subrequests = Some(budget.used() as u64);
+        // redacted, as it is shown by the unauthenticated status endpoint
+        platform_state.last_error = result
+            .as_ref()
+            .err()
+            .map(|e| log::redact(&format!("{e:#}")));
+        platform_state.deferred_reason = None;
+
+        match result {
+            Ok(_) => platform_state.record_success(),
+            // maintenance of the forum does not count as a failure, posting is simply retried
+            Err(e) if utils::is_forum_read_only(e) => {
+                platform_state.last_error = None;
+                platform_state.deferred_reason = Some(String::from("forum read-only"));
+            }
+            // neither does anything else that may go away by itself, e.g. a rate limit,
+            // as backing off is only for failures that need someone to look into them
+            Err(e) if utils::is_retriable(e) => {}
+            Err(_) => platform_state.record_failure(utils::iso_string_in),
+        }
+    });
 
-                    console_log!(
-                        "localization_change_commits = {:?}",
-                        localization_change_commits
-                    );
+    if let Err(e) = update.await {
+        log_error!(
+            platform = platform,
+            "could not record check of {platform}: {e:?}"
+        );
+    }
+}
 
-                    if !localization_change_commits.is_empty() {
-                        let mut all_complete = true;
+async fn check_platform(
+    state_controller: &StateController,
+    env: &Env,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    budget: &RequestBudget,
+    use_comparison_cache: bool,
+) -> anyhow::Result<PlatformCheckOutcome> {
+    log_info!(platform = platform, "checking platform = {platform}");
 
-                        for commit in localization_change_commits {
-                            let with_files =
-                                utils::get_github_commit(platform, commit.sha()).await?;
+    let github_token = utils::github_token(env);
 
-                            console_log!("with_files = {:?}", with_files);
+    // before anything else, so that a bad template is noticed even if there is nothing to post
+    utils::post_header_template(env, platform)?;
 
-                            let mut changes = LocalizationChange::unsorted_changes_from_files(
-                                platform,
-                                &with_files.files,
-                            );
+    let last_posted_tag = state_controller.platform_state(platform).last_posted_tag;
 
-                            console_log!("changes = {:?}", changes);
+    let etag = tags_etag::get(env, platform, &last_posted_tag.name).await;
+    log_info!(platform = platform, "etag = {:?}", etag);
 
-                            build_localization_changes.add_unsorted_changes(&mut changes);
+    let (all_tags, new_etag): (Vec<Tag>, _) = match utils::get_json_from_url_if_modified(
+        http_client,
+        &platform.github_api_tags_url(),
+        github_token.as_deref(),
+        etag.as_deref(),
+    )
+    .await
+    .context("could not fetch tags from GitHub")?
+    {
+        Conditional::Modified { value, etag } => (value, etag),
+        Conditional::NotModified => {
+            log_info!(
+                platform = platform,
+                "tags were not modified since everything was posted"
+            );
+            return Ok(LatestVersionIsAlreadyPosted);
+        }
+    };
 
-                            let complete = with_files.are_files_likely_complete().unwrap();
-                            console_log!(
-                                "for commit.sha = {} files.complete = {complete}",
-                                commit.sha()
-                            );
+    log_info!(platform = platform, "all_tags = {:?}", all_tags);
 
-                            all_complete &= complete;
-                        }
+    let prerelease_identifiers = utils::prerelease_identifiers(env, platform)?;
+    log_info!(
+        platform = platform,
+        "prerelease_identifiers = {:?}",
+        prerelease_identifiers
+    );
 
-                        if all_complete {
-                            build_localization_changes.completeness = Completeness::LikelyComplete;
+    let mut tags: Vec<(Tag, Version)> = all_tags
+        .iter()
+        .filter_map(|tag| match tag.to_version() {
+            Ok(version) => Some((tag.clone(), version)),
+            Err(e) => {
+                // tags that are not versions are not posted, so they do not stop the run
+                log_warn!(platform = platform, "skipping tag {}: {e:?}", tag.name);
+                None
+            }
+        })
+        .filter(|(_, version)| platform.should_post_version(version, &prerelease_identifiers))
+        .collect();
 
-                            console_log!(
-                                "got complete files for all localization change commits, build_localization_changes.completeness = {:?}",
-                                build_localization_changes.completeness
-                            );
-                        }
-                    }
-                }
+    log_info!(platform = platform, "tags = {:?}", tags);
 
-                let localization_changes_completeness = build_localization_changes
-                    .completeness
-                    .min(if !same_release {
-                        Completeness::Complete
-                    } else {
-                        state_controller
-                            .platform_state(platform)
-                            .localization_changes_completeness
-                    });
-
-                let (release_localization_changes, last_posted_tag_previous_release) =
-                    if !same_release {
-                        (None, old_tag)
-                    } else {
-                        let last_posted_tag_previous_release = &state_controller
-                            .platform_state(platform)
-                            .last_posted_tag_previous_release;
+    tags.sort_unstable_by(|(_, lhs), (_, rhs)| lhs.cmp(rhs));
+    log_info!(platform = platform, "after sorting, tags = {:?}", tags);
+
+    if edit_post_if_tag_recut(
+        state_controller,
+        env,
+        platform,
+        http_client,
+        &tags,
+        use_comparison_cache,
+    )
+    .await?
+    {
+        return Ok(EditedPostForRecutTag);
+    }
 
-                        let changes = state_controller
-                            .platform_state(platform)
-                            .localization_changes
-                            .clone();
+    let tags_to_post = tags_to_post(&tags, &last_posted_tag);
 
-                        let mut release_localization_changes = LocalizationChanges {
-                            platform,
-                            old_tag: last_posted_tag_previous_release,
-                            new_tag,
-                            completeness: localization_changes_completeness,
-                            unsorted_changes: changes,
-                        };
-
-                        release_localization_changes.add_unsorted_changes(
-                            &mut build_localization_changes.unsorted_changes.clone(),
-                        );
+    log_info!(platform = platform, "tags_to_post = {:?}", tags_to_post);
 
-                        (
-                            Some(release_localization_changes),
-                            last_posted_tag_previous_release,
-                        )
-                    };
+    let earliest_last_posted_at =
+        utils::min_post_interval_minutes(env)?.map(|minutes| utils::iso_string_ago(minutes * 60));
 
-                console_log!(
-                    "last_posted_tag_previous_release = {:?}, release_localization_changes = {:?}",
-                    last_posted_tag_previous_release,
-                    release_localization_changes,
-                );
+    if let Some(pending_tag) = deferred_tag(
+        &state_controller.platform_state(platform),
+        &tags_to_post,
+        earliest_last_posted_at.as_deref(),
+    ) {
+        log_warn!(
+            platform = platform,
+            "last post for {platform} was made too recently, deferring {}",
+            pending_tag.name
+        );
 
-                let localization_changes = release_localization_changes
-                    .as_ref()
-                    .unwrap_or(&build_localization_changes)
-                    .unsorted_changes
-                    .clone();
+        state_controller
+            .update_platform_state(platform, |platform_state| {
+                platform_state.pending_tag = Some(pending_tag.name.clone());
+            })
+            .await
+            .context("could not record pending tag")?;
 
-                let post = markdown::Post::new(
-                    platform,
-                    old_tag,
-                    new_tag,
-                    commits,
-                    unfiltered_commits_len,
-                    LocalizationChangeCollection {
-                        build_changes: build_localization_changes,
-                        release_changes: release_localization_changes,
-                    },
-                );
+        return Ok(PostDeferred);
+    }
 
-                let post_number = post
-                    .post(
-                        utils::is_dry_run(env)?,
-                        &discourse_api_key,
-                        new_topic_id,
-                        reply_to_post_number,
-                    )
-                    .await
-                    .context("could not post commits to Discourse")?;
-
-                console_log!("posted post_number = {:?}", post_number);
-
-                state_controller
-                    .set_platform_state(
-                        platform,
-                        state::PlatformState {
-                            last_posted_tag_previous_release: last_posted_tag_previous_release
-                                .clone(),
-                            last_posted_tag: new_tag.clone(),
-                            last_post_number: Some(post_number),
-                            posted_archiving_message: false,
-                            localization_changes_completeness,
-                            localization_changes,
-                        },
-                    )
-                    .await
-                    .context("could not set platform state after posting commits")?;
+    // only now, so that retrying a post is deferred and limited like any other post
+    let pending = if state_controller.is_dry_run() {
+        None
+    } else {
+        let earliest_created_at = utils::iso_string_ago(utils::pending_post_ttl_minutes(env)? * 60);
+        pending_post::retryable(env, platform, &last_posted_tag, &earliest_created_at).await
+    };
+
+    let next_tags = tags_to_post.array_windows().next();
+
+    let tag_to_post = pending
+        .as_ref()
+        .map(|pending| &pending.new_tag)
+        .or(next_tags.map(|[_, (new_tag, _)]| new_tag));
+
+    // posting takes the most subrequests, so it is not started once the budget is used up
+    if let Some(tag_to_post) = tag_to_post.filter(|_| budget.is_exhausted()) {
+        log_warn!(
+            platform = platform,
+            "made {} subrequest(s), leaving {} to the next run",
+            budget.used(),
+            tag_to_post.name
+        );
+        return Ok(OverRequestBudget);
+    }
+
+    if let Some(pending) = pending {
+        log_warn!(
+            platform = platform,
+            "retrying pending post of {} for {platform}",
+            pending.new_tag.name
+        );
+
+        let new_tag_name = pending.new_tag.name.clone();
+        return publish(state_controller, env, platform, http_client, pending, true)
+            .await
+            .with_context(|| format!("could not retry posting for new_tag = {new_tag_name}"));
+    }
 
-                Ok(PostedCommits)
+    if let Some([(old_tag, old_version), (new_tag, new_version)]) = next_tags {
+        post_commits(
+            state_controller,
+            env,
+            platform,
+            http_client,
+            old_tag,
+            old_version,
+            new_tag,
+            new_version,
+            use_comparison_cache,
+        )
+        .await
+        .with_context(|| format!("could not post commits for new_tag = {}", new_tag.name))
+    } else {
+        // only once everything is posted, as the tags are not looked at again until they change
+        match new_etag {
+            Some(new_etag) if !state_controller.is_dry_run() => {
+                tags_etag::set(env, platform, &last_posted_tag.name, new_etag).await;
             }
-            None => Ok(NewTopicNotFound),
+            _ => log_info!(platform = platform, "not saving tags ETag"),
         }
-    } else {
+
         Ok(LatestVersionIsAlreadyPosted)
     }
 }
 
-async fn post_archiving_message_if_necessary(
-    same_release: bool,
-    state_controller: &mut StateController,
+#[allow(clippy::too_many_arguments)]
+async fn post_commits(
+    state_controller: &StateController,
     env: &Env,
     platform: Platform,
-    discourse_api_key: &str,
+    http_client: &impl HttpClient,
+    old_tag: &Tag,
     old_version: &Version,
-    new_topic_id: u64,
-) -> anyhow::Result<()> {
-    if same_release
-        || state_controller
-            .platform_state(platform)
-            .posted_archiving_message
-    {
-        console_log!("archiving message not necessary");
-        return Ok(());
-    } else {
-        console_log!("attempting to post archiving message");
-    }
+    new_tag: &Tag,
+    new_version: &Version,
+    use_comparison_cache: bool,
+) -> anyhow::Result<PlatformCheckOutcome> {
+    log_info!(
+        platform = platform,
+        "looking at [old_tag: {:?}, new_tag: {:?}]",
+        old_tag,
+        new_tag
+    );
+
+    let discourse_api_key = utils::api_key(env)?;
+
+    let new_topic_id = utils::get_topic_id_or_override(
+        http_client,
+        env,
+        &discourse_api_key,
+        platform,
+        new_version,
+    )
+    .await
+    .context("could not find new_topic_id")?;
+
+    match new_topic_id {
+        Some(new_topic_id) => {
+            log_info!(platform = platform, "new_topic_id = {new_topic_id}");
+
+            let same_release = is_same_release(old_version, new_version);
+            log_info!(platform = platform, "same_release = {}", same_release);
+
+            let Some(prepared_post) = prepare_post(
+                &state_controller.platform_state(platform),
+                env,
+                platform,
+                http_client,
+                old_tag,
+                new_tag,
+                same_release,
+                use_comparison_cache,
+            )
+            .await?
+            else {
+                return Ok(NewTagIsBehind);
+            };
 
-    let old_topic_id =
-        utils::get_topic_id_or_override(env, discourse_api_key, platform, old_version)
-            .await
-            .context("could not find old_topic_id")?;
+            if prepared_post.same_code && !utils::post_empty_comparisons(env)? {
+                log_warn!(
+                    platform = platform,
+                    "{} points at the same code as {}, skipping it",
+                    new_tag.name,
+                    old_tag.name
+                );
 
-    match old_topic_id {
-        Some(old_topic_id) => {
-            console_log!("old_topic_id = {old_topic_id}");
+                if !state_controller.is_dry_run() {
+                    // so that the tag is not looked at again
+                    let previous_state = state_controller.platform_state(platform);
+                    state_controller
+                        .set_platform_state(
+                            platform,
+                            prepared_post.into_skipped_platform_state(new_tag, &previous_state),
+                        )
+                        .await
+                        .context("could not set platform state after skipping tag")?;
+                }
 
-            let markdown_text = utils::archiving_post_markdown(new_topic_id);
-            console_log!("markdown_text.len() = {}", markdown_text.len());
+                return Ok(SkippedSameCode);
+            }
 
-            let result = if !utils::is_dry_run(env)? {
-                utils::post_to_discourse(
-                    &markdown_text,
-                    discourse_api_key,
-                    old_topic_id,
-                    state_controller.platform_state(platform).last_post_number,
-                )
-                .await
+            // Post archiving message to old topic if necessary and possible
+            post_archiving_message_if_necessary(
+                same_release,
+                state_controller,
+                env,
+                platform,
+                http_client,
+                &discourse_api_key,
+                old_version,
+                new_topic_id,
+            )
+            .await?;
+
+            // Post commits to new topic
+
+            let reply_to_post_number = if same_release {
+                state_controller.platform_state(platform).last_post_number
             } else {
-                console_warn!("dry run; not posting to Discourse");
-                Ok(0)
+                None
             };
+            log_info!(
+                platform = platform,
+                "reply_to_post_number = {:?}",
+                reply_to_post_number
+            );
+
+            if state_controller.is_dry_run() {
+                log_warn!(
+                    platform = platform,
+                    "dry run; not posting to Discourse, would post to topic_id = {new_topic_id}"
+                );
+                log_info!(
+                    platform = platform,
+                    "markdown_text = {}",
+                    prepared_post.markdown_text
+                );
 
-            match result {
-                Ok(post_number) => {
-                    console_log!("posted archiving message, post number = {}", post_number);
+                return Ok(RenderedPostInDryRun {
+                    topic_id: new_topic_id,
+                    markdown_text: prepared_post.markdown_text,
+                });
+            }
 
-                    let mut new_state = state_controller.platform_state(platform).clone();
-                    new_state.posted_archiving_message = true;
+            let pending = pending_post::PendingPost {
+                old_tag: old_tag.clone(),
+                new_tag: new_tag.clone(),
+                topic_id: new_topic_id,
+                reply_to_post_number,
+                prepared_post,
+                created_at: utils::now_iso_string(),
+            };
 
-                    state_controller
-                        .set_platform_state(platform, new_state)
-                        .await
-                        .context("could not set platform state after posting archiving message")?;
+            publish(state_controller, env, platform, http_client, pending, false).await
+        }
+        None => Ok(NewTopicNotFound),
+    }
+}
 
-                    utils::delay(POSTING_DELAY_MILLISECONDS).await;
-                }
-                Err(_) => {
-                    console_warn!("could not post archiving message to old topic, it is likely already archived; ignoring, will not post archiving message for this release");
-                }
-            }
+/// Posts `pending` to Discourse and updates the state of `platform` for it.
+///
+/// `pending` is saved beforehand and deleted once posted, so that if posting fails,
+/// later runs retry posting exactly the same content. A retry (`is_retry`) first looks
+/// for the post in the topic, as the earlier attempt may have made it before failing.
+async fn publish(
+    state_controller: &StateController,
+    env: &Env,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    pending: pending_post::PendingPost,
+    is_retry: bool,
+) -> anyhow::Result<PlatformCheckOutcome> {
+    let discourse_api_key = utils::api_key(env)?;
+    let additional_topic_ids = utils::additional_topic_ids(env, platform)?;
+
+    record_post_attempt(state_controller, platform).await?;
+    pending_post::save(env, platform, &pending).await;
+
+    let pending_post::PendingPost {
+        old_tag,
+        new_tag,
+        topic_id,
+        reply_to_post_number,
+        mut prepared_post,
+        ..
+    } = pending;
+
+    let already_posted = if is_retry {
+        utils::find_recent_post_in_discourse(
+            http_client,
+            &discourse_api_key,
+            topic_id,
+            &prepared_post.markdown_text,
+        )
+        .await
+        .context("could not check whether the pending post was already made")?
+    } else {
+        None
+    };
+
+    let posted = match already_posted {
+        Some(posted) => {
+            log_warn!(
+                platform = platform,
+                "pending post of {} was already made, not posting it again",
+                new_tag.name
+            );
+            Ok(posted)
         }
         None => {
-            console_warn!("old topic does not exist? ignoring, will not post archiving message for this release");
+            utils::post_to_discourse(
+                http_client,
+                &prepared_post.markdown_text,
+                &discourse_api_key,
+                topic_id,
+                reply_to_post_number,
+            )
+            .await
+        }
+    };
+
+    let outcome = if posted.is_ok() { "success" } else { "failure" };
+    metrics::incr(
+        "posts",
+        metrics::Dimensions::platform(platform).outcome(outcome),
+    );
+
+    let posted = posted.context("could not post commits to Discourse")?;
+
+    log_info!(platform = platform, "posted = {:?}", posted);
+
+    // right away, so that the post is not made again even if updating the state fails
+    pending_post::delete(env, platform).await;
+
+    let commit_subjects: Vec<String> = prepared_post
+        .posted_commits
+        .iter()
+        .map(|commit| commit.subject.clone())
+        .collect();
+
+    posted_commits::record(
+        env,
+        platform,
+        &new_tag.exact_version_string(),
+        &std::mem::take(&mut prepared_post.posted_commits),
+    )
+    .await;
+
+    let markdown_text = std::mem::take(&mut prepared_post.markdown_text);
+    let post_url = utils::discourse_post_url(topic_id, posted.number);
+    let commits_len = prepared_post.commits_len;
+    let localization_changes_len = prepared_post.build_localization_changes_len;
+
+    let previous_state = state_controller.platform_state(platform);
+    let new_state = prepared_post
+        .into_platform_state(
+            &previous_state,
+            &new_tag,
+            &posted,
+            &post_url,
+            &utils::now_iso_string(),
+        )
+        .with_status_of(&previous_state);
+
+    if let Some(summary) = closed_cycle_summary(&previous_state, &new_state) {
+        log_info!(platform = platform, "{summary}");
+    }
+
+    commit_and_announce(
+        async {
+            state_controller
+                .set_platform_state(platform, new_state)
+                .await
+                .context("could not set platform state after posting commits")
+        },
+        announce_post(
+            utils::owned_env(env),
+            platform,
+            old_tag,
+            new_tag.clone(),
+            commits_len,
+            commit_subjects,
+            localization_changes_len,
+            topic_id,
+            posted.number,
+            post_url,
+        ),
+    )
+    .await?;
+
+    publish_to_additional_topics(
+        state_controller,
+        env,
+        platform,
+        http_client,
+        &new_tag,
+        &markdown_text,
+        topic_id,
+        &additional_topic_ids,
+    )
+    .await;
+
+    Ok(PostedCommits)
+}
+
+/// Posts `markdown_text` for `new_tag` to `additional_topic_ids` (except for the primary `topic_id`).
+///
+/// Failures are only reported, as the post in the primary topic was already made.
+#[allow(clippy::too_many_arguments)]
+async fn publish_to_additional_topics(
+    state_controller: &StateController,
+    env: &Env,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    new_tag: &Tag,
+    markdown_text: &str,
+    topic_id: u64,
+    additional_topic_ids: &[u64],
+) {
+    let discourse_api_key = match utils::api_key(env) {
+        Ok(discourse_api_key) => discourse_api_key,
+        Err(e) => {
+            log_error!(
+                platform = platform,
+                "could not post to additional topics: {e:?}"
+            );
+            return;
         }
+    };
+    let discourse_api_key = discourse_api_key.as_str();
+
+    let results =
+        post_to_additional_topics(additional_topic_ids, topic_id, |topic_id| async move {
+            utils::delay(POSTING_DELAY_MILLISECONDS).await;
+            utils::post_to_discourse(
+                http_client,
+                markdown_text,
+                discourse_api_key,
+                topic_id,
+                None,
+            )
+            .await
+        })
+        .await;
+
+    for (additional_topic_id, result) in &results {
+        let outcome = match result {
+            Ok(posted) => {
+                log_info!(
+                    platform = platform,
+                    "posted to additional topic {additional_topic_id}, post number = {}",
+                    posted.number
+                );
+                "success"
+            }
+            Err(_) => "failure",
+        };
+        metrics::incr(
+            "additional_posts",
+            metrics::Dimensions::platform(platform).outcome(outcome),
+        );
+    }
+
+    if let Some(e) = additional_topics_error(&results) {
+        log_error!(platform = platform, "{e:?}");
+        failure_notification::notify(
+            env,
+            Some(platform),
+            Some(&new_tag.name),
+            &e,
+            state_controller.is_dry_run(),
+        )
+        .await;
+
+        let report = sentry::Report {
+            platform: Some(platform),
+            tag: Some(&new_tag.name),
+        };
+        sentry::report(env, &e, report).await;
+    }
+}
+
+/// Posts with `post` to each of `topic_ids` except `primary_topic_id`, one after another,
+/// carrying on after failures.
+///
+/// These posts are never replies, as the post that the primary topic replies to is not in them.
+async fn post_to_additional_topics<P, Fut>(
+    topic_ids: &[u64],
+    primary_topic_id: u64,
+    post: P,
+) -> Vec<(u64, anyhow::Result<utils::DiscoursePost>)>
+where
+    P: Fn(u64) -> Fut,
+    Fut: Future<Output = anyhow::Result<utils::DiscoursePost>>,
+{
+    let mut results = Vec::new();
+
+    for &topic_id in topic_ids.iter().filter(|&&id| id != primary_topic_id) {
+        results.push((topic_id, post(topic_id).await));
     }
 
-    console_log!("post_archiving_message_if_necessary done");
+    results
+}
+
+/// Describes the additional topics that could not be posted to, if any.
+fn additional_topics_error(
+    results: &[(u64, anyhow::Result<utils::DiscoursePost>)],
+) -> Option<anyhow::Error> {
+    let failures: Vec<String> = results
+        .iter()
+        .filter_map(|(topic_id, result)| {
+            result
+                .as_ref()
+                .err()
+                .map(|e| format!("topic {topic_id}: {e:#}"))
+        })
+        .collect();
+
+    (!failures.is_empty()).then(|| {
+        anyhow::anyhow!(
+            "could not post to {} of {} additional topics: {}",
+            failures.len(),
+            results.len(),
+            failures.join("; ")
+        )
+    })
+}
+
+/// Counts a try to post for `platform`; reset once the post is made.
+async fn record_post_attempt(
+    state_controller: &StateController,
+    platform: Platform,
+) -> anyhow::Result<()> {
+    state_controller
+        .update_platform_state(platform, |platform_state| {
+            platform_state.post_attempts += 1;
+        })
+        .await
+        .context("could not record post attempt")
+}
+
+/// Commits the state after a post with `commit`, waiting for it, and only then hands
+/// `announce` over to run in the background (see [`background::defer`]).
+///
+/// Nothing is announced if committing fails, as the post would then be made again.
+async fn commit_and_announce(
+    commit: impl Future<Output = anyhow::Result<()>>,
+    announce: impl Future<Output = ()> + 'static,
+) -> anyhow::Result<()> {
+    commit.await?;
+    background::defer(announce).await;
 
     Ok(())
 }
+
+/// Records the post in history and sends notifications about it; run in the background
+/// once the state is committed (see [`commit_and_announce`]).
+///
+/// Failures are only logged, as the post was already made.
+#[allow(clippy::too_many_arguments)]
+async fn announce_post(
+    env: Env,
+    platform: Platform,
+    old_tag: Tag,
+    new_tag: Tag,
+    commits_len: usize,
+    commit_subjects: Vec<String>,
+    localization_changes_len: usize,
+    topic_id: u64,
+    post_number: u64,
+    post_url: String,
+) {
+    history::record(
+        &env,
+        platform,
+        history::HistoryEntry {
+            tag: new_tag.name.clone(),
+            version: new_tag.exact_version_string(),
+            date: utils::now_iso_string(),
+            post_url: post_url.clone(),
+            commits_count: commits_len,
+            previous_tag: Some(old_tag.name.clone()),
+            topic_id: Some(topic_id),
+            post_number: Some(post_number),
+            commit_subjects: history::trimmed_commit_subjects(&commit_subjects),
+        },
+    )
+    .await;
+
+    let notification = notify::Notification::new(
+        platform,
+        &old_tag,
+        &new_tag,
+        commits_len,
+        post_url.clone(),
+        localization_changes_len,
+    );
+
+    notify::send(&env, &notification).await;
+
+    mastodon::publish(&env, platform, &old_tag, &new_tag, commits_len, &post_url).await;
+
+    matrix::notify(&env, platform, &old_tag, &new_tag, commits_len, &post_url).await;
+
+    bluesky::publish(&env, platform, &old_tag, &new_tag, commits_len, &post_url).await;
+
+    telegram::announce(
+        &env,
+        platform,
+        &old_tag,
+        &new_tag,
+        &commit_subjects,
+        &post_url,
+    )
+    .await;
+
+    release_history::append(&env, platform, &new_tag, commits_len, &post_url).await;
+}
+
+fn is_same_release(old_version: &Version, new_version: &Version) -> bool {
+    old_version.major == new_version.major && old_version.minor == new_version.minor
+}
+
+/// Whether the localization changes accumulated in `platform_state` are added to
+/// for `new_tag`, i.e. whether they belong to the release cycle of `new_tag`.
+///
+/// Falls back to `same_release` if the cycle of `new_tag` is unknown.
+fn accumulates_localization_changes(
+    platform_state: &state::PlatformState,
+    new_tag: &Tag,
+    same_release: bool,
+) -> bool {
+    match new_tag.to_version() {
+        Ok(new_version) => {
+            platform_state.localization_cycle() == Some(state::release_cycle(&new_version))
+        }
+        Err(_) => same_release,
+    }
+}
+
+/// Describes the release cycle that was closed by going from `previous` to `new`, if any.
+fn closed_cycle_summary(
+    previous: &state::PlatformState,
+    new: &state::PlatformState,
+) -> Option<String> {
+    let closed_cycle = previous.localization_cycle()?;
+
+    (new.localization_cycle().as_ref() != Some(&closed_cycle)).then(|| {
+        format!(
+            "closed release cycle {closed_cycle} with localization changes for {} language(s), {:?}",
+            previous.localization_changes.len(),
+            previous.localization_changes_completeness
+        )
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct PreparedPost {
+    markdown_text: String,
+    commits_len: usize,
+    build_localization_changes_len: usize,
+    last_posted_tag_previous_release: Tag,
+    localization_changes_completeness: Completeness,
+    #[serde(default)]
+    localization_changes_completeness_rationale: Option<String>,
+    localization_changes: UnsortedChanges,
+    localization_changes_cycle: Option<String>,
+    /// Commits listed in the post, recorded once it is made to mark them in later posts.
+    #[serde(default)]
+    posted_commits: Vec<posted_commits::PostedCommit>,
+    /// Whether the new tag points at the same code as the old one, so the post is only a note.
+    #[serde(default)]
+    same_code: bool,
+}
+
+impl PreparedPost {
+    /// The note that `new_tag` points at the same code as `old_tag`, which adds no commits.
+    ///
+    /// `previous_release_state` is as for [`prepare_post_from_comparison`], and its
+    /// localization changes are kept as they are.
+    fn same_code(
+        old_tag: &Tag,
+        new_tag: &Tag,
+        previous_release_state: Option<&state::PlatformState>,
+    ) -> Self {
+        let (
+            last_posted_tag_previous_release,
+            localization_changes_completeness,
+            localization_changes_completeness_rationale,
+            localization_changes,
+        ) = match previous_release_state {
+            Some(state) => (
+                state.last_posted_tag_previous_release.clone(),
+                state.localization_changes_completeness,
+                state.localization_changes_completeness_rationale.clone(),
+                state.localization_changes.clone(),
+            ),
+            None => (
+                old_tag.clone(),
+                Completeness::Complete,
+                None,
+                UnsortedChanges::new(),
+            ),
+        };
+
+        Self {
+            markdown_text: same_code_note(old_tag, new_tag),
+            commits_len: 0,
+            build_localization_changes_len: 0,
+            last_posted_tag_previous_release,
+            localization_changes_completeness,
+            localization_changes_completeness_rationale,
+            localization_changes,
+            localization_changes_cycle: new_tag
+                .to_version()
+                .ok()
+                .map(|version| state::release_cycle(&version)),
+            posted_commits: Vec::new(),
+            same_code: true,
+        }
+    }
+
+    /// Returns the state of the platform after this was skipped instead of posted for `new_tag`,
+    /// keeping the last post of `previous`.
+    fn into_skipped_platform_state(
+        self,
+        new_tag: &Tag,
+        previous: &state::PlatformState,
+    ) -> state::PlatformState {
+        state::PlatformState {
+            last_posted_tag_previous_release: self.last_posted_tag_previous_release,
+            last_posted_tag: new_tag.clone(),
+            post_attempts: 0,
+            pending_tag: None,
+            localization_changes_completeness: self.localization_changes_completeness,
+            localization_changes_completeness_rationale: self
+                .localization_changes_completeness_rationale,
+            localization_changes: self.localization_changes,
+            localization_changes_cycle: self.localization_changes_cycle,
+            localization_before_last_posted_tag: Some(previous.localization_state()),
+            ..previous.clone()
+        }
+    }
+
+    /// Returns the state of the platform after this was posted for `new_tag` as `posted`
+    /// at `post_url`, at ISO 8601 timestamp `posted_at`, with `previous` being the state
+    /// it was prepared from.
+    fn into_platform_state(
+        self,
+        previous: &state::PlatformState,
+        new_tag: &Tag,
+        posted: &utils::DiscoursePost,
+        post_url: &str,
+        posted_at: &str,
+    ) -> state::PlatformState {
+        state::PlatformState {
+            last_posted_tag_previous_release: self.last_posted_tag_previous_release,
+            last_posted_tag: new_tag.clone(),
+            last_post_number: Some(posted.number),
+            last_post_id: Some(posted.id),
+            last_post_url: Some(post_url.to_string()),
+            last_posted_at: Some(posted_at.to_string()),
+            post_attempts: 0,
+            pending_tag: None,
+            posted_archiving_message: false,
+            posted_cycle_summary: false,
+            tag_moved: false,
+            localization_changes_completeness: self.localization_changes_completeness,
+            localization_changes_completeness_rationale: self
+                .localization_changes_completeness_rationale,
+            localization_changes: self.localization_changes,
+            localization_changes_cycle: self.localization_changes_cycle,
+            localization_before_last_posted_tag: Some(previous.localization_state()),
+            enabled: true,
+            last_checked_at: None,
+            last_error: None,
+            consecutive_failures: 0,
+            next_attempt_after: None,
+            deferred_reason: None,
+            run_subrequests: None,
+        }
+    }
+}
+
+/// Gets the comparison between `old_tag` and `new_tag` from GitHub and renders the post,
+/// along with the localization changes that should be saved to state after posting it
+/// on top of `platform_state`.
+///
+/// Returns `None` if `new_tag` is behind `old_tag`.
+#[allow(clippy::too_many_arguments)]
+async fn prepare_post(
+    platform_state: &state::PlatformState,
+    env: &Env,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    same_release: bool,
+    use_comparison_cache: bool,
+) -> anyhow::Result<Option<PreparedPost>> {
+    let github_token = utils::github_token(env);
+    let fallback_tag = &platform_state.last_posted_tag_previous_release;
+
+    let (compared_tag, comparison) = compare_or_fall_back(
+        old_tag,
+        fallback_tag,
+        |tag| {
+            let github_token = github_token.as_deref();
+
+            async move {
+                comparison_cache::get_github_comparison(
+                    env,
+                    http_client,
+                    github_token,
+                    platform,
+                    &tag,
+                    new_tag,
+                    use_comparison_cache,
+                )
+                .await
+            }
+        },
+        || utils::github_tag_exists(github_token.as_deref(), platform, &old_tag.name),
+    )
+    .await
+    .context("could not get build comparison from GitHub")?;
+
+    let notice = (compared_tag != *old_tag).then(|| {
+        log_warn!(
+            platform = platform,
+            "{} was deleted, compared from {} instead",
+            old_tag.name,
+            compared_tag.name
+        );
+        deleted_tag_notice(old_tag, &compared_tag)
+    });
+
+    let (previous_release_state, release_comparison) = release_localization_inputs(
+        env,
+        http_client,
+        github_token.as_deref(),
+        platform,
+        platform_state,
+        new_tag,
+        same_release,
+        use_comparison_cache,
+    )
+    .await;
+
+    let dependency_bump_prefixes = utils::dependency_bump_prefixes(env)?;
+    let posted_commits = posted_commits::get(env, platform).await;
+
+    let show_cycle_stats = utils::show_cycle_stats(env)?;
+    let header_template = utils::post_header_template(env, platform)?;
+    let history = match show_cycle_stats {
+        true => history::entries(env, platform).await.unwrap_or_else(|e| {
+            log_error!(
+                platform = platform,
+                "could not get history for cycle stats: {e:?}"
+            );
+            Vec::new()
+        }),
+        false => Vec::new(),
+    };
+
+    prepare_post_from_comparison(
+        http_client,
+        github_token.as_deref(),
+        platform,
+        &compared_tag,
+        new_tag,
+        &comparison,
+        previous_release_state,
+        release_comparison.as_ref(),
+        Some(&posted_commits),
+        &history,
+        markdown::PostOptions {
+            notice: notice.as_deref(),
+            show_commit_verification: utils::show_commit_verification(env)?,
+            collapse_threshold: utils::collapse_threshold(env)?,
+            dependency_bump_prefixes: dependency_bump_prefixes.as_deref(),
+            inline_dependency_bumps: utils::inline_dependency_bumps(env)?,
+            commit_list_style: utils::commit_list_style(env)?,
+            commit_order: utils::commit_order(env, platform)?,
+            show_cycle_stats,
+            show_contributors: utils::show_contributors(env)?,
+            header_template: header_template.as_ref(),
+        },
+    )
+    .await
+}
+
+/// The inputs that the localization changes of the release of `new_tag` are accumulated
+/// from on top of `platform_state` (see [`prepare_post_from_comparison`]): the state itself
+/// if the changes belong to the same release cycle, along with the files changed since its
+/// `last_posted_tag_previous_release` if they could be gotten.
+#[allow(clippy::too_many_arguments)]
+async fn release_localization_inputs<'a>(
+    env: &Env,
+    http_client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    platform_state: &'a state::PlatformState,
+    new_tag: &Tag,
+    same_release: bool,
+    use_comparison_cache: bool,
+) -> (
+    Option<&'a state::PlatformState>,
+    Option<types::github::Comparison>,
+) {
+    let previous_release_state = Some(platform_state).filter(|platform_state| {
+        accumulates_localization_changes(platform_state, new_tag, same_release)
+    });
+
+    // the release changes can still be accumulated without it, so failing to get it does not prevent posting
+    let release_comparison = match &previous_release_state {
+        Some(state) if rate_limit::is_conserving() => {
+            log_warn!(
+                platform = platform,
+                "GitHub rate limit headroom is low, not getting comparison from {} for release localization changes",
+                state.last_posted_tag_previous_release.name
+            );
+            None
+        }
+        Some(state) => comparison_cache::get_github_comparison_files(
+            env,
+            http_client,
+            github_token,
+            platform,
+            &state.last_posted_tag_previous_release,
+            new_tag,
+            use_comparison_cache,
+        )
+        .await
+        .map_err(|e| {
+            log_error!(
+                platform = platform,
+                "could not get comparison from {} for release localization changes: {e:?}",
+                state.last_posted_tag_previous_release.name
+            );
+        })
+        .ok(),
+        None => None,
+    };
+
+    (previous_release_state, release_comparison)
+}
+
+/// Gets the comparison from `old_tag` with `compare`, or from `fallback_tag` if the comparison
+/// was not found because `old_tag` does not exist anymore, as checked with `old_tag_exists`.
+///
+/// Returns the tag that the comparison is from, along with the comparison.
+async fn compare_or_fall_back<C, CFut, E, EFut>(
+    old_tag: &Tag,
+    fallback_tag: &Tag,
+    compare: C,
+    old_tag_exists: E,
+) -> anyhow::Result<(Tag, types::github::Comparison)>
+where
+    C: Fn(Tag) -> CFut,
+    CFut: Future<Output = anyhow::Result<types::github::Comparison>>,
+    E: FnOnce() -> EFut,
+    EFut: Future<Output = anyhow::Result<bool>>,
+{
+    let error = match compare(old_tag.clone()).await {
+        Ok(comparison) => return Ok((old_tag.clone(), comparison)),
+        Err(e) if utils::is_not_found(&e) => e,
+        Err(e) => return Err(e),
+    };
+
+    if old_tag_exists()
+        .await
+        .context("could not check whether the old tag exists")?
+    {
+        return Err(error);
+    }
+
+    let comparison = compare(fallback_tag.clone()).await.with_context(|| {
+        format!(
+            "could not get comparison from {} instead of deleted {}",
+            fallback_tag.name, old_tag.name
+        )
+    })?;
+
+    Ok((fallback_tag.clone(), comparison))
+}
+
+fn same_code_note(old_tag: &Tag, new_tag: &Tag) -> String {
+    format!(
+        "Tag {} points at the same code as {}.",
+        new_tag.name, old_tag.name
+    )
+}
+
+fn deleted_tag_notice(deleted_tag: &Tag, compared_tag: &Tag) -> String {
+    format!(
+        "The previous tag {} disappeared, so commits are listed since {}.",
+        deleted_tag.exact_version_string(),
+        compared_tag.exact_version_string()
+    )
+}
+
+/// Returns the next tag of `tags_to_post` (see [`tags_to_post`]) if posting it is deferred,
+/// i.e. if the last post was made after `earliest_last_posted_at`, if given.
+///
+/// The tag stays in `tags_to_post` until it is posted, so it is picked up again by later runs.
+fn deferred_tag<'a>(
+    platform_state: &state::PlatformState,
+    tags_to_post: &'a [(Tag, Version)],
+    earliest_last_posted_at: Option<&str>,
+) -> Option<&'a Tag> {
+    let earliest_last_posted_at = earliest_last_posted_at?;
+    let (next_tag, _) = tags_to_post.get(1)?;
+
+    platform_state
+        .is_posting_deferred(earliest_last_posted_at)
+        .then_some(next_tag)
+}
+
+/// Returns the tags from `last_posted_tag` onwards, oldest first.
+///
+/// If `last_posted_tag` is not among `tags` (e.g. because it was deleted),
+/// it is followed by the tags with a greater version instead.
+fn tags_to_post(tags: &[(Tag, Version)], last_posted_tag: &Tag) -> Vec<(Tag, Version)> {
+    if tags.iter().any(|(tag, _)| tag.name == last_posted_tag.name) {
+        return tags
+            .iter()
+            .skip_while(|(tag, _)| tag.name != last_posted_tag.name)
+            .cloned()
+            .collect();
+    }
+
+    let Ok(last_posted_version) = last_posted_tag.to_version() else {
+        return Vec::new();
+    };
+
+    let newer_tags = tags
+        .iter()
+        .filter(|(_, version)| *version > last_posted_version)
+        .cloned();
+
+    std::iter::once((last_posted_tag.clone(), last_posted_version.clone()))
+        .chain(newer_tags)
+        .collect()
+}
+
+/// Renders the post for an already fetched `comparison`.
+///
+/// `previous_release_state` is the state of `platform` if `new_tag` is in the same release
+/// as the last posted tag, and is used to accumulate localization changes for the release,
+/// unless `release_comparison` (the files from its `last_posted_tag_previous_release`
+/// to `new_tag`) has all of them.
+///
+/// Returns `None` if the comparison should not be posted (see [`types::github::ComparisonStatus::should_post`]).
+///
+/// If the comparison has no new commits, the post is only a note (see [`PreparedPost::same_code`]).
+#[allow(clippy::too_many_arguments)]
+async fn prepare_post_from_comparison(
+    http_client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    comparison: &types::github::Comparison,
+    previous_release_state: Option<&state::PlatformState>,
+    release_comparison: Option<&types::github::Comparison>,
+    posted_commits: Option<&posted_commits::PostedCommits>,
+    history: &[history::HistoryEntry],
+    options: markdown::PostOptions<'_>,
+) -> anyhow::Result<Option<PreparedPost>> {
+    log_info!(platform = platform, "comparison = {:?}", comparison);
+
+    if !comparison.status.should_post() {
+        log_warn!(
+            platform = platform,
+            "{} is {:?} compared to {}, not posting",
+            new_tag.name,
+            comparison.status,
+            old_tag.name
+        );
+        return Ok(None);
+    }
+
+    if comparison.is_empty() {
+        log_warn!(
+            platform = platform,
+            "{} has no new commits compared to {}",
+            new_tag.name,
+            old_tag.name
+        );
+        return Ok(Some(PreparedPost::same_code(
+            old_tag,
+            new_tag,
+            previous_release_state,
+        )));
+    }
+
+    let unfiltered_commits: Vec<markdown::Commit> = comparison
+        .commits
+        .iter()
+        .map(|github_commit| markdown::Commit::from_github_commit(platform, github_commit))
+        .collect();
+
+    let unfiltered_commits_len = comparison.new_commits_len();
+    log_info!(
+        platform = platform,
+        "unfiltered_commits_len = {:?}",
+        unfiltered_commits_len
+    );
+
+    let libsignal_bumps =
+        get_libsignal_bumps(http_client, github_token, platform, new_tag, comparison).await;
+    log_info!(
+        platform = platform,
+        "libsignal_bumps = {:?}",
+        libsignal_bumps
+    );
+
+    let commits: Vec<markdown::Commit> = unfiltered_commits
+        .into_iter()
+        .filter(|commit| platform.should_show_commit(commit.full_message()))
+        .map(|commit| match libsignal_bumps.get(commit.sha()) {
+            Some(libsignal_bump) => commit.with_libsignal_bump(libsignal_bump.clone()),
+            None => commit,
+        })
+        .map(|commit| {
+            let new_version = new_tag.exact_version_string();
+
+            // translation updates often have the same subject, so only their SHA is matched
+            let also_in = posted_commits.and_then(|posted_commits| {
+                posted_commits.version_of(
+                    commit.sha(),
+                    commit.subject(),
+                    !commit.is_likely_localization_change(),
+                    &new_version,
+                )
+            });
+
+            match also_in {
+                Some(version) => commit.with_also_in(version),
+                None => commit,
+            }
+        })
+        .collect();
+
+    let posted_commits: Vec<posted_commits::PostedCommit> = commits
+        .iter()
+        .map(|commit| posted_commits::PostedCommit {
+            sha: commit.sha().to_string(),
+            subject: commit.subject().to_string(),
+        })
+        .collect();
+
+    let commits_len = commits.len();
+    log_info!(platform = platform, "commits_len = {:?}", commits_len);
+
+    // release notes are optional, so failing to get them does not prevent posting
+    let release = utils::get_github_release(github_token, platform, &new_tag.name)
+        .await
+        .unwrap_or_else(|e| {
+            log_error!(
+                platform = platform,
+                "could not get release for {}: {e:?}",
+                new_tag.name
+            );
+            None
+        });
+    log_info!(platform = platform, "release = {:?}", release);
+
+    let mut build_localization_changes =
+        LocalizationChanges::from_comparison(platform, old_tag, new_tag, comparison);
+
+    if let Completeness::Incomplete = build_localization_changes.completeness {
+        let touching_shas = get_shas_touching_localization(
+            http_client,
+            github_token,
+            platform,
+            new_tag,
+            comparison,
+        )
+        .await;
+        log_info!(platform = platform, "touching_shas = {:?}", touching_shas);
+
+        let likely_localization_change_shas: Vec<&str> = commits
+            .iter()
+            .filter(|commit| commit.is_likely_localization_change())
+            .map(markdown::Commit::sha)
+            .collect();
+
+        log_info!(
+            platform = platform,
+            "likely_localization_change_shas = {:?}",
+            likely_localization_change_shas
+        );
+
+        let shas =
+            localization_change_shas(&likely_localization_change_shas, touching_shas.as_deref());
+
+        let mut all_complete = true;
+
+        for &sha in &shas {
+            let with_files = utils::get_github_commit(github_token, platform, sha).await?;
+
+            log_info!(platform = platform, "with_files = {:?}", with_files);
+
+            let changes =
+                LocalizationChange::unsorted_changes_from_files(platform, &with_files.files);
+
+            log_info!(platform = platform, "changes = {:?}", changes);
+
+            build_localization_changes.add_unsorted_changes(&changes);
+
+            let complete = with_files.are_files_likely_complete().unwrap();
+            log_info!(
+                platform = platform,
+                "for commit.sha = {sha} files.complete = {complete}"
+            );
+
+            all_complete &= complete;
+        }
+
+        let (completeness, completeness_rationale) =
+            completeness_from_commit_files(touching_shas.is_some(), shas.len(), all_complete);
+
+        build_localization_changes.completeness = completeness;
+        build_localization_changes.completeness_rationale = Some(completeness_rationale);
+
+        log_info!(
+            platform = platform,
+            "after getting files of {} commits, build_localization_changes.completeness = {:?}",
+            shas.len(),
+            build_localization_changes.completeness
+        );
+    }
+
+    let (release_localization_changes, last_posted_tag_previous_release) =
+        match previous_release_state {
+            None => (None, old_tag),
+            Some(state) => (
+                Some(release_localization_changes(
+                    platform,
+                    state,
+                    new_tag,
+                    &build_localization_changes,
+                    release_comparison,
+                )),
+                &state.last_posted_tag_previous_release,
+            ),
+        };
+
+    let (localization_changes_completeness, localization_changes_completeness_rationale) = {
+        let changes = release_localization_changes
+            .as_ref()
+            .unwrap_or(&build_localization_changes);
+
+        (changes.completeness, changes.completeness_rationale.clone())
+    };
+
+    log_info!(
+        platform = platform,
+        "last_posted_tag_previous_release = {:?}, release_localization_changes = {:?}",
+        last_posted_tag_previous_release,
+        release_localization_changes,
+    );
+
+    let build_localization_changes_len = build_localization_changes.unsorted_changes.len();
+
+    let localization_changes = release_localization_changes
+        .as_ref()
+        .unwrap_or(&build_localization_changes)
+        .unsorted_changes
+        .clone();
+
+    let new_strings = NewString::from_comparison(platform, comparison).unwrap_or_else(|| {
+        log_warn!(
+            platform = platform,
+            "patch of {} is not available, not listing new strings",
+            localization::base_strings_path(platform)
+        );
+        Vec::new()
+    });
+    log_info!(
+        platform = platform,
+        "new_strings.len() = {}",
+        new_strings.len()
+    );
+
+    let mut post = markdown::Post::new(
+        platform,
+        old_tag,
+        new_tag,
+        commits,
+        unfiltered_commits_len,
+        LocalizationChangeCollection {
+            build_changes: build_localization_changes,
+            release_changes: release_localization_changes,
+        },
+    );
+
+    post = post
+        .with_options(options)
+        .with_github_commits_counts(comparison.commits.len(), comparison.total_commits);
+
+    if options.show_cycle_stats {
+        let posted_builds: Vec<markdown::PostedBuild> = history
+            .iter()
+            .map(history::HistoryEntry::posted_build)
+            .collect();
+
+        let cycle_stats = markdown::CycleStats::new(
+            new_tag,
+            commits_len,
+            &utils::now_iso_string(),
+            &posted_builds,
+        );
+        log_info!(platform = platform, "cycle_stats = {:?}", cycle_stats);
+
+        if let Some(cycle_stats) = cycle_stats {
+            post = post.with_cycle_stats(cycle_stats);
+        }
+    }
+
+    if let Some(release) = &release {
+        post = post.with_release(release);
+    }
+
+    if !new_strings.is_empty() {
+        post = post.with_new_strings(&new_strings);
+    }
+
+    if let Some(notice) = comparison.status.notice() {
+        log_warn!(
+            platform = platform,
+            "comparison status = {:?}",
+            comparison.status
+        );
+        post = post.with_notice(notice);
+    }
+
+    let markdown_text = post.markdown()?;
+
+    Ok(Some(PreparedPost {
+        markdown_text,
+        commits_len,
+        build_localization_changes_len,
+        last_posted_tag_previous_release: last_posted_tag_previous_release.clone(),
+        localization_changes_completeness,
+        localization_changes_completeness_rationale,
+        localization_changes,
+        localization_changes_cycle: new_tag
+            .to_version()
+            .ok()
+            .map(|version| state::release_cycle(&version)),
+        posted_commits,
+        same_code: false,
+    }))
+}
+
+/// Localization changes of the release that `new_tag` is in, from `release_comparison`
+/// if it has all of them, or else accumulated from the changes saved in `state`
+/// and `build_localization_changes`.
+fn release_localization_changes<'a>(
+    platform: Platform,
+    state: &'a state::PlatformState,
+    new_tag: &'a Tag,
+    build_localization_changes: &LocalizationChanges<'_>,
+    release_comparison: Option<&'a types::github::Comparison>,
+) -> LocalizationChanges<'a> {
+    let last_posted_tag_previous_release = &state.last_posted_tag_previous_release;
+
+    if let Some(release_comparison) = release_comparison {
+        let changes = LocalizationChanges::from_comparison(
+            platform,
+            last_posted_tag_previous_release,
+            new_tag,
+            release_comparison,
+        );
+
+        if let Completeness::Complete = changes.completeness {
+            return changes;
+        }
+
+        log_warn!(
+            platform = platform,
+            "comparison from {} is {:?}, accumulating release localization changes instead",
+            last_posted_tag_previous_release.name,
+            changes.completeness
+        );
+    }
+
+    // the changes of the release are only as complete as the least complete of their parts
+    let (completeness, completeness_rationale) =
+        if state.localization_changes_completeness < build_localization_changes.completeness {
+            (
+                state.localization_changes_completeness,
+                state.localization_changes_completeness_rationale.clone(),
+            )
+        } else {
+            (
+                build_localization_changes.completeness,
+                build_localization_changes.completeness_rationale.clone(),
+            )
+        };
+
+    let mut changes = LocalizationChanges {
+        platform,
+        old_tag: last_posted_tag_previous_release,
+        new_tag,
+        completeness,
+        completeness_rationale,
+        unsorted_changes: state.localization_changes.clone(),
+    };
+
+    changes.add_unsorted_changes(&build_localization_changes.unsorted_changes);
+
+    changes
+}
+
+/// Returns the libsignal bumps of the commits of `comparison` that change the dependency
+/// manifests of `platform` (see [`libsignal::manifest_paths`]), by SHA.
+///
+/// Failures are only logged, as the bumps are only shown next to the commits.
+async fn get_libsignal_bumps(
+    http_client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    new_tag: &Tag,
+    comparison: &types::github::Comparison,
+) -> HashMap<String, libsignal::LibsignalBump> {
+    let mut bumps = HashMap::new();
+
+    if !libsignal::may_be_bumped_in(platform, comparison) {
+        return bumps;
+    }
+
+    let mut shas: Vec<String> = Vec::new();
+
+    for path in libsignal::manifest_paths(platform) {
+        match utils::get_github_comparison_shas_touching(
+            http_client,
+            github_token,
+            platform,
+            &new_tag.name,
+            comparison,
+            path,
+        )
+        .await
+        {
+            Ok(path_shas) => {
+                for sha in path_shas {
+                    if !shas.contains(&sha) {
+                        shas.push(sha);
+                    }
+                }
+            }
+            Err(e) => log_error!(
+                platform = platform,
+                "could not get commits that touch {path}: {e:?}"
+            ),
+        }
+    }
+
+    let mentions_libsignal = |sha: &str| {
+        comparison.commits.iter().any(|commit| {
+            commit.sha == sha && commit.commit.message.to_lowercase().contains("libsignal")
+        })
+    };
+
+    // stable, so that commits keep their order otherwise
+    shas.sort_by_key(|sha| !mentions_libsignal(sha));
+
+    if shas.len() > MAX_LIBSIGNAL_COMMITS_TO_INSPECT {
+        log_warn!(
+            platform = platform,
+            "{} commits touch dependency manifests, only looking for libsignal bumps in {}",
+            shas.len(),
+            MAX_LIBSIGNAL_COMMITS_TO_INSPECT
+        );
+        shas.truncate(MAX_LIBSIGNAL_COMMITS_TO_INSPECT);
+    }
+
+    for sha in shas {
+        let commit = match utils::get_github_commit(github_token, platform, &sha).await {
+            Ok(commit) => commit,
+            Err(e) => {
+                log_error!(
+                    platform = platform,
+                    "could not get commit {sha} to look for a libsignal bump: {e:?}"
+                );
+                continue;
+            }
+        };
+
+        let subject = commit.commit.message.lines().next().unwrap_or_default();
+
+        if let Some(bump) = libsignal::LibsignalBump::from_files(
+            platform,
+            subject,
+            commit.files.as_deref().unwrap_or_default(),
+        ) {
+            bumps.insert(sha, bump);
+        }
+    }
+
+    bumps
+}
+
+/// Returns the SHAs of the commits of `comparison` that change files in the localization
+/// directories of `platform`, or `None` if they could not be found.
+async fn get_shas_touching_localization(
+    http_client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    new_tag: &Tag,
+    comparison: &types::github::Comparison,
+) -> Option<Vec<String>> {
+    let mut shas = Vec::new();
+
+    for path in localization::StringsFileKind::path_bases(platform) {
+        match utils::get_github_comparison_shas_touching(
+            http_client,
+            github_token,
+            platform,
+            &new_tag.name,
+            comparison,
+            path,
+        )
+        .await
+        {
+            Ok(path_shas) => shas.extend(path_shas),
+            Err(e) => {
+                log_error!(
+                    platform = platform,
+                    "could not get commits that touch {path}: {e:?}"
+                );
+                return None;
+            }
+        }
+    }
+
+    if shas.len() > MAX_LOCALIZATION_COMMITS_TO_INSPECT {
+        log_warn!(
+            platform = platform,
+            "{} commits touch localization, not getting the files of all of them",
+            shas.len()
+        );
+        return None;
+    }
+
+    Some(shas)
+}
+
+/// Returns the commits to get the files of when the comparison has too many files:
+/// the ones likely changing localization, along with the ones known to touch it.
+fn localization_change_shas<'a>(
+    likely_localization_change_shas: &[&'a str],
+    touching_shas: Option<&'a [String]>,
+) -> Vec<&'a str> {
+    let mut shas = likely_localization_change_shas.to_vec();
+
+    for sha in touching_shas.unwrap_or_default() {
+        if !shas.contains(&sha.as_str()) {
+            shas.push(sha);
+        }
+    }
+
+    shas
+}
+
+/// Completeness of localization changes after getting the files of commits individually,
+/// with a rationale for readers.
+///
+/// Changes are only complete if all commits touching localization were found,
+/// and likely complete if only the likely ones were.
+fn completeness_from_commit_files(
+    found_touching_commits: bool,
+    commits_len: usize,
+    all_complete: bool,
+) -> (Completeness, String) {
+    let max_files = types::github::Commit::GITHUB_API_MAX_FILES;
+
+    match (found_touching_commits, all_complete) {
+        (_, false) => (
+            Completeness::Incomplete,
+            format!("A commit has {max_files} changed files, GitHub's limit for a commit, so some may be missing."),
+        ),
+        (true, true) => (
+            Completeness::Complete,
+            format!("The files of all {commits_len} commit(s) touching localization were checked."),
+        ),
+        (false, true) if commits_len > 0 => (
+            Completeness::LikelyComplete,
+            format!("Commits touching localization could not be listed, so only the files of {commits_len} commit(s) that look like translation updates were checked."),
+        ),
+        (false, true) => (
+            Completeness::Incomplete,
+            String::from("GitHub did not list all changed files, and commits touching localization could not be listed."),
+        ),
+    }
+}
+
+/// Updates `platform_state` for its last posted tag having been re-cut as `new_tag`,
+/// with `edited_post` if the post was edited for it.
+fn recut_platform_state(
+    platform_state: &mut state::PlatformState,
+    new_tag: Tag,
+    edited_post: Option<PreparedPost>,
+) {
+    platform_state.last_posted_tag = new_tag;
+    platform_state.tag_moved = edited_post.is_none();
+
+    if let Some(prepared_post) = edited_post {
+        platform_state.last_posted_tag_previous_release =
+            prepared_post.last_posted_tag_previous_release;
+        platform_state.localization_changes_completeness =
+            prepared_post.localization_changes_completeness;
+        platform_state.localization_changes_completeness_rationale =
+            prepared_post.localization_changes_completeness_rationale;
+        platform_state.localization_changes = prepared_post.localization_changes;
+        platform_state.localization_changes_cycle = prepared_post.localization_changes_cycle;
+    }
+}
+
+/// Edits the last post for `platform` if its tag was re-cut, i.e. deleted and pushed again
+/// pointing at a different commit, and if editing posts for moved tags is enabled.
+/// The new commit of the tag is saved to state if editing is enabled, and the tag is
+/// marked as moved in state unless the post was edited.
+///
+/// Returns `true` if the post was edited.
+async fn edit_post_if_tag_recut(
+    state_controller: &StateController,
+    env: &Env,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    tags: &[(Tag, Version)],
+    use_comparison_cache: bool,
+) -> anyhow::Result<bool> {
+    let platform_state = state_controller.platform_state(platform);
+    let last_posted_tag = platform_state.last_posted_tag.clone();
+
+    if last_posted_tag.sha().is_none() {
+        log_info!(
+            platform = platform,
+            "commit of last posted tag is unknown, not checking whether it moved"
+        );
+        return Ok(false);
+    }
+
+    let position = tags
+        .iter()
+        .position(|(tag, _)| tag.name == last_posted_tag.name);
+
+    // the tags listing only has the latest tags, so ask GitHub directly otherwise
+    let live_sha = match position.and_then(|position| tags[position].0.sha()) {
+        Some(sha) => Some(sha.to_string()),
+        None => utils::get_tag_sha(
+            utils::github_token(env).as_deref(),
+            platform,
+            &last_posted_tag.name,
+        )
+        .await
+        .context("could not get commit of last posted tag")?,
+    };
+
+    let Some(new_tag) = live_sha.and_then(|sha| moved_tag(&last_posted_tag, &sha)) else {
+        log_info!(platform = platform, "last posted tag was not re-cut");
+        return Ok(false);
+    };
+
+    log_error!(platform = platform,
+        "last posted tag was re-cut, posts may have been compared from the wrong commit: last_posted_tag = {:?}, new_tag = {:?}",
+        last_posted_tag,
+        new_tag
+    );
+
+    if !utils::edit_post_for_moved_tag(env)? {
+        log_warn!(
+            platform = platform,
+            "editing posts for moved tags is disabled, not editing post"
+        );
+
+        state_controller
+            .update_platform_state(platform, |platform_state| platform_state.tag_moved = true)
+            .await
+            .context("could not set platform state after finding moved tag")?;
+
+        return Ok(false);
+    }
+
+    let position_and_previous = position.and_then(|position| {
+        position
+            .checked_sub(1)
+            .map(|previous| (&tags[position], &tags[previous]))
+    });
+
+    // the post is made again from the state it was made from, so that the localization
+    // changes of the build it was made for are not added on top of themselves
+    let state_before_post = platform_state.before_last_posted_tag();
+
+    let edited_post = match (
+        position_and_previous,
+        platform_state.last_post_id,
+        &state_before_post,
+    ) {
+        (
+            Some(((_, new_version), (old_tag, old_version))),
+            Some(post_id),
+            Some(state_before_post),
+        ) => {
+            log_info!(
+                platform = platform,
+                "looking at [old_tag: {:?}, new_tag: {:?}]",
+                old_tag,
+                new_tag
+            );
+
+            let same_release = is_same_release(old_version, new_version);
+            let prepared_post = prepare_post(
+                state_before_post,
+                env,
+                platform,
+                http_client,
+                old_tag,
+                &new_tag,
+                same_release,
+                use_comparison_cache,
+            )
+            .await?;
+
+            match prepared_post {
+                Some(prepared_post) => {
+                    if !state_controller.is_dry_run() {
+                        utils::edit_post_in_discourse(
+                            &prepared_post.markdown_text,
+                            &utils::api_key(env)?,
+                            post_id,
+                            RECUT_TAG_EDIT_REASON,
+                        )
+                        .await
+                        .context("could not edit post in Discourse")?;
+                    } else {
+                        log_warn!(
+                            platform = platform,
+                            "dry run; not editing post_id = {post_id} in Discourse"
+                        );
+                        log_info!(
+                            platform = platform,
+                            "markdown_text = {}",
+                            prepared_post.markdown_text
+                        );
+                    }
+
+                    log_info!(platform = platform, "edited post_id = {post_id}");
+
+                    Some(prepared_post)
+                }
+                None => {
+                    log_warn!(
+                        platform = platform,
+                        "re-cut tag is behind the previous tag, not editing post"
+                    );
+                    None
+                }
+            }
+        }
+        (None, _, _) => {
+            log_warn!(
+                platform = platform,
+                "previous tag not found, not editing post"
+            );
+            None
+        }
+        (_, None, _) => {
+            log_warn!(
+                platform = platform,
+                "last_post_id is unknown, not editing post"
+            );
+            None
+        }
+        (_, _, None) => {
+            log_warn!(
+                platform = platform,
+                "localization changes from before the last post are unknown, not editing post"
+            );
+            None
+        }
+    };
+
+    let edited = edited_post.is_some();
+
+    state_controller
+        .update_platform_state(platform, |platform_state| {
+            recut_platform_state(platform_state, new_tag, edited_post);
+        })
+        .await
+        .context("could not set platform state after handling re-cut tag")?;
+
+    Ok(edited)
+}
+
+/// Returns the tag pointing at `live_sha` if it differs from the commit `last_posted_tag`
+/// was posted for.
+fn moved_tag(last_posted_tag: &Tag, live_sha: &str) -> Option<Tag> {
+    let live_tag = Tag::with_sha(&last_posted_tag.name, live_sha);
+    live_tag.is_recut_of(last_posted_tag).then_some(live_tag)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn post_archiving_message_if_necessary(
+    same_release: bool,
+    state_controller: &StateController,
+    env: &Env,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    discourse_api_key: &str,
+    old_version: &Version,
+    new_topic_id: u64,
+) -> anyhow::Result<()> {
+    if same_release
+        || state_controller
+            .platform_state(platform)
+            .posted_archiving_message
+    {
+        log_info!(platform = platform, "archiving message not necessary");
+        return Ok(());
+    } else {
+        log_info!(platform = platform, "attempting to post archiving message");
+    }
+
+    let old_topic_id =
+        utils::get_topic_id_or_override(http_client, env, discourse_api_key, platform, old_version)
+            .await
+            .context("could not find old_topic_id")?;
+
+    match old_topic_id {
+        Some(old_topic_id) => {
+            log_info!(platform = platform, "old_topic_id = {old_topic_id}");
+
+            post_cycle_summary_if_necessary(
+                state_controller,
+                platform,
+                http_client,
+                discourse_api_key,
+                old_topic_id,
+            )
+            .await?;
+
+            let markdown_text = utils::archiving_post_markdown(new_topic_id);
+            log_info!(
+                platform = platform,
+                "markdown_text.len() = {}",
+                markdown_text.len()
+            );
+
+            let result = if !state_controller.is_dry_run() {
+                utils::post_to_discourse(
+                    http_client,
+                    &markdown_text,
+                    discourse_api_key,
+                    old_topic_id,
+                    state_controller.platform_state(platform).last_post_number,
+                )
+                .await
+                .map(|post| post.number)
+            } else {
+                log_warn!(platform = platform, "dry run; not posting to Discourse");
+                Ok(0)
+            };
+
+            match result {
+                Ok(post_number) => {
+                    log_info!(
+                        platform = platform,
+                        "posted archiving message, post number = {}",
+                        post_number
+                    );
+
+                    state_controller
+                        .update_platform_state(platform, |platform_state| {
+                            platform_state.posted_archiving_message = true;
+                        })
+                        .await
+                        .context("could not set platform state after posting archiving message")?;
+
+                    utils::delay(POSTING_DELAY_MILLISECONDS).await;
+                }
+                Err(_) => {
+                    log_warn!(platform = platform, "could not post archiving message to old topic, it is likely already archived; ignoring, will not post archiving message for this release");
+                }
+            }
+        }
+        None => {
+            log_warn!(platform = platform, "old topic does not exist? ignoring, will not post archiving message for this release");
+        }
+    }
+
+    log_info!(
+        platform = platform,
+        "post_archiving_message_if_necessary done"
+    );
+
+    Ok(())
+}
+
+/// Renders the summary of the localization changes accumulated in `platform_state`
+/// for its release cycle, if the cycle is known.
+fn cycle_summary_markdown(
+    platform: Platform,
+    platform_state: &state::PlatformState,
+) -> Option<String> {
+    let cycle = platform_state.localization_cycle()?;
+
+    let summary = markdown::CycleSummary::new(
+        &cycle,
+        LocalizationChanges {
+            platform,
+            old_tag: &platform_state.last_posted_tag_previous_release,
+            new_tag: &platform_state.last_posted_tag,
+            completeness: platform_state.localization_changes_completeness,
+            completeness_rationale: platform_state
+                .localization_changes_completeness_rationale
+                .clone(),
+            unsorted_changes: platform_state.localization_changes.clone(),
+        },
+    );
+
+    Some(summary.markdown())
+}
+
+/// Posts the summary of the localization changes of the release cycle that just ended
+/// to its topic, `old_topic_id`, unless it was already posted.
+///
+/// Failing to post the summary does not prevent posting the new version.
+async fn post_cycle_summary_if_necessary(
+    state_controller: &StateController,
+    platform: Platform,
+    http_client: &impl HttpClient,
+    discourse_api_key: &str,
+    old_topic_id: u64,
+) -> anyhow::Result<()> {
+    let platform_state = state_controller.platform_state(platform);
+
+    if platform_state.posted_cycle_summary {
+        log_info!(platform = platform, "cycle summary already posted");
+        return Ok(());
+    }
+
+    let Some(markdown_text) = cycle_summary_markdown(platform, &platform_state) else {
+        log_warn!(
+            platform = platform,
+            "release cycle of {} is unknown, not posting cycle summary",
+            platform_state.last_posted_tag.name
+        );
+        return Ok(());
+    };
+
+    if state_controller.is_dry_run() {
+        log_warn!(
+            platform = platform,
+            "dry run; not posting cycle summary to Discourse, markdown_text = {markdown_text}"
+        );
+        return Ok(());
+    }
+
+    let result = utils::post_to_discourse(
+        http_client,
+        &markdown_text,
+        discourse_api_key,
+        old_topic_id,
+        None,
+    )
+    .await;
+
+    match result {
+        Ok(posted) => {
+            log_info!(
+                platform = platform,
+                "posted cycle summary, post number = {}",
+                posted.number
+            );
+
+            state_controller
+                .update_platform_state(platform, |platform_state| {
+                    platform_state.posted_cycle_summary = true;
+                })
+                .await
+                .context("could not set platform state after posting cycle summary")?;
+
+            utils::delay(POSTING_DELAY_MILLISECONDS).await;
+        }
+        Err(e) => {
+            log_warn!(
+                platform = platform,
+                "could not post cycle summary to old topic: {e:?}; ignoring"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{core::poll_once, platform::Platform::*};
+
+    /// Like the start of [`check_platform`]: gets the tags of `platform` with `client`,
+    /// and finds whether there is a tag after `v1.2.3` to post.
+    async fn check_tags(
+        client: &http::FakeClient,
+        platform: Platform,
+    ) -> anyhow::Result<PlatformCheckOutcome> {
+        let tags: Vec<Tag> = match utils::get_json_from_url_if_modified(
+            client,
+            &platform.github_api_tags_url(),
+            None,
+            None,
+        )
+        .await
+        .context("could not fetch tags from GitHub")?
+        {
+            Conditional::Modified { value, .. } => value,
+            Conditional::NotModified => return Ok(LatestVersionIsAlreadyPosted),
+        };
+
+        let mut tags: Vec<(Tag, Version)> = tags
+            .into_iter()
+            .map(|tag| {
+                let version = tag.to_version().unwrap();
+                (tag, version)
+            })
+            .collect();
+        tags.sort_unstable_by(|(_, lhs), (_, rhs)| lhs.cmp(rhs));
+
+        Ok(match tags_to_post(&tags, &Tag::new("v1.2.3")).len() {
+            0 | 1 => LatestVersionIsAlreadyPosted,
+            _ => PostedCommits,
+        })
+    }
+
+    #[test]
+    fn one_failing_platform_does_not_block_others() {
+        let client = http::FakeClient::default()
+            .respond_to_get(
+                &Android.github_api_tags_url(),
+                404,
+                &serde_json::json!({ "message": "Not Found" }),
+                None,
+            )
+            .respond_to_get(
+                &Ios.github_api_tags_url(),
+                200,
+                &serde_json::json!([{ "name": "v1.2.3" }]),
+                None,
+            )
+            .respond_to_get(
+                &Desktop.github_api_tags_url(),
+                200,
+                &serde_json::json!([{ "name": "v1.2.4" }, { "name": "v1.2.3" }]),
+                None,
+            );
+
+        let results = poll_once(check_concurrently(&[Android, Ios, Desktop], |platform| {
+            check_tags(&client, platform)
+        }));
+
+        let outcomes: Vec<_> = results
+            .iter()
+            .map(|(platform, result)| match result {
+                Ok(outcome) => (*platform, outcome.description().to_string()),
+                Err(e) => (*platform, e.to_string()),
+            })
+            .collect();
+
+        assert_eq!(
+            outcomes,
+            vec![
+                (Android, String::from("could not fetch tags from GitHub")),
+                (Ios, String::from("nothing new")),
+                (Desktop, String::from("posted")),
+            ]
+        );
+
+        let mut requested_urls = client.requested_urls();
+        requested_urls.sort_unstable();
+        let mut tags_urls: Vec<String> = [Android, Ios, Desktop]
+            .iter()
+            .map(Platform::github_api_tags_url)
+            .collect();
+        tags_urls.sort_unstable();
+
+        assert_eq!(requested_urls, tags_urls);
+    }
+
+    #[test]
+    fn platforms_over_budget_are_reported() {
+        let results: PlatformCheckResults = vec![
+            (Android, Ok(PostedCommits)),
+            (Ios, Ok(OverRequestBudget)),
+            (Desktop, Ok(OverRequestBudget)),
+        ];
+
+        assert_eq!(skipped_over_budget(&results), [Ios, Desktop]);
+        assert_eq!(
+            OverRequestBudget.description(),
+            "skipped: over request budget"
+        );
+    }
+
+    fn post_to_topics(topic_ids: &[u64]) -> Vec<(u64, anyhow::Result<utils::DiscoursePost>)> {
+        poll_once(post_to_additional_topics(
+            topic_ids,
+            100,
+            |topic_id| async move {
+                match topic_id {
+                    200 => Err(anyhow!("could not post to Discourse")),
+                    topic_id => Ok(utils::DiscoursePost {
+                        id: topic_id * 10,
+                        number: 1,
+                    }),
+                }
+            },
+        ))
+    }
+
+    #[test]
+    fn additional_topics_partially_fail() {
+        let results = post_to_topics(&[200, 100, 300]);
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|(topic_id, result)| (*topic_id, result.is_ok()))
+                .collect::<Vec<_>>(),
+            vec![(200, false), (300, true)]
+        );
+        assert_eq!(
+            additional_topics_error(&results).unwrap().to_string(),
+            "could not post to 1 of 2 additional topics: topic 200: could not post to Discourse"
+        );
+    }
+
+    #[test_case(&[]; "none")]
+    #[test_case(&[100]; "only the primary topic")]
+    #[test_case(&[300, 400]; "all succeed")]
+    fn additional_topics_succeed(topic_ids: &[u64]) {
+        assert!(additional_topics_error(&post_to_topics(topic_ids)).is_none());
+    }
+
+    /// Runs [`commit_and_announce`] with a background spawner, returning its result,
+    /// whether the state was committed before it returned and how many tasks were deferred.
+    fn commit_then_announce(commit_succeeds: bool) -> (bool, bool, usize) {
+        use std::{cell::Cell, rc::Rc};
+
+        let spawner = background::FakeSpawner::default();
+        let committed = Cell::new(false);
+        let announced = Rc::new(Cell::new(false));
+
+        let announced_in_task = Rc::clone(&announced);
+        let result = poll_once(invocation::scope(
+            spawner.invocation(),
+            commit_and_announce(
+                async {
+                    committed.set(true);
+                    match commit_succeeds {
+                        true => Ok(()),
+                        false => Err(anyhow!(
+                            "could not set platform state after posting commits"
+                        )),
+                    }
+                },
+                async move { announced_in_task.set(true) },
+            ),
+        ));
+
+        assert!(!announced.get());
+
+        (result.is_ok(), committed.get(), spawner.spawned())
+    }
+
+    #[test]
+    fn state_committed_before_announcing() {
+        assert_eq!(commit_then_announce(true), (true, true, 1));
+    }
+
+    #[test]
+    fn nothing_announced_if_commit_fails() {
+        assert_eq!(commit_then_announce(false), (false, true, 0));
+    }
+
+    #[test]
+    fn localization_change_shas_include_touching_commits() {
+        let touching_shas = vec![String::from("abc222"), String::from("abc111")];
+
+        assert_eq!(
+            localization_change_shas(&["abc111", "abc333"], Some(&touching_shas)),
+            vec!["abc111", "abc333", "abc222"]
+        );
+    }
+
+    #[test]
+    fn localization_change_shas_without_touching_commits() {
+        assert_eq!(localization_change_shas(&["abc111"], None), vec!["abc111"]);
+    }
+
+    #[test_case(true, 2, true, Completeness::Complete; "found touching commits")]
+    #[test_case(true, 0, true, Completeness::Complete; "no commits touch localization")]
+    #[test_case(true, 2, false, Completeness::Incomplete; "commit with too many files")]
+    #[test_case(false, 2, true, Completeness::LikelyComplete; "only likely commits")]
+    #[test_case(false, 0, true, Completeness::Incomplete; "no likely commits")]
+    #[test_case(false, 2, false, Completeness::Incomplete; "likely commit with too many files")]
+    fn completeness_after_commit_files(
+        found_touching_commits: bool,
+        commits_len: usize,
+        all_complete: bool,
+        result: Completeness,
+    ) {
+        assert_eq!(
+            completeness_from_commit_files(found_touching_commits, commits_len, all_complete).0,
+            result
+        );
+    }
+
+    fn comparison_with_total(total_commits: usize) -> types::github::Comparison {
+        types::github::Comparison {
+            status: types::github::ComparisonStatus::Ahead,
+            ahead_by: total_commits,
+            total_commits,
+            commits: Vec::new(),
+            files: Some(Vec::new()),
+            merge_base_commit: None,
+        }
+    }
+
+    fn not_found() -> anyhow::Error {
+        utils::UnexpectedStatus {
+            url: "https://api.github.com/repos/signalapp/Signal-Android/compare/v1.2.3...v1.2.5"
+                .to_string(),
+            status_code: 404,
+            body_excerpt: String::new(),
+        }
+        .into()
+    }
+
+    fn compare_with_deleted(
+        deleted: &'static str,
+    ) -> impl Fn(Tag) -> std::future::Ready<anyhow::Result<types::github::Comparison>> {
+        move |tag| {
+            std::future::ready(if tag.name == deleted {
+                Err(not_found())
+            } else {
+                Ok(comparison_with_total(tag.name.len()))
+            })
+        }
+    }
+
+    #[test]
+    fn comparison_from_existing_tag() {
+        let result = poll_once(compare_or_fall_back(
+            &Tag::new("v1.2.3"),
+            &Tag::new("v1.1.0"),
+            compare_with_deleted("v0.0.0"),
+            || async { panic!("should not check whether the tag exists") },
+        ))
+        .unwrap();
+
+        assert_eq!(result, (Tag::new("v1.2.3"), comparison_with_total(6)));
+    }
+
+    #[test]
+    fn comparison_falls_back_when_old_tag_was_deleted() {
+        let result = poll_once(compare_or_fall_back(
+            &Tag::new("v1.2.3"),
+            &Tag::new("v1.1.10"),
+            compare_with_deleted("v1.2.3"),
+            || async { Ok(false) },
+        ))
+        .unwrap();
+
+        assert_eq!(result, (Tag::new("v1.1.10"), comparison_with_total(7)));
+    }
+
+    #[test]
+    fn comparison_does_not_fall_back_when_old_tag_exists() {
+        let result = poll_once(compare_or_fall_back(
+            &Tag::new("v1.2.3"),
+            &Tag::new("v1.1.10"),
+            compare_with_deleted("v1.2.3"),
+            || async { Ok(true) },
+        ));
+
+        assert!(utils::is_not_found(&result.unwrap_err()));
+    }
+
+    #[test]
+    fn comparison_does_not_fall_back_on_other_errors() {
+        let result = poll_once(compare_or_fall_back(
+            &Tag::new("v1.2.3"),
+            &Tag::new("v1.1.10"),
+            |_| async { Err(anyhow!("could not connect")) },
+            || async { panic!("should not check whether the tag exists") },
+        ));
+
+        assert_eq!(result.unwrap_err().to_string(), "could not connect");
+    }
+
+    #[test]
+    fn deleted_tag_notice_names_both_tags() {
+        assert_eq!(
+            deleted_tag_notice(&Tag::new("v1.2.3"), &Tag::new("v1.1.10")),
+            "The previous tag 1.2.3 disappeared, so commits are listed since 1.1.10."
+        );
+    }
+
+    fn tags_with_versions(names: &[&str]) -> Vec<(Tag, Version)> {
+        names
+            .iter()
+            .map(|name| {
+                let tag = Tag::new(*name);
+                let version = tag.to_version().unwrap();
+                (tag, version)
+            })
+            .collect()
+    }
+
+    #[test_case(&["v1.2.3", "v1.2.4", "v1.2.5"], "v1.2.4", &["v1.2.4", "v1.2.5"]; "last posted tag listed")]
+    #[test_case(&["v1.2.3", "v1.2.5", "v1.2.6"], "v1.2.4", &["v1.2.4", "v1.2.5", "v1.2.6"]; "last posted tag deleted")]
+    #[test_case(&["v1.2.3"], "v1.2.4", &["v1.2.4"]; "last posted tag deleted, nothing newer")]
+    fn tags_to_post_from_last_posted(tags: &[&str], last_posted_tag: &str, result: &[&str]) {
+        assert_eq!(
+            tags_to_post(&tags_with_versions(tags), &Tag::new(last_posted_tag)),
+            tags_with_versions(result)
+        );
+    }
+
+    #[test_case(Tag::with_sha("v1.2.3", "abc"), "abc", None; "same sha")]
+    #[test_case(Tag::with_sha("v1.2.3", "abc"), "def", Some(Tag::with_sha("v1.2.3", "def")); "moved")]
+    #[test_case(Tag::new("v1.2.3"), "def", None; "posted sha unknown")]
+    fn last_posted_tag_moved(last_posted_tag: Tag, live_sha: &str, result: Option<Tag>) {
+        assert_eq!(moved_tag(&last_posted_tag, live_sha), result);
+    }
+
+    fn posted_at(last_posted_tag: &str, last_posted_at: Option<&str>) -> state::PlatformState {
+        let mut platform_state = platform_state(last_posted_tag, None);
+        platform_state.last_posted_at = last_posted_at.map(ToOwned::to_owned);
+        platform_state
+    }
+
+    #[test_case(Some("2022-07-01T00:30:00.000Z"), Some("v1.2.4"); "posted within interval")]
+    #[test_case(Some("2022-07-01T00:00:00.001Z"), Some("v1.2.4"); "just within interval")]
+    #[test_case(Some("2022-07-01T00:00:00.000Z"), None; "interval just elapsed")]
+    #[test_case(Some("2022-06-30T00:00:00.000Z"), None; "posted long ago")]
+    #[test_case(None, None; "never posted")]
+    fn post_deferred_until_interval_elapsed(last_posted_at: Option<&str>, result: Option<&str>) {
+        let tags = tags_with_versions(&["v1.2.3", "v1.2.4"]);
+
+        assert_eq!(
+            deferred_tag(
+                &posted_at("v1.2.3", last_posted_at),
+                &tags,
+                Some("2022-07-01T00:00:00.000Z")
+            )
+            .map(|tag| &tag.name[..]),
+            result
+        );
+    }
+
+    #[test]
+    fn post_not_deferred_without_interval() {
+        let tags = tags_with_versions(&["v1.2.3", "v1.2.4"]);
+
+        assert_eq!(
+            deferred_tag(
+                &posted_at("v1.2.3", Some("2022-07-01T00:30:00.000Z")),
+                &tags,
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn queued_tags_are_deferred_one_at_a_time() {
+        let tags = tags_with_versions(&["v1.2.3", "v1.2.4", "v1.2.5", "v1.2.6"]);
+        let earliest_last_posted_at = Some("2022-07-01T00:00:00.000Z");
+
+        let pending = |last_posted_tag: &str| {
+            deferred_tag(
+                &posted_at(last_posted_tag, Some("2022-07-01T00:30:00.000Z")),
+                &tags_to_post(&tags, &Tag::new(last_posted_tag)),
+                earliest_last_posted_at,
+            )
+            .map(|tag| tag.name.clone())
+        };
+
+        assert_eq!(pending("v1.2.3"), Some(String::from("v1.2.4")));
+        assert_eq!(pending("v1.2.4"), Some(String::from("v1.2.5")));
+        assert_eq!(pending("v1.2.6"), None);
+    }
+
+    fn platform_state(last_posted_tag: &str, cycle: Option<&str>) -> state::PlatformState {
+        let mut platform_state =
+            state::PlatformState::initial(Tag::new("v1.1.0"), Tag::new(last_posted_tag));
+        platform_state.localization_changes_cycle = cycle.map(ToOwned::to_owned);
+        platform_state
+    }
+
+    #[test_case(Some("1.2"), "v1.2.5", false, true; "same cycle")]
+    #[test_case(Some("1.2"), "v1.3.0", false, false; "new cycle")]
+    #[test_case(Some("1.2"), "v1.3.0", true, false; "new cycle despite same release")]
+    #[test_case(None, "v1.2.5", true, true; "old state, same cycle as last posted tag")]
+    #[test_case(None, "v1.3.0", false, false; "old state, new cycle")]
+    #[test_case(Some("1.2"), "latest", true, true; "unknown cycle, same release")]
+    fn localization_changes_accumulation(
+        cycle: Option<&str>,
+        new_tag: &str,
+        same_release: bool,
+        result: bool,
+    ) {
+        assert_eq!(
+            accumulates_localization_changes(
+                &platform_state("v1.2.4", cycle),
+                &Tag::new(new_tag),
+                same_release
+            ),
+            result
+        );
+    }
+
+    fn release_changes_with(
+        build_language_codes: &[&str],
+        release_comparison: Option<&types::github::Comparison>,
+    ) -> (Completeness, UnsortedChanges) {
+        let mut state = platform_state("v1.2.4", Some("1.2"));
+        state.localization_changes_completeness = Completeness::Complete;
+        state.localization_changes =
+            LocalizationChange::unsorted_changes(vec![LocalizationChange::test_change(
+                "en",
+                vec![localization::StringsFileKind::Main],
+            )]);
+
+        let old_tag = Tag::new("v1.2.4");
+        let new_tag = Tag::new("v1.2.5");
+
+        let build_localization_changes = LocalizationChanges {
+            platform: Android,
+            old_tag: &old_tag,
+            new_tag: &new_tag,
+            completeness: Completeness::Complete,
+            completeness_rationale: None,
+            unsorted_changes: LocalizationChange::unsorted_changes(
+                build_language_codes
+                    .iter()
+                    .map(|code| {
+                        LocalizationChange::test_change(
+                            code,
+                            vec![localization::StringsFileKind::Main],
+                        )
+                    })
+                    .collect(),
+            ),
+        };
+
+        let changes = release_localization_changes(
+            Android,
+            &state,
+            &new_tag,
+            &build_localization_changes,
+            release_comparison,
+        );
+
+        assert_eq!(changes.old_tag, &Tag::new("v1.1.0"));
+        (changes.completeness, changes.unsorted_changes)
+    }
+
+    fn comparison_with_files(
+        total_commits: usize,
+        filenames: &[&str],
+    ) -> types::github::Comparison {
+        types::github::Comparison {
+            files: Some(
+                filenames
+                    .iter()
+                    .map(|filename| types::github::File::new(*filename))
+                    .collect(),
+            ),
+            ..comparison_with_total(total_commits)
+        }
+    }
+
+    fn language_codes(changes: UnsortedChanges) -> Vec<String> {
+        LocalizationChange::sorted_changes(changes)
+            .iter()
+            .map(|change| change.language().language_code.clone())
+            .collect()
+    }
+
+    #[test]
+    fn release_changes_from_complete_comparison() {
+        let comparison = comparison_with_files(3, &["app/src/main/res/values-de/strings.xml"]);
+
+        let (completeness, changes) = release_changes_with(&["fr"], Some(&comparison));
+
+        assert_eq!(completeness, Completeness::Complete);
+        assert_eq!(language_codes(changes), vec!["de"]);
+    }
+
+    #[test]
+    fn release_changes_accumulated_without_comparison() {
+        let (completeness, changes) = release_changes_with(&["fr"], None);
+
+        assert_eq!(completeness, Completeness::Complete);
+        assert_eq!(language_codes(changes), vec!["en", "fr"]);
+    }
+
+    #[test]
+    fn release_changes_accumulated_from_incomplete_comparison() {
+        let comparison = comparison_with_files(300, &["app/src/main/res/values-de/strings.xml"]);
+
+        let (completeness, changes) = release_changes_with(&["fr"], Some(&comparison));
+
+        assert_eq!(completeness, Completeness::Complete);
+        assert_eq!(language_codes(changes), vec!["en", "fr"]);
+    }
+
+    #[test]
+    fn cycle_rollover_is_summarized() {
+        let mut previous = platform_state("v1.2.4", Some("1.2"));
+        previous.localization_changes_completeness = Completeness::LikelyComplete;
+
+        assert_eq!(
+            closed_cycle_summary(&previous, &platform_state("v1.3.0", Some("1.3"))),
+            Some(String::from(
+                "closed release cycle 1.2 with localization changes for 0 language(s), LikelyComplete"
+            ))
+        );
+    }
+
+    #[test]
+    fn cycle_summary_of_platform_state() {
+        let markdown = cycle_summary_markdown(Desktop, &platform_state("v1.2.4", Some("1.2")));
+
+        assert!(markdown.is_some_and(|markdown| markdown.starts_with(
+            "## Localization Changes in 1.2\n[quote]\nLanguages changed during the beta cycle of 1.2, from 1.1.0 to 1.2.4. The list may be partial.\n"
+        )));
+    }
+
+    #[test]
+    fn cycle_summary_of_unknown_cycle() {
+        assert_eq!(
+            cycle_summary_markdown(Desktop, &platform_state("latest", None)),
+            None
+        );
+    }
+
+    fn same_code_post(
+        old_tag: &str,
+        new_tag: &str,
+        previous_release_state: Option<&state::PlatformState>,
+    ) -> PreparedPost {
+        poll_once(prepare_post_from_comparison(
+            &http::FakeClient::default(),
+            None,
+            Android,
+            &Tag::new(old_tag),
+            &Tag::new(new_tag),
+            &comparison_with_total(0),
+            previous_release_state,
+            None,
+            None,
+            &[],
+            markdown::PostOptions::default(),
+        ))
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn empty_comparison_is_a_note() {
+        let mut previous = platform_state("v1.2.4", Some("1.2"));
+        previous.localization_changes_completeness = Completeness::LikelyComplete;
+
+        let prepared_post = same_code_post("v1.2.4", "v1.2.5", Some(&previous));
+
+        assert!(prepared_post.same_code);
+        assert_eq!(
+            prepared_post.markdown_text,
+            "Tag v1.2.5 points at the same code as v1.2.4."
+        );
+        assert_eq!(prepared_post.commits_len, 0);
+        assert_eq!(
+            prepared_post.last_posted_tag_previous_release,
+            Tag::new("v1.1.0")
+        );
+        assert_eq!(
+            prepared_post.localization_changes_completeness,
+            Completeness::LikelyComplete
+        );
+    }
+
+    #[test]
+    fn empty_comparison_in_new_release() {
+        let prepared_post = same_code_post("v1.2.4", "v1.3.0", None);
+
+        assert_eq!(
+            prepared_post.last_posted_tag_previous_release,
+            Tag::new("v1.2.4")
+        );
+        assert_eq!(
+            prepared_post.localization_changes_cycle,
+            Some(String::from("1.3"))
+        );
+    }
+
+    #[test]
+    fn skipping_empty_comparison_advances_last_posted_tag() {
+        let mut previous = platform_state("v1.2.4", Some("1.2"));
+        previous.last_post_number = Some(5);
+        previous.post_attempts = 2;
+        previous.pending_tag = Some(String::from("v1.2.5"));
+
+        let skipped = same_code_post("v1.2.4", "v1.2.5", Some(&previous))
+            .into_skipped_platform_state(&Tag::new("v1.2.5"), &previous);
+
+        assert_eq!(skipped.last_posted_tag, Tag::new("v1.2.5"));
+        assert_eq!(skipped.last_posted_tag_previous_release, Tag::new("v1.1.0"));
+        assert_eq!(skipped.last_post_number, Some(5));
+        assert_eq!(skipped.post_attempts, 0);
+        assert_eq!(skipped.pending_tag, None);
+    }
+
+    /// The post for `new_tag` on top of `previous`, whose build changed the languages
+    /// with `build_language_codes`.
+    fn post_with_build_changes(
+        previous: &state::PlatformState,
+        new_tag: &Tag,
+        build_language_codes: &[&str],
+    ) -> PreparedPost {
+        let old_tag = previous.last_posted_tag.clone();
+
+        let build_localization_changes = LocalizationChanges {
+            platform: Android,
+            old_tag: &old_tag,
+            new_tag,
+            completeness: Completeness::Complete,
+            completeness_rationale: None,
+            unsorted_changes: LocalizationChange::unsorted_changes(
+                build_language_codes
+                    .iter()
+                    .map(|code| {
+                        LocalizationChange::test_change(
+                            code,
+                            vec![localization::StringsFileKind::Main],
+                        )
+                    })
+                    .collect(),
+            ),
+        };
+
+        let changes = release_localization_changes(
+            Android,
+            previous,
+            new_tag,
+            &build_localization_changes,
+            None,
+        );
+
+        PreparedPost {
+            markdown_text: String::new(),
+            commits_len: 1,
+            build_localization_changes_len: build_language_codes.len(),
+            last_posted_tag_previous_release: previous.last_posted_tag_previous_release.clone(),
+            localization_changes_completeness: changes.completeness,
+            localization_changes_completeness_rationale: None,
+            localization_changes: changes.unsorted_changes,
+            localization_changes_cycle: Some(String::from("1.2")),
+            posted_commits: Vec::new(),
+            same_code: false,
+        }
+    }
+
+    #[test]
+    fn recut_edit_replaces_localization_changes_of_build() {
+        let mut previous = platform_state("v1.2.4", Some("1.2"));
+        previous.localization_changes_completeness = Completeness::Complete;
+        previous.localization_changes =
+            LocalizationChange::unsorted_changes(vec![LocalizationChange::test_change(
+                "en",
+                vec![localization::StringsFileKind::Main],
+            )]);
+
+        let recut_tag = Tag::new("v1.2.5");
+
+        let mut posted = post_with_build_changes(&previous, &recut_tag, &["fr"])
+            .into_platform_state(
+                &previous,
+                &recut_tag,
+                &utils::DiscoursePost { id: 42, number: 5 },
+                "https://community.signalusers.org/t/123/5",
+                "2022-07-02T00:00:00.000Z",
+            );
+        assert_eq!(
+            language_codes(posted.localization_changes.clone()),
+            vec!["en", "fr"]
+        );
+
+        let state_before_post = posted.before_last_posted_tag().unwrap();
+        assert_eq!(
+            state_before_post.localization_changes,
+            previous.localization_changes
+        );
+        assert_eq!(
+            state_before_post.last_posted_tag_previous_release,
+            Tag::new("v1.1.0")
+        );
+
+        let edited_post = post_with_build_changes(&state_before_post, &recut_tag, &["de"]);
+        recut_platform_state(&mut posted, recut_tag.clone(), Some(edited_post));
+
+        assert_eq!(
+            language_codes(posted.localization_changes.clone()),
+            vec!["en", "de"]
+        );
+        assert_eq!(
+            posted.localization_changes_completeness,
+            Completeness::Complete
+        );
+        assert_eq!(posted.last_posted_tag_previous_release, Tag::new("v1.1.0"));
+        assert_eq!(posted.last_posted_tag, recut_tag);
+        assert!(!posted.tag_moved);
+        assert_eq!(
+            posted.localization_before_last_posted_tag,
+            Some(previous.localization_state())
+        );
+    }
+
+    #[test]
+    fn recut_without_edit_marks_tag_moved() {
+        let mut platform_state = platform_state("v1.2.5", Some("1.2"));
+        let localization_changes = platform_state.localization_changes.clone();
+
+        recut_platform_state(&mut platform_state, Tag::new("v1.2.5"), None);
+
+        assert!(platform_state.tag_moved);
+        assert_eq!(platform_state.localization_changes, localization_changes);
+    }
+
+    #[test_case(Some("1.2"), Some("1.2"); "same cycle")]
+    #[test_case(None, Some("1.2"); "old state")]
+    fn cycle_not_closed(previous_cycle: Option<&str>, new_cycle: Option<&str>) {
+        assert_eq!(
+            closed_cycle_summary(
+                &platform_state("v1.2.4", previous_cycle),
+                &platform_state("v1.2.5", new_cycle)
+            ),
+            None
+        );
+    }
+}
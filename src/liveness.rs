@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Context};
+use worker::Env;
+use worker_kv::KvStore;
+
+use crate::{
+    failure_notification,
+    log::{log_error, log_info, log_warn},
+    state::{self, STATE_KV_BINDING},
+    utils,
+};
+
+/// ISO 8601 timestamp of when a run last completed without errors.
+const LAST_SUCCESSFUL_RUN_KV_KEY: &str = "last_successful_run";
+
+/// The value of [`LAST_SUCCESSFUL_RUN_KV_KEY`] that the last "bot may be down" alert was sent for.
+const ALERTED_FOR_KV_KEY: &str = "down_alert_sent_for";
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+async fn get(kv_store: &KvStore, key: &str) -> anyhow::Result<Option<String>> {
+    kv_store
+        .get(key)
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))
+}
+
+async fn put(kv_store: &KvStore, key: &str, value: &str) -> anyhow::Result<()> {
+    kv_store
+        .put(key, value)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+/// Whether `last_successful_run` is before `cutoff`, both ISO 8601 timestamps in UTC,
+/// which are compared as strings.
+///
+/// If no run was recorded as successful yet, the bot is not considered down.
+pub fn is_overdue(last_successful_run: Option<&str>, cutoff: &str) -> bool {
+    matches!(last_successful_run, Some(last_successful_run) if last_successful_run < cutoff)
+}
+
+/// Whether to alert that the bot may be down: only if the last successful run is overdue,
+/// and only once until there is another successful run.
+pub fn should_alert(
+    last_successful_run: Option<&str>,
+    cutoff: &str,
+    alerted_for: Option<&str>,
+) -> bool {
+    is_overdue(last_successful_run, cutoff) && alerted_for != last_successful_run
+}
+
+/// Cutoff for [`is_overdue`] with the configured threshold.
+pub fn cutoff(env: &Env) -> anyhow::Result<String> {
+    Ok(utils::iso_string_ago(
+        utils::down_alert_threshold_hours(env)? * 60 * 60,
+    ))
+}
+
+/// Returns when a run last completed without errors, if known.
+pub async fn last_successful_run(env: &Env) -> anyhow::Result<Option<String>> {
+    get(
+        &kv_store(env)?,
+        &state::kv_key(env, LAST_SUCCESSFUL_RUN_KV_KEY),
+    )
+    .await
+}
+
+/// Records that a run completed without errors; failures are only logged.
+pub async fn record_success(env: &Env) {
+    let result = match kv_store(env) {
+        Ok(kv_store) => {
+            put(
+                &kv_store,
+                &state::kv_key(env, LAST_SUCCESSFUL_RUN_KV_KEY),
+                &utils::now_iso_string(),
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        log_error!("could not record successful run: {e:?}");
+    }
+}
+
+/// Alerts that the bot may be down if the run that failed with `error` comes after
+/// no successful run for longer than the configured threshold.
+///
+/// Failures are only logged, so that the original error is not masked.
+pub async fn check_after_failure(env: &Env, error: &anyhow::Error, is_dry_run: bool) {
+    if let Err(e) = try_check_after_failure(env, error, is_dry_run).await {
+        log_error!("could not check when the last successful run was: {e:?}");
+    }
+}
+
+async fn try_check_after_failure(
+    env: &Env,
+    error: &anyhow::Error,
+    is_dry_run: bool,
+) -> anyhow::Result<()> {
+    let kv_store = kv_store(env)?;
+
+    let alerted_for_key = state::kv_key(env, ALERTED_FOR_KV_KEY);
+
+    let last_successful_run =
+        get(&kv_store, &state::kv_key(env, LAST_SUCCESSFUL_RUN_KV_KEY)).await?;
+    let alerted_for = get(&kv_store, &alerted_for_key).await?;
+    let cutoff = cutoff(env)?;
+
+    if !should_alert(
+        last_successful_run.as_deref(),
+        &cutoff,
+        alerted_for.as_deref(),
+    ) {
+        log_info!(
+            "last_successful_run = {last_successful_run:?}, alerted_for = {alerted_for:?}; not alerting"
+        );
+        return Ok(());
+    }
+
+    let last_successful_run = last_successful_run.unwrap_or_default();
+    log_warn!("no successful run since {last_successful_run}, bot may be down");
+
+    if failure_notification::notify_down(env, &last_successful_run, error, is_dry_run).await? {
+        put(&kv_store, &alerted_for_key, &last_successful_run).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    const CUTOFF: &str = "2022-07-02T06:00:00.000Z";
+
+    #[test_case(None, false; "never recorded")]
+    #[test_case(Some("2022-07-02T05:59:59.999Z"), true; "just before cutoff")]
+    #[test_case(Some("2022-07-02T06:00:00.000Z"), false; "at cutoff")]
+    #[test_case(Some("2022-07-02T06:00:00.001Z"), false; "just after cutoff")]
+    #[test_case(Some("2022-06-30T12:00:00.000Z"), true; "days before cutoff")]
+    fn overdue(last_successful_run: Option<&str>, result: bool) {
+        assert_eq!(is_overdue(last_successful_run, CUTOFF), result);
+    }
+
+    #[test]
+    fn alert_when_overdue() {
+        assert!(should_alert(Some("2022-07-01T00:00:00.000Z"), CUTOFF, None));
+    }
+
+    #[test]
+    fn no_alert_when_recent() {
+        assert!(!should_alert(
+            Some("2022-07-02T07:00:00.000Z"),
+            CUTOFF,
+            None
+        ));
+    }
+
+    #[test]
+    fn alert_only_once() {
+        let last_successful_run = Some("2022-07-01T00:00:00.000Z");
+
+        assert!(!should_alert(
+            last_successful_run,
+            CUTOFF,
+            last_successful_run
+        ));
+    }
+
+    #[test]
+    fn alert_again_after_another_success() {
+        assert!(should_alert(
+            Some("2022-07-02T01:00:00.000Z"),
+            CUTOFF,
+            Some("2022-07-01T00:00:00.000Z")
+        ));
+    }
+
+    #[test]
+    fn no_alert_without_successful_run() {
+        assert!(!should_alert(None, CUTOFF, None));
+    }
+}
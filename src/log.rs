@@ -0,0 +1,45 @@
+use std::future::Future;
+
+use worker::{console_error, console_log, console_warn, js_sys, Env};
+
+pub(crate) use crate::core::log::{log_error, log_info, log_warn};
+pub use crate::core::log::{redact, trace_id};
+use crate::{
+    core::log::{self, Level},
+    utils,
+};
+
+fn write_to_console(level: Level, line: &str) {
+    match level {
+        Level::Info => console_log!("{line}"),
+        Level::Warn => console_warn!("{line}"),
+        Level::Error => console_error!("{line}"),
+    }
+}
+
+/// Runs `future`, an invocation of the worker, with its own logging: generates the trace id
+/// that is added to every line, applies `LOG_LEVEL`, and gets the secrets to redact.
+pub async fn scope<F: Future>(env: &Env, future: F) -> F::Output {
+    let level = utils::log_level(env).map(|name| (Level::from_name(&name), name));
+
+    let context = log::Context {
+        output: write_to_console,
+        trace_id: utils::random_hex_string(2, js_sys::Math::random),
+        min_level: match &level {
+            Ok((Some(level), _)) => *level,
+            _ => Level::Info,
+        },
+        secrets: utils::secret_values(env),
+    };
+
+    log::scope(context, async {
+        match level {
+            Ok((Some(_), _)) => (),
+            Ok((None, name)) => log_error!("unknown LOG_LEVEL {name:?}, logging everything"),
+            Err(e) => log_error!("could not get LOG_LEVEL, logging everything: {e:?}"),
+        }
+
+        future.await
+    })
+    .await
+}
@@ -1,7 +0,0 @@
-mod commit;
-mod commit_status;
-mod post;
-
-pub use commit::*;
-pub use commit_status::*;
-pub use post::*;
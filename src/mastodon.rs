@@ -0,0 +1,201 @@
+use anyhow::{bail, Context};
+use serde_json::json;
+use worker::{Env, Fetch, Method, Url};
+
+use crate::{
+    hash,
+    log::{log_error, log_info},
+    platform::Platform,
+    types::github::Tag,
+    utils,
+};
+
+/// Default character limit of a status on Mastodon instances.
+pub const MAX_STATUS_CHARS: usize = 500;
+
+pub fn status_text(
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) -> String {
+    announcement_text(
+        MAX_STATUS_CHARS,
+        platform,
+        old_tag,
+        new_tag,
+        commits_count,
+        post_url,
+    )
+}
+
+/// Line about the new version followed by `post_url`, truncating the line so that
+/// the text has at most `max_chars` characters.
+pub fn announcement_text(
+    max_chars: usize,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) -> String {
+    let commits_word_suffix = if commits_count == 1 { "" } else { "s" };
+
+    let text = format!(
+        "Signal {platform} {} is out — {commits_count} commit{commits_word_suffix} since {}",
+        new_tag.exact_version_string(),
+        old_tag.exact_version_string()
+    );
+    let suffix = format!(": {post_url}");
+
+    let max_text_chars = max_chars.saturating_sub(suffix.chars().count());
+
+    let text = if text.chars().count() > max_text_chars {
+        let truncated: String = text
+            .chars()
+            .take(max_text_chars.saturating_sub(1))
+            .collect();
+        format!("{truncated}…")
+    } else {
+        text
+    };
+
+    text + &suffix
+}
+
+fn idempotency_key(platform: Platform, new_tag: &Tag) -> String {
+    hash::sha256_string(&format!("{platform}/{}", new_tag.name))
+}
+
+/// Publishes a status about the new version, if Mastodon is configured.
+///
+/// Failures are only logged, as publishing the status is not essential.
+pub async fn publish(
+    env: &Env,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) {
+    let status = status_text(platform, old_tag, new_tag, commits_count, post_url);
+
+    if let Err(e) = try_publish(env, &status, &idempotency_key(platform, new_tag)).await {
+        log_error!(
+            platform = platform,
+            "could not publish status to Mastodon: {e:?}"
+        );
+    }
+}
+
+async fn try_publish(env: &Env, status: &str, idempotency_key: &str) -> anyhow::Result<()> {
+    let base_url = match utils::mastodon_base_url(env)? {
+        Some(base_url) => base_url,
+        None => {
+            log_info!("no Mastodon base URL configured; not publishing status");
+            return Ok(());
+        }
+    };
+
+    let url = Url::parse(&format!(
+        "{}/api/v1/statuses",
+        base_url.trim_end_matches('/')
+    ))
+    .context("could not parse URL")?;
+    let access_token = utils::mastodon_access_token(env)?;
+
+    let body = json!({
+        "status": status,
+        "visibility": "public",
+    });
+
+    let request = utils::RequestBuilder::new(url, Method::Post)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .header("Idempotency-Key", idempotency_key)
+        .json(&body)
+        .build()?;
+
+    let response = utils::fetch(Fetch::Request(request)).await?;
+
+    match response.status_code() {
+        200..=299 => {
+            log_info!("published status to Mastodon");
+            Ok(())
+        }
+        status_code => bail!("Mastodon responded with status code {status_code}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    #[test_case(
+        Desktop, "v7.1.0-beta.1", "v7.1.0-beta.2", 12,
+        "Signal Desktop 7.1.0-beta.2 is out — 12 commits since 7.1.0-beta.1: https://community.signalusers.org/t/123/45";
+        "Desktop: several commits"
+    )]
+    #[test_case(
+        Android, "v7.1.3", "v7.1.4", 1,
+        "Signal Android 7.1.4 is out — 1 commit since 7.1.3: https://community.signalusers.org/t/123/45";
+        "Android: one commit"
+    )]
+    fn status(
+        platform: Platform,
+        old_tag: &str,
+        new_tag: &str,
+        commits_count: usize,
+        result: &str,
+    ) {
+        assert_str_eq!(
+            status_text(
+                platform,
+                &Tag::new(old_tag),
+                &Tag::new(new_tag),
+                commits_count,
+                "https://community.signalusers.org/t/123/45"
+            ),
+            result
+        );
+    }
+
+    #[test]
+    fn status_truncated() {
+        let post_url = format!("https://community.signalusers.org/t/{}", "1".repeat(420));
+
+        let status = status_text(
+            Ios,
+            &Tag::new("7.1.0.1-beta"),
+            &Tag::new("7.1.0.2-beta"),
+            3,
+            &post_url,
+        );
+
+        assert_eq!(status.chars().count(), MAX_STATUS_CHARS);
+        assert!(status.starts_with("Signal iOS 7.1.0.2-beta is out"));
+        assert!(status.ends_with(&format!("…: {post_url}")));
+    }
+
+    #[test]
+    fn idempotency_key_depends_on_platform_and_tag() {
+        let tag = Tag::new("v1.2.3");
+
+        assert_eq!(
+            idempotency_key(Android, &tag),
+            idempotency_key(Android, &tag)
+        );
+        assert_ne!(
+            idempotency_key(Android, &tag),
+            idempotency_key(Desktop, &tag)
+        );
+        assert_ne!(
+            idempotency_key(Android, &tag),
+            idempotency_key(Android, &Tag::new("v1.2.4"))
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{anyhow, bail, Context};
+use serde_json::{json, Value};
+use worker::{Env, Fetch, Method, Url};
+
+use crate::{
+    feed::escape_xml,
+    hash,
+    log::{log_error, log_info},
+    platform::Platform,
+    types::github::Tag,
+    utils,
+};
+
+/// Content of the `m.room.message` event about the new version, with a plain `body`
+/// and an HTML `formatted_body`.
+pub fn notice_content(
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) -> Value {
+    let commits_word_suffix = if commits_count == 1 { "" } else { "s" };
+    let new_version = new_tag.exact_version_string();
+    let old_version = old_tag.exact_version_string();
+
+    let body = format!(
+        "Signal {platform} {new_version} is out — {commits_count} commit{commits_word_suffix} since {old_version}: {post_url}"
+    );
+    let formatted_body = format!(
+        "Signal {platform} <b>{}</b> is out — {commits_count} commit{commits_word_suffix} since {}: <a href=\"{}\">forum post</a>",
+        escape_xml(&new_version),
+        escape_xml(&old_version),
+        escape_xml(post_url)
+    );
+
+    json!({
+        "msgtype": "m.notice",
+        "body": body,
+        "format": "org.matrix.custom.html",
+        "formatted_body": formatted_body,
+    })
+}
+
+/// The same for every try to send the notice about `new_tag`, so that the homeserver
+/// does not send it twice.
+fn transaction_id(platform: Platform, new_tag: &Tag) -> String {
+    hash::sha256_string(&format!("{platform}/{}", new_tag.name))
+}
+
+/// URL that the notice is sent to with `PUT`, with `room_id` escaped as a path segment.
+fn send_url(homeserver_url: &str, room_id: &str, transaction_id: &str) -> anyhow::Result<Url> {
+    let mut url = Url::parse(homeserver_url).context("could not parse homeserver URL")?;
+
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("homeserver URL cannot be a base"))?
+        .pop_if_empty()
+        .extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            room_id,
+            "send",
+            "m.room.message",
+            transaction_id,
+        ]);
+
+    Ok(url)
+}
+
+/// Sends a notice about the new version to the configured Matrix room, if any.
+///
+/// Failures are only logged, as sending the notice is not essential.
+pub async fn notify(
+    env: &Env,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) {
+    let content = notice_content(platform, old_tag, new_tag, commits_count, post_url);
+
+    if let Err(e) = try_notify(env, &content, &transaction_id(platform, new_tag)).await {
+        log_error!(
+            platform = platform,
+            "could not send notice to Matrix: {e:?}"
+        );
+    }
+}
+
+async fn try_notify(env: &Env, content: &Value, transaction_id: &str) -> anyhow::Result<()> {
+    let (homeserver_url, room_id) = match (
+        utils::matrix_homeserver_url(env)?,
+        utils::matrix_room_id(env)?,
+    ) {
+        (Some(homeserver_url), Some(room_id)) => (homeserver_url, room_id),
+        _ => {
+            log_info!("no Matrix homeserver URL or room ID configured; not sending notice");
+            return Ok(());
+        }
+    };
+
+    let url = send_url(&homeserver_url, &room_id, transaction_id)?;
+    let access_token = utils::matrix_access_token(env)?;
+
+    let request = utils::RequestBuilder::new(url, Method::Put)
+        .header("Authorization", format!("Bearer {access_token}"))
+        .json(content)
+        .build()?;
+
+    let response = utils::fetch(Fetch::Request(request)).await?;
+
+    match response.status_code() {
+        200..=299 => {
+            log_info!("sent notice to Matrix");
+            Ok(())
+        }
+        status_code => bail!("Matrix responded with status code {status_code}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_ne, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    #[test_case(
+        Desktop, "v7.1.0-beta.1", "v7.1.0-beta.2", 12,
+        "Signal Desktop 7.1.0-beta.2 is out — 12 commits since 7.1.0-beta.1: https://community.signalusers.org/t/123/45",
+        r#"Signal Desktop <b>7.1.0-beta.2</b> is out — 12 commits since 7.1.0-beta.1: <a href="https://community.signalusers.org/t/123/45">forum post</a>"#;
+        "Desktop: several commits"
+    )]
+    #[test_case(
+        Android, "v7.1.3", "v7.1.4", 1,
+        "Signal Android 7.1.4 is out — 1 commit since 7.1.3: https://community.signalusers.org/t/123/45",
+        r#"Signal Android <b>7.1.4</b> is out — 1 commit since 7.1.3: <a href="https://community.signalusers.org/t/123/45">forum post</a>"#;
+        "Android: one commit"
+    )]
+    fn notice(
+        platform: Platform,
+        old_tag: &str,
+        new_tag: &str,
+        commits_count: usize,
+        body: &str,
+        formatted_body: &str,
+    ) {
+        let content = notice_content(
+            platform,
+            &Tag::new(old_tag),
+            &Tag::new(new_tag),
+            commits_count,
+            "https://community.signalusers.org/t/123/45",
+        );
+
+        assert_eq!(content["msgtype"], "m.notice");
+        assert_eq!(content["format"], "org.matrix.custom.html");
+        assert_str_eq!(content["body"].as_str().unwrap(), body);
+        assert_str_eq!(content["formatted_body"].as_str().unwrap(), formatted_body);
+    }
+
+    #[test]
+    fn formatted_body_escaped() {
+        let content = notice_content(
+            Ios,
+            &Tag::new("7.1.0.1-beta"),
+            &Tag::new("7.1.0.2-beta"),
+            3,
+            "https://community.signalusers.org/t/123/45?a=1&b=\"2\"",
+        );
+
+        assert!(content["formatted_body"]
+            .as_str()
+            .unwrap()
+            .ends_with(r#"<a href="https://community.signalusers.org/t/123/45?a=1&amp;b=&quot;2&quot;">forum post</a>"#));
+    }
+
+    #[test]
+    fn transaction_id_depends_on_platform_and_tag() {
+        let tag = Tag::new("v1.2.3");
+
+        assert_eq!(transaction_id(Android, &tag), transaction_id(Android, &tag));
+        assert_eq!(
+            transaction_id(Android, &tag),
+            hash::sha256_string("Android/v1.2.3")
+        );
+        assert_ne!(transaction_id(Android, &tag), transaction_id(Desktop, &tag));
+        assert_ne!(
+            transaction_id(Android, &tag),
+            transaction_id(Android, &Tag::new("v1.2.4"))
+        );
+    }
+
+    #[test_case("https://matrix.example.org"; "no trailing slash")]
+    #[test_case("https://matrix.example.org/"; "trailing slash")]
+    fn url(homeserver_url: &str) {
+        assert_str_eq!(
+            send_url(homeserver_url, "!ops:example.org", "abc123")
+                .unwrap()
+                .as_str(),
+            "https://matrix.example.org/_matrix/client/v3/rooms/!ops:example.org/send/m.room.message/abc123"
+        );
+    }
+
+    #[test]
+    fn room_id_escaped() {
+        assert_str_eq!(
+            send_url("https://matrix.example.org", "!ops/room?#", "abc123")
+                .unwrap()
+                .as_str(),
+            "https://matrix.example.org/_matrix/client/v3/rooms/!ops%2Froom%3F%23/send/m.room.message/abc123"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use worker::{
+    js_sys::{Array, Function, Object, Reflect},
+    wasm_bindgen::{JsCast, JsValue},
+    Env,
+};
+
+use crate::{invocation, log::log_error, platform::Platform};
+
+/// Binding of the Workers Analytics Engine dataset that data points are written to.
+const METRICS_BINDING: &str = "METRICS";
+
+/// What data points are grouped by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Dimensions {
+    pub platform: Option<Platform>,
+    pub outcome: Option<&'static str>,
+}
+
+impl Dimensions {
+    pub fn platform(platform: Platform) -> Self {
+        Self {
+            platform: Some(platform),
+            outcome: None,
+        }
+    }
+
+    pub fn outcome(self, outcome: &'static str) -> Self {
+        Self {
+            outcome: Some(outcome),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPoint {
+    pub name: &'static str,
+    pub value: f64,
+    pub dimensions: Dimensions,
+}
+
+/// Where data points are written to.
+pub trait Sink {
+    fn write(&self, data_point: &DataPoint);
+}
+
+/// Sink for an invocation of the worker: data points are written to the Analytics Engine
+/// dataset bound as [`METRICS_BINDING`], or dropped if there is none.
+pub fn sink(env: &Env) -> Option<Box<dyn Sink>> {
+    AnalyticsEngine::from_env(env).map(|sink| Box::new(sink) as Box<dyn Sink>)
+}
+
+/// Writes `data_point` to the sink of the current invocation, if any.
+fn write(data_point: DataPoint) {
+    invocation::with(|invocation| {
+        if let Some(sink) = invocation.and_then(|invocation| invocation.metrics.as_ref()) {
+            sink.write(&data_point);
+        }
+    });
+}
+
+/// Counts one occurrence of `name`.
+pub fn incr(name: &'static str, dimensions: Dimensions) {
+    write(DataPoint {
+        name,
+        value: 1.0,
+        dimensions,
+    });
+}
+
+/// Records that `name` took `milliseconds`.
+pub fn timing(name: &'static str, milliseconds: f64, dimensions: Dimensions) {
+    write(DataPoint {
+        name,
+        value: milliseconds,
+        dimensions,
+    });
+}
+
+/// Records `value` of `name`, e.g. a count for a whole run.
+pub fn gauge(name: &'static str, value: f64, dimensions: Dimensions) {
+    write(DataPoint {
+        name,
+        value,
+        dimensions,
+    });
+}
+
+struct AnalyticsEngine {
+    dataset: JsValue,
+    write_data_point: Function,
+}
+
+impl AnalyticsEngine {
+    fn from_env(env: &Env) -> Option<Self> {
+        let dataset = Reflect::get(env, &JsValue::from_str(METRICS_BINDING))
+            .ok()
+            .filter(|dataset| !dataset.is_undefined())?;
+
+        let write_data_point = Reflect::get(&dataset, &JsValue::from_str("writeDataPoint"))
+            .ok()?
+            .dyn_into::<Function>()
+            .ok()?;
+
+        Some(Self {
+            dataset,
+            write_data_point,
+        })
+    }
+}
+
+/// Blobs, double and index of `data_point` for Analytics Engine; blobs are the name
+/// and the dimensions, empty if not known.
+fn fields(data_point: &DataPoint) -> ([String; 3], f64, &'static str) {
+    let dimensions = data_point.dimensions;
+
+    (
+        [
+            data_point.name.to_string(),
+            dimensions
+                .platform
+                .map(|platform| platform.to_string())
+                .unwrap_or_default(),
+            dimensions.outcome.unwrap_or_default().to_string(),
+        ],
+        data_point.value,
+        data_point.name,
+    )
+}
+
+impl Sink for AnalyticsEngine {
+    fn write(&self, data_point: &DataPoint) {
+        let (blobs, double, index) = fields(data_point);
+
+        let object = Object::new();
+        let set =
+            |key: &str, value: JsValue| Reflect::set(&object, &JsValue::from_str(key), &value);
+
+        let result = set(
+            "blobs",
+            blobs
+                .iter()
+                .map(|blob| JsValue::from_str(blob))
+                .collect::<Array>()
+                .into(),
+        )
+        .and_then(|_| set("doubles", Array::of1(&JsValue::from_f64(double)).into()))
+        .and_then(|_| set("indexes", Array::of1(&JsValue::from_str(index)).into()))
+        .and_then(|_| self.write_data_point.call1(&self.dataset, &object));
+
+        if let Err(e) = result {
+            log_error!("could not write data point {}: {e:?}", data_point.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{core::poll_once, invocation::Invocation, platform::Platform::*};
+
+    /// Keeps data points in memory, shared with the test.
+    struct MemorySink(Rc<RefCell<Vec<DataPoint>>>);
+
+    impl Sink for MemorySink {
+        fn write(&self, data_point: &DataPoint) {
+            self.0.borrow_mut().push(data_point.clone());
+        }
+    }
+
+    /// An invocation that keeps its data points in memory, and the data points.
+    fn memory_sink() -> (Invocation, Rc<RefCell<Vec<DataPoint>>>) {
+        let data_points = Rc::new(RefCell::new(Vec::new()));
+        let invocation = Invocation {
+            metrics: Some(Box::new(MemorySink(data_points.clone()))),
+            ..Default::default()
+        };
+
+        (invocation, data_points)
+    }
+
+    #[test]
+    fn data_points_are_written_to_sink() {
+        let (invocation, data_points) = memory_sink();
+
+        poll_once(invocation::scope(invocation, async {
+            incr("github_requests", Dimensions::default());
+            incr("posts", Dimensions::platform(Ios).outcome("success"));
+            timing("run_ms", 1234.5, Dimensions::default().outcome("failure"));
+        }));
+
+        assert_eq!(
+            *data_points.borrow(),
+            vec![
+                DataPoint {
+                    name: "github_requests",
+                    value: 1.0,
+                    dimensions: Dimensions::default(),
+                },
+                DataPoint {
+                    name: "posts",
+                    value: 1.0,
+                    dimensions: Dimensions {
+                        platform: Some(Ios),
+                        outcome: Some("success"),
+                    },
+                },
+                DataPoint {
+                    name: "run_ms",
+                    value: 1234.5,
+                    dimensions: Dimensions {
+                        platform: None,
+                        outcome: Some("failure"),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn data_points_are_dropped_without_sink() {
+        let (invocation, data_points) = memory_sink();
+
+        poll_once(invocation::scope(invocation, async {}));
+        poll_once(invocation::scope(Invocation::default(), async {
+            incr("github_requests", Dimensions::default());
+        }));
+        incr("github_requests", Dimensions::default());
+
+        assert_eq!(*data_points.borrow(), Vec::new());
+    }
+
+    #[test]
+    fn analytics_engine_fields() {
+        let data_point = DataPoint {
+            name: "posts",
+            value: 1.0,
+            dimensions: Dimensions::platform(Desktop).outcome("failure"),
+        };
+
+        assert_eq!(
+            fields(&data_point),
+            (
+                [
+                    String::from("posts"),
+                    String::from("Desktop"),
+                    String::from("failure")
+                ],
+                1.0,
+                "posts"
+            )
+        );
+    }
+
+    #[test]
+    fn analytics_engine_fields_without_dimensions() {
+        let data_point = DataPoint {
+            name: "run_ms",
+            value: 850.0,
+            dimensions: Dimensions::default(),
+        };
+
+        assert_eq!(
+            fields(&data_point).0,
+            [String::from("run_ms"), String::new(), String::new()]
+        );
+    }
+}
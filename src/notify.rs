@@ -0,0 +1,124 @@
+use anyhow::{bail, Context};
+use serde::Serialize;
+use worker::{Env, Fetch, Method, Url};
+
+use crate::{
+    hash,
+    log::{log_error, log_info},
+    platform::Platform,
+    types::github::Tag,
+    utils,
+};
+
+const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// Payload of the webhook notification sent after each successful post.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Notification {
+    pub platform: String,
+    pub old_tag: String,
+    pub new_tag: String,
+    pub commits_count: usize,
+    pub post_url: String,
+    pub localization_changes_count: usize,
+}
+
+impl Notification {
+    pub fn new(
+        platform: Platform,
+        old_tag: &Tag,
+        new_tag: &Tag,
+        commits_count: usize,
+        post_url: String,
+        localization_changes_count: usize,
+    ) -> Self {
+        Self {
+            platform: platform.to_string(),
+            old_tag: old_tag.name.clone(),
+            new_tag: new_tag.name.clone(),
+            commits_count,
+            post_url,
+            localization_changes_count,
+        }
+    }
+
+    fn body(&self) -> anyhow::Result<String> {
+        serde_json::to_string(self).context("could not serialize notification")
+    }
+}
+
+fn signature_header_value(secret: &str, body: &str) -> String {
+    format!("sha256={}", hash::hmac_sha256_string(secret, body))
+}
+
+/// Sends `notification` to the configured webhook, if any.
+///
+/// Failures are only logged, as delivering the notification is not essential.
+pub async fn send(env: &Env, notification: &Notification) {
+    if let Err(e) = try_send(env, notification).await {
+        log_error!("could not deliver webhook notification: {e:?}");
+    }
+}
+
+async fn try_send(env: &Env, notification: &Notification) -> anyhow::Result<()> {
+    let url = match utils::notify_webhook_url(env)? {
+        Some(url) => url,
+        None => {
+            log_info!("no webhook URL configured; not notifying");
+            return Ok(());
+        }
+    };
+
+    let body = notification.body()?;
+    let signature = signature_header_value(&utils::notify_webhook_secret(env)?, &body);
+
+    let url = Url::parse(&url).context("could not parse URL")?;
+    let request = utils::RequestBuilder::new(url, Method::Post)
+        .text(body)
+        .header("Content-Type", "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .build()?;
+
+    let response = utils::fetch(Fetch::Request(request)).await?;
+
+    match response.status_code() {
+        200..=299 => {
+            log_info!("delivered webhook notification");
+            Ok(())
+        }
+        status_code => bail!("webhook responded with status code {status_code}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_str_eq;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    #[test]
+    fn body() {
+        let notification = Notification::new(
+            Android,
+            &Tag::new("v1.2.3"),
+            &Tag::new("v1.2.4"),
+            2,
+            utils::discourse_post_url(123, 45),
+            3,
+        );
+
+        assert_str_eq!(
+            notification.body().unwrap(),
+            r#"{"platform":"Android","old_tag":"v1.2.3","new_tag":"v1.2.4","commits_count":2,"post_url":"https://community.signalusers.org/t/123/45","localization_changes_count":3}"#
+        );
+    }
+
+    #[test]
+    fn signature() {
+        assert_str_eq!(
+            signature_header_value("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
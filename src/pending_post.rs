@@ -0,0 +1,252 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use worker::Env;
+use worker_kv::KvStore;
+
+use crate::{
+    log::{log_error, log_info, log_warn},
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+    types::github::Tag,
+    PreparedPost,
+};
+
+const PENDING_POST_KV_KEY_PREFIX: &str = "pending";
+
+/// A rendered post that is about to be made, kept until it is, so that a failed
+/// attempt is retried with exactly the same content.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PendingPost {
+    pub old_tag: Tag,
+    pub new_tag: Tag,
+    pub topic_id: u64,
+    pub reply_to_post_number: Option<u64>,
+    pub prepared_post: PreparedPost,
+    /// ISO 8601 timestamp of when the post was rendered.
+    pub created_at: String,
+}
+
+/// What to do with the pending post of a platform.
+#[derive(Debug, PartialEq)]
+enum Action {
+    Retry(PendingPost),
+    /// Dropped for the given reason, as its content may be out of date.
+    Drop(PendingPost, &'static str),
+}
+
+fn kv_key(platform: Platform) -> String {
+    format!(
+        "{PENDING_POST_KV_KEY_PREFIX}:{}",
+        platform.to_string().to_ascii_lowercase()
+    )
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Decides what to do with `pending`, given `last_posted_tag` from the state and
+/// `earliest_created_at`, the ISO 8601 timestamp before which pending posts are expired.
+fn action(pending: PendingPost, last_posted_tag: &Tag, earliest_created_at: &str) -> Action {
+    if pending.old_tag.name != last_posted_tag.name {
+        Action::Drop(pending, "the state has moved on since it was rendered")
+    } else if pending.created_at.as_str() < earliest_created_at {
+        Action::Drop(pending, "it expired")
+    } else {
+        Action::Retry(pending)
+    }
+}
+
+/// Returns the pending post of `platform` if it should be retried, i.e. if it follows
+/// `last_posted_tag` and was created after `earliest_created_at`; other pending posts are deleted.
+///
+/// Failures are only logged, as new tags are looked for anyway.
+pub async fn retryable(
+    env: &Env,
+    platform: Platform,
+    last_posted_tag: &Tag,
+    earliest_created_at: &str,
+) -> Option<PendingPost> {
+    let pending = match try_get(env, platform).await {
+        Ok(pending) => pending?,
+        Err(e) => {
+            log_error!(platform = platform, "could not get pending post: {e:?}");
+            return None;
+        }
+    };
+
+    match action(pending, last_posted_tag, earliest_created_at) {
+        Action::Retry(pending) => Some(pending),
+        Action::Drop(pending, reason) => {
+            log_warn!(
+                platform = platform,
+                "dropping pending post of {} for {platform} created at {}, as {reason}",
+                pending.new_tag.name,
+                pending.created_at
+            );
+            delete(env, platform).await;
+            None
+        }
+    }
+}
+
+async fn try_get(env: &Env, platform: Platform) -> anyhow::Result<Option<PendingPost>> {
+    let key = state::kv_key(env, &kv_key(platform));
+
+    kv_store(env)?
+        .get(&key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))
+}
+
+/// Saves `pending` as the pending post of `platform`, replacing any previous one.
+///
+/// Failures are only logged, so that they do not prevent posting.
+pub async fn save(env: &Env, platform: Platform, pending: &PendingPost) {
+    match try_save(env, platform, pending).await {
+        Ok(_) => log_info!(
+            platform = platform,
+            "saved pending post of {} for {platform}",
+            pending.new_tag.name
+        ),
+        Err(e) => log_error!(platform = platform, "could not save pending post: {e:?}"),
+    }
+}
+
+async fn try_save(env: &Env, platform: Platform, pending: &PendingPost) -> anyhow::Result<()> {
+    kv_store(env)?
+        .put(&state::kv_key(env, &kv_key(platform)), pending)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+/// Deletes the pending post of `platform`, once it is posted.
+///
+/// Failures are only logged; a pending post left behind is dropped by the next run,
+/// as the state has moved on by then.
+pub async fn delete(env: &Env, platform: Platform) {
+    let result = match kv_store(env) {
+        Ok(kv_store) => kv_store
+            .delete(&state::kv_key(env, &kv_key(platform)))
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not delete from KV"),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(_) => log_info!(platform = platform, "deleted pending post for {platform}"),
+        Err(e) => log_error!(platform = platform, "could not delete pending post: {e:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{
+        localization::{Completeness, UnsortedChanges},
+        platform::Platform::*,
+    };
+
+    fn pending(created_at: &str) -> PendingPost {
+        PendingPost {
+            old_tag: Tag::new("v1.2.3"),
+            new_tag: Tag::new("v1.2.4"),
+            topic_id: 123,
+            reply_to_post_number: Some(45),
+            prepared_post: PreparedPost {
+                markdown_text: String::from("New version: 1.2.4"),
+                commits_len: 3,
+                build_localization_changes_len: 0,
+                last_posted_tag_previous_release: Tag::new("v1.1.0"),
+                localization_changes_completeness: Completeness::Complete,
+                localization_changes_completeness_rationale: None,
+                localization_changes: UnsortedChanges::default(),
+                localization_changes_cycle: Some(String::from("1.2")),
+                posted_commits: Vec::new(),
+                same_code: false,
+            },
+            created_at: created_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn key() {
+        assert_eq!(kv_key(Ios), "pending:ios");
+    }
+
+    #[test]
+    fn round_trip() {
+        let pending = pending("2022-07-01T00:00:00.000Z");
+        let json = serde_json::to_string(&pending).unwrap();
+
+        assert_eq!(serde_json::from_str::<PendingPost>(&json).unwrap(), pending);
+    }
+
+    #[test]
+    fn without_completeness_rationale() {
+        let pending = pending("2022-07-01T00:00:00.000Z");
+
+        let mut value = serde_json::to_value(&pending).unwrap();
+        value["prepared_post"]
+            .as_object_mut()
+            .unwrap()
+            .remove("localization_changes_completeness_rationale");
+
+        assert_eq!(
+            serde_json::from_value::<PendingPost>(value).unwrap(),
+            pending
+        );
+    }
+
+    #[test_case("2022-07-01T12:00:00.000Z"; "recent")]
+    #[test_case("2022-07-01T00:00:00.000Z"; "just before expiring")]
+    fn retried(created_at: &str) {
+        assert_eq!(
+            action(
+                pending(created_at),
+                &Tag::new("v1.2.3"),
+                "2022-07-01T00:00:00.000Z"
+            ),
+            Action::Retry(pending(created_at))
+        );
+    }
+
+    #[test]
+    fn expired() {
+        assert_eq!(
+            action(
+                pending("2022-06-30T23:59:59.999Z"),
+                &Tag::new("v1.2.3"),
+                "2022-07-01T00:00:00.000Z"
+            ),
+            Action::Drop(pending("2022-06-30T23:59:59.999Z"), "it expired")
+        );
+    }
+
+    #[test]
+    fn stale() {
+        assert_eq!(
+            action(
+                pending("2022-07-01T12:00:00.000Z"),
+                &Tag::new("v1.2.4"),
+                "2022-07-01T00:00:00.000Z"
+            ),
+            Action::Drop(
+                pending("2022-07-01T12:00:00.000Z"),
+                "the state has moved on since it was rendered"
+            )
+        );
+    }
+}
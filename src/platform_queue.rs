@@ -0,0 +1,386 @@
+use anyhow::{anyhow, bail, Context};
+use serde::{Deserialize, Serialize};
+use worker::{
+    js_sys::{Array, Function, Promise, Reflect},
+    wasm_bindgen::{JsCast, JsValue},
+    wasm_bindgen_futures::JsFuture,
+    Env,
+};
+use worker_kv::KvStore;
+
+use crate::{
+    log::{log_error, log_info, log_warn},
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+    utils, PlatformCheckOutcome,
+};
+
+/// Binding of the queue that platforms are checked through; without it, the scheduled
+/// handler checks them itself.
+const QUEUE_BINDING: &str = "PLATFORM_QUEUE";
+
+/// Version of [`CheckMessage`], bumped with changes that older consumers could misread.
+pub const MESSAGE_SCHEMA_VERSION: u64 = 1;
+
+const PROCESSED_KV_KEY_PREFIX: &str = "queue";
+
+/// Processed messages are remembered for much longer than they may be redelivered.
+const PROCESSED_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+/// Asks for a check of one platform, enqueued by a scheduled run.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CheckMessage {
+    pub schema_version: u64,
+    /// The same for redeliveries of the message, e.g. `android-1656720000000`.
+    pub id: String,
+    /// Name of the platform, as displayed (see [`Platform::from_name`]).
+    pub platform: String,
+    /// ISO 8601 timestamp of the scheduled run that enqueued the message.
+    pub scheduled_at: String,
+}
+
+impl CheckMessage {
+    /// Message for `platform` from the scheduled run at `scheduled_time`, in milliseconds
+    /// since the UNIX epoch.
+    pub fn new(platform: Platform, scheduled_time: u64, scheduled_at: String) -> Self {
+        Self {
+            schema_version: MESSAGE_SCHEMA_VERSION,
+            id: format!(
+                "{}-{scheduled_time}",
+                platform.to_string().to_ascii_lowercase()
+            ),
+            platform: platform.to_string(),
+            scheduled_at,
+        }
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        serde_json::to_string(self).context("could not serialize queue message")
+    }
+
+    /// Parses the body of a message, which must be of [`MESSAGE_SCHEMA_VERSION`].
+    pub fn from_json(body: &str) -> anyhow::Result<Self> {
+        let message: Self = serde_json::from_str(body)
+            .with_context(|| format!("could not parse queue message, body = {body:?}"))?;
+
+        if message.schema_version != MESSAGE_SCHEMA_VERSION {
+            bail!(
+                "queue message {} has schema_version = {}, expected {MESSAGE_SCHEMA_VERSION}",
+                message.id,
+                message.schema_version
+            );
+        }
+
+        Ok(message)
+    }
+
+    pub fn platform(&self) -> anyhow::Result<Platform> {
+        Platform::from_name(&self.platform).ok_or_else(|| {
+            anyhow!(
+                "queue message {} has unknown platform = {:?}",
+                self.id,
+                self.platform
+            )
+        })
+    }
+}
+
+/// What is done with a message once it was handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposition {
+    /// Done with, it is not delivered again.
+    Ack,
+    /// Delivered again later, with the backoff of the queue.
+    Retry,
+}
+
+/// A message is only retried if the check failed in a way that trying again later may fix,
+/// see [`utils::is_retriable`]; other failures are already reported by the check, and
+/// skipped checks are done again by the next scheduled run anyway.
+pub fn disposition(result: &anyhow::Result<PlatformCheckOutcome>) -> Disposition {
+    match result {
+        Err(e) if utils::is_retriable(e) => Disposition::Retry,
+        Ok(_) | Err(_) => Disposition::Ack,
+    }
+}
+
+/// The queue bound as [`QUEUE_BINDING`].
+pub struct Queue {
+    queue: JsValue,
+    send: Function,
+}
+
+impl Queue {
+    /// Returns `None` if there is no queue binding, i.e. platforms are checked directly.
+    pub fn from_env(env: &Env) -> Option<Self> {
+        let queue = Reflect::get(env, &JsValue::from_str(QUEUE_BINDING))
+            .ok()
+            .filter(|queue| !queue.is_undefined())?;
+
+        let send = Reflect::get(&queue, &JsValue::from_str("send"))
+            .ok()?
+            .dyn_into::<Function>()
+            .ok()?;
+
+        Some(Self { queue, send })
+    }
+
+    async fn send(&self, message: &CheckMessage) -> anyhow::Result<()> {
+        let body = JsValue::from_str(&message.to_json()?);
+
+        let promise = self
+            .send
+            .call1(&self.queue, &body)
+            .map_err(|e| anyhow!("{e:?}"))
+            .context("could not send to queue")?;
+
+        JsFuture::from(Promise::from(promise))
+            .await
+            .map_err(|e| anyhow!("{e:?}"))
+            .with_context(|| format!("could not send queue message {}", message.id))?;
+
+        Ok(())
+    }
+}
+
+/// Enqueues a check of each of `platforms` for the scheduled run at `scheduled_time`,
+/// in milliseconds since the UNIX epoch.
+pub async fn enqueue(
+    queue: &Queue,
+    platforms: &[Platform],
+    scheduled_time: u64,
+) -> anyhow::Result<()> {
+    let scheduled_at: String = worker::js_sys::Date::new(&(scheduled_time as f64).into())
+        .to_iso_string()
+        .into();
+
+    for &platform in platforms {
+        let message = CheckMessage::new(platform, scheduled_time, scheduled_at.clone());
+        queue.send(&message).await?;
+
+        log_info!(platform = platform, "enqueued check as {}", message.id);
+    }
+
+    Ok(())
+}
+
+/// A message of a batch delivered to the queue handler.
+pub struct ReceivedMessage {
+    message: JsValue,
+    pub body: String,
+}
+
+impl ReceivedMessage {
+    /// Tells the queue what to do with the message.
+    pub fn settle(&self, disposition: Disposition) {
+        let method = match disposition {
+            Disposition::Ack => "ack",
+            Disposition::Retry => "retry",
+        };
+
+        let result = Reflect::get(&self.message, &JsValue::from_str(method))
+            .and_then(|function| function.dyn_into::<Function>())
+            .and_then(|function| function.call0(&self.message));
+
+        if let Err(e) = result {
+            log_error!("could not {method} queue message: {e:?}");
+        }
+    }
+}
+
+/// The messages of `batch`; messages whose body is not a string are dropped.
+pub fn messages(batch: &JsValue) -> Vec<ReceivedMessage> {
+    let messages = Reflect::get(batch, &JsValue::from_str("messages"))
+        .ok()
+        .and_then(|messages| messages.dyn_into::<Array>().ok())
+        .unwrap_or_default();
+
+    messages
+        .iter()
+        .filter_map(|message| {
+            match Reflect::get(&message, &JsValue::from_str("body"))
+                .ok()
+                .and_then(|body| body.as_string())
+            {
+                Some(body) => Some(ReceivedMessage { message, body }),
+                None => {
+                    log_warn!("dropping queue message without a string body");
+                    ReceivedMessage {
+                        message,
+                        body: String::new(),
+                    }
+                    .settle(Disposition::Ack);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn kv_key(id: &str) -> String {
+    format!("{PROCESSED_KV_KEY_PREFIX}:{id}")
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Whether the message with `id` was already handled, so that a redelivery is not checked again.
+///
+/// Failures are only logged, as the check does not post twice for the same tag anyway.
+pub async fn was_processed(env: &Env, id: &str) -> bool {
+    let key = state::kv_key(env, &kv_key(id));
+
+    let result = match kv_store(env) {
+        Ok(kv_store) => kv_store
+            .get(&key)
+            .text()
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .with_context(|| format!("could not get value for key {key}")),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(value) => value.is_some(),
+        Err(e) => {
+            log_error!("could not check if queue message {id} was processed: {e:?}");
+            false
+        }
+    }
+}
+
+/// Remembers that the message with `id` was handled; failures are only logged.
+pub async fn mark_processed(env: &Env, id: &str) {
+    if let Err(e) = try_mark_processed(env, id).await {
+        log_error!("could not mark queue message {id} as processed: {e:?}");
+    }
+}
+
+async fn try_mark_processed(env: &Env, id: &str) -> anyhow::Result<()> {
+    kv_store(env)?
+        .put(&state::kv_key(env, &kv_key(id)), utils::now_iso_string())
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .expiration_ttl(PROCESSED_TTL_SECONDS)
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{core::error::Error, platform::Platform::*, PlatformCheckOutcome::*};
+
+    fn message() -> CheckMessage {
+        CheckMessage::new(
+            Android,
+            1656720000000,
+            String::from("2022-07-02T00:00:00.000Z"),
+        )
+    }
+
+    #[test]
+    fn new_message() {
+        assert_eq!(
+            message(),
+            CheckMessage {
+                schema_version: 1,
+                id: String::from("android-1656720000000"),
+                platform: String::from("Android"),
+                scheduled_at: String::from("2022-07-02T00:00:00.000Z"),
+            }
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = message().to_json().unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"schema_version":1,"id":"android-1656720000000","platform":"Android","scheduled_at":"2022-07-02T00:00:00.000Z"}"#
+        );
+        assert_eq!(CheckMessage::from_json(&json).unwrap(), message());
+        assert_eq!(
+            CheckMessage::from_json(&json).unwrap().platform().unwrap(),
+            Android
+        );
+    }
+
+    #[test_case(Android; "android")]
+    #[test_case(Ios; "ios")]
+    #[test_case(Desktop; "desktop")]
+    fn platform_round_trip(platform: Platform) {
+        let json = CheckMessage::new(platform, 0, String::new())
+            .to_json()
+            .unwrap();
+
+        assert_eq!(
+            CheckMessage::from_json(&json).unwrap().platform().unwrap(),
+            platform
+        );
+    }
+
+    #[test]
+    fn newer_schema_version() {
+        let json = r#"{"schema_version":2,"id":"android-1656720000000","platform":"Android","scheduled_at":"2022-07-02T00:00:00.000Z"}"#;
+
+        assert_eq!(
+            CheckMessage::from_json(json).unwrap_err().to_string(),
+            "queue message android-1656720000000 has schema_version = 2, expected 1"
+        );
+    }
+
+    #[test]
+    fn unknown_platform() {
+        let message = CheckMessage {
+            platform: String::from("Watch"),
+            ..message()
+        };
+
+        assert_eq!(
+            message.platform().unwrap_err().to_string(),
+            r#"queue message android-1656720000000 has unknown platform = "Watch""#
+        );
+    }
+
+    #[test]
+    fn not_a_message() {
+        assert!(CheckMessage::from_json("android").is_err());
+    }
+
+    #[test]
+    fn redelivery_has_same_key() {
+        assert_eq!(kv_key(&message().id), kv_key(&message().id));
+        assert_eq!(kv_key(&message().id), "queue:android-1656720000000");
+    }
+
+    #[test]
+    fn dispositions() {
+        assert_eq!(disposition(&Ok(PostedCommits)), Disposition::Ack);
+        assert_eq!(disposition(&Ok(RateLimited)), Disposition::Ack);
+        assert_eq!(
+            disposition(&Err(Error::TransientNetwork {
+                message: String::from("connection reset")
+            }
+            .into())),
+            Disposition::Retry
+        );
+        assert_eq!(
+            disposition(&Err(Error::PostTooLong.into())),
+            Disposition::Ack
+        );
+        assert_eq!(
+            disposition(&Err(anyhow!("could not parse URL"))),
+            Disposition::Ack
+        );
+    }
+}
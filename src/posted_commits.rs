@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use worker::Env;
+use worker_kv::KvStore;
+
+use crate::{
+    log::{log_error, log_info},
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+};
+
+const POSTED_COMMITS_KV_KEY_PREFIX: &str = "posted-commits";
+
+/// At most this many commits are kept for each platform.
+pub const MAX_COMMITS: usize = 500;
+
+/// A commit listed in a post.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PostedCommit {
+    pub sha: String,
+    pub subject: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    #[serde(flatten)]
+    commit: PostedCommit,
+    /// Version of the post that listed the commit.
+    version: String,
+}
+
+/// Recently posted commits of a platform, newest first, indexed by SHA and by subject.
+#[derive(Debug, Default, PartialEq)]
+pub struct PostedCommits {
+    entries: Vec<Entry>,
+    /// Index of the newest entry with each SHA.
+    by_sha: HashMap<String, usize>,
+    /// Index of the newest entry with each subject.
+    by_subject: HashMap<String, usize>,
+}
+
+impl PostedCommits {
+    fn from_entries(entries: Vec<Entry>) -> Self {
+        let mut posted_commits = Self {
+            entries,
+            ..Default::default()
+        };
+
+        posted_commits.reindex();
+        posted_commits
+    }
+
+    fn reindex(&mut self) {
+        self.by_sha.clear();
+        self.by_subject.clear();
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            self.by_sha.entry(entry.commit.sha.clone()).or_insert(index);
+            self.by_subject
+                .entry(entry.commit.subject.clone())
+                .or_insert(index);
+        }
+    }
+
+    /// Adds `commits` as posted for `version`, dropping the oldest commits beyond [`MAX_COMMITS`].
+    pub fn insert(&mut self, version: &str, commits: &[PostedCommit]) {
+        let new_entries = commits.iter().rev().map(|commit| Entry {
+            commit: commit.clone(),
+            version: version.to_string(),
+        });
+
+        self.entries.splice(0..0, new_entries);
+        self.entries.truncate(MAX_COMMITS);
+        self.reindex();
+    }
+
+    /// Returns the newest version other than `version` that listed the commit with `sha`,
+    /// or else (if `match_subject`) one with `subject`.
+    pub fn version_of(
+        &self,
+        sha: &str,
+        subject: &str,
+        match_subject: bool,
+        version: &str,
+    ) -> Option<&str> {
+        let by_subject = match_subject
+            .then(|| self.by_subject.get(subject))
+            .flatten();
+
+        self.by_sha
+            .get(sha)
+            .into_iter()
+            .chain(by_subject)
+            .map(|&index| self.entries[index].version.as_str())
+            .find(|&posted_version| posted_version != version)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+fn kv_key(platform: Platform) -> String {
+    format!(
+        "{POSTED_COMMITS_KV_KEY_PREFIX}:{}",
+        platform.to_string().to_ascii_lowercase()
+    )
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Returns the recently posted commits of `platform`.
+///
+/// Failures are only logged, as the commits are only used to mark duplicates.
+pub async fn get(env: &Env, platform: Platform) -> PostedCommits {
+    match try_get(env, platform).await {
+        Ok(posted_commits) => posted_commits,
+        Err(e) => {
+            log_error!(platform = platform, "could not get posted commits: {e:?}");
+            PostedCommits::default()
+        }
+    }
+}
+
+async fn try_get(env: &Env, platform: Platform) -> anyhow::Result<PostedCommits> {
+    let key = state::kv_key(env, &kv_key(platform));
+
+    let entries: Option<Vec<Entry>> = kv_store(env)?
+        .get(&key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))?;
+
+    Ok(PostedCommits::from_entries(entries.unwrap_or_default()))
+}
+
+/// Adds `commits` as posted for `version` to the posted commits of `platform`.
+///
+/// Failures are only logged, as the post was already made.
+pub async fn record(env: &Env, platform: Platform, version: &str, commits: &[PostedCommit]) {
+    if let Err(e) = try_record(env, platform, version, commits).await {
+        log_error!(
+            platform = platform,
+            "could not record posted commits: {e:?}"
+        );
+    }
+}
+
+async fn try_record(
+    env: &Env,
+    platform: Platform,
+    version: &str,
+    commits: &[PostedCommit],
+) -> anyhow::Result<()> {
+    let mut posted_commits = try_get(env, platform).await?;
+    posted_commits.insert(version, commits);
+
+    kv_store(env)?
+        .put(
+            &state::kv_key(env, &kv_key(platform)),
+            &posted_commits.entries,
+        )
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")?;
+
+    log_info!(
+        platform = platform,
+        "recorded {} posted commits for {platform}, {} in total",
+        commits.len(),
+        posted_commits.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    fn commit(sha: &str, subject: &str) -> PostedCommit {
+        PostedCommit {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+        }
+    }
+
+    fn posted_commits() -> PostedCommits {
+        let mut posted_commits = PostedCommits::default();
+        posted_commits.insert(
+            "1.2.3",
+            &[commit("aaa", "Fix crash."), commit("bbb", "Add feature.")],
+        );
+        posted_commits.insert(
+            "1.2.4",
+            &[commit("ccc", "Fix crash."), commit("ddd", "Other.")],
+        );
+        posted_commits
+    }
+
+    #[test]
+    fn key() {
+        assert_eq!(kv_key(Desktop), "posted-commits:desktop");
+    }
+
+    #[test_case("bbb", "Changed subject.", true, "1.3.0", Some("1.2.3"); "same SHA")]
+    #[test_case("eee", "Add feature.", true, "1.3.0", Some("1.2.3"); "same subject")]
+    #[test_case("eee", "Add feature.", false, "1.3.0", None; "same subject, not matched")]
+    #[test_case("eee", "Fix crash.", true, "1.3.0", Some("1.2.4"); "newest version of subject")]
+    #[test_case("aaa", "Fix crash.", true, "1.3.0", Some("1.2.3"); "SHA before subject")]
+    #[test_case("ddd", "Other.", true, "1.2.4", None; "same version")]
+    #[test_case("ccc", "Fix crash.", true, "1.2.4", None; "same version, subject also in older one")]
+    #[test_case("eee", "New.", true, "1.3.0", None; "new")]
+    fn version_of(
+        sha: &str,
+        subject: &str,
+        match_subject: bool,
+        version: &str,
+        result: Option<&str>,
+    ) {
+        assert_eq!(
+            posted_commits().version_of(sha, subject, match_subject, version),
+            result
+        );
+    }
+
+    #[test]
+    fn insert_keeps_newest() {
+        let mut posted_commits = PostedCommits::default();
+
+        let old_commits: Vec<_> = (0..MAX_COMMITS)
+            .map(|i| commit(&format!("old{i}"), &format!("Old {i}.")))
+            .collect();
+        posted_commits.insert("1.0.0", &old_commits);
+
+        posted_commits.insert("1.1.0", &[commit("new0", "New."), commit("new1", "Newer.")]);
+
+        assert_eq!(posted_commits.len(), MAX_COMMITS);
+        assert_eq!(posted_commits.entries[0].commit, commit("new1", "Newer."));
+        assert_eq!(
+            posted_commits.version_of("new0", "", false, ""),
+            Some("1.1.0")
+        );
+        assert_eq!(
+            posted_commits.version_of("old2", "", false, ""),
+            Some("1.0.0")
+        );
+        assert_eq!(posted_commits.version_of("old1", "", false, ""), None);
+        assert_eq!(posted_commits.version_of("", "Old 0.", true, ""), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let posted_commits = posted_commits();
+        let json = serde_json::to_string(&posted_commits.entries).unwrap();
+
+        assert_eq!(
+            PostedCommits::from_entries(serde_json::from_str(&json).unwrap()),
+            posted_commits
+        );
+    }
+
+    #[test]
+    fn entry_json() {
+        assert_eq!(
+            serde_json::to_string(&posted_commits().entries[0]).unwrap(),
+            r#"{"sha":"ddd","subject":"Other.","version":"1.2.4"}"#
+        );
+    }
+}
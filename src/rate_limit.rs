@@ -0,0 +1,570 @@
+use std::{error::Error, fmt};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use worker::{js_sys, Env, Response};
+use worker_kv::KvStore;
+
+use crate::{
+    core::error,
+    http::HttpResponse,
+    invocation,
+    log::{log_error, log_info, log_warn},
+    metrics,
+    state::{self, STATE_KV_BINDING},
+};
+
+/// Lowest headroom of the last run that got any from GitHub.
+const HEADROOM_KV_KEY: &str = "github_rate_limit";
+
+/// GitHub refused a request because a rate limit was exceeded.
+///
+/// https://docs.github.com/en/rest/overview/resources-in-the-rest-api#rate-limiting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitExceeded {
+    /// The hourly limit, `resets_in_seconds` is until the limit is reset.
+    Primary { resets_in_seconds: Option<u64> },
+    /// A limit for too many requests in a short time, e.g. concurrent ones.
+    Secondary { retry_after_seconds: u64 },
+}
+
+impl fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Primary {
+                resets_in_seconds: Some(seconds),
+            } => write!(
+                f,
+                "GitHub rate limit exceeded, resets in {}",
+                human_duration(*seconds)
+            ),
+            Self::Primary {
+                resets_in_seconds: None,
+            } => write!(f, "GitHub rate limit exceeded"),
+            Self::Secondary {
+                retry_after_seconds,
+            } => write!(
+                f,
+                "GitHub secondary rate limit exceeded, retry after {}",
+                human_duration(*retry_after_seconds)
+            ),
+        }
+    }
+}
+
+impl Error for RateLimitExceeded {}
+
+impl From<RateLimitExceeded> for error::Error {
+    fn from(exceeded: RateLimitExceeded) -> Self {
+        let resets_in_seconds = match exceeded {
+            RateLimitExceeded::Primary { resets_in_seconds } => resets_in_seconds,
+            RateLimitExceeded::Secondary {
+                retry_after_seconds,
+            } => Some(retry_after_seconds),
+        };
+
+        Self::GitHubRateLimited { resets_in_seconds }
+    }
+}
+
+pub fn human_duration(seconds: u64) -> String {
+    let parts = [
+        (seconds / 3600, "hour"),
+        (seconds % 3600 / 60, "minute"),
+        (seconds % 60, "second"),
+    ];
+
+    let parts: Vec<String> = parts
+        .iter()
+        .filter(|(count, _)| *count != 0)
+        .map(|(count, unit)| {
+            let suffix = if *count == 1 { "" } else { "s" };
+            format!("{count} {unit}{suffix}")
+        })
+        .collect();
+
+    if parts.is_empty() {
+        String::from("0 seconds")
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// Returns the exceeded rate limit, if the response has status `status_code` and
+/// the given rate limit headers.
+///
+/// `now` and `reset` are UNIX timestamps in seconds.
+fn rate_limit_exceeded(
+    status_code: u16,
+    remaining: Option<&str>,
+    reset: Option<&str>,
+    retry_after: Option<&str>,
+    now: u64,
+) -> Option<RateLimitExceeded> {
+    if status_code != 403 && status_code != 429 {
+        return None;
+    }
+
+    if let Some(retry_after_seconds) = retry_after.and_then(|value| value.trim().parse().ok()) {
+        return Some(RateLimitExceeded::Secondary {
+            retry_after_seconds,
+        });
+    }
+
+    if remaining.map(str::trim) == Some("0") {
+        let resets_in_seconds = reset
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(|reset| reset.saturating_sub(now));
+
+        return Some(RateLimitExceeded::Primary { resets_in_seconds });
+    }
+
+    None
+}
+
+/// Fails with [`RateLimitExceeded`] if `response` indicates that a GitHub rate limit was exceeded.
+pub fn check(response: &Response) -> anyhow::Result<()> {
+    let headers = response.headers();
+
+    check_headers(response.status_code(), |name| {
+        headers
+            .get(name)
+            .map_err(|e| anyhow!(e.to_string()))
+            .with_context(|| format!("could not get {name} header"))
+    })
+}
+
+/// Like [`check`], for responses of an [`HttpClient`](crate::http::HttpClient).
+pub fn check_http(response: &HttpResponse) -> anyhow::Result<()> {
+    check_headers(response.status_code, |name| {
+        Ok(response.header(name).map(str::to_string))
+    })
+}
+
+fn check_headers(
+    status_code: u16,
+    header: impl Fn(&str) -> anyhow::Result<Option<String>>,
+) -> anyhow::Result<()> {
+    // only other status codes can be checked without the current time
+    if status_code != 403 && status_code != 429 {
+        return Ok(());
+    }
+
+    let now = (js_sys::Date::now() / 1000.0) as u64;
+
+    match rate_limit_exceeded(
+        status_code,
+        header("x-ratelimit-remaining")?.as_deref(),
+        header("x-ratelimit-reset")?.as_deref(),
+        header("retry-after")?.as_deref(),
+        now,
+    ) {
+        Some(exceeded) => Err(exceeded.into()),
+        None => Ok(()),
+    }
+}
+
+/// Returns the exceeded rate limit if it is the cause of `error`.
+pub fn exceeded_in(error: &anyhow::Error) -> Option<&RateLimitExceeded> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<RateLimitExceeded>())
+}
+
+/// What is left of the GitHub rate limit, from the `x-ratelimit-*` headers of a response.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Headroom {
+    pub remaining: u64,
+    pub limit: Option<u64>,
+    /// UNIX timestamp in seconds of when the limit is reset.
+    pub reset: Option<u64>,
+}
+
+fn headroom_from_headers(
+    remaining: Option<&str>,
+    limit: Option<&str>,
+    reset: Option<&str>,
+) -> Option<Headroom> {
+    let parse = |value: Option<&str>| value.and_then(|value| value.trim().parse().ok());
+
+    Some(Headroom {
+        remaining: parse(remaining)?,
+        limit: parse(limit),
+        reset: parse(reset),
+    })
+}
+
+/// The one of `lowest` and `observed` with less remaining.
+fn lower(lowest: Option<Headroom>, observed: Headroom) -> Headroom {
+    match lowest {
+        Some(lowest) if lowest.remaining <= observed.remaining => lowest,
+        _ => observed,
+    }
+}
+
+/// Whether `observed` is the first headroom below `floor` since `lowest`, so that
+/// it is warned about once per run.
+fn dropped_below(lowest: Option<Headroom>, observed: Headroom, floor: u64) -> bool {
+    observed.remaining < floor && !matches!(lowest, Some(lowest) if lowest.remaining < floor)
+}
+
+/// Whether `headroom` is below `floor` and was not reset yet at `now`, a UNIX timestamp
+/// in seconds; never with no `floor` configured.
+pub fn is_low(headroom: Option<&Headroom>, floor: Option<u64>, now: u64) -> bool {
+    match (headroom, floor) {
+        (Some(headroom), Some(floor)) => {
+            headroom.remaining < floor && !matches!(headroom.reset, Some(reset) if reset <= now)
+        }
+        _ => false,
+    }
+}
+
+/// Headroom of a run, kept on its [`invocation::Invocation`], as the isolate may handle
+/// several runs concurrently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunHeadroom {
+    /// Lowest headroom seen since the start of the run, see [`start_run`].
+    lowest: Option<Headroom>,
+    /// See [`configure`].
+    floor: Option<u64>,
+    /// See [`is_conserving`].
+    conserving: bool,
+}
+
+/// Headroom of the run of the current invocation; without one, nothing is recorded.
+fn run_headroom() -> RunHeadroom {
+    invocation::with(|invocation| {
+        invocation
+            .map(|invocation| invocation.rate_limit.get())
+            .unwrap_or_default()
+    })
+}
+
+fn update_run_headroom(update: impl FnOnce(&mut RunHeadroom)) {
+    invocation::with(|invocation| {
+        if let Some(invocation) = invocation {
+            let mut run_headroom = invocation.rate_limit.get();
+            update(&mut run_headroom);
+            invocation.rate_limit.set(run_headroom);
+        }
+    });
+}
+
+/// Sets the floor below which headroom is warned about and quota is conserved.
+pub fn configure(floor: Option<u64>) {
+    update_run_headroom(|run_headroom| run_headroom.floor = floor);
+}
+
+/// Records the headroom of a response from the GitHub API, if it has one.
+pub fn observe(response: &Response) {
+    let headers = response.headers();
+    let header = |name| headers.get(name).ok().flatten();
+
+    if let Some(observed) = headroom_from_headers(
+        header("x-ratelimit-remaining").as_deref(),
+        header("x-ratelimit-limit").as_deref(),
+        header("x-ratelimit-reset").as_deref(),
+    ) {
+        observe_headroom(observed);
+    }
+}
+
+fn observe_headroom(observed: Headroom) {
+    update_run_headroom(|run_headroom| {
+        if let Some(floor) = run_headroom.floor {
+            if dropped_below(run_headroom.lowest, observed, floor) {
+                log_warn!(
+                    "GitHub rate limit remaining = {} dropped below floor = {floor}",
+                    observed.remaining
+                );
+            }
+        }
+
+        run_headroom.lowest = Some(lower(run_headroom.lowest, observed));
+    });
+}
+
+/// Whether the run started with little headroom, in which case the least important
+/// requests to GitHub are skipped.
+pub fn is_conserving() -> bool {
+    run_headroom().conserving
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Returns the lowest headroom of the last run, if any was recorded.
+pub async fn stored(env: &Env) -> anyhow::Result<Option<Headroom>> {
+    let key = state::kv_key(env, HEADROOM_KV_KEY);
+
+    kv_store(env)?
+        .get(&key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))
+}
+
+async fn store(env: &Env, headroom: &Headroom) -> anyhow::Result<()> {
+    kv_store(env)?
+        .put(&state::kv_key(env, HEADROOM_KV_KEY), headroom)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+/// Forgets the headroom seen so far, and decides from the one of the last run whether
+/// to conserve quota in this one (see [`is_conserving`]).
+pub async fn start_run(env: &Env) {
+    update_run_headroom(|run_headroom| run_headroom.lowest = None);
+
+    let stored = stored(env).await.unwrap_or_else(|e| {
+        log_error!("could not get GitHub rate limit headroom: {e:?}");
+        None
+    });
+
+    let now = (js_sys::Date::now() / 1000.0) as u64;
+    let conserving = is_low(stored.as_ref(), run_headroom().floor, now);
+
+    if conserving {
+        log_warn!("GitHub rate limit headroom is low, conserving quota, stored = {stored:?}");
+    }
+
+    update_run_headroom(|run_headroom| run_headroom.conserving = conserving);
+}
+
+/// Records the lowest headroom seen in the run, if any; failures are only logged.
+pub async fn finish_run(env: &Env) {
+    let Some(lowest) = run_headroom().lowest else {
+        return;
+    };
+
+    log_info!("lowest GitHub rate limit headroom = {lowest:?}");
+    metrics::gauge(
+        "github_rate_limit_remaining",
+        lowest.remaining as f64,
+        metrics::Dimensions::default(),
+    );
+
+    if let Err(e) = store(env, &lowest).await {
+        log_error!("could not record GitHub rate limit headroom: {e:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{core::poll_once, invocation::Invocation};
+
+    const NOW: u64 = 1_656_633_600;
+
+    #[test]
+    fn lowest_headroom_is_kept_per_invocation() {
+        let lowest = |observed: &'static [u64]| {
+            poll_once(invocation::scope(Invocation::default(), async {
+                configure(Some(100));
+
+                for &remaining in observed {
+                    observe_headroom(headroom(remaining));
+                }
+
+                run_headroom().lowest
+            }))
+        };
+
+        assert_eq!(lowest(&[4000, 3000, 3500]), Some(headroom(3000)));
+        assert_eq!(lowest(&[4500]), Some(headroom(4500)));
+        assert_eq!(lowest(&[]), None);
+        assert_eq!(run_headroom(), RunHeadroom::default());
+    }
+
+    #[test_case(0, "0 seconds"; "zero")]
+    #[test_case(1, "1 second"; "one second")]
+    #[test_case(59, "59 seconds"; "seconds")]
+    #[test_case(60, "1 minute"; "one minute")]
+    #[test_case(725, "12 minutes 5 seconds"; "minutes and seconds")]
+    #[test_case(3661, "1 hour 1 minute 1 second"; "all units")]
+    fn duration(seconds: u64, result: &str) {
+        assert_str_eq!(human_duration(seconds), result);
+    }
+
+    #[test]
+    fn primary() {
+        let reset = (NOW + 725).to_string();
+
+        assert_eq!(
+            rate_limit_exceeded(403, Some("0"), Some(&reset), None, NOW),
+            Some(RateLimitExceeded::Primary {
+                resets_in_seconds: Some(725)
+            })
+        );
+    }
+
+    #[test]
+    fn primary_without_reset() {
+        assert_eq!(
+            rate_limit_exceeded(429, Some("0"), None, None, NOW),
+            Some(RateLimitExceeded::Primary {
+                resets_in_seconds: None
+            })
+        );
+    }
+
+    #[test]
+    fn secondary() {
+        assert_eq!(
+            rate_limit_exceeded(403, Some("4000"), None, Some("60"), NOW),
+            Some(RateLimitExceeded::Secondary {
+                retry_after_seconds: 60
+            })
+        );
+    }
+
+    #[test_case(200, Some("0"), None; "success")]
+    #[test_case(403, Some("4000"), None; "forbidden for another reason")]
+    #[test_case(404, None, Some("60"); "not found")]
+    fn not_exceeded(status_code: u16, remaining: Option<&str>, retry_after: Option<&str>) {
+        assert_eq!(
+            rate_limit_exceeded(status_code, remaining, None, retry_after, NOW),
+            None
+        );
+    }
+
+    #[test]
+    fn message() {
+        assert_str_eq!(
+            RateLimitExceeded::Primary {
+                resets_in_seconds: Some(725)
+            }
+            .to_string(),
+            "GitHub rate limit exceeded, resets in 12 minutes 5 seconds"
+        );
+
+        assert_str_eq!(
+            RateLimitExceeded::Secondary {
+                retry_after_seconds: 60
+            }
+            .to_string(),
+            "GitHub secondary rate limit exceeded, retry after 1 minute"
+        );
+    }
+
+    #[test]
+    fn exceeded_in_error_chain() {
+        let exceeded = RateLimitExceeded::Secondary {
+            retry_after_seconds: 60,
+        };
+
+        let error = anyhow::Error::from(exceeded.clone())
+            .context("could not fetch from GitHub")
+            .context("could not fetch tags from GitHub");
+
+        assert_eq!(exceeded_in(&error), Some(&exceeded));
+        assert_eq!(exceeded_in(&anyhow!("could not fetch")), None);
+    }
+
+    fn headroom(remaining: u64) -> Headroom {
+        Headroom {
+            remaining,
+            limit: Some(5000),
+            reset: Some(NOW + 600),
+        }
+    }
+
+    #[test]
+    fn headroom_from_all_headers() {
+        let reset = (NOW + 600).to_string();
+
+        assert_eq!(
+            headroom_from_headers(Some("4321"), Some("5000"), Some(&reset)),
+            Some(headroom(4321))
+        );
+    }
+
+    #[test]
+    fn headroom_from_remaining_only() {
+        assert_eq!(
+            headroom_from_headers(Some(" 59 "), None, Some("soon")),
+            Some(Headroom {
+                remaining: 59,
+                limit: None,
+                reset: None,
+            })
+        );
+    }
+
+    #[test_case(None; "missing")]
+    #[test_case(Some(""); "empty")]
+    #[test_case(Some("-1"); "negative")]
+    fn no_headroom(remaining: Option<&str>) {
+        assert_eq!(headroom_from_headers(remaining, Some("5000"), None), None);
+    }
+
+    #[test]
+    fn lowest_kept() {
+        assert_eq!(lower(None, headroom(4000)), headroom(4000));
+        assert_eq!(lower(Some(headroom(3000)), headroom(4000)), headroom(3000));
+        assert_eq!(lower(Some(headroom(4000)), headroom(3000)), headroom(3000));
+    }
+
+    #[test_case(None, 99, true; "first response below floor")]
+    #[test_case(Some(150), 99, true; "drops below floor")]
+    #[test_case(Some(99), 98, false; "already below floor")]
+    #[test_case(Some(150), 100, false; "at floor")]
+    #[test_case(None, 4000, false; "above floor")]
+    fn dropped(lowest: Option<u64>, remaining: u64, result: bool) {
+        assert_eq!(
+            dropped_below(lowest.map(headroom), headroom(remaining), 100),
+            result
+        );
+    }
+
+    #[test_case(Some(99), Some(100), NOW, true; "below floor")]
+    #[test_case(Some(100), Some(100), NOW, false; "at floor")]
+    #[test_case(Some(4000), Some(100), NOW, false; "above floor")]
+    #[test_case(Some(99), None, NOW, false; "no floor")]
+    #[test_case(None, Some(100), NOW, false; "never recorded")]
+    #[test_case(Some(99), Some(100), NOW + 600, false; "reset since")]
+    #[test_case(Some(99), Some(100), NOW + 599, true; "just before reset")]
+    fn low(remaining: Option<u64>, floor: Option<u64>, now: u64, result: bool) {
+        assert_eq!(is_low(remaining.map(headroom).as_ref(), floor, now), result);
+    }
+
+    #[test]
+    fn low_without_reset() {
+        let headroom = Headroom {
+            remaining: 10,
+            limit: None,
+            reset: None,
+        };
+
+        assert!(is_low(Some(&headroom), Some(100), NOW));
+    }
+
+    #[test]
+    fn headroom_json() {
+        assert_str_eq!(
+            serde_json::to_string(&headroom(4321)).unwrap(),
+            r#"{"remaining":4321,"limit":5000,"reset":1656634200}"#
+        );
+    }
+
+    #[test_case(RateLimitExceeded::Primary { resets_in_seconds: Some(725) }, Some(725); "primary")]
+    #[test_case(RateLimitExceeded::Primary { resets_in_seconds: None }, None; "primary without reset")]
+    #[test_case(RateLimitExceeded::Secondary { retry_after_seconds: 60 }, Some(60); "secondary")]
+    fn converted(exceeded: RateLimitExceeded, resets_in_seconds: Option<u64>) {
+        assert_eq!(
+            error::Error::from(exceeded),
+            error::Error::GitHubRateLimited { resets_in_seconds }
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{bail, Context};
+use worker::Env;
+
+use crate::{
+    http,
+    log::{log_error, log_info, log_warn},
+    platform::Platform,
+    types::github::Tag,
+    utils,
+};
+
+/// Rows are added to the table right below this line of the release history post.
+const RELEASE_HISTORY_MARKER: &str = "<!-- release-history -->";
+
+const TABLE_HEADER: &str = "| Version | Date | Commits | Post |\n| --- | --- | --- | --- |";
+
+/// Row of the release history table for `version`, linking to the post about it at `post_url`.
+pub fn row(version: &str, date: &str, commits_count: usize, post_url: &str) -> String {
+    format!("| {version} | {date} | {commits_count} | [post]({post_url}) |")
+}
+
+fn is_row_of(line: &str, version: &str) -> bool {
+    line.trim()
+        .strip_prefix('|')
+        .and_then(|rest| rest.split('|').next())
+        .is_some_and(|first_cell| first_cell.trim() == version)
+}
+
+fn is_delimiter_row(line: &str) -> bool {
+    let line = line.trim();
+
+    line.starts_with('|')
+        && line.contains('-')
+        && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+/// Adds `row` for `version` as the first row of the table below the marker in `raw`,
+/// starting the table if there is none yet.
+///
+/// Returns `None` if `raw` already has a row for `version`, so that retries do not add it twice.
+pub fn insert_row(raw: &str, version: &str, row: &str) -> anyhow::Result<Option<String>> {
+    if raw.lines().any(|line| is_row_of(line, version)) {
+        return Ok(None);
+    }
+
+    let lines: Vec<&str> = raw.lines().collect();
+
+    let Some(marker_index) = lines
+        .iter()
+        .position(|line| line.trim() == RELEASE_HISTORY_MARKER)
+    else {
+        bail!("release history post has no {RELEASE_HISTORY_MARKER} marker");
+    };
+
+    let has_table = lines
+        .get(marker_index + 1)
+        .is_some_and(|line| line.trim().starts_with('|'))
+        && lines
+            .get(marker_index + 2)
+            .is_some_and(|line| is_delimiter_row(line));
+
+    let (insert_index, inserted) = match has_table {
+        true => (marker_index + 3, row.to_string()),
+        false => (marker_index + 1, format!("{TABLE_HEADER}\n{row}")),
+    };
+
+    let mut new_lines: Vec<&str> = lines[..insert_index].to_vec();
+    new_lines.push(&inserted);
+    new_lines.extend(&lines[insert_index..]);
+
+    let mut new_raw = new_lines.join("\n");
+    if raw.ends_with('\n') {
+        new_raw.push('\n');
+    }
+
+    Ok(Some(new_raw))
+}
+
+/// Adds a row for `new_tag` to the release history post of `platform`, if one is configured.
+///
+/// Failures are only logged, as the post was already made.
+pub async fn append(
+    env: &Env,
+    platform: Platform,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) {
+    if let Err(e) = try_append(env, platform, new_tag, commits_count, post_url).await {
+        log_error!(
+            platform = platform,
+            "could not add {} to release history: {e:?}",
+            new_tag.name
+        );
+    }
+}
+
+async fn try_append(
+    env: &Env,
+    platform: Platform,
+    new_tag: &Tag,
+    commits_count: usize,
+    post_url: &str,
+) -> anyhow::Result<()> {
+    let Some(post_id) = utils::release_history_post_id(env, platform)? else {
+        log_info!(
+            platform = platform,
+            "no release history post configured; not adding to it"
+        );
+        return Ok(());
+    };
+
+    let api_key = utils::api_key(env)?;
+    let version = new_tag.exact_version_string();
+    let now = utils::now_iso_string();
+    let date = now.split('T').next().unwrap_or(&now);
+    let row = row(&version, date, commits_count, post_url);
+
+    // the post is edited by people too, so an edit made meanwhile is retried once instead of overwritten
+    for _ in 0..2 {
+        let post = utils::get_post_raw_in_discourse(&http::FetchClient, &api_key, post_id)
+            .await
+            .context("could not get release history post")?;
+
+        let Some(new_raw) = insert_row(&post.raw, &version, &row)? else {
+            log_info!(platform = platform, "release history already has {version}");
+            return Ok(());
+        };
+
+        let current_version =
+            utils::get_post_raw_in_discourse(&http::FetchClient, &api_key, post_id)
+                .await
+                .context("could not check release history post version")?
+                .version;
+
+        if current_version != post.version {
+            log_warn!(
+                platform = platform,
+                "release history post was edited meanwhile (version {} → {current_version})",
+                post.version
+            );
+            continue;
+        }
+
+        utils::edit_post_in_discourse(&new_raw, &api_key, post_id, &format!("add {version}"))
+            .await
+            .context("could not edit release history post")?;
+
+        log_info!(platform = platform, "added {version} to release history");
+        return Ok(());
+    }
+
+    bail!("release history post kept being edited while adding {version}")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+
+    const ROW: &str =
+        "| 1.2.4 | 2022-07-02 | 12 | [post](https://community.signalusers.org/t/123/5) |";
+
+    #[test]
+    fn row_markdown() {
+        assert_str_eq!(
+            row(
+                "1.2.4",
+                "2022-07-02",
+                12,
+                "https://community.signalusers.org/t/123/5"
+            ),
+            ROW
+        );
+    }
+
+    #[test]
+    fn inserted_as_first_row() {
+        let raw = "Release history.\n\n<!-- release-history -->\n| Version | Date | Commits | Post |\n| --- | --- | --- | --- |\n| 1.2.3 | 2022-07-01 | 3 | [post](https://community.signalusers.org/t/123/4) |\n\nMore text.\n";
+
+        assert_str_eq!(
+            insert_row(raw, "1.2.4", ROW).unwrap().unwrap(),
+            format!("Release history.\n\n<!-- release-history -->\n| Version | Date | Commits | Post |\n| --- | --- | --- | --- |\n{ROW}\n| 1.2.3 | 2022-07-01 | 3 | [post](https://community.signalusers.org/t/123/4) |\n\nMore text.\n")
+        );
+    }
+
+    #[test_case("Release history.\n<!-- release-history -->", format!("Release history.\n<!-- release-history -->\n{TABLE_HEADER}\n{ROW}"); "marker at the end")]
+    #[test_case("<!-- release-history -->\n\nMore text.", format!("<!-- release-history -->\n{TABLE_HEADER}\n{ROW}\n\nMore text."); "marker followed by text")]
+    #[test_case("  <!-- release-history -->  \r\n", format!("  <!-- release-history -->  \n{TABLE_HEADER}\n{ROW}\n"); "marker with spaces")]
+    fn table_is_started(raw: &str, result: String) {
+        assert_str_eq!(insert_row(raw, "1.2.4", ROW).unwrap().unwrap(), result);
+    }
+
+    #[test_case("<!-- release-history -->\n| Version | Date | Commits | Post |\n| --- | --- | --- | --- |\n| 1.2.4 | 2022-07-02 | 12 | [post](https://community.signalusers.org/t/123/5) |"; "same row")]
+    #[test_case("<!-- release-history -->\n| Version | Date | Commits | Post |\n|:-|:-|-:|:-|\n|1.2.4|2022-07-03|13|[post](https://community.signalusers.org/t/123/6)|"; "edited row")]
+    fn already_present(raw: &str) {
+        assert_eq!(insert_row(raw, "1.2.4", ROW).unwrap(), None);
+    }
+
+    #[test]
+    fn other_versions_are_not_matched() {
+        let raw = "<!-- release-history -->\n| Version | Date | Commits | Post |\n| --- | --- | --- | --- |\n| 1.2.40 | 2022-08-01 | 1 | [post](https://community.signalusers.org/t/124/2) |";
+
+        assert!(insert_row(raw, "1.2.4", ROW).unwrap().is_some());
+    }
+
+    #[test]
+    fn marker_missing() {
+        assert_eq!(
+            insert_row("| Version |", "1.2.4", ROW)
+                .unwrap_err()
+                .to_string(),
+            "release history post has no <!-- release-history --> marker"
+        );
+    }
+}
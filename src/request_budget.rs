@@ -0,0 +1,82 @@
+use crate::utils;
+
+/// Subrequests of a run, counted in [`utils::fetch`], against a soft cap that keeps the run
+/// under the limit Cloudflare puts on subrequests per invocation.
+///
+/// Once the cap is reached, no new work is started on platforms; work that is already
+/// underway is finished, so the cap may be exceeded by it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestBudget {
+    soft_cap: Option<usize>,
+    /// Subrequests counted before the run, as the count is kept for the whole invocation,
+    /// e.g. a queue batch that checks several platforms.
+    counted_before: usize,
+}
+
+impl RequestBudget {
+    /// Starts counting the subrequests of a run; without `soft_cap`, they are only counted.
+    pub fn start(soft_cap: Option<usize>) -> Self {
+        Self::new(soft_cap, utils::subrequest_count())
+    }
+
+    fn new(soft_cap: Option<usize>, counted_before: usize) -> Self {
+        Self {
+            soft_cap,
+            counted_before,
+        }
+    }
+
+    pub fn soft_cap(&self) -> Option<usize> {
+        self.soft_cap
+    }
+
+    /// Subrequests made by the run so far.
+    pub fn used(&self) -> usize {
+        self.used_at(utils::subrequest_count())
+    }
+
+    fn used_at(&self, count: usize) -> usize {
+        count.saturating_sub(self.counted_before)
+    }
+
+    /// Whether new work is held off, as the run has made as many subrequests as the cap allows.
+    pub fn is_exhausted(&self) -> bool {
+        self.is_exhausted_at(utils::subrequest_count())
+    }
+
+    fn is_exhausted_at(&self, count: usize) -> bool {
+        self.soft_cap
+            .is_some_and(|soft_cap| self.used_at(count) >= soft_cap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(None, 10, 1000, false; "no cap")]
+    #[test_case(Some(40), 10, 10, false; "nothing used")]
+    #[test_case(Some(40), 10, 49, false; "under the cap")]
+    #[test_case(Some(40), 10, 50, true; "at the cap")]
+    #[test_case(Some(40), 10, 75, true; "over the cap")]
+    #[test_case(Some(0), 10, 10, true; "zero cap")]
+    fn exhausted(soft_cap: Option<usize>, counted_before: usize, count: usize, result: bool) {
+        assert_eq!(
+            RequestBudget::new(soft_cap, counted_before).is_exhausted_at(count),
+            result
+        );
+    }
+
+    #[test_case(10, 25, 15; "since the start")]
+    #[test_case(10, 10, 0; "nothing yet")]
+    #[test_case(10, 5, 0; "count behind the start")]
+    fn used(counted_before: usize, count: usize, result: usize) {
+        assert_eq!(
+            RequestBudget::new(Some(40), counted_before).used_at(count),
+            result
+        );
+    }
+}
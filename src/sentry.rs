@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context};
+use serde::Serialize;
+use worker::{js_sys, Env, Fetch, Method, Url};
+
+use crate::{
+    log::{self, log_error, log_info},
+    platform::Platform,
+    utils,
+};
+
+const SENTRY_VERSION: u8 = 7;
+
+/// Where to send events to, parsed from a DSN like `https://<public key>@<host>/<project id>`.
+#[derive(Debug, PartialEq, Eq)]
+struct Dsn {
+    public_key: String,
+    store_url: String,
+}
+
+impl Dsn {
+    fn parse(dsn: &str) -> anyhow::Result<Self> {
+        let url = Url::parse(dsn).context("could not parse DSN as a URL")?;
+
+        let public_key = url.username();
+        if public_key.is_empty() {
+            bail!("DSN has no public key");
+        }
+
+        let host = url.host_str().context("DSN has no host")?;
+        let port = url
+            .port()
+            .map(|port| format!(":{port}"))
+            .unwrap_or_default();
+
+        let path = url.path().trim_matches('/');
+        let (path_prefix, project_id) = match path.rsplit_once('/') {
+            Some((path_prefix, project_id)) => (format!("/{path_prefix}"), project_id),
+            None => (String::new(), path),
+        };
+        if project_id.is_empty() {
+            bail!("DSN has no project id");
+        }
+
+        Ok(Self {
+            public_key: public_key.to_string(),
+            store_url: format!(
+                "{}://{host}{port}{path_prefix}/api/{project_id}/store/",
+                url.scheme()
+            ),
+        })
+    }
+
+    fn auth_header_value(&self) -> String {
+        format!(
+            "Sentry sentry_version={SENTRY_VERSION}, sentry_client={}, sentry_key={}",
+            utils::client_name(),
+            self.public_key
+        )
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct Exception {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct Exceptions {
+    values: Vec<Exception>,
+}
+
+/// Event in Sentry's store format (https://develop.sentry.dev/sdk/event-payloads/).
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct Event {
+    event_id: String,
+    timestamp: String,
+    platform: &'static str,
+    level: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<String>,
+    tags: BTreeMap<&'static str, String>,
+    exception: Exceptions,
+}
+
+/// What an error is reported with, in addition to the error itself.
+pub struct Report<'a> {
+    pub platform: Option<Platform>,
+    /// Last posted tag of `platform`, the one that the failed check started from.
+    pub tag: Option<&'a str>,
+}
+
+impl Event {
+    /// Builds the event for `error`; its chain becomes the exceptions, innermost cause first
+    /// as Sentry shows the last one as the main exception.
+    fn new(
+        error: &anyhow::Error,
+        report: &Report,
+        environment: Option<String>,
+        trace_id: String,
+        event_id: String,
+        timestamp: String,
+    ) -> Self {
+        let mut tags = BTreeMap::from([("trace_id", trace_id)]);
+
+        if let Some(platform) = report.platform {
+            tags.insert("platform", platform.to_string());
+        }
+
+        if let Some(tag) = report.tag {
+            tags.insert("tag", tag.to_string());
+        }
+
+        let mut values: Vec<Exception> = error
+            .chain()
+            .map(|cause| Exception {
+                kind: String::from("Error"),
+                value: cause.to_string(),
+            })
+            .collect();
+        values.reverse();
+
+        Self {
+            event_id,
+            timestamp,
+            platform: "other",
+            level: "error",
+            environment,
+            tags,
+            exception: Exceptions { values },
+        }
+    }
+}
+
+/// Sends `error` to the Sentry DSN configured with `SENTRY_DSN`, if any.
+///
+/// Failures are only logged, so that reporting never gets in the way of checking platforms.
+pub async fn report(env: &Env, error: &anyhow::Error, report: Report<'_>) {
+    if let Err(e) = try_report(env, error, &report).await {
+        log_error!("could not report error to Sentry: {e:?}");
+    }
+}
+
+async fn try_report(env: &Env, error: &anyhow::Error, report: &Report<'_>) -> anyhow::Result<()> {
+    let dsn = match utils::sentry_dsn(env)? {
+        Some(dsn) => Dsn::parse(&dsn).context("invalid SENTRY_DSN")?,
+        None => {
+            log_info!("no Sentry DSN configured; not reporting error");
+            return Ok(());
+        }
+    };
+
+    let event = Event::new(
+        error,
+        report,
+        utils::environment(env),
+        log::trace_id(),
+        utils::random_hex_string(4, js_sys::Math::random),
+        utils::now_iso_string(),
+    );
+    let body = serde_json::to_value(&event).context("could not serialize event")?;
+
+    let url = Url::parse(&dsn.store_url).context("could not parse URL")?;
+    let request = utils::RequestBuilder::new(url, Method::Post)
+        .header("X-Sentry-Auth", dsn.auth_header_value())
+        .json(&body)
+        .build()?;
+
+    let response = utils::fetch(Fetch::Request(request)).await?;
+
+    match response.status_code() {
+        200..=299 => {
+            log_info!("reported error to Sentry, event_id = {}", event.event_id);
+            Ok(())
+        }
+        status_code => bail!("Sentry responded with status code {status_code}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use serde_json::{json, Value};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    #[test_case("https://abc123@o0.ingest.sentry.io/42", "https://o0.ingest.sentry.io/api/42/store/"; "sentry")]
+    #[test_case("http://abc123@localhost:9000/prefix/42", "http://localhost:9000/prefix/api/42/store/"; "self-hosted with port and path")]
+    fn dsn(dsn: &str, store_url: &str) {
+        assert_eq!(
+            Dsn::parse(dsn).unwrap(),
+            Dsn {
+                public_key: String::from("abc123"),
+                store_url: store_url.to_string(),
+            }
+        );
+    }
+
+    #[test_case("not a url", "could not parse DSN as a URL: relative URL without a base"; "not a url")]
+    #[test_case("https://o0.ingest.sentry.io/42", "DSN has no public key"; "no public key")]
+    #[test_case("https://abc123@o0.ingest.sentry.io/", "DSN has no project id"; "no project id")]
+    fn invalid_dsn(dsn: &str, message: &str) {
+        assert_eq!(format!("{:#}", Dsn::parse(dsn).unwrap_err()), message);
+    }
+
+    #[test]
+    fn auth_header() {
+        let dsn = Dsn::parse("https://abc123@o0.ingest.sentry.io/42").unwrap();
+
+        assert_str_eq!(
+            dsn.auth_header_value(),
+            format!(
+                "Sentry sentry_version=7, sentry_client=updates-bot/{}, sentry_key=abc123",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    fn event(report: &Report, environment: Option<String>) -> Value {
+        let error = anyhow!("status code 502")
+            .context("could not post commits to Discourse")
+            .context("could not post commits for new_tag = v1.2.4");
+
+        serde_json::to_value(Event::new(
+            &error,
+            report,
+            environment,
+            String::from("0123456789abcdef"),
+            String::from("fedcba9876543210fedcba9876543210"),
+            String::from("2022-07-01T12:34:56.789Z"),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn event_for_platform() {
+        let report = Report {
+            platform: Some(Android),
+            tag: Some("v1.2.3"),
+        };
+
+        assert_eq!(
+            event(&report, Some(String::from("production"))),
+            json!({
+                "event_id": "fedcba9876543210fedcba9876543210",
+                "timestamp": "2022-07-01T12:34:56.789Z",
+                "platform": "other",
+                "level": "error",
+                "environment": "production",
+                "tags": {
+                    "platform": "Android",
+                    "tag": "v1.2.3",
+                    "trace_id": "0123456789abcdef",
+                },
+                "exception": {
+                    "values": [
+                        { "type": "Error", "value": "status code 502" },
+                        { "type": "Error", "value": "could not post commits to Discourse" },
+                        { "type": "Error", "value": "could not post commits for new_tag = v1.2.4" },
+                    ],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn event_without_platform() {
+        let report = Report {
+            platform: None,
+            tag: None,
+        };
+        let event = event(&report, None);
+
+        assert_eq!(event["tags"], json!({ "trace_id": "0123456789abcdef" }));
+        assert_eq!(event.get("environment"), None);
+    }
+}
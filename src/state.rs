@@ -1,68 +1,319 @@
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+};
+
 use anyhow::{anyhow, bail, Context};
-use semver::Version;
-use serde::{Deserialize, Serialize};
+use futures_util::lock::Mutex;
+use serde_json::Value;
 use strum::IntoEnumIterator;
-use worker::{console_log, console_warn, Env};
+use worker::Env;
 use worker_kv::KvStore;
 
+pub use crate::core::state::{from_value, release_cycle, PlatformState, PlatformStatePatch, State};
 use crate::{
-    localization::{Completeness, UnsortedChanges},
-    platform::Platform::{self, *},
-    types::github::Tag,
+    core::{
+        error::Error,
+        state::{
+            migrate, next_revision, revision_of, schema_version, PlatformStateUpdate, StoredState,
+            SCHEMA_VERSION,
+        },
+    },
+    log::{log_info, log_warn},
+    platform::Platform,
+    state_backup, state_repair, utils,
 };
 
-const STATE_KV_BINDING: &str = "STATE";
-const STATE_KV_KEY: &str = "state";
+pub const STATE_KV_BINDING: &str = "STATE";
+/// Key of the state when no environment is configured, and of the state
+/// from before it was kept separately for each environment.
+const LEGACY_STATE_KV_KEY: &str = "state";
+
+/// The state in KV as it is, without validating it.
+pub struct Snapshot {
+    pub state: State,
+    /// Schema version the state is stored with, before migrating it.
+    pub schema_version: u64,
+    /// ISO 8601 timestamp of when the state was saved, if known.
+    pub saved_at: Option<String>,
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Key of `key` for `environment`, so that e.g. staging and production
+/// do not overwrite each other's values in a shared KV namespace.
+pub fn kv_key_for(key: &str, environment: Option<&str>) -> String {
+    match environment {
+        Some(environment) => format!("{key}:{environment}"),
+        None => key.to_string(),
+    }
+}
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct State {
-    pub android: PlatformState,
-    pub ios: PlatformState,
-    pub desktop: PlatformState,
+/// Key of `key` for the configured environment, see [`kv_key_for`];
+/// every key in KV goes through it.
+pub fn kv_key(env: &Env, key: &str) -> String {
+    kv_key_for(key, utils::environment(env).as_deref())
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-pub struct PlatformState {
-    pub last_posted_tag_previous_release: Tag,
-    pub last_posted_tag: Tag,
+async fn get_text(kv_store: &KvStore, kv_key: &str) -> anyhow::Result<Option<String>> {
+    kv_store
+        .get(kv_key)
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {kv_key}"))
+}
 
-    #[serde(default)]
-    pub last_post_number: Option<u64>,
+/// State text found by [`get_or_adopt`].
+#[derive(Debug, PartialEq, Eq)]
+struct FoundText {
+    text: String,
+    /// Whether the text was found at [`LEGACY_STATE_KV_KEY`] instead of the requested key.
+    adopted: bool,
+}
 
-    #[serde(default)]
-    pub posted_archiving_message: bool,
+/// Gets the state stored at `kv_key` with `get`, or else the state stored at
+/// [`LEGACY_STATE_KV_KEY`], which is copied to `kv_key` with `copy` if `copy_adopted` is set.
+///
+/// The legacy state is kept, as other environments may not have adopted it yet.
+async fn get_or_adopt<G, GFut, C, CFut>(
+    kv_key: &str,
+    copy_adopted: bool,
+    get: G,
+    copy: C,
+) -> anyhow::Result<Option<FoundText>>
+where
+    G: Fn(String) -> GFut,
+    GFut: Future<Output = anyhow::Result<Option<String>>>,
+    C: FnOnce(String) -> CFut,
+    CFut: Future<Output = anyhow::Result<()>>,
+{
+    if let Some(text) = get(kv_key.to_string()).await? {
+        return Ok(Some(FoundText {
+            text,
+            adopted: false,
+        }));
+    }
+
+    if kv_key == LEGACY_STATE_KV_KEY {
+        return Ok(None);
+    }
+
+    let Some(text) = get(LEGACY_STATE_KV_KEY.to_string()).await? else {
+        return Ok(None);
+    };
+
+    if copy_adopted {
+        copy(text.clone())
+            .await
+            .with_context(|| format!("could not copy {LEGACY_STATE_KV_KEY} to {kv_key}"))?;
+    }
+
+    Ok(Some(FoundText {
+        text,
+        adopted: true,
+    }))
+}
+
+/// Gets the state text of the environment, see [`get_or_adopt`].
+async fn get_text_or_adopt(
+    kv_store: &KvStore,
+    kv_key: &str,
+    copy_adopted: bool,
+) -> anyhow::Result<Option<FoundText>> {
+    let found = get_or_adopt(
+        kv_key,
+        copy_adopted,
+        |key| async move { get_text(kv_store, &key).await },
+        |text| async move {
+            kv_store
+                .put(kv_key, text)
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("could not create request to put to KV")?
+                .execute()
+                .await
+                .map_err(|e| anyhow!(e.to_string()))
+                .context("could not put to KV")
+        },
+    )
+    .await?;
+
+    if let Some(FoundText { adopted: true, .. }) = found {
+        log_warn!("no state at {kv_key}, adopted the state at {LEGACY_STATE_KV_KEY}");
+    }
 
-    #[serde(default)]
-    pub localization_changes_completeness: Completeness,
-    #[serde(default)]
-    pub localization_changes: UnsortedChanges,
+    Ok(found)
 }
 
+/// Backs up `previous_state`, the state stored in KV for `environment`, before replacing it with `state`.
+async fn put(
+    kv_store: &KvStore,
+    kv_key: &str,
+    environment: Option<&str>,
+    previous_state: Option<&str>,
+    state: State,
+    revision: u64,
+) -> anyhow::Result<()> {
+    match previous_state {
+        Some(previous_state) => state_backup::save(kv_store, environment, previous_state).await,
+        None => log_info!("no state in KV to back up"),
+    }
+
+    let stored = StoredState {
+        schema_version: SCHEMA_VERSION,
+        revision,
+        saved_at: Some(utils::now_iso_string()),
+        platforms: state,
+    };
+
+    kv_store
+        .put(kv_key, &stored)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+/// Indicates whether there is state in KV, even if it could not be loaded.
+pub async fn exists(env: &Env) -> anyhow::Result<bool> {
+    Ok(
+        get_text_or_adopt(&kv_store(env)?, &kv_key(env, LEGACY_STATE_KV_KEY), false)
+            .await?
+            .is_some(),
+    )
+}
+
+/// Loads the state in KV for inspecting it, even if it is not valid.
+///
+/// Returns `None` if there is no state in KV.
+pub async fn snapshot(env: &Env) -> anyhow::Result<Option<Snapshot>> {
+    let Some(FoundText { text, .. }) =
+        get_text_or_adopt(&kv_store(env)?, &kv_key(env, LEGACY_STATE_KV_KEY), false).await?
+    else {
+        return Ok(None);
+    };
+
+    let value: Value = serde_json::from_str(&text).context("could not parse stored state")?;
+    let schema_version = schema_version(&value)?;
+    let (stored, _) = migrate(value).context("could not migrate state")?;
+
+    Ok(Some(Snapshot {
+        state: stored.platforms,
+        schema_version,
+        saved_at: stored.saved_at,
+    }))
+}
+
+/// Saves `state` to KV, replacing any existing state; only meant for setting up,
+/// as changes during a run go through [`StateController`].
+pub async fn initialize(env: &Env, state: State) -> anyhow::Result<()> {
+    state.validate().map_err(Error::invalid_state)?;
+
+    let kv_store = kv_store(env)?;
+    let kv_key = kv_key(env, LEGACY_STATE_KV_KEY);
+    let previous_state = get_text(&kv_store, &kv_key).await?;
+
+    // replaced regardless of changes, but still at a new revision, so that runs that
+    // loaded the previous state do not overwrite this one
+    let revision = match &previous_state {
+        Some(previous_state) => revision_of(previous_state).unwrap_or_default() + 1,
+        None => 0,
+    };
+
+    put(
+        &kv_store,
+        &kv_key,
+        utils::environment(env).as_deref(),
+        previous_state.as_deref(),
+        state,
+        revision,
+    )
+    .await
+}
+
+/// Shared by the checks of all platforms, which run concurrently.
 pub struct StateController {
     kv_store: KvStore,
-    state: State,
+    /// Key of the state of the configured environment.
+    kv_key: String,
+    /// The configured environment, which backups of the state are kept for.
+    environment: Option<String>,
+    state: RefCell<State>,
+    /// Revision of the state in KV as of when it was loaded or last saved by this controller.
+    revision: Cell<u64>,
+    /// Held while saving state, so that an older state never overwrites a newer one.
+    commit_lock: Mutex<()>,
+    is_dry_run: bool,
 }
 
 impl StateController {
-    pub async fn from_kv(env: &Env) -> anyhow::Result<Self> {
-        let kv_store = env
-            .kv(STATE_KV_BINDING)
-            .map_err(|e| anyhow!(e.to_string()))
-            .context("could not get KV store")?;
-
-        let state: Option<State> = kv_store
-            .get(STATE_KV_KEY)
-            .json()
-            .await
-            .map_err(|e| anyhow!(e.to_string()))
-            .with_context(|| format!("could not get value for key {STATE_KV_KEY}"))?;
+    /// In dry run, the loaded state is never changed or saved back to KV.
+    pub async fn from_kv(env: &Env, is_dry_run: bool) -> anyhow::Result<Self> {
+        let kv_store = kv_store(env)?;
+        let kv_key = kv_key(env, LEGACY_STATE_KV_KEY);
+        log_info!("kv_key = {kv_key}");
+
+        let found = get_text_or_adopt(&kv_store, &kv_key, !is_dry_run).await?;
+
+        match found {
+            Some(FoundText { text, .. }) => {
+                let value: Value = serde_json::from_str(&text)
+                    .with_context(|| format!("could not parse value for key {kv_key}"))?;
+                let (mut stored, migrated) = migrate(value).context("could not migrate state")?;
+
+                let repaired = match stored.platforms.validate() {
+                    Ok(()) => false,
+                    Err(e) => {
+                        log_warn!("state is invalid, attempting to repair it: {e:#}");
 
-        match state {
-            Some(state) => {
-                let controller = Self { kv_store, state };
+                        let repairs = state_repair::repair(env, &mut stored.platforms)
+                            .await
+                            .context("invalid state")?;
+
+                        for repair in &repairs {
+                            log_warn!("repaired state: {repair}");
+                        }
+
+                        true
+                    }
+                };
+
+                let controller = Self {
+                    kv_store,
+                    kv_key,
+                    environment: utils::environment(env),
+                    state: RefCell::new(stored.platforms),
+                    revision: Cell::new(stored.revision),
+                    commit_lock: Mutex::new(()),
+                    is_dry_run,
+                };
                 controller.log_state("loaded state from KV");
-                controller.validate_state().context("invalid state")?;
-                console_log!("state appears to be valid");
+                controller.validate_state().map_err(Error::invalid_state)?;
+                log_info!("state appears to be valid");
+
+                if migrated {
+                    log_info!("state was migrated to schema_version = {SCHEMA_VERSION}");
+                } else {
+                    log_info!("state is at schema_version = {SCHEMA_VERSION}");
+                }
+
+                if migrated || repaired {
+                    if is_dry_run {
+                        log_warn!("dry run; not saving migrated or repaired state");
+                    } else {
+                        controller
+                            .commit_changes()
+                            .await
+                            .context("could not save migrated or repaired state to KV")?;
+                        log_info!("saved migrated or repaired state");
+                    }
+                }
 
                 Ok(controller)
             }
@@ -71,84 +322,211 @@ impl StateController {
     }
 
     fn validate_state(&self) -> anyhow::Result<()> {
-        for platform in Platform::iter() {
-            let state = self.platform_state(platform);
-
-            let last_posted_version_previous_release: Version = state
-                .last_posted_tag_previous_release
-                .to_version()
-                .context("couldn't convert last_posted_tag_previous_release to a Version")?;
-
-            let last_posted_version: Version = state
-                .last_posted_tag
-                .to_version()
-                .context("couldn't convert last_posted_tag to a Version")?;
-
-            if last_posted_version_previous_release >= last_posted_version {
-                bail!("last_posted_version_previous_release >= last_posted_version for {platform}");
-            }
-        }
+        self.state.borrow().validate()
+    }
 
-        Ok(())
+    pub const fn is_dry_run(&self) -> bool {
+        self.is_dry_run
     }
 
-    pub fn platform_state(&self, platform: Platform) -> &PlatformState {
-        match platform {
-            Android => &self.state.android,
-            Ios => &self.state.ios,
-            Desktop => &self.state.desktop,
-        }
+    pub fn platform_state(&self, platform: Platform) -> PlatformState {
+        self.state.borrow().platform_state(platform).clone()
     }
 
-    fn platform_state_mut(&mut self, platform: Platform) -> &mut PlatformState {
-        match platform {
-            Android => &mut self.state.android,
-            Ios => &mut self.state.ios,
-            Desktop => &mut self.state.desktop,
-        }
+    pub fn is_paused(&self, platform: Platform) -> bool {
+        self.state.borrow().is_paused(platform)
     }
 
+    /// Replaces the state of `platform`; prefer [`Self::update_platform_state`] when only
+    /// some fields change, so that the others are kept as they are.
     pub async fn set_platform_state(
-        &mut self,
+        &self,
         platform: Platform,
         state: PlatformState,
     ) -> anyhow::Result<()> {
-        let platform_state = self.platform_state_mut(platform);
+        self.update_platform_state(platform, |platform_state| *platform_state = state)
+            .await
+    }
 
-        if *platform_state != state {
-            *platform_state = state;
-            console_log!("changed platform_state({platform}) = {platform_state:?}");
+    /// Changes the state of `platform` with `update`, saving it to KV only if anything changed.
+    pub async fn update_platform_state(
+        &self,
+        platform: Platform,
+        update: impl FnOnce(&mut PlatformState),
+    ) -> anyhow::Result<()> {
+        let (update, changes) =
+            self.state
+                .borrow_mut()
+                .update_platform_state(platform, update, self.is_dry_run);
+
+        match update {
+            PlatformStateUpdate::Changed => {
+                for change in changes {
+                    log_info!(
+                        platform = platform,
+                        "changed platform_state({platform}).{change}"
+                    );
+                }
 
-            match self.commit_changes().await {
-                Ok(_) => console_log!("saved state to KV"),
-                Err(e) => return Err(e.context("could not save state to KV")),
+                match self.commit_changes().await {
+                    Ok(_) => log_info!(platform = platform, "saved state to KV"),
+                    Err(e) => return Err(e.context("could not save state to KV")),
+                }
+            }
+            PlatformStateUpdate::Unchanged => {
+                log_warn!(
+                    platform = platform,
+                    "platform_state({platform}) did not change"
+                );
+            }
+            PlatformStateUpdate::SkippedInDryRun => {
+                log_warn!(
+                    platform = platform,
+                    "dry run; not setting platform_state({platform})"
+                );
             }
-        } else {
-            console_warn!("platform_state({platform}) did not change");
         }
 
         Ok(())
     }
 
-    async fn commit_changes(&mut self) -> anyhow::Result<()> {
-        self.kv_store
-            .put(STATE_KV_KEY, &self.state)
-            .map_err(|e| anyhow!(e.to_string()))
-            .context("could not create request to put to KV")?
-            .execute()
-            .await
-            .map_err(|e| anyhow!(e.to_string()))
-            .context("could not put to KV")
+    async fn commit_changes(&self) -> anyhow::Result<()> {
+        let _guard = self.commit_lock.lock().await;
+
+        // KV is eventually consistent, so this only catches writes that are already visible
+        let previous_state = get_text(&self.kv_store, &self.kv_key).await?;
+        let stored_revision = match &previous_state {
+            Some(previous_state) => revision_of(previous_state)?,
+            None => 0,
+        };
+        let revision = next_revision(self.revision.get(), stored_revision)?;
+
+        // cloned after acquiring the lock, so that it includes all changes made so far
+        let state = self.state.borrow().clone();
+
+        put(
+            &self.kv_store,
+            &self.kv_key,
+            self.environment.as_deref(),
+            previous_state.as_deref(),
+            state,
+            revision,
+        )
+        .await?;
+        self.revision.set(revision);
+
+        Ok(())
     }
 
     fn log_state(&self, message: &str) {
-        console_log!("{message}:");
+        log_info!("{message}:");
 
         for platform in Platform::iter() {
-            console_log!(
+            log_info!(
                 "^^^^^ platform_state({platform}) = {:?}",
                 self.platform_state(platform)
             );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::core::poll_once;
+
+    #[test_case(LEGACY_STATE_KV_KEY, None, "state"; "no environment")]
+    #[test_case(LEGACY_STATE_KV_KEY, Some("staging"), "state:staging"; "staging")]
+    #[test_case("pending:ios", Some("production"), "pending:ios:production"; "other key")]
+    fn environment_kv_key(key: &str, environment: Option<&str>, result: &str) {
+        assert_eq!(kv_key_for(key, environment), result);
+    }
+
+    /// Runs [`get_or_adopt`] against KV holding `stored` key-value pairs,
+    /// returning what was found and the texts that were copied.
+    fn get_or_adopt_in(
+        stored: &[(&str, &str)],
+        kv_key: &str,
+        copy_adopted: bool,
+    ) -> (Option<FoundText>, Vec<String>) {
+        let copied = RefCell::new(Vec::new());
+
+        let found = poll_once(get_or_adopt(
+            kv_key,
+            copy_adopted,
+            |key| {
+                let text = stored
+                    .iter()
+                    .find(|(stored_key, _)| *stored_key == key)
+                    .map(|(_, text)| text.to_string());
+                async move { Ok(text) }
+            },
+            |text| {
+                copied.borrow_mut().push(text);
+                async { Ok(()) }
+            },
+        ))
+        .unwrap();
+
+        (found, copied.into_inner())
+    }
+
+    #[test]
+    fn namespaced_state_is_used() {
+        assert_eq!(
+            get_or_adopt_in(
+                &[("state", "legacy"), ("state:staging", "staging")],
+                "state:staging",
+                true
+            ),
+            (
+                Some(FoundText {
+                    text: String::from("staging"),
+                    adopted: false,
+                }),
+                Vec::new()
+            )
+        );
+    }
+
+    #[test_case(true, vec![String::from("legacy")]; "copied")]
+    #[test_case(false, Vec::new(); "not copied in dry run")]
+    fn legacy_state_is_adopted_on_first_load(copy_adopted: bool, copied: Vec<String>) {
+        assert_eq!(
+            get_or_adopt_in(&[("state", "legacy")], "state:staging", copy_adopted),
+            (
+                Some(FoundText {
+                    text: String::from("legacy"),
+                    adopted: true,
+                }),
+                copied
+            )
+        );
+    }
+
+    #[test_case("state:staging"; "namespaced")]
+    #[test_case("state"; "legacy")]
+    fn no_state(kv_key: &str) {
+        assert_eq!(
+            get_or_adopt_in(&[("state:production", "production")], kv_key, true),
+            (None, Vec::new())
+        );
+    }
+
+    #[test]
+    fn legacy_state_without_environment() {
+        assert_eq!(
+            get_or_adopt_in(&[("state", "legacy")], "state", true),
+            (
+                Some(FoundText {
+                    text: String::from("legacy"),
+                    adopted: false,
+                }),
+                Vec::new()
+            )
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+use worker::Env;
+use worker_kv::KvStore;
+
+use crate::{
+    log::{log_error, log_info},
+    state::{self, STATE_KV_BINDING},
+    utils,
+};
+
+const BACKUP_KV_KEY_PREFIX: &str = "state_backup";
+
+/// Backups expire after this many seconds.
+const TTL_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// At most this many backups are kept, older ones are deleted.
+pub const MAX_BACKUPS: usize = 20;
+
+/// Prefix of the keys of the backups of `environment`, see [`state::kv_key_for`].
+fn kv_key_prefix(environment: Option<&str>) -> String {
+    format!("{}:", state::kv_key_for(BACKUP_KV_KEY_PREFIX, environment))
+}
+
+/// `name` is the ISO 8601 timestamp of when the backup was made.
+fn kv_key(environment: Option<&str>, name: &str) -> String {
+    format!("{}{name}", kv_key_prefix(environment))
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+/// Names start with a year, which tells the backups without an environment apart from those
+/// of environments, as the keys of the latter also start with the prefix of the former.
+fn name_from_kv_key<'a>(environment: Option<&str>, key: &'a str) -> Option<&'a str> {
+    key.strip_prefix(&kv_key_prefix(environment))
+        .filter(|name| name.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Sorts backup `names` newest first; timestamps sort the same as the times they stand for.
+fn newest_first(mut names: Vec<String>) -> Vec<String> {
+    names.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+    names
+}
+
+/// Names of the backups beyond the newest [`MAX_BACKUPS`] ones.
+fn names_to_prune(names: Vec<String>) -> Vec<String> {
+    newest_first(names).into_iter().skip(MAX_BACKUPS).collect()
+}
+
+/// Saves `previous_state`, the state of `environment` stored in KV before it is replaced,
+/// as a new backup, and deletes its oldest backups beyond [`MAX_BACKUPS`].
+///
+/// Failures are only logged, so that they do not prevent saving the new state.
+pub async fn save(kv_store: &KvStore, environment: Option<&str>, previous_state: &str) {
+    if let Err(e) = try_save(kv_store, environment, previous_state).await {
+        log_error!("could not back up state: {e:?}");
+    }
+}
+
+async fn try_save(
+    kv_store: &KvStore,
+    environment: Option<&str>,
+    previous_state: &str,
+) -> anyhow::Result<()> {
+    let key = kv_key(environment, &utils::now_iso_string());
+
+    kv_store
+        .put(&key, previous_state)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .expiration_ttl(TTL_SECONDS)
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")?;
+
+    log_info!("backed up state to {key}");
+
+    for name in names_to_prune(names_in(kv_store, environment).await?) {
+        kv_store
+            .delete(&kv_key(environment, &name))
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .with_context(|| format!("could not delete backup {name}"))?;
+
+        log_info!("deleted backup {name}");
+    }
+
+    Ok(())
+}
+
+/// Returns the names of the backups of the configured environment, newest first.
+pub async fn names(env: &Env) -> anyhow::Result<Vec<String>> {
+    names_in(&kv_store(env)?, utils::environment(env).as_deref()).await
+}
+
+async fn names_in(kv_store: &KvStore, environment: Option<&str>) -> anyhow::Result<Vec<String>> {
+    let mut names = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let mut list = kv_store.list().prefix(kv_key_prefix(environment));
+
+        if let Some(cursor) = cursor {
+            list = list.cursor(cursor);
+        }
+
+        let response = list
+            .execute()
+            .await
+            .map_err(|e| anyhow!(e.to_string()))
+            .context("could not list backups")?;
+
+        names.extend(
+            response
+                .keys
+                .iter()
+                .filter_map(|key| name_from_kv_key(environment, &key.name))
+                .map(ToOwned::to_owned),
+        );
+
+        match response.cursor {
+            Some(next_cursor) if !response.list_complete => cursor = Some(next_cursor),
+            _ => break,
+        }
+    }
+
+    Ok(newest_first(names))
+}
+
+/// Returns the backup of the configured environment called `name`, if there is one.
+pub async fn get(env: &Env, name: &str) -> anyhow::Result<Option<Value>> {
+    let key = kv_key(utils::environment(env).as_deref(), name);
+
+    kv_store(env)?
+        .get(&key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(None, "state_backup:2022-07-01T00:00:00.000Z"; "no environment")]
+    #[test_case(Some("staging"), "state_backup:staging:2022-07-01T00:00:00.000Z"; "staging")]
+    fn key_round_trip(environment: Option<&str>, result: &str) {
+        let name = "2022-07-01T00:00:00.000Z";
+        let key = kv_key(environment, name);
+
+        assert_eq!(key, result);
+        assert_eq!(name_from_kv_key(environment, &key), Some(name));
+    }
+
+    #[test_case(None, "state"; "state")]
+    #[test_case(None, "state_backup:"; "empty name")]
+    #[test_case(None, "history_android"; "other key")]
+    #[test_case(None, "state_backup:staging:2022-07-01T00:00:00.000Z"; "of an environment")]
+    #[test_case(Some("staging"), "state_backup:2022-07-01T00:00:00.000Z"; "without an environment")]
+    #[test_case(Some("staging"), "state_backup:production:2022-07-01T00:00:00.000Z"; "of another environment")]
+    fn not_a_backup_key(environment: Option<&str>, key: &str) {
+        assert_eq!(name_from_kv_key(environment, key), None);
+    }
+
+    fn names(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|day| format!("2022-07-{:02}T00:00:00.000Z", day + 1))
+            .collect()
+    }
+
+    #[test]
+    fn nothing_to_prune() {
+        assert_eq!(names_to_prune(names(MAX_BACKUPS)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn oldest_are_pruned() {
+        let mut names = names(MAX_BACKUPS + 2);
+        names.reverse();
+
+        assert_eq!(
+            names_to_prune(names),
+            vec![
+                String::from("2022-07-02T00:00:00.000Z"),
+                String::from("2022-07-01T00:00:00.000Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn sorted_newest_first() {
+        assert_eq!(
+            newest_first(names(3)),
+            vec![
+                String::from("2022-07-03T00:00:00.000Z"),
+                String::from("2022-07-02T00:00:00.000Z"),
+                String::from("2022-07-01T00:00:00.000Z"),
+            ]
+        );
+    }
+}
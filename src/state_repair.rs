@@ -0,0 +1,331 @@
+use std::{fmt, future::Future};
+
+use anyhow::Context;
+use semver::Version;
+use strum::IntoEnumIterator;
+use worker::Env;
+
+use crate::{
+    history,
+    platform::{Platform, DEFAULT_PRERELEASE_IDENTIFIERS},
+    state::State,
+    types::github::Tag,
+    utils,
+};
+
+/// Where the tag that a field was repaired with was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagSource {
+    PostHistory,
+    GitHubTags,
+}
+
+impl fmt::Display for TagSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PostHistory => write!(f, "post history"),
+            Self::GitHubTags => write!(f, "GitHub tags"),
+        }
+    }
+}
+
+/// A change made to repair the state.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Repair {
+    pub platform: Platform,
+    pub field: &'static str,
+    pub old_tag: String,
+    pub new_tag: String,
+    pub source: TagSource,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} {} -> {} (from {})",
+            self.platform, self.field, self.old_tag, self.new_tag, self.source
+        )
+    }
+}
+
+/// Returns the newest tag of `tags` that would be posted for `platform` with the default
+/// prerelease identifiers, and whose version matches `filter`.
+fn newest_tag(platform: Platform, tags: &[Tag], filter: impl Fn(&Version) -> bool) -> Option<Tag> {
+    tags.iter()
+        .filter_map(|tag| tag.to_version().ok().map(|version| (tag, version)))
+        .filter(|(_, version)| {
+            platform.should_post_version(version, DEFAULT_PRERELEASE_IDENTIFIERS) && filter(version)
+        })
+        .max_by(|(_, lhs), (_, rhs)| lhs.cmp(rhs))
+        .map(|(tag, _)| tag.clone())
+}
+
+/// Repairs the states of the platforms in `state` that are invalid:
+/// - a `last_posted_tag` that is not a version is replaced with the newest posted tag
+///   from the post history;
+/// - a `last_posted_tag_previous_release` that is not a version, or is not before
+///   `last_posted_tag`, is replaced with the newest tag of an earlier release,
+///   from the post history or else from the tags on GitHub.
+///
+/// `history_tags` and `github_tags` are only called for invalid platforms.
+/// Fails if a platform could not be repaired, leaving `state` untouched.
+///
+/// Returns the changes made.
+pub async fn repair_with<H, HFut, G, GFut>(
+    state: &mut State,
+    history_tags: H,
+    github_tags: G,
+) -> anyhow::Result<Vec<Repair>>
+where
+    H: Fn(Platform) -> HFut,
+    HFut: Future<Output = anyhow::Result<Vec<Tag>>>,
+    G: Fn(Platform) -> GFut,
+    GFut: Future<Output = anyhow::Result<Vec<Tag>>>,
+{
+    let mut repaired_state = state.clone();
+    let mut repairs = Vec::new();
+
+    for platform in Platform::iter() {
+        let platform_state = repaired_state.platform_state_mut(platform);
+
+        if platform_state.validate().is_ok() {
+            continue;
+        }
+
+        let history = history_tags(platform)
+            .await
+            .with_context(|| format!("could not get post history for {platform}"))?;
+
+        let mut new_state = platform_state.clone();
+
+        if new_state.last_posted_tag.to_version().is_err() {
+            if let Some(tag) = newest_tag(platform, &history, |_| true) {
+                repairs.push(replace_tag(
+                    platform,
+                    "last_posted_tag",
+                    &mut new_state.last_posted_tag,
+                    tag,
+                    TagSource::PostHistory,
+                ));
+            }
+        }
+
+        if let (Ok(last_posted_version), Err(_)) =
+            (new_state.last_posted_tag.to_version(), new_state.validate())
+        {
+            let is_earlier_release = |version: &Version| {
+                (version.major, version.minor)
+                    < (last_posted_version.major, last_posted_version.minor)
+            };
+
+            let found = match newest_tag(platform, &history, is_earlier_release) {
+                Some(tag) => Some((tag, TagSource::PostHistory)),
+                None => {
+                    let tags = github_tags(platform)
+                        .await
+                        .with_context(|| format!("could not get tags of {platform}"))?;
+
+                    newest_tag(platform, &tags, is_earlier_release)
+                        .map(|tag| (tag, TagSource::GitHubTags))
+                }
+            };
+
+            if let Some((tag, source)) = found {
+                repairs.push(replace_tag(
+                    platform,
+                    "last_posted_tag_previous_release",
+                    &mut new_state.last_posted_tag_previous_release,
+                    tag,
+                    source,
+                ));
+            }
+        }
+
+        new_state
+            .validate()
+            .with_context(|| format!("could not repair state for {platform}"))?;
+
+        *platform_state = new_state;
+    }
+
+    *state = repaired_state;
+
+    Ok(repairs)
+}
+
+fn replace_tag(
+    platform: Platform,
+    field: &'static str,
+    tag: &mut Tag,
+    new_tag: Tag,
+    source: TagSource,
+) -> Repair {
+    let repair = Repair {
+        platform,
+        field,
+        old_tag: tag.name.clone(),
+        new_tag: new_tag.name.clone(),
+        source,
+    };
+
+    *tag = new_tag;
+
+    repair
+}
+
+/// Repairs `state` (see [`repair_with`]) with the post history in KV and the tags on GitHub.
+pub async fn repair(env: &Env, state: &mut State) -> anyhow::Result<Vec<Repair>> {
+    let github_token = utils::github_token(env);
+
+    repair_with(
+        state,
+        |platform| async move {
+            Ok(history::entries(env, platform)
+                .await?
+                .into_iter()
+                .map(|entry| Tag::new(entry.tag))
+                .collect())
+        },
+        |platform| {
+            let github_token = github_token.clone();
+            async move { utils::get_github_tags(github_token.as_deref(), platform).await }
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{core::poll_once, platform::Platform::*, state::PlatformState};
+
+    fn tags(names: &[&str]) -> Vec<Tag> {
+        names.iter().copied().map(Tag::new).collect()
+    }
+
+    fn state(previous_tag: &str, last_tag: &str) -> State {
+        let valid = PlatformState::initial(Tag::new("v1.1.0"), Tag::new("v1.2.3"));
+
+        State {
+            android: PlatformState::initial(Tag::new(previous_tag), Tag::new(last_tag)),
+            ios: valid.clone(),
+            desktop: valid,
+        }
+    }
+
+    fn history_tags(platform: Platform) -> std::future::Ready<anyhow::Result<Vec<Tag>>> {
+        std::future::ready(Ok(match platform {
+            Android => tags(&["v1.2.3", "v1.2.2", "v1.1.5"]),
+            Ios | Desktop => Vec::new(),
+        }))
+    }
+
+    fn no_github_tags(_platform: Platform) -> std::future::Ready<anyhow::Result<Vec<Tag>>> {
+        std::future::ready(Ok(Vec::new()))
+    }
+
+    #[test]
+    fn valid_state_is_untouched() {
+        let mut state = state("v1.1.0", "v1.2.3");
+
+        let repairs = poll_once(repair_with(
+            &mut state,
+            |_| async { panic!("should not get post history of a valid state") },
+            |_| async { panic!("should not get tags of a valid state") },
+        ))
+        .unwrap();
+
+        assert_eq!(repairs, Vec::new());
+        assert_eq!(state, self::state("v1.1.0", "v1.2.3"));
+    }
+
+    #[test_case("v1.2.3"; "same tag")]
+    #[test_case("v1.3.0"; "wrong order")]
+    #[test_case("latest"; "not a version")]
+    fn previous_release_from_history(previous_tag: &str) {
+        let mut state = state(previous_tag, "v1.2.3");
+
+        let repairs = poll_once(repair_with(&mut state, history_tags, no_github_tags)).unwrap();
+
+        assert_eq!(
+            repairs,
+            vec![Repair {
+                platform: Android,
+                field: "last_posted_tag_previous_release",
+                old_tag: previous_tag.to_string(),
+                new_tag: String::from("v1.1.5"),
+                source: TagSource::PostHistory,
+            }]
+        );
+        assert_eq!(state, self::state("v1.1.5", "v1.2.3"));
+    }
+
+    #[test]
+    fn previous_release_from_github_tags() {
+        let mut state = state("v1.2.3", "v1.2.3");
+
+        let repairs = poll_once(repair_with(
+            &mut state,
+            |_| async { Ok(tags(&["v1.2.3", "v1.2.2"])) },
+            |_| async { Ok(tags(&["v1.2.3", "v1.2.2", "v1.1.6.1", "v1.1.5", "v1.0.9"])) },
+        ))
+        .unwrap();
+
+        assert_eq!(
+            repairs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["Android: last_posted_tag_previous_release v1.2.3 -> v1.1.5 (from GitHub tags)"]
+        );
+        assert_eq!(state, self::state("v1.1.5", "v1.2.3"));
+    }
+
+    #[test]
+    fn last_posted_tag_from_history() {
+        let mut state = state("v1.1.0", "latest");
+
+        let repairs = poll_once(repair_with(&mut state, history_tags, no_github_tags)).unwrap();
+
+        assert_eq!(
+            repairs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["Android: last_posted_tag latest -> v1.2.3 (from post history)"]
+        );
+        assert_eq!(state, self::state("v1.1.0", "v1.2.3"));
+    }
+
+    #[test]
+    fn both_tags_from_history() {
+        let mut state = state("latest", "latest");
+
+        let repairs = poll_once(repair_with(&mut state, history_tags, no_github_tags)).unwrap();
+
+        assert_eq!(
+            repairs.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "Android: last_posted_tag latest -> v1.2.3 (from post history)",
+                "Android: last_posted_tag_previous_release latest -> v1.1.5 (from post history)",
+            ]
+        );
+        assert_eq!(state, self::state("v1.1.5", "v1.2.3"));
+    }
+
+    #[test_case("v1.1.0", "latest", |_| async { Ok(Vec::new()) }, "could not repair state for Android: couldn't convert last_posted_tag to a Version"; "no history for last posted tag")]
+    #[test_case("v1.2.3", "v1.2.3", |_| async { Ok(tags(&["v1.2.3"])) }, "could not repair state for Android: last_posted_version_previous_release >= last_posted_version"; "no earlier release")]
+    #[test_case("v1.2.3", "v1.2.3", |_| async { Err(anyhow!("KV is unavailable")) }, "could not get post history for Android: KV is unavailable"; "history unavailable")]
+    fn unrepairable<H, HFut>(previous_tag: &str, last_tag: &str, history_tags: H, message: &str)
+    where
+        H: Fn(Platform) -> HFut,
+        HFut: Future<Output = anyhow::Result<Vec<Tag>>>,
+    {
+        let mut state = state(previous_tag, last_tag);
+
+        let result = poll_once(repair_with(&mut state, history_tags, no_github_tags));
+
+        let error = format!("{:#}", result.unwrap_err());
+        assert!(error.starts_with(message), "{error}");
+        assert_eq!(state, self::state(previous_tag, last_tag));
+    }
+}
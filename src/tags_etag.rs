@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use worker::Env;
+use worker_kv::KvStore;
+
+use crate::{
+    log::{log_error, log_info},
+    platform::Platform,
+    state::{self, STATE_KV_BINDING},
+};
+
+const TAGS_ETAG_KV_KEY_PREFIX: &str = "tags_etag";
+
+/// ETag of the tags listing of a platform, saved once everything in it was posted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct StoredEtag {
+    etag: String,
+    /// Last posted tag at the time, so that the ETag is not used after state is changed otherwise.
+    last_posted_tag: String,
+}
+
+fn kv_key(platform: Platform) -> String {
+    format!(
+        "{TAGS_ETAG_KV_KEY_PREFIX}_{}",
+        platform.to_string().to_ascii_lowercase()
+    )
+}
+
+fn kv_store(env: &Env) -> anyhow::Result<KvStore> {
+    env.kv(STATE_KV_BINDING)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get KV store")
+}
+
+fn matching_etag(stored: Option<StoredEtag>, last_posted_tag: &str) -> Option<String> {
+    stored
+        .filter(|stored| stored.last_posted_tag == last_posted_tag)
+        .map(|stored| stored.etag)
+}
+
+/// Returns the ETag to send when getting the tags of `platform`, if it was saved
+/// while `last_posted_tag` was the last posted tag.
+///
+/// Failures are only logged, as the tags can always be gotten unconditionally.
+pub async fn get(env: &Env, platform: Platform, last_posted_tag: &str) -> Option<String> {
+    match try_get(env, platform).await {
+        Ok(stored) => matching_etag(stored, last_posted_tag),
+        Err(e) => {
+            log_error!(platform = platform, "could not get tags ETag: {e:?}");
+            None
+        }
+    }
+}
+
+async fn try_get(env: &Env, platform: Platform) -> anyhow::Result<Option<StoredEtag>> {
+    let key = state::kv_key(env, &kv_key(platform));
+
+    kv_store(env)?
+        .get(&key)
+        .json()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .with_context(|| format!("could not get value for key {key}"))
+}
+
+/// Saves `etag` of the tags of `platform`, all of which are posted up to `last_posted_tag`.
+///
+/// Failures are only logged, as the tags can always be gotten unconditionally.
+pub async fn set(env: &Env, platform: Platform, last_posted_tag: &str, etag: String) {
+    let stored = StoredEtag {
+        etag,
+        last_posted_tag: last_posted_tag.to_string(),
+    };
+
+    match try_set(env, platform, &stored).await {
+        Ok(_) => log_info!(
+            platform = platform,
+            "saved tags ETag for {platform}: {stored:?}"
+        ),
+        Err(e) => log_error!(platform = platform, "could not save tags ETag: {e:?}"),
+    }
+}
+
+async fn try_set(env: &Env, platform: Platform, stored: &StoredEtag) -> anyhow::Result<()> {
+    kv_store(env)?
+        .put(&state::kv_key(env, &kv_key(platform)), stored)
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not create request to put to KV")?
+        .execute()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not put to KV")
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn stored(last_posted_tag: &str) -> StoredEtag {
+        StoredEtag {
+            etag: String::from(r#"W/"abcdef""#),
+            last_posted_tag: last_posted_tag.to_string(),
+        }
+    }
+
+    #[test]
+    fn etag_for_same_last_posted_tag() {
+        assert_eq!(
+            matching_etag(Some(stored("v1.2.3")), "v1.2.3"),
+            Some(String::from(r#"W/"abcdef""#))
+        );
+    }
+
+    #[test]
+    fn no_etag_after_state_changed() {
+        assert_eq!(matching_etag(Some(stored("v1.2.3")), "v1.2.4"), None);
+    }
+
+    #[test]
+    fn no_etag_stored() {
+        assert_eq!(matching_etag(None, "v1.2.3"), None);
+    }
+}
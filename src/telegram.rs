@@ -0,0 +1,296 @@
+use anyhow::{bail, Context};
+use serde_json::json;
+use worker::{Env, Fetch, Method, Url};
+
+use crate::{
+    log::{log_error, log_info},
+    platform::Platform,
+    types::github::Tag,
+    utils,
+};
+
+/// Limit of the text of a message on Telegram.
+pub const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// Characters that have to be escaped everywhere in MarkdownV2, except in code and link URLs.
+///
+/// https://core.telegram.org/bots/api#markdownv2-style
+const SPECIAL_CHARS: &[char] = &[
+    '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!', '\\',
+];
+
+/// Escapes `text` to be shown as is with MarkdownV2.
+pub fn escape_markdown_v2(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        if SPECIAL_CHARS.contains(&character) {
+            result.push('\\');
+        }
+        result.push(character);
+    }
+
+    result
+}
+
+/// Escapes `url` for the URL part of an inline link, where only `)` and `\` are special.
+pub fn escape_markdown_v2_url(url: &str) -> String {
+    let mut result = String::with_capacity(url.len());
+
+    for character in url.chars() {
+        if matches!(character, ')' | '\\') {
+            result.push('\\');
+        }
+        result.push(character);
+    }
+
+    result
+}
+
+/// MarkdownV2 text of the message about the new version: a line about it, the subjects of
+/// the commits, and links to the post and the comparison on GitHub.
+///
+/// The commit summary is cut short so that the text has at most `max_chars` characters.
+pub fn message_text(
+    max_chars: usize,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commit_subjects: &[String],
+    post_url: &str,
+) -> String {
+    let commits_count = commit_subjects.len();
+    let commits_word_suffix = if commits_count == 1 { "" } else { "s" };
+
+    let header = format!(
+        "*{}* is out — {commits_count} commit{commits_word_suffix} since {}",
+        escape_markdown_v2(&format!(
+            "Signal {platform} {}",
+            new_tag.exact_version_string()
+        )),
+        escape_markdown_v2(&old_tag.exact_version_string())
+    );
+    let footer = format!(
+        "[Forum post]({}) · [Comparison on GitHub]({})",
+        escape_markdown_v2_url(post_url),
+        escape_markdown_v2_url(&platform.github_comparison_url(&old_tag.name, &new_tag.name, None))
+    );
+
+    let lines: Vec<String> = commit_subjects
+        .iter()
+        .map(|subject| format!("• {}", escape_markdown_v2(subject)))
+        .collect();
+
+    let text = |shown: usize| {
+        let mut summary = lines[..shown].to_vec();
+        if shown < lines.len() {
+            summary.push(format!("…and {} more", lines.len() - shown));
+        }
+
+        match summary.is_empty() {
+            true => format!("{header}\n\n{footer}"),
+            false => format!("{header}\n\n{}\n\n{footer}", summary.join("\n")),
+        }
+    };
+
+    (0..=lines.len())
+        .rev()
+        .map(text)
+        .find(|text| text.chars().count() <= max_chars)
+        .unwrap_or_else(|| format!("{header}\n\n{footer}"))
+}
+
+/// Sends a message about the new version to the configured Telegram chat, if any.
+///
+/// Failures are only logged, as sending the message is not essential.
+pub async fn announce(
+    env: &Env,
+    platform: Platform,
+    old_tag: &Tag,
+    new_tag: &Tag,
+    commit_subjects: &[String],
+    post_url: &str,
+) {
+    let text = message_text(
+        MAX_MESSAGE_CHARS,
+        platform,
+        old_tag,
+        new_tag,
+        commit_subjects,
+        post_url,
+    );
+
+    if let Err(e) = try_announce(env, &text).await {
+        log_error!(
+            platform = platform,
+            "could not send message to Telegram: {e:?}"
+        );
+    }
+}
+
+async fn try_announce(env: &Env, text: &str) -> anyhow::Result<()> {
+    let chat_id = match utils::telegram_chat_id(env)? {
+        Some(chat_id) => chat_id,
+        None => {
+            log_info!("no Telegram chat ID configured; not sending message");
+            return Ok(());
+        }
+    };
+
+    let bot_token = utils::telegram_bot_token(env)?;
+    let url = Url::parse(&format!(
+        "https://api.telegram.org/bot{bot_token}/sendMessage"
+    ))
+    .context("could not parse URL")?;
+
+    let body = json!({
+        "chat_id": chat_id,
+        "text": text,
+        "parse_mode": "MarkdownV2",
+    });
+
+    let request = utils::RequestBuilder::new(url, Method::Post)
+        .json(&body)
+        .build()?;
+
+    let response = utils::fetch(Fetch::Request(request)).await?;
+
+    match response.status_code() {
+        200..=299 => {
+            log_info!("sent message to Telegram");
+            Ok(())
+        }
+        status_code => bail!("Telegram responded with status code {status_code}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::platform::Platform::*;
+
+    const POST_URL: &str = "https://community.signalusers.org/t/123/45";
+
+    #[test_case("7.1.4", r"7\.1\.4"; "version")]
+    #[test_case("Bump to version 7.1.4-beta.1 (#123)", r"Bump to version 7\.1\.4\-beta\.1 \(\#123\)"; "commit subject")]
+    #[test_case(r"_*[]()~`>#+-=|{}.!\", r"\_\*\[\]\(\)\~\`\>\#\+\-\=\|\{\}\.\!\\"; "all special characters")]
+    #[test_case("Räksmörgås — “quoted” 👍", "Räksmörgås — “quoted” 👍"; "nothing to escape")]
+    #[test_case("", ""; "empty")]
+    fn escaped(text: &str, result: &str) {
+        assert_str_eq!(escape_markdown_v2(text), result);
+    }
+
+    #[test_case("https://example.org/a_(b)", r"https://example.org/a_(b\)"; "parenthesis")]
+    #[test_case(r"https://example.org/a\b", r"https://example.org/a\\b"; "backslash")]
+    #[test_case("https://github.com/signalapp/Signal-Android/compare/v7.1.3...v7.1.4", "https://github.com/signalapp/Signal-Android/compare/v7.1.3...v7.1.4"; "nothing to escape")]
+    fn url_escaped(url: &str, result: &str) {
+        assert_str_eq!(escape_markdown_v2_url(url), result);
+    }
+
+    fn subjects(subjects: &[&str]) -> Vec<String> {
+        subjects.iter().map(|subject| subject.to_string()).collect()
+    }
+
+    #[test]
+    fn message() {
+        assert_str_eq!(
+            message_text(
+                MAX_MESSAGE_CHARS,
+                Android,
+                &Tag::new("v7.1.3"),
+                &Tag::new("v7.1.4"),
+                &subjects(&["Fix crash (#1)", "Bump version to 7.1.4"]),
+                POST_URL
+            ),
+            r"*Signal Android 7\.1\.4* is out — 2 commits since 7\.1\.3
+
+• Fix crash \(\#1\)
+• Bump version to 7\.1\.4
+
+[Forum post](https://community.signalusers.org/t/123/45) · [Comparison on GitHub](https://github.com/signalapp/Signal-Android/compare/v7.1.3...v7.1.4)"
+        );
+    }
+
+    #[test]
+    fn message_without_commits() {
+        assert_str_eq!(
+            message_text(
+                MAX_MESSAGE_CHARS,
+                Ios,
+                &Tag::new("7.1.0.1-beta"),
+                &Tag::new("7.1.0.2-beta"),
+                &[],
+                POST_URL
+            ),
+            r"*Signal iOS 7\.1\.0\.2\-beta* is out — 0 commits since 7\.1\.0\.1\-beta
+
+[Forum post](https://community.signalusers.org/t/123/45) · [Comparison on GitHub](https://github.com/signalapp/Signal-iOS/compare/7.1.0.1-beta...7.1.0.2-beta)"
+        );
+    }
+
+    #[test]
+    fn summary_truncated() {
+        let subjects: Vec<String> = (1..=1000)
+            .map(|number| format!("Commit number {number}"))
+            .collect();
+
+        let text = message_text(
+            MAX_MESSAGE_CHARS,
+            Desktop,
+            &Tag::new("v7.1.0-beta.1"),
+            &Tag::new("v7.1.0-beta.2"),
+            &subjects,
+            POST_URL,
+        );
+
+        assert!(text.chars().count() <= MAX_MESSAGE_CHARS);
+        assert!(text.starts_with(r"*Signal Desktop 7\.1\.0\-beta\.2* is out — 1000 commits"));
+        assert!(text.contains("• Commit number 1\n"));
+        assert!(text.contains(" more\n\n[Forum post]"));
+        assert!(!text.contains("• Commit number 1000"));
+    }
+
+    #[test]
+    fn summary_truncated_to_fit_exactly() {
+        let subjects = subjects(&["First", "Second", "Third, with a longer subject"]);
+        let full = message_text(
+            MAX_MESSAGE_CHARS,
+            Android,
+            &Tag::new("v7.1.3"),
+            &Tag::new("v7.1.4"),
+            &subjects,
+            POST_URL,
+        );
+        let max_chars = full.chars().count() - 1;
+
+        let text = message_text(
+            max_chars,
+            Android,
+            &Tag::new("v7.1.3"),
+            &Tag::new("v7.1.4"),
+            &subjects,
+            POST_URL,
+        );
+
+        assert!(text.chars().count() <= max_chars);
+        assert!(text.contains("• First\n• Second\n…and 1 more\n\n"));
+    }
+
+    #[test]
+    fn summary_dropped_if_nothing_fits() {
+        let text = message_text(
+            10,
+            Android,
+            &Tag::new("v7.1.3"),
+            &Tag::new("v7.1.4"),
+            &subjects(&["First"]),
+            POST_URL,
+        );
+
+        assert_eq!(text.lines().count(), 3);
+        assert!(!text.contains("First"));
+    }
+}
@@ -1,24 +1,83 @@
-use std::time::Duration;
+use std::{
+    collections::HashSet,
+    fmt,
+    future::Future,
+    pin::pin,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 
 use anyhow::{anyhow, bail, Context};
+use futures_util::future::{select, Either};
 use semver::Version;
 use serde::de::DeserializeOwned;
 use serde_json::{json, Value};
-use sha2::{Digest, Sha256};
 use worker::{
-    console_error, console_log, console_warn, wasm_bindgen::JsValue, Delay, Env, Fetch, Headers,
-    Method, Request, RequestInit, Response, Url,
+    js_sys,
+    wasm_bindgen::{JsCast, JsValue},
+    AbortController, Delay, Env, Fetch, Method, Request, Response, Url,
 };
 
 use crate::{
-    platform::Platform,
+    core::{
+        comparison::{
+            collect_comparison_pages, collect_pages, commits_since, comparison_files_url,
+            complete_comparison, has_commits_since, Page,
+        },
+        error::Error,
+    },
+    hash,
+    http::{FetchClient, HttpClient, HttpResponse, PreparedRequest},
+    invocation,
+    log::{log_error, log_info, log_warn},
+    markdown::{CommitListStyle, CommitOrder, HeaderTemplate},
+    metrics,
+    platform::{
+        Platform::{self, *},
+        DEFAULT_PRERELEASE_IDENTIFIERS,
+    },
+    rate_limit,
     types::{
-        discourse::PostApiResponse,
-        github::{Commit, CommitData, Comparison},
+        discourse::{EditPostApiResponse, ErrorResponse, PostApiResponse},
+        github::{Commit, CommitData, Comparison, Release, Tag, TagCommit},
     },
 };
 
-pub const USER_AGENT: &str = "updates-bot";
+const CLIENT_NAME: &str = concat!("updates-bot/", env!("CARGO_PKG_VERSION"));
+
+const USER_AGENT: &str = concat!(
+    "updates-bot/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/u32i64/signalupdates-bot)"
+);
+
+/// Name and version of the bot, e.g. `updates-bot/0.1.0`.
+pub fn client_name() -> &'static str {
+    CLIENT_NAME
+}
+
+/// User agent of every request made by the bot, [`client_name`] followed by where it comes from.
+pub fn user_agent() -> &'static str {
+    USER_AGENT
+}
+
+const GITHUB_API_HOST: &str = "api.github.com";
+const GITHUB_API_VERSION: &str = "2022-11-28";
+
+/// Delay before the first retry of a transient failure, doubled for each further retry.
+const RETRY_INITIAL_DELAY: Duration = Duration::from_millis(500);
+/// At most this many bytes of the body of an unexpected response are included in errors.
+const MAX_ERROR_BODY_BYTES: usize = 500;
+
+/// At most this much time is spent waiting between retries, to stay within the
+/// time limits of a Worker invocation.
+const RETRY_DELAY_BUDGET: Duration = Duration::from_secs(10);
+
+/// Requests time out after this many seconds, unless `REQUEST_TIMEOUT_SECONDS` is set.
+const DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 20;
+
+/// Seconds after which a request times out, see [`configure_requests`].
+static REQUEST_TIMEOUT_SECONDS: AtomicU64 = AtomicU64::new(DEFAULT_REQUEST_TIMEOUT_SECONDS);
 
 #[derive(Debug)]
 enum StringBindingKind {
@@ -41,51 +100,494 @@ fn get_env_string(env: &Env, kind: StringBindingKind, name: &str) -> anyhow::Res
         .ok_or_else(|| anyhow!("couldn't get value of string binding"))
 }
 
+/// Another handle to `env`, e.g. for work that outlives the handler, as `Env` is not `Clone`.
+pub fn owned_env(env: &Env) -> Env {
+    JsValue::from(env).unchecked_into()
+}
+
 pub fn api_key(env: &Env) -> anyhow::Result<String> {
     get_env_string(env, Secret, "DISCOURSE_API_KEY")
 }
 
+/// Returns `None` if the secret is not set, in which case GitHub is accessed anonymously.
+pub fn github_token(env: &Env) -> Option<String> {
+    get_env_string(env, Secret, "GITHUB_TOKEN")
+        .ok()
+        .filter(|string| !string.is_empty())
+}
+
+/// Returns the values of the secrets that are set, so that they can be kept out of logs.
+pub fn secret_values(env: &Env) -> Vec<String> {
+    [
+        "DISCOURSE_API_KEY",
+        "GITHUB_TOKEN",
+        "NOTIFY_WEBHOOK_SECRET",
+        "TRIGGER_TOKEN",
+        "MASTODON_ACCESS_TOKEN",
+        "MATRIX_ACCESS_TOKEN",
+        "BLUESKY_APP_PASSWORD",
+        "TELEGRAM_BOT_TOKEN",
+    ]
+    .into_iter()
+    .filter_map(|name| get_env_string(env, Secret, name).ok())
+    .filter(|value| !value.is_empty())
+    .collect()
+}
+
 pub fn topic_id_override(env: &Env) -> anyhow::Result<Option<u64>> {
     get_env_string(env, Var, "TOPIC_ID_OVERRIDE").map(|string| string.parse().ok())
 }
 
+/// Name of the environment (e.g. `production`) that the values in KV are kept separately for,
+/// or `None` if the var is not set.
+pub fn environment(env: &Env) -> Option<String> {
+    get_env_string(env, Var, "ENVIRONMENT")
+        .ok()
+        .filter(|string| !string.is_empty())
+}
+
 pub fn is_dry_run(env: &Env) -> anyhow::Result<bool> {
     get_env_string(env, Var, "DRY_RUN").map(|string| string == "true")
 }
 
+pub fn show_commit_verification(env: &Env) -> anyhow::Result<bool> {
+    get_env_string(env, Var, "SHOW_COMMIT_VERIFICATION").map(|string| string == "true")
+}
+
+pub fn show_cycle_stats(env: &Env) -> anyhow::Result<bool> {
+    get_env_string(env, Var, "SHOW_CYCLE_STATS").map(|string| string == "true")
+}
+
+pub fn show_contributors(env: &Env) -> anyhow::Result<bool> {
+    get_env_string(env, Var, "SHOW_CONTRIBUTORS").map(|string| string == "true")
+}
+
+pub fn post_empty_comparisons(env: &Env) -> anyhow::Result<bool> {
+    get_env_string(env, Var, "POST_EMPTY_COMPARISONS").map(|string| string == "true")
+}
+
+/// Whether posts compared from a re-cut tag are edited, which is on unless set to `false`.
+pub fn edit_post_for_moved_tag(env: &Env) -> anyhow::Result<bool> {
+    get_env_string(env, Var, "EDIT_POST_FOR_MOVED_TAG").map(|string| string != "false")
+}
+
+pub fn cron_platforms(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "CRON_PLATFORMS")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+/// Minimum time between two posts for the same platform, or `None` if posts are not throttled.
+pub fn min_post_interval_minutes(env: &Env) -> anyhow::Result<Option<u64>> {
+    let string = get_env_string(env, Var, "MIN_POST_INTERVAL_MINUTES")?;
+
+    match string.as_str() {
+        "" => Ok(None),
+        string => string
+            .parse()
+            .map(Some)
+            .context("could not parse MIN_POST_INTERVAL_MINUTES"),
+    }
+}
+
+/// Hours without a successful run after which a failing run alerts that the bot may be down.
+const DEFAULT_DOWN_ALERT_THRESHOLD_HOURS: u64 = 6;
+
+pub fn down_alert_threshold_hours(env: &Env) -> anyhow::Result<u64> {
+    let string = get_env_string(env, Var, "DOWN_ALERT_THRESHOLD_HOURS")?;
+
+    match string.as_str() {
+        "" => Ok(DEFAULT_DOWN_ALERT_THRESHOLD_HOURS),
+        string => string
+            .parse()
+            .context("could not parse DOWN_ALERT_THRESHOLD_HOURS"),
+    }
+}
+
+/// Lists in posts longer than this are collapsed, or `None` for the default.
+pub fn collapse_threshold(env: &Env) -> anyhow::Result<Option<usize>> {
+    let string = get_env_string(env, Var, "COLLAPSE_THRESHOLD")?;
+
+    match string.as_str() {
+        "" => Ok(None),
+        string => string
+            .parse()
+            .map(Some)
+            .context("could not parse COLLAPSE_THRESHOLD"),
+    }
+}
+
+/// Prerelease identifiers of the versions that are posted for `platform`
+/// (see [`Platform::should_post_version`]), from e.g. `DESKTOP_PRERELEASE_IDENTIFIERS`.
+pub fn prerelease_identifiers(env: &Env, platform: Platform) -> anyhow::Result<Vec<String>> {
+    match platform {
+        Android => Ok(Vec::new()),
+        Ios | Desktop => get_env_string(
+            env,
+            Var,
+            &format!(
+                "{}_PRERELEASE_IDENTIFIERS",
+                platform.to_string().to_ascii_uppercase()
+            ),
+        )
+        .map(|string| parse_prerelease_identifiers(&string)),
+    }
+}
+
+/// Topics that posts for `platform` are also made in, from `{PLATFORM}_ADDITIONAL_TOPIC_IDS`
+/// (comma-separated).
+pub fn additional_topic_ids(env: &Env, platform: Platform) -> anyhow::Result<Vec<u64>> {
+    let name = format!(
+        "{}_ADDITIONAL_TOPIC_IDS",
+        platform.to_string().to_ascii_uppercase()
+    );
+    let string = get_env_string(env, Var, &name)?;
+
+    parse_topic_ids(&string).with_context(|| format!("invalid {name}"))
+}
+
+fn parse_topic_ids(string: &str) -> anyhow::Result<Vec<u64>> {
+    let mut topic_ids = Vec::new();
+
+    for item in string
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let topic_id = item
+            .parse()
+            .with_context(|| format!("invalid topic id {item}"))?;
+
+        if !topic_ids.contains(&topic_id) {
+            topic_ids.push(topic_id);
+        }
+    }
+
+    Ok(topic_ids)
+}
+
+/// Post listing every release of `platform`, from `{PLATFORM}_RELEASE_HISTORY_POST_ID`,
+/// or `None` if it is not set.
+pub fn release_history_post_id(env: &Env, platform: Platform) -> anyhow::Result<Option<u64>> {
+    let name = format!(
+        "{}_RELEASE_HISTORY_POST_ID",
+        platform.to_string().to_ascii_uppercase()
+    );
+
+    match get_env_string(env, Var, &name)?.trim() {
+        "" => Ok(None),
+        string => string
+            .parse()
+            .map(Some)
+            .with_context(|| format!("invalid {name}")),
+    }
+}
+
+/// Parses comma-separated prerelease identifiers, or returns
+/// [`DEFAULT_PRERELEASE_IDENTIFIERS`] if there are none.
+fn parse_prerelease_identifiers(string: &str) -> Vec<String> {
+    let identifiers: Vec<String> = string
+        .split(',')
+        .map(str::trim)
+        .filter(|identifier| !identifier.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect();
+
+    if identifiers.is_empty() {
+        DEFAULT_PRERELEASE_IDENTIFIERS
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    } else {
+        identifiers
+    }
+}
+
+/// Subject prefixes of dependency bumps, comma-separated, or `None` for the default.
+pub fn dependency_bump_prefixes(env: &Env) -> anyhow::Result<Option<Vec<String>>> {
+    get_env_string(env, Var, "DEPENDENCY_BUMP_PREFIXES").map(|string| {
+        Some(
+            string
+                .split(',')
+                .map(str::trim)
+                .filter(|prefix| !prefix.is_empty())
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>(),
+        )
+        .filter(|prefixes| !prefixes.is_empty())
+    })
+}
+
+pub fn inline_dependency_bumps(env: &Env) -> anyhow::Result<bool> {
+    get_env_string(env, Var, "INLINE_DEPENDENCY_BUMPS").map(|string| string == "true")
+}
+
+/// Order of the commits in posts for `platform`, oldest first unless e.g.
+/// `DESKTOP_COMMIT_ORDER` is `newest-first`.
+pub fn commit_order(env: &Env, platform: Platform) -> anyhow::Result<CommitOrder> {
+    let name = format!("{}_COMMIT_ORDER", platform.to_string().to_ascii_uppercase());
+    let string = get_env_string(env, Var, &name)?;
+
+    match string.as_str() {
+        "" => Ok(CommitOrder::default()),
+        string => {
+            CommitOrder::from_name(string).with_context(|| format!("unknown {name} {string}"))
+        }
+    }
+}
+
+/// Wording of the heading of posts for `platform`, if `{PLATFORM}_POST_HEADER_TEMPLATE` is set.
+pub fn post_header_template(
+    env: &Env,
+    platform: Platform,
+) -> anyhow::Result<Option<HeaderTemplate>> {
+    let name = format!(
+        "{}_POST_HEADER_TEMPLATE",
+        platform.to_string().to_ascii_uppercase()
+    );
+    let string = get_env_string(env, Var, &name)?;
+
+    match string.as_str() {
+        "" => Ok(None),
+        string => HeaderTemplate::parse(string)
+            .map(Some)
+            .with_context(|| format!("invalid {name}")),
+    }
+}
+
+/// How the commits of posts are listed, bullets unless `COMMIT_LIST_STYLE` is `table`.
+pub fn commit_list_style(env: &Env) -> anyhow::Result<CommitListStyle> {
+    let string = get_env_string(env, Var, "COMMIT_LIST_STYLE")?;
+
+    match string.as_str() {
+        "" => Ok(CommitListStyle::default()),
+        string => CommitListStyle::from_name(string)
+            .with_context(|| format!("unknown COMMIT_LIST_STYLE {string}")),
+    }
+}
+
+/// Pending posts older than this are dropped instead of retried.
+const DEFAULT_PENDING_POST_TTL_MINUTES: u64 = 24 * 60;
+
+pub fn pending_post_ttl_minutes(env: &Env) -> anyhow::Result<u64> {
+    let string = get_env_string(env, Var, "PENDING_POST_TTL_MINUTES")?;
+
+    match string.as_str() {
+        "" => Ok(DEFAULT_PENDING_POST_TTL_MINUTES),
+        string => string
+            .parse()
+            .context("could not parse PENDING_POST_TTL_MINUTES"),
+    }
+}
+
+/// Subrequests after which a run stops starting work on platforms, from `SUBREQUEST_SOFT_CAP`;
+/// `None` if it is not set.
+pub fn subrequest_soft_cap(env: &Env) -> anyhow::Result<Option<usize>> {
+    match get_env_string(env, Var, "SUBREQUEST_SOFT_CAP")?.trim() {
+        "" => Ok(None),
+        string => string
+            .parse()
+            .map(Some)
+            .context("could not parse SUBREQUEST_SOFT_CAP"),
+    }
+}
+
+/// Seconds that `GET` responses from each host are cached for, from `HTTP_CACHE_TTLS`
+/// (comma-separated `host=seconds`); responses from other hosts are not cached.
+pub fn http_cache_ttls(env: &Env) -> anyhow::Result<Vec<(String, u64)>> {
+    parse_http_cache_ttls(&get_env_string(env, Var, "HTTP_CACHE_TTLS")?)
+        .context("invalid HTTP_CACHE_TTLS")
+}
+
+fn parse_http_cache_ttls(string: &str) -> anyhow::Result<Vec<(String, u64)>> {
+    string
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (host, seconds) = item
+                .split_once('=')
+                .with_context(|| format!("expected host=seconds, got {item}"))?;
+            let seconds = seconds
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid number of seconds for {}", host.trim()))?;
+
+            Ok((host.trim().to_ascii_lowercase(), seconds))
+        })
+        .collect()
+}
+
+pub fn request_timeout_seconds(env: &Env) -> anyhow::Result<u64> {
+    let string = get_env_string(env, Var, "REQUEST_TIMEOUT_SECONDS")?;
+
+    match string.as_str() {
+        "" => Ok(DEFAULT_REQUEST_TIMEOUT_SECONDS),
+        string => string
+            .parse()
+            .context("could not parse REQUEST_TIMEOUT_SECONDS"),
+    }
+}
+
+/// Applies `REQUEST_TIMEOUT_SECONDS` to the requests of this run.
+///
+/// Failures are only logged, keeping the default timeout.
+pub fn configure_requests(env: &Env) {
+    let seconds = request_timeout_seconds(env).unwrap_or_else(|e| {
+        log_error!("{e:?}");
+        DEFAULT_REQUEST_TIMEOUT_SECONDS
+    });
+
+    REQUEST_TIMEOUT_SECONDS.store(seconds, Ordering::Relaxed);
+
+    rate_limit::configure(github_rate_limit_floor(env).unwrap_or_else(|e| {
+        log_error!("{e:?}");
+        None
+    }));
+}
+
+/// GitHub rate limit remaining below which a warning is logged and the least important
+/// requests are skipped, or `None` if headroom is only recorded.
+pub fn github_rate_limit_floor(env: &Env) -> anyhow::Result<Option<u64>> {
+    let string = get_env_string(env, Var, "GITHUB_RATE_LIMIT_FLOOR")?;
+
+    match string.as_str() {
+        "" => Ok(None),
+        string => string
+            .parse()
+            .map(Some)
+            .context("could not parse GITHUB_RATE_LIMIT_FLOOR"),
+    }
+}
+
+fn request_timeout() -> Duration {
+    Duration::from_secs(REQUEST_TIMEOUT_SECONDS.load(Ordering::Relaxed))
+}
+
+/// Returns `None` if the var is empty, in which case errors are not reported.
+pub fn sentry_dsn(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "SENTRY_DSN")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+pub fn log_level(env: &Env) -> anyhow::Result<String> {
+    get_env_string(env, Var, "LOG_LEVEL")
+}
+
+pub fn notify_webhook_url(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "NOTIFY_WEBHOOK_URL")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+pub fn notify_webhook_secret(env: &Env) -> anyhow::Result<String> {
+    get_env_string(env, Secret, "NOTIFY_WEBHOOK_SECRET")
+}
+
+pub fn trigger_token(env: &Env) -> anyhow::Result<String> {
+    get_env_string(env, Secret, "TRIGGER_TOKEN")
+}
+
+pub fn mastodon_base_url(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "MASTODON_BASE_URL")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+pub fn mastodon_access_token(env: &Env) -> anyhow::Result<String> {
+    get_env_string(env, Secret, "MASTODON_ACCESS_TOKEN")
+}
+
+/// Returns `None` if the secret is not set, in which case nothing is posted to Bluesky.
+pub fn bluesky_identifier(env: &Env) -> Option<String> {
+    get_env_string(env, Secret, "BLUESKY_IDENTIFIER")
+        .ok()
+        .filter(|string| !string.is_empty())
+}
+
+pub fn bluesky_app_password(env: &Env) -> anyhow::Result<String> {
+    get_env_string(env, Secret, "BLUESKY_APP_PASSWORD")
+}
+
+pub fn bluesky_service_url(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "BLUESKY_SERVICE_URL")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+pub fn matrix_homeserver_url(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "MATRIX_HOMESERVER_URL")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+/// ID of the room that notices about new posts are sent to, e.g. `!abc:example.org`.
+pub fn matrix_room_id(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "MATRIX_ROOM_ID")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+pub fn matrix_access_token(env: &Env) -> anyhow::Result<String> {
+    get_env_string(env, Secret, "MATRIX_ACCESS_TOKEN")
+}
+
+/// ID of the chat that messages about new posts are sent to, e.g. `@signalupdates`.
+pub fn telegram_chat_id(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "TELEGRAM_CHAT_ID")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
+pub fn telegram_bot_token(env: &Env) -> anyhow::Result<String> {
+    get_env_string(env, Secret, "TELEGRAM_BOT_TOKEN")
+}
+
+pub fn failure_notification_username(env: &Env) -> anyhow::Result<Option<String>> {
+    get_env_string(env, Var, "FAILURE_NOTIFICATION_USERNAME")
+        .map(|string| Some(string).filter(|string| !string.is_empty()))
+}
+
 pub async fn get_topic_id(
+    client: &impl HttpClient,
     api_key: &str,
     platform: Platform,
     version: &Version,
 ) -> anyhow::Result<Option<u64>> {
-    console_log!("getting topic id for version {version}");
+    log_info!(
+        platform = platform,
+        "getting topic id for version {version}"
+    );
 
     let url =
         Url::parse(&platform.discourse_topic_slug_url(version)).context("could not parse URL")?;
 
-    let request = create_request(url, Method::Get, None, Some(api_key))?;
-    let response: crate::types::discourse::TopicResponse = get_json_from_request(request).await?;
+    let request = RequestBuilder::new(url, Method::Get)
+        .discourse_api_key(Some(api_key))
+        .prepare();
+
+    // Discourse responds with 404 and an `error_type` if there is no such topic
+    let response: crate::types::discourse::TopicResponse =
+        send_for_json(client, request, &[404]).await?;
 
     match (&response.post_stream, &response.error_type) {
         (Some(post_stream), _) => match post_stream.posts.first() {
             Some(post) => Ok(Some(post.topic_id)),
             None => {
-                console_error!("response = {:?}", response);
+                log_error!(platform = platform, "response = {:?}", response);
                 bail!("no posts in topic")
             }
         },
         (None, Some(error_type)) if error_type == "not_found" => {
-            console_warn!("topic not found, response = {:?}", response);
+            log_warn!(
+                platform = platform,
+                "topic not found, response = {:?}",
+                response
+            );
             Ok(None)
         }
         (None, _) => {
-            console_error!("response = {:?}", response);
+            log_error!(platform = platform, "response = {:?}", response);
             bail!("discourse API request likely failed")
         }
     }
 }
 
 pub async fn get_topic_id_or_override(
+    client: &impl HttpClient,
     env: &Env,
     api_key: &str,
     platform: Platform,
@@ -93,258 +595,2399 @@ pub async fn get_topic_id_or_override(
 ) -> anyhow::Result<Option<u64>> {
     match topic_id_override(env)? {
         Some(id) => {
-            console_warn!("using topic id override: {id}");
+            log_warn!(platform = platform, "using topic id override: {id}");
             Ok(Some(id))
         }
-        None => get_topic_id(api_key, platform, version)
+        None => get_topic_id(client, api_key, platform, version)
             .await
             .context("could not find topic_id"),
     }
 }
 
-pub async fn get_json_from_url<T: DeserializeOwned>(url: &str) -> anyhow::Result<T> {
-    let url = Url::parse(url).context("could not parse URL")?;
-    let request = create_request(url, Method::Get, None, None)?;
-    json_from_configuration(Fetch::Request(request)).await
+/// Response to a conditional request.
+pub enum Conditional<T> {
+    Modified { value: T, etag: Option<String> },
+    NotModified,
 }
 
-pub async fn get_json_from_request<T: DeserializeOwned>(request: Request) -> anyhow::Result<T> {
-    json_from_configuration(Fetch::Request(request)).await
-}
+/// Gets JSON from `url` with `client`, unless it still has the given `etag`.
+pub async fn get_json_from_url_if_modified<T: DeserializeOwned>(
+    client: &impl HttpClient,
+    url: &str,
+    github_token: Option<&str>,
+    etag: Option<&str>,
+) -> anyhow::Result<Conditional<T>> {
+    let parsed_url = Url::parse(url).context("could not parse URL")?;
+    let mut builder = RequestBuilder::new(parsed_url, Method::Get).github_token(github_token);
 
-async fn json_from_configuration<T: DeserializeOwned>(configuration: Fetch) -> anyhow::Result<T> {
-    let mut response = fetch(configuration).await?;
-    json_from_response(&mut response).await
-}
+    if let Some(etag) = etag {
+        builder = builder.header("If-None-Match", etag);
+    }
 
-async fn fetch(configuration: Fetch) -> anyhow::Result<Response> {
-    let result = configuration
-        .send()
-        .await
-        .map_err(|e| anyhow!(e.to_string()))
-        .context("could not fetch");
+    let request = builder.prepare();
 
-    if let Ok(response) = &result {
-        console_log!("response.status_code() = {}", response.status_code());
+    let response =
+        retry_with_backoff(|| client.send(request.clone()), is_transient_http_failure).await?;
+
+    if response.status_code == 304 {
+        return Ok(Conditional::NotModified);
     }
 
-    result
+    rate_limit::check_http(&response)?;
+
+    let etag = response.header("ETag").map(str::to_string);
+    let value = json_from_body(response.status_code, url, &response.body, &[])?;
+
+    Ok(Conditional::Modified { value, etag })
 }
 
-async fn json_from_response<T: DeserializeOwned>(response: &mut Response) -> anyhow::Result<T> {
-    response
-        .json()
-        .await
-        .map_err(|e| anyhow!(e.to_string()))
-        .context("could not get JSON")
+pub async fn get_json_from_request<T: DeserializeOwned>(request: Request) -> anyhow::Result<T> {
+    let url = request_url(&request)?;
+    let mut response = fetch(Fetch::Request(request)).await?;
+    json_from_response(&mut response, &url, &[]).await
 }
 
-pub fn create_request(
-    url: Url,
-    method: Method,
-    body: Option<Value>,
-    discourse_api_key: Option<&str>,
-) -> anyhow::Result<Request> {
-    console_log!("constructing request for url {url}");
+/// Sends `request` with `client` and parses the response, also if it has one of the
+/// `accepted_status_codes` instead of 2xx.
+async fn send_for_json<T: DeserializeOwned>(
+    client: &impl HttpClient,
+    request: PreparedRequest,
+    accepted_status_codes: &[u16],
+) -> anyhow::Result<T> {
+    let url = request.url.clone();
+    let response = client.send(request).await?;
+    json_from_body(
+        response.status_code,
+        &url,
+        &response.body,
+        accepted_status_codes,
+    )
+}
 
-    let mut headers = Headers::new();
+fn request_url(request: &Request) -> anyhow::Result<String> {
+    request
+        .url()
+        .map(|url| url.to_string())
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get request URL")
+}
 
-    if let Some(api_key) = discourse_api_key {
-        headers.set("User-Api-Key", api_key).unwrap();
+/// Resolves to the output of `future`, or to `None` if `timeout` resolves first.
+async fn with_timeout<T>(
+    future: impl Future<Output = T>,
+    timeout: impl Future<Output = ()>,
+) -> Option<T> {
+    match select(pin!(future), pin!(timeout)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
     }
+}
 
-    headers.set("Content-Type", "application/json").unwrap();
-    headers.set("Accept", "application/json").unwrap();
-    headers.set("User-Agent", USER_AGENT).unwrap();
-
-    let mut request_init = RequestInit::new();
-    request_init.with_method(method).with_headers(headers);
+/// Sends a request, failing with [`TimedOut`] and aborting it if there is no response
+/// within the configured timeout.
+pub async fn fetch(configuration: Fetch) -> anyhow::Result<Response> {
+    let url = match &configuration {
+        Fetch::Url(url) => Some(url.clone()),
+        Fetch::Request(request) => request.url().ok(),
+    };
 
-    if let Some(body) = body {
-        request_init.with_body(Some(JsValue::from_str(&body.to_string())));
+    if let Some(url) = &url {
+        count_request(url);
     }
 
-    Request::new_with_init(url.as_ref(), &request_init)
-        .map_err(|e| anyhow!(e.to_string()))
-        .context("could not create request")
-}
-
-pub async fn get_github_comparison(
-    platform: Platform,
-    old_tag: &str,
-    new_tag: &str,
-) -> anyhow::Result<Comparison> {
-    console_log!("getting comparison between {old_tag} and {new_tag} for {platform} from GitHub");
+    let is_github = url
+        .as_ref()
+        .is_some_and(|url| url.host_str() == Some(GITHUB_API_HOST));
 
-    let initial_url = platform.github_api_comparison_url(old_tag, new_tag);
+    let timeout = request_timeout();
+    let controller = AbortController::default();
+    let signal = controller.signal();
 
-    let comparison = get_paginated_github_response(
-        &initial_url,
-        Comparison {
-            total_commits: 0,
-            commits: Vec::new(),
-            files: Some(Vec::new()),
-        },
-        |target, source| {
-            target.total_commits = source.total_commits; // always the total number of commits
-            target.commits.append(&mut source.commits);
-            if let Some(part_files) = &mut source.files {
-                target.files.as_mut().unwrap().append(part_files);
-            }
-        },
+    let result = match with_timeout(
+        configuration.send_with_signal(&signal),
+        Delay::from(timeout),
     )
-    .await?;
+    .await
+    {
+        Some(result) => result
+            .map_err(|e| Error::TransientNetwork {
+                message: e.to_string(),
+            })
+            .context("could not fetch"),
+        None => {
+            controller.abort();
 
-    if comparison.total_commits != comparison.commits.len() {
-        bail!(
-            "incomplete full comparison: total_commits = {} but commits.len() = {}, commits = {:?}",
-            comparison.total_commits,
-            comparison.commits.len(),
-            comparison.commits
-        )
+            Err(TimedOut {
+                url: url.map(|url| url.to_string()).unwrap_or_default(),
+                timeout,
+            }
+            .into())
+        }
     };
 
-    Ok(comparison)
-}
+    if let Ok(response) = &result {
+        log_info!("response.status_code() = {}", response.status_code());
 
-pub async fn get_github_commit(platform: Platform, sha: &str) -> anyhow::Result<Commit> {
-    console_log!("getting commit {sha} for {platform} from GitHub");
+        if is_github {
+            rate_limit::observe(response);
+        }
+    }
 
-    let initial_url = platform.github_api_commit_url(sha);
+    result
+}
 
-    let commit = get_paginated_github_response(
-        &initial_url,
-        Commit {
-            sha: sha.to_string(),
-            commit: CommitData {
-                message: String::new(),
-            },
-            files: Some(Vec::new()),
-        },
-        |target, source| {
-            target.sha = source.sha.clone();
-            target.commit = source.commit.clone();
-            if let Some(part_files) = &mut source.files {
-                target.files.as_mut().unwrap().append(part_files);
-            }
-        },
-    )
-    .await?;
+/// A request that got no response in time.
+#[derive(Debug)]
+pub struct TimedOut {
+    pub url: String,
+    pub timeout: Duration,
+}
 
-    Ok(commit)
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "request to {} timed out after {} seconds",
+            self.url,
+            self.timeout.as_secs()
+        )
+    }
 }
 
-/// `merge`: `Fn(&mut target, &mut source)`
-pub async fn get_paginated_github_response<T, F>(
-    initial_url: &str,
-    initial_result: T,
-    merge: F,
-) -> anyhow::Result<T>
-where
-    T: DeserializeOwned,
-    F: Fn(&mut T, &mut T),
-{
-    console_log!("getting paginated response from GitHub");
+impl std::error::Error for TimedOut {}
 
-    let mut page = 1;
-    let per_page = 100;
+/// Counts a request to `url` for the current invocation, see [`subrequest_count`].
+fn count_request(url: &Url) {
+    let is_github = url.host_str() == Some(GITHUB_API_HOST);
 
-    let mut url_string = format!("{initial_url}?page={page}&per_page={per_page}");
+    invocation::with(|invocation| {
+        if let Some(invocation) = invocation {
+            invocation.subrequests.set(invocation.subrequests.get() + 1);
 
-    let mut result: T = initial_result;
+            if is_github {
+                invocation
+                    .github_requests
+                    .set(invocation.github_requests.get() + 1);
+            }
+        }
+    });
 
-    loop {
-        console_log!("getting page = {page}, url = {url_string}");
+    if is_github {
+        metrics::incr("github_requests", metrics::Dimensions::default());
+    }
+}
 
-        let url = Url::parse(&url_string).context("could not parse URL")?;
-        let request = create_request(url, Method::Get, None, None)?;
+/// Number of requests made to the GitHub API by the current invocation.
+pub fn github_request_count() -> usize {
+    invocation::with(|invocation| {
+        invocation.map_or(0, |invocation| invocation.github_requests.get())
+    })
+}
 
-        let mut response = fetch(Fetch::Request(request))
-            .await
-            .context("could not fetch from GitHub")?;
+/// Number of subrequests (of any kind) made by the current invocation, which Cloudflare
+/// limits per invocation.
+pub fn subrequest_count() -> usize {
+    invocation::with(|invocation| invocation.map_or(0, |invocation| invocation.subrequests.get()))
+}
 
-        let mut part: T = json_from_response(&mut response)
-            .await
-            .context("could not get JSON for part")?;
+/// Fetches a clone of `request`, so that it can be fetched again when retrying.
+async fn fetch_request(request: &Request) -> anyhow::Result<Response> {
+    let request = request
+        .clone()
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not clone request")?;
 
-        merge(&mut result, &mut part);
+    fetch(Fetch::Request(request)).await
+}
 
-        let link_header_string = match response.headers().get("Link").unwrap() {
-            Some(header_string) => header_string,
-            None => {
-                console_warn!(
-                    "no `Link` header in GitHub's response, likely done getting paginated response"
-                );
-                break;
-            }
-        };
+fn is_transient_status(status_code: u16) -> bool {
+    (500..=599).contains(&status_code)
+}
+
+/// Server errors and retriable errors, e.g. network-level ones (including timeouts),
+/// are likely transient, client errors are not.
+fn is_transient_failure(result: &anyhow::Result<Response>) -> bool {
+    match result {
+        Ok(response) => is_transient_status(response.status_code()),
+        Err(e) => is_retriable(e),
+    }
+}
+
+/// Like [`is_transient_failure`], for responses of an [`HttpClient`].
+fn is_transient_http_failure(result: &anyhow::Result<HttpResponse>) -> bool {
+    match result {
+        Ok(response) => is_transient_status(response.status_code),
+        Err(e) => is_retriable(e),
+    }
+}
+
+/// Returns the delay before retry number `retry` (starting at 0), jittered with `random`
+/// in `[0, 1)` so that retries of concurrent requests do not all happen at once.
+fn backoff_delay(retry: u32, random: f64) -> Duration {
+    (RETRY_INITIAL_DELAY * 2u32.saturating_pow(retry)).mul_f64(0.5 + random / 2.0)
+}
+
+/// Runs `operation` again while `should_retry` is true for its output, waiting with
+/// exponential backoff in between, for at most [`RETRY_DELAY_BUDGET`] in total.
+///
+/// Returns the output of the last attempt.
+pub async fn retry_with_backoff<T, F, Fut>(operation: F, should_retry: impl Fn(&T) -> bool) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    retry_with_backoff_using(operation, should_retry, js_sys::Math::random, |duration| {
+        log_warn!("transient failure, retrying in {duration:?}");
+        Delay::from(duration)
+    })
+    .await
+}
+
+async fn retry_with_backoff_using<T, F, Fut, S, SFut>(
+    mut operation: F,
+    should_retry: impl Fn(&T) -> bool,
+    mut random: impl FnMut() -> f64,
+    mut sleep: S,
+) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+    S: FnMut(Duration) -> SFut,
+    SFut: Future<Output = ()>,
+{
+    let mut waited = Duration::ZERO;
+    let mut retry = 0;
+
+    loop {
+        let output = operation().await;
+
+        if !should_retry(&output) {
+            return output;
+        }
+
+        let delay = backoff_delay(retry, random());
+
+        if waited + delay > RETRY_DELAY_BUDGET {
+            return output;
+        }
+
+        sleep(delay).await;
+
+        waited += delay;
+        retry += 1;
+    }
+}
+
+/// Parses the body of `response` to `url`, failing with the status code and the start
+/// of the body if it has a status code that is neither 2xx nor one of `accepted_status_codes`.
+async fn json_from_response<T: DeserializeOwned>(
+    response: &mut Response,
+    url: &str,
+    accepted_status_codes: &[u16],
+) -> anyhow::Result<T> {
+    let body = response
+        .text()
+        .await
+        .map_err(|e| anyhow!(e.to_string()))
+        .context("could not get response body")?;
+
+    json_from_body(response.status_code(), url, &body, accepted_status_codes)
+}
+
+fn json_from_body<T: DeserializeOwned>(
+    status_code: u16,
+    url: &str,
+    body: &str,
+    accepted_status_codes: &[u16],
+) -> anyhow::Result<T> {
+    if !(200..=299).contains(&status_code) && !accepted_status_codes.contains(&status_code) {
+        return Err(UnexpectedStatus {
+            url: url.to_string(),
+            status_code,
+            body_excerpt: body_excerpt(body).to_string(),
+        }
+        .into());
+    }
+
+    serde_json::from_str(body).with_context(|| {
+        format!(
+            "could not get JSON from {url} (status code {status_code}), body = {:?}",
+            body_excerpt(body)
+        )
+    })
+}
+
+/// A response with a status code that is neither 2xx nor otherwise accepted.
+#[derive(Debug)]
+pub struct UnexpectedStatus {
+    pub url: String,
+    pub status_code: u16,
+    pub body_excerpt: String,
+}
+
+impl fmt::Display for UnexpectedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} responded with status code {}, body = {:?}",
+            self.url, self.status_code, self.body_excerpt
+        )
+    }
+}
+
+impl std::error::Error for UnexpectedStatus {}
+
+/// Classifies `error` by the first cause in its chain that tells what went wrong,
+/// or `None` if nothing in it does.
+pub fn classify_error(error: &anyhow::Error) -> Option<Error> {
+    error.chain().find_map(|cause| {
+        if let Some(error) = cause.downcast_ref::<Error>() {
+            Some(error.clone())
+        } else if let Some(exceeded) = cause.downcast_ref::<rate_limit::RateLimitExceeded>() {
+            Some(Error::from(exceeded.clone()))
+        } else if let Some(timed_out) = cause.downcast_ref::<TimedOut>() {
+            Some(Error::TransientNetwork {
+                message: timed_out.to_string(),
+            })
+        } else {
+            cause
+                .downcast_ref::<UnexpectedStatus>()
+                .filter(|unexpected| is_transient_status(unexpected.status_code))
+                .map(|unexpected| Error::TransientNetwork {
+                    message: unexpected.to_string(),
+                })
+        }
+    })
+}
+
+/// Indicates whether trying again later may get past `error`, see [`Error::is_retriable`].
+pub fn is_retriable(error: &anyhow::Error) -> bool {
+    classify_error(error).is_some_and(|error| error.is_retriable())
+}
+
+/// Indicates whether `error` was caused by the forum being read-only.
+pub fn is_forum_read_only(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| matches!(cause.downcast_ref::<Error>(), Some(Error::ForumReadOnly)))
+}
+
+/// Whether a Discourse response with `status_code` and `body` means that the forum is read-only:
+/// status code 503, or the `read_only_mode` error type.
+fn is_read_only_response(status_code: u16, body: &str) -> bool {
+    status_code == 503
+        || serde_json::from_str::<ErrorResponse>(body)
+            .is_ok_and(|response| response.error_type.as_deref() == Some("read_only_mode"))
+}
+
+/// Indicates whether `error` was caused by a response with status code 404.
+pub fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<UnexpectedStatus>())
+        .any(|unexpected| unexpected.status_code == 404)
+}
+
+/// Returns at most the first [`MAX_ERROR_BODY_BYTES`] of `body`.
+fn body_excerpt(body: &str) -> &str {
+    let mut end = body.len().min(MAX_ERROR_BODY_BYTES);
+
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &body[..end]
+}
+
+/// Returns the default headers for a request to `url`.
+///
+/// Each credential is only included for requests to the host it is meant for.
+fn request_headers(
+    url: &Url,
+    discourse_api_key: Option<&str>,
+    github_token: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    let mut headers = vec![
+        ("Content-Type", String::from("application/json")),
+        ("User-Agent", String::from(user_agent())),
+    ];
+
+    if url.host_str() == Some(GITHUB_API_HOST) {
+        headers.push(("Accept", String::from("application/vnd.github+json")));
+        headers.push(("X-GitHub-Api-Version", String::from(GITHUB_API_VERSION)));
+
+        if let Some(token) = github_token {
+            headers.push(("Authorization", format!("Bearer {token}")));
+        }
+    } else {
+        headers.push(("Accept", String::from("application/json")));
+
+        if let Some(api_key) = discourse_api_key {
+            headers.push(("User-Api-Key", api_key.to_string()));
+        }
+    }
+
+    headers
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RequestBody {
+    Json(String),
+    Text(String),
+}
+
+/// Builds a request with the headers of [`request_headers`], followed by the ones added
+/// with [`RequestBuilder::header`], each replacing an earlier header of the same name.
+pub struct RequestBuilder<'a> {
+    url: Url,
+    method: Method,
+    discourse_api_key: Option<&'a str>,
+    github_token: Option<&'a str>,
+    headers: Vec<(&'static str, String)>,
+    body: Option<RequestBody>,
+}
+
+impl<'a> RequestBuilder<'a> {
+    pub fn new(url: Url, method: Method) -> Self {
+        Self {
+            url,
+            method,
+            discourse_api_key: None,
+            github_token: None,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// Authenticates the request with `api_key` if it is to Discourse.
+    pub fn discourse_api_key(mut self, api_key: Option<&'a str>) -> Self {
+        self.discourse_api_key = api_key;
+        self
+    }
+
+    /// Authenticates the request with `token` if it is to the GitHub API.
+    pub fn github_token(mut self, token: Option<&'a str>) -> Self {
+        self.github_token = token;
+        self
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub fn json(mut self, body: &Value) -> Self {
+        self.body = Some(RequestBody::Json(body.to_string()));
+        self
+    }
+
+    /// Sends `body` as plain text, unless a `Content-Type` header says otherwise.
+    pub fn text(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(RequestBody::Text(body.into()));
+        self
+    }
 
-        let link_header = parse_link_header::parse_with_rel(&link_header_string)
-            .context("could not parse `Link` header")?;
+    /// Returns the headers and the body of the request.
+    fn parts(&self) -> (Vec<(&'static str, String)>, Option<&str>) {
+        let mut headers = request_headers(&self.url, self.discourse_api_key, self.github_token);
 
-        match link_header.get("next") {
-            Some(link) => {
-                url_string = link.raw_uri.clone();
-                page += 1;
+        if let Some(RequestBody::Text(_)) = self.body {
+            headers.push(("Content-Type", String::from("text/plain; charset=utf-8")));
+        }
+
+        headers.extend(self.headers.iter().cloned());
+
+        let mut unique_headers: Vec<(&'static str, String)> = Vec::new();
+        for (name, value) in headers {
+            match unique_headers
+                .iter_mut()
+                .find(|(unique_name, _)| unique_name.eq_ignore_ascii_case(name))
+            {
+                Some(header) => header.1 = value,
+                None => unique_headers.push((name, value)),
             }
-            None => {
-                console_log!("no `next` link, done getting full response");
-                break;
+        }
+
+        let body = self.body.as_ref().map(|body| match body {
+            RequestBody::Json(body) | RequestBody::Text(body) => body.as_str(),
+        });
+
+        (unique_headers, body)
+    }
+
+    pub fn prepare(self) -> PreparedRequest {
+        log_info!("constructing request for url {}", self.url);
+
+        let (headers, body) = self.parts();
+        let body = body.map(str::to_string);
+
+        PreparedRequest {
+            method: self.method,
+            url: self.url.to_string(),
+            headers,
+            body,
+        }
+    }
+
+    pub fn build(self) -> anyhow::Result<Request> {
+        self.prepare().into_request()
+    }
+}
+
+/// Builds a request like [`RequestBuilder`] does, with the arguments that requests used to be
+/// created with; prefer the builder.
+#[allow(dead_code)]
+pub fn create_request(
+    url: Url,
+    method: Method,
+    body: Option<Value>,
+    discourse_api_key: Option<&str>,
+    github_token: Option<&str>,
+    extra_headers: &[(&'static str, String)],
+) -> anyhow::Result<Request> {
+    let mut builder = RequestBuilder::new(url, method)
+        .discourse_api_key(discourse_api_key)
+        .github_token(github_token);
+
+    if let Some(body) = body {
+        builder = builder.json(&body);
+    }
+
+    extra_headers
+        .iter()
+        .fold(builder, |builder, (name, value)| {
+            builder.header(name, value)
+        })
+        .build()
+}
+
+pub async fn get_github_comparison(
+    client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    old_tag: &str,
+    new_tag: &str,
+) -> anyhow::Result<Comparison> {
+    log_info!(
+        platform = platform,
+        "getting comparison between {old_tag} and {new_tag} for {platform} from GitHub"
+    );
+
+    let initial_url = Url::parse(&platform.github_api_comparison_url(old_tag, new_tag))
+        .context("could not parse URL")?;
+
+    metrics::incr(
+        "github_comparisons",
+        metrics::Dimensions::platform(platform),
+    );
+
+    let mut comparison = collect_comparison_pages(&initial_url, |url_string| {
+        metrics::incr(
+            "github_comparison_pages",
+            metrics::Dimensions::platform(platform),
+        );
+        get_github_page(client, github_token, url_string)
+    })
+    .await?;
+
+    if comparison.commits.len() < comparison.total_commits
+        && comparison.total_commits > Comparison::GITHUB_API_MAX_COMMITS
+    {
+        log_warn!(platform = platform,
+            "comparison has {} commits, more than GitHub returns; getting them from the list of commits instead",
+            comparison.total_commits
+        );
+
+        let merge_base_sha = comparison
+            .merge_base_commit
+            .as_ref()
+            .map(|commit| commit.sha.clone())
+            .context("no merge_base_commit in comparison")?;
+
+        comparison.commits = get_github_commits_since(
+            client,
+            github_token,
+            platform,
+            new_tag,
+            &merge_base_sha,
+            comparison.total_commits,
+        )
+        .await
+        .context("could not get commits from the list of commits")?;
+    }
+
+    complete_comparison(comparison)
+}
+
+/// Gets the files changed between `old_tag` and `new_tag` with a single request, however
+/// many commits there are in between; only the first commit of the comparison is included.
+pub async fn get_github_comparison_files(
+    client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    old_tag: &str,
+    new_tag: &str,
+) -> anyhow::Result<Comparison> {
+    log_info!(
+        platform = platform,
+        "getting files changed between {old_tag} and {new_tag} for {platform} from GitHub"
+    );
+
+    let initial_url = Url::parse(&platform.github_api_comparison_url(old_tag, new_tag))
+        .context("could not parse URL")?;
+
+    metrics::incr(
+        "github_comparison_pages",
+        metrics::Dimensions::platform(platform),
+    );
+
+    let page: Page<Comparison> =
+        get_github_page(client, github_token, comparison_files_url(&initial_url))
+            .await
+            .context("could not get files of comparison")?;
+
+    Ok(page.part)
+}
+
+/// Gets the commits reachable from `new_tag` that come after `merge_base_sha`, oldest first,
+/// expecting `total_commits` of them.
+async fn get_github_commits_since(
+    client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    new_tag: &str,
+    merge_base_sha: &str,
+    total_commits: usize,
+) -> anyhow::Result<Vec<Commit>> {
+    log_info!(
+        platform = platform,
+        "getting commits of {new_tag} since {merge_base_sha} for {platform} from GitHub"
+    );
+
+    let commits = get_paginated_github_response_until(
+        client,
+        github_token,
+        &platform.github_api_commits_url(new_tag, None),
+        Vec::new(),
+        |target: &mut Vec<Commit>, source| target.append(source),
+        |commits| has_commits_since(commits, merge_base_sha, total_commits),
+    )
+    .await?;
+
+    commits_since(commits, merge_base_sha)
+}
+
+/// Returns the SHAs of the commits of `comparison` (between some tag and `new_tag`)
+/// that change files under `path`.
+pub async fn get_github_comparison_shas_touching(
+    client: &impl HttpClient,
+    github_token: Option<&str>,
+    platform: Platform,
+    new_tag: &str,
+    comparison: &Comparison,
+    path: &str,
+) -> anyhow::Result<Vec<String>> {
+    log_info!(
+        platform = platform,
+        "getting commits of {new_tag} that touch {path} for {platform} from GitHub"
+    );
+
+    let comparison_shas: HashSet<&str> = comparison
+        .commits
+        .iter()
+        .map(|commit| commit.sha.as_str())
+        .collect();
+
+    // newest first, so the commits before the comparison follow after all of its commits
+    let commits = get_paginated_github_response_until(
+        client,
+        github_token,
+        &platform.github_api_commits_url(new_tag, Some(path)),
+        Vec::new(),
+        |target: &mut Vec<Commit>, source| target.append(source),
+        |commits| {
+            commits
+                .iter()
+                .any(|commit| !comparison_shas.contains(commit.sha.as_str()))
+        },
+    )
+    .await?;
+
+    Ok(commits
+        .into_iter()
+        .map(|commit| commit.sha)
+        .filter(|sha| comparison_shas.contains(sha.as_str()))
+        .collect())
+}
+
+/// Gets the tags of `platform` from GitHub.
+pub async fn get_github_tags(
+    github_token: Option<&str>,
+    platform: Platform,
+) -> anyhow::Result<Vec<Tag>> {
+    match get_json_from_url_if_modified(
+        &FetchClient,
+        &platform.github_api_tags_url(),
+        github_token,
+        None,
+    )
+    .await?
+    {
+        Conditional::Modified { value: tags, .. } => Ok(tags),
+        Conditional::NotModified => bail!("tags were not modified despite no ETag being sent"),
+    }
+}
+
+/// Indicates whether `tag` is among the tags of `platform`.
+pub async fn github_tag_exists(
+    github_token: Option<&str>,
+    platform: Platform,
+    tag: &str,
+) -> anyhow::Result<bool> {
+    Ok(get_github_tags(github_token, platform)
+        .await?
+        .iter()
+        .any(|other| other.name == tag))
+}
+
+/// Gets the commit that `tag` currently points at.
+///
+/// Returns `None` if there is no such tag.
+pub async fn get_tag_sha(
+    github_token: Option<&str>,
+    platform: Platform,
+    tag: &str,
+) -> anyhow::Result<Option<String>> {
+    log_info!(
+        platform = platform,
+        "getting commit of {tag} for {platform} from GitHub"
+    );
+
+    let url = platform.github_api_commit_url(tag);
+    let parsed_url = Url::parse(&url).context("could not parse URL")?;
+    let request = RequestBuilder::new(parsed_url, Method::Get)
+        .github_token(github_token)
+        .build()?;
+
+    let mut response = retry_with_backoff(|| fetch_request(&request), is_transient_failure).await?;
+
+    // GitHub responds with 422 if the ref does not resolve to a commit
+    if matches!(response.status_code(), 404 | 422) {
+        return Ok(None);
+    }
+
+    rate_limit::check(&response)?;
+
+    json_from_response::<TagCommit>(&mut response, &url, &[])
+        .await
+        .map(|commit| Some(commit.sha))
+}
+
+/// Returns `None` if there is no release for `tag`.
+pub async fn get_github_release(
+    github_token: Option<&str>,
+    platform: Platform,
+    tag: &str,
+) -> anyhow::Result<Option<Release>> {
+    log_info!(
+        platform = platform,
+        "getting release for {tag} for {platform} from GitHub"
+    );
+
+    let url = platform.github_api_release_url(tag);
+    let parsed_url = Url::parse(&url).context("could not parse URL")?;
+    let request = RequestBuilder::new(parsed_url, Method::Get)
+        .github_token(github_token)
+        .build()?;
+
+    let mut response = retry_with_backoff(|| fetch_request(&request), is_transient_failure).await?;
+
+    if response.status_code() == 404 {
+        return Ok(None);
+    }
+
+    rate_limit::check(&response)?;
+
+    json_from_response(&mut response, &url, &[]).await.map(Some)
+}
+
+pub async fn get_github_commit(
+    github_token: Option<&str>,
+    platform: Platform,
+    sha: &str,
+) -> anyhow::Result<Commit> {
+    log_info!(
+        platform = platform,
+        "getting commit {sha} for {platform} from GitHub"
+    );
+
+    let initial_url = platform.github_api_commit_url(sha);
+
+    let commit = get_paginated_github_response(
+        github_token,
+        &initial_url,
+        Commit {
+            sha: sha.to_string(),
+            commit: CommitData {
+                message: String::new(),
+                author: None,
+                verification: None,
+            },
+            author: None,
+            files: Some(Vec::new()),
+        },
+        |target, source| {
+            target.sha = source.sha.clone();
+            target.commit = source.commit.clone();
+            if let Some(part_files) = &mut source.files {
+                target.files.as_mut().unwrap().append(part_files);
             }
+        },
+    )
+    .await?;
+
+    Ok(commit)
+}
+
+/// `merge`: `Fn(&mut target, &mut source)`
+pub async fn get_paginated_github_response<T, F>(
+    github_token: Option<&str>,
+    initial_url: &str,
+    initial_result: T,
+    merge: F,
+) -> anyhow::Result<T>
+where
+    T: DeserializeOwned,
+    F: Fn(&mut T, &mut T),
+{
+    get_paginated_github_response_until(
+        &FetchClient,
+        github_token,
+        initial_url,
+        initial_result,
+        merge,
+        |_| false,
+    )
+    .await
+}
+
+/// Like [`get_paginated_github_response`], but stops early once `is_complete` returns `true`.
+async fn get_paginated_github_response_until<T, F>(
+    client: &impl HttpClient,
+    github_token: Option<&str>,
+    initial_url: &str,
+    initial_result: T,
+    merge: F,
+    is_complete: impl Fn(&T) -> bool,
+) -> anyhow::Result<T>
+where
+    T: DeserializeOwned,
+    F: Fn(&mut T, &mut T),
+{
+    log_info!("getting paginated response from GitHub");
+
+    let per_page = 100;
+
+    let mut url = Url::parse(initial_url).context("could not parse URL")?;
+    url.query_pairs_mut()
+        .append_pair("page", "1")
+        .append_pair("per_page", &per_page.to_string());
+
+    collect_pages(
+        url.to_string(),
+        initial_result,
+        merge,
+        is_complete,
+        |url_string| get_github_page(client, github_token, url_string),
+    )
+    .await
+}
+
+async fn get_github_page<T: DeserializeOwned>(
+    client: &impl HttpClient,
+    github_token: Option<&str>,
+    url_string: String,
+) -> anyhow::Result<Page<T>> {
+    log_info!("getting page, url = {url_string}");
+
+    let url = Url::parse(&url_string).context("could not parse URL")?;
+    let request = RequestBuilder::new(url, Method::Get)
+        .github_token(github_token)
+        .prepare();
+
+    let response = retry_with_backoff(|| client.send(request.clone()), is_transient_http_failure)
+        .await
+        .context("could not fetch from GitHub")?;
+
+    rate_limit::check_http(&response)?;
+
+    let part = json_from_body(response.status_code, &url_string, &response.body, &[])
+        .context("could not get JSON for part")?;
+
+    let link_header = response.header("Link").map(str::to_string);
+
+    if link_header.is_none() {
+        log_info!("no `Link` header in GitHub's response, this is the only page");
+    }
+
+    Ok(Page { part, link_header })
+}
+
+/// Current time as an ISO 8601 string, e.g. `2022-07-01T12:34:56.789Z`.
+pub fn now_iso_string() -> String {
+    js_sys::Date::new_0().to_iso_string().into()
+}
+
+/// Time `seconds` from now as an ISO 8601 string, in the same format as [`now_iso_string`].
+pub fn iso_string_in(seconds: u64) -> String {
+    let millis = js_sys::Date::now() + (seconds * 1000) as f64;
+    js_sys::Date::new(&millis.into()).to_iso_string().into()
+}
+
+/// Time `seconds` ago as an ISO 8601 string, in the same format as [`now_iso_string`].
+pub fn iso_string_ago(seconds: u64) -> String {
+    let millis = js_sys::Date::now() - (seconds * 1000) as f64;
+    js_sys::Date::new(&millis.into()).to_iso_string().into()
+}
+
+/// Hexadecimal string of `words` random 32-bit words, from `random` numbers in `0.0..1.0`.
+pub fn random_hex_string(words: usize, mut random: impl FnMut() -> f64) -> String {
+    (0..words)
+        .map(|_| format!("{:08x}", (random() * f64::from(u32::MAX)) as u32))
+        .collect()
+}
+
+pub fn discourse_post_url(topic_id: u64, post_number: u64) -> String {
+    format!("https://community.signalusers.org/t/{topic_id}/{post_number}")
+}
+
+pub fn archiving_post_markdown(new_topic_id: u64) -> String {
+    format!(
+        "Beta testing for this release has concluded. If you find any further bugs related to this release or earlier releases, please report them on GitHub (read https://community.signalusers.org/t/27 for more information on how to do that).
+
+If you have feedback specifically related to the new beta version, please post it in the following topic: https://community.signalusers.org/t/{new_topic_id}."
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoursePost {
+    pub id: u64,
+    pub number: u64,
+}
+
+/// Makes a post in Discourse.
+///
+/// If Discourse rejects it as too similar to a recent post (e.g. a hotfix listing the same
+/// commits as the beta before it), it is tried once more with [`unique_markdown_text`].
+///
+/// If successful, returns the post ID and number.
+pub async fn post_to_discourse(
+    client: &impl HttpClient,
+    markdown_text: &str,
+    api_key: &str,
+    topic_id: u64,
+    reply_to_post_number: Option<u64>,
+) -> anyhow::Result<DiscoursePost> {
+    let body = |raw: &str| {
+        json!({
+            "topic_id": topic_id,
+            "reply_to_post_number": reply_to_post_number,
+            "raw": raw,
+        })
+    };
+
+    let create = |raw: String| async move {
+        create_post_in_discourse(client, body(&raw), api_key, Some((topic_id, raw.as_str()))).await
+    };
+
+    match create(markdown_text.to_string()).await {
+        Err(e) if is_too_similar(&e) => {
+            log_warn!(
+                "discourse rejected the post to topic_id = {topic_id} as too similar to a recent one, retrying once with a content hash footer"
+            );
+
+            create(unique_markdown_text(markdown_text))
+                .await
+                .context("could not post even with a content hash footer")
         }
+        result => result,
     }
+}
+
+/// `markdown_text` with a hidden footer of its hash, so that Discourse does not consider it
+/// the same as an otherwise identical recent post.
+pub fn unique_markdown_text(markdown_text: &str) -> String {
+    format!(
+        "{markdown_text}\n\n<!-- content hash: {} -->",
+        hash::sha256_string(markdown_text)
+    )
+}
 
-    Ok(result)
+/// Indicates whether `error` was caused by Discourse rejecting a post as too similar
+/// to a recent one, which it does with status code 422 and only a message to tell it apart.
+fn is_too_similar(error: &anyhow::Error) -> bool {
+    matches!(
+        classify_error(error),
+        Some(Error::DiscourseRejected {
+            status_code: 422,
+            errors,
+            ..
+        }) if errors.iter().any(|error| error.contains("too similar"))
+    )
 }
 
-pub fn sha256_string(input: &str) -> String {
-    let result = Sha256::digest(input.as_bytes());
-    base16ct::lower::encode_string(&result)
+/// Sends a private message in Discourse to `target_recipients` (comma-separated usernames).
+///
+/// If successful, returns the post ID and number.
+pub async fn send_private_message_in_discourse(
+    client: &impl HttpClient,
+    title: &str,
+    markdown_text: &str,
+    api_key: &str,
+    target_recipients: &str,
+) -> anyhow::Result<DiscoursePost> {
+    let body = json!({
+        "title": title,
+        "raw": markdown_text,
+        "target_recipients": target_recipients,
+        "archetype": "private_message",
+    });
+
+    create_post_in_discourse(client, body, api_key, None).await
+}
+
+/// Posts in a topic that are looked at for one with the same contents before a post is retried.
+const RECENT_POSTS_CHECKED: u64 = 3;
+
+/// Finds a post with `raw`, as is or with a content hash footer (see [`unique_markdown_text`]),
+/// among the last [`RECENT_POSTS_CHECKED`] posts of the topic with `topic_id`,
+/// e.g. one made by an earlier request that only seemed to fail.
+pub async fn find_recent_post_in_discourse(
+    client: &impl HttpClient,
+    api_key: &str,
+    topic_id: u64,
+    raw: &str,
+) -> anyhow::Result<Option<DiscoursePost>> {
+    let url = Url::parse(&format!(
+        "https://community.signalusers.org/t/{topic_id}.json"
+    ))
+    .context("could not parse URL")?;
+
+    let request = RequestBuilder::new(url, Method::Get)
+        .discourse_api_key(Some(api_key))
+        .prepare();
+    let topic: crate::types::discourse::TopicResponse = send_for_json(client, request, &[])
+        .await
+        .context("could not get topic")?;
+
+    let raws = [raw.trim().to_string(), unique_markdown_text(raw)];
+
+    let highest_post_number = topic
+        .highest_post_number
+        .context("discourse API response did not include the highest post number")?;
+
+    for post_number in (1..=highest_post_number)
+        .rev()
+        .take(RECENT_POSTS_CHECKED as usize)
+    {
+        let url = Url::parse(&format!(
+            "https://community.signalusers.org/posts/by_number/{topic_id}/{post_number}.json"
+        ))
+        .context("could not parse URL")?;
+
+        let request = RequestBuilder::new(url, Method::Get)
+            .discourse_api_key(Some(api_key))
+            .prepare();
+
+        let post: crate::types::discourse::PostRawResponse =
+            match send_for_json(client, request, &[]).await {
+                Ok(post) => post,
+                // deleted posts keep their number
+                Err(e) if is_not_found(&e) => continue,
+                Err(e) => return Err(e.context("could not get post")),
+            };
+
+        if post
+            .raw
+            .is_some_and(|post_raw| raws.iter().any(|raw| raw == post_raw.trim()))
+        {
+            if let (Some(id), Some(number)) = (post.id, post.post_number) {
+                return Ok(Some(DiscoursePost { id, number }));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Response to an attempt at creating a post.
+enum PostAttempt {
+    Sent(anyhow::Result<HttpResponse>),
+    /// The post was found to be made already by an earlier attempt.
+    AlreadyPosted(DiscoursePost),
+}
+
+/// Sends `request` to create a post, unless this `is_retry` and the post is already among
+/// the latest ones of `topic`, i.e. its ID and raw contents.
+async fn attempt_post(
+    client: &impl HttpClient,
+    request: PreparedRequest,
+    api_key: &str,
+    topic: Option<(u64, &str)>,
+    is_retry: bool,
+) -> PostAttempt {
+    if let (Some((topic_id, raw)), true) = (topic, is_retry) {
+        match find_recent_post_in_discourse(client, api_key, topic_id, raw).await {
+            Ok(Some(posted)) => {
+                log_warn!("earlier attempt already made post = {posted:?}, not posting again");
+                return PostAttempt::AlreadyPosted(posted);
+            }
+            Ok(None) => {}
+            // without knowing whether the post was made, it is not made again
+            Err(e) => return PostAttempt::Sent(Err(e)),
+        }
+    }
+
+    PostAttempt::Sent(client.send(request).await)
+}
+
+/// Creates a post with `body`.
+///
+/// Creating a post is not idempotent, and a request that fails (e.g. times out) may still
+/// have made the post. So posts in a `topic` (its ID and the raw contents of the post)
+/// are only retried after checking that the post is not among the latest ones, and
+/// private messages, which start a new topic each, are not retried.
+async fn create_post_in_discourse(
+    client: &impl HttpClient,
+    body: Value,
+    api_key: &str,
+    topic: Option<(u64, &str)>,
+) -> anyhow::Result<DiscoursePost> {
+    let url = Url::parse("https://community.signalusers.org/posts.json")
+        .context("could not parse URL")?;
+
+    let request = RequestBuilder::new(url, Method::Post)
+        .discourse_api_key(Some(api_key))
+        .json(&body)
+        .prepare();
+
+    let mut attempts = 0;
+
+    // nothing can be posted until maintenance is over, so this is left to the next run
+    let attempt = retry_with_backoff(
+        || {
+            attempts += 1;
+            attempt_post(client, request.clone(), api_key, topic, attempts > 1)
+        },
+        |attempt| match attempt {
+            PostAttempt::Sent(result) => {
+                topic.is_some()
+                    && is_transient_http_failure(result)
+                    && !result.as_ref().is_ok_and(|response| {
+                        is_read_only_response(response.status_code, &response.body)
+                    })
+            }
+            PostAttempt::AlreadyPosted(_) => false,
+        },
+    )
+    .await;
+
+    let response = match attempt {
+        PostAttempt::Sent(result) => result?,
+        PostAttempt::AlreadyPosted(posted) => return Ok(posted),
+    };
+
+    let unexpected_status = || {
+        format!(
+            "{} responded with status code {}, body = {:?}",
+            request.url,
+            response.status_code,
+            body_excerpt(&response.body)
+        )
+    };
+
+    if is_read_only_response(response.status_code, &response.body) {
+        return Err(Error::ForumReadOnly).with_context(unexpected_status);
+    }
+
+    if (400..=499).contains(&response.status_code) {
+        let (error_type, errors) = match serde_json::from_str::<ErrorResponse>(&response.body) {
+            Ok(error_response) => (error_response.error_type, error_response.errors),
+            Err(_) => (None, Vec::new()),
+        };
+
+        return Err(Error::DiscourseRejected {
+            status_code: response.status_code,
+            error_type,
+            errors,
+        })
+        .with_context(unexpected_status);
+    }
+
+    let api_response: PostApiResponse =
+        json_from_body(response.status_code, &request.url, &response.body, &[])?;
+
+    match (api_response.id, api_response.post_number) {
+        (Some(id), Some(number)) => Ok(DiscoursePost { id, number }),
+        _ => {
+            log_error!("api_response = {:?}", api_response);
+            bail!("discourse API response did not include the post ID and number, posting likely failed")
+        }
+    }
+}
+
+/// Raw contents of a post in Discourse, along with its version, which each edit increments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoursePostRaw {
+    pub raw: String,
+    pub version: u64,
+}
+
+/// Gets the raw contents of an existing post in Discourse.
+pub async fn get_post_raw_in_discourse(
+    client: &impl HttpClient,
+    api_key: &str,
+    post_id: u64,
+) -> anyhow::Result<DiscoursePostRaw> {
+    let url = Url::parse(&format!(
+        "https://community.signalusers.org/posts/{post_id}.json"
+    ))
+    .context("could not parse URL")?;
+
+    get_post_raw(client, api_key, url).await
+}
+
+/// Gets the raw contents of the post with `post_number` in the topic with `topic_id`.
+pub async fn get_post_raw_by_number_in_discourse(
+    client: &impl HttpClient,
+    api_key: &str,
+    topic_id: u64,
+    post_number: u64,
+) -> anyhow::Result<DiscoursePostRaw> {
+    let url = Url::parse(&format!(
+        "https://community.signalusers.org/posts/by_number/{topic_id}/{post_number}.json"
+    ))
+    .context("could not parse URL")?;
+
+    get_post_raw(client, api_key, url).await
 }
 
-pub fn archiving_post_markdown(new_topic_id: u64) -> String {
-    format!(
-        "Beta testing for this release has concluded. If you find any further bugs related to this release or earlier releases, please report them on GitHub (read https://community.signalusers.org/t/27 for more information on how to do that).
+async fn get_post_raw(
+    client: &impl HttpClient,
+    api_key: &str,
+    url: Url,
+) -> anyhow::Result<DiscoursePostRaw> {
+    let request = RequestBuilder::new(url, Method::Get)
+        .discourse_api_key(Some(api_key))
+        .prepare();
+    let response: crate::types::discourse::PostRawResponse =
+        send_for_json(client, request, &[]).await?;
 
-If you have feedback specifically related to the new beta version, please post it in the following topic: https://community.signalusers.org/t/{new_topic_id}."
-    )
+    match (response.raw, response.version) {
+        (Some(raw), Some(version)) => Ok(DiscoursePostRaw { raw, version }),
+        _ => bail!("discourse API response did not include the raw post and its version"),
+    }
 }
 
-/// Makes a post in Discourse.
-///
-/// If successful, returns the post number.
-pub async fn post_to_discourse(
+/// Replaces the contents of an existing post in Discourse.
+pub async fn edit_post_in_discourse(
     markdown_text: &str,
     api_key: &str,
-    topic_id: u64,
-    reply_to_post_number: Option<u64>,
-) -> anyhow::Result<u64> {
-    let url = Url::parse("https://community.signalusers.org/posts.json")
-        .context("could not parse URL")?;
+    post_id: u64,
+    edit_reason: &str,
+) -> anyhow::Result<()> {
+    let url = Url::parse(&format!(
+        "https://community.signalusers.org/posts/{post_id}.json"
+    ))
+    .context("could not parse URL")?;
 
     let body = json!({
-        "topic_id": topic_id,
-        "reply_to_post_number": reply_to_post_number,
-        "raw": markdown_text,
+        "post": {
+            "raw": markdown_text,
+            "edit_reason": edit_reason,
+        }
     });
 
-    let request = create_request(url, Method::Post, Some(body), Some(api_key))?;
-    let api_response: PostApiResponse = get_json_from_request(request).await?;
+    let request = RequestBuilder::new(url, Method::Put)
+        .discourse_api_key(Some(api_key))
+        .json(&body)
+        .build()?;
+    let api_response: EditPostApiResponse = get_json_from_request(request).await?;
 
-    match api_response.post_number {
-        Some(number) => Ok(number),
+    match api_response.post {
+        Some(_) => Ok(()),
         None => {
-            console_error!("api_response = {:?}", api_response);
-            bail!("discourse API response did not include the post number, posting likely failed")
+            log_error!("api_response = {:?}", api_response);
+            bail!("discourse API response did not include the post, editing likely failed")
         }
     }
 }
 
 /// Asynchronously waits for the specified number of milliseconds.
 pub async fn delay(milliseconds: u64) {
-    console_log!("waiting {milliseconds} milliseconds");
+    log_info!("waiting {milliseconds} milliseconds");
 
     Delay::from(Duration::from_millis(milliseconds)).await;
 
-    console_log!("done waiting {milliseconds} milliseconds");
+    log_info!("done waiting {milliseconds} milliseconds");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use pretty_assertions::{assert_eq, assert_str_eq};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{core::poll_once, http::FakeClient, invocation::Invocation, types::github::File};
+
+    #[test_case("", &[]; "none")]
+    #[test_case("123", &[123]; "one")]
+    #[test_case(" 123, 456 ,,123", &[123, 456]; "spaces, empty items and duplicates")]
+    fn topic_ids(string: &str, result: &[u64]) {
+        assert_eq!(parse_topic_ids(string).unwrap(), result);
+    }
+
+    #[test]
+    fn invalid_topic_ids() {
+        assert_eq!(
+            parse_topic_ids("123, abc").unwrap_err().to_string(),
+            "invalid topic id abc"
+        );
+    }
+
+    #[test_case("", &[]; "none")]
+    #[test_case("api.github.com=60", &[("api.github.com", 60)]; "one")]
+    #[test_case(" API.github.com = 60 ,community.signalusers.org=300,", &[("api.github.com", 60), ("community.signalusers.org", 300)]; "spaces, case and empty items")]
+    fn http_cache_ttls(string: &str, result: &[(&str, u64)]) {
+        let result: Vec<(String, u64)> = result
+            .iter()
+            .map(|(host, seconds)| (host.to_string(), *seconds))
+            .collect();
+
+        assert_eq!(parse_http_cache_ttls(string).unwrap(), result);
+    }
+
+    #[test_case("api.github.com", "expected host=seconds, got api.github.com"; "no seconds")]
+    #[test_case("api.github.com=a minute", "invalid number of seconds for api.github.com"; "invalid seconds")]
+    fn invalid_http_cache_ttls(string: &str, message: &str) {
+        assert_eq!(
+            parse_http_cache_ttls(string).unwrap_err().to_string(),
+            message
+        );
+    }
+
+    #[test_case("", &["beta"]; "default")]
+    #[test_case("beta,alpha", &["beta", "alpha"]; "several")]
+    #[test_case(" beta , RC ,", &["beta", "rc"]; "spaces, case and empty items")]
+    #[test_case(",", &["beta"]; "only separators")]
+    fn prerelease_identifiers(string: &str, result: &[&str]) {
+        assert_eq!(parse_prerelease_identifiers(string), result);
+    }
+
+    /// Fails with status code 502 `failures` times, then succeeds.
+    struct FakeFetcher {
+        failures: usize,
+        attempts: Cell<usize>,
+    }
+
+    impl FakeFetcher {
+        fn new(failures: usize) -> Self {
+            Self {
+                failures,
+                attempts: Cell::new(0),
+            }
+        }
+
+        async fn fetch(&self) -> u16 {
+            self.attempts.set(self.attempts.get() + 1);
+
+            if self.attempts.get() <= self.failures {
+                502
+            } else {
+                200
+            }
+        }
+    }
+
+    fn retry(fetcher: &FakeFetcher, random: f64, delays: &RefCell<Vec<Duration>>) -> u16 {
+        poll_once(retry_with_backoff_using(
+            || fetcher.fetch(),
+            |status_code| is_transient_status(*status_code),
+            || random,
+            |duration| {
+                delays.borrow_mut().push(duration);
+                std::future::ready(())
+            },
+        ))
+    }
+
+    #[test]
+    fn response_before_timeout() {
+        let fetcher = FakeFetcher::new(0);
+
+        assert_eq!(
+            poll_once(with_timeout(fetcher.fetch(), std::future::pending())),
+            Some(200)
+        );
+    }
+
+    #[test]
+    fn hanging_fetch_times_out() {
+        let hanging_fetch = std::future::pending::<u16>();
+
+        assert_eq!(
+            poll_once(with_timeout(hanging_fetch, std::future::ready(()))),
+            None
+        );
+    }
+
+    #[test]
+    fn timeout_is_transient() {
+        let result: anyhow::Result<Response> = Err(TimedOut {
+            url: String::from("https://api.github.com/repos/signalapp/Signal-Android/tags"),
+            timeout: Duration::from_secs(20),
+        }
+        .into());
+
+        assert_eq!(
+            format!("{:#}", result.as_ref().unwrap_err()),
+            "request to https://api.github.com/repos/signalapp/Signal-Android/tags timed out after 20 seconds"
+        );
+        assert!(is_transient_failure(&result));
+    }
+
+    #[test]
+    fn retry_until_success() {
+        let fetcher = FakeFetcher::new(3);
+        let delays = RefCell::new(Vec::new());
+
+        assert_eq!(retry(&fetcher, 0.0, &delays), 200);
+        assert_eq!(fetcher.attempts.get(), 4);
+        assert_eq!(
+            delays.into_inner(),
+            vec![
+                Duration::from_millis(250),
+                Duration::from_millis(500),
+                Duration::from_millis(1000)
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_within_budget() {
+        let fetcher = FakeFetcher::new(usize::MAX);
+        let delays = RefCell::new(Vec::new());
+
+        assert_eq!(retry(&fetcher, 0.999, &delays), 502);
+
+        let delays = delays.into_inner();
+        assert!(delays.iter().sum::<Duration>() <= RETRY_DELAY_BUDGET);
+        assert_eq!(delays.len(), 4);
+        assert_eq!(fetcher.attempts.get(), 5);
+    }
+
+    #[test]
+    fn no_retry_for_client_errors() {
+        let delays = RefCell::new(Vec::new());
+
+        let status_code = poll_once(retry_with_backoff_using(
+            || std::future::ready(404),
+            |status_code| is_transient_status(*status_code),
+            || 0.0,
+            |duration| {
+                delays.borrow_mut().push(duration);
+                std::future::ready(())
+            },
+        ));
+
+        assert_eq!(status_code, 404);
+        assert!(delays.into_inner().is_empty());
+    }
+
+    const COMPARISON_URL: &str =
+        "https://api.github.com/repos/signalapp/Signal-Desktop/compare/v1.2.3-beta.1...v1.2.3-beta.2";
+
+    fn comparison_page_json(total_commits: usize, shas: &[&str]) -> Value {
+        json!({
+            "status": "ahead",
+            "ahead_by": total_commits,
+            "total_commits": total_commits,
+            "commits": shas
+                .iter()
+                .map(|sha| json!({ "sha": sha, "commit": { "message": sha }, "files": null }))
+                .collect::<Vec<_>>(),
+            "files": [{ "filename": format!("{}.txt", shas.join("-")) }],
+        })
+    }
+
+    fn comparison_page(total_commits: usize, shas: &[&str]) -> Comparison {
+        serde_json::from_value(comparison_page_json(total_commits, shas)).unwrap()
+    }
+
+    fn get_comparison(client: &FakeClient) -> anyhow::Result<Comparison> {
+        poll_once(get_github_comparison(
+            client,
+            Some("ghp_token"),
+            Platform::Desktop,
+            "v1.2.3-beta.1",
+            "v1.2.3-beta.2",
+        ))
+    }
+
+    #[test]
+    fn single_page_comparison_without_link_header() {
+        let client = FakeClient::default().respond_to_get(
+            &format!("{COMPARISON_URL}?page=1&per_page=100"),
+            200,
+            &comparison_page_json(2, &["a", "b"]),
+            None,
+        );
+
+        let comparison = get_comparison(&client).unwrap();
+
+        assert_eq!(comparison, {
+            let mut expected = comparison_page(2, &["a", "b"]);
+            expected.files = Some(vec![File::new("a-b.txt")]);
+            expected
+        });
+        assert_eq!(
+            client.requested_urls(),
+            vec![format!("{COMPARISON_URL}?page=1&per_page=100")]
+        );
+    }
+
+    #[test]
+    fn comparison_request_is_authenticated() {
+        let client = FakeClient::default().respond_to_get(
+            &format!("{COMPARISON_URL}?page=1&per_page=100"),
+            200,
+            &comparison_page_json(0, &[]),
+            None,
+        );
+
+        get_comparison(&client).unwrap();
+
+        let requests = client.requests();
+        assert_eq!(requests[0].method, Method::Get);
+        assert!(requests[0]
+            .headers
+            .contains(&("Authorization", String::from("Bearer ghp_token"))));
+    }
+
+    #[test]
+    fn multiple_page_comparison() {
+        let client = FakeClient::default()
+            .respond_to_get(
+                &format!("{COMPARISON_URL}?page=1&per_page=100"),
+                200,
+                &comparison_page_json(3, &["a", "b"]),
+                Some(format!(
+                    r#"<{COMPARISON_URL}?page=2&per_page=100>; rel="next", <{COMPARISON_URL}?page=2&per_page=100>; rel="last""#
+                )),
+            )
+            .respond_to_get(
+                &format!("{COMPARISON_URL}?page=2&per_page=100"),
+                200,
+                &comparison_page_json(3, &["c"]),
+                Some(format!(
+                    r#"<{COMPARISON_URL}?page=1&per_page=100>; rel="first", <{COMPARISON_URL}?page=1&per_page=100>; rel="prev""#
+                )),
+            );
+
+        let comparison = get_comparison(&client).unwrap();
+
+        assert_eq!(
+            comparison
+                .commits
+                .iter()
+                .map(|commit| commit.sha.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(comparison.files.unwrap().len(), 2);
+        assert_eq!(
+            client.requested_urls(),
+            vec![
+                format!("{COMPARISON_URL}?page=1&per_page=100"),
+                format!("{COMPARISON_URL}?page=2&per_page=100"),
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_comparison_without_link_header() {
+        let client = FakeClient::default().respond_to_get(
+            &format!("{COMPARISON_URL}?page=1&per_page=100"),
+            200,
+            &comparison_page_json(3, &["a", "b"]),
+            None,
+        );
+
+        let error = get_comparison(&client).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("incomplete full comparison: total_commits = 3 but commits.len() = 2"));
+    }
+
+    #[test]
+    fn comparison_files_with_one_request() {
+        let client = FakeClient::default().respond_to_get(
+            &format!("{COMPARISON_URL}?page=1&per_page=1"),
+            200,
+            &comparison_page_json(400, &["a"]),
+            Some(format!(
+                r#"<{COMPARISON_URL}?page=2&per_page=1>; rel="next", <{COMPARISON_URL}?page=250&per_page=1>; rel="last""#
+            )),
+        );
+
+        let comparison = poll_once(get_github_comparison_files(
+            &client,
+            None,
+            Platform::Desktop,
+            "v1.2.3-beta.1",
+            "v1.2.3-beta.2",
+        ))
+        .unwrap();
+
+        assert_eq!(comparison.total_commits, 400);
+        assert_eq!(comparison.files, Some(vec![File::new("a.txt")]));
+        assert_eq!(
+            client.requested_urls(),
+            vec![format!("{COMPARISON_URL}?page=1&per_page=1")]
+        );
+    }
+
+    #[test]
+    fn comparison_page_not_found() {
+        let client = FakeClient::default().respond_to_get(
+            &format!("{COMPARISON_URL}?page=1&per_page=100"),
+            404,
+            &json!({ "message": "Not Found" }),
+            None,
+        );
+
+        let error = get_comparison(&client).unwrap_err();
+
+        assert_eq!(error.to_string(), "could not get page 1");
+        assert!(is_not_found(&error));
+    }
+
+    #[test]
+    fn json_from_not_found() {
+        let error = json_from_body::<Comparison>(
+            404,
+            "https://api.github.com/repos/signalapp/Signal-Android/compare/v1.2.3...v1.2.4",
+            "<html><body>Not Found</body></html>",
+            &[],
+        )
+        .unwrap_err();
+
+        assert_str_eq!(
+            format!("{error:#}"),
+            r#"https://api.github.com/repos/signalapp/Signal-Android/compare/v1.2.3...v1.2.4 responded with status code 404, body = "<html><body>Not Found</body></html>""#
+        );
+    }
+
+    #[test_case(404, true; "not found")]
+    #[test_case(500, false; "server error")]
+    fn not_found(status_code: u16, result: bool) {
+        let error = json_from_body::<Comparison>(
+            status_code,
+            "https://api.github.com/repos/signalapp/Signal-Android/compare/v1.2.3...v1.2.4",
+            "",
+            &[],
+        )
+        .unwrap_err()
+        .context("could not get build comparison from GitHub");
+
+        assert_eq!(is_not_found(&error), result);
+    }
+
+    #[test_case(500, true; "server error")]
+    #[test_case(404, false; "not found")]
+    fn unexpected_status_classified(status_code: u16, result: bool) {
+        let error = json_from_body::<Comparison>(
+            status_code,
+            "https://api.github.com/repos/signalapp/Signal-Android/compare/v1.2.3...v1.2.4",
+            "",
+            &[],
+        )
+        .unwrap_err()
+        .context("could not get build comparison from GitHub");
+
+        assert_eq!(is_retriable(&error), result);
+    }
+
+    #[test]
+    fn rate_limit_classified() {
+        let error = anyhow::Error::from(rate_limit::RateLimitExceeded::Primary {
+            resets_in_seconds: Some(60),
+        })
+        .context("could not fetch tags from GitHub");
+
+        assert_eq!(
+            classify_error(&error),
+            Some(Error::GitHubRateLimited {
+                resets_in_seconds: Some(60)
+            })
+        );
+        assert!(is_retriable(&error));
+    }
+
+    #[test]
+    fn timeout_classified() {
+        let error = anyhow::Error::from(TimedOut {
+            url: String::from("https://api.github.com/"),
+            timeout: Duration::from_secs(10),
+        })
+        .context("could not fetch from GitHub");
+
+        assert_eq!(
+            classify_error(&error),
+            Some(Error::TransientNetwork {
+                message: String::from(
+                    "request to https://api.github.com/ timed out after 10 seconds"
+                )
+            })
+        );
+    }
+
+    #[test]
+    fn unclassified() {
+        let error = anyhow!("could not parse URL").context("could not post");
+
+        assert_eq!(classify_error(&error), None);
+        assert!(!is_retriable(&error));
+    }
+
+    #[test]
+    fn json_from_accepted_status_code() {
+        let value: Value = json_from_body(
+            404,
+            "https://community.signalusers.org/t/signal-android-1-2-beta.json",
+            r#"{"error_type":"not_found"}"#,
+            &[404],
+        )
+        .unwrap();
+
+        assert_eq!(value, json!({ "error_type": "not_found" }));
+    }
+
+    const TOPIC_URL: &str =
+        "https://community.signalusers.org/t/beta-feedback-for-the-upcoming-android-1-2-release.json";
+
+    fn get_android_topic_id(status_code: u16, body: Value) -> anyhow::Result<Option<u64>> {
+        let client = FakeClient::default().respond_to_get(TOPIC_URL, status_code, &body, None);
+
+        poll_once(get_topic_id(
+            &client,
+            "discourse-key",
+            Platform::Android,
+            &Version::new(1, 2, 3),
+        ))
+    }
+
+    #[test]
+    fn topic_found() {
+        let topic_id = get_android_topic_id(
+            200,
+            json!({ "post_stream": { "posts": [{ "topic_id": 123 }, { "topic_id": 123 }] } }),
+        );
+
+        assert_eq!(topic_id.unwrap(), Some(123));
+    }
+
+    #[test]
+    fn topic_not_found() {
+        let topic_id = get_android_topic_id(404, json!({ "error_type": "not_found" }));
+
+        assert_eq!(topic_id.unwrap(), None);
+    }
+
+    #[test_case(200, json!({ "post_stream": { "posts": [] } }), "no posts in topic"; "no posts")]
+    #[test_case(404, json!({ "error_type": "invalid_access" }), "discourse API request likely failed"; "other error type")]
+    #[test_case(403, json!({ "errors": ["forbidden"] }), r#"https://community.signalusers.org/t/beta-feedback-for-the-upcoming-android-1-2-release.json responded with status code 403, body = "{\"errors\":[\"forbidden\"]}""#; "unexpected status code")]
+    fn topic_id_error(status_code: u16, body: Value, message: &str) {
+        let error = get_android_topic_id(status_code, body).unwrap_err();
+
+        assert_eq!(error.to_string(), message);
+    }
+
+    const POSTS_URL: &str = "https://community.signalusers.org/posts.json";
+
+    fn post(client: &FakeClient) -> anyhow::Result<DiscoursePost> {
+        poll_once(post_to_discourse(
+            client,
+            "New version: 1.2.4",
+            "discourse-key",
+            123,
+            Some(45),
+        ))
+    }
+
+    fn posts_response(status_code: u16, body: Value) -> HttpResponse {
+        HttpResponse {
+            status_code,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn posted_to_discourse() {
+        let client = FakeClient::default().respond(
+            Method::Post,
+            POSTS_URL,
+            posts_response(200, json!({ "id": 1000, "post_number": 46 })),
+        );
+
+        assert_eq!(
+            post(&client).unwrap(),
+            DiscoursePost {
+                id: 1000,
+                number: 46
+            }
+        );
+
+        let requests = client.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            serde_json::from_str::<Value>(requests[0].body.as_deref().unwrap()).unwrap(),
+            json!({
+                "topic_id": 123,
+                "reply_to_post_number": 45,
+                "raw": "New version: 1.2.4",
+            })
+        );
+        assert!(requests[0]
+            .headers
+            .contains(&("User-Api-Key", String::from("discourse-key"))));
+    }
+
+    #[test]
+    fn post_without_number() {
+        let client = FakeClient::default().respond(
+            Method::Post,
+            POSTS_URL,
+            posts_response(200, json!({ "id": 1000, "errors": [] })),
+        );
+
+        assert_eq!(
+            post(&client).unwrap_err().to_string(),
+            "discourse API response did not include the post ID and number, posting likely failed"
+        );
+    }
+
+    #[test_case(503, json!({ "errors": ["Service Unavailable"] }); "service unavailable")]
+    #[test_case(403, json!({ "errors": ["The site is in read only mode. Interactions are disabled."], "error_type": "read_only_mode" }); "read-only error type")]
+    fn post_while_read_only(status_code: u16, body: Value) {
+        let client = FakeClient::default().respond(
+            Method::Post,
+            POSTS_URL,
+            posts_response(status_code, body),
+        );
+
+        let error = post(&client).unwrap_err();
+
+        assert!(is_forum_read_only(&error));
+        assert_eq!(error.root_cause().to_string(), "forum is read-only");
+        assert_eq!(client.requests().len(), 1);
+    }
+
+    #[test]
+    fn post_rejected() {
+        let client = FakeClient::default().respond(
+            Method::Post,
+            POSTS_URL,
+            posts_response(422, json!({ "errors": ["Body is too short"] })),
+        );
+
+        let error = post(&client).unwrap_err();
+
+        assert_eq!(
+            classify_error(&error),
+            Some(Error::DiscourseRejected {
+                status_code: 422,
+                error_type: None,
+                errors: vec![String::from("Body is too short")],
+            })
+        );
+        assert!(!is_forum_read_only(&error));
+        assert!(!is_retriable(&error));
+        assert_eq!(client.requests().len(), 1);
+    }
+
+    const POST_TOPIC_URL: &str = "https://community.signalusers.org/t/123.json";
+
+    fn post_by_number_url(post_number: u64) -> String {
+        format!("https://community.signalusers.org/posts/by_number/123/{post_number}.json")
+    }
+
+    fn retry_post(client: &FakeClient) -> PostAttempt {
+        let request = RequestBuilder::new(Url::parse(POSTS_URL).unwrap(), Method::Post)
+            .json(&json!({ "topic_id": 123, "raw": "New version: 1.2.4" }))
+            .prepare();
+
+        poll_once(attempt_post(
+            client,
+            request,
+            "discourse-key",
+            Some((123, "New version: 1.2.4")),
+            true,
+        ))
+    }
+
+    #[test_case(String::from("New version: 1.2.4\n"); "same raw")]
+    #[test_case(unique_markdown_text("New version: 1.2.4"); "raw with content hash footer")]
+    fn retried_post_found_in_topic(raw: String) {
+        let client = FakeClient::default()
+            .respond_to_get(
+                POST_TOPIC_URL,
+                200,
+                &json!({ "highest_post_number": 47 }),
+                None,
+            )
+            .respond_to_get(
+                &post_by_number_url(47),
+                200,
+                &json!({ "id": 1001, "post_number": 47, "raw": "Thanks!" }),
+                None,
+            )
+            .respond_to_get(
+                &post_by_number_url(46),
+                200,
+                &json!({ "id": 1000, "post_number": 46, "raw": raw }),
+                None,
+            );
+
+        let PostAttempt::AlreadyPosted(posted) = retry_post(&client) else {
+            panic!("post was sent again");
+        };
+
+        assert_eq!(
+            posted,
+            DiscoursePost {
+                id: 1000,
+                number: 46
+            }
+        );
+        assert!(client
+            .requests()
+            .iter()
+            .all(|request| request.method != Method::Post));
+    }
+
+    #[test]
+    fn retried_post_not_found_in_topic() {
+        let client = FakeClient::default()
+            .respond_to_get(
+                POST_TOPIC_URL,
+                200,
+                &json!({ "highest_post_number": 46 }),
+                None,
+            )
+            .respond_to_get(
+                &post_by_number_url(46),
+                404,
+                &json!({ "error_type": "not_found" }),
+                None,
+            )
+            .respond_to_get(
+                &post_by_number_url(45),
+                200,
+                &json!({ "id": 999, "post_number": 45, "raw": "New version: 1.2.3" }),
+                None,
+            )
+            .respond_to_get(
+                &post_by_number_url(44),
+                200,
+                &json!({ "id": 998, "post_number": 44, "raw": "Thanks!" }),
+                None,
+            )
+            .respond(
+                Method::Post,
+                POSTS_URL,
+                posts_response(200, json!({ "id": 1000, "post_number": 47 })),
+            );
+
+        let PostAttempt::Sent(response) = retry_post(&client) else {
+            panic!("post was not sent");
+        };
+
+        assert_eq!(response.unwrap().status_code, 200);
+        assert_eq!(
+            client.requested_urls().last().map(String::as_str),
+            Some(POSTS_URL)
+        );
+    }
+
+    #[test]
+    fn retried_post_not_sent_if_topic_unavailable() {
+        let client = FakeClient::default().respond_to_get(
+            POST_TOPIC_URL,
+            502,
+            &json!({ "errors": ["Bad Gateway"] }),
+            None,
+        );
+
+        assert!(matches!(retry_post(&client), PostAttempt::Sent(Err(_))));
+        assert_eq!(client.requested_urls(), [POST_TOPIC_URL]);
+    }
+
+    fn too_similar_response() -> HttpResponse {
+        posts_response(
+            422,
+            json!({
+                "action": "create_post",
+                "errors": ["Body is too similar to what you recently posted"],
+            }),
+        )
+    }
+
+    #[test]
+    fn too_similar_post_retried_with_footer() {
+        let client = FakeClient::default()
+            .respond(
+                Method::Post,
+                POSTS_URL,
+                posts_response(200, json!({ "id": 1000, "post_number": 46 })),
+            )
+            .respond_once(Method::Post, POSTS_URL, too_similar_response());
+
+        assert_eq!(
+            post(&client).unwrap(),
+            DiscoursePost {
+                id: 1000,
+                number: 46
+            }
+        );
+
+        let raws: Vec<Value> = client
+            .requests()
+            .iter()
+            .map(|request| {
+                serde_json::from_str::<Value>(request.body.as_deref().unwrap()).unwrap()["raw"]
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            raws,
+            [
+                json!("New version: 1.2.4"),
+                json!(unique_markdown_text("New version: 1.2.4")),
+            ]
+        );
+    }
+
+    #[test]
+    fn too_similar_post_retried_once() {
+        let client = FakeClient::default().respond(Method::Post, POSTS_URL, too_similar_response());
+
+        let error = post(&client).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "could not post even with a content hash footer"
+        );
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[test]
+    fn unique_markdown() {
+        assert_eq!(
+            unique_markdown_text("New version: 1.2.4"),
+            format!(
+                "New version: 1.2.4\n\n<!-- content hash: {} -->",
+                hash::sha256_string("New version: 1.2.4")
+            )
+        );
+    }
+
+    const POST_URL: &str = "https://community.signalusers.org/posts/789.json";
+
+    #[test]
+    fn got_post_raw() {
+        let client = FakeClient::default().respond_to_get(
+            POST_URL,
+            200,
+            &json!({ "id": 789, "raw": "| Version |", "version": 3 }),
+            None,
+        );
+
+        assert_eq!(
+            poll_once(get_post_raw_in_discourse(&client, "discourse-key", 789)).unwrap(),
+            DiscoursePostRaw {
+                raw: String::from("| Version |"),
+                version: 3
+            }
+        );
+    }
+
+    #[test]
+    fn got_post_raw_by_number() {
+        let client = FakeClient::default().respond_to_get(
+            "https://community.signalusers.org/posts/by_number/123/45.json",
+            200,
+            &json!({ "id": 789, "raw": "| Version |", "version": 1 }),
+            None,
+        );
+
+        assert_eq!(
+            poll_once(get_post_raw_by_number_in_discourse(
+                &client,
+                "discourse-key",
+                123,
+                45
+            ))
+            .unwrap(),
+            DiscoursePostRaw {
+                raw: String::from("| Version |"),
+                version: 1
+            }
+        );
+    }
+
+    #[test]
+    fn post_raw_by_number_not_found() {
+        let client = FakeClient::default().respond_to_get(
+            "https://community.signalusers.org/posts/by_number/123/45.json",
+            404,
+            &json!({ "errors": ["The requested URL or resource could not be found."], "error_type": "not_found" }),
+            None,
+        );
+
+        let error = poll_once(get_post_raw_by_number_in_discourse(
+            &client,
+            "discourse-key",
+            123,
+            45,
+        ))
+        .unwrap_err();
+
+        assert!(is_not_found(&error));
+    }
+
+    #[test]
+    fn post_raw_missing() {
+        let client =
+            FakeClient::default().respond_to_get(POST_URL, 200, &json!({ "id": 789 }), None);
+
+        assert_eq!(
+            poll_once(get_post_raw_in_discourse(&client, "discourse-key", 789))
+                .unwrap_err()
+                .to_string(),
+            "discourse API response did not include the raw post and its version"
+        );
+    }
+
+    #[test]
+    fn json_from_malformed_body() {
+        let error = json_from_body::<Comparison>(
+            200,
+            "https://api.github.com/repos/signalapp/Signal-Android/compare/v1.2.3...v1.2.4",
+            r#"{"total_commits": 3, "commits": ["#,
+            &[],
+        )
+        .unwrap_err();
+
+        let message = format!("{error:#}");
+        assert!(message.starts_with("could not get JSON from https://api.github.com/repos/signalapp/Signal-Android/compare/v1.2.3...v1.2.4 (status code 200), body = "));
+        assert!(message.contains(r#"{\"total_commits\": 3, \"commits\": ["#));
+    }
+
+    #[test]
+    fn long_body_excerpt() {
+        let body = "é".repeat(MAX_ERROR_BODY_BYTES);
+
+        let excerpt = body_excerpt(&body);
+        assert_eq!(excerpt.len(), MAX_ERROR_BODY_BYTES);
+        assert!(body.starts_with(excerpt));
+
+        assert_eq!(body_excerpt("short"), "short");
+    }
+
+    #[test_case(0, 0.0, 250; "first retry, no jitter")]
+    #[test_case(0, 0.5, 375; "first retry, jitter")]
+    #[test_case(3, 0.0, 2000; "fourth retry")]
+    fn backoff(retry: u32, random: f64, milliseconds: u64) {
+        assert_eq!(
+            backoff_delay(retry, random),
+            Duration::from_millis(milliseconds)
+        );
+    }
+
+    fn header<'a>(headers: &'a [(&str, String)], name: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|(header_name, _)| *header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn github_request_headers() {
+        let url = Url::parse("https://api.github.com/repos/signalapp/Signal-Android/tags").unwrap();
+        let headers = request_headers(&url, Some("discourse key"), Some("github token"));
+
+        assert_eq!(
+            header(&headers, "Authorization"),
+            Some("Bearer github token")
+        );
+        assert_eq!(
+            header(&headers, "Accept"),
+            Some("application/vnd.github+json")
+        );
+        assert_eq!(header(&headers, "X-GitHub-Api-Version"), Some("2022-11-28"));
+        assert_eq!(header(&headers, "User-Api-Key"), None);
+    }
+
+    #[test]
+    fn anonymous_github_request_headers() {
+        let url = Url::parse("https://api.github.com/repos/signalapp/Signal-Android/tags").unwrap();
+        let headers = request_headers(&url, None, None);
+
+        assert_eq!(header(&headers, "Authorization"), None);
+        assert_eq!(header(&headers, "X-GitHub-Api-Version"), Some("2022-11-28"));
+    }
+
+    #[test]
+    fn user_agent_format() {
+        assert_str_eq!(
+            user_agent(),
+            format!(
+                "updates-bot/{} (+https://github.com/u32i64/signalupdates-bot)",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+        assert!(user_agent().starts_with(&format!("{} ", client_name())));
+    }
+
+    #[test]
+    fn request_without_body() {
+        let url = Url::parse("https://api.github.com/repos/signalapp/Signal-Android/tags").unwrap();
+        let builder = RequestBuilder::new(url, Method::Get)
+            .github_token(Some("github token"))
+            .header("If-None-Match", r#"W/"abcdef""#);
+
+        assert_eq!(
+            builder.parts(),
+            (
+                vec![
+                    ("Content-Type", String::from("application/json")),
+                    ("User-Agent", String::from(user_agent())),
+                    ("Accept", String::from("application/vnd.github+json")),
+                    ("X-GitHub-Api-Version", String::from("2022-11-28")),
+                    ("Authorization", String::from("Bearer github token")),
+                    ("If-None-Match", String::from(r#"W/"abcdef""#)),
+                ],
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn request_with_json_body() {
+        let url = Url::parse("https://community.signalusers.org/posts.json").unwrap();
+        let builder = RequestBuilder::new(url, Method::Post)
+            .discourse_api_key(Some("discourse key"))
+            .json(&json!({ "raw": "text" }));
+
+        assert_eq!(
+            builder.parts(),
+            (
+                vec![
+                    ("Content-Type", String::from("application/json")),
+                    ("User-Agent", String::from(user_agent())),
+                    ("Accept", String::from("application/json")),
+                    ("User-Api-Key", String::from("discourse key")),
+                ],
+                Some(r#"{"raw":"text"}"#)
+            )
+        );
+    }
+
+    #[test]
+    fn request_with_text_body() {
+        let url = Url::parse("https://example.com/hook").unwrap();
+        let builder = RequestBuilder::new(url, Method::Post).text("New version: 1.2.4");
+        let (headers, body) = builder.parts();
+
+        assert_eq!(
+            header(&headers, "Content-Type"),
+            Some("text/plain; charset=utf-8")
+        );
+        assert_eq!(body, Some("New version: 1.2.4"));
+    }
+
+    #[test]
+    fn added_headers_replace_default_ones() {
+        let url = Url::parse("https://example.com/hook").unwrap();
+        let builder = RequestBuilder::new(url, Method::Post)
+            .text(r#"{"platform":"Android"}"#)
+            .header("content-type", "application/json")
+            .header("X-Signature-256", "sha256=abc");
+        let (headers, body) = builder.parts();
+
+        assert_eq!(
+            headers,
+            vec![
+                ("Content-Type", String::from("application/json")),
+                ("User-Agent", String::from(user_agent())),
+                ("Accept", String::from("application/json")),
+                ("X-Signature-256", String::from("sha256=abc")),
+            ]
+        );
+        assert_eq!(body, Some(r#"{"platform":"Android"}"#));
+    }
+
+    #[test]
+    fn discourse_request_headers() {
+        let url = Url::parse("https://community.signalusers.org/posts.json").unwrap();
+        let headers = request_headers(&url, Some("discourse key"), Some("github token"));
+
+        assert_eq!(header(&headers, "User-Api-Key"), Some("discourse key"));
+        assert_eq!(header(&headers, "Accept"), Some("application/json"));
+        assert_eq!(header(&headers, "Authorization"), None);
+        assert_eq!(header(&headers, "X-GitHub-Api-Version"), None);
+    }
+
+    #[test]
+    fn requests_are_counted() {
+        let counts = poll_once(invocation::scope(Invocation::default(), async {
+            count_request(
+                &Url::parse("https://api.github.com/repos/signalapp/Signal-Android/tags").unwrap(),
+            );
+            count_request(&Url::parse("https://community.signalusers.org/posts.json").unwrap());
+
+            (github_request_count(), subrequest_count())
+        }));
+
+        assert_eq!(counts, (1, 2));
+    }
+
+    #[test]
+    fn requests_are_counted_per_invocation() {
+        poll_once(invocation::scope(Invocation::default(), async {
+            count_request(&Url::parse("https://community.signalusers.org/posts.json").unwrap());
+        }));
+
+        let counts = poll_once(invocation::scope(Invocation::default(), async {
+            (github_request_count(), subrequest_count())
+        }));
+
+        assert_eq!(counts, (0, 0));
+        assert_eq!(subrequest_count(), 0);
+    }
+
+    #[test]
+    fn random_hex() {
+        let mut randoms = [0.0, 0.5, 1.0].into_iter();
+
+        assert_str_eq!(
+            random_hex_string(3, || randoms.next().unwrap()),
+            "000000007fffffffffffffff"
+        );
+    }
 }
@@ -0,0 +1,46 @@
+#!/bin/sh
+# Records the comparisons used by the snapshot tests in src/core/markdown/post.rs from the
+# GitHub API, trimmed to the fields that the bot reads, and renders the snapshots from them
+# again; review the diff of tests/ before committing it.
+#
+# Set GITHUB_TOKEN to avoid the rate limit for unauthenticated requests.
+set -eu
+
+cd "$(dirname "$0")"
+
+TRIM='{
+    status, ahead_by, behind_by, total_commits,
+    merge_base_commit: { sha: .merge_base_commit.sha },
+    commits: [.commits[] | {
+        sha, html_url,
+        author: (if .author then { login: .author.login } else null end),
+        commit: {
+            message: .commit.message,
+            author: { name: .commit.author.name, email: .commit.author.email, date: .commit.author.date },
+            verification: { verified: .commit.verification.verified, reason: .commit.verification.reason }
+        },
+        parents: [.parents[] | { sha }]
+    }],
+    files: [.files[]? | { filename, status, previous_filename, additions, deletions, changes, patch }
+        | with_entries(select(.value != null))]
+}'
+
+record() {
+    repository=$1
+    comparison=$2
+    fixture=$3
+
+    curl --fail --silent --show-error \
+        --header "Accept: application/vnd.github+json" \
+        ${GITHUB_TOKEN:+--header "Authorization: Bearer $GITHUB_TOKEN"} \
+        "https://api.github.com/repos/signalapp/$repository/compare/$comparison" |
+        jq --indent 4 "$TRIM" >"$fixture.json"
+
+    echo "recorded $repository $comparison into $fixture.json"
+}
+
+record Signal-Android v6.30.1...v6.30.2 android_small
+record Signal-Desktop v6.40.0-beta.1...v6.40.0-beta.2 desktop_large
+record Signal-iOS 6.45.0.2...6.45.0.3 ios_localization
+
+UPDATE_SNAPSHOTS=1 cargo test --manifest-path ../../Cargo.toml post_markdown_snapshot